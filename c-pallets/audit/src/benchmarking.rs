@@ -1,6 +1,6 @@
 use super::*;
 use crate::{Pallet as Audit, *};
-use cp_cess_common::{IpAddress, Hash, DataType};
+use cp_cess_common::{peer, IpAddress, Hash, DataType, NodePublicKey, FRAGMENT_SIZE, SEGMENT_SIZE};
 use codec::{alloc::string::ToString, Decode};
 pub use frame_benchmarking::{
 	account, benchmarks, impl_benchmark_test_suite, whitelist_account, whitelisted_caller,
@@ -16,9 +16,10 @@ use pallet_cess_staking::{
 use pallet_tee_worker::{Config as TeeWorkerConfig, testing_utils::add_scheduler, Pallet as TeeWorker};
 use pallet_sminer::{Config as SminerConfig, Pallet as Sminer};
 use sp_runtime::{
-	traits::{Bounded, One, StaticLookup, TrailingZeroInput, Zero},
+	traits::{Bounded, One, Saturating, StaticLookup, TrailingZeroInput, Zero},
 	Perbill, Percent,
 };
+use sp_core::offchain::{OpaqueNetworkState, OpaquePeerId};
 use sp_std::prelude::*;
 
 use frame_system::RawOrigin;
@@ -30,6 +31,51 @@ pub trait Config:
 }
 
 const USER_SEED: u32 = 999666;
+// A mature network the challenges are generated over.
+const NETWORK_FILLERS: u128 = 1_000_000;
+const NETWORK_FILES: u128 = 100_000;
+
+// A challenge over the whole network shared by `miners`, with full random lists.
+fn network_challenge<T: Config>(miners: Vec<AccountOf<T>>) -> Result<ChallengeInfo<T>, &'static str> {
+    let count = (miners.len() as u128).max(1);
+    let idle_space = NETWORK_FILLERS * FRAGMENT_SIZE / count;
+    let service_space = NETWORK_FILES * SEGMENT_SIZE / count;
+    let miner_snapshot_list: Vec<MinerSnapShot<AccountOf<T>>> = miners
+        .into_iter()
+        .map(|miner| MinerSnapShot { miner, idle_space, service_space })
+        .collect();
+
+    Ok(ChallengeInfo::<T> {
+        net_snap_shot: NetSnapShot {
+            start: frame_system::Pallet::<T>::block_number(),
+            life: 1200u32.into(),
+            total_reward: 0,
+            total_idle_space: NETWORK_FILLERS * FRAGMENT_SIZE,
+            total_service_space: NETWORK_FILES * SEGMENT_SIZE,
+            random_index_list: (0 .. 1024u32).collect::<Vec<u32>>().try_into().map_err(|_| "random index list convert err")?,
+            random_list: (0 .. 1024u32).map(|i| [i as u8; 20]).collect::<Vec<[u8; 20]>>().try_into().map_err(|_| "random list convert err")?,
+            proof_format: ProofFormat::Podr2V1,
+        },
+        miner_snapshot_list: miner_snapshot_list.try_into().map_err(|_| "miner snapshot list convert err")?,
+    })
+}
+
+fn register_miners<T: Config>(count: u32) -> Result<Vec<AccountOf<T>>, &'static str> {
+    let mut miners = Vec::new();
+    for i in 0 .. count {
+        let miner: AccountOf<T> = account("miner", i, USER_SEED);
+        let mut node_key = [0u8; 32];
+        node_key[..4].copy_from_slice(&i.to_le_bytes());
+        let peer_id = peer::peer_id_from_node_key(&NodePublicKey::from_raw(node_key));
+        let collaterals = <T as pallet_sminer::Config>::Currency::minimum_balance().saturating_mul(1_000u32.into());
+        <T as pallet_sminer::Config>::Currency::make_free_balance_be(&miner, collaterals.saturating_mul(2u32.into()));
+        Sminer::<T>::regnstk(RawOrigin::Signed(miner.clone()).into(), miner.clone(), peer_id, collaterals)
+            .map_err(|_| "miner register err")?;
+        miners.push(miner);
+    }
+
+    Ok(miners)
+}
 
 benchmarks! {
     submit_challenge_prove {
@@ -144,4 +190,50 @@ benchmarks! {
             assert!(!unverify_prove.to_vec().contains(value));
         }
     }
+
+    save_challenge_info {
+        let v in 0 .. <T as crate::Config>::ChallengeMinerMax::get();
+        let s in 0 .. <T as crate::Config>::MaxSpotAudits::get();
+        frame_system::Pallet::<T>::set_block_number(1u32.into());
+        let miners: Vec<AccountOf<T>> = (0 .. v).map(|i| account("miner", i, USER_SEED)).collect();
+        let challenge_info = network_challenge::<T>(miners)?;
+        // The other validators already proposed the same challenge, this one accepts it.
+        let hash = sp_io::hashing::sha2_256(&challenge_info.encode());
+        ChallengeProposal::<T>::insert(&hash, (1, challenge_info.clone()));
+        let queue: Vec<(Hash, AccountOf<T>)> = (0 .. s)
+            .map(|i| (Hash([i as u8; 64]), account("requester", i, USER_SEED)))
+            .collect();
+        <SpotAuditQueue<T>>::put(BoundedVec::try_from(queue).map_err(|_| "spot audit queue convert err")?);
+        let key = <T as crate::Config>::AuthorityId::decode(&mut TrailingZeroInput::zeroes()).map_err(|_| "key decode err")?;
+        let signature = <<T as crate::Config>::AuthorityId as RuntimeAppPublic>::Signature::decode(&mut TrailingZeroInput::zeroes())
+            .map_err(|_| "signature decode err")?;
+        let seg_digest = SegDigest::<BlockNumberOf<T>> {
+            validators_len: 0,
+            block_num: 1u32.into(),
+            network_state: OpaqueNetworkState { peer_id: OpaquePeerId(Vec::new()), external_addresses: Vec::new() },
+        };
+    }: _(RawOrigin::None, challenge_info, key, seg_digest, signature)
+    verify {
+        assert_eq!(ChallengeProposal::<T>::count(), 0);
+        assert!(<SpotAuditQueue<T>>::get().is_empty());
+    }
+
+    clear_challenge {
+        let v in 0 .. <T as crate::Config>::ChallengeMinerMax::get();
+        let miners = register_miners::<T>(v)?;
+        // Every miner is on its last strike and forced to exit.
+        for miner in miners.iter() {
+            <CountedClear<T>>::insert(miner, 2);
+        }
+        let now: BlockNumberOf<T> = 1200u32.into();
+        <ChallengeSnapShot<T>>::put(network_challenge::<T>(miners.clone())?);
+        <ChallengeDuration<T>>::put(now);
+    }: {
+        Audit::<T>::on_initialize(now);
+    }
+    verify {
+        for miner in miners.iter() {
+            assert!(!<CountedClear<T>>::contains_key(miner));
+        }
+    }
 }
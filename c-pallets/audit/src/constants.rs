@@ -1,3 +1,8 @@
 pub(super) const IDLE_FAULT_TOLERANT: u8 = 2;
 
-pub(super) const SERVICE_FAULT_TOLERANT: u8 = 2;
\ No newline at end of file
+pub(super) const SERVICE_FAULT_TOLERANT: u8 = 2;
+// Consecutive absent verdicts after which an unreachable miner is punished.
+pub(super) const UNREACHABLE_FAULT_TOLERANT: u8 = 3;
+
+// Accepted challenges whose randomness is kept for the randomness reports.
+pub(super) const CHALLENGE_SEED_LOG_LIMIT: u32 = 64;
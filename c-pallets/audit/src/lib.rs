@@ -49,7 +49,7 @@ use types::*;
 mod constants;
 use constants::*;
 
-// pub mod migrations;
+pub mod migrations;
 
 pub use pallet::*;
 
@@ -57,8 +57,8 @@ pub use pallet::*;
 pub mod benchmarking;
 
 use sp_runtime::{
-	traits::{CheckedAdd, SaturatedConversion},
-	RuntimeDebug, Permill,
+	traits::{AccountIdConversion, CheckedAdd, SaturatedConversion, Zero},
+	RuntimeDebug, Permill, Perbill,
 	offchain::storage::{StorageValueRef, StorageRetrievalError},
 };
 
@@ -70,7 +70,8 @@ use frame_support::{
 	pallet_prelude::*,
 	storage::bounded_vec::BoundedVec,
 	traits::{
-		FindAuthor, Randomness, ReservableCurrency, EstimateNextSessionRotation,
+		FindAuthor, Randomness, Currency, ReservableCurrency, EstimateNextSessionRotation,
+		BalanceStatus, ExistenceRequirement::KeepAlive,
 		ValidatorSetWithIdentification, ValidatorSet, OneSessionHandler, StorageVersion,
 	},
 	PalletId, WeakBoundedVec, BoundedSlice,
@@ -82,7 +83,7 @@ use sp_core::{
 use sp_runtime::{Saturating, app_crypto::RuntimeAppPublic};
 use frame_system::offchain::{CreateSignedTransaction, SubmitTransaction};
 use pallet_file_bank::RandomFileList;
-use pallet_tee_worker::ScheduleFind;
+use pallet_tee_worker::{ScheduleAssign, ScheduleExit, ScheduleFind, ROLE_VERIFIER};
 use pallet_sminer::MinerControl;
 use pallet_storage_handler::StorageHandle;
 use scale_info::TypeInfo;
@@ -98,11 +99,13 @@ pub use weights::WeightInfo;
 
 type AccountOf<T> = <T as frame_system::Config>::AccountId;
 type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
+type BalanceOf<T> =
+	<<T as pallet::Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 pub const AUDIT: KeyTypeId = KeyTypeId(*b"cess");
 // type FailureRate = u32;
 
-const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(3);
 
 pub mod sr25519 {
 	mod app_sr25519 {
@@ -198,12 +201,14 @@ pub mod pallet {
 		//Random files used to obtain this batch of challenges
 		type File: RandomFileList<Self::AccountId>;
 		//Judge whether it is the trait of the consensus node
-		type Scheduler: ScheduleFind<Self::AccountId>;
+		type Scheduler: ScheduleFind<Self::AccountId> + ScheduleAssign<Self::AccountId>;
 		//It is used to increase or decrease the miners' computing power, space, and execute
 		// punishment
 		type MinerControl: MinerControl<Self::AccountId>;
 
 		type StorageHandle: StorageHandle<Self::AccountId>;
+		//Hands the failed challenges to the pallets listening for them
+		type EventRouter: StorageEventRouter<Self::AccountId>;
 		//Configuration to be used for offchain worker
 		type AuthorityId: Member
 			+ Parameter
@@ -224,6 +229,49 @@ pub mod pallet {
 
 		#[pallet::constant]
 		type LockTime: Get<BlockNumberOf<Self>>;
+		//Minimum interval between two sampling submissions of the same account
+		#[pallet::constant]
+		type SamplingInterval: Get<BlockNumberOf<Self>>;
+		//Reward paid from the pallet account for each sampling result the tee committee confirmed
+		#[pallet::constant]
+		type SamplingReward: Get<BalanceOf<Self>>;
+		//Reserved from an account registering as a sampler, refunded when it unregisters
+		#[pallet::constant]
+		type SamplerBond: Get<BalanceOf<Self>>;
+		//Share of the bond moved to the pallet account for a result the tee committee refuted
+		#[pallet::constant]
+		type SamplerSlash: Get<Perbill>;
+		//A miner can extend its challenge deadline once in each period
+		#[pallet::constant]
+		type ExtensionPeriod: Get<BlockNumberOf<Self>>;
+		//Number of blocks added to the challenge deadline by an extension
+		#[pallet::constant]
+		type ExtensionLife: Get<BlockNumberOf<Self>>;
+		//Fee paid to the pallet account for an extension
+		#[pallet::constant]
+		type ExtensionFee: Get<BalanceOf<Self>>;
+		//Fee of a spot audit, paid to the tee workers verifying the proofs of the audited miners
+		#[pallet::constant]
+		type SpotAuditFee: Get<BalanceOf<Self>>;
+		//Minimum interval between two spot audit requests of the same account
+		#[pallet::constant]
+		type SpotAuditInterval: Get<BlockNumberOf<Self>>;
+		//Maximum number of spot audits waiting for the next challenge
+		#[pallet::constant]
+		type MaxSpotAudits: Get<u32>;
+		//Length of a seed epoch, seeds committed in one epoch are revealed in the next
+		//and randomize the challenges of the epoch after
+		#[pallet::constant]
+		type SeedEpoch: Get<BlockNumberOf<Self>>;
+		//Fewest connection attempts an absent verdict must be backed by
+		#[pallet::constant]
+		type MinAbsentAttempts: Get<u32>;
+		//Most connection attempts an absent verdict can carry
+		#[pallet::constant]
+		type MaxConnectionAttempts: Get<u32> + Clone + Eq + PartialEq;
+		//Fewest blocks between two connection attempts of an absent verdict
+		#[pallet::constant]
+		type AbsentAttemptInterval: Get<BlockNumberOf<Self>>;
 	}
 
 	#[pallet::event]
@@ -234,6 +282,36 @@ pub mod pallet {
 		SubmitProof { miner: AccountOf<T> },
 
 		VerifyProof { tee_worker: AccountOf<T>, miner: AccountOf<T> },
+		//A miner extended its challenge deadline
+		ChallengeExtended { miner: AccountOf<T>, deadline: BlockNumberOf<T>, fee: BalanceOf<T> },
+		//A sampler submitted the result of sampling a public dataset, it waits for the tee committee
+		SubmitSampling { sampler: AccountOf<T>, miner: AccountOf<T>, file_hash: Hash, result: bool },
+		//The tee committee confirmed the sampling result, it was counted into the reputation of the miner
+		SamplingConfirmed { sampler: AccountOf<T>, miner: AccountOf<T>, file_hash: Hash, result: bool, reward: BalanceOf<T> },
+		//The tee committee refuted the sampling result, the bond of the sampler was slashed
+		SamplingRefuted { sampler: AccountOf<T>, miner: AccountOf<T>, file_hash: Hash, slashed: BalanceOf<T> },
+		//An account reserved the bond of a sampler
+		SamplerRegistered { sampler: AccountOf<T>, bond: BalanceOf<T> },
+		//A sampler left and its bond was refunded
+		SamplerUnregistered { sampler: AccountOf<T>, bond: BalanceOf<T> },
+		//A spot audit of a file was queued for the next challenge
+		SpotAuditRequested { requester: AccountOf<T>, file_hash: Hash, fee: BalanceOf<T> },
+		//The miners of a queued spot audit were included in the challenge
+		SpotAuditScheduled { requester: AccountOf<T>, file_hash: Hash },
+		//The proof of a spot audited miner was handed to a tee worker outside the excluded ones
+		SpotAuditVerifierSelected { miner: AccountOf<T>, tee_worker: AccountOf<T>, excluded: Vec<AccountOf<T>> },
+		//A tee worker committed to the seed it reveals in the given epoch
+		SeedCommitted { tee_worker: AccountOf<T>, epoch: u32 },
+		//A tee worker revealed its seed, it was mixed into the seed of the epoch
+		SeedRevealed { tee_worker: AccountOf<T>, epoch: u32 },
+		//A tee worker did not reveal the seed it committed to in time and was punished
+		SeedWithheld { tee_worker: AccountOf<T>, epoch: u32 },
+		//The proof format changed, the previous one is accepted until `transition_end`
+		ProofFormatSet { current: ProofFormat, previous: ProofFormat, transition_end: BlockNumberOf<T> },
+		//A tee worker could not reach the miner whose proofs it verifies
+		MinerAbsent { tee_worker: AccountOf<T>, miner: AccountOf<T>, attempts: u32, punished: bool },
+		//The randomness of the last accepted challenges, oldest first
+		ChallengeRandomnessReported { records: Vec<ChallengeSeedRecord<BlockNumberOf<T>>> },
 
 	}
 
@@ -272,6 +350,52 @@ pub mod pallet {
 		NonExistentMission,
 
 		UnexpectedError,
+		//The file is not opted in to public sampling
+		NotPublicDataset,
+		//The fragment does not belong to the file
+		NonExistentFragment,
+		//The account submitted a sampling result too recently
+		SamplingTooFrequent,
+		//The account did not reserve the bond of a sampler
+		NotSampler,
+		//The account is already a sampler
+		SamplerExists,
+		//The last sampling result of the sampler is still waiting for the tee committee
+		SamplingPending,
+		//The sampler has no sampling result waiting for the tee committee
+		NoPendingSampling,
+		//The authority already voted for the challenge proposal
+		DuplicateChallengeVote,
+		//The miner already extended a challenge in this period
+		ExtensionUsed,
+		//The verification of the challenge ends before the challenge could be extended
+		ExtensionUnavailable,
+		//The file does not exist
+		NonExistentFile,
+		//The file is already queued for a spot audit
+		SpotAuditQueued,
+		//The account requested a spot audit too recently
+		SpotAuditTooFrequent,
+		//The tee worker already committed a seed for the next epoch
+		SeedCommitted,
+		//The tee worker did not commit a seed for the current epoch
+		NoSeedCommitment,
+		//The revealed seed does not match the commitment
+		SeedMismatch,
+		//The proofs are in a format that is not accepted anymore, or not yet
+		UnsupportedProofFormat,
+		//The absent verdict carries fewer connection attempts than required
+		InsufficientAttempts,
+		//A connection attempt is to another peer or outside the challenge
+		InvalidAttempt,
+		//Two connection attempts are closer than the required interval
+		AttemptsTooClose,
+		//Fewer than half of the tee committee signed the verdict
+		CommitteeMajorityMissing,
+		//The verdict is not signed by the node key of the tee worker
+		InvalidVerdictSignature,
+		//A connection attempt is not signed by the node key of the tee worker
+		InvalidAttemptSignature,
 	}
 
 	//Relevant time nodes for storage challenges
@@ -292,10 +416,22 @@ pub mod pallet {
 	#[pallet::getter(fn keys)]
 	pub(super) type Keys<T: Config> = StorageValue<_, WeakBoundedVec<T::AuthorityId, T::StringLimit>, ValueQuery>;
 
+	/// The block each authority may propose a challenge again from, unsigned proposals
+	/// whose digest was signed before it are rejected as replays.
+	#[pallet::storage]
+	#[pallet::getter(fn next_unsigned_at)]
+	pub(super) type NextUnsignedAt<T: Config> = StorageMap<_, Blake2_128Concat, T::AuthorityId, BlockNumberOf<T>>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn challenge_proposal)]
 	pub(super) type ChallengeProposal<T: Config> = CountedStorageMap<_, Blake2_128Concat, [u8; 32], (u32, ChallengeInfo<T>)>;
 
+	/// The authorities that voted for each challenge proposal, each counts once.
+	#[pallet::storage]
+	#[pallet::getter(fn challenge_proposal_voters)]
+	pub(super) type ChallengeProposalVoters<T: Config> =
+		StorageMap<_, Blake2_128Concat, [u8; 32], BoundedVec<T::AuthorityId, T::StringLimit>, ValueQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn challenge_snap_shot)]
 	pub(super) type ChallengeSnapShot<T: Config> = StorageValue<_, ChallengeInfo<T>>;
@@ -316,15 +452,100 @@ pub mod pallet {
 	#[pallet::getter(fn counted_clear)]
 	pub(super) type CountedClear<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, u8, ValueQuery>;
 
+	//Consecutive absent verdicts of each miner
+	#[pallet::storage]
+	#[pallet::getter(fn counted_unreachable)]
+	pub(super) type CountedUnreachable<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, u32, ValueQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn lock)]
 	pub(super) type Lock<T: Config> = StorageValue<_, bool, ValueQuery>;
 
+	// The extended challenge deadline of a miner, only valid for the challenge it was paid for.
+	#[pallet::storage]
+	#[pallet::getter(fn challenge_extension)]
+	pub(super) type ChallengeExtension<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, ChallengeExtensionInfo<BlockNumberOf<T>>>;
+
+	// The block at which a miner last extended its challenge deadline.
+	#[pallet::storage]
+	#[pallet::getter(fn last_extension)]
+	pub(super) type LastExtension<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberOf<T>>;
+
+	// Snapshots of the miners that have not submitted their proof yet, keyed by the extended deadline.
+	#[pallet::storage]
+	#[pallet::getter(fn extended_challenge)]
+	pub(super) type ExtendedChallenge<T: Config> = 
+		StorageMap<_, Blake2_128Concat, BlockNumberOf<T>, BoundedVec<MinerSnapShot<AccountOf<T>>, T::ChallengeMinerMax>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn last_sampling)]
+	pub(super) type LastSampling<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberOf<T>>;
+
+	// The bond reserved from each sampler.
+	#[pallet::storage]
+	#[pallet::getter(fn sampler_bond)]
+	pub(super) type Samplers<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BalanceOf<T>>;
+
+	// The sampling result of each sampler waiting for the tee committee.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_sampling)]
+	pub(super) type PendingSampling<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, SamplingReport<AccountOf<T>, BlockNumberOf<T>>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn miner_reputation)]
+	pub(super) type MinerReputation<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, ReputationInfo, ValueQuery>;
+
+	// Spot audits waiting for the next challenge, with the account that paid for each.
+	#[pallet::storage]
+	#[pallet::getter(fn spot_audit_queue)]
+	pub(super) type SpotAuditQueue<T: Config> =
+		StorageValue<_, BoundedVec<(Hash, AccountOf<T>), T::MaxSpotAudits>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn last_spot_audit)]
+	pub(super) type LastSpotAudit<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberOf<T>>;
+
+	// Spot audit fees paid to the tee worker verifying the next proof of a miner.
+	#[pallet::storage]
+	#[pallet::getter(fn spot_audit_bounty)]
+	pub(super) type SpotAuditBounty<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BalanceOf<T>, ValueQuery>;
+
+	// Tee workers that may not verify the next proof of a spot audited miner,
+	// the schedulers the audited files were uploaded through.
+	#[pallet::storage]
+	#[pallet::getter(fn spot_audit_exclusion)]
+	pub(super) type SpotAuditExclusion<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, BoundedVec<AccountOf<T>, T::MaxSpotAudits>, ValueQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn test_option)]
 	pub(super) type TestOption<T: Config> = 
 		StorageValue<_, Option<T::AccountId>>;
 
+	//Seed commitments of the tee workers, keyed by the epoch the seed is revealed in
+	#[pallet::storage]
+	#[pallet::getter(fn seed_commitment)]
+	pub(super) type SeedCommitment<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, u32, Blake2_128Concat, AccountOf<T>, H256>;
+
+	//The seeds revealed in an epoch mixed together, the challenges of the next epoch use it
+	#[pallet::storage]
+	#[pallet::getter(fn epoch_seed)]
+	pub(super) type EpochSeed<T: Config> = StorageMap<_, Twox64Concat, u32, [u8; 32], ValueQuery>;
+
+	//The proof formats miners can submit proofs in
+	#[pallet::storage]
+	#[pallet::getter(fn proof_protocol)]
+	pub(super) type ProofProtocols<T: Config> = StorageValue<_, ProofProtocol<BlockNumberOf<T>>, ValueQuery>;
+
+	//The randomness of the last accepted challenges, oldest first
+	#[pallet::storage]
+	#[pallet::getter(fn challenge_seed_log)]
+	pub(super) type ChallengeSeedLog<T: Config> =
+		StorageValue<_, BoundedVec<ChallengeSeedRecord<BlockNumberOf<T>>, ConstU32<CHALLENGE_SEED_LOG_LIMIT>>, ValueQuery>;
+
 	#[pallet::pallet]
 	#[pallet::storage_version(STORAGE_VERSION)]
 	#[pallet::generate_store(pub(super) trait Store)]
@@ -336,7 +557,9 @@ pub mod pallet {
 			let weight: Weight = Weight::from_ref_time(0);
 			weight
 				.saturating_add(Self::clear_challenge(now))
+				.saturating_add(Self::clear_extended_challenge(now))
 				.saturating_add(Self::clear_verify_mission(now))
+				.saturating_add(Self::clear_seed_epoch(now))
 		}
 
 		fn offchain_worker(now: T::BlockNumber) {
@@ -363,16 +586,23 @@ pub mod pallet {
 	impl<T: Config> Pallet<T> {
 		#[pallet::call_index(0)]
 		#[transactional]
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::save_challenge_info(
+			challenge_info.miner_snapshot_list.len() as u32,
+			T::MaxSpotAudits::get(),
+		))]
 		pub fn save_challenge_info(
 			origin: OriginFor<T>,
 			challenge_info: ChallengeInfo<T>,
-			_key: T::AuthorityId,
+			key: T::AuthorityId,
 			_seg_digest: SegDigest<BlockNumberOf<T>>,
 			_signature: <T::AuthorityId as RuntimeAppPublic>::Signature,
 		) -> DispatchResult {
 			ensure_none(origin)?;
 
+			// Each authority proposes at most once per `LockTime`, the same interval its offchain worker waits.
+			let now = <frame_system::Pallet<T>>::block_number();
+			<NextUnsignedAt<T>>::insert(&key, now.saturating_add(T::LockTime::get()));
+
 			let encode_info: Vec<u8> = challenge_info.encode();
 
 			let hash = sp_io::hashing::sha2_256(&encode_info);
@@ -382,34 +612,41 @@ pub mod pallet {
 				.checked_mul(2).ok_or(Error::<T>::Overflow)?
 				.checked_div(3).ok_or(Error::<T>::Overflow)?;
 
-			if ChallengeProposal::<T>::contains_key(&hash) {
-				let proposal = ChallengeProposal::<T>::get(&hash).unwrap();
-				if proposal.0 + 1 >= limit {
-					let cur_blcok = <ChallengeDuration<T>>::get();
-					let now = <frame_system::Pallet<T>>::block_number();
-					if now > cur_blcok {
-						let duration = now.checked_add(&proposal.1.net_snap_shot.life).ok_or(Error::<T>::Overflow)?;
-						<ChallengeDuration<T>>::put(duration);
-						let one_hour = T::OneHours::get();
-						let v_duration = duration
-							.checked_add(&proposal.1.net_snap_shot.life).ok_or(Error::<T>::Overflow)?
-							.checked_add(&one_hour).ok_or(Error::<T>::Overflow)?;
-						<VerifyDuration<T>>::put(v_duration);
-						let _ = ChallengeProposal::<T>::clear(ChallengeProposal::<T>::count(), None);
+			let proposal = match ChallengeProposal::<T>::get(&hash) {
+				Some(proposal) => proposal,
+				None => {
+					if ChallengeProposal::<T>::count() > count {
+						// Proposal Generally Less
+						Self::clear_challenge_proposals();
+						return Ok(());
 					}
+					(0, challenge_info)
+				},
+			};
 
-					Self::deposit_event(Event::<T>::GenerateChallenge);
-				}
-			} else {
-				if ChallengeProposal::<T>::count() > count {
-					// Proposal Generally Less
-					let _ = ChallengeProposal::<T>::clear(ChallengeProposal::<T>::count(), None);
-				} else {
-					ChallengeProposal::<T>::insert(
-						&hash,
-						(1, challenge_info),
-					);
+			let votes = ChallengeProposalVoters::<T>::try_mutate(&hash, |voters| -> Result<u32, DispatchError> {
+				ensure!(!voters.contains(&key), Error::<T>::DuplicateChallengeVote);
+				voters.try_push(key.clone()).map_err(|_| Error::<T>::BoundedVecError)?;
+				Ok(voters.len() as u32)
+			})?;
+			ChallengeProposal::<T>::insert(&hash, (votes, proposal.1.clone()));
+
+			if votes >= limit {
+				let cur_blcok = <ChallengeDuration<T>>::get();
+				if now > cur_blcok {
+					let duration = now.checked_add(&proposal.1.net_snap_shot.life).ok_or(Error::<T>::Overflow)?;
+					<ChallengeDuration<T>>::put(duration);
+					let one_hour = T::OneHours::get();
+					let v_duration = duration
+						.checked_add(&proposal.1.net_snap_shot.life).ok_or(Error::<T>::Overflow)?
+						.checked_add(&one_hour).ok_or(Error::<T>::Overflow)?;
+					<VerifyDuration<T>>::put(v_duration);
+					Self::schedule_spot_audits(&proposal.1);
+					Self::record_challenge_seed(now, &proposal.1.net_snap_shot);
+					Self::clear_challenge_proposals();
 				}
+
+				Self::deposit_event(Event::<T>::GenerateChallenge);
 			}
 
 			Ok(())
@@ -422,10 +659,12 @@ pub mod pallet {
 			origin: OriginFor<T>,
 			idle_prove: BoundedVec<u8, T::SigmaMax>,
 			service_prove: BoundedVec<u8, T::SigmaMax>,
+			proof_format: ProofFormat,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			ensure!(Self::proof_format_accepted(proof_format), Error::<T>::UnsupportedProofFormat);
 
-			let miner_snapshot = <ChallengeSnapShot<T>>::try_mutate(|challenge_opt| -> Result<MinerSnapShot<AccountOf<T>>, DispatchError> {
+			let result = <ChallengeSnapShot<T>>::try_mutate(|challenge_opt| -> Result<MinerSnapShot<AccountOf<T>>, DispatchError> {
 				let challenge_info = challenge_opt.as_mut().ok_or(Error::<T>::NoChallenge)?;
 
 				for (index, miner_snapshot) in challenge_info.miner_snapshot_list.clone().iter().enumerate() {
@@ -440,20 +679,38 @@ pub mod pallet {
 				}
 
 				Err(Error::<T>::NoChallenge)?
-			})?;
+			});
+			// The challenge may have been extended by the miner.
+			let miner_snapshot = match result {
+				Ok(miner_snapshot) => miner_snapshot,
+				Err(_) => Self::take_extended_snapshot(&sender)?,
+			};
+			<ChallengeExtension<T>>::remove(&sender);
 
-			let tee_list = T::Scheduler::get_controller_list();
+			let tee_list = T::Scheduler::get_role_controller_list(ROLE_VERIFIER);
 			ensure!(tee_list.len() > 0, Error::<T>::SystemError);
 
-			let seed: u32 = <frame_system::Pallet<T>>::block_number().saturated_into();
-			let index = Self::random_number(seed) as u32;
-			let index: u32 = index % (tee_list.len() as u32);
-			let tee_acc = &tee_list[index as usize];
+			let excluded = <SpotAuditExclusion<T>>::take(&sender);
+			let tee_acc = &if excluded.is_empty() {
+				let seed: u32 = <frame_system::Pallet<T>>::block_number().saturated_into();
+				let index = Self::random_number(seed) as u32;
+				let index: u32 = index % (tee_list.len() as u32);
+				tee_list[index as usize].clone()
+			} else {
+				let tee_acc = Self::select_spot_verifier(&sender, tee_list, &excluded);
+				Self::deposit_event(Event::<T>::SpotAuditVerifierSelected {
+					miner: sender.clone(),
+					tee_worker: tee_acc.clone(),
+					excluded: excluded.into_inner(),
+				});
+				tee_acc
+			};
 
 			let prove_info = ProveInfo::<T> {
 				snap_shot: miner_snapshot,
 				idle_prove,
 				service_prove,
+				proof_format,
 			};
 
 			<CountedClear<T>>::insert(&sender, u8::MIN);
@@ -463,12 +720,25 @@ pub mod pallet {
 
 				Ok(())
 			})?;
+			T::Scheduler::task_assigned(tee_acc, 1);
 
 			Self::deposit_event(Event::<T>::SubmitProof { miner: sender });
 
 			Ok(())
 		}
 
+		/// Submit the verdict on the proof of a miner.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the tee worker the proof was assigned to.
+		/// The verdict only counts once more than half of the tee committee of the session signed it.
+		///
+		/// Parameters:
+		/// - `miner`: The miner whose proof was verified.
+		/// - `idle_result`: Whether the idle proof is valid.
+		/// - `service_result`: Whether the service proof is valid.
+		/// - `nonce`: Nonce of the tee worker, above the one of its last consumed message.
+		/// - `signatures`: Signatures of committee members over
+		///   (miner, idle_result, service_result, challenge start, tee worker, nonce).
 		#[pallet::call_index(2)]
 		#[transactional]
 		#[pallet::weight(100_000_000)]
@@ -477,11 +747,16 @@ pub mod pallet {
 			miner: AccountOf<T>,
 			idle_result: bool,
 			service_result: bool,
-			_tee_signature: NodeSignature,
+			nonce: u64,
+			signatures: CommitteeSignatures<AccountOf<T>>,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-	
-			// TODO! Podr2Key verify
+
+			let challenge = <ChallengeSnapShot<T>>::try_get().map_err(|_| Error::<T>::UnexpectedError)?;
+			let message = (&miner, idle_result, service_result, challenge.net_snap_shot.start, &sender, nonce).encode();
+			ensure!(T::Scheduler::is_committee_majority(&message, &signatures), Error::<T>::CommitteeMajorityMissing);
+			T::Scheduler::consume_nonce(&sender, nonce)?;
+
 			UnverifyProof::<T>::mutate(&sender, |unverify_list| -> DispatchResult {
 				let _last_count = unverify_list.len();
 
@@ -490,6 +765,13 @@ pub mod pallet {
 						let snap_shot = <ChallengeSnapShot<T>>::try_get().map_err(|_| Error::<T>::UnexpectedError)?;
 
 						if idle_result && service_result {
+							T::MinerControl::record_challenge_result(&miner, true);
+						} else {
+							T::EventRouter::route(StorageEvent::ChallengeFailed { miner: miner.clone() });
+						}
+
+						if idle_result && service_result {
+							<CountedUnreachable<T>>::remove(&miner);
 							T::MinerControl::calculate_miner_reward(
 								&miner,
 								snap_shot.net_snap_shot.total_reward,
@@ -528,9 +810,447 @@ pub mod pallet {
 
 				Err(Error::<T>::NonExistentMission)?
 			})?;
+			T::Scheduler::task_finished(&sender, 1);
+
+			let bounty = <SpotAuditBounty<T>>::take(&miner);
+			if !bounty.is_zero() {
+				let pot: AccountOf<T> = T::MyPalletId::get().into_account_truncating();
+				<T as pallet::Config>::Currency::transfer(&pot, &sender, bounty, KeepAlive)?;
+			}
 
 			Self::deposit_event(Event::<T>::VerifyProof { tee_worker: sender, miner, });
 	
+			Ok(())
+		}
+		/// Submit the result of sampling a public dataset.
+		///
+		/// The dispatch origin of this call must be _Signed_ by a sampler.
+		/// Samplers fetch a fragment of an opted-in file from the miner storing it
+		/// and report whether the fragment was served correctly. The result only counts
+		/// once the tee committee checked the fragment with `confirm_sampling_result`,
+		/// until then the sampler cannot submit another one.
+		///
+		/// Parameters:
+		/// - `file_hash`: The sampled file.
+		/// - `fragment_hash`: The sampled fragment.
+		/// - `result`: Whether the miner served the fragment.
+		#[pallet::call_index(3)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn submit_sampling_result(
+			origin: OriginFor<T>,
+			file_hash: Hash,
+			fragment_hash: Hash,
+			result: bool,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Samplers<T>>::contains_key(&sender), Error::<T>::NotSampler);
+			ensure!(T::File::is_public_dataset(&file_hash), Error::<T>::NotPublicDataset);
+			let miner = T::File::get_fragment_miner(&file_hash, &fragment_hash)
+				.ok_or(Error::<T>::NonExistentFragment)?;
+			ensure!(sender != miner, Error::<T>::NotQualified);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			if let Some(last) = <LastSampling<T>>::get(&sender) {
+				let next = last.checked_add(&T::SamplingInterval::get()).ok_or(Error::<T>::Overflow)?;
+				ensure!(now >= next, Error::<T>::SamplingTooFrequent);
+			}
+			// A result the committee did not check within the interval is dropped unpaid.
+			if let Some(report) = <PendingSampling<T>>::get(&sender) {
+				let expiry = report.submitted_at.checked_add(&T::SamplingInterval::get()).ok_or(Error::<T>::Overflow)?;
+				ensure!(now >= expiry, Error::<T>::SamplingPending);
+			}
+			<LastSampling<T>>::insert(&sender, now);
+			<PendingSampling<T>>::insert(&sender, SamplingReport {
+				miner: miner.clone(),
+				file_hash,
+				fragment_hash,
+				result,
+				submitted_at: now,
+			});
+
+			Self::deposit_event(Event::<T>::SubmitSampling { sampler: sender, miner, file_hash, result });
+
+			Ok(())
+		}
+		/// Submit the check of the tee committee on a pending sampling result.
+		///
+		/// The dispatch origin of this call must be _Signed_ by a tee worker, and the check
+		/// only counts once more than half of the tee committee signed it. A confirmed result
+		/// is counted into the reputation of the miner and the sampler is rewarded from the
+		/// pallet account, a refuted one slashes `SamplerSlash` of the sampler bond into it.
+		///
+		/// Parameters:
+		/// - `sampler`: The sampler of the result.
+		/// - `served`: Whether the committee got the fragment from the miner, with the right hash.
+		/// - `nonce`: Nonce of the tee worker, above the one of its last consumed message.
+		/// - `signatures`: Signatures of committee members over
+		///   (sampler, miner, file hash, fragment hash, result, served, tee worker, nonce).
+		#[pallet::call_index(11)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn confirm_sampling_result(
+			origin: OriginFor<T>,
+			sampler: AccountOf<T>,
+			served: bool,
+			nonce: u64,
+			signatures: CommitteeSignatures<AccountOf<T>>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(T::Scheduler::contains_scheduler(sender.clone()), Error::<T>::ScheduleNonExistent);
+
+			let report = <PendingSampling<T>>::get(&sampler).ok_or(Error::<T>::NoPendingSampling)?;
+			let message = (
+				&sampler,
+				&report.miner,
+				report.file_hash,
+				report.fragment_hash,
+				report.result,
+				served,
+				&sender,
+				nonce,
+			).encode();
+			ensure!(T::Scheduler::is_committee_majority(&message, &signatures), Error::<T>::CommitteeMajorityMissing);
+			T::Scheduler::consume_nonce(&sender, nonce)?;
+			<PendingSampling<T>>::remove(&sampler);
+
+			let pot: AccountOf<T> = T::MyPalletId::get().into_account_truncating();
+			if served == report.result {
+				<MinerReputation<T>>::try_mutate(&report.miner, |reputation| -> DispatchResult {
+					reputation.sampled = reputation.sampled.checked_add(1).ok_or(Error::<T>::Overflow)?;
+					if !report.result {
+						reputation.failed = reputation.failed.checked_add(1).ok_or(Error::<T>::Overflow)?;
+					}
+					Ok(())
+				})?;
+
+				// The pot may run dry, the confirmed result is still recorded.
+				let mut reward = T::SamplingReward::get();
+				if <T as pallet::Config>::Currency::transfer(&pot, &sampler, reward, KeepAlive).is_err() {
+					reward = 0u32.saturated_into();
+				}
+
+				Self::deposit_event(Event::<T>::SamplingConfirmed {
+					sampler,
+					miner: report.miner,
+					file_hash: report.file_hash,
+					result: report.result,
+					reward,
+				});
+			} else {
+				let bond = <Samplers<T>>::get(&sampler).unwrap_or_else(Zero::zero);
+				let slash = T::SamplerSlash::get().mul_floor(bond);
+				let unmoved = <T as pallet::Config>::Currency::repatriate_reserved(&sampler, &pot, slash, BalanceStatus::Free)?;
+				let slashed = slash.saturating_sub(unmoved);
+				<Samplers<T>>::insert(&sampler, bond.saturating_sub(slashed));
+
+				Self::deposit_event(Event::<T>::SamplingRefuted {
+					sampler,
+					miner: report.miner,
+					file_hash: report.file_hash,
+					slashed,
+				});
+			}
+
+			Ok(())
+		}
+		/// Register as a sampler of public datasets.
+		///
+		/// The dispatch origin of this call must be _Signed_.
+		/// `SamplerBond` is reserved from the account, refuted sampling results are slashed from it.
+		#[pallet::call_index(12)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn register_sampler(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(!<Samplers<T>>::contains_key(&sender), Error::<T>::SamplerExists);
+
+			let bond = T::SamplerBond::get();
+			<T as pallet::Config>::Currency::reserve(&sender, bond)?;
+			<Samplers<T>>::insert(&sender, bond);
+
+			Self::deposit_event(Event::<T>::SamplerRegistered { sampler: sender, bond });
+
+			Ok(())
+		}
+		/// Stop sampling and get the rest of the bond back.
+		///
+		/// The dispatch origin of this call must be _Signed_ by a sampler
+		/// whose last result is not waiting for the tee committee.
+		#[pallet::call_index(13)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn unregister_sampler(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let bond = <Samplers<T>>::get(&sender).ok_or(Error::<T>::NotSampler)?;
+			if let Some(report) = <PendingSampling<T>>::get(&sender) {
+				let now = <frame_system::Pallet<T>>::block_number();
+				let expiry = report.submitted_at.checked_add(&T::SamplingInterval::get()).ok_or(Error::<T>::Overflow)?;
+				ensure!(now >= expiry, Error::<T>::SamplingPending);
+				<PendingSampling<T>>::remove(&sender);
+			}
+
+			<T as pallet::Config>::Currency::unreserve(&sender, bond);
+			<Samplers<T>>::remove(&sender);
+
+			Self::deposit_event(Event::<T>::SamplerUnregistered { sampler: sender, bond });
+
+			Ok(())
+		}
+		/// Request an extension of the challenge deadline.
+		///
+		/// A challenged miner that has not submitted its proof yet can delay
+		/// the punishment by `ExtensionLife` blocks, once per `ExtensionPeriod`.
+		/// The extended deadline never passes the end of the verification of the challenge.
+		/// The extension costs `ExtensionFee`, paid to the pallet account.
+		#[pallet::call_index(4)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn request_challenge_extension(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let duration = <ChallengeDuration<T>>::get();
+			ensure!(now < duration, Error::<T>::NoChallenge);
+			let challenge_info = <ChallengeSnapShot<T>>::get().ok_or(Error::<T>::NoChallenge)?;
+			ensure!(
+				challenge_info.miner_snapshot_list.iter().any(|snapshot| snapshot.miner == sender),
+				Error::<T>::NoChallenge
+			);
+
+			if let Some(last) = <LastExtension<T>>::get(&sender) {
+				let next = last.checked_add(&T::ExtensionPeriod::get()).ok_or(Error::<T>::Overflow)?;
+				ensure!(now >= next, Error::<T>::ExtensionUsed);
+			}
+
+			let fee = T::ExtensionFee::get();
+			let pot: AccountOf<T> = T::MyPalletId::get().into_account_truncating();
+			<T as pallet::Config>::Currency::transfer(&sender, &pot, fee, KeepAlive)?;
+
+			// The proof must still be verified before the verification of the challenge ends.
+			let deadline = duration
+				.checked_add(&T::ExtensionLife::get()).ok_or(Error::<T>::Overflow)?
+				.min(<VerifyDuration<T>>::get());
+			ensure!(deadline > duration, Error::<T>::ExtensionUnavailable);
+			<ChallengeExtension<T>>::insert(&sender, ChallengeExtensionInfo {
+				challenge_start: challenge_info.net_snap_shot.start,
+				deadline,
+			});
+			<LastExtension<T>>::insert(&sender, now);
+
+			Self::deposit_event(Event::<T>::ChallengeExtended { miner: sender, deadline, fee });
+
+			Ok(())
+		}
+		/// Request a spot audit of a file.
+		///
+		/// The miners storing the fragments of the file are added to the next challenge.
+		/// The fee of `SpotAuditFee` is held by the pallet account and paid to the tee
+		/// workers verifying the proofs of those miners.
+		/// Each account can request one spot audit per `SpotAuditInterval`.
+		///
+		/// Parameters:
+		/// - `file_hash`: The file to audit.
+		#[pallet::call_index(5)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn request_spot_audit(origin: OriginFor<T>, file_hash: Hash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(!T::File::get_file_miners(&file_hash).is_empty(), Error::<T>::NonExistentFile);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			if let Some(last) = <LastSpotAudit<T>>::get(&sender) {
+				let next = last.checked_add(&T::SpotAuditInterval::get()).ok_or(Error::<T>::Overflow)?;
+				ensure!(now >= next, Error::<T>::SpotAuditTooFrequent);
+			}
+
+			<SpotAuditQueue<T>>::try_mutate(|queue| -> DispatchResult {
+				ensure!(!queue.iter().any(|(hash, _)| hash == &file_hash), Error::<T>::SpotAuditQueued);
+				queue.try_push((file_hash, sender.clone())).map_err(|_| Error::<T>::StorageLimitReached)?;
+				Ok(())
+			})?;
+			<LastSpotAudit<T>>::insert(&sender, now);
+
+			let fee = T::SpotAuditFee::get();
+			let pot: AccountOf<T> = T::MyPalletId::get().into_account_truncating();
+			<T as pallet::Config>::Currency::transfer(&sender, &pot, fee, KeepAlive)?;
+
+			Self::deposit_event(Event::<T>::SpotAuditRequested { requester: sender, file_hash, fee });
+
+			Ok(())
+		}
+
+		/// Switch the format of the challenge proofs.
+		///
+		/// The dispatch origin of this call must be _Root_.
+		/// New challenges ask for the new format, proofs in the current format
+		/// are still accepted for `transition` blocks.
+		///
+		/// Parameters:
+		/// - `format`: The new proof format.
+		/// - `transition`: Blocks during which the current format is still accepted.
+		#[pallet::call_index(8)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn set_proof_format(
+			origin: OriginFor<T>,
+			format: ProofFormat,
+			transition: BlockNumberOf<T>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let transition_end = now.checked_add(&transition).ok_or(Error::<T>::Overflow)?;
+			let previous = <ProofProtocols<T>>::get().current;
+			<ProofProtocols<T>>::put(ProofProtocol {
+				current: format,
+				previous: Some(previous),
+				transition_end,
+			});
+
+			Self::deposit_event(Event::<T>::ProofFormatSet { current: format, previous, transition_end });
+
+			Ok(())
+		}
+
+		/// Commit to a seed revealed in the next epoch.
+		///
+		/// The dispatch origin of this call must be a tee worker.
+		/// The revealed seeds of an epoch randomize the challenges of the epoch after it,
+		/// so no one knows the seeds of the current epoch when committing to them.
+		///
+		/// Parameters:
+		/// - `commitment`: `blake2_256` of the tee worker account and the seed, SCALE encoded.
+		#[pallet::call_index(6)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn commit_seed(origin: OriginFor<T>, commitment: H256) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(T::Scheduler::contains_scheduler(sender.clone()), Error::<T>::ScheduleNonExistent);
+
+			let epoch = Self::seed_epoch(<frame_system::Pallet<T>>::block_number()).saturating_add(1);
+			ensure!(!<SeedCommitment<T>>::contains_key(epoch, &sender), Error::<T>::SeedCommitted);
+			<SeedCommitment<T>>::insert(epoch, &sender, commitment);
+
+			Self::deposit_event(Event::<T>::SeedCommitted { tee_worker: sender, epoch });
+
+			Ok(())
+		}
+
+		/// Reveal the seed committed in the previous epoch.
+		///
+		/// The dispatch origin of this call must be the tee worker that committed the seed.
+		/// A tee worker that does not reveal before the end of the epoch is punished.
+		/// The last tee worker to reveal can still withhold its seed to pick between two
+		/// outcomes, the punishment makes that choice expensive rather than impossible.
+		///
+		/// Parameters:
+		/// - `seed`: The committed seed.
+		#[pallet::call_index(7)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn reveal_seed(origin: OriginFor<T>, seed: [u8; 32]) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let epoch = Self::seed_epoch(<frame_system::Pallet<T>>::block_number());
+			let commitment = <SeedCommitment<T>>::take(epoch, &sender).ok_or(Error::<T>::NoSeedCommitment)?;
+			let hash = H256(sp_io::hashing::blake2_256(&(&sender, seed).encode()));
+			ensure!(hash == commitment, Error::<T>::SeedMismatch);
+
+			<EpochSeed<T>>::mutate(epoch, |mixed| {
+				*mixed = sp_io::hashing::blake2_256(&(*mixed, seed).encode());
+			});
+
+			Self::deposit_event(Event::<T>::SeedRevealed { tee_worker: sender, epoch });
+
+			Ok(())
+		}
+
+		/// Report that the miner whose proofs the tee worker verifies cannot be reached.
+		///
+		/// The dispatch origin of this call must be the tee worker the proofs were assigned to.
+		/// The verdict is backed by connection attempts spread over the challenge, so that an
+		/// unreachable miner is told apart from one that lost data. It clears the mission
+		/// without touching the failed challenge counts, and the miner is punished with the
+		/// lighter unreachable punishment once the verdicts repeat.
+		///
+		/// Parameters:
+		/// - `miner`: The unreachable miner.
+		/// - `attempts`: The attestations of the failed connections, oldest first.
+		/// - `nonce`: Nonce of the tee worker, above the one of its last consumed message.
+		/// - `tee_signature`: Signature of the tee worker over (miner, attempts, challenge start, nonce).
+		#[pallet::call_index(9)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn submit_absent_verdict(
+			origin: OriginFor<T>,
+			miner: AccountOf<T>,
+			attempts: BoundedVec<ConnectionAttempt<BlockNumberOf<T>>, T::MaxConnectionAttempts>,
+			nonce: u64,
+			tee_signature: NodeSignature,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let snap_shot = <ChallengeSnapShot<T>>::get().ok_or(Error::<T>::NoChallenge)?;
+			let message = (&miner, &attempts, snap_shot.net_snap_shot.start, nonce).encode();
+			let node_key = T::Scheduler::get_node_key(&sender)?;
+			ensure!(
+				sp_io::crypto::ed25519_verify(&sp_core::ed25519::Signature::from_raw(tee_signature), &message, &node_key),
+				Error::<T>::InvalidVerdictSignature
+			);
+			T::Scheduler::consume_nonce(&sender, nonce)?;
+			Self::check_connection_attempts(&miner, snap_shot.net_snap_shot.start, &attempts, &node_key)?;
+
+			UnverifyProof::<T>::try_mutate(&sender, |unverify_list| -> DispatchResult {
+				let index = unverify_list
+					.iter()
+					.position(|prove_info| prove_info.snap_shot.miner == miner)
+					.ok_or(Error::<T>::NonExistentMission)?;
+				unverify_list.remove(index);
+				Ok(())
+			})?;
+			T::Scheduler::task_finished(&sender, 1);
+			T::EventRouter::route(StorageEvent::ChallengeFailed { miner: miner.clone() });
+
+			let count = <CountedUnreachable<T>>::get(&miner) + 1;
+			let punished = count >= UNREACHABLE_FAULT_TOLERANT as u32;
+			if punished {
+				T::MinerControl::unreachable_punish(&miner)?;
+				<CountedUnreachable<T>>::remove(&miner);
+			} else {
+				<CountedUnreachable<T>>::insert(&miner, count);
+			}
+
+			Self::deposit_event(Event::<T>::MinerAbsent {
+				tee_worker: sender,
+				miner,
+				attempts: attempts.len() as u32,
+				punished,
+			});
+
+			Ok(())
+		}
+
+		/// Emit the randomness of the last accepted challenges.
+		///
+		/// The dispatch origin of this call must be _Root_.
+		/// Lets auditors check off-chain, e.g. after an upgrade touching consensus,
+		/// that the seeds are derived from the vrf outputs and the revealed epoch seeds.
+		///
+		/// Parameters:
+		/// - `count`: Number of records to emit, the newest ones.
+		#[pallet::call_index(10)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn report_challenge_randomness(origin: OriginFor<T>, count: u32) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let log = <ChallengeSeedLog<T>>::get().into_inner();
+			let skip = log.len().saturating_sub(count as usize);
+			let records = log.into_iter().skip(skip).collect();
+
+			Self::deposit_event(Event::<T>::ChallengeRandomnessReported { records });
+
 			Ok(())
 		}
 	}
@@ -556,49 +1276,285 @@ pub mod pallet {
 	}
 
 	impl<T: Config> Pallet<T> {
-		fn clear_challenge(now: BlockNumberOf<T>) -> Weight {
+		// At the start of an epoch the tee workers that did not reveal their seed in the last one
+		// are punished and their commitments dropped, along with the seed no challenge uses anymore.
+		fn clear_seed_epoch(now: BlockNumberOf<T>) -> Weight {
 			let mut weight: Weight = Weight::from_ref_time(0);
-			let duration = <ChallengeDuration<T>>::get();
-			weight = weight.saturating_add(T::DbWeight::get().reads(1));
-			if now == duration {
-				let snap_shot = match <ChallengeSnapShot<T>>::get() {
-					Some(snap_shot) => snap_shot,
-					None => return weight,
-				};
-				weight = weight.saturating_add(T::DbWeight::get().reads(1));
-				for miner_snapshot in snap_shot.miner_snapshot_list.iter() {
+			let epoch_length = T::SeedEpoch::get();
+			if epoch_length.is_zero() || !(now % epoch_length).is_zero() {
+				return weight;
+			}
 
-					let count = <CountedClear<T>>::get(&miner_snapshot.miner) + 1;
-					weight = weight.saturating_add(T::DbWeight::get().reads(1));
+			let epoch = Self::seed_epoch(now);
+			if epoch >= 1 {
+				for (tee_worker, _) in <SeedCommitment<T>>::drain_prefix(epoch - 1) {
+					if let Err(e) = T::Scheduler::punish_scheduler(tee_worker.clone()) {
+						log::error!("punish withheld seed of {:?} failed: {:?}", tee_worker, e);
+					}
+					Self::deposit_event(Event::<T>::SeedWithheld { tee_worker, epoch: epoch - 1 });
+					weight = weight.saturating_add(T::DbWeight::get().reads_writes(6, 6));
+				}
+			}
+			if epoch >= 2 {
+				<EpochSeed<T>>::remove(epoch - 2);
+				weight = weight.saturating_add(T::DbWeight::get().writes(1));
+			}
+
+			weight
+		}
 
-					let _ = T::MinerControl::clear_punish(
-						&miner_snapshot.miner, 
-						count, 
-						miner_snapshot.idle_space, 
-						miner_snapshot.service_space
-					);
-					weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+		// The attempts must be signed by the tee worker, to the peer the miner registered,
+		// made since the challenge started and spread by `AbsentAttemptInterval`,
+		// so a single dropped connection is not reported as an absent miner.
+		fn check_connection_attempts(
+			miner: &AccountOf<T>,
+			start: BlockNumberOf<T>,
+			attempts: &[ConnectionAttempt<BlockNumberOf<T>>],
+			node_key: &NodePublicKey,
+		) -> DispatchResult {
+			ensure!(attempts.len() as u32 >= T::MinAbsentAttempts::get(), Error::<T>::InsufficientAttempts);
 
-					if count >= 3 {
-						let result = T::File::force_miner_exit(&miner_snapshot.miner);
-						if result.is_err() {
-							log::info!("force clear miner: {:?} failed", miner_snapshot.miner);
+			let peer_id = T::MinerControl::get_peer_id(miner)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+			let mut last: Option<BlockNumberOf<T>> = None;
+			for attempt in attempts.iter() {
+				ensure!(attempt.peer_id == peer_id, Error::<T>::InvalidAttempt);
+				let message = (miner, &attempt.peer_id, attempt.at).encode();
+				ensure!(
+					sp_io::crypto::ed25519_verify(
+						&sp_core::ed25519::Signature::from_raw(attempt.tee_signature),
+						&message,
+						node_key,
+					),
+					Error::<T>::InvalidAttemptSignature
+				);
+				ensure!(attempt.at >= start && attempt.at <= now, Error::<T>::InvalidAttempt);
+				if let Some(last) = last {
+					let next = last.checked_add(&T::AbsentAttemptInterval::get()).ok_or(Error::<T>::Overflow)?;
+					ensure!(attempt.at >= next, Error::<T>::AttemptsTooClose);
+				}
+				last = Some(attempt.at);
+			}
+
+			Ok(())
+		}
+
+		// The current format, or the previous one during the transition window.
+		fn proof_format_accepted(format: ProofFormat) -> bool {
+			let protocol = <ProofProtocols<T>>::get();
+			if format == protocol.current {
+				return true;
+			}
+
+			protocol.previous == Some(format) &&
+				<frame_system::Pallet<T>>::block_number() < protocol.transition_end
+		}
+
+		fn seed_epoch(now: BlockNumberOf<T>) -> u32 {
+			let epoch_length = T::SeedEpoch::get();
+			if epoch_length.is_zero() {
+				return 0;
+			}
+
+			(now / epoch_length).saturated_into()
+		}
+
+		// The seed revealed in the last epoch, fixed for the whole current epoch.
+		fn challenge_seed() -> [u8; 32] {
+			let epoch = Self::seed_epoch(<frame_system::Pallet<T>>::block_number());
+
+			<EpochSeed<T>>::get(epoch.saturating_sub(1))
+		}
+
+		// Keep the randomness of an accepted challenge, dropping the oldest record when full.
+		fn record_challenge_seed(now: BlockNumberOf<T>, snap_shot: &NetSnapShot<BlockNumberOf<T>>) {
+			let epoch_seed = Self::challenge_seed();
+			let (seed, seed_block) =
+				T::MyRandomness::random(&(T::MyPalletId::get(), snap_shot.start, epoch_seed).encode());
+			let seed = seed
+				.and_then(|seed| H256::decode(&mut seed.as_ref()).ok())
+				.unwrap_or_default();
+			let challenge_hash =
+				H256(sp_io::hashing::blake2_256(&(&snap_shot.random_index_list, &snap_shot.random_list).encode()));
+			let vrf_digest = <frame_system::Pallet<T>>::digest()
+				.logs
+				.iter()
+				.find_map(|log| log.as_pre_runtime())
+				.and_then(|(_, data)| data.to_vec().try_into().ok())
+				.unwrap_or_default();
+
+			let record = ChallengeSeedRecord {
+				accepted_at: now,
+				start: snap_shot.start,
+				epoch_seed,
+				seed,
+				seed_block,
+				challenge_hash,
+				vrf_digest,
+			};
+			<ChallengeSeedLog<T>>::mutate(|log| {
+				if log.len() >= CHALLENGE_SEED_LOG_LIMIT as usize {
+					log.remove(0);
+				}
+				let _ = log.try_push(record);
+			});
+		}
+
+		// The miners still in the snapshot at the end of the challenge did not submit a proof,
+		// weighed as if every one of them was on its last strike.
+		fn clear_challenge(now: BlockNumberOf<T>) -> Weight {
+			let duration = <ChallengeDuration<T>>::get();
+			if now != duration {
+				return T::DbWeight::get().reads(1);
+			}
+			let snap_shot = match <ChallengeSnapShot<T>>::get() {
+				Some(snap_shot) => snap_shot,
+				None => return T::DbWeight::get().reads(2),
+			};
+			for miner_snapshot in snap_shot.miner_snapshot_list.iter() {
+				// Miners that extended the challenge are punished at their extended deadline,
+				// an extension left from an earlier challenge does not apply.
+				if let Some(extension) = <ChallengeExtension<T>>::get(&miner_snapshot.miner) {
+					if extension.challenge_start == snap_shot.net_snap_shot.start && extension.deadline > now {
+						let result = <ExtendedChallenge<T>>::mutate(&extension.deadline, |list| list.try_push(miner_snapshot.clone()));
+						if result.is_ok() {
+							continue;
 						}
-						<CountedClear<T>>::remove(&miner_snapshot.miner);
-					} else {
-						<CountedClear<T>>::insert(
-							&miner_snapshot.miner, 
-							count,
-						);
 					}
+					<ChallengeExtension<T>>::remove(&miner_snapshot.miner);
 				}
 
+				let _ = Self::punish_missing_proof(miner_snapshot);
+			}
+
+			T::WeightInfo::clear_challenge(snap_shot.miner_snapshot_list.len() as u32)
+		}
+
+		// Picks the tee worker verifying the proof of a spot audited miner from the block
+		// randomness, leaving out the excluded workers unless no other worker is left.
+		fn select_spot_verifier(
+			miner: &AccountOf<T>,
+			tee_list: Vec<AccountOf<T>>,
+			excluded: &[AccountOf<T>],
+		) -> AccountOf<T> {
+			let candidates: Vec<AccountOf<T>> = tee_list
+				.iter()
+				.filter(|tee| !excluded.contains(tee))
+				.cloned()
+				.collect();
+			let candidates = if candidates.is_empty() { tee_list } else { candidates };
+
+			let (random_seed, _) = T::MyRandomness::random(&(T::MyPalletId::get(), b"spot-verifier", miner).encode());
+			let random_seed = random_seed.unwrap_or_default();
+			let random_number = <u64>::decode(&mut random_seed.as_ref()).unwrap_or_default();
+			let index = (random_number % candidates.len() as u64) as usize;
+
+			candidates[index].clone()
+		}
+
+		// Moves the spot audits with miners in the accepted challenge out of the queue,
+		// the fee of each becomes a bounty of its challenged miners.
+		// The fee of a file deleted while queued stays in the pallet account.
+		fn schedule_spot_audits(challenge_info: &ChallengeInfo<T>) {
+			let fee = T::SpotAuditFee::get();
+			<SpotAuditQueue<T>>::mutate(|queue| {
+				queue.retain(|(file_hash, requester)| {
+					let file_miners = T::File::get_file_miners(file_hash);
+					if file_miners.is_empty() {
+						return false;
+					}
+					let miner_list: Vec<AccountOf<T>> = file_miners
+						.into_iter()
+						.filter(|miner| challenge_info.miner_snapshot_list.iter().any(|snapshot| &snapshot.miner == miner))
+						.collect();
+					if miner_list.is_empty() {
+						return true;
+					}
+
+					// The first miner collects the remainder of the division.
+					let share = fee / (miner_list.len() as u32).saturated_into();
+					let remainder = fee.saturating_sub(share.saturating_mul((miner_list.len() as u32).saturated_into()));
+					let scheduler = T::File::get_file_scheduler(file_hash);
+					for (index, miner) in miner_list.iter().enumerate() {
+						let bounty = if index == 0 { share.saturating_add(remainder) } else { share };
+						<SpotAuditBounty<T>>::mutate(miner, |value| *value = value.saturating_add(bounty));
+						if let Some(scheduler) = &scheduler {
+							<SpotAuditExclusion<T>>::mutate(miner, |excluded| {
+								if !excluded.contains(scheduler) {
+									let _ = excluded.try_push(scheduler.clone());
+								}
+							});
+						}
+					}
+
+					Self::deposit_event(Event::<T>::SpotAuditScheduled {
+						requester: requester.clone(),
+						file_hash: *file_hash,
+					});
+
+					false
+				});
+			});
+		}
+
+		fn clear_extended_challenge(now: BlockNumberOf<T>) -> Weight {
+			let mut weight: Weight = Weight::from_ref_time(0);
+			let extended_list = <ExtendedChallenge<T>>::take(&now);
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			for miner_snapshot in extended_list.iter() {
+				<ChallengeExtension<T>>::remove(&miner_snapshot.miner);
 				weight = weight.saturating_add(T::DbWeight::get().writes(1));
+				weight = weight.saturating_add(Self::punish_missing_proof(miner_snapshot));
+			}
+
+			weight
+		}
+
+		fn punish_missing_proof(miner_snapshot: &MinerSnapShot<AccountOf<T>>) -> Weight {
+			let mut weight: Weight = Weight::from_ref_time(0);
+			let count = <CountedClear<T>>::get(&miner_snapshot.miner) + 1;
+			weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+			let _ = T::MinerControl::clear_punish(
+				&miner_snapshot.miner, 
+				count, 
+				miner_snapshot.idle_space, 
+				miner_snapshot.service_space
+			);
+			T::EventRouter::route(StorageEvent::ChallengeFailed { miner: miner_snapshot.miner.clone() });
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 2));
+
+			if count >= 3 {
+				let result = T::File::force_miner_exit(&miner_snapshot.miner);
+				if result.is_err() {
+					log::info!("force clear miner: {:?} failed", miner_snapshot.miner);
+				}
+				<CountedClear<T>>::remove(&miner_snapshot.miner);
+			} else {
+				<CountedClear<T>>::insert(
+					&miner_snapshot.miner, 
+					count,
+				);
 			}
+			weight = weight.saturating_add(T::DbWeight::get().writes(1));
 
 			weight
 		}
 
+		fn take_extended_snapshot(miner: &AccountOf<T>) -> Result<MinerSnapShot<AccountOf<T>>, DispatchError> {
+			let extension = <ChallengeExtension<T>>::get(miner).ok_or(Error::<T>::NoChallenge)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(now < extension.deadline, Error::<T>::NoChallenge);
+
+			let miner_snapshot = <ExtendedChallenge<T>>::try_mutate(&extension.deadline, |list| -> Result<MinerSnapShot<AccountOf<T>>, DispatchError> {
+				let index = list.iter().position(|snapshot| &snapshot.miner == miner).ok_or(Error::<T>::NoChallenge)?;
+				Ok(list.remove(index))
+			})?;
+			<ChallengeExtension<T>>::remove(miner);
+
+			Ok(miner_snapshot)
+		}
+
 		fn clear_verify_mission(now: BlockNumberOf<T>) -> Weight {
 			let mut weight: Weight = Weight::from_ref_time(0);
 			let duration = <VerifyDuration<T>>::get();
@@ -606,13 +1562,14 @@ pub mod pallet {
 				let mut seed: u32 = 0;
 				// Used to calculate the new validation period.
 				let mut mission_count: u32 = 0;
-				let tee_list = T::Scheduler::get_controller_list();
+				let tee_list = T::Scheduler::get_role_controller_list(ROLE_VERIFIER);
 				let mut reassign_list: BTreeMap<AccountOf<T>, BoundedVec<ProveInfo<T>, T::VerifyMissionMax>> = Default::default();
 
 				for (acc, unverify_list) in UnverifyProof::<T>::iter() {
 					seed += 1;
 					weight = weight.saturating_add(T::DbWeight::get().reads(1));
 					if unverify_list.len() > 0 {
+						let mission_len = unverify_list.len() as u32;
 						match T::Scheduler::punish_scheduler(acc.clone()) {
 							Ok(()) => log::info!("punish scheduler success"),
 							Err(e) => log::error!("punish scheduler failed: {:?}", e),
@@ -646,7 +1603,8 @@ pub mod pallet {
 	
 						weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
 
-						UnverifyProof::<T>::remove(acc);
+						UnverifyProof::<T>::remove(&acc);
+						T::Scheduler::task_finished(&acc, mission_len);
 					}
 				}
 
@@ -655,7 +1613,8 @@ pub mod pallet {
 					<ChallengeSnapShot<T>>::kill();
 				} else {
 					for (acc, unverify_list) in reassign_list {
-						let result = UnverifyProof::<T>::mutate(acc, |tar_unverify_list| -> DispatchResult {
+						let mission_len = unverify_list.len() as u32;
+						let result = UnverifyProof::<T>::mutate(&acc, |tar_unverify_list| -> DispatchResult {
 							tar_unverify_list.try_append(&mut unverify_list.to_vec()).map_err(|_| Error::<T>::Overflow)?;
 							// tar_unverify_list.try_push(mission)
 							Ok(())
@@ -667,6 +1626,7 @@ pub mod pallet {
 							weight = weight.saturating_add(T::DbWeight::get().writes(1));
 							return weight;
 						}
+						T::Scheduler::task_assigned(&acc, mission_len);
 
 						weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
 					}
@@ -693,6 +1653,15 @@ pub mod pallet {
 				return InvalidTransaction::Stale.into();
 			} 
 
+			if seg_digest.block_num > <frame_system::Pallet<T>>::block_number() {
+				return InvalidTransaction::Future.into();
+			}
+			if let Some(next_unsigned_at) = <NextUnsignedAt<T>>::get(&key) {
+				if seg_digest.block_num < next_unsigned_at {
+					return InvalidTransaction::Stale.into();
+				}
+			}
+
 			let signature_valid = seg_digest.using_encoded(|encoded_seg_digest| {
 				key.verify(&encoded_seg_digest, &signature)
 			});
@@ -716,6 +1685,13 @@ pub mod pallet {
 				.build()
 		}
 
+		// Proposals are bounded by the number of authorities, one more than that clears them.
+		fn clear_challenge_proposals() {
+			let limit = ChallengeProposal::<T>::count().saturating_add(1);
+			let _ = ChallengeProposal::<T>::clear(limit, None);
+			let _ = ChallengeProposalVoters::<T>::clear(limit, None);
+		}
+
 		//Record challenge time
 		fn _record_challenge_time(duration: BlockNumberOf<T>) -> DispatchResult {
 			let now = <frame_system::Pallet<T>>::block_number();
@@ -902,6 +1878,44 @@ pub mod pallet {
 				}
 			}
 
+			// The miners of the files queued for a spot audit join the randomly selected ones,
+			// followed by the miners storing randomly drawn fillers and files,
+			// so that a miner holding more data is challenged more often.
+			let mut extra_miners: Vec<AccountOf<T>> = Default::default();
+			for (file_hash, _) in <SpotAuditQueue<T>>::get() {
+				extra_miners.extend(T::File::get_file_miners(&file_hash));
+			}
+			let data_count = need_miner_count / 2 + 1;
+			let data_seed: u32 = now.saturated_into();
+			for (miner, _) in T::File::get_random_fillers(data_count, data_seed) {
+				extra_miners.push(miner);
+			}
+			for file_hash in T::File::get_random_files(data_count, data_seed) {
+				extra_miners.extend(T::File::get_file_miners(&file_hash));
+			}
+			for miner in extra_miners {
+				if miner_list.iter().any(|snapshot| snapshot.miner == miner) {
+					continue;
+				}
+				match T::MinerControl::get_miner_state(&miner) {
+					Ok(state) if state != "lock".as_bytes().to_vec() => (),
+					_ => continue,
+				};
+				let (idle_space, service_space) = match T::MinerControl::get_power(&miner) {
+					Ok(power) => power,
+					Err(_) => continue,
+				};
+				let miner_total_space = idle_space + service_space;
+				if miner_total_space > max_space {
+					max_space = miner_total_space;
+				}
+				total_idle_space = total_idle_space.checked_add(idle_space).ok_or(OffchainErr::Overflow)?;
+				total_service_space = total_service_space.checked_add(service_space).ok_or(OffchainErr::Overflow)?;
+				if miner_list.try_push(MinerSnapShot::<AccountOf<T>> { miner, idle_space, service_space }).is_err() {
+					break;
+				}
+			}
+
 			let mut random_index_list: Vec<u32> = Default::default();
 			let need_count = CHUNK_COUNT * 46 / 1000;
 			let mut seed: u32 = u32::MIN;
@@ -934,6 +1948,7 @@ pub mod pallet {
 				total_service_space,
 				random_index_list: random_index_list.try_into().map_err(|_| OffchainErr::GenerateInfoError)?,
 				random_list: random_list.try_into().map_err(|_| OffchainErr::GenerateInfoError)?,
+				proof_format: <ProofProtocols<T>>::get().current,
 			};
 
 			Ok( ChallengeInfo::<T>{ net_snap_shot: snap_shot, miner_snapshot_list: miner_list } )
@@ -1016,8 +2031,10 @@ pub mod pallet {
 		}
 
 		// Generate a random number from a given seed.
+		// The revealed seeds of the tee workers are mixed in, so a block author alone cannot pick it.
 		pub fn random_number(seed: u32) -> u64 {
-			let (random_seed, _) = T::MyRandomness::random(&(T::MyPalletId::get(), seed).encode());
+			let (random_seed, _) =
+				T::MyRandomness::random(&(T::MyPalletId::get(), seed, Self::challenge_seed()).encode());
 			let random_seed = match random_seed {
 				Some(v) => v,
 				None => Default::default(),
@@ -1033,7 +2050,7 @@ pub mod pallet {
 			loop {
 				increase += 1;
 				let (r_seed, _) =
-					T::MyRandomness::random(&(T::MyPalletId::get(), increase).encode());
+					T::MyRandomness::random(&(T::MyPalletId::get(), increase, Self::challenge_seed()).encode());
 				let r_seed = match r_seed {
 					Some(v) => v,
 					None => Default::default(),
@@ -1049,6 +2066,32 @@ pub mod pallet {
 	}
 }
 
+impl<T: Config> ScheduleExit<<T as frame_system::Config>::AccountId> for Pallet<T> {
+	// The proofs the exited worker had to verify go to a random remaining worker.
+	fn on_scheduler_exit(acc: &AccountOf<T>) -> DispatchResult {
+		let unverify_list = UnverifyProof::<T>::get(acc);
+		if unverify_list.is_empty() {
+			return Ok(());
+		}
+
+		let tee_list = T::Scheduler::get_role_controller_list(ROLE_VERIFIER);
+		if tee_list.is_empty() {
+			log::info!("no tee worker left to take over the proofs of {:?}", acc);
+			return Ok(());
+		}
+
+		let index = Self::random_number(tee_list.len() as u32) as usize % tee_list.len();
+		UnverifyProof::<T>::try_mutate(&tee_list[index], |tar_unverify_list| -> DispatchResult {
+			tar_unverify_list.try_append(&mut unverify_list.to_vec()).map_err(|_| Error::<T>::Overflow)?;
+			Ok(())
+		})?;
+		T::Scheduler::task_assigned(&tee_list[index], unverify_list.len() as u32);
+		UnverifyProof::<T>::remove(acc);
+
+		Ok(())
+	}
+}
+
 impl<T: Config> sp_runtime::BoundToRuntimeAppPublic for Pallet<T> {
 	type Public = T::AuthorityId;
 }
@@ -1,75 +1,136 @@
 use crate::*;
-use codec::{Decode, Encode};
-use frame_support::{
-	codec, generate_storage_alias,
-	traits::{Get},
-};
 use frame_support::traits::OnRuntimeUpgrade;
 
-pub struct MigrationSegmentBook<T: crate::Config>(sp_std::marker::PhantomData<T>);
-impl<T: crate::Config> OnRuntimeUpgrade for MigrationSegmentBook<T> {
+/// Tag the running challenge and the proofs waiting for verification
+/// with the first proof format, and tie the challenge extensions to their challenge.
+pub struct MigrationProofFormat<T: crate::Config>(sp_std::marker::PhantomData<T>);
+impl<T: crate::Config> OnRuntimeUpgrade for MigrationProofFormat<T> {
 	fn on_runtime_upgrade() -> Weight {
-        log::info!("Audit migrate start!");
+		log::info!("Audit migrate start!");
 		migrate::<T>()
 	}
 
 	#[cfg(feature = "try-runtime")]
-	fn pre_upgrade() -> Result<(), &'static str> {
+	fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
 		log::info!("audit check access");
-		return Ok(())
+		return Ok(Default::default())
 	}
 
 	#[cfg(feature = "try-runtime")]
-	fn post_upgrade() -> Result<(), &'static str> {
-		let weights = migrate::<T>();
+	fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+		ensure!(StorageVersion::get::<Pallet<T>>() == 3, "audit not migrated");
 		return Ok(())
 	}
 }
 
 pub fn migrate<T: Config>() -> Weight {
 	let version = StorageVersion::get::<Pallet<T>>();
-	let mut weight: Weight = 0;
+	let mut weight: Weight = Weight::from_ref_time(0);
+
+	if version < 2 {
+		log::info!("Audit version 1 -> 2 migrations start!");
+		weight = weight.saturating_add(v2::migrate::<T>());
+		StorageVersion::new(2).put::<Pallet<T>>();
+	}
 
-	if version < 1 {
-        log::info!("Audit version 1 -> 2 migrations start!");
-        weight = weight.saturating_add(v2::migrate::<T>());
-        StorageVersion::new(2).put::<Pallet<T>>();
+	if version < 3 {
+		log::info!("Audit version 2 -> 3 migrations start!");
+		weight = weight.saturating_add(v3::migrate::<T>());
+		StorageVersion::new(3).put::<Pallet<T>>();
 	}
 
 	weight
 }
 
 mod v2 {
-    use super::*;
-
-    #[derive(Decode, Encode)]
-    struct OldProveInfo<T: Config> {
-	    file_id: Hash,
-	    miner_acc: AccountOf<T>,
-	    challenge_info: ChallengeInfo<T>,
-	    mu: BoundedList<T>,
-	    sigma: BoundedVec<u8, T::StringLimit>,
-	    name: BoundedVec<u8, T::StringLimit>,
-	    u: BoundedList<T>,
-    }
-
-    generate_storage_alias!(
-		Audit,
-		UnVerifyProof<T: Config> => Map<
-            (Blake2_128Concat, T::AccountId),
-            BoundedVec<OldProveInfo<T>, T::ChallengeMaximum>
-        >
-	);
-
-    pub fn migrate<T: Config>() -> Weight {
-        let mut weight: Weight = 0;
-
-        for (acc, prove_list) in <UnVerifyProof<T>>::iter() {
-            log::info!("prove_list len is: {:?}", prove_list.len());
-            <UnVerifyProof<T>>::remove(&acc);
-            weight = weight.saturating_add(T::DbWeight::get().writes(1 as Weight));
-        }
-
-        weight
-    }
-}
\ No newline at end of file
+	use super::*;
+
+	#[derive(Decode)]
+	struct OldNetSnapShot<Block> {
+		start: Block,
+		life: Block,
+		total_reward: u128,
+		total_idle_space: u128,
+		total_service_space: u128,
+		random_index_list: BoundedVec<u32, ConstU32<1024>>,
+		random_list: BoundedVec<[u8; 20], ConstU32<1024>>,
+	}
+
+	#[derive(Decode)]
+	struct OldChallengeInfo<T: Config> {
+		net_snap_shot: OldNetSnapShot<BlockNumberOf<T>>,
+		miner_snapshot_list: BoundedVec<MinerSnapShot<AccountOf<T>>, T::ChallengeMinerMax>,
+	}
+
+	#[derive(Decode)]
+	struct OldProveInfo<T: Config> {
+		snap_shot: MinerSnapShot<AccountOf<T>>,
+		idle_prove: BoundedVec<u8, T::SigmaMax>,
+		service_prove: BoundedVec<u8, T::SigmaMax>,
+	}
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		let _ = <ChallengeSnapShot<T>>::translate::<OldChallengeInfo<T>, _>(|old| {
+			old.map(|old| {
+				let snap_shot = old.net_snap_shot;
+				ChallengeInfo::<T> {
+					net_snap_shot: NetSnapShot {
+						start: snap_shot.start,
+						life: snap_shot.life,
+						total_reward: snap_shot.total_reward,
+						total_idle_space: snap_shot.total_idle_space,
+						total_service_space: snap_shot.total_service_space,
+						random_index_list: snap_shot.random_index_list,
+						random_list: snap_shot.random_list,
+						proof_format: ProofFormat::Podr2V1,
+					},
+					miner_snapshot_list: old.miner_snapshot_list,
+				}
+			})
+		});
+		weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+
+		// Proposals are submitted again by the validators every block.
+		let result = <ChallengeProposal<T>>::clear(u32::MAX, None);
+		weight = weight.saturating_add(T::DbWeight::get().writes(result.unique as u64));
+
+		<UnverifyProof<T>>::translate::<Vec<OldProveInfo<T>>, _>(|_acc, old_list| {
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			let prove_list: Vec<ProveInfo<T>> = old_list
+				.into_iter()
+				.map(|old| ProveInfo::<T> {
+					snap_shot: old.snap_shot,
+					idle_prove: old.idle_prove,
+					service_prove: old.service_prove,
+					proof_format: ProofFormat::Podr2V1,
+				})
+				.collect();
+			prove_list.try_into().ok()
+		});
+
+		weight
+	}
+}
+
+mod v3 {
+	use super::*;
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		// Extensions are only paid while a challenge is running, so they belong to the current one.
+		let challenge_start = <ChallengeSnapShot<T>>::get()
+			.map(|challenge_info| challenge_info.net_snap_shot.start)
+			.unwrap_or_default();
+		weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+		<ChallengeExtension<T>>::translate::<BlockNumberOf<T>, _>(|_miner, deadline| {
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			Some(ChallengeExtensionInfo { challenge_start, deadline })
+		});
+
+		weight
+	}
+}
@@ -5,7 +5,7 @@ use crate as audit;
 use frame_support::{
     parameter_types,
     weights::Weight,
-    traits::{ConstU32, EqualPrivilegeOnly, OneSessionHandler},
+    traits::{ConstU32, ConstU64, EqualPrivilegeOnly, OneSessionHandler},
 };
 use frame_system::{EnsureRoot};
 use sp_core::{H256, sr25519::Signature};
@@ -122,7 +122,10 @@ parameter_types! {
 	#[derive(Clone, Eq, PartialEq)]
 	pub const UploadFillerLimit: u8 = 10;
 	#[derive(Clone, Eq, PartialEq)]
+	pub const BatchDeleteLimit: u32 = 100;
+	#[derive(Clone, Eq, PartialEq)]
 	pub const InvalidLimit: u32 = 100000;
+	pub const InvalidFileGracePeriod: u64 = 100;
 	#[derive(Clone, Eq, PartialEq)]
 	pub const RecoverLimit: u32 = 8000;
 	#[derive(Clone, Eq, PartialEq)]
@@ -155,9 +158,14 @@ impl pallet_file_bank::Config for Test {
 	type BucketLimit = BucketLimit;
 	type OssFindAuthor = Oss;
 	type FrozenDays = FrozenDays;
+	type MaxLedgerEntries = ConstU32<200>;
+	type TrialDeposit = ConstU64<10>;
+	type TrialDepositPeriod = ConstU64<100>;
 	type RecoverLimit = RecoverLimit;
 	type InvalidLimit = InvalidLimit;
+	type InvalidFileGracePeriod = InvalidFileGracePeriod;
 	type UploadFillerLimit = UploadFillerLimit;
+	type BatchDeleteLimit = BatchDeleteLimit;
 	type MinLength = MinLength;
 }
 
@@ -283,6 +291,7 @@ impl pallet_cess_staking::Config for Test {
     type RewardRemainder = ();
     type RuntimeEvent = RuntimeEvent;
     type Slash = ();
+    type SlashPolicy = ();
     type Reward = ();
     type SessionsPerEra = ();
     type BondingDuration = ();
@@ -366,6 +375,7 @@ parameter_types! {
 
 impl pallet_sminer::Config for Test {
     type Currency = Balances;
+    type SlashPolicy = ();
       // The ubiquitous event type.
       type RuntimeEvent = RuntimeEvent;
       type PalletId = RewardPalletId;
@@ -455,11 +465,14 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
     }
         .assimilate_storage(&mut t)
         .unwrap();
-		pallet_file_bank::GenesisConfig::<Test> {
-			price: 30
-		}
-			.assimilate_storage(&mut t)
-			.unwrap();
+	frame_support::traits::GenesisBuild::<Test>::assimilate_storage(
+		&pallet_file_bank::GenesisConfig {
+			declaration_limit: None,
+			file_size_limit: None,
+		},
+		&mut t,
+	)
+		.unwrap();
     let mut ext = sp_io::TestExternalities::new(t);
     ext.execute_with(|| {
         System::set_block_number(1); //must set block_number, otherwise the deposit_event() don't work
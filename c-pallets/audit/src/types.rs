@@ -20,6 +20,8 @@ pub struct NetSnapShot<Block> {
 	pub(super) total_service_space: u128,
 	pub(super) random_index_list: BoundedVec<u32, ConstU32<1024>>,
 	pub(super) random_list: BoundedVec<[u8; 20], ConstU32<1024>>,
+	// Encoding the proofs of this challenge are expected in.
+	pub(super) proof_format: ProofFormat,
 }
 
 #[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
@@ -29,6 +31,14 @@ pub struct MinerSnapShot<AccountId> {
 	pub(super) service_space: u128,
 }
 
+// A paid extension of the challenge deadline of a miner.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ChallengeExtensionInfo<Block> {
+	// Start of the challenge the extension was paid for.
+	pub(super) challenge_start: Block,
+	pub(super) deadline: Block,
+}
+
 // Structure for storing miner certificates
 #[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 #[scale_info(skip_type_params(T))]
@@ -37,6 +47,8 @@ pub struct ProveInfo<T: pallet::Config> {
 	pub(super) snap_shot: MinerSnapShot<AccountOf<T>>,
 	pub(super) idle_prove: BoundedVec<u8, T::SigmaMax>,
 	pub(super) service_prove: BoundedVec<u8, T::SigmaMax>,
+	// Encoding of the proofs, tells the tee worker how to decode them.
+	pub(super) proof_format: ProofFormat,
 }
 
 #[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
@@ -55,3 +67,79 @@ pub struct SegDigest<BlockNumber> {
 	pub(super) block_num: BlockNumber,
 	pub(super) network_state: OpaqueNetworkState,
 }
+
+/// Attestation of a tee worker that it failed to connect to a miner.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ConnectionAttempt<BlockNumber> {
+	// Block at which the connection was attempted.
+	pub(super) at: BlockNumber,
+	// Peer the tee worker tried to connect to.
+	pub(super) peer_id: PeerId,
+	// Signature of the tee worker over the miner, the peer id and the block.
+	pub(super) tee_signature: NodeSignature,
+}
+
+#[derive(PartialEq, Eq, Encode, Decode, Clone, Default, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ReputationInfo {
+	// Number of samplings of the fragments stored by the miner.
+	pub(super) sampled: u32,
+	// Number of samplings the miner failed to serve.
+	pub(super) failed: u32,
+}
+
+/// A sampling result waiting for the tee committee to check it.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct SamplingReport<AccountId, BlockNumber> {
+	pub(super) miner: AccountId,
+	pub(super) file_hash: Hash,
+	pub(super) fragment_hash: Hash,
+	// Whether the sampler says the miner served the fragment.
+	pub(super) result: bool,
+	pub(super) submitted_at: BlockNumber,
+}
+
+/// Encoding of the PoDR2 proofs submitted for a challenge.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, Copy, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum ProofFormat {
+	// Sigma and tags as produced by the first PoDR2 release.
+	Podr2V1,
+	// Compressed tags.
+	Podr2Compressed,
+}
+
+impl Default for ProofFormat {
+	fn default() -> Self {
+		ProofFormat::Podr2V1
+	}
+}
+
+/// The proof formats accepted by the chain.
+///
+/// While switching formats the previous one is still accepted until `transition_end`,
+/// so that miners and tee workers can upgrade in any order.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, Default, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ProofProtocol<BlockNumber> {
+	pub(super) current: ProofFormat,
+	pub(super) previous: Option<ProofFormat>,
+	pub(super) transition_end: BlockNumber,
+}
+
+/// The randomness behind an accepted challenge, kept so that auditors can check off-chain
+/// that the challenges are not biased.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ChallengeSeedRecord<BlockNumber> {
+	// Block at which the challenge was accepted.
+	pub(super) accepted_at: BlockNumber,
+	// Start of the challenge as proposed by the validators.
+	pub(super) start: BlockNumber,
+	// Seed revealed by the tee workers that the challenge was mixed with.
+	pub(super) epoch_seed: [u8; 32],
+	// Output of the chain randomness for the pallet id, `start` and `epoch_seed`.
+	pub(super) seed: H256,
+	// Block the randomness output was derived from.
+	pub(super) seed_block: BlockNumber,
+	// `blake2_256` of the random index list and the random list of the challenge.
+	pub(super) challenge_hash: H256,
+	// Pre-runtime digest of the accepting block, carries the vrf output and proof of its author.
+	pub(super) vrf_digest: BoundedVec<u8, ConstU32<256>>,
+}
@@ -50,6 +50,8 @@ use sp_std::marker::PhantomData;
 pub trait WeightInfo {
 	fn submit_challenge_prove(v: u32, ) -> Weight;
 	fn verify_proof(v: u32, ) -> Weight;
+	fn save_challenge_info(v: u32, s: u32, ) -> Weight;
+	fn clear_challenge(v: u32, ) -> Weight;
 }
 
 /// Weights for pallet_audit using the Substrate node and recommended hardware.
@@ -75,6 +77,43 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(2 as u64))
 			.saturating_add(T::DbWeight::get().writes(1 as u64))
 	}
+	// Storage: Audit Keys (r:1 w:0)
+	// Storage: Audit ChallengeProposal (r:1 w:1)
+	// Storage: Audit CounterForChallengeProposal (r:1 w:1)
+	// Storage: Audit ChallengeDuration (r:1 w:1)
+	// Storage: Audit VerifyDuration (r:0 w:1)
+	// Storage: Audit SpotAuditQueue (r:1 w:1)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: Audit SpotAuditBounty (r:1 w:1)
+	// Storage: Audit SpotAuditExclusion (r:1 w:1)
+	// Storage: Audit ChallengeSeedLog (r:1 w:1)
+	fn save_challenge_info(v: u32, s: u32, ) -> Weight {
+		Weight::from_ref_time(61_874_000 as u64)
+			// Standard Error: 4_000
+			.saturating_add(Weight::from_ref_time(1_482_000 as u64).saturating_mul(v as u64))
+			// Standard Error: 651_000
+			.saturating_add(Weight::from_ref_time(23_916_000 as u64).saturating_mul(s as u64))
+			.saturating_add(T::DbWeight::get().reads(6 as u64))
+			.saturating_add(T::DbWeight::get().reads((3 as u64).saturating_mul(s as u64)))
+			.saturating_add(T::DbWeight::get().writes(6 as u64))
+			.saturating_add(T::DbWeight::get().writes((2 as u64).saturating_mul(s as u64)))
+	}
+	// Storage: Audit ChallengeDuration (r:1 w:0)
+	// Storage: Audit ChallengeSnapShot (r:1 w:0)
+	// Storage: Audit ChallengeExtension (r:1 w:0)
+	// Storage: Audit CountedClear (r:1 w:1)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: Sminer MinerReputation (r:1 w:1)
+	// Storage: Sminer CurrencyReward (r:1 w:1)
+	// Storage: FileBank RestoralTarget (r:1 w:1)
+	fn clear_challenge(v: u32, ) -> Weight {
+		Weight::from_ref_time(24_310_000 as u64)
+			// Standard Error: 38_000
+			.saturating_add(Weight::from_ref_time(52_647_000 as u64).saturating_mul(v as u64))
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().reads((6 as u64).saturating_mul(v as u64)))
+			.saturating_add(T::DbWeight::get().writes((5 as u64).saturating_mul(v as u64)))
+	}
 }
 
 // For backwards compatibility and tests
@@ -99,4 +138,41 @@ impl WeightInfo for () {
 			.saturating_add(RocksDbWeight::get().reads(2 as u64))
 			.saturating_add(RocksDbWeight::get().writes(1 as u64))
 	}
+	// Storage: Audit Keys (r:1 w:0)
+	// Storage: Audit ChallengeProposal (r:1 w:1)
+	// Storage: Audit CounterForChallengeProposal (r:1 w:1)
+	// Storage: Audit ChallengeDuration (r:1 w:1)
+	// Storage: Audit VerifyDuration (r:0 w:1)
+	// Storage: Audit SpotAuditQueue (r:1 w:1)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: Audit SpotAuditBounty (r:1 w:1)
+	// Storage: Audit SpotAuditExclusion (r:1 w:1)
+	// Storage: Audit ChallengeSeedLog (r:1 w:1)
+	fn save_challenge_info(v: u32, s: u32, ) -> Weight {
+		Weight::from_ref_time(61_874_000 as u64)
+			// Standard Error: 4_000
+			.saturating_add(Weight::from_ref_time(1_482_000 as u64).saturating_mul(v as u64))
+			// Standard Error: 651_000
+			.saturating_add(Weight::from_ref_time(23_916_000 as u64).saturating_mul(s as u64))
+			.saturating_add(RocksDbWeight::get().reads(6 as u64))
+			.saturating_add(RocksDbWeight::get().reads((3 as u64).saturating_mul(s as u64)))
+			.saturating_add(RocksDbWeight::get().writes(6 as u64))
+			.saturating_add(RocksDbWeight::get().writes((2 as u64).saturating_mul(s as u64)))
+	}
+	// Storage: Audit ChallengeDuration (r:1 w:0)
+	// Storage: Audit ChallengeSnapShot (r:1 w:0)
+	// Storage: Audit ChallengeExtension (r:1 w:0)
+	// Storage: Audit CountedClear (r:1 w:1)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: Sminer MinerReputation (r:1 w:1)
+	// Storage: Sminer CurrencyReward (r:1 w:1)
+	// Storage: FileBank RestoralTarget (r:1 w:1)
+	fn clear_challenge(v: u32, ) -> Weight {
+		Weight::from_ref_time(24_310_000 as u64)
+			// Standard Error: 38_000
+			.saturating_add(Weight::from_ref_time(52_647_000 as u64).saturating_mul(v as u64))
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().reads((6 as u64).saturating_mul(v as u64)))
+			.saturating_add(RocksDbWeight::get().writes((5 as u64).saturating_mul(v as u64)))
+	}
 }
@@ -0,0 +1,143 @@
+//! # Changelog Pallet
+//!
+//! Keeps a record of every runtime version of the chain, with the protocol features
+//! it enables and the storage migrations it applied, so that SDKs and worker software
+//! can tell which storage features are live on a network by reading the chain state.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_system::pallet_prelude::*;
+use frame_support::pallet_prelude::*;
+use sp_runtime::SaturatedConversion;
+use sp_std::prelude::*;
+
+pub use pallet::*;
+
+type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
+// Name of a protocol feature or a storage migration, for example `file-bank/file-versioning`.
+pub type ChangelogName = BoundedVec<u8, ConstU32<64>>;
+
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct ChangelogEntry<T: Config> {
+	// The block at which the runtime version took effect.
+	pub block: BlockNumberOf<T>,
+	// Every protocol feature live with this runtime version.
+	pub features: BoundedVec<ChangelogName, T::FeatureLimit>,
+	// The storage migrations applied by the upgrade to this runtime version.
+	pub migrations: BoundedVec<ChangelogName, T::FeatureLimit>,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + sp_std::fmt::Debug {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+		// Protocol features enabled by the current runtime
+		type Features: Get<Vec<&'static [u8]>>;
+		// Storage migrations applied by the current runtime upgrade
+		type Migrations: Get<Vec<&'static [u8]>>;
+		// Maximum number of features or migrations in one entry
+		#[pallet::constant]
+		type FeatureLimit: Get<u32> + Clone + Eq + PartialEq;
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		//A runtime version was added to the changelog
+		ChangelogAppended { spec_version: u32, features: u32, migrations: u32 },
+	}
+
+	/// The changelog entry of each runtime version, keyed by `spec_version`.
+	#[pallet::storage]
+	#[pallet::getter(fn changelog)]
+	pub(super) type Changelog<T: Config> = StorageMap<_, Blake2_128Concat, u32, ChangelogEntry<T>>;
+
+	/// The `spec_version` of the latest changelog entry.
+	#[pallet::storage]
+	#[pallet::getter(fn latest_spec_version)]
+	pub(super) type LatestSpecVersion<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::genesis_config]
+	#[derive(Default)]
+	pub struct GenesisConfig {}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			// No migration has been applied at genesis.
+			Pallet::<T>::append_entry(false);
+		}
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberOf<T>> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			Self::append_entry(true);
+			T::DbWeight::get().reads_writes(1, 2)
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Record the running runtime version, unless it is already recorded.
+	fn append_entry(with_migrations: bool) {
+		let spec_version = <T as frame_system::Config>::Version::get().spec_version;
+		if <Changelog<T>>::contains_key(spec_version) {
+			return;
+		}
+
+		let features = Self::to_names(T::Features::get());
+		let migrations = if with_migrations {
+			Self::to_names(T::Migrations::get())
+		} else {
+			Default::default()
+		};
+		let block = <frame_system::Pallet<T>>::block_number();
+
+		Self::deposit_event(Event::<T>::ChangelogAppended {
+			spec_version,
+			features: features.len().saturated_into(),
+			migrations: migrations.len().saturated_into(),
+		});
+		<Changelog<T>>::insert(spec_version, ChangelogEntry::<T> { block, features, migrations });
+		<LatestSpecVersion<T>>::put(spec_version);
+	}
+
+	fn to_names(list: Vec<&'static [u8]>) -> BoundedVec<ChangelogName, T::FeatureLimit> {
+		let mut names: BoundedVec<ChangelogName, T::FeatureLimit> = Default::default();
+		for name in list {
+			match ChangelogName::try_from(name.to_vec()) {
+				Ok(name) => {
+					if names.try_push(name).is_err() {
+						log::error!("changelog feature limit reached");
+						break;
+					}
+				},
+				Err(_) => log::error!("changelog name too long: {:?}", name),
+			}
+		}
+		names
+	}
+
+	/// Whether the feature is live in the latest runtime version.
+	pub fn is_feature_enabled(name: &[u8]) -> bool {
+		let spec_version = <LatestSpecVersion<T>>::get();
+		if let Some(entry) = <Changelog<T>>::get(spec_version) {
+			return entry.features.iter().any(|feature| feature.as_slice() == name);
+		}
+		false
+	}
+}
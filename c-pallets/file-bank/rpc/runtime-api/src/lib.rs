@@ -0,0 +1,59 @@
+//! Runtime API definition for the file-bank pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+use cp_cess_common::{
+	AccountStatement, CallWeight, FileBrief, Hash, LedgerKind, PeerId, RegionAvailability, ReputationBrief,
+	SpaceInfo, StorageCostEstimate,
+};
+
+sp_api::decl_runtime_apis! {
+	pub trait FileBankApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// Returns the tee worker assigned to the deal, its peer id,
+		/// and the block at which the current stage of the deal ends.
+		fn deal_scheduler(deal_hash: Hash) -> Option<(AccountId, PeerId, BlockNumber)>;
+		/// Returns the package of the user, `None` if no package was purchased.
+		fn query_user_space(acc: AccountId) -> Option<SpaceInfo<BlockNumber>>;
+		/// Returns the summary of a stored file.
+		fn query_file(file_hash: Hash) -> Option<FileBrief<AccountId, BlockNumber>>;
+		/// Returns every registered storage miner.
+		fn miner_list() -> Vec<AccountId>;
+		/// Returns the current popularity score of a file, decayed per era.
+		fn file_popularity(file_hash: Hash) -> u32;
+		/// Returns up to `limit` of the most popular files with their scores.
+		fn hot_files(limit: u32) -> Vec<(Hash, u32)>;
+		/// Returns the purchases, renewals, refunds and space changes of the package
+		/// of an account between two blocks, both inclusive.
+		fn account_statement(acc: AccountId, from_block: BlockNumber, to_block: BlockNumber) -> AccountStatement<BlockNumber>;
+		/// Returns the names of the buckets of an account.
+		fn user_buckets(acc: AccountId) -> Vec<Vec<u8>>;
+		/// Returns the files in a bucket of an account, `None` if the bucket does not exist.
+		fn bucket_files(acc: AccountId, bucket_name: Vec<u8>) -> Option<Vec<Hash>>;
+		/// Returns the challenge, endorsement and placement reputation of a miner.
+		fn miner_reputation(acc: AccountId) -> ReputationBrief;
+		/// Returns the storage key of a file and the SCALE encoded `FileInfo` stored under it.
+		fn file_storage(file_hash: Hash) -> (Vec<u8>, Option<Vec<u8>>);
+		/// Returns the child storage key of the file trie of the user, the key of a file in it
+		/// and the space charged for the file, `None` if the user does not hold the file.
+		fn user_file_storage(acc: AccountId, file_hash: Hash) -> (Vec<u8>, Vec<u8>, Option<u128>);
+		/// Returns the fee a package purchase, renewal or upgrade of the account would be charged,
+		/// `None` if the operation would fail. `count` is in gibs, or in months for a renewal.
+		fn query_package_price(acc: AccountId, package_type: LedgerKind, count: u32) -> Option<u128>;
+		/// Returns the weight of every file-bank call with its storage reads, writes and proof size.
+		fn call_weights() -> Vec<CallWeight>;
+		/// Returns the package and fees storing `replication` copies of a file of `file_size` bytes takes,
+		/// `None` if the unit price is not set or the size overflows.
+		fn estimate_storage_cost(file_size: u128, replication: u32) -> Option<StorageCostEstimate>;
+		/// Returns the miners, their health and the fragments they store per region,
+		/// as recomputed at the last day boundary.
+		fn region_heatmap() -> Vec<RegionAvailability>;
+		/// Returns the states the file entered through the events of the block,
+		/// "pending", "active", "pending_delete" or "deleted".
+		fn file_state_changes(file_hash: Hash) -> Vec<Vec<u8>>;
+	}
+}
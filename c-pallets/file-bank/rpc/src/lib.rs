@@ -0,0 +1,589 @@
+//! RPC interface for the file-bank pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use cp_cess_common::{Hash, LedgerKind};
+use futures::{stream, FutureExt, StreamExt};
+use jsonrpsee::{
+	core::{async_trait, Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::{
+		error::{CallError, ErrorObject},
+		SubscriptionResult,
+	},
+	SubscriptionSink,
+};
+use sc_client_api::{BlockchainEvents, ProofProvider};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::ChildInfo, traits::SpawnNamed, Bytes};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_file_bank_runtime_api::FileBankApi as FileBankRuntimeApi;
+
+/// The tee worker that receives the file data of a deal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DealScheduler<AccountId, BlockNumber> {
+	/// Controller account of the tee worker.
+	pub scheduler: AccountId,
+	/// Peer id of the tee worker.
+	pub peer_id: Vec<u8>,
+	/// Block at which the current stage of the deal ends,
+	/// the deal is reassigned if it is still unfinished by then.
+	pub deadline: BlockNumber,
+}
+
+/// Package of a user.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPackage<BlockNumber> {
+	pub total_space: u128,
+	pub used_space: u128,
+	pub locked_space: u128,
+	pub remaining_space: u128,
+	pub start: BlockNumber,
+	pub deadline: BlockNumber,
+	/// "normal", "frozen" or "dead".
+	pub state: String,
+}
+
+/// Summary of a stored file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSummary<AccountId, BlockNumber> {
+	/// Accounts holding the file.
+	pub owners: Vec<AccountId>,
+	pub file_size: u128,
+	pub segment_count: u32,
+	/// Block at which the file was stored.
+	pub completion: BlockNumber,
+	/// "active", "calculate", "missing" or "recovery".
+	pub state: String,
+}
+
+/// A file-bank `File` entry with the proof of it in the state trie.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileProof<BlockHash> {
+	/// Block whose state root the proof checks against.
+	pub at: BlockHash,
+	/// Storage key of the file.
+	pub key: Bytes,
+	/// SCALE encoded `FileInfo`, `None` if the file does not exist.
+	pub value: Option<Bytes>,
+	/// Trie nodes proving the value, or its absence, under the key.
+	pub proof: Vec<Bytes>,
+}
+
+/// A file in the file trie of a user with the proof of it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFileProof<BlockHash> {
+	/// Block whose state root the proof checks against.
+	pub at: BlockHash,
+	/// Child storage key of the file trie of the user, its keys are listed by `childstate_getKeys`.
+	pub child_key: Bytes,
+	/// Key of the file in the trie.
+	pub key: Bytes,
+	/// Space charged for the file, `None` if the user does not hold the file.
+	pub file_size: Option<u128>,
+	/// Trie nodes proving the entry, or its absence, from the state root through the child trie root.
+	pub proof: Vec<Bytes>,
+}
+
+/// Cost of storing a file in a new package, native and through the storage precompile.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCost {
+	/// Space taken from the package by all the copies of the file.
+	pub charged_space: u128,
+	/// Smallest package in gibs to buy for the file.
+	pub package_gib: u32,
+	/// Fee of that package for a month, before the region multiplier.
+	pub package_fee: u128,
+	/// Weight fee of the purchase and the upload declarations.
+	pub transaction_fee: u128,
+	/// Total of the package fee and the transaction fee.
+	pub native_cost: u128,
+	/// Gas of the same calls through the storage precompile.
+	pub evm_gas: u64,
+}
+
+/// A state a file entered in a finalized block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStateChange<BlockHash> {
+	/// The finalized block of the change.
+	pub at: BlockHash,
+	/// "pending", "active", "pending_delete" or "deleted".
+	pub state: String,
+}
+
+#[rpc(client, server)]
+pub trait FileBankApi<BlockHash, AccountId, BlockNumber> {
+	/// Query the tee worker assigned to a deal.
+	#[method(name = "cess_dealScheduler")]
+	fn deal_scheduler(
+		&self,
+		deal_hash: String,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<DealScheduler<AccountId, BlockNumber>>>;
+
+	/// Query the package purchased by a user.
+	#[method(name = "cess_getUserPackage")]
+	fn get_user_package(
+		&self,
+		acc: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<UserPackage<BlockNumber>>>;
+
+	/// Query the package of a user at a past block.
+	///
+	/// Unlike `cess_getUserPackage` the block must be given and known to the node,
+	/// blocks older than the state kept by the node can only be queried on archive nodes.
+	#[method(name = "cess_getUserPackageAt")]
+	fn get_user_package_at(
+		&self,
+		acc: AccountId,
+		block_hash: BlockHash,
+	) -> RpcResult<Option<UserPackage<BlockNumber>>>;
+
+	/// Query the summary of a file.
+	#[method(name = "cess_getFileInfo")]
+	fn get_file_info(
+		&self,
+		file_hash: String,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<FileSummary<AccountId, BlockNumber>>>;
+
+	/// Query the summary of a file at a past block, see `cess_getUserPackageAt`.
+	#[method(name = "cess_getFileAt")]
+	fn get_file_at(
+		&self,
+		file_hash: String,
+		block_hash: BlockHash,
+	) -> RpcResult<Option<FileSummary<AccountId, BlockNumber>>>;
+
+	/// Query every registered storage miner.
+	#[method(name = "cess_getMinerList")]
+	fn get_miner_list(&self, at: Option<BlockHash>) -> RpcResult<Vec<AccountId>>;
+
+	/// Quote the fee of a package operation of an account at a block.
+	///
+	/// `package_type` is "purchase", "renewal" or "upgrade", `count` the gibs bought,
+	/// the months renewed or the total gibs after the upgrade. Referral discounts are not included.
+	#[method(name = "cess_queryPackagePrice")]
+	fn query_package_price(
+		&self,
+		acc: AccountId,
+		package_type: String,
+		count: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<u128>>;
+
+	/// Query the stored info of a file with a read proof, for clients without the state.
+	#[method(name = "cess_getFileProof")]
+	fn get_file_proof(&self, file_hash: String, at: Option<BlockHash>) -> RpcResult<FileProof<BlockHash>>;
+
+	/// Query whether a user holds a file with a proof of it in the file trie of the user.
+	#[method(name = "cess_getUserFileProof")]
+	fn get_user_file_proof(
+		&self,
+		acc: AccountId,
+		file_hash: String,
+		at: Option<BlockHash>,
+	) -> RpcResult<UserFileProof<BlockHash>>;
+
+	/// Estimate the cost of storing `replication` copies of a file of `file_size` bytes.
+	///
+	/// Length fees and tips are not included, neither is the base cost of an evm transaction.
+	#[method(name = "cess_estimateStorageCost")]
+	fn estimate_storage_cost(
+		&self,
+		file_size: u128,
+		replication: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<StorageCost>>;
+
+	/// Subscribe to the states a file enters in finalized blocks.
+	#[subscription(
+		name = "cess_subscribeFileState" => "cess_fileState",
+		unsubscribe = "cess_unsubscribeFileState",
+		item = FileStateChange<BlockHash>,
+	)]
+	fn subscribe_file_state(&self, file_hash: String);
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// The hash is not a 64 byte hash string.
+	InvalidHash,
+	/// The read proof could not be generated.
+	ProofError,
+	/// The package type is not one of the quoted operations.
+	InvalidPackageType,
+	/// The block is not known to the node.
+	UnknownBlock,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::InvalidHash => 2,
+			Error::ProofError => 3,
+			Error::InvalidPackageType => 4,
+			Error::UnknownBlock => 5,
+		}
+	}
+}
+
+/// Provides RPC methods to query the file-bank pallet.
+pub struct FileBank<C, P> {
+	client: Arc<C>,
+	executor: Arc<dyn SpawnNamed>,
+	_marker: PhantomData<P>,
+}
+
+impl<C, P> FileBank<C, P> {
+	/// Create new `FileBank` with the given reference to the client,
+	/// the subscriptions are driven on `executor`.
+	pub fn new(client: Arc<C>, executor: Arc<dyn SpawnNamed>) -> Self {
+		Self { client, executor, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, AccountId, BlockNumber> FileBank<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: FileBankRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec,
+	BlockNumber: Codec,
+{
+	// Historical queries name their block, a block the node does not know
+	// is an error rather than a fallback to the best block.
+	fn known_block(&self, block_hash: <Block as BlockT>::Hash) -> Result<BlockId<Block>, CallError> {
+		match self.client.number(block_hash) {
+			Ok(Some(_)) => Ok(BlockId::hash(block_hash)),
+			Ok(None) => Err(CallError::Custom(ErrorObject::owned(
+				Error::UnknownBlock.into(),
+				"Block is not known to the node.",
+				Some(format!("{:?}", block_hash)),
+			))),
+			Err(e) => Err(runtime_error("Unable to look up the block.", e)),
+		}
+	}
+
+	fn user_package(
+		&self,
+		at: &BlockId<Block>,
+		acc: AccountId,
+		message: &str,
+	) -> RpcResult<Option<UserPackage<BlockNumber>>> {
+		let result = self
+			.client
+			.runtime_api()
+			.query_user_space(at, acc)
+			.map_err(|e| runtime_error(message, e))?;
+
+		Ok(result.map(|info| UserPackage {
+			total_space: info.total_space,
+			used_space: info.used_space,
+			locked_space: info.locked_space,
+			remaining_space: info.remaining_space,
+			start: info.start,
+			deadline: info.deadline,
+			state: String::from_utf8_lossy(&info.state).into_owned(),
+		}))
+	}
+
+	fn file_summary(
+		&self,
+		at: &BlockId<Block>,
+		file_hash: &str,
+		message: &str,
+	) -> RpcResult<Option<FileSummary<AccountId, BlockNumber>>> {
+		let file_hash = parse_hash(file_hash, "File hash must be 64 bytes.")?;
+
+		let result = self
+			.client
+			.runtime_api()
+			.query_file(at, file_hash)
+			.map_err(|e| runtime_error(message, e))?;
+
+		Ok(result.map(|brief| FileSummary {
+			owners: brief.owners,
+			file_size: brief.file_size,
+			segment_count: brief.segment_count,
+			completion: brief.completion,
+			state: String::from_utf8_lossy(&brief.state).into_owned(),
+		}))
+	}
+}
+
+#[async_trait]
+impl<C, Block, AccountId, BlockNumber>
+	FileBankApiServer<<Block as BlockT>::Hash, AccountId, BlockNumber> for FileBank<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ ProofProvider<Block>
+		+ BlockchainEvents<Block>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: FileBankRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec + Clone + Send + Sync + 'static,
+	BlockNumber: Codec + Clone + Send + Sync + 'static,
+{
+	fn deal_scheduler(
+		&self,
+		deal_hash: String,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<DealScheduler<AccountId, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let deal_hash = parse_hash(&deal_hash, "Deal hash must be 64 bytes.")?;
+
+		let result = api
+			.deal_scheduler(&at, deal_hash)
+			.map_err(|e| runtime_error("Unable to query deal scheduler.", e))?;
+
+		Ok(result.map(|(scheduler, peer_id, deadline)| DealScheduler {
+			scheduler,
+			peer_id: peer_id.to_vec(),
+			deadline,
+		}))
+	}
+	fn get_user_package(
+		&self,
+		acc: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<UserPackage<BlockNumber>>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		self.user_package(&at, acc, "Unable to query user package.")
+	}
+
+	fn get_user_package_at(
+		&self,
+		acc: AccountId,
+		block_hash: <Block as BlockT>::Hash,
+	) -> RpcResult<Option<UserPackage<BlockNumber>>> {
+		let at = self.known_block(block_hash)?;
+
+		self.user_package(&at, acc, "Unable to query user package, the state of the block may be pruned.")
+	}
+
+	fn get_file_info(
+		&self,
+		file_hash: String,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<FileSummary<AccountId, BlockNumber>>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		self.file_summary(&at, &file_hash, "Unable to query file info.")
+	}
+
+	fn get_file_at(
+		&self,
+		file_hash: String,
+		block_hash: <Block as BlockT>::Hash,
+	) -> RpcResult<Option<FileSummary<AccountId, BlockNumber>>> {
+		let at = self.known_block(block_hash)?;
+
+		self.file_summary(&at, &file_hash, "Unable to query file info, the state of the block may be pruned.")
+	}
+
+	fn get_miner_list(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<Vec<AccountId>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api
+			.miner_list(&at)
+			.map_err(|e| runtime_error("Unable to query miner list.", e))?;
+
+		Ok(result)
+	}
+
+	fn query_package_price(
+		&self,
+		acc: AccountId,
+		package_type: String,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<u128>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let package_type = match package_type.as_str() {
+			"purchase" => LedgerKind::Purchase,
+			"renewal" => LedgerKind::Renewal,
+			"upgrade" => LedgerKind::Upgrade,
+			_ => Err(CallError::Custom(ErrorObject::owned(
+				Error::InvalidPackageType.into(),
+				"Package type must be purchase, renewal or upgrade.",
+				Some(package_type.clone()),
+			)))?,
+		};
+
+		let result = api
+			.query_package_price(&at, acc, package_type, count)
+			.map_err(|e| runtime_error("Unable to quote package price.", e))?;
+
+		Ok(result)
+	}
+
+	fn get_file_proof(
+		&self,
+		file_hash: String,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<FileProof<<Block as BlockT>::Hash>> {
+		let api = self.client.runtime_api();
+		let block_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let at = BlockId::hash(block_hash);
+
+		let file_hash = parse_hash(&file_hash, "File hash must be 64 bytes.")?;
+
+		let (key, value) = api
+			.file_storage(&at, file_hash)
+			.map_err(|e| runtime_error("Unable to query file storage.", e))?;
+		let proof = self
+			.client
+			.read_proof(&block_hash, &mut std::iter::once(key.as_slice()))
+			.map_err(|e| {
+				CallError::Custom(ErrorObject::owned(
+					Error::ProofError.into(),
+					"Unable to generate the read proof.",
+					Some(e.to_string()),
+				))
+			})?;
+
+		Ok(FileProof {
+			at: block_hash,
+			key: key.into(),
+			value: value.map(Into::into),
+			proof: proof.into_iter_nodes().map(Into::into).collect(),
+		})
+	}
+
+	fn get_user_file_proof(
+		&self,
+		acc: AccountId,
+		file_hash: String,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<UserFileProof<<Block as BlockT>::Hash>> {
+		let api = self.client.runtime_api();
+		let block_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let at = BlockId::hash(block_hash);
+
+		let file_hash = parse_hash(&file_hash, "File hash must be 64 bytes.")?;
+
+		let (child_key, key, file_size) = api
+			.user_file_storage(&at, acc, file_hash)
+			.map_err(|e| runtime_error("Unable to query user file storage.", e))?;
+		let proof = self
+			.client
+			.read_child_proof(&block_hash, &ChildInfo::new_default(&child_key), &mut std::iter::once(key.as_slice()))
+			.map_err(|e| {
+				CallError::Custom(ErrorObject::owned(
+					Error::ProofError.into(),
+					"Unable to generate the read proof.",
+					Some(e.to_string()),
+				))
+			})?;
+
+		Ok(UserFileProof {
+			at: block_hash,
+			child_key: child_key.into(),
+			key: key.into(),
+			file_size,
+			proof: proof.into_iter_nodes().map(Into::into).collect(),
+		})
+	}
+
+	fn estimate_storage_cost(
+		&self,
+		file_size: u128,
+		replication: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<StorageCost>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let result = api
+			.estimate_storage_cost(&at, file_size, replication)
+			.map_err(|e| runtime_error("Unable to estimate storage cost.", e))?;
+
+		Ok(result.map(|estimate| StorageCost {
+			charged_space: estimate.charged_space,
+			package_gib: estimate.package_gib,
+			package_fee: estimate.package_fee,
+			transaction_fee: estimate.transaction_fee,
+			native_cost: estimate.package_fee.saturating_add(estimate.transaction_fee),
+			evm_gas: estimate.evm_gas,
+		}))
+	}
+
+	fn subscribe_file_state(&self, mut sink: SubscriptionSink, file_hash: String) -> SubscriptionResult {
+		let file_hash = match parse_hash(&file_hash, "File hash must be 64 bytes.") {
+			Ok(file_hash) => file_hash,
+			Err(e) => {
+				let _ = sink.reject(JsonRpseeError::Call(e));
+				return Ok(())
+			},
+		};
+
+		let client = self.client.clone();
+		let changes = self.client.finality_notification_stream().flat_map(move |notification| {
+			// Blocks finalized together with the notified one come first, oldest first.
+			let blocks: Vec<<Block as BlockT>::Hash> =
+				notification.tree_route.iter().cloned().chain(std::iter::once(notification.hash)).collect();
+			let changes: Vec<FileStateChange<<Block as BlockT>::Hash>> = blocks
+				.into_iter()
+				.flat_map(|hash| {
+					let states = client
+						.runtime_api()
+						.file_state_changes(&BlockId::hash(hash), file_hash)
+						.unwrap_or_default();
+					states.into_iter().map(move |state| FileStateChange {
+						at: hash,
+						state: String::from_utf8_lossy(&state).into_owned(),
+					})
+				})
+				.collect();
+			stream::iter(changes)
+		});
+
+		let fut = async move {
+			sink.pipe_from_stream(changes).await;
+		};
+		self.executor.spawn("cess-file-state-subscription", Some("rpc"), fut.boxed());
+
+		Ok(())
+	}
+}
+
+fn parse_hash(hash: &str, message: &str) -> Result<Hash, CallError> {
+	let hash = Hash::slice_to_array_64(hash.as_bytes()).map_err(|e| {
+		CallError::Custom(ErrorObject::owned(
+			Error::InvalidHash.into(),
+			message,
+			Some(format!("{:?}", e)),
+		))
+	})?;
+
+	Ok(Hash(hash))
+}
+
+fn runtime_error(message: &str, e: impl std::fmt::Display) -> CallError {
+	CallError::Custom(ErrorObject::owned(Error::RuntimeError.into(), message, Some(e.to_string())))
+}
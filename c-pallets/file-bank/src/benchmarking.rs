@@ -295,7 +295,7 @@ benchmarks! {
 			file_name: file_name.try_into().map_err(|_e| "file name convert err")?,
 			bucket_name: bucket_name.try_into().map_err(|_e| "bucket name convert err")?,
 		};
-	}: _(RawOrigin::Signed(caller), file_hash.clone(), user_brief)
+	}: _(RawOrigin::Signed(caller), file_hash.into(), user_brief)
 	verify {
 		let file_hash = Hash([5u8; 64]);
 		assert!(<File<T>>::contains_key(file_hash));
@@ -357,7 +357,7 @@ benchmarks! {
 		);
 		let (file_hash, caller, _, _) = add_file::<T>(file_hash)?;
 		assert!(File::<T>::contains_key(&file_hash));
-	}: _(RawOrigin::Signed(caller.clone()), caller.clone(), file_hash)
+	}: _(RawOrigin::Signed(caller.clone()), caller.clone(), vec![file_hash.into()])
 	verify {
 	    assert!(!File::<T>::contains_key(&file_hash));
 	}
@@ -434,7 +434,7 @@ benchmarks! {
 		file_hash_list.push(file_hash.clone());
 		let file_hash_list: BoundedVec<Hash, T::InvalidLimit> = file_hash_list.try_into().map_err(|_| "vec to boundedvec error")?;
 		<InvalidFile<T>>::insert(&miner, file_hash_list);
-	}: _(RawOrigin::Signed(miner.clone()), file_hash)
+	}: _(RawOrigin::Signed(miner.clone()), file_hash.into())
 	verify {
 		let list = <InvalidFile<T>>::get(&miner);
 		assert_eq!(list.len(), 0);
@@ -496,10 +496,75 @@ benchmarks! {
 		let file = <File<T>>::get(&file_hash).unwrap();
 		assert_eq!(file.user_brief_list[0].user, caller.clone());
 		let bounded_bucket_name1: BoundedVec<u8, T::NameStrLimit> = bucket_name1.try_into().map_err(|_| "bounded_vec convert err!")?;
-	}: _(RawOrigin::Signed(caller.clone()), bounded_bucket_name1.clone(), target_brief, file_hash.clone())
+	}: _(RawOrigin::Signed(caller.clone()), bounded_bucket_name1.clone(), target_brief, file_hash.into())
 	verify {
 		let file = <File<T>>::get(&file_hash).unwrap();
 		assert_eq!(file.user_brief_list.len(), 1);
 		assert_eq!(file.user_brief_list[0].user, target.clone());
 	}
+
+	lease_sweep {
+		let v in 0 .. 4999;
+		log::info!("start lease_sweep");
+		let mut target_list: Vec<AccountOf<T>> = Vec::new();
+		for i in 0 .. v {
+			target_list.push(account("user", i, SEED));
+		}
+		let target_list: BoundedVec<AccountOf<T>, ConstU32<5000>> = target_list.try_into().map_err(|_| "vec to boundedvec error")?;
+		<ClearUserList<T>>::put(target_list);
+	}: _(RawOrigin::Root)
+	verify {
+		assert_eq!(<ClearUserList<T>>::get().len(), v as usize);
+	}
+
+	clear_user_files {
+		let v in 0 .. CLEAR_FILE_BATCH;
+		log::info!("start clear_user_files");
+		let caller: AccountOf<T> = account("user1", 100, SEED);
+		let bucket_name: BoundedVec<u8, T::NameStrLimit> = "test-bucket1".as_bytes().to_vec().try_into().map_err(|_| "bounded_vec convert err!")?;
+		for i in 0 .. v {
+			let mut raw = [0u8; 64];
+			raw[..4].copy_from_slice(&i.to_le_bytes());
+			let file_hash = Hash(raw);
+			let user_brief = UserBrief::<T>{
+				user: caller.clone(),
+				file_name: "test-file".as_bytes().to_vec().try_into().map_err(|_| "bounded_vec convert err!")?,
+				bucket_name: bucket_name.clone(),
+			};
+			let file = FileInfo::<T>{
+				segment_list: Default::default(),
+				owner: vec![user_brief].try_into().map_err(|_| "bounded_vec convert err!")?,
+				file_size: 0,
+				completion: 0u32.saturated_into(),
+				stat: FileState::Active,
+			};
+			<File<T>>::insert(&file_hash, file);
+			FileBank::<T>::add_user_hold_fileslice(&caller, file_hash, 0)?;
+		}
+		let target_list: BoundedVec<AccountOf<T>, ConstU32<5000>> = vec![caller.clone()].try_into().map_err(|_| "vec to boundedvec error")?;
+		<ClearUserList<T>>::put(target_list);
+	}: {
+		FileBank::<T>::clear_user_files();
+	}
+	verify {
+		assert!(!<UserFileIndex<T>>::contains_key(&caller));
+		assert_eq!(<ClearUserList<T>>::get().len(), 0);
+	}
+
+	user_file_root {
+		log::info!("start user_file_root");
+		let caller: AccountOf<T> = account("user1", 100, SEED);
+		for i in 0 .. CLEAR_FILE_BATCH {
+			let mut raw = [0u8; 64];
+			raw[..4].copy_from_slice(&i.to_le_bytes());
+			FileBank::<T>::add_user_hold_fileslice(&caller, Hash(raw), 0)?;
+		}
+		let trie = FileBank::<T>::user_file_trie(&caller);
+		child::put(&trie, &[0u8; 64].encode(), &1u128);
+	}: {
+		FileBank::<T>::user_file_root(&trie);
+	}
+	verify {
+		assert!(<UserFileIndex<T>>::contains_key(&caller));
+	}
 }
@@ -1,4 +1,61 @@
 // The average number of bytes that a storage node can transmit within each block
 pub(super) const TRANSFER_RATE: u128 = 8_947_849;
 
-pub(super) const CALCULATE_RATE: u128 = 67_108_864;
\ No newline at end of file
+pub(super) const CALCULATE_RATE: u128 = 67_108_864;
+
+// Blocks the scheduler has to report the missing segments after the calculation ended.
+pub(super) const SEGMENT_REPORT_PERIOD: u32 = 600;
+
+// Placement weight of a miner with the lowest reputation, out of 1000,
+// so that a miner can still be chosen and win back its reputation.
+pub(super) const MIN_PLACEMENT_WEIGHT: u64 = 10;
+
+// The used space of a user may differ from its file list by at most one segment before it is corrected.
+pub(super) const SPACE_AUDIT_TOLERANCE: u128 = cp_cess_common::SEGMENT_SIZE;
+
+// File access permission bits.
+pub(super) const ACL_READ: u8 = 0b001;
+
+pub(super) const ACL_WRITE: u8 = 0b010;
+
+pub(super) const ACL_DELETE: u8 = 0b100;
+
+pub(super) const ACL_ALL: u8 = ACL_READ | ACL_WRITE | ACL_DELETE;
+
+// Name of the periodic scheduler task that sweeps expired leases.
+pub(super) const LEASE_SWEEP_TASK_ID: &[u8] = b"file-bank/lease-sweep";
+
+// Files the recovery scan goes through per block.
+pub(super) const RECOVERY_SCAN_BATCH: usize = 50;
+
+// Prefix of the child storage key of the file trie of a user, followed by the encoded account.
+pub(super) const USER_FILE_TRIE_PREFIX: &[u8] = b"cess/file-bank/user-files/";
+
+// Storage orders settled per block.
+pub(super) const STORAGE_ORDER_BATCH: usize = 50;
+
+// Files moved per block from the file lists of users into their file tries by the version 4 migration.
+pub(super) const USER_FILE_MIGRATION_BATCH: u32 = 300;
+
+// Users swept at most by a lease sweep, the bound of the lease expire queue of a day
+// and of the list of users whose files are cleared.
+pub(super) const LEASE_SWEEP_LIMIT: u32 = 5000;
+
+// Draws made per random filler or file position before giving up on finding a new one.
+pub(super) const RANDOM_POSITION_DRAWS: u32 = 4;
+
+// Files of cleared users deleted per block.
+pub(super) const CLEAR_FILE_BATCH: u32 = 300;
+
+// Share links a file can have at once.
+pub(super) const FILE_SHARE_LIMIT: u32 = 20;
+
+// Invalid file reminders the offchain worker submits per scan.
+pub(super) const REMINDER_BATCH: u32 = 20;
+
+// Blocks between two invalid file scans of the offchain worker.
+pub(super) const REMINDER_SCAN_INTERVAL: u32 = 10;
+
+// The queues of the block hook take at most the maximum block weight divided by this,
+// what does not fit is handled in the next blocks.
+pub(super) const QUEUE_WEIGHT_DIVISOR: u64 = 4;
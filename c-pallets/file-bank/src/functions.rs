@@ -85,6 +85,8 @@ impl<T: Config> Pallet<T> {
         };
 
         <File<T>>::insert(file_hash, file_info);
+        <FileRefCount<T>>::insert(file_hash, 1);
+        Self::refresh_file_summary(file_hash);
 
         Ok(())
     }
@@ -131,19 +133,36 @@ impl<T: Config> Pallet<T> {
         })
     }
 
+    // Releases a slot of the pending deals of `acc` once one of its deals leaves `DealMap`.
+    pub(super) fn pending_deal_finished(acc: &AccountOf<T>) {
+        <PendingDealCount<T>>::mutate_exists(acc, |count_opt| {
+            *count_opt = count_opt.map(|count| count.saturating_sub(1)).filter(|count| *count != 0);
+        });
+    }
+
     pub(super) fn generate_deal(
         file_hash: Hash, 
         file_info: BoundedVec<SegmentList<T>, T::SegmentCount>, 
         user_brief: UserBrief<T>,
         file_size: u128,
+        policy: StoragePolicy,
     ) -> DispatchResult {
-        let miner_task_list = Self::random_assign_miner(&file_info)?;
+        <PendingDealCount<T>>::try_mutate(&user_brief.user, |count| -> DispatchResult {
+            ensure!(*count < T::MaxPendingDeals::get(), Error::<T>::TooManyPendingDeals);
+            *count = count.saturating_add(1);
+            Ok(())
+        })?;
+
+        let rate = T::StorageHandle::get_user_unit_price(&user_brief.user)?;
+        let miner_task_list = Self::random_assign_miner(&file_info, rate, &policy)?;
 
         let space = Self::cal_file_size(file_info.len() as u128);
 
         let life = space / TRANSFER_RATE + 1;
 
-        Self::start_first_task(file_hash.0.to_vec(), file_hash, 1, life as u32)?;
+        let scheduler = Self::assign_scheduler()?;
+
+        let deadline = Self::start_first_task(file_hash.0.to_vec(), file_hash, 1, life as u32)?;
 
         let deal = DealInfo::<T> {
             stage: 1,
@@ -155,14 +174,19 @@ impl<T: Config> Pallet<T> {
             assigned_miner: miner_task_list,
             share_info: Default::default(),
             complete_list: Default::default(),
+            scheduler,
+            deadline,
         };
 
         DealMap::insert(&file_hash, deal);
+        if policy != StoragePolicy::default() {
+            <DealPolicy<T>>::insert(&file_hash, policy);
+        }
 
         Ok(())
     }
 
-    pub(super) fn start_first_task(task_id: Vec<u8>, deal_hash: Hash, count: u8, life: u32) -> DispatchResult {
+    pub(super) fn start_first_task(task_id: Vec<u8>, deal_hash: Hash, count: u8, life: u32) -> Result<BlockNumberOf<T>, DispatchError> {
         let start: u32 = <frame_system::Pallet<T>>::block_number().saturated_into();
         let survival_block = start
             .checked_add(50 * (count as u32)).ok_or(Error::<T>::Overflow)?
@@ -177,10 +201,10 @@ impl<T: Config> Pallet<T> {
                 Call::deal_reassign_miner{deal_hash: deal_hash, count: count, life: life}.into(),
         ).map_err(|_| Error::<T>::Unexpected)?;
 
-        Ok(())
+        Ok(survival_block.saturated_into())
     }
     
-    pub(super) fn start_second_task(task_id: Vec<u8>, deal_hash: Hash, life: u32) -> DispatchResult {
+    pub(super) fn start_second_task(task_id: Vec<u8>, deal_hash: Hash, life: u32) -> Result<BlockNumberOf<T>, DispatchError> {
         let start: u32 = <frame_system::Pallet<T>>::block_number().saturated_into();
         // todo! calculate time
         let survival_block = start
@@ -195,11 +219,978 @@ impl<T: Config> Pallet<T> {
                 Call::calculate_end{deal_hash: deal_hash}.into(), 
         ).map_err(|_| Error::<T>::Unexpected)?;
 
+        Ok(survival_block.saturated_into())
+    }
+    pub(super) fn start_third_task(task_id: Vec<u8>, deal_hash: Hash) -> Result<BlockNumberOf<T>, DispatchError> {
+        let start: u32 = <frame_system::Pallet<T>>::block_number().saturated_into();
+        let survival_block = start
+            .checked_add(SEGMENT_REPORT_PERIOD).ok_or(Error::<T>::Overflow)?;
+
+        T::FScheduler::schedule_named(
+                task_id,
+                DispatchTime::At(survival_block.saturated_into()),
+                Option::None,
+                schedule::HARD_DEADLINE,
+                frame_system::RawOrigin::Root.into(),
+                Call::segment_report_timeout{deal_hash: deal_hash}.into(),
+        ).map_err(|_| Error::<T>::Unexpected)?;
+
+        Ok(survival_block.saturated_into())
+    }
+    /// helper: check segments completed.
+    ///
+    /// Whether the scheduler reported every segment of the deal.
+    ///
+    /// Parameters:
+    /// - `deal_hash`: deal hash.
+    /// - `segment_count`: number of segments in the declaration.
+    pub(super) fn check_segments_completed(deal_hash: &Hash, segment_count: u32) -> bool {
+        <FileSegments<T>>::iter_prefix(deal_hash).count() as u32 == segment_count
+    }
+    /// helper: activate file.
+    ///
+    /// The last step of a deal, the file becomes active and the deal is removed.
+    ///
+    /// Parameters:
+    /// - `deal_hash`: deal hash.
+    pub(super) fn activate_file(deal_hash: &Hash) -> DispatchResult {
+        let mut scheduler: Option<AccountOf<T>> = None;
+        if let Some(deal_info) = <DealMap<T>>::get(deal_hash) {
+            T::Scheduler::task_finished(&deal_info.scheduler, 1);
+            Self::pending_deal_finished(&deal_info.user.user);
+            <FileScheduler<T>>::insert(deal_hash, &deal_info.scheduler);
+            scheduler = Some(deal_info.scheduler);
+        }
+
+        let (owner, file_size) = <File<T>>::try_mutate(deal_hash, |file_opt| -> Result<(AccountOf<T>, u128), DispatchError> {
+            let file = file_opt.as_mut().ok_or(Error::<T>::BugInvalid)?;
+            file.stat = FileState::Active;
+            let owner = file.owner.first().ok_or(Error::<T>::BugInvalid)?.user.clone();
+            Ok((owner, file.file_size))
+        })?;
+        Self::refresh_file_summary(deal_hash);
+        Self::index_active_file(deal_hash);
+        Self::start_storage_order(&owner, deal_hash);
+
+        <DealMap<T>>::remove(deal_hash);
+        <DealPolicy<T>>::remove(deal_hash);
+        Self::release_deal_deposit(deal_hash);
+
+        if let Some(scheduler) = &scheduler {
+            T::EventRouter::route(StorageEvent::DealCompleted {
+                owner: owner.clone(),
+                scheduler: scheduler.clone(),
+                file_hash: *deal_hash,
+                file_size,
+            });
+        }
+        Self::deposit_event(Event::<T>::CalculateEnd{ file_hash: *deal_hash, owner, file_size, scheduler });
+
+        Ok(())
+    }
+
+    /// helper: remove deal.
+    ///
+    /// Drop a deal that will not be stored. The reserved space of the owner
+    /// and of the miners is released and the declaration deposit is refunded.
+    ///
+    /// Parameters:
+    /// - `deal_hash`: deal hash.
+    pub(super) fn remove_deal(deal_hash: &Hash) -> DispatchResult {
+        let deal_info = <DealMap<T>>::try_get(deal_hash).map_err(|_| Error::<T>::NonExistent)?;
+        Self::release_deal(deal_hash, &deal_info.user.user, deal_info.segment_list.len() as u128, &deal_info.assigned_miner)?;
+
+        T::Scheduler::task_finished(&deal_info.scheduler, 1);
+        <DealMap<T>>::remove(deal_hash);
+
+        Ok(())
+    }
+
+    // Releases what a deal reserved from its owner and its miners, the entry of `DealMap` is left to the caller.
+    pub(super) fn release_deal(
+        deal_hash: &Hash,
+        owner: &AccountOf<T>,
+        segment_count: u128,
+        assigned_miner: &BoundedVec<MinerTaskList<T>, T::StringLimit>,
+    ) -> DispatchResult {
+        // A deal paid by a storage order gives the whole escrow back.
+        if <StorageOrders<T>>::contains_key(owner, deal_hash) {
+            Self::close_storage_order(owner, deal_hash)?;
+        } else {
+            let needed_space = Self::cal_file_size(segment_count);
+            T::StorageHandle::unlock_user_space(owner, needed_space)?;
+        }
+        // unlock mienr space
+        for miner_task in assigned_miner.iter() {
+            let count = miner_task.fragment_list.len() as u128;
+            T::MinerControl::cancel_space(&miner_task.miner, FRAGMENT_SIZE * count)?;
+        }
+
+        Self::pending_deal_finished(owner);
+        <DealPolicy<T>>::remove(deal_hash);
+        Self::release_deal_deposit(deal_hash);
+        Self::clear_file_segments(deal_hash);
+
+        Ok(())
+    }
+
+    /// helper: remove calculated deal.
+    ///
+    /// Drop a deal whose calculation ended but whose segments were not all reported.
+    /// The file generated for the deal is deleted from every owner, the space of the owners
+    /// and of the miners is released and the declaration deposit is refunded.
+    ///
+    /// Parameters:
+    /// - `deal_hash`: deal hash.
+    pub(super) fn remove_calculated_deal(deal_hash: &Hash) -> DispatchResult {
+        let deal_info = <DealMap<T>>::try_get(deal_hash).map_err(|_| Error::<T>::NonExistent)?;
+        let owner = deal_info.user.user.clone();
+        let file = <File<T>>::try_get(deal_hash).map_err(|_| Error::<T>::NonExistent)?;
+
+        // Holders that declared the file again while it was calculated are refunded too.
+        for user_brief in file.owner.iter() {
+            Self::bucket_remove_file(deal_hash, &user_brief.user, &file)?;
+            Self::remove_user_hold_file_list(deal_hash, &user_brief.user)?;
+            if user_brief.user != owner {
+                Self::release_held_space(&user_brief.user, deal_hash, &file)?;
+            }
+        }
+        Self::remove_file_last_owner(deal_hash, &owner, true)?;
+
+        T::Scheduler::task_finished(&deal_info.scheduler, 1);
+        Self::pending_deal_finished(&owner);
+        <DealMap<T>>::remove(deal_hash);
+        <DealPolicy<T>>::remove(deal_hash);
+        Self::release_deal_deposit(deal_hash);
+
+        Ok(())
+    }
+
+    // Counts a declaration of `acc` in the current block, at most `MaxDeclarationsPerBlock` are allowed.
+    pub(super) fn record_block_declaration(acc: &AccountOf<T>) -> DispatchResult {
+        let now = <frame_system::Pallet<T>>::block_number();
+        <BlockDeclarations<T>>::try_mutate(acc, |record| -> DispatchResult {
+            let count = match record {
+                Some((block, count)) if *block == now => *count,
+                _ => 0,
+            };
+            ensure!(count < T::MaxDeclarationsPerBlock::get(), Error::<T>::TooManyDeclarations);
+            *record = Some((now, count.saturating_add(1)));
+            Ok(())
+        })
+    }
+
+    pub(super) fn reserve_deal_deposit(deal_hash: &Hash, owner: &AccountOf<T>) -> DispatchResult {
+        let deposit = T::DeclarationDeposit::get();
+        T::Currency::reserve(owner, deposit)?;
+        <DealDeposit<T>>::insert(deal_hash, (owner.clone(), deposit));
+
+        Ok(())
+    }
+
+    // Deals declared before deposits were taken have nothing to refund.
+    pub(super) fn release_deal_deposit(deal_hash: &Hash) {
+        if let Some((owner, deposit)) = <DealDeposit<T>>::take(deal_hash) {
+            T::Currency::unreserve(&owner, deposit);
+        }
+    }
+
+    pub(super) fn clear_file_segments(deal_hash: &Hash) {
+        let _ = <FileSegments<T>>::clear_prefix(deal_hash, T::SegmentCount::get(), None);
+    }
+    /// helper: split download fee.
+    ///
+    /// Pay the owner and each miner storing the file their share of the fee,
+    /// the rest, including the rounding dust, goes to the treasury.
+    ///
+    /// Parameters:
+    /// - `buyer`: the account paying the fee.
+    /// - `owner`: the holder selling the file.
+    /// - `file`: the sold file.
+    /// - `price`: the download fee.
+    pub(super) fn split_download_fee(
+        buyer: &AccountOf<T>,
+        owner: &AccountOf<T>,
+        file: &FileInfo<T>,
+        price: BalanceOf<T>,
+    ) -> DispatchResult {
+        let mut miner_list: Vec<AccountOf<T>> = Default::default();
+        for segment_info in file.segment_list.iter() {
+            for fragment_info in segment_info.fragment_list.iter() {
+                if !miner_list.contains(&fragment_info.miner) {
+                    miner_list.push(fragment_info.miner.clone());
+                }
+            }
+        }
+
+        let owner_fee = T::OwnerShare::get().mul_floor(price);
+        let mut remaining = price.checked_sub(&owner_fee).ok_or(Error::<T>::Overflow)?;
+        T::Currency::transfer(buyer, owner, owner_fee, KeepAlive)?;
+
+        if miner_list.len() > 0 {
+            let miner_fee = T::MinerShare::get().mul_floor(price)
+                .checked_div(&(miner_list.len() as u32).saturated_into()).ok_or(Error::<T>::Overflow)?;
+            for miner in miner_list.iter() {
+                T::Currency::transfer(buyer, miner, miner_fee, KeepAlive)?;
+                remaining = remaining.checked_sub(&miner_fee).ok_or(Error::<T>::Overflow)?;
+            }
+        }
+
+        let treasury = T::TreasuryPalletId::get().into_account_truncating();
+        T::Currency::transfer(buyer, &treasury, remaining, KeepAlive)?;
+
+        Ok(())
+    }
+    /// helper: finalize file delete.
+    ///
+    /// Delete the file of the owner once the grace period is over.
+    /// When the owner is the last holder, the miners are told through `InvalidFile`
+    /// that they can drop the fragments.
+    ///
+    /// Parameters:
+    /// - `owner`: the holder of the file.
+    /// - `file_hash`: file hash.
+    pub(super) fn finalize_file_delete(owner: &AccountOf<T>, file_hash: &Hash) -> Result<Weight, DispatchError> {
+        let mut weight: Weight = Weight::from_ref_time(0);
+        let file = <File<T>>::try_get(file_hash).map_err(|_| Error::<T>::NonExistent)?;
+        weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+        let mut invalid_list: Vec<(AccountOf<T>, Hash)> = Default::default();
+        if file.owner.len() == 1 {
+            for segment_info in file.segment_list.iter() {
+                for fragment_info in segment_info.fragment_list.iter() {
+                    invalid_list.push((fragment_info.miner.clone(), fragment_info.hash));
+                }
+            }
+        }
+
+        let temp_weight = Self::delete_user_file(file_hash, owner, &file)?;
+        weight = weight.saturating_add(temp_weight);
+        Self::bucket_remove_file(file_hash, owner, &file)?;
+        Self::remove_user_hold_file_list(file_hash, owner)?;
+        Self::clear_file_acl(file_hash, owner);
+        <FileVersions<T>>::remove(owner, file_hash);
+        <FileMirror<T>>::remove(file_hash, owner);
+        weight = weight
+            .saturating_add(T::DbWeight::get().reads_writes(3, 5))
+            .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root());
+
+        for (miner, fragment_hash) in invalid_list {
+            let temp_weight = Self::notify_invalid_file(&miner, fragment_hash);
+            weight = weight.saturating_add(temp_weight);
+        }
+
+        Self::deposit_event(Event::<T>::DeleteFinalized { owner: owner.clone(), file_hash: *file_hash, file_size: file.file_size });
+
+        Ok(weight)
+    }
+    /// helper: notify invalid file.
+    ///
+    /// Tell the miner that it can drop the fragment,
+    /// it has `InvalidFileGracePeriod` blocks to acknowledge it with `clear_invalid_file`.
+    ///
+    /// Parameters:
+    /// - `miner`: the miner storing the fragment.
+    /// - `fragment_hash`: fragment hash.
+    pub(super) fn notify_invalid_file(miner: &AccountOf<T>, fragment_hash: Hash) -> Weight {
+        let mut weight: Weight = T::DbWeight::get().reads_writes(1, 1);
+        if <InvalidFile<T>>::try_mutate(miner, |hash_list| hash_list.try_push(fragment_hash)).is_err() {
+            log::error!("invalid file list of miner is full: {:?}", miner);
+            return weight;
+        }
+
+        let now = <frame_system::Pallet<T>>::block_number();
+        let deadline = now.saturating_add(T::InvalidFileGracePeriod::get());
+        // Without a deadline the entry waits for the reminders of the authorities.
+        Self::schedule_invalid_file_deadline(miner, &fragment_hash, deadline);
+        <InvalidFileReminders<T>>::insert(miner, &fragment_hash, (0, now.saturating_add(T::InvalidFileReminderPeriod::get())));
+        weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 3));
+
+        Self::deposit_event(Event::<T>::InvalidFileNotified { miner: miner.clone(), file_hash: fragment_hash, deadline });
+
+        weight
+    }
+    /// helper: schedule invalid file deadline.
+    ///
+    /// Queue the invalid file for expiry at `deadline`,
+    /// returns false if the queue of that block is full.
+    ///
+    /// Parameters:
+    /// - `miner`: the miner storing the fragment.
+    /// - `fragment_hash`: fragment hash.
+    /// - `deadline`: the block the miner has to clear the fragment by.
+    pub(super) fn schedule_invalid_file_deadline(miner: &AccountOf<T>, fragment_hash: &Hash, deadline: BlockNumberOf<T>) -> bool {
+        if <InvalidFileQueue<T>>::try_mutate(&deadline, |queue| queue.try_push((miner.clone(), *fragment_hash))).is_err() {
+            log::error!("invalid file queue is full at block: {:?}", deadline);
+            return false;
+        }
+        <InvalidFileDeadline<T>>::insert(miner, fragment_hash, deadline);
+
+        true
+    }
+    /// helper: remind invalid file due.
+    ///
+    /// Whether the miner can be reminded of the invalid file at `now`.
+    /// Files notified before reminders existed are due right away.
+    ///
+    /// Parameters:
+    /// - `miner`: the miner storing the fragment.
+    /// - `fragment_hash`: fragment hash.
+    /// - `now`: the current block.
+    pub(super) fn invalid_file_reminder_due(miner: &AccountOf<T>, fragment_hash: &Hash, now: BlockNumberOf<T>) -> bool {
+        match <InvalidFileReminders<T>>::get(miner, fragment_hash) {
+            Some((_, next)) => next <= now,
+            None => true,
+        }
+    }
+    /// helper: expire invalid file.
+    ///
+    /// Drop an invalid file the miner did not clear in time and punish the miner.
+    /// Files that were cleared or got a new deadline are skipped.
+    ///
+    /// Parameters:
+    /// - `miner`: the miner storing the fragment.
+    /// - `fragment_hash`: fragment hash.
+    /// - `now`: the block the deadline is at.
+    pub(super) fn expire_invalid_file(miner: &AccountOf<T>, fragment_hash: &Hash, now: BlockNumberOf<T>) -> Result<Weight, DispatchError> {
+        let mut weight: Weight = T::DbWeight::get().reads(1);
+        if <InvalidFileDeadline<T>>::get(miner, fragment_hash) != Some(now) {
+            return Ok(weight);
+        }
+
+        <InvalidFileDeadline<T>>::remove(miner, fragment_hash);
+        <InvalidFileReminders<T>>::remove(miner, fragment_hash);
+        <InvalidFile<T>>::mutate(miner, |hash_list| hash_list.retain(|hash| hash != fragment_hash));
+        weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 3));
+
+        T::MinerControl::invalid_file_punish(miner)?;
+        weight = weight.saturating_add(T::DbWeight::get().reads_writes(3, 2));
+
+        Self::deposit_event(Event::<T>::InvalidFileExpired { miner: miner.clone(), file_hash: *fragment_hash });
+
+        Ok(weight)
+    }
+    /// helper: record declared bytes.
+    ///
+    /// Add the declared bytes to the current era of the user,
+    /// and reject the declaration if it exceeds the user or governance limit.
+    ///
+    /// Parameters:
+    /// - `acc`: the user whose space is declared.
+    /// - `bytes`: the space of the declaration.
+    pub(super) fn record_declared_bytes(acc: &AccountOf<T>, bytes: u128) -> DispatchResult {
+        let limit = match (<DeclarationLimit<T>>::get(acc), <GlobalDeclarationLimit<T>>::get()) {
+            (Some(user), Some(global)) => Some(user.min(global)),
+            (user, global) => user.or(global),
+        };
+        let limit = match limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let now = <frame_system::Pallet<T>>::block_number();
+        let era: u32 = (now / T::DeclarationEra::get()).saturated_into();
+        <DeclaredBytes<T>>::try_mutate(acc, |(cur_era, declared)| -> DispatchResult {
+            if *cur_era != era {
+                *cur_era = era;
+                *declared = 0;
+            }
+            let total = declared.checked_add(bytes).ok_or(Error::<T>::Overflow)?;
+            ensure!(total <= limit, Error::<T>::DeclarationLimitExceeded);
+            *declared = total;
+            Ok(())
+        })
+    }
+    /// helper: schedule lease sweep.
+    ///
+    /// Replace the periodic lease sweep task, the first run is at the next multiple of `period`.
+    ///
+    /// Parameters:
+    /// - `now`: current block.
+    /// - `period`: blocks between two sweeps.
+    pub(super) fn schedule_lease_sweep(now: BlockNumberOf<T>, period: BlockNumberOf<T>) -> DispatchResult {
+        let task_id: Vec<u8> = LEASE_SWEEP_TASK_ID.to_vec();
+        // Nothing is scheduled on the first configuration, the task may also have been
+        // removed by the scheduler, so a missing task is not an error.
+        let _ = T::FScheduler::cancel_named(task_id.clone());
+
+        let first_block = now
+            .checked_div(&period).ok_or(Error::<T>::Overflow)?
+            .checked_add(&1u32.saturated_into()).ok_or(Error::<T>::Overflow)?
+            .checked_mul(&period).ok_or(Error::<T>::Overflow)?;
+
+        T::FScheduler::schedule_named(
+                task_id,
+                DispatchTime::At(first_block),
+                Some((period, u32::MAX)),
+                schedule::HARD_DEADLINE,
+                frame_system::RawOrigin::Root.into(),
+                Call::lease_sweep{}.into(),
+        ).map_err(|_| Error::<T>::Unexpected)?;
+
+        <LeaseSweepPeriod<T>>::put(period);
+
+        Ok(())
+    }
+    /// helper: assign scheduler.
+    ///
+    /// Pick the marker tee worker with the fewest unfinished deals to receive the file data of a deal.
+    ///
+    /// Result:
+    /// - AccountOf<T>: the controller account of the selected tee worker.
+    pub(super) fn assign_scheduler() -> Result<AccountOf<T>, DispatchError> {
+        let scheduler = T::Scheduler::get_least_loaded_role_controller(ROLE_MARKER)
+            .map_err(|_| Error::<T>::ScheduleNonExistent)?;
+        T::Scheduler::task_assigned(&scheduler, 1);
+
+        Ok(scheduler)
+    }
+    /// helper: replace deal scheduler.
+    ///
+    /// Hand the deals of an exited tee worker to the remaining workers.
+    /// If no worker remains, the deals are left to time out.
+    ///
+    /// Parameters:
+    /// - `scheduler`: the controller account of the exited tee worker.
+    pub(super) fn replace_deal_scheduler(scheduler: &AccountOf<T>) -> DispatchResult {
+        let deal_list: Vec<Hash> = <DealMap<T>>::iter()
+            .filter(|(_, deal_info)| &deal_info.scheduler == scheduler)
+            .map(|(deal_hash, _)| deal_hash)
+            .collect();
+
+        for deal_hash in deal_list {
+            let new_scheduler = match Self::assign_scheduler() {
+                Ok(new_scheduler) => new_scheduler,
+                Err(_) => {
+                    log::info!("no tee worker left to take over deal: {:?}", deal_hash);
+                    return Ok(());
+                },
+            };
+
+            <DealMap<T>>::try_mutate(&deal_hash, |opt| -> DispatchResult {
+                let deal_info = opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+                deal_info.scheduler = new_scheduler.clone();
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::<T>::DealSchedulerReplaced {
+                deal_hash,
+                old_scheduler: scheduler.clone(),
+                new_scheduler,
+            });
+        }
+
+        Ok(())
+    }
+    /// helper: get deal scheduler.
+    ///
+    /// Query the tee worker assigned to a deal, used by the runtime api.
+    ///
+    /// Parameters:
+    /// - `deal_hash`: deal hash.
+    ///
+    /// Result:
+    /// - Option: controller account and peer id of the tee worker, and the deadline of the current stage.
+    pub fn get_deal_scheduler(deal_hash: &Hash) -> Option<(AccountOf<T>, PeerId, BlockNumberOf<T>)> {
+        let deal_info = <DealMap<T>>::get(deal_hash)?;
+        let peer_id = T::Scheduler::get_peer_id(&deal_info.scheduler).ok()?;
+
+        Some((deal_info.scheduler, peer_id, deal_info.deadline))
+    }
+    /// helper: get file brief.
+    ///
+    /// Summarize a file for the runtime api.
+    ///
+    /// Parameters:
+    /// - `file_hash`: file hash.
+    ///
+    /// Result:
+    /// - Option: holders, size, segment count, completion block and state of the file.
+    pub fn get_file_brief(file_hash: &Hash) -> Option<FileBrief<AccountOf<T>, BlockNumberOf<T>>> {
+        let file = <File<T>>::get(file_hash)?;
+        let state: &str = match file.stat {
+            FileState::Active => "active",
+            FileState::Calculate => "calculate",
+            FileState::Missing => "missing",
+            FileState::Recovery => "recovery",
+        };
+
+        Some(FileBrief {
+            owners: file.owner.iter().map(|user_brief| user_brief.user.clone()).collect(),
+            file_size: file.file_size,
+            segment_count: file.segment_list.len() as u32,
+            completion: file.completion,
+            state: state.as_bytes().to_vec(),
+        })
+    }
+
+    /// helper: start deal auction.
+    ///
+    /// Open the sealed-bid auction of a large deal.
+    /// Miners commit during `AuctionPeriod` blocks and reveal during the following `AuctionPeriod` blocks.
+    ///
+    /// Parameters:
+    /// - `deal_hash`: deal hash.
+    pub(super) fn start_deal_auction(deal_hash: &Hash) -> DispatchResult {
+        let now = <frame_system::Pallet<T>>::block_number();
+        let period = T::AuctionPeriod::get();
+        let commit_end = now.checked_add(&period).ok_or(Error::<T>::Overflow)?;
+        let reveal_end = commit_end.checked_add(&period).ok_or(Error::<T>::Overflow)?;
+
+        <AuctionQueue<T>>::try_mutate(&reveal_end, |list| -> DispatchResult {
+            list.try_push(*deal_hash).map_err(|_| Error::<T>::BoundedVecError)?;
+            Ok(())
+        })?;
+        <DealAuctions<T>>::insert(deal_hash, DealAuction::<T> {
+            commit_end,
+            reveal_end,
+            commitments: Default::default(),
+            bids: Default::default(),
+        });
+
+        Self::deposit_event(Event::<T>::DealAuctionStarted { deal_hash: *deal_hash, commit_end, reveal_end });
+
         Ok(())
     }
+    /// helper: process block queues.
+    ///
+    /// Handle the auctions, deletions, invalid files and share links queued for `block`
+    /// until `weight` exceeds `budget`. The entries not handled stay queued for `block`,
+    /// like the file lists moved by the version 4 migration.
+    ///
+    /// Parameters:
+    /// - `block`: the block the entries were queued for.
+    /// - `budget`: the weight the block hook may spend on the queues.
+    /// - `weight`: the weight spent by the block hook so far.
+    ///
+    /// Result:
+    /// - bool: whether every queue of `block` was emptied.
+    pub(super) fn process_block_queues(block: BlockNumberOf<T>, budget: Weight, weight: &mut Weight) -> bool {
+        let auction_list = <AuctionQueue<T>>::take(&block);
+        *weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+        for (index, deal_hash) in auction_list.iter().enumerate() {
+            if weight.any_gt(budget) {
+                let rest: BoundedVec<Hash, ConstU32<1000>> = auction_list[index..].to_vec().try_into().unwrap_or_default();
+                <AuctionQueue<T>>::insert(&block, rest);
+                return false;
+            }
+            match Self::settle_deal_auction(deal_hash) {
+                Ok(temp_weight) => *weight = weight.saturating_add(temp_weight),
+                Err(e) => log::error!("settle auction {:?} failed. error is: {:?}", deal_hash, e),
+            }
+        }
+
+        let delete_list = <DeleteQueue<T>>::take(&block);
+        *weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+        for (index, (owner, file_hash)) in delete_list.iter().enumerate() {
+            if weight.any_gt(budget) {
+                let rest: BoundedVec<(AccountOf<T>, Hash), ConstU32<1000>> = delete_list[index..].to_vec().try_into().unwrap_or_default();
+                <DeleteQueue<T>>::insert(&block, rest);
+                return false;
+            }
+            // Cancelled or already deleted requests are skipped.
+            *weight = weight.saturating_add(T::DbWeight::get().reads(1));
+            if <PendingDelete<T>>::get(owner, file_hash) != Some(block) {
+                continue;
+            }
+            <PendingDelete<T>>::remove(owner, file_hash);
+            *weight = weight.saturating_add(T::DbWeight::get().writes(1));
+            match Self::finalize_file_delete(owner, file_hash) {
+                Ok(temp_weight) => *weight = weight.saturating_add(temp_weight),
+                Err(e) => log::error!("finalize delete {:?} failed. error is: {:?}", file_hash, e),
+            }
+        }
+
+        let expired_list = <InvalidFileQueue<T>>::take(&block);
+        *weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+        for (index, (miner, file_hash)) in expired_list.iter().enumerate() {
+            if weight.any_gt(budget) {
+                let rest: BoundedVec<(AccountOf<T>, Hash), ConstU32<5000>> = expired_list[index..].to_vec().try_into().unwrap_or_default();
+                <InvalidFileQueue<T>>::insert(&block, rest);
+                return false;
+            }
+            match Self::expire_invalid_file(miner, file_hash, block) {
+                Ok(temp_weight) => *weight = weight.saturating_add(temp_weight),
+                Err(e) => log::error!("expire invalid file {:?} failed. error is: {:?}", file_hash, e),
+            }
+        }
+
+        let share_list = <ShareExpiryQueue<T>>::take(&block);
+        *weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+        for (index, (file_hash, share_id)) in share_list.iter().enumerate() {
+            if weight.any_gt(budget) {
+                let rest: BoundedVec<(Hash, [u8; 32]), ConstU32<1000>> = share_list[index..].to_vec().try_into().unwrap_or_default();
+                <ShareExpiryQueue<T>>::insert(&block, rest);
+                return false;
+            }
+            // Revoked and used up shares are already gone.
+            <FileShares<T>>::mutate_exists(file_hash, |shares_opt| {
+                if let Some(shares) = shares_opt {
+                    shares.retain(|share| &share.share_id != share_id);
+                    if shares.is_empty() {
+                        *shares_opt = None;
+                    }
+                }
+            });
+            *weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+        }
+
+        true
+    }
+    /// helper: settle deal auction.
+    ///
+    /// Price a deal by its auction.
+    /// The `MinAuctionBidders` lowest revealed bids win and the highest of them is the price of the deal.
+    /// With fewer revealed bids the deal keeps the posted price of its space.
+    ///
+    /// Parameters:
+    /// - `deal_hash`: deal hash.
+    pub(super) fn settle_deal_auction(deal_hash: &Hash) -> Result<Weight, DispatchError> {
+        let mut weight: Weight = Weight::from_ref_time(0);
+
+        let auction = <DealAuctions<T>>::take(deal_hash).ok_or(Error::<T>::NonExistent)?;
+        weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+
+        let mut bids = auction.bids.into_inner();
+        bids.sort_by(|a, b| a.1.cmp(&b.1));
+        let needed = T::MinAuctionBidders::get() as usize;
+
+        let (price, auctioned, winners) = if needed > 0 && bids.len() >= needed {
+            let price = bids[needed - 1].1;
+            let winners: Vec<AccountOf<T>> = bids.into_iter().take(needed).map(|(miner, _)| miner).collect();
+            let winners: BoundedVec<AccountOf<T>, T::StringLimit> = winners.try_into().map_err(|_| Error::<T>::BoundedVecError)?;
+            (price, true, winners)
+        } else {
+            (Self::posted_deal_price(deal_hash)?, false, Default::default())
+        };
+        weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+        <DealPrice<T>>::insert(deal_hash, price);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+
+        Self::deposit_event(Event::<T>::DealAuctionSettled { deal_hash: *deal_hash, price, auctioned, winners });
+
+        Ok(weight)
+    }
+    /// helper: posted deal price.
+    ///
+    /// The monthly price of the space of a deal at the unit price of the packages.
+    ///
+    /// Parameters:
+    /// - `deal_hash`: deal hash.
+    fn posted_deal_price(deal_hash: &Hash) -> Result<BalanceOf<T>, DispatchError> {
+        let segment_count = match <DealMap<T>>::get(deal_hash) {
+            Some(deal_info) => deal_info.segment_list.len() as u128,
+            None => <File<T>>::try_get(deal_hash).map_err(|_| Error::<T>::NonExistent)?.segment_list.len() as u128,
+        };
+        let space = segment_count * (SEGMENT_SIZE * 15 / 10);
+        let gib_count = (space + G_BYTE - 1) / G_BYTE;
+        let price = T::StorageHandle::get_unit_price()?
+            .checked_mul(gib_count)
+            .ok_or(Error::<T>::Overflow)?;
+
+        Ok(price.saturated_into())
+    }
+    /// helper: popularity era.
+    ///
+    /// The era of the current block for popularity scores.
+    pub(super) fn popularity_era() -> u32 {
+        let now = <frame_system::Pallet<T>>::block_number();
+        (now / T::PopularityEra::get()).saturated_into()
+    }
+    /// helper: decay popularity.
+    ///
+    /// Roll a popularity record forward to `era`, decaying the score once for every era passed.
+    ///
+    /// Parameters:
+    /// - `info`: the popularity record of a file.
+    /// - `era`: the current popularity era.
+    fn decay_popularity(info: &mut PopularityInfo, era: u32) {
+        if era > info.era {
+            let passed = (era - info.era) as usize;
+            info.score = T::PopularityDecay::get().saturating_pow(passed).mul_floor(info.score);
+            info.era = era;
+            info.era_gain = 0;
+        }
+    }
+    /// helper: add popularity.
+    ///
+    /// Count one retrieval into the popularity of a file, up to `MaxPopularityGain` per era.
+    ///
+    /// Parameters:
+    /// - `file_hash`: file hash.
+    /// - `era`: the current popularity era.
+    ///
+    /// Result:
+    /// - u32: the score of the file after the retrieval.
+    pub(super) fn add_popularity(file_hash: &Hash, era: u32) -> u32 {
+        <FilePopularity<T>>::mutate(file_hash, |opt| {
+            let info = opt.get_or_insert_with(|| PopularityInfo { score: 0, era, era_gain: 0 });
+            Self::decay_popularity(info, era);
+            if info.era_gain < T::MaxPopularityGain::get() {
+                info.era_gain += 1;
+                info.score = info.score.saturating_add(1);
+            }
+            info.score
+        })
+    }
+    /// helper: get file popularity.
+    ///
+    /// The current popularity score of a file, used by the runtime api.
+    ///
+    /// Parameters:
+    /// - `file_hash`: file hash.
+    pub fn get_file_popularity(file_hash: &Hash) -> u32 {
+        match <FilePopularity<T>>::get(file_hash) {
+            Some(mut info) => {
+                Self::decay_popularity(&mut info, Self::popularity_era());
+                info.score
+            },
+            None => 0,
+        }
+    }
+    /// helper: get hot files.
+    ///
+    /// The most popular files, used by the runtime api to give caching hints.
+    ///
+    /// Parameters:
+    /// - `limit`: maximum number of files returned.
+    ///
+    /// Result:
+    /// - Vec: file hashes and current scores, from the most popular down.
+    pub fn get_hot_files(limit: u32) -> Vec<(Hash, u32)> {
+        let era = Self::popularity_era();
+        let mut list: Vec<(Hash, u32)> = <FilePopularity<T>>::iter()
+            .map(|(file_hash, mut info)| {
+                Self::decay_popularity(&mut info, era);
+                (file_hash, info.score)
+            })
+            .filter(|(_, score)| *score > 0)
+            .collect();
+        list.sort_by(|a, b| b.1.cmp(&a.1));
+        list.truncate(limit as usize);
+
+        list
+    }
+    /// helper: get user buckets.
+    ///
+    /// The names of the buckets of a user, in the order they were created.
+    ///
+    /// Parameters:
+    /// - `acc`: the bucket owner.
+    pub fn get_user_buckets(acc: &AccountOf<T>) -> Vec<Vec<u8>> {
+        <UserBucketList<T>>::get(acc).into_iter().map(|name| name.to_vec()).collect()
+    }
+    /// helper: get bucket files.
+    ///
+    /// The files listed in a bucket of a user.
+    ///
+    /// Parameters:
+    /// - `acc`: the bucket owner.
+    /// - `name`: bucket name.
+    ///
+    /// Result:
+    /// - Option: the file hashes, `None` if the user has no bucket of that name.
+    pub fn get_bucket_files(acc: &AccountOf<T>, name: Vec<u8>) -> Option<Vec<Hash>> {
+        let name: BoundedVec<u8, T::NameStrLimit> = name.try_into().ok()?;
+        let bucket = <Bucket<T>>::get(acc, &name)?;
+
+        Some(bucket.object_list.to_vec())
+    }
+
+    /// Get the storage key of a file and the raw value stored under it.
+    ///
+    /// Parameters:
+    /// - `file_hash`: The file hash.
+    ///
+    /// Result:
+    /// - (Vec<u8>, Option<Vec<u8>>): the key and the SCALE encoded `FileInfo`, `None` if the file does not exist.
+    pub fn get_file_storage(file_hash: &Hash) -> (Vec<u8>, Option<Vec<u8>>) {
+        let key = <File<T>>::hashed_key_for(file_hash);
+        let value = frame_support::storage::unhashed::get_raw(&key);
+
+        (key, value)
+    }
+
+    /// Get the child storage key of the file trie of a user and the entry of a file in it.
+    ///
+    /// Parameters:
+    /// - `acc`: The user.
+    /// - `file_hash`: The file hash.
+    ///
+    /// Result:
+    /// - (Vec<u8>, Vec<u8>, Option<u128>): the child storage key, the key of the file in the trie
+    ///   and the space charged for the file, `None` if the user does not hold the file.
+    pub fn get_user_file_storage(acc: &AccountOf<T>, file_hash: &Hash) -> (Vec<u8>, Vec<u8>, Option<u128>) {
+        let trie = Self::user_file_trie(acc);
+        let key = file_hash.encode();
+        let file_size = child::get::<u128>(&trie, &key);
+
+        (trie.storage_key().to_vec(), key, file_size)
+    }
 
+    /// The state a file enters with an event of this pallet, read in the state of the block of the event.
+    ///
+    /// Parameters:
+    /// - `event`: The event.
+    /// - `file_hash`: The file hash.
+    ///
+    /// Result:
+    /// - "pending", "active", "pending_delete" or "deleted",
+    ///   `None` if the event leaves the state of the file as it was.
+    pub fn file_state_transition(event: &Event<T>, file_hash: &Hash) -> Option<&'static [u8]> {
+        let deleted = || if <File<T>>::contains_key(file_hash) { None } else { Some(&b"deleted"[..]) };
+        match event {
+            Event::UploadDeclaration { deal_hash, deduplicated: false, .. } if deal_hash == file_hash =>
+                Some(b"pending"),
+            Event::CalculateEnd { file_hash: hash, .. } if hash == file_hash => Some(b"active"),
+            Event::DeleteRequested { file_hash: hash, .. } if hash == file_hash => Some(b"pending_delete"),
+            Event::DeleteCancelled { file_hash: hash, .. } if hash == file_hash => Some(b"active"),
+            Event::DeleteFinalized { file_hash: hash, .. } if hash == file_hash => deleted(),
+            Event::DeleteFile { file_hash_list, .. } if file_hash_list.contains(file_hash) => deleted(),
+            Event::BatchDeleteFile { deleted: list, .. } if list.contains(file_hash) => deleted(),
+            Event::StorageOrderClosed { file_hash: hash, .. } if hash == file_hash => deleted(),
+            _ => None,
+        }
+    }
+
+    /// helper: call weights.
+    ///
+    /// The weight of every call, in call index order.
+    /// The storage accesses are the ones listed by the benchmarks in `weights.rs`.
+    ///
+    /// Result:
+    /// - Vec<CallWeight>: the weights, calls taking a list weighed for a single item.
+    pub fn get_call_weights() -> Vec<CallWeight> {
+        let calls: Vec<(u8, &str, Weight, Option<(u64, u64)>)> = vec![
+            (
+                0,
+                "upload_declaration",
+                <T as pallet::Config>::WeightInfo::upload_declaration()
+                    .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()),
+                Some((3, 3)),
+            ),
+            (1, "deal_reassign_miner", <T as pallet::Config>::WeightInfo::deal_reassign_miner(), Some((14, 12))),
+            (
+                2,
+                "ownership_transfer",
+                <T as pallet::Config>::WeightInfo::ownership_transfer()
+                    .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root().saturating_mul(2)),
+                Some((7, 7)),
+            ),
+            (
+                3,
+                "transfer_report",
+                <T as pallet::Config>::WeightInfo::upload(2)
+                    .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()),
+                Some((17, 14)),
+            ),
+            (4, "calculate_end", <T as pallet::Config>::WeightInfo::calculate_end(), Some((15, 15))),
+            (5, "replace_file_report", <T as pallet::Config>::WeightInfo::replace_file_report(1), Some((5, 7))),
+            (
+                6,
+                "delete_file",
+                <T as pallet::Config>::WeightInfo::delete_file()
+                    .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()),
+                Some((9, 9)),
+            ),
+            (7, "force_reassign_deal", <T as pallet::Config>::WeightInfo::force_reassign_deal(), Some((18, 15))),
+            (8, "upload_filler", <T as pallet::Config>::WeightInfo::upload_filler(1), Some((7, 6))),
+            (9, "delete_filler", <T as pallet::Config>::WeightInfo::upload_filler(1), Some((7, 6))),
+            (10, "grant_access", <T as pallet::Config>::WeightInfo::grant_access(), Some((1, 1))),
+            (11, "create_bucket", <T as pallet::Config>::WeightInfo::create_bucket(), Some((2, 2))),
+            (
+                12,
+                "delete_bucket",
+                <T as pallet::Config>::WeightInfo::delete_bucket()
+                    .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()),
+                Some((2, 2)),
+            ),
+            (13, "generate_restoral_order", <T as pallet::Config>::WeightInfo::generate_restoral_order(), Some((2, 3))),
+            (14, "claim_restoral_order", <T as pallet::Config>::WeightInfo::claim_restoral_order(), Some((2, 1))),
+            (15, "claim_restoral_noexist_order", <T as pallet::Config>::WeightInfo::claim_restoral_noexist_order(), Some((4, 3))),
+            (16, "restoral_order_complete", <T as pallet::Config>::WeightInfo::restoral_order_complete(), Some((9, 7))),
+            (17, "miner_exit_prep", <T as pallet::Config>::WeightInfo::miner_exit_prep(), Some((4, 4))),
+            (18, "miner_exit", <T as pallet::Config>::WeightInfo::miner_exit(), Some((4, 6))),
+            (19, "miner_withdraw", <T as pallet::Config>::WeightInfo::miner_withdraw(), Some((5, 4))),
+            (20, "revoke_access", <T as pallet::Config>::WeightInfo::revoke_access(), Some((1, 1))),
+            (21, "set_public_dataset", <T as pallet::Config>::WeightInfo::set_public_dataset(), Some((1, 1))),
+            (22, "upload_segment", <T as pallet::Config>::WeightInfo::upload_segment(), Some((3, 1))),
+            (23, "update_file", <T as pallet::Config>::WeightInfo::update_file(), Some((4, 2))),
+            (24, "rollback_version", <T as pallet::Config>::WeightInfo::rollback_version(), Some((3, 2))),
+            (25, "audit_user_space", <T as pallet::Config>::WeightInfo::audit_user_space(), Some((3, 1))),
+            (26, "lease_sweep", <T as pallet::Config>::WeightInfo::lease_sweep(0), Some((3, 3))),
+            (27, "set_lease_sweep_period", <T as pallet::Config>::WeightInfo::set_lease_sweep_period(), Some((3, 4))),
+            (28, "set_declaration_limit", <T as pallet::Config>::WeightInfo::set_declaration_limit(), Some((0, 1))),
+            (29, "set_global_declaration_limit", <T as pallet::Config>::WeightInfo::set_declaration_limit(), Some((0, 1))),
+            (30, "request_mirror", <T as pallet::Config>::WeightInfo::request_mirror(), Some((1, 1))),
+            (31, "confirm_mirror", <T as pallet::Config>::WeightInfo::confirm_mirror(), Some((2, 1))),
+            (32, "request_delete", <T as pallet::Config>::WeightInfo::request_delete(), Some((4, 2))),
+            (33, "cancel_delete", <T as pallet::Config>::WeightInfo::cancel_delete(), Some((2, 1))),
+            (34, "set_download_price", <T as pallet::Config>::WeightInfo::set_download_price(), Some((1, 1))),
+            (35, "buy_file", <T as pallet::Config>::WeightInfo::buy_file(), Some((5, 5))),
+            (36, "submit_retrieval_receipt", <T as pallet::Config>::WeightInfo::submit_retrieval_receipt(), Some((6, 3))),
+            (37, "commit_deal_bid", <T as pallet::Config>::WeightInfo::commit_deal_bid(), Some((2, 1))),
+            (38, "reveal_deal_bid", <T as pallet::Config>::WeightInfo::reveal_deal_bid(), Some((1, 1))),
+            (
+                39,
+                "batch_delete_file",
+                <T as pallet::Config>::WeightInfo::delete_file()
+                    .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()),
+                Some((9, 9)),
+            ),
+            (40, "clear_invalid_file", <T as pallet::Config>::WeightInfo::clear_invalid_file(), Some((1, 1))),
+            (41, "transfer_file", <T as pallet::Config>::WeightInfo::transfer_file(), Some((2, 1))),
+            (42, "create_share", <T as pallet::Config>::WeightInfo::create_share(), Some((5, 3))),
+            (43, "revoke_share", <T as pallet::Config>::WeightInfo::revoke_share(), Some((1, 1))),
+            (44, "redeem_share", <T as pallet::Config>::WeightInfo::redeem_share(), Some((3, 1))),
+            (45, "set_file_size_limit", <T as pallet::Config>::WeightInfo::set_file_size_limit(), Some((0, 1))),
+            (46, "set_file_size_exemption", <T as pallet::Config>::WeightInfo::set_file_size_limit(), Some((0, 1))),
+            (47, "remind_invalid_file", <T as pallet::Config>::WeightInfo::remind_invalid_file(), Some((4, 3))),
+            (48, "set_bucket_policy", <T as pallet::Config>::WeightInfo::set_bucket_policy(), Some((1, 1))),
+            (49, "withdraw_declaration", <T as pallet::Config>::WeightInfo::withdraw_declaration(), Some((8, 9))),
+            (50, "create_storage_order", <T as pallet::Config>::WeightInfo::create_storage_order(), Some((4, 2))),
+            (51, "cancel_storage_order", <T as pallet::Config>::WeightInfo::cancel_storage_order(), Some((3, 2))),
+            (
+                52,
+                "segment_report_timeout",
+                <T as pallet::Config>::WeightInfo::segment_report_timeout()
+                    .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()),
+                Some((9, 12)),
+            ),
+            (
+                53,
+                "accept_transfer",
+                <T as pallet::Config>::WeightInfo::ownership_transfer()
+                    .saturating_add(<T as pallet::Config>::WeightInfo::create_bucket())
+                    .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root().saturating_mul(2)),
+                Some((10, 10)),
+            ),
+        ];
+
+        calls
+            .into_iter()
+            .map(|(call_index, name, weight, db_access)| CallWeight {
+                call_index,
+                name: name.as_bytes().to_vec(),
+                ref_time: weight.ref_time(),
+                proof_size: weight.proof_size(),
+                db_access,
+            })
+            .collect()
+    }
+
+    // The nonce of the owner only grows, so an id is never handed out twice,
+    // not even after the share it was given to was revoked or expired.
+    pub(super) fn generate_share_id(owner: &AccountOf<T>, file_hash: &Hash, nonce: u64) -> [u8; 32] {
+        (owner, file_hash, nonce).using_encoded(sp_io::hashing::blake2_256)
+    }
+
+    // Miners whose price floor is above `rate`, the unit price the user pays, are skipped.
     pub(super) fn random_assign_miner(
-        needed_list: &BoundedVec<SegmentList<T>, T::SegmentCount>
+        needed_list: &BoundedVec<SegmentList<T>, T::SegmentCount>,
+        rate: u128,
+        policy: &StoragePolicy,
     ) -> Result<BoundedVec<MinerTaskList<T>, T::StringLimit>, DispatchError> {
         let mut miner_task_list: BoundedVec<MinerTaskList<T>, T::StringLimit> = Default::default();
         let mut miner_idle_space_list: Vec<u128> = Default::default();
@@ -210,6 +1201,14 @@ impl<T: Config> Pallet<T> {
 
         let mut all_miner = T::MinerControl::get_all_miner()?;
         let mut total = all_miner.len() as u32;
+        // Miners are chosen with a chance proportional to their reputation, endorsements included.
+        let mut weight_list: Vec<u64> = all_miner
+            .iter()
+            .map(|miner| {
+                let per_mill = T::MinerControl::get_placement_reputation(miner).deconstruct() as u64 / 1_000_000;
+                per_mill.max(MIN_PLACEMENT_WEIGHT)
+            })
+            .collect();
 
         // ensure!(total > miner_count, Error::<T>::NodesInsufficient);
         let max_count = miner_count * 5;
@@ -223,7 +1222,16 @@ impl<T: Config> Pallet<T> {
                 break;
             }
 
-            let index = Self::generate_random_number(seed)? as u32 % total;
+            let total_weight: u64 = weight_list.iter().sum();
+            let mut point = Self::generate_random_number(seed)? as u64 % total_weight;
+            let mut index = 0;
+            for (i, weight) in weight_list.iter().enumerate() {
+                if point < *weight {
+                    index = i;
+                    break;
+                }
+                point -= weight;
+            }
             // seed + 1
             seed = seed.checked_add(1).ok_or(Error::<T>::Overflow)?;
 
@@ -236,13 +1244,24 @@ impl<T: Config> Pallet<T> {
             cur_count += 1;
 
             // Judge whether the idle space of the miners is sufficient.
-            let miner = all_miner[index as usize].clone();
-            all_miner.remove(index as usize);
+            let miner = all_miner[index].clone();
+            all_miner.remove(index);
+            weight_list.remove(index);
             total = total - 1;
             let result = T::MinerControl::is_positive(&miner)?;
             if !result {
                 continue;
             }
+            // Users could not reach a miner registered with a malformed peer id.
+            if !peer::is_valid_peer_id(&T::MinerControl::get_peer_id(&miner)?) {
+                continue;
+            }
+            if T::MinerControl::get_price_floor(&miner).map_or(false, |floor| floor > rate) {
+                continue;
+            }
+            if !Self::satisfies_policy(&miner, policy) {
+                continue;
+            }
            
             let cur_space: u128 = T::MinerControl::get_miner_idle_space(&miner)?;
             // If sufficient, the miner is selected.
@@ -290,14 +1309,122 @@ impl<T: Config> Pallet<T> {
         }
         // lock miner space
         for miner_task in miner_task_list.iter() {
-            T::MinerControl::lock_space(&miner_task.miner, miner_task.fragment_list.len() as u128 * FRAGMENT_SIZE)?;
+            T::MinerControl::reserve_space(&miner_task.miner, miner_task.fragment_list.len() as u128 * FRAGMENT_SIZE)?;
+        }
+
+        Ok(miner_task_list)
+    }
+
+    // Whether the miner may store the fragments of a file placed by `policy`.
+    pub(super) fn satisfies_policy(miner: &AccountOf<T>, policy: &StoragePolicy) -> bool {
+        if let Some(region) = policy.region {
+            if T::MinerControl::get_miner_region(miner) != Some(region) {
+                return false;
+            }
+        }
+        if let Some(min_reputation) = policy.min_reputation {
+            if T::MinerControl::get_placement_reputation(miner) < min_reputation {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub(super) fn check_storage_policy(policy: &StoragePolicy) -> DispatchResult {
+        if let Some(region) = policy.region {
+            ensure!(region.iter().all(|byte| byte.is_ascii_uppercase()), Error::<T>::InvalidRegion);
+        }
+
+        Ok(())
+    }
+
+    // The size must fill the last of the segments, and be within the limit unless the owner is exempt.
+    pub(super) fn check_file_size(owner: &AccountOf<T>, file_size: u128, segment_count: u32) -> DispatchResult {
+        let segment_count = segment_count as u128;
+        ensure!(
+            file_size > segment_count.saturating_sub(1).saturating_mul(SEGMENT_SIZE)
+                && file_size <= segment_count.saturating_mul(SEGMENT_SIZE),
+            Error::<T>::FileSizeMismatch
+        );
+
+        if let Some((min, max)) = <FileSizeLimit<T>>::get() {
+            if !<FileSizeExempt<T>>::get(owner) {
+                ensure!(file_size >= min, Error::<T>::FileTooSmall);
+                ensure!(file_size <= max, Error::<T>::FileTooLarge);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn cal_file_size(len: u128) -> u128 {
+        len * (SEGMENT_SIZE * 15 / 10)
+    }
+
+    // The space charged to a user declaring a file that is already stored.
+    pub(super) fn deduplicated_space(needed_space: u128) -> u128 {
+        needed_space.saturating_sub(T::DedupDiscount::get().mul_floor(needed_space))
+    }
+
+    // The space charged to the user for the file, as recorded when the user got the file.
+    pub(super) fn held_file_size(acc: &AccountOf<T>, file_hash: &Hash, file: &FileInfo<T>) -> u128 {
+        child::get::<u128>(&Self::user_file_trie(acc), &file_hash.encode())
+            .unwrap_or_else(|| Self::cal_file_size(file.segment_list.len() as u128))
+    }
+
+    pub fn get_file_ref_count(file_hash: &Hash) -> u32 {
+        <FileRefCount<T>>::get(file_hash)
+    }
+
+    // Rewrites the summary of the file from its `FileInfo`, call after every change of the file.
+    pub(super) fn refresh_file_summary(file_hash: &Hash) {
+        match <File<T>>::get(file_hash) {
+            Some(file) => <FileSummary<T>>::insert(file_hash, Self::summarize_file(&file)),
+            None => <FileSummary<T>>::remove(file_hash),
+        }
+    }
+
+    pub(super) fn summarize_file(file: &FileInfo<T>) -> FileSummaryInfo {
+        let replica_count = file.segment_list
+            .iter()
+            .map(|segment| segment.fragment_list.iter().filter(|fragment| fragment.avail).count() as u32)
+            .sum();
+
+        FileSummaryInfo {
+            stat: file.stat.clone(),
+            file_size: file.file_size,
+            replica_count,
+            owner_count: file.owner.len() as u32,
         }
+    }
 
-        Ok(miner_task_list)
+    pub fn get_file_summary(file_hash: &Hash) -> Option<FileSummaryInfo> {
+        <FileSummary<T>>::get(file_hash)
     }
 
-    pub(super) fn cal_file_size(len: u128) -> u128 {
-        len * (SEGMENT_SIZE * 15 / 10)
+    // Removes one file of `owner` on behalf of `operator`, with everything recorded for it.
+    pub(super) fn delete_owned_file(
+        operator: &AccountOf<T>,
+        owner: &AccountOf<T>,
+        is_authorized: bool,
+        file_hash: &Hash,
+    ) -> DispatchResult {
+        ensure!(
+            is_authorized || Self::check_file_acl(file_hash, operator, owner, ACL_DELETE),
+            Error::<T>::NoPermission
+        );
+        let file = <File<T>>::try_get(file_hash).map_err(|_| Error::<T>::NonExistent)?;
+        let _ = Self::delete_user_file(file_hash, owner, &file)?;
+        Self::bucket_remove_file(file_hash, owner, &file)?;
+        Self::remove_user_hold_file_list(file_hash, owner)?;
+        Self::clear_file_acl(file_hash, owner);
+        <FileVersions<T>>::remove(owner, file_hash);
+        <FileMirror<T>>::remove(file_hash, owner);
+        <PendingDelete<T>>::remove(owner, file_hash);
+        <DownloadPrice<T>>::remove(file_hash, owner);
+
+        Ok(())
     }
 
     pub(super) fn delete_user_file(file_hash: &Hash, acc: &AccountOf<T>, file: &FileInfo<T>) -> Result<Weight, DispatchError> {
@@ -341,10 +1468,65 @@ impl<T: Config> Pallet<T> {
         file_hash: &Hash, 
         acc: &AccountOf<T>,
     ) -> DispatchResult {
-        <UserHoldFileList<T>>::try_mutate(acc, |file_list| -> DispatchResult {
-            file_list.retain(|temp_file| &temp_file.file_hash != file_hash);
-            Ok(())
-        })
+        let trie = Self::user_file_trie(acc);
+        let key = file_hash.encode();
+        let file_size = match child::get::<u128>(&trie, &key) {
+            Some(file_size) => file_size,
+            None => return Ok(()),
+        };
+        child::kill(&trie, &key);
+
+        Self::update_user_file_index(acc, &trie, 1, file_size);
+
+        Ok(())
+    }
+
+    // Removes several files from the file trie of a user, its root is recomputed once.
+    pub(super) fn remove_user_hold_files(file_hashes: &[Hash], acc: &AccountOf<T>) {
+        let trie = Self::user_file_trie(acc);
+        let (mut removed, mut removed_size) = (0u32, 0u128);
+        for file_hash in file_hashes.iter() {
+            let key = file_hash.encode();
+            if let Some(file_size) = child::get::<u128>(&trie, &key) {
+                child::kill(&trie, &key);
+                removed = removed.saturating_add(1);
+                removed_size = removed_size.saturating_add(file_size);
+            }
+        }
+
+        if removed > 0 {
+            Self::update_user_file_index(acc, &trie, removed, removed_size);
+        }
+    }
+
+    // The child trie holding the files of a user, file hash -> space charged for the file.
+    pub(super) fn user_file_trie(acc: &AccountOf<T>) -> ChildInfo {
+        ChildInfo::new_default(&[USER_FILE_TRIE_PREFIX, &acc.encode()].concat())
+    }
+
+    // The files held by a user, in key order.
+    pub(super) fn user_files(trie: &ChildInfo) -> ChildTriePrefixIterator<(Hash, u128)> {
+        ChildTriePrefixIterator::with_prefix_over_key::<Identity>(trie, &[])
+    }
+
+    pub(super) fn user_file_root(trie: &ChildInfo) -> T::Hash {
+        let root = child::root(trie, <T as frame_system::Config>::Version::get().state_version());
+        T::Hash::decode(&mut &root[..]).unwrap_or_default()
+    }
+
+    // Takes `removed` files of `removed_size` bytes off the index of a user and
+    // recomputes its root, the index goes away with the last file.
+    fn update_user_file_index(acc: &AccountOf<T>, trie: &ChildInfo, removed: u32, removed_size: u128) {
+        <UserFileIndex<T>>::mutate_exists(acc, |index_opt| {
+            if let Some(index) = index_opt {
+                index.count = index.count.saturating_sub(removed);
+                index.total_size = index.total_size.saturating_sub(removed_size);
+                index.root = Self::user_file_root(trie);
+                if index.count == 0 {
+                    *index_opt = None;
+                }
+            }
+        });
     }
 
 
@@ -354,9 +1536,8 @@ impl<T: Config> Pallet<T> {
             let file = file_opt.as_mut().ok_or(Error::<T>::Overflow)?;
             for (index, user_brief) in file.owner.iter().enumerate() {
                 if acc == &user_brief.user {
-                    let file_size = Self::cal_file_size(file.segment_list.len() as u128);
                     if user_clear {
-                        T::StorageHandle::update_user_space(acc, 2, file_size)?;
+                        Self::release_held_space(acc, file_hash, file)?;
                     }
                     file.owner.remove(index);
                     break;
@@ -364,6 +1545,8 @@ impl<T: Config> Pallet<T> {
             }
             Ok(())
         })?;
+        <FileRefCount<T>>::mutate(file_hash, |count| *count = count.saturating_sub(1));
+        Self::refresh_file_summary(file_hash);
 
         Ok(())
     }
@@ -404,13 +1587,25 @@ impl<T: Config> Pallet<T> {
         }
 
         if user_clear {
-            T::StorageHandle::update_user_space(acc, 2, total_fragment_dec as u128 * FRAGMENT_SIZE)?;
-            weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+            // The last owner may have declared the file after it was stored, at a discount.
+            Self::release_held_space(acc, file_hash, &file)?;
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 1));
         }
         T::StorageHandle::sub_total_service_space(total_fragment_dec as u128 * FRAGMENT_SIZE)?;
         weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
 
         <File<T>>::remove(file_hash);
+        Self::unindex_active_file(file_hash);
+        <FileRefCount<T>>::remove(file_hash);
+        <FileSummary<T>>::remove(file_hash);
+        <FileScheduler<T>>::remove(file_hash);
+        <PublicDataset<T>>::remove(file_hash);
+        let _ = <FileMirror<T>>::clear_prefix(file_hash, T::OwnerLimit::get(), None);
+        let _ = <DownloadPrice<T>>::clear_prefix(file_hash, T::OwnerLimit::get(), None);
+        let _ = <RetrievalReceipts<T>>::clear_prefix(file_hash, u32::MAX, None);
+        <FilePopularity<T>>::remove(file_hash);
+        <DealPrice<T>>::remove(file_hash);
+        Self::clear_file_segments(file_hash);
         weight = weight.saturating_add(T::DbWeight::get().writes(1));
 
         Ok(weight)
@@ -455,14 +1650,21 @@ impl<T: Config> Pallet<T> {
         file_hash: Hash,
         file_size: u128,
     ) -> DispatchResult {
-        let file_info =
-            UserFileSliceInfo { file_hash: file_hash, file_size };
-        <UserHoldFileList<T>>::try_mutate(user, |v| -> DispatchResult {
-            v.try_push(file_info).map_err(|_| Error::<T>::StorageLimitReached)?;
+        let trie = Self::user_file_trie(user);
+        let key = file_hash.encode();
+        let previous = child::get::<u128>(&trie, &key);
+        child::put(&trie, &key, &file_size);
+
+        <UserFileIndex<T>>::try_mutate(user, |index_opt| -> DispatchResult {
+            let index = index_opt.get_or_insert_with(Default::default);
+            match previous {
+                Some(previous) => index.total_size = index.total_size.saturating_sub(previous),
+                None => index.count = index.count.checked_add(1).ok_or(Error::<T>::Overflow)?,
+            }
+            index.total_size = index.total_size.checked_add(file_size).ok_or(Error::<T>::Overflow)?;
+            index.root = Self::user_file_root(&trie);
             Ok(())
-        })?;
-
-        Ok(())
+        })
     }
     /// helper: get current scheduler.
     ///
@@ -471,17 +1673,19 @@ impl<T: Config> Pallet<T> {
     /// Parameters:
     ///
     /// Result:
-    /// - AccountOf: consensus
-    // pub(super) fn get_current_scheduler() -> Result<AccountOf<T>, DispatchError> {
-    //     let digest = <frame_system::Pallet<T>>::digest();
-    //     let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
-    //     let acc = T::FindAuthor::find_author(pre_runtime_digests).map(|a| a);
-    //     let acc = match acc {
-    //         Some(e) => T::Scheduler::get_controller_acc(e),
-    //         None => T::Scheduler::get_first_controller()?,
-    //     };
-    //     Ok(acc)
-    // }
+    /// - AccountOf: the controller of the tee worker run by the block author,
+    ///   the first available tee worker if the author runs none.
+    pub fn get_current_scheduler() -> Result<AccountOf<T>, DispatchError> {
+        let digest = <frame_system::Pallet<T>>::digest();
+        let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+        let acc = T::FindAuthor::find_author(pre_runtime_digests)
+            .and_then(|stash| T::Scheduler::get_controller_acc(&stash));
+        let acc = match acc {
+            Some(e) => e,
+            None => T::Scheduler::get_first_controller()?,
+        };
+        Ok(acc)
+    }
     /// helper: check_is_file_owner.
     ///
     /// Check whether the user is the owner of the file.
@@ -517,13 +1721,134 @@ impl<T: Config> Pallet<T> {
         false
     }
 
+    /// helper: File acl check method.
+    /// Check whether the owner granted the operator the permission on the file.
+    ///
+    /// Parameters:
+    /// - `file_hash`: file hash.
+    /// - `operator`: AccountId.
+    /// - `owner`: AccountId of the file holder.
+    /// - `permission`: required permission bit.
+    ///
+    /// Result:
+    /// - bool: True means there is permission, false means there is no permission.
+    pub fn check_file_acl(file_hash: &Hash, operator: &AccountOf<T>, owner: &AccountOf<T>, permission: u8) -> bool {
+        if let Some(acl) = <FileAcl<T>>::get(file_hash, operator) {
+            return &acl.grantor == owner && acl.permission & permission == permission;
+        }
+        false
+    }
+    /// helper: clear file acl.
+    ///
+    /// Remove the accesses granted by the owner once the owner no longer holds the file.
+    ///
+    /// Parameters:
+    /// - `file_hash`: file hash.
+    /// - `owner`: AccountId of the file holder.
+    pub(super) fn clear_file_acl(file_hash: &Hash, owner: &AccountOf<T>) {
+        let grantee_list: Vec<AccountOf<T>> = <FileAcl<T>>::iter_prefix(file_hash)
+            .filter(|(_, acl)| &acl.grantor == owner)
+            .map(|(acc, _)| acc)
+            .collect();
+
+        for acc in grantee_list {
+            <FileAcl<T>>::remove(file_hash, &acc);
+        }
+    }
+
     pub(super) fn clear_filler(miner: &AccountOf<T>, maybe_cursor: Option<&[u8]>) {
+        if maybe_cursor.is_none() {
+            Self::unindex_miner_fillers(miner);
+        }
         let result = <FillerMap<T>>::clear_prefix(miner, 100000, maybe_cursor);
         if let Some(cursor) = result.maybe_cursor {
             Self::clear_filler(miner, Some(&cursor));
         }
     }
 
+    // Appends a filler to `FillerIndex`.
+    pub(super) fn index_filler(miner: &AccountOf<T>, filler_hash: &Hash) {
+        let position = <FillerCount<T>>::get();
+        <FillerIndex<T>>::insert(position, (miner.clone(), *filler_hash));
+        <FillerPosition<T>>::insert(miner, filler_hash, position);
+        <FillerCount<T>>::put(position.saturating_add(1));
+    }
+
+    // Removes a filler from `FillerIndex`, the last filler moves into its position.
+    pub(super) fn unindex_filler(miner: &AccountOf<T>, filler_hash: &Hash) {
+        let position = match <FillerPosition<T>>::take(miner, filler_hash) {
+            Some(position) => position,
+            None => return,
+        };
+        let last = <FillerCount<T>>::get().saturating_sub(1);
+        if let Some((moved_miner, moved_hash)) = <FillerIndex<T>>::take(last) {
+            if position != last {
+                <FillerPosition<T>>::insert(&moved_miner, &moved_hash, position);
+                <FillerIndex<T>>::insert(position, (moved_miner, moved_hash));
+            }
+        }
+        <FillerCount<T>>::put(last);
+    }
+
+    // Removes every filler of a miner from `FillerIndex`.
+    pub(super) fn unindex_miner_fillers(miner: &AccountOf<T>) -> Weight {
+        let filler_list: Vec<Hash> = <FillerPosition<T>>::iter_key_prefix(miner).collect();
+        for filler_hash in filler_list.iter() {
+            Self::unindex_filler(miner, filler_hash);
+        }
+
+        T::DbWeight::get().reads_writes(filler_list.len() as u64 * 3, filler_list.len() as u64 * 4)
+    }
+
+    // Appends a file that became active to `ActiveFileIndex`.
+    pub(super) fn index_active_file(file_hash: &Hash) {
+        if <ActiveFilePosition<T>>::contains_key(file_hash) {
+            return;
+        }
+        let position = <ActiveFileCount<T>>::get();
+        <ActiveFileIndex<T>>::insert(position, *file_hash);
+        <ActiveFilePosition<T>>::insert(file_hash, position);
+        <ActiveFileCount<T>>::put(position.saturating_add(1));
+    }
+
+    // Removes a file from `ActiveFileIndex`, the last file moves into its position.
+    pub(super) fn unindex_active_file(file_hash: &Hash) {
+        let position = match <ActiveFilePosition<T>>::take(file_hash) {
+            Some(position) => position,
+            None => return,
+        };
+        let last = <ActiveFileCount<T>>::get().saturating_sub(1);
+        if let Some(moved_hash) = <ActiveFileIndex<T>>::take(last) {
+            if position != last {
+                <ActiveFilePosition<T>>::insert(&moved_hash, position);
+                <ActiveFileIndex<T>>::insert(position, moved_hash);
+            }
+        }
+        <ActiveFileCount<T>>::put(last);
+    }
+
+    // Picks up to `count` distinct positions below `total`, or all of them when there are no more.
+    // The randomness is read once, every draw hashes it with the number of the draw,
+    // and at most `RANDOM_POSITION_DRAWS` draws are made per position.
+    pub(super) fn random_positions(total: u32, count: u32, seed: u32) -> Vec<u32> {
+        if count >= total {
+            return (0..total).collect();
+        }
+        let (random_seed, _) =
+            T::MyRandomness::random(&(T::FilbakPalletId::get(), b"random-positions", seed).encode());
+        let max_draws = count.saturating_mul(RANDOM_POSITION_DRAWS);
+        let mut positions: BTreeSet<u32> = Default::default();
+        let mut draw: u32 = 0;
+        while (positions.len() as u32) < count && draw < max_draws {
+            let hash = (&random_seed, draw).using_encoded(sp_io::hashing::blake2_128);
+            let random_number = u32::from_le_bytes([hash[0], hash[1], hash[2], hash[3]]);
+            positions.insert(random_number % total);
+            draw = draw.saturating_add(1);
+        }
+
+        positions.into_iter().collect()
+    }
+
     pub(super) fn force_miner_exit(miner: &AccountOf<T>) -> DispatchResult {
         Self::clear_filler(&miner, None);
 
@@ -533,7 +1858,9 @@ impl<T: Config> Pallet<T> {
         T::MinerControl::force_miner_exit(miner)?;
 
         Self::create_restoral_target(miner, service_space)?;
-        
+        Self::queue_recovery(miner);
+        T::EventRouter::route(StorageEvent::ReplicaLost { miner: miner.clone(), service_space });
+
         Ok(())
     }
 
@@ -569,6 +1896,507 @@ impl<T: Config> Pallet<T> {
         })
     }
 
+    // Moves the ownership of `sender` to the account of `target_brief`, charging the file
+    // to the package of the target and releasing it from the package of the sender.
+    pub(super) fn transfer_file_owner(
+        sender: &AccountOf<T>,
+        target_brief: UserBrief<T>,
+        file_hash: &Hash,
+    ) -> DispatchResult {
+        let file = <File<T>>::try_get(file_hash).map_err(|_| Error::<T>::FileNonExistent)?;
+        //If the file does not exist, false will also be returned
+        ensure!(Self::check_is_file_owner(sender, file_hash), Error::<T>::NotOwner);
+        ensure!(!Self::check_is_file_owner(&target_brief.user, file_hash), Error::<T>::IsOwned);
+
+        ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
+        ensure!(!<StorageOrders<T>>::contains_key(sender, file_hash), Error::<T>::OrderedFile);
+        ensure!(<Bucket<T>>::contains_key(&target_brief.user, &target_brief.bucket_name), Error::<T>::NonExistent);
+        //Modify the space usage of target acc,
+        //and determine whether the space is enough to support transfer
+        let file_size = Self::cal_file_size(file.segment_list.len() as u128);
+        T::StorageHandle::update_user_space(&target_brief.user, 1, file_size)?;
+        //Increase the ownership of the file for target acc
+        <File<T>>::try_mutate(file_hash, |file_opt| -> DispatchResult {
+            let file = file_opt.as_mut().ok_or(Error::<T>::FileNonExistent)?;
+            file.owner.try_push(target_brief.clone()).map_err(|_| Error::<T>::BoundedVecError)?;
+            Ok(())
+        })?;
+        Self::refresh_file_summary(file_hash);
+        <FileRefCount<T>>::mutate(file_hash, |count| *count = count.saturating_add(1));
+        //Add files to the bucket of target acc
+        <Bucket<T>>::try_mutate(
+            &target_brief.user,
+            &target_brief.bucket_name,
+            |bucket_info_opt| -> DispatchResult {
+                let bucket_info = bucket_info_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+                bucket_info.object_list.try_push(*file_hash).map_err(|_| Error::<T>::LengthExceedsLimit)?;
+                Ok(())
+        })?;
+        //Increase the corresponding space usage for target acc
+        Self::add_user_hold_fileslice(
+            &target_brief.user,
+            *file_hash,
+            file_size,
+        )?;
+        //Clean up the file holding information of the original user
+        let file = <File<T>>::try_get(file_hash).map_err(|_| Error::<T>::NonExistent)?;
+
+        let _ = Self::delete_user_file(file_hash, sender, &file)?;
+
+        Self::bucket_remove_file(file_hash, sender, &file)?;
+
+        Self::remove_user_hold_file_list(file_hash, sender)?;
+
+        Ok(())
+    }
+
+    // Queues the exited miner for the next recovery scan.
+    pub(super) fn queue_recovery(miner: &AccountOf<T>) {
+        <PendingRecoveryMiners<T>>::mutate(|miner_list| {
+            if !miner_list.contains(miner) && miner_list.try_push(miner.clone()).is_err() {
+                log::error!("recovery of miner {:?} not queued, the queue is full", miner);
+            }
+        });
+    }
+
+    // Deletes the files of the users collected by the lease sweep, at most `CLEAR_FILE_BATCH`
+    // per block, and then their space and buckets, returns the number of files deleted.
+    pub(super) fn clear_user_files() -> u32 {
+        let mut count: u32 = 0;
+        let acc_list = ClearUserList::<T>::get();
+        for acc in acc_list.iter() {
+            let trie = Self::user_file_trie(acc);
+            let (mut removed, mut removed_size) = (0u32, 0u128);
+            while let Some((file_hash, file_size)) = Self::user_files(&trie).next() {
+                if count == CLEAR_FILE_BATCH {
+                    // The rest is deleted in the next block.
+                    Self::update_user_file_index(acc, &trie, removed, removed_size);
+                    return count;
+                }
+                count = count + 1;
+                child::kill(&trie, &file_hash.encode());
+                removed = removed.saturating_add(1);
+                removed_size = removed_size.saturating_add(file_size);
+                if let Ok(file) = <File<T>>::try_get(&file_hash) {
+                    if file.owner.len() > 1 {
+                        if let Err(e) = Self::remove_file_owner(&file_hash, &acc, false) {
+                            log::info!("delete file {:?} failed. error is: {:?}", e, file_hash);
+                        }
+                    } else {
+                        if let Err(e) = Self::remove_file_last_owner(&file_hash, &acc, false) {
+                            log::info!("delete file {:?} failed. error is: {:?}", e, file_hash);
+                        }
+                    }
+                } else {
+                    log::error!("space lease, delete file bug!");
+                    log::error!("acc: {:?}, file_hash: {:?}", &acc, &file_hash);
+                }
+            }
+
+            if let Err(e) = T::StorageHandle::delete_user_space_storage(&acc) {
+                log::info!("delete user sapce error: {:?}, \n failed user: {:?}", e, acc);
+            }
+
+            ClearUserList::<T>::mutate(|target_list| {
+                target_list.retain(|temp_acc| temp_acc != acc);
+            });
+
+            <UserFileIndex<T>>::remove(&acc);
+            if <StorageOrders<T>>::iter_prefix(&acc).next().is_some() {
+                // Files paid by storage orders outlive the package, their buckets are kept.
+                let bucket_list: Vec<_> = <Bucket<T>>::iter_prefix(&acc).collect();
+                for (bucket_name, mut bucket) in bucket_list {
+                    bucket.object_list.retain(|file_hash| <StorageOrders<T>>::contains_key(&acc, file_hash));
+                    <Bucket<T>>::insert(&acc, &bucket_name, bucket);
+                }
+            } else {
+                // todo! clear all
+                let _ = <Bucket<T>>::clear_prefix(&acc, 100000, None);
+                <UserBucketList<T>>::remove(&acc);
+            }
+        }
+
+        count
+    }
+
+    // Goes through the next `RECOVERY_SCAN_BATCH` files of the running scan, or starts
+    // a scan for the queued miners, and dispatches the fragments of the scanned miners.
+    pub(super) fn dispatch_recovery(now: BlockNumberOf<T>) -> Weight {
+        let mut weight: Weight = Weight::from_ref_time(0);
+
+        weight = weight.saturating_add(T::DbWeight::get().reads(1));
+        let mut scan = match <RecoveryScan<T>>::get() {
+            Some(scan) => scan,
+            None => {
+                let miners = <PendingRecoveryMiners<T>>::take();
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+                if miners.is_empty() {
+                    return weight;
+                }
+                RecoveryScanInfo::<T> { miners, last_key: Default::default() }
+            },
+        };
+
+        let file_list: Vec<Hash> = if scan.last_key.is_empty() {
+            <File<T>>::iter_keys().take(RECOVERY_SCAN_BATCH).collect()
+        } else {
+            <File<T>>::iter_keys_from(scan.last_key.to_vec()).take(RECOVERY_SCAN_BATCH).collect()
+        };
+        weight = weight.saturating_add(T::DbWeight::get().reads(file_list.len() as u64));
+
+        for file_hash in file_list.iter() {
+            match Self::recover_file(file_hash, &scan.miners, now) {
+                Ok(temp_weight) => weight = weight.saturating_add(temp_weight),
+                Err(e) => log::error!("recover file {:?} failed. error is: {:?}", file_hash, e),
+            }
+        }
+
+        match file_list.last() {
+            Some(file_hash) if file_list.len() == RECOVERY_SCAN_BATCH => {
+                match <File<T>>::hashed_key_for(file_hash).try_into() {
+                    Ok(last_key) => {
+                        scan.last_key = last_key;
+                        <RecoveryScan<T>>::put(scan);
+                    },
+                    Err(_) => {
+                        log::error!("recovery scan stopped at {:?}, the key is too long", file_hash);
+                        <RecoveryScan<T>>::kill();
+                    },
+                }
+            },
+            _ => <RecoveryScan<T>>::kill(),
+        }
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+
+        weight
+    }
+
+    /// helper: storage order daily payment.
+    ///
+    /// The daily price of the space at the unit price of the packages,
+    /// which is the price of a gib for 30 days.
+    ///
+    /// Parameters:
+    /// - `space`: the space paid by the order.
+    pub(super) fn storage_order_daily_payment(space: u128) -> Result<BalanceOf<T>, DispatchError> {
+        let gib_count = (space + G_BYTE - 1) / G_BYTE;
+        let price = T::StorageHandle::get_unit_price()?
+            .checked_mul(gib_count)
+            .ok_or(Error::<T>::Overflow)?
+            / 30;
+
+        Ok(price.saturated_into())
+    }
+
+    // The order of the file pays its miners from now on, files paid by packages are left alone.
+    pub(super) fn start_storage_order(owner: &AccountOf<T>, file_hash: &Hash) {
+        let now = <frame_system::Pallet<T>>::block_number();
+        <StorageOrders<T>>::mutate(owner, file_hash, |order_opt| {
+            if let Some(order) = order_opt {
+                if order.start.is_none() {
+                    order.start = Some(now);
+                    order.settled_at = now;
+                }
+            }
+        });
+    }
+
+    // Pays the miners of the file from the escrow for the days passed since the last settlement,
+    // up to the days of the order. Miners that failed a challenge since then are left out and
+    // their share stays in the escrow. Returns whether all days of the order are paid.
+    pub(super) fn settle_storage_order(
+        owner: &AccountOf<T>,
+        file_hash: &Hash,
+        now: BlockNumberOf<T>,
+    ) -> Result<(Weight, bool), DispatchError> {
+        let mut weight: Weight = Weight::from_ref_time(0);
+        let mut order = <StorageOrders<T>>::try_get(owner, file_hash).map_err(|_| Error::<T>::NonExistent)?;
+        weight = weight.saturating_add(T::DbWeight::get().reads(1));
+        let start = match order.start {
+            Some(start) => start,
+            None => return Ok((weight, false)),
+        };
+
+        let elapsed: u32 = (now.saturating_sub(start) / T::OneDay::get()).saturated_into();
+        let days = elapsed.min(order.days).saturating_sub(order.paid_days);
+        if days == 0 {
+            return Ok((weight, order.paid_days >= order.days));
+        }
+
+        let mut miner_list = <Self as RandomFileList<AccountOf<T>>>::get_file_miners(file_hash);
+        miner_list.retain(|miner| match <ChallengeFailures<T>>::get(miner) {
+            Some(failed_at) => failed_at <= order.settled_at,
+            None => true,
+        });
+        weight = weight.saturating_add(T::DbWeight::get().reads(1 + miner_list.len() as u64));
+
+        let mut paid: BalanceOf<T> = 0u32.saturated_into();
+        if miner_list.len() > 0 {
+            let amount = order.daily_payment
+                .checked_mul(&days.saturated_into())
+                .ok_or(Error::<T>::Overflow)?
+                .min(order.escrow);
+            let share = amount
+                .checked_div(&(miner_list.len() as u32).saturated_into())
+                .ok_or(Error::<T>::Overflow)?;
+            for miner in miner_list.iter() {
+                T::Currency::repatriate_reserved(owner, miner, share, BalanceStatus::Free)?;
+                paid = paid.checked_add(&share).ok_or(Error::<T>::Overflow)?;
+            }
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 2).saturating_mul(miner_list.len() as u64));
+        }
+
+        order.escrow = order.escrow.checked_sub(&paid).ok_or(Error::<T>::Overflow)?;
+        order.paid_days = order.paid_days.saturating_add(days);
+        order.settled_at = now;
+        let finished = order.paid_days >= order.days;
+        <StorageOrders<T>>::insert(owner, file_hash, order);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+
+        Self::deposit_event(Event::<T>::StorageOrderSettled {
+            owner: owner.clone(),
+            file_hash: *file_hash,
+            days,
+            paid,
+            miners: miner_list.len() as u32,
+        });
+
+        Ok((weight, finished))
+    }
+
+    // Pays what the miners earned so far and refunds the rest of the escrow to the owner.
+    pub(super) fn close_storage_order(owner: &AccountOf<T>, file_hash: &Hash) -> Result<Weight, DispatchError> {
+        let now = <frame_system::Pallet<T>>::block_number();
+        let (mut weight, _) = Self::settle_storage_order(owner, file_hash, now)?;
+        let order = <StorageOrders<T>>::take(owner, file_hash).ok_or(Error::<T>::NonExistent)?;
+        T::Currency::unreserve(owner, order.escrow);
+        weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 2));
+
+        Self::deposit_event(Event::<T>::StorageOrderClosed { owner: owner.clone(), file_hash: *file_hash, refund: order.escrow });
+
+        Ok(weight)
+    }
+
+    // Gives back what the holder paid for the file, the space of its package
+    // or the unspent escrow of its storage order.
+    pub(super) fn release_held_space(acc: &AccountOf<T>, file_hash: &Hash, file: &FileInfo<T>) -> DispatchResult {
+        if <StorageOrders<T>>::contains_key(acc, file_hash) {
+            Self::close_storage_order(acc, file_hash)?;
+        } else {
+            let file_size = Self::held_file_size(acc, file_hash, file);
+            T::StorageHandle::update_user_space(acc, 2, file_size)?;
+        }
+
+        Ok(())
+    }
+
+    // Settles the next `STORAGE_ORDER_BATCH` orders after the cursor. The file of an order
+    // paid for all its days is deleted from the owner, files still being tagged are retried.
+    pub(super) fn settle_storage_orders(now: BlockNumberOf<T>) -> Weight {
+        let mut weight: Weight = Weight::from_ref_time(0);
+
+        let cursor = <StorageOrderCursor<T>>::get();
+        let order_list: Vec<(AccountOf<T>, Hash)> = if cursor.is_empty() {
+            <StorageOrders<T>>::iter_keys().take(STORAGE_ORDER_BATCH).collect()
+        } else {
+            <StorageOrders<T>>::iter_keys_from(cursor.to_vec()).take(STORAGE_ORDER_BATCH).collect()
+        };
+        weight = weight.saturating_add(T::DbWeight::get().reads(1 + order_list.len() as u64));
+
+        for (owner, file_hash) in order_list.iter() {
+            match with_storage_layer(|| Self::settle_storage_order(owner, file_hash, now)) {
+                Ok((temp_weight, finished)) => {
+                    weight = weight.saturating_add(temp_weight);
+                    if !finished {
+                        continue;
+                    }
+                },
+                Err(e) => {
+                    log::error!("settle storage order {:?} failed. error is: {:?}", file_hash, e);
+                    continue;
+                },
+            }
+
+            let result = with_storage_layer(|| -> Result<Weight, DispatchError> {
+                if <File<T>>::contains_key(file_hash) {
+                    Self::delete_owned_file(owner, owner, true, file_hash)?;
+                    Ok(T::DbWeight::get()
+                        .reads_writes(9, 9)
+                        .saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()))
+                } else {
+                    Self::close_storage_order(owner, file_hash)
+                }
+            });
+            match result {
+                Ok(temp_weight) => weight = weight.saturating_add(temp_weight),
+                Err(e) => log::info!("expire storage order {:?} failed. error is: {:?}", file_hash, e),
+            }
+        }
+
+        let next_cursor: BoundedVec<u8, ConstU32<256>> = match order_list.last() {
+            Some((owner, file_hash)) if order_list.len() == STORAGE_ORDER_BATCH => {
+                <StorageOrders<T>>::hashed_key_for(owner, file_hash).try_into().unwrap_or_default()
+            },
+            _ => Default::default(),
+        };
+        <StorageOrderCursor<T>>::put(next_cursor);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+
+        weight
+    }
+
+    // Storage orders do not pay the miner for the day it failed a challenge in.
+    pub fn record_challenge_failure(miner: &AccountOf<T>) {
+        let now = <frame_system::Pallet<T>>::block_number();
+        <ChallengeFailures<T>>::insert(miner, now);
+    }
+
+    // Creates a restoral order for every fragment of the file stored by one of the lost miners.
+    // The positive miners holding the other replicas of the segment take turns in restoring them.
+    // When none is left, the order goes to the scheduler of the file and can be claimed at once by
+    // any miner, the scheduler seeds the data to it.
+    pub(super) fn recover_file(
+        file_hash: &Hash,
+        lost_miners: &[AccountOf<T>],
+        now: BlockNumberOf<T>,
+    ) -> Result<Weight, DispatchError> {
+        let mut weight: Weight = Weight::from_ref_time(0);
+        let life: BlockNumberOf<T> = T::RestoralOrderLife::get().saturated_into();
+        let scheduler = <FileScheduler<T>>::get(file_hash);
+        weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+        let mut dispatched: Vec<Hash> = Vec::new();
+        <File<T>>::try_mutate(file_hash, |file_opt| -> DispatchResult {
+            let file = file_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+            for segment in file.segment_list.iter_mut() {
+                let survivors: Vec<AccountOf<T>> = segment.fragment_list
+                    .iter()
+                    .filter(|fragment| fragment.avail && !lost_miners.contains(&fragment.miner))
+                    .filter(|fragment| T::MinerControl::is_positive(&fragment.miner).unwrap_or(false))
+                    .map(|fragment| fragment.miner.clone())
+                    .collect();
+
+                for (index, fragment) in segment.fragment_list.iter_mut().enumerate() {
+                    if !lost_miners.contains(&fragment.miner) || <RestoralOrder<T>>::contains_key(&fragment.hash) {
+                        continue;
+                    }
+                    weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+                    let (assignee, deadline) = match (survivors.get(index % survivors.len().max(1)), &scheduler) {
+                        (Some(miner), _) => (miner.clone(), now.checked_add(&life).ok_or(Error::<T>::Overflow)?),
+                        (None, Some(scheduler)) => (scheduler.clone(), now),
+                        (None, None) => continue,
+                    };
+
+                    let restoral_order = RestoralOrderInfo::<T> {
+                        count: u32::MIN,
+                        miner: assignee.clone(),
+                        origin_miner: fragment.miner.clone(),
+                        file_hash: *file_hash,
+                        fragment_hash: fragment.hash,
+                        gen_block: now,
+                        deadline,
+                    };
+                    <RestoralOrder<T>>::insert(&fragment.hash, restoral_order);
+                    weight = weight.saturating_add(T::DbWeight::get().writes(1));
+                    fragment.avail = false;
+                    dispatched.push(fragment.hash);
+
+                    Self::deposit_event(Event::<T>::RecoverFile {
+                        file_hash: *file_hash,
+                        fragment_hash: fragment.hash,
+                        lost_miner: fragment.miner.clone(),
+                        assignee,
+                    });
+                }
+            }
+
+            Ok(())
+        })?;
+
+        if dispatched.is_empty() {
+            return Ok(weight);
+        }
+
+        Self::refresh_file_summary(file_hash);
+        <RecoveryQueue<T>>::try_mutate(file_hash, |info_opt| -> DispatchResult {
+            let info = info_opt.get_or_insert_with(|| RecoveryInfo::<T> { pending: Default::default(), restored: 0 });
+            info.pending.try_append(&mut dispatched).map_err(|_| Error::<T>::BoundedVecError)?;
+            Ok(())
+        })?;
+        weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 3));
+
+        Ok(weight)
+    }
+
+    // The tag must be computed after the order was claimed, and signed by a majority of the
+    // tee committee over the fragment of the order held by the miner of the order.
+    // The nonce of the worker that computed it is consumed, so the tag is not accepted twice.
+    pub(super) fn check_restoral_tag(
+        order: &RestoralOrderInfo<T>,
+        tag: &RestoralTag<AccountOf<T>, BlockNumberOf<T>>,
+        now: BlockNumberOf<T>,
+    ) -> DispatchResult {
+        ensure!(
+            tag.computed_at >= order.gen_block && tag.computed_at <= now,
+            Error::<T>::StaleRestoralTag
+        );
+        ensure!(
+            tag.signatures.iter().any(|(signer, _)| signer == &tag.worker),
+            Error::<T>::InvalidRestoralTag
+        );
+
+        let message = (
+            &order.fragment_hash,
+            &order.miner,
+            &tag.tag_hash,
+            &tag.computed_at,
+            &tag.worker,
+            tag.nonce,
+        ).encode();
+        ensure!(
+            T::Scheduler::is_committee_majority(&message, &tag.signatures),
+            Error::<T>::InvalidRestoralTag
+        );
+        T::Scheduler::consume_nonce(&tag.worker, tag.nonce)?;
+
+        Ok(())
+    }
+
+    // Counts the restored fragment of a file in recovery, the recovery completes with its last fragment.
+    pub(super) fn record_recovery(file_hash: &Hash, fragment_hash: &Hash) {
+        let completed = <RecoveryQueue<T>>::mutate_exists(file_hash, |info_opt| {
+            let info = info_opt.as_mut()?;
+            let index = info.pending.iter().position(|hash| hash == fragment_hash)?;
+            info.pending.remove(index);
+            info.restored = info.restored.saturating_add(1);
+            if info.pending.is_empty() {
+                return info_opt.take().map(|info| info.restored);
+            }
+
+            None
+        });
+
+        if let Some(restored) = completed {
+            Self::deposit_event(Event::<T>::RecoveryComplete { file_hash: *file_hash, restored });
+        }
+    }
+
+    // The NFC form of a name given by a user, at least `minimum` bytes long.
+    pub(super) fn normalize_name(
+        raw: &BoundedVec<u8, T::NameStrLimit>,
+        minimum: u32,
+    ) -> Result<BoundedVec<u8, T::NameStrLimit>, DispatchError> {
+        let normalized = name::normalize_name(raw, minimum as usize, T::NameStrLimit::get() as usize)
+            .map_err(|e| match e {
+                name::NameError::TooShort => Error::<T>::LessMinLength,
+                name::NameError::TooLong => Error::<T>::LengthExceedsLimit,
+                name::NameError::NotUtf8 | name::NameError::ControlCharacter => Error::<T>::MalformedName,
+            })?;
+
+        Ok(normalized.try_into().map_err(|_| Error::<T>::LengthExceedsLimit)?)
+    }
+
     pub(super) fn check_bucket_name_spec(name: Vec<u8>) -> bool {
         let mut point_flag: bool = false;
         let mut count = 0;
@@ -603,4 +2431,118 @@ impl<T: Config> Pallet<T> {
 
         true
     }
-}
\ No newline at end of file
+
+    /// helper: check invalid file reminder.
+    ///
+    /// Validate an unsigned reminder, it has to be signed by one of the authorities
+    /// and the file must still be due for a reminder.
+    ///
+    /// Parameters:
+    /// - `reminder`: the reminder submitted by the offchain worker.
+    /// - `signature`: signature of the reminder by its key.
+    pub(super) fn check_invalid_file_reminder(
+        reminder: &InvalidFileReminder<AccountOf<T>, BlockNumberOf<T>, T::AuthorityId>,
+        signature: &<T::AuthorityId as RuntimeAppPublic>::Signature,
+    ) -> TransactionValidity {
+        let now = <frame_system::Pallet<T>>::block_number();
+        let period = T::InvalidFileReminderPeriod::get();
+
+        if !T::Authorities::contains(&reminder.authority) {
+            return InvalidTransaction::BadSigner.into();
+        }
+        if reminder.block_number > now || !Self::invalid_file_reminder_due(&reminder.miner, &reminder.file_hash, now) {
+            return InvalidTransaction::Future.into();
+        }
+        if reminder.block_number.saturating_add(period) <= now
+            || !<InvalidFile<T>>::get(&reminder.miner).contains(&reminder.file_hash)
+        {
+            return InvalidTransaction::Stale.into();
+        }
+
+        let signature_valid = reminder.using_encoded(|encoded_reminder| {
+            reminder.authority.verify(&encoded_reminder, signature)
+        });
+
+        if !signature_valid {
+            log::error!("bad invalid file reminder signature.");
+            return InvalidTransaction::BadProof.into()
+        }
+
+        // One reminder per round is included, whichever authority sent it.
+        let count = <InvalidFileReminders<T>>::get(&reminder.miner, &reminder.file_hash)
+            .map(|(count, _)| count)
+            .unwrap_or(0);
+        ValidTransaction::with_tag_prefix("InvalidFileReminder")
+            .priority(T::UnsignedPriority::get())
+            .and_provides((&reminder.miner, reminder.file_hash, count))
+            .longevity(TryInto::<u64>::try_into(period).unwrap_or(64_u64))
+            .propagate(true)
+            .build()
+    }
+
+    /// helper: offchain remind invalid files.
+    ///
+    /// Scan the invalid files of all miners and submit a reminder for those
+    /// the miners left uncleared for `InvalidFileReminderPeriod` blocks,
+    /// at most `REMINDER_BATCH` per scan.
+    ///
+    /// Parameters:
+    /// - `now`: the block the offchain worker runs at.
+    pub(super) fn offchain_remind_invalid_files(now: BlockNumberOf<T>) -> Result<(), OffchainErr> {
+        let key = T::AuthorityId::all()
+            .into_iter()
+            .find(|key| T::Authorities::contains(key))
+            .ok_or(OffchainErr::Ineligible)?;
+
+        if !Self::check_reminder_lock(now, &key) {
+            return Err(OffchainErr::Working);
+        }
+
+        let mut submitted: u32 = 0;
+        for (miner, hash_list) in <InvalidFile<T>>::iter() {
+            for file_hash in hash_list.into_iter() {
+                if submitted >= REMINDER_BATCH {
+                    return Ok(());
+                }
+                if !Self::invalid_file_reminder_due(&miner, &file_hash, now) {
+                    continue;
+                }
+
+                let reminder = InvalidFileReminder {
+                    miner: miner.clone(),
+                    file_hash,
+                    block_number: now,
+                    authority: key.clone(),
+                };
+                let signature = key.sign(&reminder.encode()).ok_or(OffchainErr::FailedSigning)?;
+                let call = Call::remind_invalid_file { reminder, signature };
+
+                if let Err(e) = SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()) {
+                    log::error!("{:?}", e);
+                    return Err(OffchainErr::SubmitTransactionFailed);
+                }
+                submitted += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Keeps the offchain worker from scanning on every block
+    // while the reminders of the last scan are still waiting for inclusion.
+    fn check_reminder_lock(now: BlockNumberOf<T>, key: &T::AuthorityId) -> bool {
+        let lock_key = (b"file-bank::invalid-file-reminder", key).encode();
+        let storage = StorageValueRef::persistent(&lock_key);
+
+        let res = storage.mutate(|status: Result<Option<BlockNumberOf<T>>, StorageRetrievalError>| {
+            match status {
+                Ok(Some(last_block)) if last_block.saturating_add(REMINDER_SCAN_INTERVAL.into()) > now => {
+                    Err(OffchainErr::Working)
+                },
+                _ => Ok(now),
+            }
+        });
+
+        res.is_ok()
+    }
+}
@@ -25,8 +25,8 @@ mod mock;
 mod tests;
 
 use frame_support::traits::{
-	FindAuthor, Randomness,
-	StorageVersion,
+	FindAuthor, Randomness, Contains,
+	StorageVersion, Currency, ReservableCurrency, BalanceStatus, ExistenceRequirement::KeepAlive,
 	schedule::{Anon as ScheduleAnon, DispatchTime, Named as ScheduleNamed}, 
 };
 // use sc_network::Multiaddr;
@@ -35,7 +35,7 @@ pub use pallet::*;
 #[cfg(feature = "runtime-benchmarks")]
 pub mod benchmarking;
 pub mod weights;
-// pub mod migrations;
+pub mod migrations;
 
 mod types;
 pub use types::*;
@@ -52,6 +52,10 @@ use frame_support::{
 	PalletId, 
 	dispatch::{Dispatchable, DispatchResult}, 
 	pallet_prelude::*,
+	storage::{
+		child::{self, ChildInfo},
+		with_storage_layer, ChildTriePrefixIterator,
+	},
 	weights::Weight,
 	traits::schedule,
 };
@@ -59,29 +63,51 @@ use frame_system::pallet_prelude::*;
 use scale_info::TypeInfo;
 use cp_cess_common::*;
 use pallet_storage_handler::StorageHandle;
-use cp_scheduler_credit::SchedulerCreditCounter;
 use sp_runtime::{
 	traits::{
-		BlockNumberProvider, CheckedAdd,
+		AccountIdConversion, BlockNumberProvider, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Saturating,
 	},
-	RuntimeDebug, SaturatedConversion,
+	app_crypto::RuntimeAppPublic,
+	offchain::storage::{StorageRetrievalError, StorageValueRef},
+	RuntimeDebug, SaturatedConversion, Perbill,
 };
+use frame_system::offchain::{SendTransactionTypes, SubmitTransaction};
 use sp_std::{
 	convert::TryInto, 
 	prelude::*, 
 	str, 
-	collections::btree_map::BTreeMap
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
 };
 use pallet_sminer::MinerControl;
-use pallet_tee_worker::ScheduleFind;
+use pallet_tee_worker::{ScheduleAssign, ScheduleExit, ScheduleFind, ROLE_MARKER};
 use pallet_oss::OssFindAuthor;
 
 pub use weights::WeightInfo;
 
 type AccountOf<T> = <T as frame_system::Config>::AccountId;
 type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
+type BalanceOf<T> =
+	<<T as pallet::Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(6);
+
+enum OffchainErr {
+	Ineligible,
+	Working,
+	FailedSigning,
+	SubmitTransactionFailed,
+}
 
-const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+impl sp_std::fmt::Debug for OffchainErr {
+	fn fmt(&self, fmt: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		match *self {
+			OffchainErr::Ineligible => write!(fmt, "No local key belongs to an authority"),
+			OffchainErr::Working => write!(fmt, "The invalid files were scanned recently"),
+			OffchainErr::FailedSigning => write!(fmt, "Signing invalid file reminder failed"),
+			OffchainErr::SubmitTransactionFailed => write!(fmt, "Failed to submit transaction."),
+		}
+	}
+}
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -95,7 +121,7 @@ pub mod pallet {
 	pub const FILE_ACTIVE: &str = "active";
 
 	#[pallet::config]
-	pub trait Config: frame_system::Config + sp_std::fmt::Debug {
+	pub trait Config: frame_system::Config + sp_std::fmt::Debug + SendTransactionTypes<Call<Self>> {
 		/// The overarching event type.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
@@ -113,7 +139,7 @@ pub mod pallet {
 		//Find the consensus of the current block
 		type FindAuthor: FindAuthor<Self::AccountId>;
 		//Used to find out whether the schedule exists
-		type Scheduler: ScheduleFind<Self::AccountId>;
+		type Scheduler: ScheduleFind<Self::AccountId> + ScheduleAssign<Self::AccountId>;
 		//It is used to control the computing power and space of miners
 		type MinerControl: MinerControl<Self::AccountId>;
 		//Interface that can generate random seeds
@@ -132,12 +158,18 @@ pub mod pallet {
 
 		#[pallet::constant]
 		type UploadFillerLimit: Get<u8> + Clone + Eq + PartialEq;
+		// The most files deleted by one `batch_delete_file`.
+		#[pallet::constant]
+		type BatchDeleteLimit: Get<u32> + Clone + Eq + PartialEq;
 
 		#[pallet::constant]
 		type RecoverLimit: Get<u32> + Clone + Eq + PartialEq;
 
 		#[pallet::constant]
 		type InvalidLimit: Get<u32> + Clone + Eq + PartialEq;
+		// Blocks a miner has to clear an invalid file before it is punished.
+		#[pallet::constant]
+		type InvalidFileGracePeriod: Get<BlockNumberOf<Self>>;
 		// User defined name length limit
 		#[pallet::constant]
 		type NameStrLimit: Get<u32> + Clone + Eq + PartialEq;
@@ -164,47 +196,217 @@ pub mod pallet {
 		#[pallet::constant]
 		type RestoralOrderLife: Get<u32> + Clone + Eq + PartialEq;
 
-		type CreditCounter: SchedulerCreditCounter<Self::AccountId>;
+		//Hands the storage events of the pallet to the pallets listening for them
+		type EventRouter: StorageEventRouter<Self::AccountId>;
 		//Used to confirm whether the origin is authorized
 		type OssFindAuthor: OssFindAuthor<Self::AccountId>;
 
 		#[pallet::constant]
 		type MissionCount: Get<u32> + Clone + Eq + PartialEq;
+		//The origin allowed to intervene in stuck deals
+		type ForceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		// Maximum number of history versions kept for a versioned file
+		#[pallet::constant]
+		type VersionLimit: Get<u32> + Clone + Eq + PartialEq;
+		// Length of the period the declaration limits apply to
+		#[pallet::constant]
+		type DeclarationEra: Get<BlockNumberOf<Self>>;
+		// Blocks during which a requested deletion can still be cancelled
+		#[pallet::constant]
+		type DeleteGracePeriod: Get<BlockNumberOf<Self>>;
+		/// The currency trait.
+		type Currency: ReservableCurrency<Self::AccountId>;
+		// The account receiving the treasury share of download fees
+		#[pallet::constant]
+		type TreasuryPalletId: Get<PalletId>;
+		// Share of a download fee paid to the file owner
+		#[pallet::constant]
+		type OwnerShare: Get<Perbill>;
+		// Share of a download fee split between the miners storing the file, the rest goes to the treasury
+		#[pallet::constant]
+		type MinerShare: Get<Perbill>;
+		// Length of the period file popularity decays over
+		#[pallet::constant]
+		type PopularityEra: Get<BlockNumberOf<Self>>;
+		// Share of the popularity score kept from one era to the next
+		#[pallet::constant]
+		type PopularityDecay: Get<Perbill>;
+		// Maximum number of retrieval receipts an account submits per era
+		#[pallet::constant]
+		type MaxReceiptsPerEra: Get<u32>;
+		// Maximum score a file gains per era
+		#[pallet::constant]
+		type MaxPopularityGain: Get<u32>;
+		// Deals of at least this many bytes are priced by an auction among miners
+		#[pallet::constant]
+		type AuctionThreshold: Get<u128>;
+		// Length of the commit phase and of the reveal phase of a deal auction
+		#[pallet::constant]
+		type AuctionPeriod: Get<BlockNumberOf<Self>>;
+		// Revealed bids needed to price a deal by auction, otherwise the posted price applies
+		#[pallet::constant]
+		type MinAuctionBidders: Get<u32>;
+		// Share of the space waived for users declaring a file that is already stored
+		#[pallet::constant]
+		type DedupDiscount: Get<Perbill>;
+		// The gateways serving downloads through share links
+		type Gateways: Contains<Self::AccountId>;
+		// Maximum number of deals of one account waiting to be stored at the same time
+		#[pallet::constant]
+		type MaxPendingDeals: Get<u32>;
+		// The key the offchain worker of an authority signs its invalid file reminders with
+		type AuthorityId: Member
+			+ Parameter
+			+ RuntimeAppPublic
+			+ Ord
+			+ MaybeSerializeDeserialize
+			+ MaxEncodedLen;
+		// The keys allowed to remind miners of the invalid files they did not clear
+		type Authorities: Contains<Self::AuthorityId>;
+		// Blocks an invalid file waits for the miner before each reminder
+		#[pallet::constant]
+		type InvalidFileReminderPeriod: Get<BlockNumberOf<Self>>;
+		// Base priority of the unsigned invalid file reminders
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+		// Reserved from the owner for each new deal, refunded once the deal is stored or withdrawn
+		#[pallet::constant]
+		type DeclarationDeposit: Get<BalanceOf<Self>>;
+		// Maximum number of files one account can declare in a block
+		#[pallet::constant]
+		type MaxDeclarationsPerBlock: Get<u32>;
+		// Whether files can be paid by storage orders, next to packages
+		#[pallet::constant]
+		type StorageOrdersEnabled: Get<bool>;
 	}
 
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
-		//file upload declaration
-		UploadDeclaration { operator: AccountOf<T>, owner: AccountOf<T>, deal_hash: Hash },
+		//file upload declaration, `charged_space` is the space taken from the package of the owner
+		UploadDeclaration { operator: AccountOf<T>, owner: AccountOf<T>, deal_hash: Hash, file_size: u128, charged_space: u128, deduplicated: bool },
 		//file uploaded.
 		TransferReport { acc: AccountOf<T>, failed_list: Vec<Hash> },
 		//File deletion event
 		DeleteFile { operator:AccountOf<T>, owner: AccountOf<T>, file_hash_list: Vec<Hash> },
+		//Files deleted by a batch, with the error of each file that was kept
+		BatchDeleteFile { operator: AccountOf<T>, owner: AccountOf<T>, deleted: Vec<Hash>, failed: Vec<(Hash, DispatchError)> },
 
 		ReplaceFiller { acc: AccountOf<T>, filler_list: Vec<Hash> },
 
-		CalculateEnd{ file_hash: Hash },
+		CalculateEnd{ file_hash: Hash, owner: AccountOf<T>, file_size: u128, scheduler: Option<AccountOf<T>> },
+		//The scheduler did not report every segment in time, the deal was removed and the owner refunded
+		SegmentReportTimeout { file_hash: Hash, owner: AccountOf<T>, scheduler: AccountOf<T> },
 		//Filler chain success event
-		FillerUpload { acc: AccountOf<T>, file_size: u64 },
+		FillerUpload { acc: AccountOf<T>, file_size: u64, count: u32 },
+		//Fillers of an upload that were already stored and left out
+		FillerUploadSkipped { acc: AccountOf<T>, filler_list: Vec<Hash> },
 
 		FillerDelete { acc: AccountOf<T>, filler_hash: Hash },
 		//Event to successfully create a bucket
 		CreateBucket { operator: AccountOf<T>, owner: AccountOf<T>, bucket_name: Vec<u8>},
 		//Successfully delete the bucket event
 		DeleteBucket { operator: AccountOf<T>, owner: AccountOf<T>, bucket_name: Vec<u8>},
+		//The owner withdrew a deal before it was stored, the deposit was refunded
+		DeclarationWithdrawn { operator: AccountOf<T>, owner: AccountOf<T>, deal_hash: Hash },
+		//The placement policy inherited by the files declared into the bucket changed, None clears it
+		BucketPolicySet { operator: AccountOf<T>, owner: AccountOf<T>, bucket_name: Vec<u8>, policy: Option<StoragePolicy> },
 
 		Withdraw { acc: AccountOf<T> },
 
-		GenerateRestoralOrder { miner: AccountOf<T>, fragment_hash: Hash },
+		GenerateRestoralOrder { miner: AccountOf<T>, file_hash: Hash, fragment_hash: Hash },
 
-		ClaimRestoralOrder { miner: AccountOf<T>, order_id: Hash },
+		ClaimRestoralOrder { miner: AccountOf<T>, order_id: Hash, deadline: BlockNumberOf<T> },
 
 		RecoveryCompleted { miner: AccountOf<T>, order_id: Hash },
 
-		StorageCompleted { file_hash: Hash },
+		//The fragments of the file were stored, the miners have to tag them before the deadline
+		StorageCompleted { file_hash: Hash, owner: AccountOf<T>, file_size: u128, miners: Vec<AccountOf<T>>, deadline: BlockNumberOf<T> },
 
 		MinerExitPrep { miner: AccountOf<T> },
+		//The file owner granted access to another account
+		GrantAccess { owner: AccountOf<T>, file_hash: Hash, acc: AccountOf<T>, permission: u8 },
+		//The file owner revoked the access of another account
+		RevokeAccess { owner: AccountOf<T>, file_hash: Hash, acc: AccountOf<T> },
+		//The file owner opted the file in or out of public sampling
+		PublicDataset { owner: AccountOf<T>, file_hash: Hash, enabled: bool },
+		//The scheduler reported a segment of the deal
+		SegmentUploaded { scheduler: AccountOf<T>, file_hash: Hash, index: u32, miner: AccountOf<T> },
+		//A new version of the file was linked to its predecessor
+		FileVersionUpdated { acc: AccountOf<T>, file_hash: Hash, new_hash: Hash, version: u32 },
+		//The file was rolled back to a history version
+		FileVersionRollback { acc: AccountOf<T>, file_hash: Hash, target: Hash },
+		//The used space of the user was recomputed from its file list
+		UserSpaceCorrected { operator: AccountOf<T>, acc: AccountOf<T>, recorded: u128, actual: u128 },
+		//The period of the lease sweep task was changed
+		LeaseSweepPeriodSet { period: BlockNumberOf<T> },
+		//The user changed its own declaration limit
+		DeclarationLimitSet { acc: AccountOf<T>, limit: Option<u128> },
+		//Governance changed the declaration limit of all accounts
+		GlobalDeclarationLimitSet { limit: Option<u128> },
+		//The owner requested a mirror of the file on a partner network
+		MirrorRequested { owner: AccountOf<T>, file_hash: Hash, network: BoundedVec<u8, T::NameStrLimit> },
+		//A gateway confirmed the mirror of the file
+		MirrorConfirmed { operator: AccountOf<T>, owner: AccountOf<T>, file_hash: Hash, locator: Hash },
+		//The deletion of the file was requested, it is finalized at the given block
+		DeleteRequested { operator: AccountOf<T>, owner: AccountOf<T>, file_hash: Hash, finalize: BlockNumberOf<T> },
+		//The requested deletion was cancelled
+		DeleteCancelled { operator: AccountOf<T>, owner: AccountOf<T>, file_hash: Hash },
+		//The grace period is over and the file was deleted
+		DeleteFinalized { owner: AccountOf<T>, file_hash: Hash, file_size: u128 },
+		//The miner can drop the fragment, it has to clear it before the deadline
+		InvalidFileNotified { miner: AccountOf<T>, file_hash: Hash, deadline: BlockNumberOf<T> },
+		//The miner acknowledged that it dropped the fragment
+		ClearInvalidFile { acc: AccountOf<T>, file_hash: Hash },
+		//The miner did not clear the fragment before the deadline and was punished
+		InvalidFileExpired { miner: AccountOf<T>, file_hash: Hash },
+		//An authority reminded the miner of the fragment, `deadline` is the block the miner is punished at
+		InvalidFileReminded { miner: AccountOf<T>, file_hash: Hash, count: u32, deadline: Option<BlockNumberOf<T>> },
+		//The owner changed the download price of the file, `None` stops the sale
+		DownloadPriceSet { owner: AccountOf<T>, file_hash: Hash, price: Option<BalanceOf<T>> },
+		//The buyer paid the download fee of the file
+		BuyFile { buyer: AccountOf<T>, owner: AccountOf<T>, file_hash: Hash, price: BalanceOf<T> },
+		//The exited miner paid for the replicas that were not restored
+		CompensateReplica { miner: AccountOf<T>, lost_space: u128 },
+		//The deal was reassigned by governance
+		ForceReassignDeal { deal_hash: Hash, old_scheduler: AccountOf<T>, new_scheduler: AccountOf<T>, deadline: BlockNumberOf<T> },
+		//The scheduler of the deal exited, the deal was handed to another one
+		DealSchedulerReplaced { deal_hash: Hash, old_scheduler: AccountOf<T>, new_scheduler: AccountOf<T> },
+		//A retrieval of the file was reported
+		RetrievalReceipt { acc: AccountOf<T>, file_hash: Hash, score: u32 },
+		//An auction was opened to price a large deal
+		DealAuctionStarted { deal_hash: Hash, commit_end: BlockNumberOf<T>, reveal_end: BlockNumberOf<T> },
+		//A miner committed a sealed bid
+		DealBidCommitted { miner: AccountOf<T>, deal_hash: Hash },
+		//A miner revealed its bid
+		DealBidRevealed { miner: AccountOf<T>, deal_hash: Hash, price: BalanceOf<T> },
+		//The price of the deal was set, by the auction or by the posted price when `auctioned` is false
+		DealAuctionSettled { deal_hash: Hash, price: BalanceOf<T>, auctioned: bool, winners: BoundedVec<AccountOf<T>, T::StringLimit> },
+		//A fragment lost with an exited miner was handed to a replica miner of its segment,
+		//or to the scheduler of the file, which seeds it to the miner claiming the order
+		RecoverFile { file_hash: Hash, fragment_hash: Hash, lost_miner: AccountOf<T>, assignee: AccountOf<T> },
+		//All fragments of the file lost with exited miners were restored
+		RecoveryComplete { file_hash: Hash, restored: u32 },
+		//The ownership of the file moved to another account
+		FileOwnershipTransferred { owner: AccountOf<T>, new_owner: AccountOf<T>, file_hash: Hash },
+		//The owner offered the file to another account, which has to accept it
+		FileTransferRequested { owner: AccountOf<T>, new_owner: AccountOf<T>, file_hash: Hash },
+		//A share link of the file was created
+		ShareCreated { owner: AccountOf<T>, file_hash: Hash, share_id: [u8; 32], expiry: BlockNumberOf<T>, max_downloads: u32 },
+		//The share link was revoked by its creator
+		ShareRevoked { owner: AccountOf<T>, file_hash: Hash, share_id: [u8; 32] },
+		//Governance changed the smallest and largest size of a declared file
+		FileSizeLimitSet { limit: Option<(u128, u128)> },
+		//Governance exempted an account from the file size limit, or revoked the exemption
+		FileSizeExemptionSet { acc: AccountOf<T>, exempt: bool },
+		//A gateway served a download through the share link, it is removed when no download is left
+		ShareRedeemed { gateway: AccountOf<T>, file_hash: Hash, share_id: [u8; 32], remaining: u32 },
+		//The owner reserved the escrow of a file paid by a storage order
+		StorageOrderCreated { owner: AccountOf<T>, file_hash: Hash, space: u128, days: u32, escrow: BalanceOf<T> },
+		//The days the miners of the file stored it were paid from the escrow, failed miners are left out
+		StorageOrderSettled { owner: AccountOf<T>, file_hash: Hash, days: u32, paid: BalanceOf<T>, miners: u32 },
+		//The order ended, the escrow that was not paid out was refunded
+		StorageOrderClosed { owner: AccountOf<T>, file_hash: Hash, refund: BalanceOf<T> },
 	}
 
 	#[pallet::error]
@@ -270,6 +472,50 @@ pub mod pallet {
 		MinerStateError,
 
 		Expired,
+		//The deal is no longer in the transfer stage
+		StageError,
+		//The permission bits are not recognized
+		InvalidPermission,
+		//The file is already part of a version history
+		VersionConflict,
+		//The bytes declared in this era exceed the declaration limit
+		DeclarationLimitExceeded,
+		//The account submitted the maximum number of retrieval receipts in this era
+		ReceiptLimitExceeded,
+		//The current phase of the deal auction does not allow this
+		AuctionPhaseError,
+		//The revealed bid does not match the commitment
+		BidMismatch,
+		//The tag of the restored fragment was computed before the order was claimed or in the future
+		StaleRestoralTag,
+		//The tag of the restored fragment is not signed by a majority of the tee committee
+		InvalidRestoralTag,
+		//The name is not utf-8 or contains control characters
+		MalformedName,
+		//The declared file is smaller than the file size limit allows
+		FileTooSmall,
+		//The declared file is larger than the file size limit allows
+		FileTooLarge,
+		//The declared size does not match the number of segments
+		FileSizeMismatch,
+		//The account has `MaxPendingDeals` deals waiting to be stored
+		TooManyPendingDeals,
+		//The miner was reminded of the invalid file less than `InvalidFileReminderPeriod` blocks ago
+		ReminderTooEarly,
+		//The region of the storage policy is not two upper case letters
+		InvalidRegion,
+		//The account declared `MaxDeclarationsPerBlock` files in this block
+		TooManyDeclarations,
+		//Storage orders are disabled by the runtime
+		StorageOrdersDisabled,
+		//The file is larger than the space paid by its storage order
+		StorageOrderTooSmall,
+		//The ownership of a file paid by a storage order cannot be transferred
+		OrderedFile,
+		//No transfer of the file to the caller is pending
+		TransferNotPending,
+		//The file lists of the users are still being moved into their file tries
+		Migrating,
 	}
 
 	
@@ -277,20 +523,37 @@ pub mod pallet {
 	#[pallet::getter(fn deal_map)]
 	pub(super) type DealMap<T: Config> = StorageMap<_, Blake2_128Concat, Hash, DealInfo<T>>;
 
+	/// The number of deals of each account in `DealMap`.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_deal_count)]
+	pub(super) type PendingDealCount<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, u32, ValueQuery>;
+
+	/// The tee worker that received the data of a stored file while it was a deal.
+	#[pallet::storage]
+	#[pallet::getter(fn file_scheduler)]
+	pub(super) type FileScheduler<T: Config> = StorageMap<_, Blake2_128Concat, Hash, AccountOf<T>>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn file)]
 	pub(super) type File<T: Config> =
 		StorageMap<_, Blake2_128Concat, Hash, FileInfo<T>>;
 
+	/// Compact copy of the state, size, replicas and owners of each file, see `FileSummaryInfo`.
 	#[pallet::storage]
-	#[pallet::getter(fn user_hold_file_list)]
-	pub(super) type UserHoldFileList<T: Config> = StorageMap<
-		_,
-		Blake2_128Concat,
-		T::AccountId,
-		BoundedVec<UserFileSliceInfo, T::StringLimit>,
-		ValueQuery,
-	>;
+	#[pallet::getter(fn file_summary)]
+	pub(super) type FileSummary<T: Config> = StorageMap<_, Blake2_128Concat, Hash, FileSummaryInfo>;
+
+	/// Number of owners sharing the single stored copy of a file.
+	#[pallet::storage]
+	#[pallet::getter(fn file_ref_count)]
+	pub(super) type FileRefCount<T: Config> = StorageMap<_, Blake2_128Concat, Hash, u32, ValueQuery>;
+
+	/// Count, total size and child trie root of the files held by each user.
+	/// The files themselves are in the child trie of the user, see `user_file_trie`.
+	#[pallet::storage]
+	#[pallet::getter(fn user_file_index)]
+	pub(super) type UserFileIndex<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, UserFileListInfo<T::Hash>>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn filler_map)]
@@ -303,6 +566,31 @@ pub mod pallet {
 		FillerInfo<T>,
 	>;
 
+	/// Every stored filler at a dense position, so that random fillers are drawn by position.
+	#[pallet::storage]
+	pub(super) type FillerIndex<T: Config> = StorageMap<_, Twox64Concat, u32, (AccountOf<T>, Hash)>;
+
+	/// Position of each filler in `FillerIndex`.
+	#[pallet::storage]
+	pub(super) type FillerPosition<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, AccountOf<T>, Blake2_128Concat, Hash, u32>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn filler_count)]
+	pub(super) type FillerCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// Every active file at a dense position, so that random files are drawn by position.
+	#[pallet::storage]
+	pub(super) type ActiveFileIndex<T: Config> = StorageMap<_, Twox64Concat, u32, Hash>;
+
+	/// Position of each active file in `ActiveFileIndex`.
+	#[pallet::storage]
+	pub(super) type ActiveFilePosition<T: Config> = StorageMap<_, Blake2_128Concat, Hash, u32>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn active_file_count)]
+	pub(super) type ActiveFileCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn pending_replacements)]
 	pub(super) type PendingReplacements<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, u32, ValueQuery>;
@@ -329,6 +617,17 @@ pub mod pallet {
 			BucketInfo<T>,
 		>;
 
+	// Placement policy the files declared into the bucket inherit.
+	#[pallet::storage]
+	#[pallet::getter(fn bucket_policy)]
+	pub(super) type BucketPolicy<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, AccountOf<T>, Blake2_128Concat, BoundedVec<u8, T::NameStrLimit>, StoragePolicy>;
+
+	// Placement policy of a deal, followed whenever its miners are assigned.
+	#[pallet::storage]
+	#[pallet::getter(fn deal_policy)]
+	pub(super) type DealPolicy<T: Config> = StorageMap<_, Blake2_128Concat, Hash, StoragePolicy>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn user_bucket_list)]
 	pub(super) type UserBucketList<T: Config> = 
@@ -350,10 +649,311 @@ pub mod pallet {
 	pub(super) type RestoralOrder<T: Config> = 
 		StorageMap<_, Blake2_128Concat, Hash, RestoralOrderInfo<T>>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn file_acl)]
+	pub(super) type FileAcl<T: Config> =
+		StorageDoubleMap<
+			_,
+			Blake2_128Concat,
+			Hash,
+			Blake2_128Concat,
+			AccountOf<T>,
+			FileAclInfo<T>,
+		>;
+
+	// Segments of a deal reported by its scheduler, indexed by the position in the declaration.
+	#[pallet::storage]
+	#[pallet::getter(fn file_segments)]
+	pub(super) type FileSegments<T: Config> =
+		StorageDoubleMap<
+			_,
+			Blake2_128Concat,
+			Hash,
+			Blake2_128Concat,
+			u32,
+			SegmentCommitment<T>,
+		>;
+
+	// Maximum bytes the user allows itself to declare per era.
+	#[pallet::storage]
+	#[pallet::getter(fn declaration_limit)]
+	pub(super) type DeclarationLimit<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, u128>;
+
+	// Maximum bytes any account can declare per era, set by governance.
+	#[pallet::storage]
+	#[pallet::getter(fn global_declaration_limit)]
+	pub(super) type GlobalDeclarationLimit<T: Config> = StorageValue<_, u128>;
+
+	// Files each account declared in the block, only the latest block is kept.
+	#[pallet::storage]
+	#[pallet::getter(fn block_declarations)]
+	pub(super) type BlockDeclarations<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, (BlockNumberOf<T>, u32)>;
+
+	// The account and amount reserved for a deal, see `DeclarationDeposit`.
+	#[pallet::storage]
+	#[pallet::getter(fn deal_deposit)]
+	pub(super) type DealDeposit<T: Config> = StorageMap<_, Blake2_128Concat, Hash, (AccountOf<T>, BalanceOf<T>)>;
+
+	// Smallest and largest size of a declared file, set by governance.
+	#[pallet::storage]
+	#[pallet::getter(fn file_size_limit)]
+	pub(super) type FileSizeLimit<T: Config> = StorageValue<_, (u128, u128)>;
+
+	// Accounts whose declarations are not bound by the file size limit.
+	#[pallet::storage]
+	#[pallet::getter(fn file_size_exempt)]
+	pub(super) type FileSizeExempt<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, bool, ValueQuery>;
+
+	// The era index and the bytes the user declared in it.
+	#[pallet::storage]
+	#[pallet::getter(fn declared_bytes)]
+	pub(super) type DeclaredBytes<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, (u32, u128), ValueQuery>;
+
+	// Mirrors of files on partner networks requested by their holders.
+	#[pallet::storage]
+	#[pallet::getter(fn file_mirror)]
+	pub(super) type FileMirror<T: Config> =
+		StorageDoubleMap<
+			_,
+			Blake2_128Concat,
+			Hash,
+			Blake2_128Concat,
+			AccountOf<T>,
+			MirrorInfo<T>,
+		>;
+
+	// Blocks between two runs of the lease sweep task.
+	#[pallet::storage]
+	#[pallet::getter(fn lease_sweep_period)]
+	pub(super) type LeaseSweepPeriod<T: Config> = StorageValue<_, BlockNumberOf<T>>;
+
+	// History versions of a file held by the user, oldest first.
+	// The key is the latest version, older versions are not deleted when the history is full.
+	#[pallet::storage]
+	#[pallet::getter(fn file_versions)]
+	pub(super) type FileVersions<T: Config> =
+		StorageDoubleMap<
+			_,
+			Blake2_128Concat,
+			AccountOf<T>,
+			Blake2_128Concat,
+			Hash,
+			BoundedVec<Hash, T::VersionLimit>,
+			ValueQuery,
+		>;
+
+	// Public datasets that light clients are allowed to sample.
+	#[pallet::storage]
+	#[pallet::getter(fn public_dataset)]
+	pub(super) type PublicDataset<T: Config> = StorageMap<_, Blake2_128Concat, Hash, bool, ValueQuery>;
+
+	// Download price of a file set by its holder.
+	#[pallet::storage]
+	#[pallet::getter(fn download_price)]
+	pub(super) type DownloadPrice<T: Config> =
+		StorageDoubleMap<
+			_,
+			Blake2_128Concat,
+			Hash,
+			Blake2_128Concat,
+			AccountOf<T>,
+			BalanceOf<T>,
+		>;
+
+	// The block at which the buyer paid for the download of the file.
+	#[pallet::storage]
+	#[pallet::getter(fn file_buyer)]
+	pub(super) type FileBuyer<T: Config> =
+		StorageDoubleMap<
+			_,
+			Blake2_128Concat,
+			Hash,
+			Blake2_128Concat,
+			AccountOf<T>,
+			BlockNumberOf<T>,
+		>;
+
+	// Files of the user waiting for the end of the deletion grace period.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_delete)]
+	pub(super) type PendingDelete<T: Config> =
+		StorageDoubleMap<
+			_,
+			Blake2_128Concat,
+			AccountOf<T>,
+			Blake2_128Concat,
+			Hash,
+			BlockNumberOf<T>,
+		>;
+
+	// Block by which the miner has to clear each of its invalid files.
+	#[pallet::storage]
+	#[pallet::getter(fn invalid_file_deadline)]
+	pub(super) type InvalidFileDeadline<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, AccountOf<T>, Blake2_128Concat, Hash, BlockNumberOf<T>>;
+
+	// Reminders sent for each invalid file and the block the next one is due at.
+	#[pallet::storage]
+	#[pallet::getter(fn invalid_file_reminders)]
+	pub(super) type InvalidFileReminders<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, AccountOf<T>, Blake2_128Concat, Hash, (u32, BlockNumberOf<T>)>;
+
+	// Invalid files whose deadline is at the given block.
+	#[pallet::storage]
+	#[pallet::getter(fn invalid_file_queue)]
+	pub(super) type InvalidFileQueue<T: Config> =
+		StorageMap<_, Blake2_128Concat, BlockNumberOf<T>, BoundedVec<(AccountOf<T>, Hash), ConstU32<5000>>, ValueQuery>;
+
+	// Requested deletions to finalize at the given block.
+	#[pallet::storage]
+	#[pallet::getter(fn delete_queue)]
+	pub(super) type DeleteQueue<T: Config> =
+		StorageMap<_, Blake2_128Concat, BlockNumberOf<T>, BoundedVec<(AccountOf<T>, Hash), ConstU32<1000>>, ValueQuery>;
+
+	// Popularity score of a file, decayed lazily by the eras passed since the last update.
+	#[pallet::storage]
+	#[pallet::getter(fn file_popularity)]
+	pub(super) type FilePopularity<T: Config> = StorageMap<_, Blake2_128Concat, Hash, PopularityInfo>;
+
+	// The last era the account reported a retrieval of the file in.
+	#[pallet::storage]
+	#[pallet::getter(fn retrieval_receipt)]
+	pub(super) type RetrievalReceipts<T: Config> =
+		StorageDoubleMap<
+			_,
+			Blake2_128Concat,
+			Hash,
+			Blake2_128Concat,
+			AccountOf<T>,
+			u32,
+		>;
+
+	// The era of the last receipt of the account and the number of receipts in that era.
+	#[pallet::storage]
+	#[pallet::getter(fn receipt_count)]
+	pub(super) type ReceiptCount<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, (u32, u32), ValueQuery>;
+
+	// Sealed-bid auctions pricing large deals.
+	#[pallet::storage]
+	#[pallet::getter(fn deal_auction)]
+	pub(super) type DealAuctions<T: Config> = StorageMap<_, Blake2_128Concat, Hash, DealAuction<T>>;
+
+	// Deal auctions to settle at the given block.
+	#[pallet::storage]
+	#[pallet::getter(fn auction_queue)]
+	pub(super) type AuctionQueue<T: Config> =
+		StorageMap<_, Blake2_128Concat, BlockNumberOf<T>, BoundedVec<Hash, ConstU32<1000>>, ValueQuery>;
+
+	// Monthly storage price of a deal settled by its auction.
+	#[pallet::storage]
+	#[pallet::getter(fn deal_price)]
+	pub(super) type DealPrice<T: Config> = StorageMap<_, Blake2_128Concat, Hash, BalanceOf<T>>;
+
+	// Files re-seeding the fragments they lost with exited miners.
+	#[pallet::storage]
+	#[pallet::getter(fn recovery_queue)]
+	pub(super) type RecoveryQueue<T: Config> = StorageMap<_, Blake2_128Concat, Hash, RecoveryInfo<T>>;
+
+	// Exited miners waiting for the next recovery scan.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_recovery_miners)]
+	pub(super) type PendingRecoveryMiners<T: Config> =
+		StorageValue<_, BoundedVec<AccountOf<T>, ConstU32<100>>, ValueQuery>;
+
+	// The running recovery scan.
+	#[pallet::storage]
+	#[pallet::getter(fn recovery_scan)]
+	pub(super) type RecoveryScan<T: Config> = StorageValue<_, RecoveryScanInfo<T>>;
+
+	// Share links of a file, removed when they expire or run out of downloads.
+	#[pallet::storage]
+	#[pallet::getter(fn file_shares)]
+	pub(super) type FileShares<T: Config> =
+		StorageMap<_, Blake2_128Concat, Hash, BoundedVec<ShareInfo<T>, ConstU32<FILE_SHARE_LIMIT>>, ValueQuery>;
+
+	// Shares created by an account so far, mixed into the id of its next share.
+	#[pallet::storage]
+	#[pallet::getter(fn share_nonce)]
+	pub(super) type ShareNonce<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, u64, ValueQuery>;
+
+	// Share links expiring at the given block.
+	#[pallet::storage]
+	#[pallet::getter(fn share_expiry_queue)]
+	pub(super) type ShareExpiryQueue<T: Config> =
+		StorageMap<_, Blake2_128Concat, BlockNumberOf<T>, BoundedVec<(Hash, [u8; 32]), ConstU32<1000>>, ValueQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn clear_user_list)]
 	pub(super) type ClearUserList<T: Config> = 
-		StorageValue<_, BoundedVec<AccountOf<T>, ConstU32<5000>>, ValueQuery>;
+		StorageValue<_, BoundedVec<AccountOf<T>, ConstU32<LEASE_SWEEP_LIMIT>>, ValueQuery>;
+
+	// Files paid by storage orders, keyed by owner and file hash.
+	// These files are not charged to the package of the owner and are not in its file list.
+	#[pallet::storage]
+	#[pallet::getter(fn storage_order)]
+	pub(super) type StorageOrders<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, AccountOf<T>, Blake2_128Concat, Hash, StorageOrder<T>>;
+
+	// Final key of the last settled batch of storage orders, the next batch starts after it.
+	#[pallet::storage]
+	#[pallet::getter(fn storage_order_cursor)]
+	pub(super) type StorageOrderCursor<T: Config> = StorageValue<_, BoundedVec<u8, ConstU32<256>>, ValueQuery>;
+
+	// Set by the version 4 migration while file lists of users are still being moved into their file tries.
+	#[pallet::storage]
+	#[pallet::getter(fn user_file_migrating)]
+	pub(super) type UserFileMigrating<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	// The oldest block whose auction, delete, invalid file and share expiry queues did not fit
+	// in the weight budget of the block hook, the rest of them is handled from this block on.
+	#[pallet::storage]
+	#[pallet::getter(fn queue_cursor)]
+	pub(super) type QueueCursor<T: Config> = StorageValue<_, BlockNumberOf<T>>;
+
+	// Transfers offered by the owner of a file and not yet accepted, keyed by file hash and new owner.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_transfer)]
+	pub(super) type PendingTransfer<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, Hash, Blake2_128Concat, AccountOf<T>, AccountOf<T>>;
+
+	// The block a miner last failed a challenge at, storage orders do not pay it for that day.
+	#[pallet::storage]
+	#[pallet::getter(fn challenge_failures)]
+	pub(super) type ChallengeFailures<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberOf<T>>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig {
+		// Maximum bytes any account can declare per era
+		pub declaration_limit: Option<u128>,
+		// Smallest and largest size of a declared file
+		pub file_size_limit: Option<(u128, u128)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl Default for GenesisConfig {
+		fn default() -> Self {
+			Self {
+				declaration_limit: None,
+				file_size_limit: None,
+			}
+		}
+	}
+
+	// The unit price of the space is set by the genesis of the storage handler.
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			if let Some(bytes) = self.declaration_limit {
+				<GlobalDeclarationLimit<T>>::put(bytes);
+			}
+
+			if let Some((min, max)) = self.file_size_limit {
+				assert!(min <= max, "genesis file size limit must not be below its minimum");
+				<FileSizeLimit<T>>::put((min, max));
+			}
+		}
+	}
 
 	#[pallet::pallet]
 	#[pallet::storage_version(STORAGE_VERSION)]
@@ -363,70 +963,60 @@ pub mod pallet {
 	#[pallet::hooks]
 	impl<T: Config> Hooks<BlockNumberOf<T>> for Pallet<T> {
 		fn on_initialize(now: BlockNumberOf<T>) -> Weight {
-			let days = T::OneDay::get();
 			let mut weight: Weight = Weight::from_ref_time(0);
-			if now % days == 0u32.saturated_into() {
-				let (temp_weight, acc_list) = T::StorageHandle::frozen_task();
-				weight = weight.saturating_add(temp_weight);
-				let temp_acc_list: BoundedVec<AccountOf<T>, ConstU32<5000>> = 
-					acc_list.try_into().unwrap_or_default();
-				ClearUserList::<T>::put(temp_acc_list);
-				weight = weight.saturating_add(T::DbWeight::get().writes(1));
-			}
-			
-			let mut count: u32 = 0;
-			let acc_list = ClearUserList::<T>::get();
+			// The lease sweep runs as a periodic scheduler task, it is started once with a period of one day.
 			weight = weight.saturating_add(T::DbWeight::get().reads(1));
-			for acc in acc_list.iter() {
-				// todo! Delete in blocks, and delete a part of each block
-				if let Ok(mut file_info_list) = <UserHoldFileList<T>>::try_get(&acc) {
-					weight = weight.saturating_add(T::DbWeight::get().reads(1));
-					while let Some(file_info) = file_info_list.pop() {
-						count = count + 1;
-						if count == 300 {
-							<UserHoldFileList<T>>::insert(&acc, file_info_list);
-							return weight;
-						}
-						if let Ok(file) = <File<T>>::try_get(&file_info.file_hash) {
-							weight = weight.saturating_add(T::DbWeight::get().reads(1));
-							if file.owner.len() > 1 {
-								match Self::remove_file_owner(&file_info.file_hash, &acc, false) {
-									Ok(()) => weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 2)),
-									Err(e) => log::info!("delete file {:?} failed. error is: {:?}", e, file_info.file_hash),
-								};
-							 } else {
-								match Self::remove_file_last_owner(&file_info.file_hash, &acc, false) {
-									Ok(temp_weight) => weight = weight.saturating_add(temp_weight),
-									Err(e) => log::info!("delete file {:?} failed. error is: {:?}", e, file_info.file_hash),
-								};
-								if let Ok(temp_weight) = Self::remove_file_last_owner(&file_info.file_hash, &acc, false) {
-									weight = weight.saturating_add(temp_weight);
-								}
-							}
-						} else {
-							log::error!("space lease, delete file bug!");
-							log::error!("acc: {:?}, file_hash: {:?}", &acc, &file_info.file_hash);
-						}
-					}
+			if !<LeaseSweepPeriod<T>>::exists() {
+				match Self::schedule_lease_sweep(now, T::OneDay::get()) {
+					Ok(()) => weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 2)),
+					Err(e) => log::error!("schedule lease sweep failed: {:?}", e),
+				}
+			}
 
-					match T::StorageHandle::delete_user_space_storage(&acc) {
-						Ok(temp_weight) => weight = weight.saturating_add(temp_weight),
-						Err(e) => log::info!("delete user sapce error: {:?}, \n failed user: {:?}", e, acc),
-					}
+			// Queues left over by earlier blocks are handled first, oldest block first.
+			let budget = T::BlockWeights::get().max_block / QUEUE_WEIGHT_DIVISOR;
+			let mut block = <QueueCursor<T>>::take().unwrap_or(now);
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			loop {
+				if !Self::process_block_queues(block, budget, &mut weight) {
+					<QueueCursor<T>>::put(block);
+					break;
+				}
+				if block >= now {
+					break;
+				}
+				block = block.saturating_add(1u32.saturated_into());
+			}
 
-					ClearUserList::<T>::mutate(|target_list| {
-						target_list.retain(|temp_acc| temp_acc != acc);
-					});
+			weight = weight.saturating_add(Self::dispatch_recovery(now));
 
-					<UserHoldFileList<T>>::remove(&acc);
-					// todo! clear all
-					let _ = <Bucket<T>>::clear_prefix(&acc, 100000, None);
-					<UserBucketList<T>>::remove(&acc);
-				}
+			weight = weight.saturating_add(Self::settle_storage_orders(now));
+
+			let count = Self::clear_user_files();
+			weight = weight.saturating_add(<T as pallet::Config>::WeightInfo::clear_user_files(count));
+
+			weight = weight.saturating_add(T::DbWeight::get().reads(1));
+			if <UserFileMigrating<T>>::get() {
+				weight = weight.saturating_add(migrations::migrate_user_files::<T>());
 			}
-			
+
 			weight
 		}
+
+		// Runs on every node whose keystore holds a key of an authority.
+		fn offchain_worker(now: T::BlockNumber) {
+			if let Err(e) = Self::offchain_remind_invalid_files(now) {
+				match e {
+					OffchainErr::Ineligible | OffchainErr::Working => {},
+					_ => log::info!("offchain worker invalid file reminder failed: {:?}", e),
+				};
+			}
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
 	}
 
 	#[pallet::call]
@@ -441,35 +1031,63 @@ pub mod pallet {
 		///
 		/// The dispatch origin of this call must be _Signed_.
 		///
+		/// The fragments are placed by the policy of the bucket,
+		/// the fields set in `policy` take precedence over it.
+		///
 		/// Parameters:
 		/// - `file_hash`: Hash of the file to be uploaded.
 		/// - `file_name`: User defined file name.
+		/// - `policy`: Optional placement policy of the file.
 		#[pallet::call_index(0)]
 		#[transactional]
-		#[pallet::weight(<T as pallet::Config>::WeightInfo::upload_declaration())]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::upload_declaration().saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()))]
 		pub fn upload_declaration(
 			origin: OriginFor<T>,
-			file_hash: Hash,
+			file_hash: FileHash,
 			deal_info: BoundedVec<SegmentList<T>, T::SegmentCount>,
-			user_brief: UserBrief<T>,
+			mut user_brief: UserBrief<T>,
 			file_size: u128,
+			policy: Option<StoragePolicy>,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
 			// Check if you have operation permissions.
-			ensure!(Self::check_permission(sender.clone(), user_brief.user.clone()), Error::<T>::NoPermission);
+			ensure!(
+				Self::check_permission(sender.clone(), user_brief.user.clone())
+					|| Self::check_file_acl(&file_hash, &sender, &user_brief.user, ACL_WRITE),
+				Error::<T>::NoPermission
+			);
 			// Check file specifications.
 			ensure!(Self::check_file_spec(&deal_info), Error::<T>::SpecError);
+			Self::check_file_size(&user_brief.user, file_size, deal_info.len() as u32)?;
+			Self::record_block_declaration(&user_brief.user)?;
 			// Check whether the user-defined name meets the rules.
-			
 			let minimum = T::NameMinLength::get();
-			ensure!(user_brief.file_name.len() as u32 >= minimum, Error::<T>::SpecError);
-			ensure!(user_brief.bucket_name.len() as u32 >= minimum, Error::<T>::SpecError);
+			user_brief.file_name = Self::normalize_name(&user_brief.file_name, minimum)?;
+			user_brief.bucket_name = Self::normalize_name(&user_brief.bucket_name, minimum)?;
 
 			let needed_space = deal_info.len() as u128 * (SEGMENT_SIZE * 15 / 10);
-			ensure!(T::StorageHandle::get_user_avail_space(&user_brief.user)? > needed_space, Error::<T>::InsufficientAvailableSpace);		
-
-			if <File<T>>::contains_key(&file_hash) {
-				T::StorageHandle::update_user_space(&user_brief.user, 1, needed_space)?;
+			// A file that is already stored is not stored again, later owners are charged less.
+			let is_stored = <File<T>>::contains_key(&file_hash);
+			let charged_space = if is_stored { Self::deduplicated_space(needed_space) } else { needed_space };
+			// A file paid by a storage order is not charged to the package.
+			let is_ordered = match <StorageOrders<T>>::get(&user_brief.user, &file_hash) {
+				Some(order) => {
+					ensure!(order.start.is_none(), Error::<T>::IsOwned);
+					ensure!(needed_space <= order.space, Error::<T>::StorageOrderTooSmall);
+					true
+				},
+				None => {
+					ensure!(T::StorageHandle::get_user_avail_space(&user_brief.user)? > charged_space, Error::<T>::InsufficientAvailableSpace);
+					false
+				},
+			};
+			Self::record_declared_bytes(&user_brief.user, charged_space)?;
+
+			if is_stored {
+				if !is_ordered {
+					T::StorageHandle::update_user_space(&user_brief.user, 1, charged_space)?;
+				}
 
 				if <Bucket<T>>::contains_key(&user_brief.user, &user_brief.bucket_name) {
 						Self::add_file_to_bucket(&user_brief.user, &user_brief.bucket_name, &file_hash)?;
@@ -477,27 +1095,49 @@ pub mod pallet {
 						Self::create_bucket_helper(&user_brief.user, &user_brief.bucket_name, Some(file_hash))?;
 					}
 
-				Self::add_user_hold_fileslice(&user_brief.user, file_hash, needed_space)?;
+				if is_ordered {
+					Self::start_storage_order(&user_brief.user, &file_hash);
+				} else {
+					Self::add_user_hold_fileslice(&user_brief.user, file_hash, charged_space)?;
+				}
 
 				<File<T>>::try_mutate(&file_hash, |file_opt| -> DispatchResult {
 					let file = file_opt.as_mut().ok_or(Error::<T>::FileNonExistent)?;
 					file.owner.try_push(user_brief.clone()).map_err(|_e| Error::<T>::BoundedVecError)?;
 					Ok(())
 				})?;
+				Self::refresh_file_summary(&file_hash);
+				<FileRefCount<T>>::mutate(&file_hash, |count| *count = count.saturating_add(1));
 			} else {
-				T::StorageHandle::lock_user_space(&user_brief.user, needed_space)?;
+				if !is_ordered {
+					T::StorageHandle::lock_user_space(&user_brief.user, needed_space)?;
+				}
+				let policy = policy.unwrap_or_default()
+					.or(<BucketPolicy<T>>::get(&user_brief.user, &user_brief.bucket_name).unwrap_or_default());
+				Self::check_storage_policy(&policy)?;
 				// TODO! Replace the file_hash param
-				Self::generate_deal(file_hash.clone(), deal_info, user_brief.clone(), file_size)?;
+				Self::generate_deal(file_hash.clone(), deal_info, user_brief.clone(), file_size, policy)?;
+				Self::reserve_deal_deposit(&file_hash, &user_brief.user)?;
+				if needed_space >= T::AuctionThreshold::get() {
+					Self::start_deal_auction(&file_hash)?;
+				}
 			}
 
-			Self::deposit_event(Event::<T>::UploadDeclaration { operator: sender, owner: user_brief.user, deal_hash: file_hash });
+			Self::deposit_event(Event::<T>::UploadDeclaration {
+				operator: sender,
+				owner: user_brief.user,
+				deal_hash: file_hash,
+				file_size,
+				charged_space,
+				deduplicated: is_stored,
+			});
 
 			Ok(())
 		}
 		
 		#[pallet::call_index(1)]
 		#[transactional]
-		#[pallet::weight(1_000_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::deal_reassign_miner())]
 		pub fn deal_reassign_miner(
 			origin: OriginFor<T>,
 			deal_hash: Hash,
@@ -512,27 +1152,80 @@ pub mod pallet {
 					// unlock mienr space
 					for miner_task in &deal_info.assigned_miner {
 						let task_count = miner_task.fragment_list.len() as u128;
-						T::MinerControl::unlock_space(&miner_task.miner, FRAGMENT_SIZE * task_count)?;
+						T::MinerControl::cancel_space(&miner_task.miner, FRAGMENT_SIZE * task_count)?;
 					}
-					let miner_task_list = Self::random_assign_miner(&deal_info.needed_list)?;
+					let rate = T::StorageHandle::get_user_unit_price(&deal_info.user.user)?;
+					let policy = <DealPolicy<T>>::get(&deal_hash).unwrap_or_default();
+					let miner_task_list = Self::random_assign_miner(&deal_info.needed_list, rate, &policy)?;
 					deal_info.assigned_miner = miner_task_list;
 					deal_info.complete_list = Default::default();
 					deal_info.count = count;
-					Self::start_first_task(deal_hash.0.to_vec(), deal_hash, count + 1, life)?;
+					Self::clear_file_segments(&deal_hash);
+					deal_info.deadline = Self::start_first_task(deal_hash.0.to_vec(), deal_hash, count + 1, life)?;
 					Ok(())
 				})?;
 			} else {
-				let deal_info = <DealMap<T>>::try_get(&deal_hash).map_err(|_| Error::<T>::NonExistent)?;
-				let needed_space = Self::cal_file_size(deal_info.segment_list.len() as u128);
-				T::StorageHandle::unlock_user_space(&deal_info.user.user, needed_space)?;
+				Self::remove_deal(&deal_hash)?;
+			}
+
+			Ok(())
+		}
+		/// Force reassign a stuck deal.
+		///
+		/// Used by governance when the automatic reassignment fails to move a deal forward.
+		/// The deal is handed to `new_scheduler`, the miners are reassigned and the
+		/// reassignment counter is reset.
+		///
+		/// Parameters:
+		/// - `deal_hash`: The deal to reassign.
+		/// - `new_scheduler`: Controller account of the tee worker taking over the deal.
+		#[pallet::call_index(7)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::force_reassign_deal())]
+		pub fn force_reassign_deal(
+			origin: OriginFor<T>,
+			deal_hash: Hash,
+			new_scheduler: AccountOf<T>,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			ensure!(T::Scheduler::contains_scheduler(new_scheduler.clone()), Error::<T>::ScheduleNonExistent);
+
+			let (old_scheduler, deadline) = <DealMap<T>>::try_mutate(&deal_hash, |opt| -> Result<(AccountOf<T>, BlockNumberOf<T>), DispatchError> {
+				let deal_info = opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+				ensure!(deal_info.stage == 1, Error::<T>::StageError);
 				// unlock mienr space
-				for miner_task in deal_info.assigned_miner {
-					let count = miner_task.fragment_list.len() as u128;
-					T::MinerControl::unlock_space(&miner_task.miner, FRAGMENT_SIZE * count)?;
+				for miner_task in &deal_info.assigned_miner {
+					let task_count = miner_task.fragment_list.len() as u128;
+					T::MinerControl::cancel_space(&miner_task.miner, FRAGMENT_SIZE * task_count)?;
 				}
-				
-				<DealMap<T>>::remove(&deal_hash);
-			}
+				let rate = T::StorageHandle::get_user_unit_price(&deal_info.user.user)?;
+				let policy = <DealPolicy<T>>::get(&deal_hash).unwrap_or_default();
+				let miner_task_list = Self::random_assign_miner(&deal_info.needed_list, rate, &policy)?;
+				deal_info.assigned_miner = miner_task_list;
+				deal_info.complete_list = Default::default();
+				deal_info.count = 0;
+				Self::clear_file_segments(&deal_hash);
+
+				let result = T::FScheduler::cancel_named(deal_hash.0.to_vec()).map_err(|_| Error::<T>::Unexpected);
+				if let Err(_) = result {
+					log::info!("force reassign cancel schedule failed: {:?}", deal_hash.clone());
+				}
+				let space = Self::cal_file_size(deal_info.segment_list.len() as u128);
+				let life = space / TRANSFER_RATE + 1;
+				deal_info.deadline = Self::start_first_task(deal_hash.0.to_vec(), deal_hash, 1, life as u32)?;
+
+				let old_scheduler = sp_std::mem::replace(&mut deal_info.scheduler, new_scheduler.clone());
+				T::Scheduler::task_finished(&old_scheduler, 1);
+				T::Scheduler::task_assigned(&new_scheduler, 1);
+				Ok((old_scheduler, deal_info.deadline))
+			})?;
+
+			Self::deposit_event(Event::<T>::ForceReassignDeal {
+				deal_hash,
+				old_scheduler,
+				new_scheduler,
+				deadline,
+			});
 
 			Ok(())
 		}
@@ -553,241 +1246,1362 @@ pub mod pallet {
 		/// - `file_hash`: File hash, which is also the unique identifier of the file
 		#[pallet::call_index(2)]
 		#[transactional]
-		#[pallet::weight(<T as pallet::Config>::WeightInfo::ownership_transfer())]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::ownership_transfer().saturating_add(<T as pallet::Config>::WeightInfo::user_file_root().saturating_mul(2)))]
 		pub fn ownership_transfer(
 			origin: OriginFor<T>,
-			target_brief: UserBrief<T>,
-			file_hash: Hash,
+			mut target_brief: UserBrief<T>,
+			file_hash: FileHash,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::FileNonExistent)?;
-			//If the file does not exist, false will also be returned
-			ensure!(Self::check_is_file_owner(&sender, &file_hash), Error::<T>::NotOwner);
-			ensure!(!Self::check_is_file_owner(&target_brief.user, &file_hash), Error::<T>::IsOwned);
+			let file_hash: Hash = file_hash.into();
+			let minimum = T::NameMinLength::get();
+			target_brief.file_name = Self::normalize_name(&target_brief.file_name, minimum)?;
+			target_brief.bucket_name = Self::normalize_name(&target_brief.bucket_name, minimum)?;
+			let new_owner = target_brief.user.clone();
+			Self::transfer_file_owner(&sender, target_brief, &file_hash)?;
 
-			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
-			ensure!(<Bucket<T>>::contains_key(&target_brief.user, &target_brief.bucket_name), Error::<T>::NonExistent);
-			//Modify the space usage of target acc,
-			//and determine whether the space is enough to support transfer
-			let file_size = Self::cal_file_size(file.segment_list.len() as u128);
-			T::StorageHandle::update_user_space(&target_brief.user, 1, file_size)?;
-			//Increase the ownership of the file for target acc
-			<File<T>>::try_mutate(&file_hash, |file_opt| -> DispatchResult {
-				let file = file_opt.as_mut().ok_or(Error::<T>::FileNonExistent)?;
-				file.owner.try_push(target_brief.clone()).map_err(|_| Error::<T>::BoundedVecError)?;
-				Ok(())
+			Self::deposit_event(Event::<T>::FileOwnershipTransferred { owner: sender, new_owner, file_hash });
+
+			Ok(())
+		}
+		/// Upload info of stored file.
+		///
+		/// The dispatch origin of this call must be _Signed_.
+		///
+		/// The same file will only upload meta information once,
+		/// which will be uploaded by consensus.
+		///
+		/// Parameters:
+		/// - `file_hash`: The beneficiary related to signer account.
+		/// - `file_size`: File size calculated by consensus.
+		/// - `slice_info`: List of file slice information.
+		#[pallet::call_index(3)]
+		#[transactional]
+		#[pallet::weight(
+			<T as pallet::Config>::WeightInfo::upload(2)
+				.saturating_add(<T as pallet::Config>::WeightInfo::user_file_root().saturating_mul(deal_hash.len() as u64))
+		)]
+		pub fn transfer_report(
+			origin: OriginFor<T>,
+			deal_hash: Vec<Hash>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(deal_hash.len() < 5, Error::<T>::LengthExceedsLimit);
+			let mut failed_list: Vec<Hash> = Default::default();
+			for hash in deal_hash {
+				if !<DealMap<T>>::contains_key(&hash) {
+					failed_list.push(hash);
+					continue;
+				} else {
+					<DealMap<T>>::try_mutate(&hash, |deal_info_opt| -> DispatchResult {
+						// can use unwrap because there was a judgment above
+						let deal_info = deal_info_opt.as_mut().unwrap();
+						let mut task_miner_list: Vec<AccountOf<T>> = Default::default();
+						for miner_task in &deal_info.assigned_miner {
+							task_miner_list.push(miner_task.miner.clone());
+						}
+						if task_miner_list.contains(&sender) {
+							if !deal_info.complete_list.contains(&sender) {
+								deal_info.complete_list.try_push(sender.clone()).map_err(|_| Error::<T>::BoundedVecError)?;
+							}
+							// If it is the last submitter of the order.
+							if deal_info.complete_list.len() == deal_info.assigned_miner.len() {
+								deal_info.stage = 2;
+								Self::generate_file(
+									&hash,
+									deal_info.segment_list.clone(),
+									deal_info.assigned_miner.clone(),
+									deal_info.share_info.to_vec(),
+									deal_info.user.clone(),
+									FileState::Calculate,
+									deal_info.file_size,
+								)?;
+
+								let mut max_task_count = 0;
+								for miner_task in deal_info.assigned_miner.iter() {
+									let count = miner_task.fragment_list.len() as u32;
+									if count > max_task_count {
+										max_task_count = count;
+									}
+									// Miners need to report the replaced documents themselves. 
+									// If a challenge is triggered before the report is completed temporarily, 
+									// these documents to be replaced also need to be verified
+									<PendingReplacements<T>>::try_mutate(miner_task.miner.clone(), |pending_count| -> DispatchResult {
+										let pending_count_temp = pending_count.checked_add(count).ok_or(Error::<T>::Overflow)?;
+										*pending_count = pending_count_temp;
+										Ok(())
+									})?;
+								}	
+
+								let needed_space = Self::cal_file_size(deal_info.segment_list.len() as u128);
+								let is_ordered = <StorageOrders<T>>::contains_key(&deal_info.user.user, &hash);
+								if !is_ordered {
+									T::StorageHandle::unlock_and_used_user_space(&deal_info.user.user, needed_space)?;
+								}
+								T::StorageHandle::sub_total_idle_space(needed_space)?;
+								T::StorageHandle::add_total_service_space(needed_space)?;
+								let result = T::FScheduler::cancel_named(hash.0.to_vec()).map_err(|_| Error::<T>::Unexpected);
+								if let Err(_) = result {
+									log::info!("transfer report cancel schedule failed: {:?}", hash.clone());
+								}
+
+								let max_needed_cal_space = (max_task_count as u128) * FRAGMENT_SIZE;
+								let mut life: u32 = (max_needed_cal_space / TRANSFER_RATE + 1) as u32;
+								life = life + (max_needed_cal_space / CALCULATE_RATE + 1) as u32;
+
+								deal_info.deadline = Self::start_second_task(hash.0.to_vec(), hash, life)?;
+								if <Bucket<T>>::contains_key(&deal_info.user.user, &deal_info.user.bucket_name) {
+									Self::add_file_to_bucket(&deal_info.user.user, &deal_info.user.bucket_name, &hash)?;
+								} else {
+									Self::create_bucket_helper(&deal_info.user.user, &deal_info.user.bucket_name, Some(hash))?;
+								}
+
+								if !is_ordered {
+									Self::add_user_hold_fileslice(&deal_info.user.user, hash.clone(), needed_space)?;
+								}
+
+								Self::deposit_event(Event::<T>::StorageCompleted{
+									file_hash: hash,
+									owner: deal_info.user.user.clone(),
+									file_size: deal_info.file_size,
+									miners: deal_info.assigned_miner.iter().map(|task| task.miner.clone()).collect(),
+									deadline: deal_info.deadline,
+								});
+							}
+						} else {
+							failed_list.push(hash);
+						}
+
+						Ok(())
+					})?;
+				}
+			}
+
+			Self::deposit_event(Event::<T>::TransferReport{acc: sender, failed_list});
+			
+			Ok(())
+		}
+
+		#[pallet::call_index(4)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::calculate_end())]
+		pub fn calculate_end(
+			origin: OriginFor<T>,
+			deal_hash: Hash,
+		) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+
+			let deal_info = <DealMap<T>>::try_get(&deal_hash).map_err(|_| Error::<T>::NonExistent)?;
+			for miner_task in deal_info.assigned_miner {
+				let count = miner_task.fragment_list.len() as u32;
+				// Accumulate the number of fragments stored by each miner
+				T::MinerControl::confirm_space(&miner_task.miner, FRAGMENT_SIZE * count as u128)?;
+			}
+
+			// The file stays in calculation until the scheduler reported every segment.
+			if Self::check_segments_completed(&deal_hash, deal_info.segment_list.len() as u32) {
+				Self::activate_file(&deal_hash)?;
+			} else {
+				<DealMap<T>>::try_mutate(&deal_hash, |deal_opt| -> DispatchResult {
+					let deal_info = deal_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+					deal_info.stage = 3;
+					deal_info.deadline = Self::start_third_task(deal_hash.0.to_vec(), deal_hash)?;
+					Ok(())
+				})?;
+			}
+
+			Ok(())
+		}
+		/// Report a segment of a deal.
+		///
+		/// The dispatch origin of this call must be _Signed_ and be the scheduler assigned to the deal.
+		/// Each segment of the declaration is reported once, together with the miner receiving it.
+		/// The file becomes active when every segment is reported and the calculation has ended.
+		///
+		/// Parameters:
+		/// - `file_hash`: The deal hash.
+		/// - `index`: The position of the segment in the declaration.
+		/// - `miner`: The miner storing the segment.
+		/// - `segment_hash`: The segment hash, must match the declaration.
+		#[pallet::call_index(22)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::upload_segment())]
+		pub fn upload_segment(
+			origin: OriginFor<T>,
+			file_hash: FileHash,
+			index: u32,
+			miner: AccountOf<T>,
+			segment_hash: Hash,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let deal_info = <DealMap<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(deal_info.scheduler == sender, Error::<T>::NoPermission);
+
+			let segment = deal_info.segment_list.get(index as usize).ok_or(Error::<T>::SpecError)?;
+			ensure!(segment.hash == segment_hash, Error::<T>::SpecError);
+			ensure!(
+				deal_info.assigned_miner.iter().any(|miner_task| miner_task.miner == miner),
+				Error::<T>::NotQualified
+			);
+			ensure!(!<FileSegments<T>>::contains_key(&file_hash, index), Error::<T>::Existed);
+
+			let commitment = SegmentCommitment::<T> {
+				miner: miner.clone(),
+				segment_hash,
+				block: <frame_system::Pallet<T>>::block_number(),
+			};
+			<FileSegments<T>>::insert(&file_hash, index, commitment);
+
+			if deal_info.stage == 3 && Self::check_segments_completed(&file_hash, deal_info.segment_list.len() as u32) {
+				if let Err(_) = T::FScheduler::cancel_named(file_hash.0.to_vec()) {
+					log::info!("upload segment cancel schedule failed: {:?}", file_hash.clone());
+				}
+				Self::activate_file(&file_hash)?;
+			}
+
+			Self::deposit_event(Event::<T>::SegmentUploaded { scheduler: sender, file_hash, index, miner });
+
+			Ok(())
+		}
+		/// Remove a deal whose segments were not all reported in time.
+		///
+		/// Dispatched by the scheduler `SEGMENT_REPORT_PERIOD` blocks after the calculation
+		/// of the deal ended without every segment reported. The file is deleted, the space
+		/// of the owners and of the miners is released and the declaration deposit is refunded.
+		///
+		/// Parameters:
+		/// - `deal_hash`: The deal hash.
+		#[pallet::call_index(52)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::segment_report_timeout().saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()))]
+		pub fn segment_report_timeout(origin: OriginFor<T>, deal_hash: Hash) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+
+			let deal_info = <DealMap<T>>::try_get(&deal_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(deal_info.stage == 3, Error::<T>::StageError);
+			Self::remove_calculated_deal(&deal_hash)?;
+
+			Self::deposit_event(Event::<T>::SegmentReportTimeout {
+				file_hash: deal_hash,
+				owner: deal_info.user.user,
+				scheduler: deal_info.scheduler,
+			});
+
+			Ok(())
+		}
+
+		#[pallet::call_index(5)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::replace_file_report(filler.len() as u32))]
+		pub fn replace_file_report(
+			origin: OriginFor<T>,
+			filler: Vec<Hash>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(filler.len() <= 30, Error::<T>::LengthExceedsLimit);
+			let pending_count = <PendingReplacements<T>>::get(&sender);
+			ensure!(filler.len() as u32 <= pending_count, Error::<T>::LengthExceedsLimit);
+
+			let mut count: u32 = 0;
+			for filler_hash in filler.iter() {
+				if <FillerMap<T>>::contains_key(&sender, filler_hash) {
+					count += 1;
+					<FillerMap<T>>::remove(&sender, filler_hash);
+					Self::unindex_filler(&sender, filler_hash);
+				} else {
+					log::info!("filler nonexist!");
+				}
+			}
+
+			<PendingReplacements<T>>::mutate(&sender, |pending_count| -> DispatchResult {
+				let pending_count_temp = pending_count.checked_sub(count).ok_or(Error::<T>::Overflow)?;
+				*pending_count = pending_count_temp;
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::ReplaceFiller{ acc: sender, filler_list: filler });
+
+			Ok(())
+		}
+
+		#[pallet::call_index(6)]
+		#[transactional]
+		#[pallet::weight(
+			<T as pallet::Config>::WeightInfo::delete_file()
+				.saturating_add(<T as pallet::Config>::WeightInfo::user_file_root())
+				.saturating_mul(file_hash_list.len() as u64)
+		)]
+		pub fn delete_file(origin: OriginFor<T>, owner: AccountOf<T>, file_hash_list: Vec<FileHash>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash_list: Vec<Hash> = file_hash_list.into_iter().map(Into::into).collect();
+			// Check if you have operation permissions.
+			let is_authorized = Self::check_permission(sender.clone(), owner.clone());
+			ensure!(file_hash_list.len() < 10, Error::<T>::LengthExceedsLimit);
+
+			for file_hash in file_hash_list.iter() {
+				Self::delete_owned_file(&sender, &owner, is_authorized, file_hash)?;
+			}
+
+			Self::deposit_event(Event::<T>::DeleteFile{ operator: sender, owner, file_hash_list });
+
+			Ok(())
+		}
+		/// Delete many files of an owner, keeping the files that cannot be deleted.
+		///
+		/// The dispatch origin of this call must be _Signed_ and allowed to delete the files.
+		/// Unlike `delete_file` a file that fails does not revert the others,
+		/// the event lists the deleted files and the error of each kept file.
+		/// The weight of the kept files is refunded.
+		///
+		/// Parameters:
+		/// - `owner`: The owner of the files.
+		/// - `file_hash_list`: The files to delete, at most `BatchDeleteLimit`.
+		#[pallet::call_index(39)]
+		#[pallet::weight(
+			<T as pallet::Config>::WeightInfo::delete_file()
+				.saturating_add(<T as pallet::Config>::WeightInfo::user_file_root())
+				.saturating_mul(file_hash_list.len() as u64)
+		)]
+		pub fn batch_delete_file(
+			origin: OriginFor<T>,
+			owner: AccountOf<T>,
+			file_hash_list: Vec<FileHash>,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+			let file_hash_list: Vec<Hash> = file_hash_list.into_iter().map(Into::into).collect();
+			ensure!(
+				file_hash_list.len() <= T::BatchDeleteLimit::get() as usize,
+				Error::<T>::LengthExceedsLimit
+			);
+			let is_authorized = Self::check_permission(sender.clone(), owner.clone());
+
+			let mut deleted: Vec<Hash> = Default::default();
+			let mut failed: Vec<(Hash, DispatchError)> = Default::default();
+			for file_hash in file_hash_list.iter() {
+				// Each file is deleted in its own storage layer, a failure only reverts that file.
+				let result = with_storage_layer(|| Self::delete_owned_file(&sender, &owner, is_authorized, file_hash));
+				match result {
+					Ok(()) => deleted.push(file_hash.clone()),
+					Err(e) => failed.push((file_hash.clone(), e)),
+				}
+			}
+
+			let actual_weight = <T as pallet::Config>::WeightInfo::delete_file()
+				.saturating_add(<T as pallet::Config>::WeightInfo::user_file_root())
+				.saturating_mul(deleted.len() as u64)
+				.saturating_add(T::DbWeight::get().reads(2).saturating_mul(failed.len() as u64));
+
+			Self::deposit_event(Event::<T>::BatchDeleteFile { operator: sender, owner, deleted, failed });
+
+			Ok(Some(actual_weight).into())
+		}
+		/// Acknowledge that an invalid file was dropped.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the miner the file was invalidated for.
+		/// Files not cleared within `InvalidFileGracePeriod` are removed from the list
+		/// and the miner is punished.
+		///
+		/// Parameters:
+		/// - `file_hash`: The hash of the invalid fragment.
+		#[pallet::call_index(40)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::clear_invalid_file())]
+		pub fn clear_invalid_file(origin: OriginFor<T>, file_hash: FileHash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+
+			<InvalidFile<T>>::try_mutate(&sender, |hash_list| -> DispatchResult {
+				let index = hash_list.iter().position(|hash| *hash == file_hash).ok_or(Error::<T>::NonExistent)?;
+				hash_list.remove(index);
+				Ok(())
+			})?;
+			<InvalidFileDeadline<T>>::remove(&sender, &file_hash);
+			<InvalidFileReminders<T>>::remove(&sender, &file_hash);
+
+			Self::deposit_event(Event::<T>::ClearInvalidFile { acc: sender, file_hash });
+			Ok(())
+		}
+		/// Offer the ownership of a file to another account.
+		///
+		/// The dispatch origin of this call must be _Signed_ and hold the file.
+		/// Nothing is charged to the new owner until it calls `accept_transfer`,
+		/// offering the file again to the same account replaces the previous offer.
+		///
+		/// Parameters:
+		/// - `file_hash`: The transferred file.
+		/// - `new_owner`: The account offered the ownership.
+		#[pallet::call_index(41)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::transfer_file())]
+		pub fn transfer_file(origin: OriginFor<T>, file_hash: FileHash, new_owner: AccountOf<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::FileNonExistent)?;
+			ensure!(file.owner.iter().any(|user_brief| user_brief.user == sender), Error::<T>::NotOwner);
+			ensure!(!file.owner.iter().any(|user_brief| user_brief.user == new_owner), Error::<T>::IsOwned);
+			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
+			ensure!(!<StorageOrders<T>>::contains_key(&sender, &file_hash), Error::<T>::OrderedFile);
+
+			<PendingTransfer<T>>::insert(&file_hash, &new_owner, sender.clone());
+
+			Self::deposit_event(Event::<T>::FileTransferRequested { owner: sender, new_owner, file_hash });
+
+			Ok(())
+		}
+		/// Accept the ownership of a file offered by `transfer_file`.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the account the file was offered to.
+		/// The caller keeps the file name and the bucket name of the owner,
+		/// the bucket is created if the caller has none of that name.
+		/// The file is charged to the package of the caller, which must have the space left,
+		/// and released from the package of the owner.
+		///
+		/// Parameters:
+		/// - `file_hash`: The accepted file.
+		#[pallet::call_index(53)]
+		#[transactional]
+		#[pallet::weight(
+			<T as pallet::Config>::WeightInfo::ownership_transfer()
+				.saturating_add(<T as pallet::Config>::WeightInfo::create_bucket())
+				.saturating_add(<T as pallet::Config>::WeightInfo::user_file_root().saturating_mul(2))
+		)]
+		pub fn accept_transfer(origin: OriginFor<T>, file_hash: FileHash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let owner = <PendingTransfer<T>>::take(&file_hash, &sender).ok_or(Error::<T>::TransferNotPending)?;
+			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::FileNonExistent)?;
+			let user_brief = file.owner
+				.iter()
+				.find(|user_brief| user_brief.user == owner)
+				.ok_or(Error::<T>::NotOwner)?;
+
+			let target_brief = UserBrief::<T> {
+				user: sender.clone(),
+				file_name: user_brief.file_name.clone(),
+				bucket_name: user_brief.bucket_name.clone(),
+			};
+			if !<Bucket<T>>::contains_key(&sender, &target_brief.bucket_name) {
+				Self::create_bucket_helper(&sender, &target_brief.bucket_name, None)?;
+			}
+			Self::transfer_file_owner(&owner, target_brief, &file_hash)?;
+
+			Self::deposit_event(Event::<T>::FileOwnershipTransferred { owner, new_owner: sender, file_hash });
+
+			Ok(())
+		}
+		/// Grant another account access to a file.
+		///
+		/// The dispatch origin of this call must be _Signed_ and hold the file.
+		/// Granting again overwrites the previous permission of the account.
+		/// The read permission is not enforced on chain, it is published for gateways.
+		///
+		/// Parameters:
+		/// - `file_hash`: The file to share.
+		/// - `acc`: The account receiving the access.
+		/// - `permission`: Bit set of read(1), write(2) and delete(4).
+		#[pallet::call_index(10)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::grant_access())]
+		pub fn grant_access(
+			origin: OriginFor<T>,
+			file_hash: FileHash,
+			acc: AccountOf<T>,
+			permission: u8,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			ensure!(permission != 0 && permission & !ACL_ALL == 0, Error::<T>::InvalidPermission);
+			ensure!(Self::check_is_file_owner(&sender, &file_hash), Error::<T>::NotOwner);
+			ensure!(sender != acc, Error::<T>::IsOwned);
+
+			<FileAcl<T>>::insert(&file_hash, &acc, FileAclInfo::<T> { grantor: sender.clone(), permission });
+
+			Self::deposit_event(Event::<T>::GrantAccess { owner: sender, file_hash, acc, permission });
+
+			Ok(())
+		}
+		/// Revoke the access of an account to a file.
+		///
+		/// The dispatch origin of this call must be _Signed_ and be the grantor of the access.
+		///
+		/// Parameters:
+		/// - `file_hash`: The shared file.
+		/// - `acc`: The account losing the access.
+		#[pallet::call_index(20)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::revoke_access())]
+		pub fn revoke_access(
+			origin: OriginFor<T>,
+			file_hash: FileHash,
+			acc: AccountOf<T>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let acl = <FileAcl<T>>::try_get(&file_hash, &acc).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(acl.grantor == sender, Error::<T>::NotOwner);
+
+			<FileAcl<T>>::remove(&file_hash, &acc);
+
+			Self::deposit_event(Event::<T>::RevokeAccess { owner: sender, file_hash, acc });
+
+			Ok(())
+		}
+		/// Opt a file in or out of public data availability sampling.
+		///
+		/// The dispatch origin of this call must be _Signed_ and hold the file.
+		/// Once enabled, light clients can sample the fragments of the file through the audit pallet.
+		///
+		/// Parameters:
+		/// - `file_hash`: The file of the public dataset.
+		/// - `enabled`: Whether sampling is allowed.
+		#[pallet::call_index(21)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_public_dataset())]
+		pub fn set_public_dataset(origin: OriginFor<T>, file_hash: FileHash, enabled: bool) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
+			ensure!(Self::check_is_file_owner(&sender, &file_hash), Error::<T>::NotOwner);
+
+			if enabled {
+				<PublicDataset<T>>::insert(&file_hash, true);
+			} else {
+				<PublicDataset<T>>::remove(&file_hash);
+			}
+
+			Self::deposit_event(Event::<T>::PublicDataset { owner: sender, file_hash, enabled });
+
+			Ok(())
+		}
+		/// Link a new version of a file to its predecessor.
+		///
+		/// The dispatch origin of this call must be _Signed_ and hold both files.
+		/// The history of `file_hash` moves to `new_hash`, when it is full the oldest version is unlinked.
+		///
+		/// Parameters:
+		/// - `file_hash`: The latest version of the file.
+		/// - `new_hash`: The uploaded file that becomes the latest version.
+		#[pallet::call_index(23)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::update_file())]
+		pub fn update_file(origin: OriginFor<T>, file_hash: FileHash, new_hash: FileHash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let new_hash: Hash = new_hash.into();
+			ensure!(file_hash != new_hash, Error::<T>::VersionConflict);
+			ensure!(Self::check_is_file_owner(&sender, &file_hash), Error::<T>::NotOwner);
+			ensure!(Self::check_is_file_owner(&sender, &new_hash), Error::<T>::NotOwner);
+			let file = <File<T>>::try_get(&new_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
+			ensure!(!<FileVersions<T>>::contains_key(&sender, &new_hash), Error::<T>::VersionConflict);
+
+			let mut history = <FileVersions<T>>::take(&sender, &file_hash);
+			ensure!(!history.contains(&new_hash), Error::<T>::VersionConflict);
+			if history.len() as u32 >= T::VersionLimit::get() {
+				history.remove(0);
+			}
+			history.try_push(file_hash).map_err(|_| Error::<T>::BoundedVecError)?;
+			let version = history.len() as u32;
+			<FileVersions<T>>::insert(&sender, &new_hash, history);
+
+			Self::deposit_event(Event::<T>::FileVersionUpdated { acc: sender, file_hash, new_hash, version });
+
+			Ok(())
+		}
+		/// Roll a file back to one of its history versions.
+		///
+		/// The dispatch origin of this call must be _Signed_ and hold both files.
+		/// The versions newer than `target` are unlinked, but the files themselves are kept.
+		///
+		/// Parameters:
+		/// - `file_hash`: The latest version of the file.
+		/// - `target`: The history version that becomes the latest version.
+		#[pallet::call_index(24)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::rollback_version())]
+		pub fn rollback_version(origin: OriginFor<T>, file_hash: FileHash, target: FileHash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let target: Hash = target.into();
+			ensure!(Self::check_is_file_owner(&sender, &file_hash), Error::<T>::NotOwner);
+			ensure!(Self::check_is_file_owner(&sender, &target), Error::<T>::NotOwner);
+
+			let mut history = <FileVersions<T>>::get(&sender, &file_hash);
+			let index = history.iter().position(|hash| hash == &target).ok_or(Error::<T>::NonExistent)?;
+			history.truncate(index);
+
+			<FileVersions<T>>::remove(&sender, &file_hash);
+			if !history.is_empty() {
+				<FileVersions<T>>::insert(&sender, &target, history);
+			}
+
+			Self::deposit_event(Event::<T>::FileVersionRollback { acc: sender, file_hash, target });
+
+			Ok(())
+		}
+		/// Correct the used space of a user to the size of the files it holds.
+		///
+		/// The dispatch origin of this call must be _Signed_, anyone can audit any user.
+		/// The used space is only corrected when the difference exceeds the tolerance,
+		/// and not while the file lists are migrated, as the file tries are incomplete until then.
+		///
+		/// Parameters:
+		/// - `acc`: The user to audit.
+		#[pallet::call_index(25)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::audit_user_space())]
+		pub fn audit_user_space(origin: OriginFor<T>, acc: AccountOf<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(!<UserFileMigrating<T>>::get(), Error::<T>::Migrating);
+
+			let recorded = T::StorageHandle::get_user_used_space(&acc)?;
+			let actual = <UserFileIndex<T>>::get(&acc).map(|index| index.total_size).unwrap_or(0);
+
+			let diff = if recorded > actual { recorded - actual } else { actual - recorded };
+			if diff > SPACE_AUDIT_TOLERANCE {
+				T::StorageHandle::correct_user_used_space(&acc, actual)?;
+
+				Self::deposit_event(Event::<T>::UserSpaceCorrected { operator: sender, acc, recorded, actual });
+			}
+
+			Ok(())
+		}
+		/// Check the leases queued since the last sweep and collect the users whose files are cleared.
+		///
+		/// Dispatched by the periodic scheduler task, the files are deleted in the following blocks.
+		#[pallet::call_index(26)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::lease_sweep(LEASE_SWEEP_LIMIT))]
+		pub fn lease_sweep(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			let _ = ensure_root(origin)?;
+
+			let (weight, acc_list) = T::StorageHandle::frozen_task();
+			// Failing here would roll back the slot advanced by the storage handler and
+			// sweep the same leases again, so users over the list limit are skipped.
+			let mut count: u32 = 0;
+			ClearUserList::<T>::mutate(|target_list| {
+				for acc in acc_list {
+					if target_list.contains(&acc) {
+						continue;
+					}
+					if target_list.try_push(acc.clone()).is_err() {
+						log::warn!("clear user list is full, skip user: {:?}", acc);
+						continue;
+					}
+					count = count.saturating_add(1);
+				}
+			});
+
+			let actual_weight = <T as pallet::Config>::WeightInfo::lease_sweep(count)
+				.saturating_add(weight);
+			Ok(Some(actual_weight).into())
+		}
+		/// Change the period of the lease sweep task.
+		///
+		/// The dispatch origin of this call must be `ForceOrigin`.
+		/// The task is rescheduled, its next run is at the next multiple of `period`.
+		///
+		/// Parameters:
+		/// - `period`: Blocks between two sweeps.
+		#[pallet::call_index(27)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_lease_sweep_period())]
+		pub fn set_lease_sweep_period(origin: OriginFor<T>, period: BlockNumberOf<T>) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			ensure!(period != 0u32.saturated_into(), Error::<T>::IsZero);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			Self::schedule_lease_sweep(now, period)?;
+
+			Self::deposit_event(Event::<T>::LeaseSweepPeriodSet { period });
+
+			Ok(())
+		}
+		/// Sell the download of a file.
+		///
+		/// The dispatch origin of this call must be _Signed_ and hold the file.
+		///
+		/// Parameters:
+		/// - `file_hash`: The file to sell.
+		/// - `price`: Fee paid by each buyer, `None` stops the sale.
+		#[pallet::call_index(34)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_download_price())]
+		pub fn set_download_price(
+			origin: OriginFor<T>,
+			file_hash: FileHash,
+			price: Option<BalanceOf<T>>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			ensure!(Self::check_is_file_owner(&sender, &file_hash), Error::<T>::NotOwner);
+
+			match price {
+				Some(fee) => <DownloadPrice<T>>::insert(&file_hash, &sender, fee),
+				None => <DownloadPrice<T>>::remove(&file_hash, &sender),
+			}
+
+			Self::deposit_event(Event::<T>::DownloadPriceSet { owner: sender, file_hash, price });
+
+			Ok(())
+		}
+		/// Pay the download fee of a file.
+		///
+		/// The fee is split between the owner, the miners storing the file and the treasury,
+		/// according to `OwnerShare` and `MinerShare`.
+		///
+		/// Parameters:
+		/// - `file_hash`: The file to buy.
+		/// - `owner`: The holder selling the file.
+		#[pallet::call_index(35)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::buy_file())]
+		pub fn buy_file(origin: OriginFor<T>, file_hash: FileHash, owner: AccountOf<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			ensure!(sender != owner, Error::<T>::IsOwned);
+			ensure!(!<FileBuyer<T>>::contains_key(&file_hash, &sender), Error::<T>::Existed);
+			let price = <DownloadPrice<T>>::try_get(&file_hash, &owner).map_err(|_| Error::<T>::NonExistent)?;
+			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
+
+			Self::split_download_fee(&sender, &owner, &file, price)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			<FileBuyer<T>>::insert(&file_hash, &sender, now);
+
+			Self::deposit_event(Event::<T>::BuyFile { buyer: sender, owner, file_hash, price });
+
+			Ok(())
+		}
+		/// Report a retrieval of a file, counted into the popularity of the file.
+		///
+		/// The dispatch origin of this call must be _Signed_ by a holder or buyer of the file,
+		/// or by any account for a public dataset.
+		/// Each account counts once per file and era, up to `MaxReceiptsPerEra` files per era,
+		/// and a file gains at most `MaxPopularityGain` per era.
+		///
+		/// Parameters:
+		/// - `file_hash`: The retrieved file.
+		#[pallet::call_index(36)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::submit_retrieval_receipt())]
+		pub fn submit_retrieval_receipt(origin: OriginFor<T>, file_hash: FileHash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
+			ensure!(
+				<PublicDataset<T>>::get(&file_hash)
+					|| Self::check_is_file_owner(&sender, &file_hash)
+					|| <FileBuyer<T>>::contains_key(&file_hash, &sender),
+				Error::<T>::NoPermission
+			);
+
+			let era = Self::popularity_era();
+			ensure!(<RetrievalReceipts<T>>::get(&file_hash, &sender) != Some(era), Error::<T>::Existed);
+			<ReceiptCount<T>>::try_mutate(&sender, |(cur_era, count)| -> DispatchResult {
+				if *cur_era != era {
+					*cur_era = era;
+					*count = 0;
+				}
+				ensure!(*count < T::MaxReceiptsPerEra::get(), Error::<T>::ReceiptLimitExceeded);
+				*count = count.saturating_add(1);
+				Ok(())
 			})?;
-			//Add files to the bucket of target acc
-			<Bucket<T>>::try_mutate(
-				&target_brief.user,
-				&target_brief.bucket_name,
-				|bucket_info_opt| -> DispatchResult {
-					let bucket_info = bucket_info_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
-					bucket_info.object_list.try_push(file_hash.clone()).map_err(|_| Error::<T>::LengthExceedsLimit)?;
-					Ok(())
+			<RetrievalReceipts<T>>::insert(&file_hash, &sender, era);
+
+			let score = Self::add_popularity(&file_hash, era);
+
+			Self::deposit_event(Event::<T>::RetrievalReceipt { acc: sender, file_hash, score });
+
+			Ok(())
+		}
+		/// Commit a sealed bid to the auction of a large deal.
+		///
+		/// The dispatch origin of this call must be _Signed_ by a positive miner,
+		/// during the commit phase of the auction.
+		///
+		/// Parameters:
+		/// - `deal_hash`: The deal being auctioned.
+		/// - `commitment`: blake2_256 of the SCALE encoded `(miner, price, salt)`.
+		#[pallet::call_index(37)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::commit_deal_bid())]
+		pub fn commit_deal_bid(origin: OriginFor<T>, deal_hash: Hash, commitment: [u8; 32]) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(T::MinerControl::is_positive(&sender)?, Error::<T>::NotQualified);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			<DealAuctions<T>>::try_mutate(&deal_hash, |opt| -> DispatchResult {
+				let auction = opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+				ensure!(now < auction.commit_end, Error::<T>::AuctionPhaseError);
+				ensure!(!auction.commitments.iter().any(|(miner, _)| miner == &sender), Error::<T>::Existed);
+				auction.commitments.try_push((sender.clone(), commitment)).map_err(|_| Error::<T>::BoundedVecError)?;
+				Ok(())
 			})?;
-			//Increase the corresponding space usage for target acc
-			Self::add_user_hold_fileslice(
-				&target_brief.user,
-				file_hash.clone(),
-				file_size,
-			)?;
-			//Clean up the file holding information of the original user
+
+			Self::deposit_event(Event::<T>::DealBidCommitted { miner: sender, deal_hash });
+
+			Ok(())
+		}
+		/// Reveal the bid committed to the auction of a large deal.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the miner that committed,
+		/// during the reveal phase of the auction.
+		///
+		/// Parameters:
+		/// - `deal_hash`: The deal being auctioned.
+		/// - `price`: Monthly price asked for storing the deal.
+		/// - `salt`: The salt used in the commitment.
+		#[pallet::call_index(38)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::reveal_deal_bid())]
+		pub fn reveal_deal_bid(origin: OriginFor<T>, deal_hash: Hash, price: BalanceOf<T>, salt: [u8; 32]) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			<DealAuctions<T>>::try_mutate(&deal_hash, |opt| -> DispatchResult {
+				let auction = opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+				ensure!(now >= auction.commit_end && now < auction.reveal_end, Error::<T>::AuctionPhaseError);
+				let (_, commitment) = auction.commitments
+					.iter()
+					.find(|(miner, _)| miner == &sender)
+					.ok_or(Error::<T>::NonExistent)?;
+				let expected = sp_io::hashing::blake2_256(&(sender.clone(), price, salt).encode());
+				ensure!(&expected == commitment, Error::<T>::BidMismatch);
+				ensure!(!auction.bids.iter().any(|(miner, _)| miner == &sender), Error::<T>::Existed);
+				auction.bids.try_push((sender.clone(), price)).map_err(|_| Error::<T>::BoundedVecError)?;
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::DealBidRevealed { miner: sender, deal_hash, price });
+
+			Ok(())
+		}
+		/// Request the deletion of a file after the grace period.
+		///
+		/// The dispatch origin of this call must be _Signed_ with the same permission as `delete_file`.
+		/// The file is deleted by the block hook once `DeleteGracePeriod` is over,
+		/// until then the request can be cancelled with `cancel_delete`.
+		///
+		/// Parameters:
+		/// - `owner`: The holder of the file.
+		/// - `file_hash`: The file to delete.
+		#[pallet::call_index(32)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::request_delete())]
+		pub fn request_delete(origin: OriginFor<T>, owner: AccountOf<T>, file_hash: FileHash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			ensure!(
+				Self::check_permission(sender.clone(), owner.clone())
+					|| Self::check_file_acl(&file_hash, &sender, &owner, ACL_DELETE),
+				Error::<T>::NoPermission
+			);
+			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(file.stat != FileState::Calculate, Error::<T>::Calculate);
+			ensure!(Self::check_is_file_owner(&owner, &file_hash), Error::<T>::NotOwner);
+			ensure!(!<PendingDelete<T>>::contains_key(&owner, &file_hash), Error::<T>::Existed);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let mut finalize = now.checked_add(&T::DeleteGracePeriod::get()).ok_or(Error::<T>::Overflow)?;
+			// Spill over to the next blocks when the queue of the block is full.
+			let mut queued = false;
+			for _ in 0..10 {
+				queued = <DeleteQueue<T>>::mutate(&finalize, |queue| queue.try_push((owner.clone(), file_hash)).is_ok());
+				if queued {
+					break;
+				}
+				finalize = finalize.checked_add(&1u32.saturated_into()).ok_or(Error::<T>::Overflow)?;
+			}
+			ensure!(queued, Error::<T>::BoundedVecError);
+
+			<PendingDelete<T>>::insert(&owner, &file_hash, finalize);
+
+			Self::deposit_event(Event::<T>::DeleteRequested { operator: sender, owner, file_hash, finalize });
+
+			Ok(())
+		}
+		/// Cancel a requested deletion before the grace period is over.
+		///
+		/// The dispatch origin of this call must be _Signed_ with the same permission as `delete_file`.
+		///
+		/// Parameters:
+		/// - `owner`: The holder of the file.
+		/// - `file_hash`: The file to keep.
+		#[pallet::call_index(33)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::cancel_delete())]
+		pub fn cancel_delete(origin: OriginFor<T>, owner: AccountOf<T>, file_hash: FileHash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			ensure!(
+				Self::check_permission(sender.clone(), owner.clone())
+					|| Self::check_file_acl(&file_hash, &sender, &owner, ACL_DELETE),
+				Error::<T>::NoPermission
+			);
+			ensure!(<PendingDelete<T>>::contains_key(&owner, &file_hash), Error::<T>::NonExistent);
+
+			<PendingDelete<T>>::remove(&owner, &file_hash);
+
+			Self::deposit_event(Event::<T>::DeleteCancelled { operator: sender, owner, file_hash });
+
+			Ok(())
+		}
+		/// Request a mirror of a file on a partner network.
+		///
+		/// The dispatch origin of this call must be _Signed_ and hold the file.
+		/// The copy is made off chain by a gateway, requesting again resets the confirmation.
+		///
+		/// Parameters:
+		/// - `file_hash`: The file to mirror.
+		/// - `network`: Name of the partner network.
+		#[pallet::call_index(30)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::request_mirror())]
+		pub fn request_mirror(
+			origin: OriginFor<T>,
+			file_hash: FileHash,
+			network: BoundedVec<u8, T::NameStrLimit>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
+			ensure!(Self::check_is_file_owner(&sender, &file_hash), Error::<T>::NotOwner);
+			let network = Self::normalize_name(&network, 1)?;
+
+			let mirror = MirrorInfo::<T> {
+				network: network.clone(),
+				locator: None,
+				request_block: <frame_system::Pallet<T>>::block_number(),
+			};
+			<FileMirror<T>>::insert(&file_hash, &sender, mirror);
+
+			Self::deposit_event(Event::<T>::MirrorRequested { owner: sender, file_hash, network });
+
+			Ok(())
+		}
+		/// Confirm the mirror of a file on the partner network.
+		///
+		/// The dispatch origin of this call must be _Signed_ by a gateway authorized by the owner.
+		///
+		/// Parameters:
+		/// - `owner`: The holder that requested the mirror.
+		/// - `file_hash`: The mirrored file.
+		/// - `locator`: Hash of the locator of the copy on the partner network.
+		#[pallet::call_index(31)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::confirm_mirror())]
+		pub fn confirm_mirror(
+			origin: OriginFor<T>,
+			owner: AccountOf<T>,
+			file_hash: FileHash,
+			locator: Hash,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			ensure!(Self::check_permission(sender.clone(), owner.clone()), Error::<T>::NoPermission);
+
+			<FileMirror<T>>::try_mutate(&file_hash, &owner, |mirror_opt| -> DispatchResult {
+				let mirror = mirror_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+				mirror.locator = Some(locator);
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::MirrorConfirmed { operator: sender, owner, file_hash, locator });
+
+			Ok(())
+		}
+		/// Create a share link through which gateways serve the file to anyone holding its id.
+		///
+		/// The dispatch origin of this call must be _Signed_ and hold the file.
+		/// The share is removed at `expiry` or once `max_downloads` downloads were served,
+		/// a file has at most `FILE_SHARE_LIMIT` shares.
+		///
+		/// Parameters:
+		/// - `file_hash`: The file to share.
+		/// - `expiry`: The block at which the share expires.
+		/// - `max_downloads`: The downloads the share allows.
+		#[pallet::call_index(42)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::create_share())]
+		pub fn create_share(
+			origin: OriginFor<T>,
+			file_hash: FileHash,
+			expiry: BlockNumberOf<T>,
+			max_downloads: u32,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
 			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
+			ensure!(Self::check_is_file_owner(&sender, &file_hash), Error::<T>::NotOwner);
+			ensure!(max_downloads > 0, Error::<T>::IsZero);
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(expiry > now, Error::<T>::Expired);
+
+			let nonce = <ShareNonce<T>>::mutate(&sender, |nonce| {
+				*nonce = nonce.saturating_add(1);
+				*nonce
+			});
+			let share_id = Self::generate_share_id(&sender, &file_hash, nonce);
+			<FileShares<T>>::try_mutate(&file_hash, |shares| -> DispatchResult {
+				ensure!(!shares.iter().any(|share| share.share_id == share_id), Error::<T>::Existed);
+				shares
+					.try_push(ShareInfo::<T> { share_id, owner: sender.clone(), expiry, max_downloads, downloads: 0 })
+					.map_err(|_| Error::<T>::StorageLimitReached)?;
+				Ok(())
+			})?;
+			<ShareExpiryQueue<T>>::try_mutate(&expiry, |queue| queue.try_push((file_hash, share_id)))
+				.map_err(|_| Error::<T>::StorageLimitReached)?;
+
+			Self::deposit_event(Event::<T>::ShareCreated { owner: sender, file_hash, share_id, expiry, max_downloads });
+
+			Ok(())
+		}
+		/// Revoke a share link before it expires.
+		///
+		/// The dispatch origin of this call must be _Signed_ and be the creator of the share.
+		///
+		/// Parameters:
+		/// - `file_hash`: The shared file.
+		/// - `share_id`: The id of the share.
+		#[pallet::call_index(43)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::revoke_share())]
+		pub fn revoke_share(origin: OriginFor<T>, file_hash: FileHash, share_id: [u8; 32]) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			<FileShares<T>>::try_mutate_exists(&file_hash, |shares_opt| -> DispatchResult {
+				let shares = shares_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+				let index = shares.iter().position(|share| share.share_id == share_id).ok_or(Error::<T>::NonExistent)?;
+				ensure!(shares[index].owner == sender, Error::<T>::NotOwner);
+				shares.remove(index);
+				if shares.is_empty() {
+					*shares_opt = None;
+				}
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::ShareRevoked { owner: sender, file_hash, share_id });
 
-			let _ = Self::delete_user_file(&file_hash, &sender, &file)?;
+			Ok(())
+		}
+		/// Count a download served through a share link.
+		///
+		/// The dispatch origin of this call must be _Signed_ by a gateway,
+		/// which serves the file only if the call succeeds.
+		///
+		/// Parameters:
+		/// - `file_hash`: The shared file.
+		/// - `share_id`: The id of the share presented by the downloader.
+		#[pallet::call_index(44)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::redeem_share())]
+		pub fn redeem_share(origin: OriginFor<T>, file_hash: FileHash, share_id: [u8; 32]) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			ensure!(T::Gateways::contains(&sender), Error::<T>::NoPermission);
+			let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(file.stat == FileState::Active, Error::<T>::Unprepared);
 
-			Self::bucket_remove_file(&file_hash, &sender, &file)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+			let remaining = <FileShares<T>>::try_mutate_exists(&file_hash, |shares_opt| -> Result<u32, DispatchError> {
+				let shares = shares_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
+				let index = shares.iter().position(|share| share.share_id == share_id).ok_or(Error::<T>::NonExistent)?;
+				let share = &mut shares[index];
+				ensure!(share.expiry > now, Error::<T>::Expired);
+				share.downloads = share.downloads.saturating_add(1);
+				let remaining = share.max_downloads.saturating_sub(share.downloads);
+				if remaining == 0 {
+					shares.remove(index);
+					if shares.is_empty() {
+						*shares_opt = None;
+					}
+				}
+				Ok(remaining)
+			})?;
 
-			Self::remove_user_hold_file_list(&file_hash, &sender)?;
-			// let _ = Self::clear_user_file(file_hash.clone(), &sender, true)?;
+			Self::deposit_event(Event::<T>::ShareRedeemed { gateway: sender, file_hash, share_id, remaining });
 
 			Ok(())
 		}
-		/// Upload info of stored file.
+		/// Limit the bytes the caller can declare per era.
 		///
 		/// The dispatch origin of this call must be _Signed_.
-		///
-		/// The same file will only upload meta information once,
-		/// which will be uploaded by consensus.
+		/// A lower limit set by governance still applies.
 		///
 		/// Parameters:
-		/// - `file_hash`: The beneficiary related to signer account.
-		/// - `file_size`: File size calculated by consensus.
-		/// - `slice_info`: List of file slice information.
-		#[pallet::call_index(3)]
+		/// - `limit`: Maximum bytes per era, `None` removes the limit.
+		#[pallet::call_index(28)]
 		#[transactional]
-		#[pallet::weight(<T as pallet::Config>::WeightInfo::upload(2))]
-		pub fn transfer_report(
-			origin: OriginFor<T>,
-			deal_hash: Vec<Hash>,
-		) -> DispatchResult {
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_declaration_limit())]
+		pub fn set_declaration_limit(origin: OriginFor<T>, limit: Option<u128>) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			ensure!(deal_hash.len() < 5, Error::<T>::LengthExceedsLimit);
-			let mut failed_list: Vec<Hash> = Default::default();
-			for hash in deal_hash {
-				if !<DealMap<T>>::contains_key(&hash) {
-					failed_list.push(hash);
-					continue;
-				} else {
-					<DealMap<T>>::try_mutate(&hash, |deal_info_opt| -> DispatchResult {
-						// can use unwrap because there was a judgment above
-						let deal_info = deal_info_opt.as_mut().unwrap();
-						let mut task_miner_list: Vec<AccountOf<T>> = Default::default();
-						for miner_task in &deal_info.assigned_miner {
-							task_miner_list.push(miner_task.miner.clone());
-						}
-						if task_miner_list.contains(&sender) {
-							if !deal_info.complete_list.contains(&sender) {
-								deal_info.complete_list.try_push(sender.clone()).map_err(|_| Error::<T>::BoundedVecError)?;
-							}
-							// If it is the last submitter of the order.
-							if deal_info.complete_list.len() == deal_info.assigned_miner.len() {
-								deal_info.stage = 2;
-								Self::generate_file(
-									&hash,
-									deal_info.segment_list.clone(),
-									deal_info.assigned_miner.clone(),
-									deal_info.share_info.to_vec(),
-									deal_info.user.clone(),
-									FileState::Calculate,
-									deal_info.file_size,
-								)?;
-
-								let mut max_task_count = 0;
-								for miner_task in deal_info.assigned_miner.iter() {
-									let count = miner_task.fragment_list.len() as u32;
-									if count > max_task_count {
-										max_task_count = count;
-									}
-									// Miners need to report the replaced documents themselves. 
-									// If a challenge is triggered before the report is completed temporarily, 
-									// these documents to be replaced also need to be verified
-									<PendingReplacements<T>>::try_mutate(miner_task.miner.clone(), |pending_count| -> DispatchResult {
-										let pending_count_temp = pending_count.checked_add(count).ok_or(Error::<T>::Overflow)?;
-										*pending_count = pending_count_temp;
-										Ok(())
-									})?;
-								}	
 
-								let needed_space = Self::cal_file_size(deal_info.segment_list.len() as u128);
-								T::StorageHandle::unlock_and_used_user_space(&deal_info.user.user, needed_space)?;
-								T::StorageHandle::sub_total_idle_space(needed_space)?;
-								T::StorageHandle::add_total_service_space(needed_space)?;
-								let result = T::FScheduler::cancel_named(hash.0.to_vec()).map_err(|_| Error::<T>::Unexpected);
-								if let Err(_) = result {
-									log::info!("transfer report cancel schedule failed: {:?}", hash.clone());
-								}
+			match limit {
+				Some(bytes) => <DeclarationLimit<T>>::insert(&sender, bytes),
+				None => <DeclarationLimit<T>>::remove(&sender),
+			}
 
-								let max_needed_cal_space = (max_task_count as u128) * FRAGMENT_SIZE;
-								let mut life: u32 = (max_needed_cal_space / TRANSFER_RATE + 1) as u32;
-								life = life + (max_needed_cal_space / CALCULATE_RATE + 1) as u32;
+			Self::deposit_event(Event::<T>::DeclarationLimitSet { acc: sender, limit });
 
-								Self::start_second_task(hash.0.to_vec(), hash, life)?;
-								if <Bucket<T>>::contains_key(&deal_info.user.user, &deal_info.user.bucket_name) {
-									Self::add_file_to_bucket(&deal_info.user.user, &deal_info.user.bucket_name, &hash)?;
-								} else {
-									Self::create_bucket_helper(&deal_info.user.user, &deal_info.user.bucket_name, Some(hash))?;
-								}
+			Ok(())
+		}
+		/// Limit the bytes every account can declare per era.
+		///
+		/// The dispatch origin of this call must be `ForceOrigin`.
+		///
+		/// Parameters:
+		/// - `limit`: Maximum bytes per era, `None` removes the limit.
+		#[pallet::call_index(29)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_declaration_limit())]
+		pub fn set_global_declaration_limit(origin: OriginFor<T>, limit: Option<u128>) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
 
-								Self::add_user_hold_fileslice(&deal_info.user.user, hash.clone(), needed_space)?;
+			match limit {
+				Some(bytes) => <GlobalDeclarationLimit<T>>::put(bytes),
+				None => <GlobalDeclarationLimit<T>>::kill(),
+			}
 
-								Self::deposit_event(Event::<T>::StorageCompleted{ file_hash: hash });
-							}
-						} else {
-							failed_list.push(hash);
-						}
+			Self::deposit_event(Event::<T>::GlobalDeclarationLimitSet { limit });
 
-						Ok(())
-					})?;
-				}
+			Ok(())
+		}
+		/// Limit the size of declared files.
+		///
+		/// The dispatch origin of this call must be `ForceOrigin`.
+		///
+		/// Parameters:
+		/// - `limit`: Smallest and largest size in bytes, `None` removes the limit.
+		#[pallet::call_index(45)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_file_size_limit())]
+		pub fn set_file_size_limit(origin: OriginFor<T>, limit: Option<(u128, u128)>) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+
+			match limit {
+				Some((min, max)) => {
+					ensure!(min <= max, Error::<T>::SpecError);
+					<FileSizeLimit<T>>::put((min, max));
+				},
+				None => <FileSizeLimit<T>>::kill(),
 			}
 
-			Self::deposit_event(Event::<T>::TransferReport{acc: sender, failed_list});
-			
+			Self::deposit_event(Event::<T>::FileSizeLimitSet { limit });
+
 			Ok(())
 		}
-
-		#[pallet::call_index(4)]
+		/// Exempt an account from the file size limit, e.g. one with a dedicated storage agreement.
+		///
+		/// The dispatch origin of this call must be `ForceOrigin`.
+		///
+		/// Parameters:
+		/// - `acc`: The owner of the declared files.
+		/// - `exempt`: Whether the limit is lifted for the account.
+		#[pallet::call_index(46)]
 		#[transactional]
-		#[pallet::weight(1_000_000_000)]
-		pub fn calculate_end(
-			origin: OriginFor<T>,
-			deal_hash: Hash,
-		) -> DispatchResult {
-			let _ = ensure_root(origin)?;
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_file_size_limit())]
+		pub fn set_file_size_exemption(origin: OriginFor<T>, acc: AccountOf<T>, exempt: bool) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
 
-			let deal_info = <DealMap<T>>::try_get(&deal_hash).map_err(|_| Error::<T>::NonExistent)?;
-			for miner_task in deal_info.assigned_miner {
-				let count = miner_task.fragment_list.len() as u32;
-				// Accumulate the number of fragments stored by each miner
-				T::MinerControl::unlock_space_to_service(&miner_task.miner, FRAGMENT_SIZE * count as u128)?;
+			if exempt {
+				<FileSizeExempt<T>>::insert(&acc, true);
+			} else {
+				<FileSizeExempt<T>>::remove(&acc);
 			}
 
-			<File<T>>::try_mutate(&deal_hash, |file_opt| -> DispatchResult {
-				let file = file_opt.as_mut().ok_or(Error::<T>::BugInvalid)?;
-				file.stat = FileState::Active;
-				Ok(())
-			})?;
-
-			<DealMap<T>>::remove(&deal_hash);
-
-			Self::deposit_event(Event::<T>::CalculateEnd{ file_hash: deal_hash });
+			Self::deposit_event(Event::<T>::FileSizeExemptionSet { acc, exempt });
 
 			Ok(())
 		}
-
-		#[pallet::call_index(5)]
+		/// Remind a miner of an invalid file it has not cleared.
+		///
+		/// Submitted unsigned by the offchain worker of an authority, the reminder is checked
+		/// against the authorities and its signature in `validate_unsigned`.
+		/// A file without a deadline is given one, the miner is punished if the file is still
+		/// not cleared by then.
+		///
+		/// Parameters:
+		/// - `reminder`: The miner, the invalid file, the block it was made at and the reminding key.
+		/// - `signature`: Signature of the reminder by the reminding key.
+		#[pallet::call_index(47)]
 		#[transactional]
-		#[pallet::weight(1_000_000_000)]
-		pub fn replace_file_report(
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::remind_invalid_file())]
+		pub fn remind_invalid_file(
 			origin: OriginFor<T>,
-			filler: Vec<Hash>,
+			reminder: InvalidFileReminder<AccountOf<T>, BlockNumberOf<T>, T::AuthorityId>,
+			_signature: <T::AuthorityId as RuntimeAppPublic>::Signature,
 		) -> DispatchResult {
+			ensure_none(origin)?;
+
+			let miner = reminder.miner;
+			let file_hash = reminder.file_hash;
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(<InvalidFile<T>>::get(&miner).contains(&file_hash), Error::<T>::NonExistent);
+			ensure!(Self::invalid_file_reminder_due(&miner, &file_hash, now), Error::<T>::ReminderTooEarly);
+
+			let count = <InvalidFileReminders<T>>::get(&miner, &file_hash)
+				.map(|(count, _)| count)
+				.unwrap_or(0)
+				.saturating_add(1);
+			<InvalidFileReminders<T>>::insert(&miner, &file_hash, (count, now.saturating_add(T::InvalidFileReminderPeriod::get())));
+
+			let deadline = match <InvalidFileDeadline<T>>::get(&miner, &file_hash) {
+				Some(deadline) => Some(deadline),
+				None => {
+					let deadline = now.saturating_add(T::InvalidFileGracePeriod::get());
+					if Self::schedule_invalid_file_deadline(&miner, &file_hash, deadline) {
+						Some(deadline)
+					} else {
+						None
+					}
+				},
+			};
+
+			Self::deposit_event(Event::<T>::InvalidFileReminded { miner, file_hash, count, deadline });
+			Ok(())
+		}
+		/// Withdraw a declaration that is not stored yet.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the owner or an authorized operator.
+		/// Only deals in the first stage can be withdrawn, the reserved space of the owner
+		/// and of the miners is released and the declaration deposit is refunded.
+		///
+		/// Parameters:
+		/// - `deal_hash`: The deal to withdraw.
+		#[pallet::call_index(49)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::withdraw_declaration())]
+		pub fn withdraw_declaration(origin: OriginFor<T>, deal_hash: Hash) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(filler.len() <= 30, Error::<T>::LengthExceedsLimit);
-			let pending_count = <PendingReplacements<T>>::get(&sender);
-			ensure!(filler.len() as u32 <= pending_count, Error::<T>::LengthExceedsLimit);
+			let deal_info = <DealMap<T>>::try_get(&deal_hash).map_err(|_| Error::<T>::NonExistent)?;
+			let owner = deal_info.user.user;
+			ensure!(Self::check_permission(sender.clone(), owner.clone()), Error::<T>::NoPermission);
+			ensure!(deal_info.stage == 1, Error::<T>::StageError);
 
-			let mut count: u32 = 0;
-			for filler_hash in filler.iter() {
-				if <FillerMap<T>>::contains_key(&sender, filler_hash) {
-					count += 1;
-					<FillerMap<T>>::remove(&sender, filler_hash);
-				} else {
-					log::info!("filler nonexist!");
-				}
+			if T::FScheduler::cancel_named(deal_hash.0.to_vec()).is_err() {
+				log::info!("withdraw declaration cancel schedule failed: {:?}", deal_hash);
 			}
+			Self::remove_deal(&deal_hash)?;
 
-			<PendingReplacements<T>>::mutate(&sender, |pending_count| -> DispatchResult {
-				let pending_count_temp = pending_count.checked_sub(count).ok_or(Error::<T>::Overflow)?;
-				*pending_count = pending_count_temp;
-				Ok(())
-			})?;
+			Self::deposit_event(Event::<T>::DeclarationWithdrawn { operator: sender, owner, deal_hash });
 
-			Self::deposit_event(Event::<T>::ReplaceFiller{ acc: sender, filler_list: filler });
+			Ok(())
+		}
+		/// Create a storage order, paying for a file from escrow instead of a package.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the file owner.
+		/// The price of the space for `days` days, at the unit price of the packages, is reserved
+		/// from the owner. Once the file is stored, the miners of the file are paid from it for
+		/// every day they did not fail a challenge, and the file is deleted after the last day.
+		/// The file is declared with `upload_declaration` as usual, deleting it earlier
+		/// refunds the escrow that was not paid out.
+		///
+		/// Parameters:
+		/// - `file_hash`: Hash of the file paid by the order.
+		/// - `file_size`: Size of the file, in bytes.
+		/// - `days`: Days the file is stored for.
+		#[pallet::call_index(50)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::create_storage_order())]
+		pub fn create_storage_order(origin: OriginFor<T>, file_hash: FileHash, file_size: u128, days: u32) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
+			ensure!(T::StorageOrdersEnabled::get(), Error::<T>::StorageOrdersDisabled);
+			ensure!(file_size > 0 && days > 0, Error::<T>::IsZero);
+			ensure!(!<StorageOrders<T>>::contains_key(&sender, &file_hash), Error::<T>::Existed);
+			ensure!(!Self::check_is_file_owner(&sender, &file_hash), Error::<T>::IsOwned);
+
+			let segment_count = file_size.checked_add(SEGMENT_SIZE - 1).ok_or(Error::<T>::Overflow)? / SEGMENT_SIZE;
+			let space = Self::cal_file_size(segment_count);
+			let daily_payment = Self::storage_order_daily_payment(space)?;
+			let escrow = daily_payment.checked_mul(&days.saturated_into()).ok_or(Error::<T>::Overflow)?;
+			T::Currency::reserve(&sender, escrow)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			<StorageOrders<T>>::insert(&sender, &file_hash, StorageOrder::<T> {
+				space,
+				days,
+				daily_payment,
+				escrow,
+				start: None,
+				paid_days: 0,
+				settled_at: now,
+			});
+
+			Self::deposit_event(Event::<T>::StorageOrderCreated { owner: sender, file_hash, space, days, escrow });
 
 			Ok(())
 		}
+		/// Cancel a storage order whose file was not declared, the escrow is refunded.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the file owner.
+		/// Orders of stored files end by deleting the file, orders of pending deals
+		/// by withdrawing the declaration.
+		///
+		/// Parameters:
+		/// - `file_hash`: Hash of the file paid by the order.
+		#[pallet::call_index(51)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::cancel_storage_order())]
+		pub fn cancel_storage_order(origin: OriginFor<T>, file_hash: FileHash) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
 
-		#[pallet::call_index(6)]
+			let order = <StorageOrders<T>>::try_get(&sender, &file_hash).map_err(|_| Error::<T>::NonExistent)?;
+			ensure!(order.start.is_none(), Error::<T>::IsOwned);
+			if let Some(deal_info) = <DealMap<T>>::get(&file_hash) {
+				ensure!(deal_info.user.user != sender, Error::<T>::Declarated);
+			}
+			Self::close_storage_order(&sender, &file_hash)?;
+
+			Ok(())
+		}
+		/// Set the placement policy of a bucket.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the owner or an authorized operator.
+		/// Files declared into the bucket from now on inherit the policy,
+		/// files declared before keep the miners they are stored by.
+		///
+		/// Parameters:
+		/// - `owner`: The owner of the bucket.
+		/// - `name`: The bucket name.
+		/// - `policy`: The policy inherited by the files, None clears it.
+		#[pallet::call_index(48)]
 		#[transactional]
-		#[pallet::weight(1_000_000_000)]
-		pub fn delete_file(origin: OriginFor<T>, owner: AccountOf<T>, file_hash_list: Vec<Hash>) -> DispatchResult {
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_bucket_policy())]
+		pub fn set_bucket_policy(
+			origin: OriginFor<T>,
+			owner: AccountOf<T>,
+			name: BoundedVec<u8, T::NameStrLimit>,
+			policy: Option<StoragePolicy>,
+		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			// Check if you have operation permissions.
 			ensure!(Self::check_permission(sender.clone(), owner.clone()), Error::<T>::NoPermission);
-			ensure!(file_hash_list.len() < 10, Error::<T>::LengthExceedsLimit);
+			ensure!(<Bucket<T>>::contains_key(&owner, &name), Error::<T>::NonExistent);
 
-			for file_hash in file_hash_list.iter() {
-				let file = <File<T>>::try_get(&file_hash).map_err(|_| Error::<T>::NonExistent)?;
-				let _ = Self::delete_user_file(&file_hash, &owner, &file)?;
-				Self::bucket_remove_file(&file_hash, &owner, &file)?;
-				Self::remove_user_hold_file_list(&file_hash, &owner)?;
+			match policy {
+				Some(value) => {
+					Self::check_storage_policy(&value)?;
+					<BucketPolicy<T>>::insert(&owner, &name, value);
+				},
+				None => <BucketPolicy<T>>::remove(&owner, &name),
 			}
 
-			Self::deposit_event(Event::<T>::DeleteFile{ operator: sender, owner, file_hash_list });
+			Self::deposit_event(Event::<T>::BucketPolicySet {
+				operator: sender,
+				owner,
+				bucket_name: name.to_vec(),
+				policy,
+			});
 
 			Ok(())
 		}
@@ -808,7 +2622,7 @@ pub mod pallet {
 			origin: OriginFor<T>,
 			tee_worker: AccountOf<T>,
 			filler_list: Vec<FillerInfo<T>>,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let sender = ensure_signed(origin)?;
 			let limit = T::UploadFillerLimit::get();
 			if filler_list.len() > limit as usize {
@@ -820,25 +2634,36 @@ pub mod pallet {
 			let is_positive = T::MinerControl::is_positive(&sender)?;
 			ensure!(is_positive, Error::<T>::NotQualified);
 
+			// Fillers that are already stored are left out instead of failing the whole upload.
+			let mut uploaded: u32 = 0;
+			let mut skipped: Vec<Hash> = Default::default();
 			for i in filler_list.iter() {
 				if <FillerMap<T>>::contains_key(&sender, i.filler_hash.clone()) {
-					Err(Error::<T>::FileExistent)?;
+					skipped.push(i.filler_hash.clone());
+					continue;
 				}
 				<FillerMap<T>>::insert(sender.clone(), i.filler_hash.clone(), i);
+				Self::index_filler(&sender, &i.filler_hash);
+				uploaded += 1;
 			}
+			ensure!(uploaded > 0, Error::<T>::FileExistent);
 
 			let idle_space = M_BYTE
 				.checked_mul(8)
 				.ok_or(Error::<T>::Overflow)?
-				.checked_mul(filler_list.len() as u128)
+				.checked_mul(uploaded as u128)
 				.ok_or(Error::<T>::Overflow)?;
 			T::MinerControl::add_miner_idle_space(&sender, idle_space)?;
 			T::StorageHandle::add_total_idle_space(idle_space)?;
 			// TODO
 			// Self::record_uploaded_fillers_size(&sender, &filler_list)?;
 
-			Self::deposit_event(Event::<T>::FillerUpload { acc: sender, file_size: idle_space as u64 });
-			Ok(())
+			if !skipped.is_empty() {
+				Self::deposit_event(Event::<T>::FillerUploadSkipped { acc: sender.clone(), filler_list: skipped });
+			}
+			Self::deposit_event(Event::<T>::FillerUpload { acc: sender, file_size: idle_space as u64, count: uploaded });
+
+			Ok(Some(<T as pallet::Config>::WeightInfo::upload_filler(uploaded)).into())
 		}
 
 		#[pallet::call_index(9)]
@@ -861,6 +2686,7 @@ pub mod pallet {
 			T::StorageHandle::sub_total_idle_space(idle_space)?;
 
 			<FillerMap<T>>::remove(&sender, &filler_hash);
+			Self::unindex_filler(&sender, &filler_hash);
 
 			Self::deposit_event(Event::<T>::FillerDelete { acc: sender, filler_hash: filler_hash });
 
@@ -877,7 +2703,8 @@ pub mod pallet {
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			ensure!(Self::check_permission(sender.clone(), owner.clone()), Error::<T>::NoPermission);
-			
+			let name = Self::normalize_name(&name, T::NameMinLength::get())?;
+
 			Self::create_bucket_helper(&owner, &name, None)?;
 
 			Self::deposit_event(Event::<T>::CreateBucket {
@@ -891,7 +2718,7 @@ pub mod pallet {
 
 		#[pallet::call_index(12)]
 		#[transactional]
-		#[pallet::weight(<T as pallet::Config>::WeightInfo::delete_bucket())]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::delete_bucket().saturating_add(<T as pallet::Config>::WeightInfo::user_file_root()))]
 		pub fn delete_bucket(
 			origin: OriginFor<T>,
 			owner: AccountOf<T>,
@@ -908,10 +2735,10 @@ pub mod pallet {
 				 } else {
 					Self::remove_file_last_owner(file_hash, &owner, true)?;
 				}
-
-				Self::remove_user_hold_file_list(file_hash, &owner)?;
 			}
+			Self::remove_user_hold_files(&bucket.object_list, &owner);
 			<Bucket<T>>::remove(&owner, &name);
+			<BucketPolicy<T>>::remove(&owner, &name);
 			<UserBucketList<T>>::try_mutate(&owner, |bucket_list| -> DispatchResult {
 				let mut index = 0;
 				for name_tmp in bucket_list.iter() {
@@ -935,13 +2762,14 @@ pub mod pallet {
 		// restoral file
 		#[pallet::call_index(13)]
 		#[transactional]
-		#[pallet::weight(100_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::generate_restoral_order())]
 		pub fn generate_restoral_order(
 			origin: OriginFor<T>,
-			file_hash: Hash,
+			file_hash: FileHash,
 			restoral_fragment: Hash,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
 
 			ensure!(
 				!RestoralOrder::<T>::contains_key(&restoral_fragment),
@@ -968,7 +2796,7 @@ pub mod pallet {
 		
 								<RestoralOrder<T>>::insert(&restoral_fragment, restoral_order);
 		
-								Self::deposit_event(Event::<T>::GenerateRestoralOrder{ miner: sender, fragment_hash: restoral_fragment});
+								Self::deposit_event(Event::<T>::GenerateRestoralOrder{ miner: sender, file_hash, fragment_hash: restoral_fragment});
 		
 								return Ok(())
 							}
@@ -977,12 +2805,15 @@ pub mod pallet {
 				}
 	
 				Err(Error::<T>::SpecError)?
-			})
+			})?;
+			Self::refresh_file_summary(&file_hash);
+
+			Ok(())
 		}
 
 		#[pallet::call_index(14)]
 		#[transactional]
-		#[pallet::weight(100_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::claim_restoral_order())]
 		pub fn claim_restoral_order(
 			origin: OriginFor<T>,
 			restoral_fragment: Hash,
@@ -992,7 +2823,7 @@ pub mod pallet {
 			ensure!(is_positive, Error::<T>::MinerStateError);
 
 			let now = <frame_system::Pallet<T>>::block_number();
-			<RestoralOrder<T>>::try_mutate(&restoral_fragment, |order_opt| -> DispatchResult {
+			let deadline = <RestoralOrder<T>>::try_mutate(&restoral_fragment, |order_opt| -> Result<BlockNumberOf<T>, DispatchError> {
 				let order = order_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
 				
 				ensure!(now > order.deadline, Error::<T>::SpecError);
@@ -1002,24 +2833,25 @@ pub mod pallet {
 				order.deadline = now.checked_add(&life.saturated_into()).ok_or(Error::<T>::Overflow)?;
 				order.miner = sender.clone();
 
-				Ok(())
+				Ok(order.deadline)
 			})?;
 
-			Self::deposit_event(Event::<T>::ClaimRestoralOrder{ miner: sender, order_id: restoral_fragment});
+			Self::deposit_event(Event::<T>::ClaimRestoralOrder{ miner: sender, order_id: restoral_fragment, deadline });
 
 			Ok(())
 		}
 
 		#[pallet::call_index(15)]
 		#[transactional]
-		#[pallet::weight(100_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::claim_restoral_noexist_order())]
 		pub fn claim_restoral_noexist_order(
 			origin: OriginFor<T>,
 			miner: AccountOf<T>,
-			file_hash: Hash,
+			file_hash: FileHash,
 			restoral_fragment: Hash,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			let file_hash: Hash = file_hash.into();
 			let is_positive = T::MinerControl::is_positive(&sender)?;
 			ensure!(is_positive, Error::<T>::MinerStateError);
 
@@ -1033,15 +2865,15 @@ pub mod pallet {
 				Error::<T>::NonExistent,
 			);
 
+			let now = <frame_system::Pallet<T>>::block_number();
+			let life = T::RestoralOrderLife::get();
+			let deadline = now.checked_add(&life.saturated_into()).ok_or(Error::<T>::Overflow)?;
 			<File<T>>::try_mutate(&file_hash, |file_opt| -> DispatchResult {
 				let file = file_opt.as_mut().ok_or(Error::<T>::NonExistent)?;
 				for segment in &mut file.segment_list {
 					for fragment in &mut segment.fragment_list {
 						if &fragment.hash == &restoral_fragment {
 							if fragment.miner == miner {
-								let now = <frame_system::Pallet<T>>::block_number();
-								let life = T::RestoralOrderLife::get();
-								let deadline = now.checked_add(&life.saturated_into()).ok_or(Error::<T>::Overflow)?;
 								let restoral_order = RestoralOrderInfo::<T> {
 									count: u32::MIN,
 									miner: sender.clone(),
@@ -1064,18 +2896,29 @@ pub mod pallet {
 	
 				Err(Error::<T>::SpecError)?
 			})?;
+			Self::refresh_file_summary(&file_hash);
 
-			Self::deposit_event(Event::<T>::ClaimRestoralOrder{ miner: sender, order_id: restoral_fragment});
+			Self::deposit_event(Event::<T>::ClaimRestoralOrder{ miner: sender, order_id: restoral_fragment, deadline });
 
 			Ok(())
 		}
 
+		/// Report the restoral of a fragment.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the miner of the order.
+		/// The fragment is only made available again once a majority of the tee committee
+		/// signed the tag of the restored data.
+		///
+		/// Parameters:
+		/// - `fragment_hash`: The restored fragment.
+		/// - `tag`: The PoDR2 tag of the restored fragment signed by the tee committee.
 		#[pallet::call_index(16)]
 		#[transactional]
-		#[pallet::weight(100_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::restoral_order_complete())]
 		pub fn restoral_order_complete(
 			origin: OriginFor<T>,
 			fragment_hash: Hash,
+			tag: RestoralTag<AccountOf<T>, BlockNumberOf<T>>,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			let is_positive = T::MinerControl::is_positive(&sender)?;
@@ -1086,9 +2929,11 @@ pub mod pallet {
 
 			let now = <frame_system::Pallet<T>>::block_number();
 			ensure!(now < order.deadline, Error::<T>::Expired);
+			Self::check_restoral_tag(&order, &tag, now)?;
 
 			if !<File<T>>::contains_key(&order.file_hash) {
 				<RestoralOrder<T>>::remove(fragment_hash);
+				<RecoveryQueue<T>>::remove(&order.file_hash);
 				return Ok(());
 			} else {
 				<File<T>>::try_mutate(&order.file_hash, |file_opt| -> DispatchResult {
@@ -1115,9 +2960,11 @@ pub mod pallet {
 
 					Ok(())
 				})?;
+				Self::refresh_file_summary(&order.file_hash);
 			}
 
 			<RestoralOrder<T>>::remove(fragment_hash);
+			Self::record_recovery(&order.file_hash, &fragment_hash);
 
 			Self::deposit_event(Event::<T>::RecoveryCompleted{ miner: sender, order_id: fragment_hash});
 		
@@ -1127,7 +2974,7 @@ pub mod pallet {
 		// The lock in time must be greater than the survival period of the challenge
 		#[pallet::call_index(17)]
 		#[transactional]
-		#[pallet::weight(100_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::miner_exit_prep())]
 		pub fn miner_exit_prep(
 			origin: OriginFor<T>,
 		) -> DispatchResult {
@@ -1167,7 +3014,7 @@ pub mod pallet {
 
 		#[pallet::call_index(18)]
 		#[transactional]
-		#[pallet::weight(100_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::miner_exit())]
 		pub fn miner_exit(
 			origin: OriginFor<T>,
 			miner: AccountOf<T>,
@@ -1185,21 +3032,30 @@ pub mod pallet {
 			T::MinerControl::execute_exit(&miner)?;
 
 			Self::create_restoral_target(&miner, service_space)?;
+			Self::queue_recovery(&miner);
 
 			Ok(())
 		}
 
 		#[pallet::call_index(19)]
 		#[transactional]
-		#[pallet::weight(100_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::miner_withdraw())]
 		pub fn miner_withdraw(origin: OriginFor<T>) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 
 			let restoral_info = <RestoralTarget<T>>::try_get(&sender).map_err(|_| Error::<T>::MinerStateError)?;
 			let now = <frame_system::Pallet<T>>::block_number();
 
-			if now < restoral_info.cooling_block && restoral_info.restored_space != restoral_info.service_space {
-				Err(Error::<T>::MinerStateError)?;
+			// The collateral stays locked until every replica of the miner is restored,
+			// or the cooling period is over and the missing replicas are paid from the collateral.
+			if restoral_info.restored_space < restoral_info.service_space {
+				ensure!(now >= restoral_info.cooling_block, Error::<T>::MinerStateError);
+
+				let lost_space = restoral_info.service_space
+					.checked_sub(restoral_info.restored_space).ok_or(Error::<T>::Overflow)?;
+				T::MinerControl::compensate_lost_replica(&sender, lost_space)?;
+
+				Self::deposit_event(Event::<T>::CompensateReplica { miner: sender.clone(), lost_space });
 			}
 
 			T::MinerControl::withdraw(&sender)?;
@@ -1211,18 +3067,95 @@ pub mod pallet {
 			Ok(())
 		}
 	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			if let Call::remind_invalid_file { reminder, signature } = call {
+				Self::check_invalid_file_reminder(reminder, signature)
+			} else {
+				InvalidTransaction::Call.into()
+			}
+		}
+	}
+}
+
+#[cfg(any(test, feature = "try-runtime", feature = "consistency-checks"))]
+impl<T: Config> Pallet<T> {
+	/// Checks that the files held by users and the file schedulers refer to stored files,
+	/// that the owner count and summary of each stored file are up to date, that started
+	/// storage orders belong to a holder of the file, and that every invalid file deadline and reminder belongs to a file not cleared yet.
+	pub fn do_try_state() -> Result<(), &'static str> {
+		for (acc, index) in <UserFileIndex<T>>::iter() {
+			let trie = Self::user_file_trie(&acc);
+			let (mut count, mut total_size) = (0u32, 0u128);
+			for (file_hash, file_size) in Self::user_files(&trie) {
+				ensure!(<File<T>>::contains_key(&file_hash), "UserFileIndex holds a missing file");
+				count = count.saturating_add(1);
+				total_size = total_size.saturating_add(file_size);
+			}
+			ensure!(index.count == count && index.total_size == total_size, "UserFileIndex out of date");
+			ensure!(index.root == Self::user_file_root(&trie), "UserFileIndex root out of date");
+		}
+		for file_hash in <FileScheduler<T>>::iter_keys() {
+			ensure!(<File<T>>::contains_key(&file_hash), "FileScheduler of a missing file");
+		}
+		for (file_hash, file) in <File<T>>::iter() {
+			ensure!(<FileRefCount<T>>::get(&file_hash) == file.owner.len() as u32, "FileRefCount differs from the owners");
+			ensure!(<FileSummary<T>>::get(&file_hash) == Some(Self::summarize_file(&file)), "FileSummary differs from the file");
+		}
+		ensure!(<FillerIndex<T>>::iter_keys().count() as u32 == <FillerCount<T>>::get(), "FillerCount differs from FillerIndex");
+		for (position, (miner, filler_hash)) in <FillerIndex<T>>::iter() {
+			ensure!(<FillerMap<T>>::contains_key(&miner, &filler_hash), "FillerIndex holds a missing filler");
+			ensure!(<FillerPosition<T>>::get(&miner, &filler_hash) == Some(position), "FillerPosition out of date");
+		}
+		ensure!(<ActiveFileIndex<T>>::iter_keys().count() as u32 == <ActiveFileCount<T>>::get(), "ActiveFileCount differs from ActiveFileIndex");
+		for (position, file_hash) in <ActiveFileIndex<T>>::iter() {
+			ensure!(<File<T>>::contains_key(&file_hash), "ActiveFileIndex holds a missing file");
+			ensure!(<ActiveFilePosition<T>>::get(&file_hash) == Some(position), "ActiveFilePosition out of date");
+		}
+		for (owner, file_hash, order) in <StorageOrders<T>>::iter() {
+			ensure!(order.paid_days <= order.days, "StorageOrder paid for more days than ordered");
+			ensure!(
+				order.start.is_none() || Self::check_is_file_owner(&owner, &file_hash),
+				"StorageOrder of a file the owner does not hold",
+			);
+		}
+		for (miner, file_hash, _) in <InvalidFileDeadline<T>>::iter() {
+			ensure!(<InvalidFile<T>>::get(&miner).contains(&file_hash), "InvalidFileDeadline of a cleared file");
+		}
+		for (miner, file_hash, _) in <InvalidFileReminders<T>>::iter() {
+			ensure!(<InvalidFile<T>>::get(&miner).contains(&file_hash), "InvalidFileReminders of a cleared file");
+		}
+
+		Ok(())
+	}
 }
 
 pub trait RandomFileList<AccountId> {
-	//Get random challenge data
+	//Unused, challenges are proposed by the offchain workers of the audit authorities through `save_challenge_info`
 	fn get_random_challenge_data(
 	) -> Result<Vec<(AccountId, Hash, [u8; 68], Vec<u32>, u64, DataType)>, DispatchError>;
 	//Delete all filler according to miner_acc
 	fn delete_miner_all_filler(miner_acc: AccountId) -> Result<Weight, DispatchError>;
+	//Draw up to `count` distinct stored fillers at random, one read per filler
+	fn get_random_fillers(count: u32, seed: u32) -> Vec<(AccountId, Hash)>;
+	//Draw up to `count` distinct active files at random, one read per file
+	fn get_random_files(count: u32, seed: u32) -> Vec<Hash>;
 	//Delete file backup
 	fn clear_file(_file_hash: Hash) -> Result<Weight, DispatchError>;
 
 	fn force_miner_exit(miner: &AccountId) -> DispatchResult;
+	//Whether the file is opted in to public sampling
+	fn is_public_dataset(file_hash: &Hash) -> bool;
+	//Get the miner storing the fragment of a file
+	fn get_fragment_miner(file_hash: &Hash, fragment_hash: &Hash) -> Option<AccountId>;
+	//Get the miners storing the fragments of a file, empty if the file does not exist
+	fn get_file_miners(file_hash: &Hash) -> Vec<AccountId>;
+	//Get the tee worker the file was uploaded through
+	fn get_file_scheduler(file_hash: &Hash) -> Option<AccountId>;
 }
 
 impl<T: Config> RandomFileList<<T as frame_system::Config>::AccountId> for Pallet<T> {
@@ -1236,12 +3169,27 @@ impl<T: Config> RandomFileList<<T as frame_system::Config>::AccountId> for Palle
 		for (_, _value) in FillerMap::<T>::iter_prefix(&miner_acc) {
 			weight = weight.saturating_add(T::DbWeight::get().writes(1 as u64));
 		}
+		weight = weight.saturating_add(Self::unindex_miner_fillers(&miner_acc));
 		#[allow(deprecated)]
 		let _ = FillerMap::<T>::remove_prefix(&miner_acc, Option::None);
 		weight = weight.saturating_add(T::DbWeight::get().writes(1 as u64));
 		Ok(weight)
 	}
 
+	fn get_random_fillers(count: u32, seed: u32) -> Vec<(AccountOf<T>, Hash)> {
+		Self::random_positions(<FillerCount<T>>::get(), count, seed)
+			.into_iter()
+			.filter_map(|position| <FillerIndex<T>>::get(position))
+			.collect()
+	}
+
+	fn get_random_files(count: u32, seed: u32) -> Vec<Hash> {
+		Self::random_positions(<ActiveFileCount<T>>::get(), count, seed)
+			.into_iter()
+			.filter_map(|position| <ActiveFileIndex<T>>::get(position))
+			.collect()
+	}
+
 	fn clear_file(_file_hash: Hash) -> Result<Weight, DispatchError> {
 		let weight: Weight = Weight::from_ref_time(0);
 		Ok(weight)
@@ -1250,6 +3198,48 @@ impl<T: Config> RandomFileList<<T as frame_system::Config>::AccountId> for Palle
 	fn force_miner_exit(miner: &AccountOf<T>) -> DispatchResult {
 		Self::force_miner_exit(miner)
 	}
+
+	fn is_public_dataset(file_hash: &Hash) -> bool {
+		<PublicDataset<T>>::get(file_hash)
+	}
+
+	fn get_fragment_miner(file_hash: &Hash, fragment_hash: &Hash) -> Option<AccountOf<T>> {
+		let file = <File<T>>::get(file_hash)?;
+		for segment in file.segment_list.iter() {
+			for fragment in segment.fragment_list.iter() {
+				if &fragment.hash == fragment_hash {
+					return Some(fragment.miner.clone());
+				}
+			}
+		}
+
+		None
+	}
+
+	fn get_file_miners(file_hash: &Hash) -> Vec<AccountOf<T>> {
+		let mut miner_list: Vec<AccountOf<T>> = Default::default();
+		if let Some(file) = <File<T>>::get(file_hash) {
+			for segment in file.segment_list.iter() {
+				for fragment in segment.fragment_list.iter() {
+					if !miner_list.contains(&fragment.miner) {
+						miner_list.push(fragment.miner.clone());
+					}
+				}
+			}
+		}
+
+		miner_list
+	}
+
+	fn get_file_scheduler(file_hash: &Hash) -> Option<AccountOf<T>> {
+		<FileScheduler<T>>::get(file_hash)
+	}
+}
+
+impl<T: Config> ScheduleExit<<T as frame_system::Config>::AccountId> for Pallet<T> {
+	fn on_scheduler_exit(acc: &AccountOf<T>) -> DispatchResult {
+		Self::replace_deal_scheduler(acc)
+	}
 }
 
 impl<T: Config> BlockNumberProvider for Pallet<T> {
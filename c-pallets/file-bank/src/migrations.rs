@@ -1,227 +1,227 @@
-use crate::{AccountOf, Config, Pallet, Weight, BoundedString};
-use codec::{Decode, Encode};
-use frame_support::{
-	codec, generate_storage_alias,
-	pallet_prelude::*,
-	traits::{Get},
-};
-use frame_support::traits::OnRuntimeUpgrade;
-
-/// A struct that does not migration, but only checks that the counter prefix exists and is correct.
-pub struct TestMigrationFileBank<T: crate::Config>(sp_std::marker::PhantomData<T>);
-impl<T: crate::Config> OnRuntimeUpgrade for TestMigrationFileBank<T> {
+use crate::*;
+use frame_support::{storage_alias, traits::OnRuntimeUpgrade};
+
+/// Count the owners and write the summary of every file stored before
+/// `FileRefCount` and `FileSummary` existed, start moving the file lists of
+/// the users into their file tries over the next blocks, index the stored fillers
+/// and active files and assign a scheduler to the pending deals.
+pub struct MigrationFileBank<T: crate::Config>(sp_std::marker::PhantomData<T>);
+impl<T: crate::Config> OnRuntimeUpgrade for MigrationFileBank<T> {
 	fn on_runtime_upgrade() -> Weight {
+		log::info!("FileBank migrate start!");
 		migrate::<T>()
 	}
 
 	#[cfg(feature = "try-runtime")]
-	fn pre_upgrade() -> Result<(), &'static str> {
-		log::info!("🙋🏽‍file-bank check access");
-		return Ok(())
+	fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+		log::info!("file-bank check access");
+		return Ok(Default::default())
 	}
 
 	#[cfg(feature = "try-runtime")]
-	fn post_upgrade() -> Result<(), &'static str> {
-		let weights = migrate::<T>();
-		return Ok(())
+	fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+		ensure!(StorageVersion::get::<Pallet<T>>() == 6, "file-bank not migrated");
+		// `try_get` fails on entries still in the layout before version 6.
+		for deal_hash in <DealMap<T>>::iter_keys() {
+			ensure!(<DealMap<T>>::try_get(&deal_hash).is_ok(), "file-bank deal not migrated");
+		}
+		// The indexes written by the migrations agree with the files and fillers.
+		Pallet::<T>::do_try_state()
 	}
 }
 
 pub fn migrate<T: Config>() -> Weight {
-	use frame_support::traits::StorageVersion;
-
 	let version = StorageVersion::get::<Pallet<T>>();
-	let mut weight: Weight = 0;
+	let mut weight: Weight = Weight::from_ref_time(0);
+
+	if version < 3 {
+		log::info!("FileBank version 2 -> 3 migrations start!");
+		weight = weight.saturating_add(v3::migrate::<T>());
+		StorageVersion::new(3).put::<Pallet<T>>();
+	}
+
+	if version < 4 {
+		log::info!("FileBank version 3 -> 4 migrations start!");
+		weight = weight.saturating_add(v4::migrate::<T>());
+		StorageVersion::new(4).put::<Pallet<T>>();
+	}
 
-	if version < 2 {
-        weight = weight.saturating_add(v2::migrate::<T>());
-        StorageVersion::new(2).put::<Pallet<T>>();
+	if version < 5 {
+		log::info!("FileBank version 4 -> 5 migrations start!");
+		weight = weight.saturating_add(v5::migrate::<T>());
+		StorageVersion::new(5).put::<Pallet<T>>();
+	}
+
+	if version < 6 {
+		log::info!("FileBank version 5 -> 6 migrations start!");
+		weight = weight.saturating_add(v6::migrate::<T>());
+		StorageVersion::new(6).put::<Pallet<T>>();
 	}
 
 	weight
 }
 
-mod example {
-    use super::*;
-
-    #[derive(Decode, Encode)]
-    struct OldFillerInfo<T: Config> {
-        filler_size: u64,
-        index: u32,
-        block_num: u32,
-        segment_size: u32,
-        scan_size: u32,
-        miner_address: AccountOf<T>,
-        filler_id: BoundedVec<u8, T::StringLimit>,
-        filler_hash: BoundedVec<u8, T::StringLimit>,
-    }
-
-    #[derive(Decode, Encode)]
-    struct NewFillerInfo<T: Config> {
-        filler_size: u64,
-        index: u32,
-        block_num: u32,
-        segment_size: u32,
-        miner_address: AccountOf<T>,
-        filler_id: BoundedVec<u8, T::StringLimit>,
-        filler_hash: BoundedVec<u8, T::StringLimit>,
-        is_delete: bool,
-    }
-
-    generate_storage_alias!(
-		FileBank,
-		FillerMap<T: Config> => DoubleMap<
-            (Blake2_128Concat, T::AccountId),
-            (Blake2_128Concat, BoundedString<T>),
-            NewFillerInfo<T>
-        >
-	);
-
-    pub fn migrate<T: Config>() -> Weight {
-        let mut weight: Weight = 0;
-
-        <FillerMap<T>>::translate(|_key1, _key2, old: OldFillerInfo<T>| {
-            weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
-            Some(NewFillerInfo::<T>{
-                filler_size: old.filler_size,
-                index: old.index,
-                block_num: old.block_num,
-                segment_size: old.segment_size,
-                miner_address: old.miner_address,
-                filler_id: old.filler_id,
-                filler_hash: old.filler_hash,
-                is_delete: false,
-            })
-        });
-
-        weight
-    }
+// Moves a batch of the file lists left by the version 4 migration.
+pub(crate) fn migrate_user_files<T: Config>() -> Weight {
+	v4::migrate_batch::<T>()
+}
+
+mod v3 {
+	use super::*;
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		for (file_hash, file) in <File<T>>::iter() {
+			<FileRefCount<T>>::insert(&file_hash, file.owner.len() as u32);
+			<FileSummary<T>>::insert(&file_hash, Pallet::<T>::summarize_file(&file));
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 2));
+		}
+
+		weight
+	}
 }
 
-mod v2 {
+mod v4 {
 	use super::*;
-	use cp_cess_common::Hash;
-	use crate::{FillerInfo, FillerMap as NewFillerMap};
-
-	#[derive(Decode, Encode)]
-	struct OldFillerInfo<T: crate::Config> {
-		filler_size: u64,
-		index: u32,
-		block_num: u32,
-		segment_size: u32,
-		scan_size: u32,
-		miner_address: AccountOf<T>,
-		filler_id: BoundedVec<u8, T::StringLimit>,
-		filler_hash: BoundedVec<u8, T::StringLimit>,
+
+	#[storage_alias]
+	type UserHoldFileList<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		AccountOf<T>,
+		BoundedVec<UserFileSliceInfo, <T as Config>::StringLimit>,
+		ValueQuery,
+	>;
+
+	// The file lists are moved over the next blocks, `USER_FILE_MIGRATION_BATCH` files per block.
+	pub fn migrate<T: Config>() -> Weight {
+		if UserHoldFileList::<T>::iter_keys().next().is_some() {
+			<UserFileMigrating<T>>::put(true);
+		}
+
+		T::DbWeight::get().reads_writes(1, 1)
 	}
 
-	#[derive(Decode, Encode)]
-	struct NewFillerInfo<T: Config> {
-		filler_size: u64,
-		index: u32,
-		block_num: u32,
-		segment_size: u32,
-		scan_size: u32,
-		miner_address: AccountOf<T>,
-		filler_hash: Hash,
+	pub fn migrate_batch<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+		let mut budget = USER_FILE_MIGRATION_BATCH;
+
+		while budget > 0 {
+			weight = weight.saturating_add(T::DbWeight::get().reads(1));
+			let (acc, file_list) = match UserHoldFileList::<T>::iter().next() {
+				Some(entry) => entry,
+				None => {
+					<UserFileMigrating<T>>::kill();
+					log::info!("FileBank user file lists migrated");
+					weight = weight.saturating_add(T::DbWeight::get().writes(1));
+					break;
+				},
+			};
+
+			let moved = file_list.len().min(budget as usize);
+			for file_info in file_list.iter().take(moved) {
+				if let Err(e) = Pallet::<T>::add_user_hold_fileslice(&acc, file_info.file_hash, file_info.file_size) {
+					log::error!("move file {:?} of {:?} failed: {:?}", file_info.file_hash, acc, e);
+				}
+				weight = weight
+					.saturating_add(T::DbWeight::get().reads_writes(2, 2))
+					.saturating_add(<T as Config>::WeightInfo::user_file_root());
+			}
+			budget = budget.saturating_sub(moved as u32);
+
+			// The rest of a list that does not fit in the batch waits for the next block.
+			if moved < file_list.len() {
+				let rest: BoundedVec<UserFileSliceInfo, <T as Config>::StringLimit> =
+					file_list[moved..].to_vec().try_into().unwrap_or_default();
+				UserHoldFileList::<T>::insert(&acc, rest);
+			} else {
+				UserHoldFileList::<T>::remove(&acc);
+			}
+			weight = weight.saturating_add(T::DbWeight::get().writes(1));
+		}
+
+		weight
+	}
+}
+
+mod v5 {
+	use super::*;
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		for (miner, filler_hash) in <FillerMap<T>>::iter_keys() {
+			Pallet::<T>::index_filler(&miner, &filler_hash);
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 3));
+		}
+		for (file_hash, file) in <File<T>>::iter() {
+			if file.stat == FileState::Active {
+				Pallet::<T>::index_active_file(&file_hash);
+				weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 3));
+			}
+			weight = weight.saturating_add(T::DbWeight::get().reads(1));
+		}
+
+		weight
 	}
+}
 
-	generate_storage_alias!(
-		FileBank,
-		FillerMap<T: Config> => DoubleMap<
-            (Blake2_128Concat, AccountOf<T>),
-            (Blake2_128Concat, BoundedVec<u8, T::StringLimit>),
-            OldFillerInfo<T>
-        >
-	);
-
-	// generate_storage_alias!(
-	// 	FileBank,
-	// 	FillerMap<T: Config> => DoubleMap<
-  //           (Blake2_128Concat, T::AccountId),
-  //           (Blake2_128Concat, Hash),
-  //           NewFillerInfo<T>
-  //       >
-	// );
-
-	// #[derive(Decode, Encode)]
-	// struct OldSliceInfo<T: Config> {
-	// 	miner_id: u64,
-	// 	shard_size: u64,
-	// 	block_num: u32,
-	// 	shard_id: BoundedVec<u8, T::StringLimit>,
-	// 	miner_ip: BoundedVec<u8, T::StringLimit>,
-	// 	miner_acc: AccountOf<T>,
-	// }
-	//
-	// #[derive(Decode, Encode)]
-	// struct NewSliceInfo<T: Config> {
-	// 	miner_id: u64,
-	// 	shard_size: u64,
-	// 	block_num: u32,
-	// 	shard_id: [u8; 72],
-	// 	miner_ip: BoundedVec<u8, T::StringLimit>,
-	// 	miner_acc: AccountOf<T>,
-	// }
-	//
-	// generate_storage_alias!(
-	// 	FileBank,
-	// 	File
-	// );
-	//
-	// struct OldPackageDetails<T: Config> {
-	// 	pub(super) space: u128,
-	// 	pub(super) used_space: u128,
-	// 	pub(super) remaining_space: u128,
-	// 	pub(super) tenancy: u32,
-	// 	pub(super) package_type: u8,
-	// 	pub(super) start: BlockNumberOf<T>,
-	// 	pub(super) deadline: BlockNumberOf<T>,
-	// 	pub(super) state: BoundedVec<u8, T::StringLimit>,
-	// }
-	//
-	// struct NewPackageDetails<T: Config> {
-	// 	pub(super) space: u128,
-	// 	pub(super) used_space: u128,
-	// 	pub(super) remaining_space: u128,
-	// 	pub(super) tenancy: u32,
-	// 	pub(super) package_type: PackageType,
-	// 	pub(super) start: BlockNumberOf<T>,
-	// 	pub(super) deadline: BlockNumberOf<T>,
-	// 	pub(super) state: BoundedVec<u8, T::StringLimit>,
-	// }
+mod v6 {
+	use super::*;
+
+	#[derive(Decode)]
+	struct OldDealInfo<T: Config> {
+		stage: u8,
+		count: u8,
+		file_size: u128,
+		segment_list: BoundedVec<SegmentList<T>, T::SegmentCount>,
+		needed_list: BoundedVec<SegmentList<T>, T::SegmentCount>,
+		user: UserBrief<T>,
+		assigned_miner: BoundedVec<MinerTaskList<T>, T::StringLimit>,
+		share_info: BoundedVec<SegmentInfo<T>, T::SegmentCount>,
+		complete_list: BoundedVec<AccountOf<T>, T::FragmentCount>,
+	}
 
 	pub fn migrate<T: Config>() -> Weight {
-		let mut weight: Weight = 0;
-		log::info!("-----------------------------test migrations start-----------------------------------");
-		for (miner_acc, filler_id, old) in <FillerMap<T>>::iter() {
-			log::info!("-----------------------------migrations value filler_id:{:?}, len: {}", filler_id.clone(), filler_id.as_slice().len());
-			log::info!("old value filler_size: {}, index: {}, block_num: {}", old.filler_size, old.index, old.block_num);
-			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
-			let filler_hash = Hash::slice_to_array_64(&filler_id).expect("error!");
-			// {
-			// 	Ok(slice) => slice,
-			// 	Err(e) => {
-			// 		log::info!("convert err: {:?}", e);
-			// 		continue;
-			// 	},
-			// };
-			log::info!("convert success!");
-			let filler_hash = Hash(filler_hash);
-			let new_value = FillerInfo::<T>{
-				filler_size: old.filler_size,
-				index: old.index,
-				block_num: old.block_num,
-				segment_size: old.segment_size,
-				scan_size: old.scan_size,
-				miner_address: old.miner_address.clone(),
-				filler_hash: filler_hash.clone(),
+		let mut weight: Weight = Weight::from_ref_time(0);
+		let now = <frame_system::Pallet<T>>::block_number();
+		let mut dropped: Vec<(Hash, OldDealInfo<T>)> = Default::default();
+
+		<DealMap<T>>::translate::<OldDealInfo<T>, _>(|deal_hash, old| {
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(3, 2));
+			// A deal no scheduler can take is dropped, what it reserved is released below.
+			let scheduler = match Pallet::<T>::assign_scheduler() {
+				Ok(scheduler) => scheduler,
+				Err(e) => {
+					log::error!("no scheduler for deal {:?}: {:?}", deal_hash, e);
+					dropped.push((deal_hash, old));
+					return None;
+				},
 			};
-			log::info!("start insert");
-			<NewFillerMap<T>>::insert(miner_acc, filler_hash, new_value);
-			log::info!("end insert");
+			// The end of the current stage was not recorded, the task of the stage still runs.
+			Some(DealInfo::<T> {
+				stage: old.stage,
+				count: old.count,
+				file_size: old.file_size,
+				segment_list: old.segment_list,
+				needed_list: old.needed_list,
+				user: old.user,
+				assigned_miner: old.assigned_miner,
+				share_info: old.share_info,
+				complete_list: old.complete_list,
+				scheduler,
+				deadline: now,
+			})
+		});
+
+		for (deal_hash, old) in dropped.iter() {
+			if let Err(e) = Pallet::<T>::release_deal(deal_hash, &old.user.user, old.segment_list.len() as u128, &old.assigned_miner) {
+				log::error!("release dropped deal {:?} failed: {:?}", deal_hash, e);
+			}
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(6, 6));
 		}
-		log::info!("migrations end!");
+
 		weight
 	}
 }
@@ -20,16 +20,17 @@
 use super::*;
 use crate as file_bank;
 use frame_support::{
-    parameter_types,
+    assert_ok, parameter_types,
     weights::Weight,
-    traits::{ConstU32, EqualPrivilegeOnly, OneSessionHandler},
+    traits::{ConstU32, EqualPrivilegeOnly, Everything, OneSessionHandler},
 };
 use frame_system::{EnsureRoot};
 use sp_core::{H256, sr25519::Signature};
 use sp_runtime::{
     testing::{Header, TestXt, UintAuthorityId},
     traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentityLookup, IdentifyAccount, Verify},
-    Perbill,
+    transaction_validity::TransactionPriority,
+    Perbill, Percent,
 };
 use frame_support_test::TestRandomness;
 use frame_benchmarking::account;
@@ -52,6 +53,8 @@ type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
 type Balance = u64;
 
+pub(crate) const ONE_DAY: BlockNumber = 60 * 10 * 24;
+
 frame_support::construct_runtime!(
 	pub enum Test where
 		Block = Block,
@@ -62,6 +65,7 @@ frame_support::construct_runtime!(
 		Balances: pallet_balances,
 		FileBank: file_bank,
 		Sminer: pallet_sminer,
+		StorageHandler: pallet_storage_handler,
 		Scheduler: pallet_scheduler,
 		Timestamp: pallet_timestamp,
 		Staking: pallet_cess_staking,
@@ -75,18 +79,21 @@ frame_support::construct_runtime!(
 	}
 );
 
+parameter_types! {
+	#[derive(Clone, PartialEq, Eq)]
+	pub const P2PLength: u32 = 200;
+}
+
 impl pallet_oss::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type WeightInfo = ();
+	type P2PLength = P2PLength;
 }
 
 parameter_types! {
 	#[derive(Clone, PartialEq, Eq)]
 	pub const StringLimit: u32 = 100;
-	#[derive(Clone, PartialEq, Eq)]
-	pub const OneHours: u32 = 60 * 10;
-	#[derive(Clone, PartialEq, Eq)]
-	pub const OneDay: u32 = 60 * 10 * 24;
+	pub const OneDay: BlockNumber = ONE_DAY;
 }
 
 parameter_types! {
@@ -124,10 +131,17 @@ parameter_types! {
 	pub const ItemLimit: u32 = 1024;
 	pub const MaxAward: u128 = 1_306_849_000_000_000_000;
 	pub const LockInPeriod: u8 = 2;
+	pub const ChallengeMinerMax: u32 = 8000;
+	pub const MinerUnsignedPriority: TransactionPriority = TransactionPriority::max_value();
+	pub const MinerHeartbeatInterval: BlockNumber = 60 * 10;
+	pub const EndorsementInterval: BlockNumber = 60 * 10;
+	pub const MaxEndorsements: u32 = 100;
+	pub const EndorsementHalfStake: Balance = 10_000;
 }
 
 impl pallet_sminer::Config for Test {
 	type Currency = Balances;
+	type SlashPolicy = ();
 	// The ubiquitous event type.
 	type RuntimeEvent = RuntimeEvent;
 	type PalletId = RewardPalletId;
@@ -142,6 +156,14 @@ impl pallet_sminer::Config for Test {
 	type OneDayBlock = OneDay;
 	type MaxAward = MaxAward;
 	type LockInPeriod = LockInPeriod;
+	type ChallengeMinerMax = ChallengeMinerMax;
+	type AuthorityId = UintAuthorityId;
+	type UnsignedPriority = MinerUnsignedPriority;
+	type HeartbeatInterval = MinerHeartbeatInterval;
+	type Gateways = MockGateways;
+	type EndorsementInterval = EndorsementInterval;
+	type MaxEndorsements = MaxEndorsements;
+	type EndorsementHalfStake = EndorsementHalfStake;
 }
 
 parameter_types! {
@@ -150,6 +172,17 @@ parameter_types! {
 	pub const SchedulerMaximum: u32 = 10000;
 	#[derive(Clone, PartialEq, Eq)]
 	pub const ParamsLimit: u32 = 359;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const MaxWhitelist: u32 = 200;
+	pub const HeartbeatInterval: BlockNumber = 10;
+	pub const OfflineThreshold: u32 = 3;
+	pub const EvictionThreshold: u32 = 24;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const MaxEndpoints: u32 = 8;
+	pub const CommitteeSize: u32 = 5;
+	pub const TeeWorkerBond: Balance = 1_000;
+	pub const BondSlashFraction: Perbill = Perbill::from_percent(10);
+	pub const BondUnbondingPeriod: BlockNumber = 100;
 }
 
 impl pallet_tee_worker::Config for Test {
@@ -161,6 +194,60 @@ impl pallet_tee_worker::Config for Test {
 	type CreditCounter = SchedulerCredit;
 	type SchedulerMaximum = SchedulerMaximum;
 	type ParamsLimit = ParamsLimit;
+	type MaxWhitelist = MaxWhitelist;
+	type OnExit = ();
+	type HeartbeatInterval = HeartbeatInterval;
+	type OfflineThreshold = OfflineThreshold;
+	type EvictionThreshold = EvictionThreshold;
+	type MaxEndpoints = MaxEndpoints;
+	type CommitteeSize = CommitteeSize;
+	type TeeWorkerBond = TeeWorkerBond;
+	type BondSlashFraction = BondSlashFraction;
+	type BondUnbondingPeriod = BondUnbondingPeriod;
+	type BondSlash = ();
+	type UnixTime = Timestamp;
+}
+
+parameter_types! {
+	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
+	#[derive(Clone, PartialEq, Eq)]
+	pub const StateStringMax: u32 = 20;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const FrozenDays: BlockNumber = 7 * ONE_DAY;
+	pub const MaxLedgerEntries: u32 = 10;
+	pub const TrialDeposit: Balance = 10;
+	pub const TrialDepositPeriod: BlockNumber = 100;
+	pub const PriceUnsignedPriority: TransactionPriority = TransactionPriority::max_value();
+	pub const PriceInterval: BlockNumber = 100;
+	pub const MinPriceSources: u32 = 1;
+	pub const MaxPriceDeviation: Perbill = Perbill::from_percent(20);
+	pub const MinPriceReports: u32 = 1;
+	pub const PricingAnnouncePeriod: BlockNumber = 100;
+}
+
+impl pallet_storage_handler::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type WeightInfo = ();
+	type OneDay = OneDay;
+	type FilbakPalletId = FilbakPalletId;
+	type TreasuryPalletId = TreasuryPalletId;
+	type StateStringMax = StateStringMax;
+	type FrozenDays = FrozenDays;
+	type MaxLedgerEntries = MaxLedgerEntries;
+	type TrialDeposit = TrialDeposit;
+	type TrialDepositPeriod = TrialDepositPeriod;
+	type AuthorityId = pallet_storage_handler::sr25519::AuthorityId;
+	type PriceAuthorities = Everything;
+	type PriceFeederOrigin = EnsureRoot<AccountId>;
+	type UnsignedPriority = PriceUnsignedPriority;
+	type PriceInterval = PriceInterval;
+	type MinPriceSources = MinPriceSources;
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type MinPriceReports = MinPriceReports;
+	type PricingAnnouncePeriod = PricingAnnouncePeriod;
+	type PricingCancelOrigin = EnsureRoot<AccountId>;
+	type PackagePayment = ();
 }
 
 const THRESHOLDS: [sp_npos_elections::VoteWeight; 9] =
@@ -240,6 +327,7 @@ impl pallet_cess_staking::Config for Test {
     type RewardRemainder = ();
     type RuntimeEvent = RuntimeEvent;
     type Slash = ();
+    type SlashPolicy = ();
     type Reward = ();
     type SessionsPerEra = ();
     type BondingDuration = ();
@@ -378,10 +466,12 @@ for MockStashAccountFinder<AccountId>
 
 parameter_types! {
 	pub const PeriodDuration: BlockNumber = 64_000;
+	pub const CreditDecay: Percent = Percent::from_percent(20);
 }
 impl pallet_scheduler_credit::Config for Test {
 	type StashAccountFinder = MockStashAccountFinder<Self::AccountId>;
 	type PeriodDuration = PeriodDuration;
+	type CreditDecay = CreditDecay;
 }
 
 parameter_types! {
@@ -414,7 +504,10 @@ parameter_types! {
 	#[derive(Clone, Eq, PartialEq)]
 	pub const UploadFillerLimit: u8 = 10;
 	#[derive(Clone, Eq, PartialEq)]
+	pub const BatchDeleteLimit: u32 = 100;
+	#[derive(Clone, Eq, PartialEq)]
 	pub const InvalidLimit: u32 = 100000;
+	pub const InvalidFileGracePeriod: u64 = 100;
 	#[derive(Clone, Eq, PartialEq)]
 	pub const RecoverLimit: u32 = 8000;
 	#[derive(Clone, Eq, PartialEq)]
@@ -422,35 +515,107 @@ parameter_types! {
 	#[derive(Clone, Eq, PartialEq)]
 	pub const NameStrLimit: u32 = 63;
 	#[derive(Clone, Eq, PartialEq)]
-	pub const MinLength: u32 = 3;
+	pub const NameMinLength: u32 = 3;
 	#[derive(Clone, Eq, PartialEq)]
 	pub const FileListLimit: u32 = 500000;
 	#[derive(Clone, Eq, PartialEq)]
-	pub const FrozenDays: BlockNumber = 60 * 10 * 24 * 7;
+	pub const SegmentCount: u32 = 100;
+	#[derive(Clone, Eq, PartialEq)]
+	pub const FragmentCount: u32 = 3;
+	#[derive(Clone, Eq, PartialEq)]
+	pub const OwnerLimit: u32 = 50000;
+	#[derive(Clone, Eq, PartialEq)]
+	pub const RestoralOrderLife: u32 = 250;
+	#[derive(Clone, Eq, PartialEq)]
+	pub const MissionCount: u32 = 300;
+	#[derive(Clone, Eq, PartialEq)]
+	pub const VersionLimit: u32 = 20;
+	pub const DeclarationEra: BlockNumber = ONE_DAY;
+	pub const DeleteGracePeriod: BlockNumber = 7 * ONE_DAY;
+	pub const DownloadOwnerShare: Perbill = Perbill::from_percent(70);
+	pub const DownloadMinerShare: Perbill = Perbill::from_percent(20);
+	pub const PopularityEra: BlockNumber = ONE_DAY;
+	pub const PopularityDecay: Perbill = Perbill::from_percent(50);
+	pub const MaxReceiptsPerEra: u32 = 1000;
+	pub const MaxPopularityGain: u32 = 10_000;
+	pub const AuctionThreshold: u128 = 100 * G_BYTE;
+	pub const AuctionPeriod: BlockNumber = 100;
+	pub const MinAuctionBidders: u32 = 3;
+	pub const DedupDiscount: Perbill = Perbill::from_percent(50);
+	pub const MaxPendingDeals: u32 = 50;
+	pub const InvalidFileReminderPeriod: BlockNumber = ONE_DAY;
+	pub const InvalidFileReminderPriority: TransactionPriority = TransactionPriority::max_value();
+	pub const DeclarationDeposit: Balance = 10;
+	pub const MaxDeclarationsPerBlock: u32 = 10;
+	pub const StorageOrdersEnabled: bool = true;
+}
+
+// Only `gateway1` serves downloads in these tests.
+pub struct MockGateways;
+
+impl Contains<AccountId> for MockGateways {
+	fn contains(acc: &AccountId) -> bool {
+		*acc == gateway1()
+	}
 }
 
 impl Config for Test {
 	type RuntimeEvent = RuntimeEvent;
-	type Currency = Balances;
 	type WeightInfo = ();
 	type RuntimeCall = RuntimeCall;
+	type FScheduler = Scheduler;
+	type AScheduler = Scheduler;
+	type SPalletsOrigin = OriginCaller;
+	type SProposal = RuntimeCall;
 	type FindAuthor = ();
-	type CreditCounter = SchedulerCredit;
-	type Scheduler = pallet_tee_worker::Pallet::<Test>;
-	type MinerControl = pallet_sminer::Pallet::<Test>;
+	type Scheduler = TeeWorker;
+	type MinerControl = Sminer;
 	type MyRandomness = TestRandomness<Self>;
+	type StorageHandle = StorageHandler;
 	type FilbakPalletId = FilbakPalletId;
 	type StringLimit = StringLimit;
 	type OneDay = OneDay;
-	type FileListLimit = FileListLimit;
+	type UploadFillerLimit = UploadFillerLimit;
+	type BatchDeleteLimit = BatchDeleteLimit;
+	type RecoverLimit = RecoverLimit;
+	type InvalidLimit = InvalidLimit;
+	type InvalidFileGracePeriod = InvalidFileGracePeriod;
 	type NameStrLimit = NameStrLimit;
+	type FileListLimit = FileListLimit;
 	type BucketLimit = BucketLimit;
+	type NameMinLength = NameMinLength;
+	type SegmentCount = SegmentCount;
+	type FragmentCount = FragmentCount;
+	type OwnerLimit = OwnerLimit;
+	type RestoralOrderLife = RestoralOrderLife;
+	type EventRouter = ();
 	type OssFindAuthor = Oss;
-	type FrozenDays = FrozenDays;
-	type RecoverLimit = RecoverLimit;
-	type InvalidLimit = InvalidLimit;
-	type UploadFillerLimit = UploadFillerLimit;
-	type MinLength = MinLength;
+	type MissionCount = MissionCount;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type VersionLimit = VersionLimit;
+	type DeclarationEra = DeclarationEra;
+	type DeleteGracePeriod = DeleteGracePeriod;
+	type Currency = Balances;
+	type TreasuryPalletId = TreasuryPalletId;
+	type OwnerShare = DownloadOwnerShare;
+	type MinerShare = DownloadMinerShare;
+	type PopularityEra = PopularityEra;
+	type PopularityDecay = PopularityDecay;
+	type MaxReceiptsPerEra = MaxReceiptsPerEra;
+	type MaxPopularityGain = MaxPopularityGain;
+	type AuctionThreshold = AuctionThreshold;
+	type AuctionPeriod = AuctionPeriod;
+	type MinAuctionBidders = MinAuctionBidders;
+	type DedupDiscount = DedupDiscount;
+	type Gateways = MockGateways;
+	type MaxPendingDeals = MaxPendingDeals;
+	type AuthorityId = UintAuthorityId;
+	type Authorities = Everything;
+	type InvalidFileReminderPeriod = InvalidFileReminderPeriod;
+	type UnsignedPriority = InvalidFileReminderPriority;
+	type DeclarationDeposit = DeclarationDeposit;
+	type MaxDeclarationsPerBlock = MaxDeclarationsPerBlock;
+	type StorageOrdersEnabled = StorageOrdersEnabled;
 }
 
 pub fn account1() -> AccountId {
@@ -461,6 +626,10 @@ pub fn account2() -> AccountId {
     account("account2", 0, 0)
 }
 
+pub fn account3() -> AccountId {
+    account("account3", 0, 0)
+}
+
 pub fn miner1() -> AccountId {
     account("miner1", 0, 0)
 }
@@ -473,28 +642,42 @@ pub fn controller1() -> AccountId {
     account("controller1", 0, 0)
 }
 
+pub fn gateway1() -> AccountId {
+    account("gateway1", 0, 0)
+}
+
 pub fn new_test_ext() -> sp_io::TestExternalities {
     let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
     pallet_balances::GenesisConfig::<Test> {
         balances: vec![
             (account1(), 18_000_000_000_000_000_000),
             (account2(), 1_000_000_000_000),
+            (account3(), 1_000_000_000_000),
             (miner1(), 1_000_000_000_000),
             (stash1(), 1_000_000_000_000),
             (controller1(), 1_000_000_000_000),
+            (gateway1(), 1_000_000_000_000),
         ],
     }
         .assimilate_storage(&mut t)
         .unwrap();
-		file_bank::GenesisConfig::<Test> {
-			price: 30
-		}
-			.assimilate_storage(&mut t)
-			.unwrap();
+    pallet_storage_handler::GenesisConfig::<Test> {
+        price: 30,
+    }
+        .assimilate_storage(&mut t)
+        .unwrap();
+	frame_support::traits::GenesisBuild::<Test>::assimilate_storage(
+		&file_bank::GenesisConfig {
+			declaration_limit: None,
+			file_size_limit: None,
+		},
+		&mut t,
+	)
+		.unwrap();
     let mut ext = sp_io::TestExternalities::new(t);
     ext.execute_with(|| {
         System::set_block_number(1); //must set block_number, otherwise the deposit_event() don't work
+        assert_ok!(<StorageHandler as StorageHandle<AccountId>>::add_total_idle_space(1000 * G_BYTE));
     });
     ext
 }
-
@@ -6,86 +6,35 @@ use super::*;
 use crate::{mock::*, Event};
 use mock::System as Sys;
 use frame_support::{assert_ok, assert_noop};
-use cp_cess_common::{IpAddress, Hash};
-use pallet_sminer::MinerControl;
-
-
-#[derive(Debug, Clone)]
-pub struct MockingFileBankInfo {
-    file_hash: Hash,
-    file_size: u64,
-	  slice_info: Vec<SliceInfo<Test>>,
-}
-
-type Balance = u64;
-const UNIT_PRICE: Balance = 30;
-
-impl Default for MockingFileBankInfo {
-    fn default() -> Self {
-			let file_hash = Hash([5u8; 64]);
-			let mut file_hash1: Vec<u8> = file_hash.0.to_vec();
-			file_hash1.append("-001".as_bytes().to_vec().as_mut());
-			let file_hash1: [u8; 68] = file_hash1.as_slice().try_into().unwrap();
-
-			let mut file_hash2: Vec<u8> = file_hash.0.to_vec();
-			file_hash2.append("-002".as_bytes().to_vec().as_mut());
-			let file_hash2: [u8; 68] = file_hash1.as_slice().try_into().unwrap();
-
-			let mut file_hash3: Vec<u8> = file_hash.0.to_vec();
-			file_hash3.append("-002".as_bytes().to_vec().as_mut());
-			let file_hash3: [u8; 68] = file_hash1.as_slice().try_into().unwrap();
-        MockingFileBankInfo {
-            file_hash: file_hash,
-            file_size: 12,
-            slice_info: vec![SliceInfo::<Test>{
-                miner_id: 1,
-                shard_size: 111,
-                block_num: 8,
-                shard_id: file_hash1,
-                miner_ip: IpAddress::IPV4([127,0,0,1], 15000),
-                miner_acc: mock::miner1(),
-            },
-            SliceInfo::<Test>{
-                miner_id: 1,
-                shard_size: 111,
-                block_num: 8,
-                shard_id: file_hash2,
-                miner_ip: IpAddress::IPV4([127,0,0,1], 15000),
-                miner_acc: mock::miner1(),
-            },
-            SliceInfo::<Test>{
-                miner_id: 1,
-                shard_size: 111,
-                block_num: 8,
-                shard_id: file_hash3,
-                miner_ip: IpAddress::IPV4([127,0,0,1], 15000),
-                miner_acc: mock::miner1(),
-            },
-            ]
-        }
+
+const BUCKET_NAME: &str = "cess-bucket";
+const FILE_NAME: &str = "cess-book";
+
+fn user_brief(acc: AccountId) -> UserBrief<Test> {
+    UserBrief::<Test> {
+        user: acc,
+        file_name: FILE_NAME.as_bytes().to_vec().try_into().unwrap(),
+        bucket_name: BUCKET_NAME.as_bytes().to_vec().try_into().unwrap(),
     }
 }
 
-fn upload_declaration_alias(account: AccountId, file_name: Vec<u8>, file_hash: Hash, bucket_name: Vec<u8>) -> DispatchResult {
-		let user_brief = UserBrief::<Test>{
-			user: account.clone(),
-			file_name: file_name.try_into().unwrap(),
-			bucket_name: bucket_name.try_into().unwrap(),
-		};
-    FileBank::upload_declaration(
-        RuntimeOrigin::signed(account.clone()),
-        file_hash,
-        user_brief,
-    )
+// One segment of three fragments, the spec of the mock.
+fn deal_info() -> BoundedVec<SegmentList<Test>, SegmentCount> {
+    let segment = SegmentList::<Test> {
+        hash: Hash([1u8; 64]),
+        fragment_list: vec![Hash([2u8; 64]), Hash([3u8; 64]), Hash([4u8; 64])].try_into().unwrap(),
+    };
+    vec![segment].try_into().unwrap()
 }
 
-fn upload_file_alias(_account: AccountId, controller: AccountId, file_info: &MockingFileBankInfo) -> DispatchResult {
-    let MockingFileBankInfo { file_hash, file_size, slice_info} = file_info.clone();
-    FileBank::upload(
-        RuntimeOrigin::signed(controller),
-        file_hash,
-        file_size,
-        slice_info,
+fn upload_declaration_alias(account: AccountId, file_hash: Hash) -> DispatchResult {
+    FileBank::upload_declaration(
+        RuntimeOrigin::signed(account.clone()),
+        file_hash.into(),
+        deal_info(),
+        user_brief(account),
+        SEGMENT_SIZE,
+        None,
     )
 }
 
@@ -97,137 +46,90 @@ fn create_new_bucket(acc: AccountId, bucket_name: Vec<u8>) -> DispatchResult {
 	)
 }
 
-fn register_scheduler(stash: AccountId, controller: AccountId) -> DispatchResult {
-    pallet_cess_staking::Bonded::<Test>::insert(&stash, controller.clone());
-    TeeWorker::registration_scheduler(
-        RuntimeOrigin::signed(controller),
-        stash,
-        IpAddress::IPV4([127,0,0,1], 15000),
-    )
+fn buy_space(acc: AccountId) -> DispatchResult {
+    StorageHandler::buy_space(RuntimeOrigin::signed(acc), 1, None, None)
+}
 
+fn used_space(acc: &AccountId) -> u128 {
+    <StorageHandler as StorageHandle<AccountId>>::get_user_used_space(acc).unwrap()
 }
 
 fn register_miner(miner: AccountId) -> DispatchResult {
+    let peer_id = peer::peer_id_from_node_key(&NodePublicKey::from_raw([1u8; 32]));
     Sminer::regnstk(
-        RuntimeOrigin::signed(miner),
-        miner.clone(),
-				IpAddress::IPV4([127,0,0,1], 15000),
-        2_000u128.try_into().unwrap(),
+        RuntimeOrigin::signed(miner.clone()),
+        miner,
+        peer_id,
+        2_000u64,
     )
 }
 
-fn add_power_for_miner(controller: AccountId, miner: AccountId) -> DispatchResult {
-    let max: u8 = 10;
-    let mut filler_list: Vec<FillerInfo<Test>> = Vec::new();
-    for i in 0 .. max {
-        let filler_hash: Hash = Hash([i; 64]);
-        filler_list.push(
-            FillerInfo::<Test> {
-                index: 1,
-                filler_size: 8 * 1_048_576,
-                block_num: 8,
-                segment_size: 1_048_576,
-                scan_size: 1_048_576,
-                miner_address: miner.clone(),
-                filler_hash: filler_hash.clone(),
-            }
-        )
+// Stores an active file for `acc` in its bucket and charges it to its package,
+// the way `activate_file` leaves a file once the miners reported its fragments.
+fn add_active_file(acc: AccountId, file_hash: Hash) {
+    let segment = deal_info()[0].clone();
+    let task = MinerTaskList::<Test> {
+        miner: miner1(),
+        fragment_list: segment.fragment_list.to_vec().try_into().unwrap(),
+    };
+    let file_size = FileBank::cal_file_size(1);
+    assert_ok!(FileBank::generate_file(
+        &file_hash,
+        deal_info(),
+        vec![task].try_into().unwrap(),
+        Vec::new(),
+        user_brief(acc.clone()),
+        FileState::Active,
+        SEGMENT_SIZE,
+    ));
+    assert_ok!(<StorageHandler as StorageHandle<AccountId>>::update_user_space(&acc, 1, file_size));
+    let bucket_name = user_brief(acc.clone()).bucket_name;
+    if <Bucket<Test>>::contains_key(&acc, &bucket_name) {
+        assert_ok!(FileBank::add_file_to_bucket(&acc, &bucket_name, &file_hash));
+    } else {
+        assert_ok!(FileBank::create_bucket_helper(&acc, &bucket_name, Some(file_hash)));
     }
-    FileBank::upload_filler(RuntimeOrigin::signed(controller), miner, filler_list)?;
-
-    Ok(())
+    assert_ok!(FileBank::add_user_hold_fileslice(&acc, file_hash, file_size));
+    FileBank::index_active_file(&file_hash);
 }
 
-#[test]
-fn buy_space_works() {
-    new_test_ext().execute_with(|| {
-        let acc1 = mock::account1();
-        let _bal_before = Balances::free_balance(acc1);
-        let miner1 = mock::miner1();
-        let space_gb = 10_u128;
-        let bn = Sys::block_number();
-
-        assert_ok!(register_miner(miner1.clone()));
-        assert_ok!(Sminer::add_power(&miner1, 1_048_576 * 1024 * space_gb));  // GB => MB
-
-        assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), 10));
-
-        let uhsd = UserOwnedSpace::<Test>::try_get(acc1).unwrap();
-        assert_eq!(space_gb * 1024 * 1_048_576, uhsd.total_space); // MB unit
-        assert_eq!(14400 * 30 + bn as u128, uhsd.deadline as u128);
-
-        assert_eq!(uhsd.total_space, uhsd.remaining_space);
-				let price = UNIT_PRICE * space_gb as u64;
-
-        let event = Sys::events().pop().expect("Expected at least one BuySpace to be found").event;
-        assert_eq!(mock::RuntimeEvent::from(Event::BuySpace { acc: acc1, storage_capacity: space_gb * 1024 * 1_048_576, spend: price }), event);
-    });
+// A stored file held by `account1` and by `account2`, so that deleting it
+// from one of them keeps the fragments on the miners.
+fn add_shared_file(file_hash: Hash) {
+    assert_ok!(buy_space(account1()));
+    assert_ok!(buy_space(account2()));
+    add_active_file(account1(), file_hash);
+    assert_ok!(upload_declaration_alias(account2(), file_hash));
 }
 
-#[test]
-fn expansion_space_work() {
-    new_test_ext().execute_with(|| {
-        let acc1 = mock::account1();
-        let miner1 = mock::miner1();
-        let space_gb = 1000_u128;
-        assert_ok!(register_miner(miner1.clone()));
-        assert_ok!(Sminer::add_power(&miner1, 1_048_576 * 1024 * space_gb));  // GB => MB
-
-        assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), 1));
-        let uhsd = UserOwnedSpace::<Test>::try_get(acc1).unwrap();
-        assert_eq!(1 * 1024 * 1_048_576, uhsd.total_space); // MB unit
-
-        assert_ok!(FileBank::expansion_space(RuntimeOrigin::signed(acc1), 1));
-				let price = UNIT_PRICE * 1;
-
-        let event = Sys::events().pop().expect("Expected at least one BuySpace to be found").event;
-        assert_eq!(mock::RuntimeEvent::from(Event::ExpansionSpace { acc: acc1, expansion_space: 1024 * 1024 * 1024, fee: price,}), event);
-
-        let uhsd = UserOwnedSpace::<Test>::try_get(acc1).unwrap();
-        assert_eq!(2 * 1024 * 1_048_576, uhsd.total_space); // MB unit
-    });
+fn initialize_block(n: u64) {
+    Sys::set_block_number(n);
+    FileBank::on_initialize(n);
 }
 
-#[test]
-fn renewal_space_work() {
-    new_test_ext().execute_with(|| {
-			let acc1 = mock::account1();
-			let miner1 = mock::miner1();
-			let space_gb = 1000_u128;
-			assert_ok!(register_miner(miner1.clone()));
-			assert_ok!(Sminer::add_power(&miner1, 1_048_576 * 1024 * space_gb));  // GB => MB
-
-			let gib_count = 2;
-			assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), gib_count));
-			let uhsd = UserOwnedSpace::<Test>::try_get(acc1).unwrap();
-			assert_eq!(2 * 1024 * 1_048_576, uhsd.total_space); // MB unit
-
-			let renewal_days = 32;
-			let price = UNIT_PRICE / 30 * (renewal_days as u64) * (gib_count as u64);
-
-			assert_ok!(FileBank::renewal_space(RuntimeOrigin::signed(acc1), renewal_days));
-			let event = Sys::events().pop().expect("Expected at least one BuySpace to be found").event;
-			assert_eq!(mock::RuntimeEvent::from(Event::RenewalSpace { acc: acc1, renewal_days, fee: price}), event);
-
-			let uhsd = UserOwnedSpace::<Test>::try_get(acc1).unwrap();
-			assert_eq!(2 * 1024 * 1_048_576, uhsd.total_space);
-			assert_eq!(1, uhsd.start);
-			assert_eq!(62 * 14400 + 1, uhsd.deadline);
-    });
+fn last_event() -> RuntimeEvent {
+    Sys::events().pop().expect("Expected at least one event").event
 }
 
 #[test]
-fn upload_declaration() {
+fn upload_declaration_without_permission_fails() {
     new_test_ext().execute_with(|| {
-			let acc1 = mock::account1();
-			let file_name = "cess-book".as_bytes().to_vec();
-			let file_hash = Hash([5u8; 64]);
-			let bucket_name: Vec<u8> = "cess-bucket".as_bytes().to_vec();
-
-			assert_noop!(upload_declaration_alias(acc1.clone(), file_name.clone(), file_hash.clone(), bucket_name.clone()), Error::<Test>::NonExistent);
-			assert_ok!(create_new_bucket(acc1.clone(), bucket_name.clone()));
-
-			assert_ok!(upload_declaration_alias(acc1, file_name, file_hash, bucket_name));
+        let acc1 = mock::account1();
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+
+        assert_noop!(
+            FileBank::upload_declaration(
+                RuntimeOrigin::signed(acc2),
+                file_hash.into(),
+                deal_info(),
+                user_brief(acc1),
+                SEGMENT_SIZE,
+                None,
+            ),
+            Error::<Test>::NoPermission
+        );
     });
 }
 
@@ -235,71 +137,40 @@ fn upload_declaration() {
 fn upload_works() {
     new_test_ext().execute_with(|| {
         let acc1 = mock::account1();
-        let miner1 = mock::miner1();
-        let stash1 = mock::stash1();
-        let controller1 = mock::controller1();
-        let mfi = MockingFileBankInfo::default();
-				let bucket_name = "cess-bucket".as_bytes().to_vec();
-				let file_name = "cess-book".as_bytes().to_vec();
-
-				assert_ok!(create_new_bucket(acc1.clone(), bucket_name.clone()));
-        assert_ok!(upload_declaration_alias(acc1, file_name.clone(), mfi.file_hash.clone(), bucket_name.clone()));
-        assert_noop!(upload_file_alias(acc1, controller1, &mfi), Error::<Test>::ScheduleNonExistent);
-        assert_ok!(register_miner(miner1));
-        assert_ok!(register_scheduler(stash1.clone(), controller1.clone()));
-        //upload() file not work have not buy space
-
-
-        let space_gb = 20_u128;
-        assert_ok!(Sminer::add_power(&miner1, 1_048_576 * 1024 * space_gb));
-        assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), 2));
-        assert_ok!(add_power_for_miner(controller1, miner1));
-
-        assert_ok!(upload_file_alias(acc1, controller1, &mfi));
-
-        let file_hash = mfi.file_hash;
-        let file_size = mfi.file_size;
-        let file_slice_info = UserFileSliceInfo{
-            file_hash: file_hash.clone(),
-            file_size: file_size,
-        };
-        assert!(File::<Test>::contains_key(&file_hash));
-        let t = UserOwnedSpace::<Test>::try_get(acc1).unwrap();
-        assert_eq!(mfi.file_size as u128, t.used_space);
-        assert_eq!(t.total_space - mfi.file_size as u128, t.remaining_space);
-        assert!(UserHoldFileList::<Test>::try_get(acc1).unwrap().contains(&file_slice_info));
-
-        let event = Sys::events().pop().expect("Expected at least one FileUpload to be found").event;
-        assert_eq!(mock::RuntimeEvent::from(Event::FileUpload { acc: controller1 }), event);
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        assert_ok!(buy_space(acc2.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        // The file is already stored, the second owner holds it at a discount.
+        assert_ok!(upload_declaration_alias(acc2.clone(), file_hash));
+
+        let charged = FileBank::deduplicated_space(FileBank::cal_file_size(1));
+        assert!(FileBank::check_is_file_owner(&acc2, &file_hash));
+        assert_eq!(FileRefCount::<Test>::get(&file_hash), 2);
+        assert_eq!(used_space(&acc2), charged);
+        assert_eq!(UserFileIndex::<Test>::get(&acc2).unwrap().count, 1);
+        assert_eq!(last_event(), mock::RuntimeEvent::from(Event::UploadDeclaration {
+            operator: acc2.clone(),
+            owner: acc2,
+            deal_hash: file_hash,
+            file_size: SEGMENT_SIZE,
+            charged_space: charged,
+            deduplicated: true,
+        }));
     });
 }
 
 #[test]
 fn upload_should_not_work_when_insufficient_storage() {
     new_test_ext().execute_with(|| {
-        let acc1 = mock::account1();
-        let stash1 = mock::stash1();
-        let miner1 = mock::miner1();
-        let controller1 = mock::controller1();
-        let mut mfi = MockingFileBankInfo::default();
-        let space_gb = 20_u128;
-				let bucket_name = "cess-bucket".as_bytes().to_vec();
-        assert_ok!(register_miner(miner1));
-        assert_ok!(register_scheduler(stash1.clone(), controller1.clone()));
-        assert_ok!(add_power_for_miner(controller1, miner1));
-        assert_ok!(Sminer::add_power(&miner1, 1_048_576 * 1024 * space_gb));
-        assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), 2));
-        mfi.file_size = 3 * 1024 * 1024 * 1024;  // large file
-
-				assert_ok!(create_new_bucket(acc1.clone(), bucket_name.clone()));
-				assert_ok!(upload_declaration_alias(acc1, "cess-book".as_bytes().to_vec(), mfi.file_hash, bucket_name.clone()));
-        assert_noop!(upload_file_alias(acc1.clone(), controller1.clone(), &mfi), Error::<Test>::InsufficientStorage);
-
-        if let Err(e) = upload_file_alias(acc1, controller1, &mfi) {
-            if let DispatchError::Module(m) = e {
-                assert_eq!("InsufficientStorage", m.message.unwrap());
-            }
-        }
+        let file_hash = Hash([5u8; 64]);
+
+        assert_noop!(
+            upload_declaration_alias(mock::account1(), file_hash),
+            pallet_storage_handler::Error::<Test>::NotPurchasedSpace
+        );
     })
 }
 
@@ -307,78 +178,31 @@ fn upload_should_not_work_when_insufficient_storage() {
 fn delete_file_works() {
     new_test_ext().execute_with(|| {
         let acc1 = mock::account1();
-        let stash1 = mock::stash1();
-        let miner1 = mock::miner1();
-        let controller1 = mock::controller1();
-        let mfi = MockingFileBankInfo::default();
-				let bucket_name = "cess-bucket".as_bytes().to_vec();
-        assert_noop!(FileBank::delete_file(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), mfi.file_hash.clone()), Error::<Test>::FileNonExistent);
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        assert_noop!(FileBank::delete_file(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), vec![file_hash.into()]), Error::<Test>::NonExistent);
+        add_shared_file(file_hash);
 
-        let space_gb = 20_u128;
-        assert_ok!(register_miner(miner1.clone()));
-        assert_ok!(Sminer::add_power(&miner1, 1_048_576 * 1024 * space_gb));
-        assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), 2));
-        // acc1 upload file
-        assert_ok!(register_scheduler(stash1.clone(), controller1.clone()));
-				assert_ok!(create_new_bucket(acc1.clone(), bucket_name.clone()));
-        assert_ok!(upload_declaration_alias(acc1, "cess-book".as_bytes().to_vec(), mfi.file_hash, bucket_name.clone()));
-        assert_ok!(add_power_for_miner(controller1, miner1));
-        assert_ok!(upload_file_alias(acc1, controller1, &mfi));
-        assert_noop!(FileBank::delete_file(RuntimeOrigin::signed(mock::account2()), mock::account2(), mfi.file_hash.clone()), Error::<Test>::NotOwner);
-        let ss_before = UserOwnedSpace::<Test>::try_get(acc1).unwrap();
-
-        assert_ok!(FileBank::delete_file(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), mfi.file_hash.clone()));
-
-        let ss_after = UserOwnedSpace::<Test>::try_get(acc1).unwrap();
-        assert!(!File::<Test>::contains_key(&mfi.file_hash));
-        assert_ne!(ss_before.remaining_space, ss_after.remaining_space);
-
-        let event = Sys::events().pop().expect("Expected at least one DeleteFile to be found").event;
-        assert_eq!(mock::RuntimeEvent::from(Event::DeleteFile { operator: acc1.clone(), owner: acc1.clone(), file_hash: mfi.file_hash }), event);
-    });
-}
+        assert_noop!(FileBank::delete_file(RuntimeOrigin::signed(mock::account3()), acc2.clone(), vec![file_hash.into()]), Error::<Test>::NoPermission);
+        assert_ok!(FileBank::delete_file(RuntimeOrigin::signed(acc2.clone()), acc2.clone(), vec![file_hash.into()]));
 
-#[test]
-fn upload_filler_work() {
-    new_test_ext().execute_with(|| {
-        let stash1 = mock::stash1();
-        let miner1 = mock::miner1();
-        let controller1 = mock::controller1();
-        assert_noop!(add_power_for_miner(controller1.clone(), miner1.clone()), Error::<Test>::ScheduleNonExistent);
-        assert_ok!(register_scheduler(stash1.clone(), controller1.clone()));
-        assert_noop!(add_power_for_miner(controller1.clone(), miner1.clone()), pallet_sminer::Error::<Test>::NotMiner);
-        assert_ok!(register_miner(miner1));
-        assert_ok!(add_power_for_miner(controller1.clone(), miner1.clone()));
-
-        let (power, _) = Sminer::get_power_and_space(miner1.clone()).unwrap();
-        assert_eq!(1_048_576 * 8 * 10, power);
+        assert!(File::<Test>::contains_key(&file_hash));
+        assert!(!FileBank::check_is_file_owner(&acc2, &file_hash));
+        assert_eq!(used_space(&acc2), 0);
+        assert_eq!(last_event(), mock::RuntimeEvent::from(Event::DeleteFile { operator: acc2.clone(), owner: acc2, file_hash_list: vec![file_hash] }));
     });
 }
 
 #[test]
 fn clear_invalid_file_work() {
     new_test_ext().execute_with(|| {
-        let acc1 = mock::account1();
-        let stash1 = mock::stash1();
         let miner1 = mock::miner1();
-        let controller1 = mock::controller1();
-        let mfi = MockingFileBankInfo::default();
-				let bucket_name = "cess-bucket".as_bytes().to_vec();
+        let file_hash = Hash([5u8; 64]);
+        assert_noop!(FileBank::clear_invalid_file(RuntimeOrigin::signed(miner1.clone()), file_hash.into()), Error::<Test>::NonExistent);
 
-        assert_ok!(register_miner(miner1.clone()));
-        assert_ok!(Sminer::add_power(&miner1 ,1_048_576 * 1024 * 20));
-        assert_ok!(register_scheduler(stash1.clone(), controller1.clone()));
-
-				assert_ok!(create_new_bucket(acc1.clone(), bucket_name.clone()));
-        assert_ok!(upload_declaration_alias(acc1, "cess-book".as_bytes().to_vec(), mfi.file_hash, bucket_name));
-        assert_ok!(add_power_for_miner(controller1, miner1));
-        assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), 2));
-        assert_ok!(upload_file_alias(acc1, controller1, &mfi));
-
-        let mut file_hash_list = InvalidFile::<Test>::get(miner1.clone());
-        assert_ok!(FileBank::clear_invalid_file(RuntimeOrigin::signed(miner1.clone()), file_hash_list[0]));
-        file_hash_list.remove(0);
-        assert_eq!(file_hash_list, InvalidFile::<Test>::get(miner1.clone()));
+        InvalidFile::<Test>::try_mutate(&miner1, |list| list.try_push(file_hash)).unwrap();
+        assert_ok!(FileBank::clear_invalid_file(RuntimeOrigin::signed(miner1.clone()), file_hash.into()));
+        assert!(InvalidFile::<Test>::get(&miner1).is_empty());
     });
 }
 
@@ -389,9 +213,10 @@ fn create_bucket_works() {
 		let bucket_name = "cess-bucket".as_bytes().to_vec();
 		let bound_bucket_name: BoundedVec<u8, NameStrLimit> = bucket_name.try_into().unwrap();
 		assert_ok!(FileBank::create_bucket(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), bound_bucket_name.clone()));
-		let bucket = Bucket::<Test>::get(&acc1, bound_bucket_name).unwrap();
+		let bucket = Bucket::<Test>::get(&acc1, bound_bucket_name.clone()).unwrap();
 		assert_eq!(bucket.authority.len(), 1);
 		assert_eq!(bucket.authority[0], acc1);
+		assert_noop!(FileBank::create_bucket(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), bound_bucket_name), Error::<Test>::Existed);
 	});
 }
 
@@ -414,88 +239,547 @@ fn transfer_ownership_works() {
 	new_test_ext().execute_with(|| {
 		let acc1 = account1();
 		let acc2 = account2();
-		let mfi = MockingFileBankInfo::default();
-		let bucket_name = "cess-bucket".as_bytes().to_vec();
-		let bound_bucket_name: BoundedVec<u8, NameStrLimit> = bucket_name.clone().try_into().unwrap();
-
-		let stash1 = mock::stash1();
-		let miner1 = mock::miner1();
-		let controller1 = mock::controller1();
-
-		assert_ok!(register_miner(miner1.clone()));
-		assert_ok!(Sminer::add_power(&miner1 ,1_048_576 * 1024 * 20));
-		assert_ok!(register_scheduler(stash1.clone(), controller1.clone()));
-		assert_ok!(add_power_for_miner(controller1, miner1));
-
-		assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), 2));
-		assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc2), 2));
-
-		assert_ok!(create_new_bucket(acc1.clone(), bucket_name.clone()));
-		assert_ok!(create_new_bucket(acc2.clone(), bucket_name.clone()));
-		assert_ok!(upload_declaration_alias(acc1, "cess-book".as_bytes().to_vec(), mfi.file_hash.clone(), bucket_name.clone()));
-		assert_ok!(upload_file_alias(acc1, controller1, &mfi));
+		let file_hash = Hash([5u8; 64]);
+		assert_ok!(buy_space(acc1.clone()));
+		assert_ok!(buy_space(acc2.clone()));
+		add_active_file(acc1.clone(), file_hash);
+		assert_ok!(create_new_bucket(acc2.clone(), BUCKET_NAME.as_bytes().to_vec()));
+
+		assert_ok!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc1.clone()), user_brief(acc2.clone()), file_hash.into()));
+
+		let file = <File<Test>>::get(&file_hash).unwrap();
+		assert_eq!(file.owner[0].user, acc2.clone());
+		assert!(!FileBank::check_is_file_owner(&acc1, &file_hash));
+		assert_eq!(used_space(&acc1), 0);
+		assert_eq!(used_space(&acc2), FileBank::cal_file_size(1));
+	})
+}
 
-		let file = <File<Test>>::get(&mfi.file_hash).unwrap();
-		assert_eq!(file.user_brief_list[0].user, acc1.clone());
+#[test]
+fn transfer_ownership_exception() {
+	new_test_ext().execute_with(|| {
+		let acc1 = account1();
+		let acc2 = account2();
+		let file_hash = Hash([5u8; 64]);
+		assert_ok!(buy_space(acc1.clone()));
+		add_active_file(acc1.clone(), file_hash);
 
-		let target_brief = UserBrief::<Test> {
-			user: acc2.clone(),
-			file_name: "test-file2".as_bytes().to_vec().try_into().unwrap(),
-			bucket_name: bound_bucket_name.clone(),
-		};
+		assert_noop!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc2.clone()), user_brief(acc2.clone()), file_hash.into()), Error::<Test>::NotOwner);
+		assert_noop!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc1.clone()), user_brief(acc2.clone()), Hash([8u8; 64]).into()), Error::<Test>::FileNonExistent);
+		assert_noop!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc1.clone()), user_brief(acc2.clone()), file_hash.into()), Error::<Test>::NonExistent);
 
-		assert_ok!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc1.clone()), bound_bucket_name, target_brief, mfi.file_hash.clone()));
+		assert_ok!(create_new_bucket(acc2.clone(), BUCKET_NAME.as_bytes().to_vec()));
+		assert_noop!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc1.clone()), user_brief(acc2.clone()), file_hash.into()), pallet_storage_handler::Error::<Test>::NotPurchasedSpace);
+	})
+}
 
-		let file = <File<Test>>::get(&mfi.file_hash).unwrap();
-		assert_eq!(file.user_brief_list[0].user, acc2.clone());
-		assert!(!FileBank::check_is_file_owner(&acc1, &mfi.file_hash));
+#[test]
+fn transfer_file_charges_nothing_until_accepted() {
+	new_test_ext().execute_with(|| {
+		let acc1 = account1();
+		let acc2 = account2();
+		let file_hash = Hash([5u8; 64]);
+		assert_ok!(buy_space(acc1.clone()));
+		assert_ok!(buy_space(acc2.clone()));
+		add_active_file(acc1.clone(), file_hash);
+
+		assert_ok!(FileBank::transfer_file(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc2.clone()));
+		assert_eq!(PendingTransfer::<Test>::get(&file_hash, &acc2), Some(acc1.clone()));
+		assert_eq!(used_space(&acc2), 0);
+		assert!(!<Bucket<Test>>::contains_key(&acc2, &user_brief(acc2.clone()).bucket_name));
+		assert!(!FileBank::check_is_file_owner(&acc2, &file_hash));
+
+		assert_ok!(FileBank::accept_transfer(RuntimeOrigin::signed(acc2.clone()), file_hash.into()));
+		assert!(!PendingTransfer::<Test>::contains_key(&file_hash, &acc2));
+		assert!(FileBank::check_is_file_owner(&acc2, &file_hash));
+		assert!(!FileBank::check_is_file_owner(&acc1, &file_hash));
+		assert!(<Bucket<Test>>::contains_key(&acc2, &user_brief(acc2.clone()).bucket_name));
+		assert_eq!(used_space(&acc1), 0);
+		assert_eq!(used_space(&acc2), FileBank::cal_file_size(1));
+		assert_eq!(last_event(), mock::RuntimeEvent::from(Event::FileOwnershipTransferred { owner: acc1, new_owner: acc2, file_hash }));
 	})
 }
 
 #[test]
-fn transfer_ownership_exception() {
+fn transfer_file_exception() {
 	new_test_ext().execute_with(|| {
 		let acc1 = account1();
 		let acc2 = account2();
-		let mfi = MockingFileBankInfo::default();
-		let bucket_name = "cess-bucket".as_bytes().to_vec();
-		let bound_bucket_name: BoundedVec<u8, NameStrLimit> = bucket_name.clone().try_into().unwrap();
+		let file_hash = Hash([5u8; 64]);
+		assert_ok!(buy_space(acc1.clone()));
+		add_active_file(acc1.clone(), file_hash);
+
+		assert_noop!(FileBank::transfer_file(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), acc2.clone()), Error::<Test>::NotOwner);
+		assert_noop!(FileBank::transfer_file(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc1.clone()), Error::<Test>::IsOwned);
+		assert_noop!(FileBank::accept_transfer(RuntimeOrigin::signed(acc2.clone()), file_hash.into()), Error::<Test>::TransferNotPending);
+
+		// The offer stays until the new owner has the space to accept it.
+		assert_ok!(FileBank::transfer_file(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc2.clone()));
+		assert_noop!(FileBank::accept_transfer(RuntimeOrigin::signed(acc2.clone()), file_hash.into()), pallet_storage_handler::Error::<Test>::NotPurchasedSpace);
+		assert_noop!(FileBank::accept_transfer(RuntimeOrigin::signed(mock::account3()), file_hash.into()), Error::<Test>::TransferNotPending);
+		assert!(PendingTransfer::<Test>::contains_key(&file_hash, &acc2));
+	})
+}
+
+#[test]
+fn grant_and_revoke_access_works() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_ok!(FileBank::grant_access(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc2.clone(), ACL_DELETE));
+        assert!(FileBank::check_file_acl(&file_hash, &acc2, &acc1, ACL_DELETE));
+        assert!(!FileBank::check_file_acl(&file_hash, &acc2, &acc1, ACL_WRITE));
+
+        assert_noop!(FileBank::revoke_access(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), acc2.clone()), Error::<Test>::NotOwner);
+        assert_ok!(FileBank::revoke_access(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc2.clone()));
+        assert!(!FileAcl::<Test>::contains_key(&file_hash, &acc2));
+        assert_eq!(last_event(), mock::RuntimeEvent::from(Event::RevokeAccess { owner: acc1, file_hash, acc: acc2 }));
+    });
+}
+
+#[test]
+fn grant_access_rejects_invalid_grants() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_noop!(FileBank::grant_access(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc2.clone(), 0), Error::<Test>::InvalidPermission);
+        assert_noop!(FileBank::grant_access(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc2.clone(), 0b1000), Error::<Test>::InvalidPermission);
+        assert_noop!(FileBank::grant_access(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), acc1.clone(), ACL_READ), Error::<Test>::NotOwner);
+        assert_noop!(FileBank::grant_access(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc1.clone(), ACL_READ), Error::<Test>::IsOwned);
+    });
+}
 
-		let stash1 = mock::stash1();
-		let miner1 = mock::miner1();
-		let controller1 = mock::controller1();
+#[test]
+fn granted_delete_access_allows_deleting() {
+    new_test_ext().execute_with(|| {
+        let acc2 = mock::account2();
+        let acc3 = mock::account3();
+        let file_hash = Hash([5u8; 64]);
+        add_shared_file(file_hash);
 
-		assert_ok!(register_miner(miner1.clone()));
-		assert_ok!(Sminer::add_power(&miner1 ,1_048_576 * 1024 * 20));
-		assert_ok!(register_scheduler(stash1.clone(), controller1.clone()));
-		assert_ok!(add_power_for_miner(controller1, miner1));
+        assert_ok!(FileBank::grant_access(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), acc3.clone(), ACL_DELETE));
+        assert_ok!(FileBank::delete_file(RuntimeOrigin::signed(acc3), acc2.clone(), vec![file_hash.into()]));
 
-		assert_ok!(FileBank::buy_space(RuntimeOrigin::signed(acc1), 2));
+        assert!(!FileBank::check_is_file_owner(&acc2, &file_hash));
+    });
+}
 
-		assert_ok!(create_new_bucket(acc1.clone(), bucket_name.clone()));
-		assert_ok!(upload_declaration_alias(acc1, "cess-book".as_bytes().to_vec(), mfi.file_hash.clone(), bucket_name.clone()));
-		assert_ok!(upload_file_alias(acc1, controller1, &mfi));
+#[test]
+fn update_and_rollback_version_works() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let v1 = Hash([5u8; 64]);
+        let v2 = Hash([6u8; 64]);
+        let v3 = Hash([7u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), v1);
+        add_active_file(acc1.clone(), v2);
+        add_active_file(acc1.clone(), v3);
+
+        assert_ok!(FileBank::update_file(RuntimeOrigin::signed(acc1.clone()), v1.into(), v2.into()));
+        assert_ok!(FileBank::update_file(RuntimeOrigin::signed(acc1.clone()), v2.into(), v3.into()));
+        assert_eq!(FileVersions::<Test>::get(&acc1, &v3).to_vec(), vec![v1, v2]);
+
+        assert_ok!(FileBank::rollback_version(RuntimeOrigin::signed(acc1.clone()), v3.into(), v2.into()));
+        assert!(!FileVersions::<Test>::contains_key(&acc1, &v3));
+        assert_eq!(FileVersions::<Test>::get(&acc1, &v2).to_vec(), vec![v1]);
+        // The newer file is unlinked, not deleted.
+        assert!(FileBank::check_is_file_owner(&acc1, &v3));
+    });
+}
 
-		let target_brief = UserBrief::<Test> {
-			user: acc2.clone(),
-			file_name: "test-file2".as_bytes().to_vec().try_into().unwrap(),
-			bucket_name: bound_bucket_name.clone(),
-		};
+#[test]
+fn update_and_rollback_version_rejects_foreign_files() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let acc2 = mock::account2();
+        let v1 = Hash([5u8; 64]);
+        let v2 = Hash([6u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        assert_ok!(buy_space(acc2.clone()));
+        add_active_file(acc1.clone(), v1);
+        add_active_file(acc2.clone(), v2);
+
+        assert_noop!(FileBank::update_file(RuntimeOrigin::signed(acc1.clone()), v1.into(), v1.into()), Error::<Test>::VersionConflict);
+        assert_noop!(FileBank::update_file(RuntimeOrigin::signed(acc1.clone()), v1.into(), v2.into()), Error::<Test>::NotOwner);
+        assert_noop!(FileBank::rollback_version(RuntimeOrigin::signed(acc1.clone()), v1.into(), v2.into()), Error::<Test>::NotOwner);
+    });
+}
 
-		assert_noop!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc2.clone()), bound_bucket_name.clone(), target_brief.clone(), mfi.file_hash.clone()), Error::<Test>::NotOwner);
-		let file_hash = Hash([8u8; 64]);
-		assert_noop!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc1.clone()), bound_bucket_name.clone(), target_brief.clone(), file_hash.clone()), Error::<Test>::FileNonExistent);
-		assert_noop!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc1.clone()), bound_bucket_name.clone(), target_brief.clone(), mfi.file_hash.clone()), Error::<Test>::NonExistent);
+#[test]
+fn create_redeem_and_revoke_share_works() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let gateway = mock::gateway1();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_ok!(FileBank::create_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 100, 2));
+        let share_id = FileShares::<Test>::get(&file_hash)[0].share_id;
+
+        assert_ok!(FileBank::redeem_share(RuntimeOrigin::signed(gateway.clone()), file_hash.into(), share_id));
+        assert_eq!(last_event(), mock::RuntimeEvent::from(Event::ShareRedeemed { gateway: gateway.clone(), file_hash, share_id, remaining: 1 }));
+        assert_ok!(FileBank::redeem_share(RuntimeOrigin::signed(gateway.clone()), file_hash.into(), share_id));
+        // Used up shares are removed.
+        assert!(FileShares::<Test>::get(&file_hash).is_empty());
+
+        // The id of the used up share is not handed out again in the same block.
+        assert_ok!(FileBank::create_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 100, 2));
+        let used_up_id = share_id;
+        let share_id = FileShares::<Test>::get(&file_hash)[0].share_id;
+        assert_ne!(share_id, used_up_id);
+        assert_ok!(FileBank::revoke_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), share_id));
+        assert_noop!(FileBank::redeem_share(RuntimeOrigin::signed(gateway), file_hash.into(), share_id), Error::<Test>::NonExistent);
+    });
+}
 
-		assert_ok!(create_new_bucket(acc2.clone(), bucket_name.clone()));
-		assert_noop!(FileBank::ownership_transfer(RuntimeOrigin::signed(acc1.clone()), bound_bucket_name.clone(), target_brief.clone(), mfi.file_hash.clone()), Error::<Test>::NotPurchasedSpace);
-	})
+#[test]
+fn share_rejects_invalid_requests() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_noop!(FileBank::create_share(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), 100, 2), Error::<Test>::NotOwner);
+        assert_noop!(FileBank::create_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 100, 0), Error::<Test>::IsZero);
+        assert_noop!(FileBank::create_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 1, 2), Error::<Test>::Expired);
+
+        assert_ok!(FileBank::create_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 100, 2));
+        let share_id = FileShares::<Test>::get(&file_hash)[0].share_id;
+        assert_noop!(FileBank::redeem_share(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), share_id), Error::<Test>::NoPermission);
+        assert_noop!(FileBank::revoke_share(RuntimeOrigin::signed(acc2), file_hash.into(), share_id), Error::<Test>::NotOwner);
+    });
+}
+
+#[test]
+fn share_expires_at_its_block() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_ok!(FileBank::create_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 5, 2));
+        let share_id = FileShares::<Test>::get(&file_hash)[0].share_id;
+
+        initialize_block(4);
+        assert_eq!(FileShares::<Test>::get(&file_hash).len(), 1);
+        initialize_block(5);
+        assert!(FileShares::<Test>::get(&file_hash).is_empty());
+        assert_noop!(FileBank::redeem_share(RuntimeOrigin::signed(mock::gateway1()), file_hash.into(), share_id), Error::<Test>::NonExistent);
+    });
+}
+
+#[test]
+fn queues_over_the_budget_are_carried_over() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+        assert_ok!(FileBank::create_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 3, 2));
+        Sys::set_block_number(2);
+        assert_ok!(FileBank::create_share(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 3, 2));
+
+        // Nothing is handled once the budget is spent, the entries stay queued.
+        let mut weight = Weight::from_ref_time(1);
+        assert!(!FileBank::process_block_queues(3, Weight::zero(), &mut weight));
+        assert_eq!(ShareExpiryQueue::<Test>::get(3).len(), 2);
+        assert_eq!(FileShares::<Test>::get(&file_hash).len(), 2);
+
+        // A later block handles the queues left over by the earlier blocks first.
+        QueueCursor::<Test>::put(3);
+        initialize_block(4);
+        assert!(ShareExpiryQueue::<Test>::get(3).is_empty());
+        assert!(FileShares::<Test>::get(&file_hash).is_empty());
+        assert!(!QueueCursor::<Test>::exists());
+    });
+}
+
+#[test]
+fn commit_and_reveal_deal_bid_works() {
+    new_test_ext().execute_with(|| {
+        let miner1 = mock::miner1();
+        let deal_hash = Hash([5u8; 64]);
+        let price: u64 = 100;
+        let salt = [7u8; 32];
+        assert_ok!(register_miner(miner1.clone()));
+        assert_ok!(FileBank::start_deal_auction(&deal_hash));
+
+        let commitment = sp_io::hashing::blake2_256(&(miner1.clone(), price, salt).encode());
+        assert_ok!(FileBank::commit_deal_bid(RuntimeOrigin::signed(miner1.clone()), deal_hash, commitment));
+        assert_noop!(FileBank::commit_deal_bid(RuntimeOrigin::signed(miner1.clone()), deal_hash, commitment), Error::<Test>::Existed);
+
+        let auction = DealAuctions::<Test>::get(&deal_hash).unwrap();
+        Sys::set_block_number(auction.commit_end);
+        assert_ok!(FileBank::reveal_deal_bid(RuntimeOrigin::signed(miner1.clone()), deal_hash, price, salt));
+        assert_eq!(DealAuctions::<Test>::get(&deal_hash).unwrap().bids.to_vec(), vec![(miner1.clone(), price)]);
+        assert_eq!(last_event(), mock::RuntimeEvent::from(Event::DealBidRevealed { miner: miner1, deal_hash, price }));
+    });
+}
+
+#[test]
+fn reveal_deal_bid_rejects_wrong_phase_and_bid() {
+    new_test_ext().execute_with(|| {
+        let miner1 = mock::miner1();
+        let deal_hash = Hash([5u8; 64]);
+        let price: u64 = 100;
+        let salt = [7u8; 32];
+        assert_ok!(register_miner(miner1.clone()));
+        assert_ok!(FileBank::start_deal_auction(&deal_hash));
+
+        let commitment = sp_io::hashing::blake2_256(&(miner1.clone(), price, salt).encode());
+        assert_ok!(FileBank::commit_deal_bid(RuntimeOrigin::signed(miner1.clone()), deal_hash, commitment));
+        assert_noop!(FileBank::reveal_deal_bid(RuntimeOrigin::signed(miner1.clone()), deal_hash, price, salt), Error::<Test>::AuctionPhaseError);
+
+        let auction = DealAuctions::<Test>::get(&deal_hash).unwrap();
+        Sys::set_block_number(auction.commit_end);
+        assert_noop!(FileBank::commit_deal_bid(RuntimeOrigin::signed(miner1.clone()), deal_hash, commitment), Error::<Test>::AuctionPhaseError);
+        assert_noop!(FileBank::reveal_deal_bid(RuntimeOrigin::signed(miner1.clone()), deal_hash, price + 1, salt), Error::<Test>::BidMismatch);
+    });
+}
+
+#[test]
+fn request_delete_finalizes_after_grace_period() {
+    new_test_ext().execute_with(|| {
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        add_shared_file(file_hash);
+
+        assert_ok!(FileBank::request_delete(RuntimeOrigin::signed(acc2.clone()), acc2.clone(), file_hash.into()));
+        let finalize = PendingDelete::<Test>::get(&acc2, &file_hash).unwrap();
+        assert_eq!(finalize, 1 + DeleteGracePeriod::get());
+        assert_noop!(FileBank::request_delete(RuntimeOrigin::signed(acc2.clone()), acc2.clone(), file_hash.into()), Error::<Test>::Existed);
+
+        initialize_block(finalize - 1);
+        assert!(FileBank::check_is_file_owner(&acc2, &file_hash));
+        initialize_block(finalize);
+        assert!(!FileBank::check_is_file_owner(&acc2, &file_hash));
+        assert!(!PendingDelete::<Test>::contains_key(&acc2, &file_hash));
+        assert!(FileBank::check_is_file_owner(&mock::account1(), &file_hash));
+    });
+}
+
+#[test]
+fn cancel_delete_keeps_the_file() {
+    new_test_ext().execute_with(|| {
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        add_shared_file(file_hash);
+
+        assert_noop!(FileBank::cancel_delete(RuntimeOrigin::signed(acc2.clone()), acc2.clone(), file_hash.into()), Error::<Test>::NonExistent);
+        assert_ok!(FileBank::request_delete(RuntimeOrigin::signed(acc2.clone()), acc2.clone(), file_hash.into()));
+        assert_noop!(FileBank::cancel_delete(RuntimeOrigin::signed(mock::account3()), acc2.clone(), file_hash.into()), Error::<Test>::NoPermission);
+        assert_ok!(FileBank::cancel_delete(RuntimeOrigin::signed(acc2.clone()), acc2.clone(), file_hash.into()));
+
+        initialize_block(1 + DeleteGracePeriod::get());
+        assert!(FileBank::check_is_file_owner(&acc2, &file_hash));
+    });
+}
+
+#[test]
+fn create_and_cancel_storage_order_works() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let file_hash = Hash([5u8; 64]);
+        let reserved = Balances::reserved_balance(&acc1);
+
+        assert_ok!(FileBank::create_storage_order(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), SEGMENT_SIZE, 10));
+        let order = StorageOrders::<Test>::get(&acc1, &file_hash).unwrap();
+        assert_eq!(order.space, FileBank::cal_file_size(1));
+        assert_eq!(order.escrow, order.daily_payment * 10);
+        assert_eq!(Balances::reserved_balance(&acc1), reserved + order.escrow);
+        assert_noop!(FileBank::create_storage_order(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), SEGMENT_SIZE, 10), Error::<Test>::Existed);
+
+        assert_ok!(FileBank::cancel_storage_order(RuntimeOrigin::signed(acc1.clone()), file_hash.into()));
+        assert!(!StorageOrders::<Test>::contains_key(&acc1, &file_hash));
+        assert_eq!(Balances::reserved_balance(&acc1), reserved);
+        assert_eq!(last_event(), mock::RuntimeEvent::from(Event::StorageOrderClosed { owner: acc1, file_hash, refund: order.escrow }));
+    });
+}
+
+#[test]
+fn storage_order_rejects_held_files() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_noop!(FileBank::create_storage_order(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), 0, 10), Error::<Test>::IsZero);
+        assert_noop!(FileBank::create_storage_order(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), SEGMENT_SIZE, 10), Error::<Test>::IsOwned);
+        assert_noop!(FileBank::cancel_storage_order(RuntimeOrigin::signed(acc1), file_hash.into()), Error::<Test>::NonExistent);
+    });
+}
+
+#[test]
+fn request_and_confirm_mirror_works() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let gateway = mock::gateway1();
+        let file_hash = Hash([5u8; 64]);
+        let network: BoundedVec<u8, NameStrLimit> = "arweave".as_bytes().to_vec().try_into().unwrap();
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_ok!(FileBank::request_mirror(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), network.clone()));
+        assert_ok!(Oss::authorize(RuntimeOrigin::signed(acc1.clone()), gateway.clone()));
+        let locator = Hash([9u8; 64]);
+        assert_ok!(FileBank::confirm_mirror(RuntimeOrigin::signed(gateway), acc1.clone(), file_hash.into(), locator));
+
+        let mirror = FileMirror::<Test>::get(&file_hash, &acc1).unwrap();
+        assert_eq!(mirror.network, network);
+        assert_eq!(mirror.locator, Some(locator));
+    });
+}
+
+#[test]
+fn mirror_rejects_unauthorized_accounts() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        let network: BoundedVec<u8, NameStrLimit> = "arweave".as_bytes().to_vec().try_into().unwrap();
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_noop!(FileBank::request_mirror(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), network.clone()), Error::<Test>::NotOwner);
+        assert_noop!(FileBank::confirm_mirror(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), file_hash.into(), Hash([9u8; 64])), Error::<Test>::NonExistent);
+        assert_ok!(FileBank::request_mirror(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), network));
+        assert_noop!(FileBank::confirm_mirror(RuntimeOrigin::signed(acc2), acc1, file_hash.into(), Hash([9u8; 64])), Error::<Test>::NoPermission);
+    });
+}
+
+#[test]
+fn buy_file_splits_the_download_fee() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let acc2 = mock::account2();
+        let miner1 = mock::miner1();
+        let file_hash = Hash([5u8; 64]);
+        let price: u64 = 100;
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+        assert_ok!(FileBank::set_download_price(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), Some(price)));
+
+        let owner_before = Balances::free_balance(&acc1);
+        let miner_before = Balances::free_balance(&miner1);
+        let buyer_before = Balances::free_balance(&acc2);
+        assert_ok!(FileBank::buy_file(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), acc1.clone()));
+
+        assert_eq!(Balances::free_balance(&acc2), buyer_before - price);
+        assert_eq!(Balances::free_balance(&acc1), owner_before + 70);
+        assert_eq!(Balances::free_balance(&miner1), miner_before + 20);
+        assert!(FileBuyer::<Test>::contains_key(&file_hash, &acc2));
+
+        // The buyer can report its retrievals.
+        assert_ok!(FileBank::submit_retrieval_receipt(RuntimeOrigin::signed(acc2.clone()), file_hash.into()));
+        assert_noop!(FileBank::submit_retrieval_receipt(RuntimeOrigin::signed(acc2), file_hash.into()), Error::<Test>::Existed);
+    });
+}
+
+#[test]
+fn buy_file_rejects_unsold_and_bought_files() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let acc2 = mock::account2();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+
+        assert_noop!(FileBank::set_download_price(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), Some(100)), Error::<Test>::NotOwner);
+        assert_noop!(FileBank::buy_file(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), acc1.clone()), Error::<Test>::NonExistent);
+        assert_noop!(FileBank::submit_retrieval_receipt(RuntimeOrigin::signed(acc2.clone()), file_hash.into()), Error::<Test>::NoPermission);
+
+        assert_ok!(FileBank::set_download_price(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), Some(100)));
+        assert_noop!(FileBank::buy_file(RuntimeOrigin::signed(acc1.clone()), file_hash.into(), acc1.clone()), Error::<Test>::IsOwned);
+        assert_ok!(FileBank::buy_file(RuntimeOrigin::signed(acc2.clone()), file_hash.into(), acc1.clone()));
+        assert_noop!(FileBank::buy_file(RuntimeOrigin::signed(acc2), file_hash.into(), acc1), Error::<Test>::Existed);
+    });
+}
+
+#[test]
+fn audit_user_space_corrects_the_used_space() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let file_hash = Hash([5u8; 64]);
+        assert_ok!(buy_space(acc1.clone()));
+        add_active_file(acc1.clone(), file_hash);
+        let actual = FileBank::cal_file_size(1);
+
+        // Within the tolerance nothing is corrected.
+        assert_ok!(<StorageHandler as StorageHandle<AccountId>>::update_user_space(&acc1, 1, SPACE_AUDIT_TOLERANCE));
+        assert_ok!(FileBank::audit_user_space(RuntimeOrigin::signed(mock::account2()), acc1.clone()));
+        assert_eq!(used_space(&acc1), actual + SPACE_AUDIT_TOLERANCE);
+
+        assert_ok!(<StorageHandler as StorageHandle<AccountId>>::update_user_space(&acc1, 1, SPACE_AUDIT_TOLERANCE));
+        assert_ok!(FileBank::audit_user_space(RuntimeOrigin::signed(mock::account2()), acc1.clone()));
+        assert_eq!(used_space(&acc1), actual);
+    });
+}
+
+#[test]
+fn audit_user_space_requires_a_package() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            FileBank::audit_user_space(RuntimeOrigin::signed(mock::account2()), mock::account1()),
+            pallet_storage_handler::Error::<Test>::NotPurchasedSpace
+        );
+    });
+}
+
+#[test]
+fn audit_user_space_waits_for_the_migration() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        assert_ok!(buy_space(acc1.clone()));
+        // The file list of the user is not moved into its file trie yet.
+        assert_ok!(<StorageHandler as StorageHandle<AccountId>>::update_user_space(&acc1, 1, FileBank::cal_file_size(1)));
+        UserFileMigrating::<Test>::put(true);
+
+        assert_noop!(FileBank::audit_user_space(RuntimeOrigin::signed(mock::account2()), acc1.clone()), Error::<Test>::Migrating);
+        assert_eq!(used_space(&acc1), FileBank::cal_file_size(1));
+    });
+}
+
+#[test]
+fn set_bucket_policy_works() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let name: BoundedVec<u8, NameStrLimit> = BUCKET_NAME.as_bytes().to_vec().try_into().unwrap();
+        let policy = StoragePolicy { region: Some(*b"EU"), min_reputation: None };
+        assert_ok!(create_new_bucket(acc1.clone(), name.to_vec()));
+
+        assert_ok!(FileBank::set_bucket_policy(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), name.clone(), Some(policy)));
+        assert_eq!(BucketPolicy::<Test>::get(&acc1, &name), Some(policy));
+
+        assert_ok!(FileBank::set_bucket_policy(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), name.clone(), None));
+        assert!(!BucketPolicy::<Test>::contains_key(&acc1, &name));
+    });
+}
+
+#[test]
+fn set_bucket_policy_rejects_invalid_policies() {
+    new_test_ext().execute_with(|| {
+        let acc1 = mock::account1();
+        let name: BoundedVec<u8, NameStrLimit> = BUCKET_NAME.as_bytes().to_vec().try_into().unwrap();
+        let policy = StoragePolicy { region: Some(*b"EU"), min_reputation: None };
+
+        assert_noop!(FileBank::set_bucket_policy(RuntimeOrigin::signed(acc1.clone()), acc1.clone(), name.clone(), Some(policy)), Error::<Test>::NonExistent);
+        assert_ok!(create_new_bucket(acc1.clone(), name.to_vec()));
+        assert_noop!(FileBank::set_bucket_policy(RuntimeOrigin::signed(mock::account2()), acc1.clone(), name.clone(), Some(policy)), Error::<Test>::NoPermission);
+        let lower_case = StoragePolicy { region: Some(*b"eu"), min_reputation: None };
+        assert_noop!(FileBank::set_bucket_policy(RuntimeOrigin::signed(acc1.clone()), acc1, name, Some(lower_case)), Error::<Test>::InvalidRegion);
+    });
 }
-// #[test]
-// fn update_price_works() {
-//     new_test_ext().execute_with(|| {
-//         let acc1 = mock::account1();
-//         assert_ok!(FileBank::update_price(Origin::signed(acc1), Vec::from("1000")));
-//         assert_eq!(1000 / 3, UnitPrice::<Test>::try_get().unwrap());
-//     });
-// }
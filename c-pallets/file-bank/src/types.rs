@@ -3,6 +3,29 @@ use super::*;
 type AccountOf<T> = <T as frame_system::Config>::AccountId;
 type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
 // Cess type
+// The hash a file is passed by in the extrinsics, a distinct type so that the hash of a
+// segment or fragment is not taken for the one of a file. It encodes to the same 64 bytes
+// as `cp_cess_common::Hash`, which keys the files in storage, so no migration is needed.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct FileHash(pub Hash);
+
+impl From<Hash> for FileHash {
+	fn from(hash: Hash) -> Self {
+		FileHash(hash)
+	}
+}
+
+impl From<FileHash> for Hash {
+	fn from(file_hash: FileHash) -> Self {
+		file_hash.0
+	}
+}
+
+impl AsRef<[u8]> for FileHash {
+	fn as_ref(&self) -> &[u8] {
+		&self.0 .0
+	}
+}
 // pub(super) type SegmentList<T> = BoundedVec<(Hash, BoundedVec<Hash, <T as pallet::Config>::FragmentCount>),  <T as pallet::Config>::SegmentCount>;
 // pub(super) type MinerTaskList<T> = BoundedVec<(AccountOf<T>, BoundedVec<Hash,  <T as pallet::Config>::FragmentCount>),  <T as pallet::Config>::FragmentCount>;
 
@@ -35,8 +58,9 @@ pub enum FileState {
 #[scale_info(skip_type_params(T))]
 #[codec(mel_bound())]
 pub struct DealInfo<T: Config> {
-	// There are two stages in total: 
-	// the first stage and the second stage, represented by 1 or 2, respectively.
+	// There are three stages in total: 
+	// the first stage and the second stage, represented by 1 or 2, respectively,
+	// and 3 when the calculation ended but some segments are not reported yet.
 	pub(super) stage: u8, 
 	pub(super) count: u8,
 	pub(super) file_size: u128,
@@ -46,6 +70,10 @@ pub struct DealInfo<T: Config> {
 	pub(super) assigned_miner: BoundedVec<MinerTaskList<T>, T::StringLimit>,
 	pub(super) share_info: BoundedVec<SegmentInfo<T>, T::SegmentCount>,
 	pub(super) complete_list: BoundedVec<AccountOf<T>, T::FragmentCount>,
+	// The tee worker that the user streams the file data to.
+	pub(super) scheduler: AccountOf<T>,
+	// The block at which the current stage of the deal ends.
+	pub(super) deadline: BlockNumberOf<T>,
 }
 
 //TODO! BoundedVec type -> BTreeMap
@@ -60,6 +88,16 @@ pub struct FileInfo<T: Config> {
 	pub(super) stat: FileState,
 }
 
+// The fields of a `FileInfo` read on hot paths, kept in step with it by `refresh_file_summary`.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct FileSummaryInfo {
+	pub stat: FileState,
+	pub file_size: u128,
+	// Fragments of the file that are available on their miner.
+	pub replica_count: u32,
+	pub owner_count: u32,
+}
+
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 #[scale_info(skip_type_params(T))]
 #[codec(mel_bound())]
@@ -68,6 +106,9 @@ pub struct SegmentInfo<T: Config> {
 	pub(super) fragment_list: BoundedVec<FragmentInfo<T>, T::FragmentCount>,
 }
 
+// One replica of a segment. The fragments of a segment are placed on different miners
+// chosen by `random_assign_miner`, each miner reports its fragments through `transfer_report`,
+// and a fragment that is no longer available is re-assigned through a restoral order.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 #[scale_info(skip_type_params(T))]
 #[codec(mel_bound())]
@@ -87,12 +128,22 @@ pub struct FillerInfo<T: Config> {
 	pub filler_hash: Hash,
 }
 
+// Entry of the file list users held before the lists moved into child tries.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 pub struct UserFileSliceInfo {
 	pub(super) file_hash: Hash,
 	pub(super) file_size: u128,
 }
 
+// What the main trie keeps of the files of a user. The child trie of the user maps
+// each file hash to the space charged for the file, single files are proven against `root`.
+#[derive(PartialEq, Eq, Clone, Default, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct UserFileListInfo<Hash> {
+	pub(super) count: u32,
+	pub(super) total_size: u128,
+	pub(super) root: Hash,
+}
+
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 #[scale_info(skip_type_params(T))]
 #[codec(mel_bound())]
@@ -101,6 +152,26 @@ pub struct BucketInfo<T: Config> {
 	pub(super) authority: BoundedVec<AccountOf<T>, T::StringLimit>,
 }
 
+// Placement requirements of the fragments of a file, set on a bucket for the files declared
+// into it. A policy given with the declaration takes precedence field by field.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct StoragePolicy {
+	// Only miners that declared this region store the fragments.
+	pub region: Option<RegionCode>,
+	// Lowest placement reputation of the miners storing the fragments.
+	pub min_reputation: Option<Perbill>,
+}
+
+impl StoragePolicy {
+	// The fields of `self` that are set, the fields of `inherited` otherwise.
+	pub fn or(self, inherited: StoragePolicy) -> StoragePolicy {
+		StoragePolicy {
+			region: self.region.or(inherited.region),
+			min_reputation: self.min_reputation.or(inherited.min_reputation),
+		}
+	}
+}
+
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 #[scale_info(skip_type_params(T))]
 #[codec(mel_bound())]
@@ -131,3 +202,128 @@ pub struct RestoralOrderInfo<T: Config> {
 	pub(super) deadline: BlockNumberOf<T>,
 }
 
+// PoDR2 tag computed by the tee committee over a restored fragment.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct RestoralTag<AccountId, BlockNumber> {
+	// Hash of the tag, the miner keeps the tag itself next to the fragment.
+	pub(super) tag_hash: Hash,
+	// The block the tag was computed at, it must not predate the claim of the order.
+	pub(super) computed_at: BlockNumber,
+	// The tee worker that computed the tag, one of the signers, and its nonce.
+	pub(super) worker: AccountId,
+	pub(super) nonce: u64,
+	// Signatures of the tee committee over (fragment_hash, miner, tag_hash, computed_at, worker, nonce).
+	pub(super) signatures: CommitteeSignatures<AccountId>,
+}
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct FileAclInfo<T: Config> {
+	// The file owner who granted the access.
+	pub(super) grantor: AccountOf<T>,
+	// Bit set of ACL_READ, ACL_WRITE and ACL_DELETE.
+	pub(super) permission: u8,
+}
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct ShareInfo<T: Config> {
+	pub(super) share_id: [u8; 32],
+	// The file owner who created the share.
+	pub(super) owner: AccountOf<T>,
+	pub(super) expiry: BlockNumberOf<T>,
+	pub(super) max_downloads: u32,
+	pub(super) downloads: u32,
+}
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct SegmentCommitment<T: Config> {
+	pub(super) miner: AccountOf<T>,
+	pub(super) segment_hash: Hash,
+	pub(super) block: BlockNumberOf<T>,
+}
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct MirrorInfo<T: Config> {
+	// Name of the partner network, for example "arweave" or "ipfs".
+	pub(super) network: BoundedVec<u8, T::NameStrLimit>,
+	// Hash of the locator of the copy on the partner network, set once a gateway confirms it.
+	pub(super) locator: Option<Hash>,
+	pub(super) request_block: BlockNumberOf<T>,
+}
+
+// Fragments of a file lost with exited miners that are being re-seeded.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct RecoveryInfo<T: Config> {
+	// Fragments with a restoral order that is not completed yet.
+	pub(super) pending: BoundedVec<Hash, T::RecoverLimit>,
+	pub(super) restored: u32,
+}
+
+// A scan of all files for the fragments of exited miners, resumed every block after `last_key`.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct RecoveryScanInfo<T: Config> {
+	pub(super) miners: BoundedVec<AccountOf<T>, ConstU32<100>>,
+	pub(super) last_key: BoundedVec<u8, ConstU32<128>>,
+}
+
+#[derive(PartialEq, Eq, Clone, Default, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct PopularityInfo {
+	// Score as of the start of `era`, plus the gains within it.
+	pub(super) score: u32,
+	pub(super) era: u32,
+	// Score gained within `era`, capped by `MaxPopularityGain`.
+	pub(super) era_gain: u32,
+}
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct DealAuction<T: Config> {
+	// Bids are committed before this block and revealed from it on.
+	pub(super) commit_end: BlockNumberOf<T>,
+	// The auction is settled at this block.
+	pub(super) reveal_end: BlockNumberOf<T>,
+	pub(super) commitments: BoundedVec<(AccountOf<T>, [u8; 32]), T::StringLimit>,
+	pub(super) bids: BoundedVec<(AccountOf<T>, BalanceOf<T>), T::StringLimit>,
+}
+
+// Reminder of an invalid file the miner has not cleared, signed by the offchain worker of an authority.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct InvalidFileReminder<AccountId, BlockNumber, AuthorityId> {
+	pub(super) miner: AccountId,
+	pub(super) file_hash: Hash,
+	// The block the reminder was made at.
+	pub(super) block_number: BlockNumber,
+	// The key of the authority that signed the reminder.
+	pub(super) authority: AuthorityId,
+}
+
+// A file paid from escrow instead of a package. The escrow is reserved from the owner and
+// paid out, one day at a time, to the miners of the file that did not fail a challenge that day.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct StorageOrder<T: Config> {
+	// The largest space a file declared against the order can take.
+	pub(super) space: u128,
+	pub(super) days: u32,
+	pub(super) daily_payment: BalanceOf<T>,
+	// What is still reserved from the owner.
+	pub(super) escrow: BalanceOf<T>,
+	// The block the file was stored at, None while the file is not stored yet.
+	pub(super) start: Option<BlockNumberOf<T>>,
+	pub(super) paid_days: u32,
+	// The block the last day was paid at, failures before it were already accounted.
+	pub(super) settled_at: BlockNumberOf<T>,
+}
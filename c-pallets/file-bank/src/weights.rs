@@ -57,6 +57,49 @@ pub trait WeightInfo {
 	fn create_bucket() -> Weight;
 	fn delete_bucket() -> Weight;
 	fn ownership_transfer() -> Weight;
+	fn grant_access() -> Weight;
+	fn revoke_access() -> Weight;
+	fn set_public_dataset() -> Weight;
+	fn upload_segment() -> Weight;
+	fn update_file() -> Weight;
+	fn rollback_version() -> Weight;
+	fn audit_user_space() -> Weight;
+	fn set_declaration_limit() -> Weight;
+	fn request_mirror() -> Weight;
+	fn confirm_mirror() -> Weight;
+	fn request_delete() -> Weight;
+	fn cancel_delete() -> Weight;
+	fn set_download_price() -> Weight;
+	fn buy_file() -> Weight;
+	fn submit_retrieval_receipt() -> Weight;
+	fn commit_deal_bid() -> Weight;
+	fn reveal_deal_bid() -> Weight;
+	fn lease_sweep(v: u32, ) -> Weight;
+	fn clear_user_files(v: u32, ) -> Weight;
+	fn create_share() -> Weight;
+	fn revoke_share() -> Weight;
+	fn redeem_share() -> Weight;
+	fn set_file_size_limit() -> Weight;
+	fn remind_invalid_file() -> Weight;
+	fn set_bucket_policy() -> Weight;
+	fn withdraw_declaration() -> Weight;
+	fn create_storage_order() -> Weight;
+	fn cancel_storage_order() -> Weight;
+	fn segment_report_timeout() -> Weight;
+	fn deal_reassign_miner() -> Weight;
+	fn force_reassign_deal() -> Weight;
+	fn calculate_end() -> Weight;
+	fn replace_file_report(v: u32, ) -> Weight;
+	fn set_lease_sweep_period() -> Weight;
+	fn generate_restoral_order() -> Weight;
+	fn claim_restoral_order() -> Weight;
+	fn claim_restoral_noexist_order() -> Weight;
+	fn restoral_order_complete() -> Weight;
+	fn miner_exit_prep() -> Weight;
+	fn miner_exit() -> Weight;
+	fn miner_withdraw() -> Weight;
+	fn user_file_root() -> Weight;
+	fn transfer_file() -> Weight;
 }
 
 /// Weights for pallet_file_bank using the Substrate node and recommended hardware.
@@ -90,7 +133,7 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: TeeWorker SchedulerMap (r:1 w:0)
 	// Storage: FileBank File (r:1 w:1)
 	// Storage: FileBank UserOwnedSpace (r:1 w:1)
-	// Storage: FileBank UserHoldFileList (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:1)
 	// Storage: FileBank FileKeysMap (r:1 w:1)
 	// Storage: FileBank CounterForFileKeysMap (r:1 w:1)
 	// Storage: SchedulerCredit CurrentCounters (r:1 w:1)
@@ -118,7 +161,7 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: Sminer TotalServiceSpace (r:1 w:1)
 	// Storage: FileBank FileKeysMap (r:1 w:1)
 	// Storage: FileBank CounterForFileKeysMap (r:1 w:1)
-	// Storage: FileBank UserHoldFileList (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:1)
 	fn delete_file() -> Weight {
 		Weight::from_ref_time(376_051_000 as u64)
 			.saturating_add(T::DbWeight::get().reads(9 as u64))
@@ -161,12 +204,399 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	// Storage: FileBank File (r:1 w:1)
 	// Storage: FileBank Bucket (r:2 w:2)
 	// Storage: FileBank UserOwnedSpace (r:2 w:2)
-	// Storage: FileBank UserHoldFileList (r:2 w:2)
+	// Storage: FileBank UserFileIndex (r:2 w:2)
 	fn ownership_transfer() -> Weight {
 		Weight::from_ref_time(322_561_000 as u64)
 			.saturating_add(T::DbWeight::get().reads(7 as u64))
 			.saturating_add(T::DbWeight::get().writes(7 as u64))
 	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank FileAcl (r:0 w:1)
+	fn grant_access() -> Weight {
+		Weight::from_ref_time(28_502_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank FileAcl (r:1 w:1)
+	fn revoke_access() -> Weight {
+		Weight::from_ref_time(26_114_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank PublicDataset (r:0 w:1)
+	fn set_public_dataset() -> Weight {
+		Weight::from_ref_time(27_640_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:0)
+	// Storage: FileBank FileSegments (r:1 w:1)
+	// Storage: FileBank File (r:1 w:0)
+	fn upload_segment() -> Weight {
+		Weight::from_ref_time(41_208_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank File (r:2 w:0)
+	// Storage: FileBank FileVersions (r:2 w:2)
+	fn update_file() -> Weight {
+		Weight::from_ref_time(35_412_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: FileBank File (r:2 w:0)
+	// Storage: FileBank FileVersions (r:1 w:2)
+	fn rollback_version() -> Weight {
+		Weight::from_ref_time(33_905_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: FileBank UserFileMigrating (r:1 w:0)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:0)
+	fn audit_user_space() -> Weight {
+		Weight::from_ref_time(48_730_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank DeclarationLimit (r:0 w:1)
+	fn set_declaration_limit() -> Weight {
+		Weight::from_ref_time(18_210_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank FileMirror (r:0 w:1)
+	fn request_mirror() -> Weight {
+		Weight::from_ref_time(30_115_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: Oss AuthorityList (r:1 w:0)
+	// Storage: FileBank FileMirror (r:1 w:1)
+	fn confirm_mirror() -> Weight {
+		Weight::from_ref_time(29_870_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: Oss AuthorityList (r:1 w:0)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank PendingDelete (r:1 w:1)
+	// Storage: FileBank DeleteQueue (r:1 w:1)
+	fn request_delete() -> Weight {
+		Weight::from_ref_time(40_356_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: Oss AuthorityList (r:1 w:0)
+	// Storage: FileBank PendingDelete (r:1 w:1)
+	fn cancel_delete() -> Weight {
+		Weight::from_ref_time(24_903_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank DownloadPrice (r:0 w:1)
+	fn set_download_price() -> Weight {
+		Weight::from_ref_time(26_410_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank FileBuyer (r:1 w:1)
+	// Storage: FileBank DownloadPrice (r:1 w:0)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: System Account (r:2 w:4)
+	fn buy_file() -> Weight {
+		Weight::from_ref_time(120_530_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(5 as u64))
+			.saturating_add(T::DbWeight::get().writes(5 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank PublicDataset (r:1 w:0)
+	// Storage: FileBank FileBuyer (r:1 w:0)
+	// Storage: FileBank RetrievalReceipts (r:1 w:1)
+	// Storage: FileBank ReceiptCount (r:1 w:1)
+	// Storage: FileBank FilePopularity (r:1 w:1)
+	fn submit_retrieval_receipt() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(6 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: FileBank DealAuctions (r:1 w:1)
+	fn commit_deal_bid() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank DealAuctions (r:1 w:1)
+	fn reveal_deal_bid() -> Weight {
+		Weight::from_ref_time(35_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler NextLeaseSlot (r:1 w:1)
+	// Storage: StorageHandler LeaseExpireQueue (r:1 w:1)
+	// Storage: FileBank ClearUserList (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	fn lease_sweep(v: u32, ) -> Weight {
+		Weight::from_ref_time(28_460_000 as u64)
+			// Standard Error: 21_000
+			.saturating_add(Weight::from_ref_time(1_132_000 as u64).saturating_mul(v as u64))
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().reads((3 as u64).saturating_mul(v as u64)))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+			.saturating_add(T::DbWeight::get().writes((2 as u64).saturating_mul(v as u64)))
+	}
+	// Storage: FileBank ClearUserList (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:1)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank FileRefCount (r:0 w:1)
+	// Storage: FileBank FileSummary (r:0 w:1)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: StorageHandler TotalServiceSpace (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:0 w:1)
+	// Storage: FileBank Bucket (r:0 w:1)
+	// Storage: FileBank UserBucketList (r:0 w:1)
+	fn clear_user_files(v: u32, ) -> Weight {
+		Weight::from_ref_time(41_870_000 as u64)
+			// Standard Error: 96_000
+			.saturating_add(Weight::from_ref_time(61_254_000 as u64).saturating_mul(v as u64))
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().reads((3 as u64).saturating_mul(v as u64)))
+			.saturating_add(T::DbWeight::get().writes(5 as u64))
+			.saturating_add(T::DbWeight::get().writes((5 as u64).saturating_mul(v as u64)))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank UserFileIndex (r:1 w:0)
+	// Storage: FileBank ShareNonce (r:1 w:1)
+	// Storage: FileBank FileShares (r:1 w:1)
+	// Storage: FileBank ShareExpiryQueue (r:1 w:1)
+	fn create_share() -> Weight {
+		Weight::from_ref_time(41_236_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(5 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: FileBank FileShares (r:1 w:1)
+	fn revoke_share() -> Weight {
+		Weight::from_ref_time(27_918_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: Oss Oss (r:1 w:0)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank FileShares (r:1 w:1)
+	fn redeem_share() -> Weight {
+		Weight::from_ref_time(33_504_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank FileSizeLimit (r:0 w:1)
+	fn set_file_size_limit() -> Weight {
+		Weight::from_ref_time(14_806_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank InvalidFile (r:1 w:0)
+	// Storage: FileBank InvalidFileReminders (r:1 w:1)
+	// Storage: FileBank InvalidFileDeadline (r:1 w:1)
+	// Storage: FileBank InvalidFileQueue (r:1 w:1)
+	fn remind_invalid_file() -> Weight {
+		Weight::from_ref_time(38_412_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: FileBank Bucket (r:1 w:0)
+	// Storage: FileBank BucketPolicy (r:0 w:1)
+	fn set_bucket_policy() -> Weight {
+		Weight::from_ref_time(24_907_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: Sminer MinerItems (r:3 w:3)
+	// Storage: FileBank PendingDealCount (r:1 w:1)
+	// Storage: FileBank DealDeposit (r:1 w:1)
+	// Storage: FileBank DealPolicy (r:0 w:1)
+	fn withdraw_declaration() -> Weight {
+		Weight::from_ref_time(96_530_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(8 as u64))
+			.saturating_add(T::DbWeight::get().writes(9 as u64))
+	}
+	// Storage: FileBank StorageOrders (r:1 w:1)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: StorageHandler UnitPrice (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn create_storage_order() -> Weight {
+		Weight::from_ref_time(38_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: FileBank StorageOrders (r:1 w:1)
+	// Storage: FileBank DealMap (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn cancel_storage_order() -> Weight {
+		Weight::from_ref_time(34_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: FileBank File (r:2 w:1)
+	// Storage: FileBank Bucket (r:1 w:1)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: StorageHandler TotalServiceSpace (r:1 w:1)
+	// Storage: FileBank DealDeposit (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: FileBank FileSegments (r:0 w:4)
+	fn segment_report_timeout() -> Weight {
+		Weight::from_ref_time(88_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(9 as u64))
+			.saturating_add(T::DbWeight::get().writes(12 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: Sminer MinerItems (r:8 w:8)
+	// Storage: Sminer AllMiner (r:1 w:0)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:0)
+	// Storage: FileBank DealPolicy (r:1 w:0)
+	// Storage: FileBank FileSegments (r:0 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:1 w:1)
+	fn deal_reassign_miner() -> Weight {
+		Weight::from_ref_time(180_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(14 as u64))
+			.saturating_add(T::DbWeight::get().writes(12 as u64))
+	}
+	// Storage: TeeWorker SchedulerMap (r:1 w:0)
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: Sminer MinerItems (r:8 w:8)
+	// Storage: Sminer AllMiner (r:1 w:0)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:0)
+	// Storage: FileBank DealPolicy (r:1 w:0)
+	// Storage: FileBank FileSegments (r:0 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:2 w:2)
+	// Storage: SchedulerCredit CurrentCounters (r:2 w:2)
+	fn force_reassign_deal() -> Weight {
+		Weight::from_ref_time(200_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(18 as u64))
+			.saturating_add(T::DbWeight::get().writes(15 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: Sminer MinerItems (r:8 w:8)
+	// Storage: FileBank FileSegments (r:1 w:0)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank FileScheduler (r:0 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:1 w:1)
+	fn calculate_end() -> Weight {
+		Weight::from_ref_time(120_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(15 as u64))
+			.saturating_add(T::DbWeight::get().writes(15 as u64))
+	}
+	// Storage: FileBank PendingReplacements (r:1 w:1)
+	// Storage: FileBank FillerMap (r:1 w:1)
+	// Storage: FileBank FillerPosition (r:1 w:2)
+	// Storage: FileBank FillerCount (r:1 w:1)
+	// Storage: FileBank FillerIndex (r:1 w:2)
+	fn replace_file_report(v: u32, ) -> Weight {
+		Weight::from_ref_time(25_000_000 as u64)
+			.saturating_add(Weight::from_ref_time(12_000_000 as u64).saturating_mul(v as u64))
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().reads((4 as u64).saturating_mul(v as u64)))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+			.saturating_add(T::DbWeight::get().writes((6 as u64).saturating_mul(v as u64)))
+	}
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:2 w:2)
+	// Storage: FileBank LeaseSweepPeriod (r:0 w:1)
+	fn set_lease_sweep_period() -> Weight {
+		Weight::from_ref_time(40_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	// Storage: FileBank RestoralOrder (r:1 w:1)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank FileSummary (r:0 w:1)
+	fn generate_restoral_order() -> Weight {
+		Weight::from_ref_time(45_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: FileBank RestoralOrder (r:1 w:1)
+	fn claim_restoral_order() -> Weight {
+		Weight::from_ref_time(35_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: FileBank RestoralOrder (r:1 w:1)
+	// Storage: FileBank RestoralTarget (r:1 w:0)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank FileSummary (r:0 w:1)
+	fn claim_restoral_noexist_order() -> Weight {
+		Weight::from_ref_time(50_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: Sminer MinerItems (r:2 w:2)
+	// Storage: FileBank RestoralOrder (r:1 w:1)
+	// Storage: TeeWorker TeeWorkerMap (r:3 w:0)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank RestoralTarget (r:1 w:1)
+	// Storage: FileBank FileSummary (r:0 w:1)
+	// Storage: FileBank RecoveryQueue (r:1 w:1)
+	fn restoral_order_complete() -> Weight {
+		Weight::from_ref_time(90_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(9 as u64))
+			.saturating_add(T::DbWeight::get().writes(7 as u64))
+	}
+	// Storage: FileBank MinerLock (r:1 w:1)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:1 w:1)
+	fn miner_exit_prep() -> Weight {
+		Weight::from_ref_time(45_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: FileBank FillerMap (r:0 w:1)
+	// Storage: StorageHandler TotalIdleSpace (r:1 w:1)
+	// Storage: Sminer AllMiner (r:1 w:1)
+	// Storage: FileBank RestoralTarget (r:0 w:1)
+	// Storage: FileBank PendingRecoveryMiners (r:1 w:1)
+	fn miner_exit() -> Weight {
+		Weight::from_ref_time(80_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(6 as u64))
+	}
+	// Storage: FileBank RestoralTarget (r:1 w:0)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: Sminer AllMiner (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn miner_withdraw() -> Weight {
+		Weight::from_ref_time(60_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(5 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	// Storage: FileBank UserFileTrie (r:1 w:0)
+	fn user_file_root() -> Weight {
+		Weight::from_ref_time(24_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank StorageOrders (r:1 w:0)
+	// Storage: FileBank PendingTransfer (r:0 w:1)
+	fn transfer_file() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
 }
 
 // For backwards compatibility and tests
@@ -199,7 +629,7 @@ impl WeightInfo for () {
 	// Storage: TeeWorker SchedulerMap (r:1 w:0)
 	// Storage: FileBank File (r:1 w:1)
 	// Storage: FileBank UserOwnedSpace (r:1 w:1)
-	// Storage: FileBank UserHoldFileList (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:1)
 	// Storage: FileBank FileKeysMap (r:1 w:1)
 	// Storage: FileBank CounterForFileKeysMap (r:1 w:1)
 	// Storage: SchedulerCredit CurrentCounters (r:1 w:1)
@@ -227,7 +657,7 @@ impl WeightInfo for () {
 	// Storage: Sminer TotalServiceSpace (r:1 w:1)
 	// Storage: FileBank FileKeysMap (r:1 w:1)
 	// Storage: FileBank CounterForFileKeysMap (r:1 w:1)
-	// Storage: FileBank UserHoldFileList (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:1)
 	fn delete_file() -> Weight {
 		Weight::from_ref_time(376_051_000 as u64)
 			.saturating_add(RocksDbWeight::get().reads(9 as u64))
@@ -270,10 +700,397 @@ impl WeightInfo for () {
 	// Storage: FileBank File (r:1 w:1)
 	// Storage: FileBank Bucket (r:2 w:2)
 	// Storage: FileBank UserOwnedSpace (r:2 w:2)
-	// Storage: FileBank UserHoldFileList (r:2 w:2)
+	// Storage: FileBank UserFileIndex (r:2 w:2)
 	fn ownership_transfer() -> Weight {
 		Weight::from_ref_time(322_561_000 as u64)
 			.saturating_add(RocksDbWeight::get().reads(7 as u64))
 			.saturating_add(RocksDbWeight::get().writes(7 as u64))
 	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank FileAcl (r:0 w:1)
+	fn grant_access() -> Weight {
+		Weight::from_ref_time(28_502_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank FileAcl (r:1 w:1)
+	fn revoke_access() -> Weight {
+		Weight::from_ref_time(26_114_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank PublicDataset (r:0 w:1)
+	fn set_public_dataset() -> Weight {
+		Weight::from_ref_time(27_640_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:0)
+	// Storage: FileBank FileSegments (r:1 w:1)
+	// Storage: FileBank File (r:1 w:0)
+	fn upload_segment() -> Weight {
+		Weight::from_ref_time(41_208_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank File (r:2 w:0)
+	// Storage: FileBank FileVersions (r:2 w:2)
+	fn update_file() -> Weight {
+		Weight::from_ref_time(35_412_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: FileBank File (r:2 w:0)
+	// Storage: FileBank FileVersions (r:1 w:2)
+	fn rollback_version() -> Weight {
+		Weight::from_ref_time(33_905_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: FileBank UserFileMigrating (r:1 w:0)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:0)
+	fn audit_user_space() -> Weight {
+		Weight::from_ref_time(48_730_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank DeclarationLimit (r:0 w:1)
+	fn set_declaration_limit() -> Weight {
+		Weight::from_ref_time(18_210_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank FileMirror (r:0 w:1)
+	fn request_mirror() -> Weight {
+		Weight::from_ref_time(30_115_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: Oss AuthorityList (r:1 w:0)
+	// Storage: FileBank FileMirror (r:1 w:1)
+	fn confirm_mirror() -> Weight {
+		Weight::from_ref_time(29_870_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: Oss AuthorityList (r:1 w:0)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank PendingDelete (r:1 w:1)
+	// Storage: FileBank DeleteQueue (r:1 w:1)
+	fn request_delete() -> Weight {
+		Weight::from_ref_time(40_356_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: Oss AuthorityList (r:1 w:0)
+	// Storage: FileBank PendingDelete (r:1 w:1)
+	fn cancel_delete() -> Weight {
+		Weight::from_ref_time(24_903_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank DownloadPrice (r:0 w:1)
+	fn set_download_price() -> Weight {
+		Weight::from_ref_time(26_410_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank FileBuyer (r:1 w:1)
+	// Storage: FileBank DownloadPrice (r:1 w:0)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: System Account (r:2 w:4)
+	fn buy_file() -> Weight {
+		Weight::from_ref_time(120_530_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes(5 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank PublicDataset (r:1 w:0)
+	// Storage: FileBank FileBuyer (r:1 w:0)
+	// Storage: FileBank RetrievalReceipts (r:1 w:1)
+	// Storage: FileBank ReceiptCount (r:1 w:1)
+	// Storage: FileBank FilePopularity (r:1 w:1)
+	fn submit_retrieval_receipt() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(6 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: FileBank DealAuctions (r:1 w:1)
+	fn commit_deal_bid() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank DealAuctions (r:1 w:1)
+	fn reveal_deal_bid() -> Weight {
+		Weight::from_ref_time(35_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler NextLeaseSlot (r:1 w:1)
+	// Storage: StorageHandler LeaseExpireQueue (r:1 w:1)
+	// Storage: FileBank ClearUserList (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	fn lease_sweep(v: u32, ) -> Weight {
+		Weight::from_ref_time(28_460_000 as u64)
+			// Standard Error: 21_000
+			.saturating_add(Weight::from_ref_time(1_132_000 as u64).saturating_mul(v as u64))
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().reads((3 as u64).saturating_mul(v as u64)))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes((2 as u64).saturating_mul(v as u64)))
+	}
+	// Storage: FileBank ClearUserList (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:1)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank FileRefCount (r:0 w:1)
+	// Storage: FileBank FileSummary (r:0 w:1)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: StorageHandler TotalServiceSpace (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:0 w:1)
+	// Storage: FileBank Bucket (r:0 w:1)
+	// Storage: FileBank UserBucketList (r:0 w:1)
+	fn clear_user_files(v: u32, ) -> Weight {
+		Weight::from_ref_time(41_870_000 as u64)
+			// Standard Error: 96_000
+			.saturating_add(Weight::from_ref_time(61_254_000 as u64).saturating_mul(v as u64))
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().reads((3 as u64).saturating_mul(v as u64)))
+			.saturating_add(RocksDbWeight::get().writes(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes((5 as u64).saturating_mul(v as u64)))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank UserFileIndex (r:1 w:0)
+	// Storage: FileBank ShareNonce (r:1 w:1)
+	// Storage: FileBank FileShares (r:1 w:1)
+	// Storage: FileBank ShareExpiryQueue (r:1 w:1)
+	fn create_share() -> Weight {
+		Weight::from_ref_time(41_236_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: FileBank FileShares (r:1 w:1)
+	fn revoke_share() -> Weight {
+		Weight::from_ref_time(27_918_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: Oss Oss (r:1 w:0)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank FileShares (r:1 w:1)
+	fn redeem_share() -> Weight {
+		Weight::from_ref_time(33_504_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank FileSizeLimit (r:0 w:1)
+	fn set_file_size_limit() -> Weight {
+		Weight::from_ref_time(14_806_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank InvalidFile (r:1 w:0)
+	// Storage: FileBank InvalidFileReminders (r:1 w:1)
+	// Storage: FileBank InvalidFileDeadline (r:1 w:1)
+	// Storage: FileBank InvalidFileQueue (r:1 w:1)
+	fn remind_invalid_file() -> Weight {
+		Weight::from_ref_time(38_412_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: FileBank Bucket (r:1 w:0)
+	// Storage: FileBank BucketPolicy (r:0 w:1)
+	fn set_bucket_policy() -> Weight {
+		Weight::from_ref_time(24_907_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: Sminer MinerItems (r:3 w:3)
+	// Storage: FileBank PendingDealCount (r:1 w:1)
+	// Storage: FileBank DealDeposit (r:1 w:1)
+	// Storage: FileBank DealPolicy (r:0 w:1)
+	fn withdraw_declaration() -> Weight {
+		Weight::from_ref_time(96_530_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(8 as u64))
+			.saturating_add(RocksDbWeight::get().writes(9 as u64))
+	}
+	// Storage: FileBank StorageOrders (r:1 w:1)
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: StorageHandler UnitPrice (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn create_storage_order() -> Weight {
+		Weight::from_ref_time(38_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: FileBank StorageOrders (r:1 w:1)
+	// Storage: FileBank DealMap (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn cancel_storage_order() -> Weight {
+		Weight::from_ref_time(34_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: FileBank File (r:2 w:1)
+	// Storage: FileBank Bucket (r:1 w:1)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: StorageHandler TotalServiceSpace (r:1 w:1)
+	// Storage: FileBank DealDeposit (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: FileBank FileSegments (r:0 w:4)
+	fn segment_report_timeout() -> Weight {
+		Weight::from_ref_time(88_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(9 as u64))
+			.saturating_add(RocksDbWeight::get().writes(12 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: Sminer MinerItems (r:8 w:8)
+	// Storage: Sminer AllMiner (r:1 w:0)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:0)
+	// Storage: FileBank DealPolicy (r:1 w:0)
+	// Storage: FileBank FileSegments (r:0 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:1 w:1)
+	fn deal_reassign_miner() -> Weight {
+		Weight::from_ref_time(180_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(14 as u64))
+			.saturating_add(RocksDbWeight::get().writes(12 as u64))
+	}
+	// Storage: TeeWorker SchedulerMap (r:1 w:0)
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: Sminer MinerItems (r:8 w:8)
+	// Storage: Sminer AllMiner (r:1 w:0)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:0)
+	// Storage: FileBank DealPolicy (r:1 w:0)
+	// Storage: FileBank FileSegments (r:0 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:2 w:2)
+	// Storage: SchedulerCredit CurrentCounters (r:2 w:2)
+	fn force_reassign_deal() -> Weight {
+		Weight::from_ref_time(200_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(18 as u64))
+			.saturating_add(RocksDbWeight::get().writes(15 as u64))
+	}
+	// Storage: FileBank DealMap (r:1 w:1)
+	// Storage: Sminer MinerItems (r:8 w:8)
+	// Storage: FileBank FileSegments (r:1 w:0)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank FileScheduler (r:0 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UserFileIndex (r:1 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:1 w:1)
+	fn calculate_end() -> Weight {
+		Weight::from_ref_time(120_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(15 as u64))
+			.saturating_add(RocksDbWeight::get().writes(15 as u64))
+	}
+	// Storage: FileBank PendingReplacements (r:1 w:1)
+	// Storage: FileBank FillerMap (r:1 w:1)
+	// Storage: FileBank FillerPosition (r:1 w:2)
+	// Storage: FileBank FillerCount (r:1 w:1)
+	// Storage: FileBank FillerIndex (r:1 w:2)
+	fn replace_file_report(v: u32, ) -> Weight {
+		Weight::from_ref_time(25_000_000 as u64)
+			.saturating_add(Weight::from_ref_time(12_000_000 as u64).saturating_mul(v as u64))
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().reads((4 as u64).saturating_mul(v as u64)))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes((6 as u64).saturating_mul(v as u64)))
+	}
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:2 w:2)
+	// Storage: FileBank LeaseSweepPeriod (r:0 w:1)
+	fn set_lease_sweep_period() -> Weight {
+		Weight::from_ref_time(40_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	// Storage: FileBank RestoralOrder (r:1 w:1)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank FileSummary (r:0 w:1)
+	fn generate_restoral_order() -> Weight {
+		Weight::from_ref_time(45_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: FileBank RestoralOrder (r:1 w:1)
+	fn claim_restoral_order() -> Weight {
+		Weight::from_ref_time(35_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: FileBank RestoralOrder (r:1 w:1)
+	// Storage: FileBank RestoralTarget (r:1 w:0)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank FileSummary (r:0 w:1)
+	fn claim_restoral_noexist_order() -> Weight {
+		Weight::from_ref_time(50_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: Sminer MinerItems (r:2 w:2)
+	// Storage: FileBank RestoralOrder (r:1 w:1)
+	// Storage: TeeWorker TeeWorkerMap (r:3 w:0)
+	// Storage: FileBank File (r:1 w:1)
+	// Storage: FileBank RestoralTarget (r:1 w:1)
+	// Storage: FileBank FileSummary (r:0 w:1)
+	// Storage: FileBank RecoveryQueue (r:1 w:1)
+	fn restoral_order_complete() -> Weight {
+		Weight::from_ref_time(90_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(9 as u64))
+			.saturating_add(RocksDbWeight::get().writes(7 as u64))
+	}
+	// Storage: FileBank MinerLock (r:1 w:1)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: Scheduler Lookup (r:1 w:1)
+	// Storage: Scheduler Agenda (r:1 w:1)
+	fn miner_exit_prep() -> Weight {
+		Weight::from_ref_time(45_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: FileBank FillerMap (r:0 w:1)
+	// Storage: StorageHandler TotalIdleSpace (r:1 w:1)
+	// Storage: Sminer AllMiner (r:1 w:1)
+	// Storage: FileBank RestoralTarget (r:0 w:1)
+	// Storage: FileBank PendingRecoveryMiners (r:1 w:1)
+	fn miner_exit() -> Weight {
+		Weight::from_ref_time(80_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(6 as u64))
+	}
+	// Storage: FileBank RestoralTarget (r:1 w:0)
+	// Storage: Sminer MinerItems (r:1 w:1)
+	// Storage: Sminer AllMiner (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn miner_withdraw() -> Weight {
+		Weight::from_ref_time(60_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	// Storage: FileBank UserFileTrie (r:1 w:0)
+	fn user_file_root() -> Weight {
+		Weight::from_ref_time(24_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+	}
+	// Storage: FileBank File (r:1 w:0)
+	// Storage: FileBank StorageOrders (r:1 w:0)
+	// Storage: FileBank PendingTransfer (r:0 w:1)
+	fn transfer_file() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
 }
@@ -16,7 +16,7 @@ use frame_support::{
 use log::{debug, warn};
 use scale_info::TypeInfo;
 use sp_runtime::{
-	traits::{SaturatedConversion, Zero},
+	traits::{SaturatedConversion, Saturating, Zero},
 	Percent,
 	RuntimeDebug, Perbill,
 };
@@ -93,6 +93,10 @@ pub mod pallet {
 		#[pallet::constant]
 		type PeriodDuration: Get<Self::BlockNumber>;
 
+		/// Part of its credit value a scheduler loses every period it does no work.
+		#[pallet::constant]
+		type CreditDecay: Get<Percent>;
+
 		type StashAccountFinder: SchedulerStashAccountFinder<Self::AccountId>;
 	}
 
@@ -161,6 +165,20 @@ impl<T: Config> Pallet<T> {
 			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
 		}
 
+		// Schedulers without work in the period keep a decayed value instead of dropping to zero.
+		if period > 0 {
+			let keep = Percent::one().saturating_sub(T::CreditDecay::get());
+			for (ctrl_account_id, credit_value) in HistoryCreditValues::<T>::iter_prefix(&period.saturating_sub(1)) {
+				weight = weight.saturating_add(T::DbWeight::get().reads(1));
+				let decayed = keep * credit_value;
+				if decayed == 0 || HistoryCreditValues::<T>::contains_key(&period, &ctrl_account_id) {
+					continue;
+				}
+				HistoryCreditValues::<T>::insert(&period, &ctrl_account_id, decayed);
+				weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			}
+		}
+
 		// Clear CurrentCounters
 		#[allow(deprecated)]
 		let cc_outcome = CurrentCounters::<T>::remove_all(None);
@@ -200,14 +218,7 @@ impl<T: Config> Pallet<T> {
 				if let Some(stash_account_id) =
 					T::StashAccountFinder::find_stash_account_id(&ctrl_account_id)
 				{
-					let mut credit_score = 0_u32;
-					for (index, weight) in PERIOD_WEIGHT.into_iter().enumerate() {
-						if last_period >= index as u32 {
-							let credit_value = HistoryCreditValues::<T>::try_get(&last_period.saturating_sub(index as u32), &ctrl_account_id)
-								.unwrap_or(0);
-							credit_score += weight * credit_value;
-						}
-					}
+					let credit_score = Self::credit_score(last_period, &ctrl_account_id);
 					debug!(
 						target: LOG_TARGET,
 						"scheduler stash account: {:?}, credit value: {}",
@@ -225,6 +236,30 @@ impl<T: Config> Pallet<T> {
 		});
 		result
 	}
+
+	/// The credit values of the scheduler in the periods up to `last_period`, weighted by age.
+	fn credit_score(last_period: u32, ctrl_account_id: &T::AccountId) -> CreditScore {
+		let mut credit_score = 0_u32;
+		for (index, weight) in PERIOD_WEIGHT.into_iter().enumerate() {
+			if last_period >= index as u32 {
+				let credit_value = HistoryCreditValues::<T>::try_get(&last_period.saturating_sub(index as u32), ctrl_account_id)
+					.unwrap_or(0);
+				credit_score += weight * credit_value;
+			}
+		}
+		credit_score
+	}
+
+	/// The credit score of a scheduler as of the last finished period.
+	pub fn current_credit(ctrl_account_id: &T::AccountId) -> CreditScore {
+		let now = <frame_system::Pallet<T>>::block_number();
+		let period: u32 = (now / T::PeriodDuration::get()).saturated_into();
+		if period == 0 {
+			return 0;
+		}
+
+		Self::credit_score(period.saturating_sub(1), ctrl_account_id)
+	}
 }
 
 impl<T: Config> SchedulerCreditCounter<T::AccountId> for Pallet<T> {
@@ -237,6 +272,10 @@ impl<T: Config> SchedulerCreditCounter<T::AccountId> for Pallet<T> {
 		Pallet::<T>::record_punishment(scheduler_id)?;
 		Ok(())
 	}
+
+	fn current_credit(scheduler_id: &T::AccountId) -> u32 {
+		Pallet::<T>::current_credit(scheduler_id)
+	}
 }
 
 impl<T: Config> ValidatorCredits<T::AccountId> for Pallet<T> {
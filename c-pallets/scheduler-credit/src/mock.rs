@@ -57,12 +57,15 @@ impl<AccountId: Clone> SchedulerStashAccountFinder<AccountId>
 
 parameter_types! {
 	pub const PeriodDuration: BlockNumber = 3600;
+	pub const CreditDecay: Percent = Percent::from_percent(20);
 }
 
 impl Config for Test {
 	type StashAccountFinder = MockStashAccountFinder<Self::AccountId>;
 
 	type PeriodDuration = PeriodDuration;
+
+	type CreditDecay = CreditDecay;
 }
 
 frame_support::construct_runtime!(
@@ -35,3 +35,26 @@ fn figure_credit_scores_works() {
 		assert_eq!(&187, vc_map.get(&3).unwrap());
 	});
 }
+
+#[test]
+fn idle_scheduler_credit_decays() {
+	ExtBuilder::default().build_and_execute(|| {
+		<Pallet<Test> as SchedulerCreditCounter<AccountId>>::record_proceed_block_size(&1, 200);
+		<Pallet<Test> as SchedulerCreditCounter<AccountId>>::record_proceed_block_size(&2, 200);
+
+		let period_duration = PeriodDuration::get();
+		System::set_block_number(period_duration);
+		Pallet::<Test>::on_initialize(System::block_number());
+		assert_eq!(HistoryCreditValues::<Test>::get(&0, &1), 500);
+
+		// only the second scheduler works in the next period
+		<Pallet<Test> as SchedulerCreditCounter<AccountId>>::record_proceed_block_size(&2, 100);
+		System::set_block_number(period_duration * 2);
+		Pallet::<Test>::on_initialize(System::block_number());
+
+		assert_eq!(HistoryCreditValues::<Test>::get(&1, &1), 400);
+		assert_eq!(HistoryCreditValues::<Test>::get(&1, &2), 1000);
+		assert_eq!(<Pallet<Test> as SchedulerCreditCounter<AccountId>>::current_credit(&1), 300);
+		assert_eq!(<Pallet<Test> as SchedulerCreditCounter<AccountId>>::current_credit(&2), 600);
+	});
+}
@@ -21,9 +21,22 @@ pub(super) const ISSUE_MUTI: Perbill = Perbill::from_percent(20);
 pub(super) const EACH_SHARE_MUTI: Perbill = Perbill::from_percent(80);
 
 pub(super) const RELEASE_NUMBER: u8 = 180;
+// Most regions kept in the heatmap, above the number of country codes.
+pub(super) const REGION_LIMIT: u32 = 300;
 
 pub(super) const IDLE_PUNI_MUTI: Perbill = Perbill::from_percent(10);
 
 pub(super) const SERVICE_PUNI_MUTI: Perbill = Perbill::from_percent(25);
-
-pub(super) const BASE_LIMIT: u128 = 2_000_000_000_000_000;
\ No newline at end of file
+// Share of the collateral limit slashed for every invalid file not cleared in time.
+pub(super) const INVALID_FILE_PUNI_MUTI: Perbill = Perbill::from_percent(1);
+// Share of the collateral limit slashed from a miner the tee workers repeatedly could not reach.
+// Lower than the challenge punishments, an unreachable miner has not been shown to lose data.
+pub(super) const UNREACHABLE_PUNI_MUTI: Perbill = Perbill::from_percent(5);
+
+pub(super) const BASE_LIMIT: u128 = 2_000_000_000_000_000;
+// Number of missed heartbeat intervals after which a miner is no longer considered alive.
+pub(super) const HEARTBEAT_TOLERANCE: u32 = 3;
+// Once a miner has more recorded challenges, its counts are halved so that recent challenges weigh more.
+pub(super) const REPUTATION_WINDOW: u32 = 100;
+// Number of recorded challenges the endorsement reputation weighs as much as in the placement reputation.
+pub(super) const ENDORSEMENT_CHALLENGES: u128 = 20;
@@ -13,8 +13,14 @@
 //! ### Dispatchable Functions
 //!
 //! * `regnstk` - Staking and register for storage miner.
+//! * `onboard_miner` - Register, set the heartbeat key and the price floor in one call.
 //! * `redeem` - Redeem and exit for storage miner.
 //! * `claim` - Claim the rewards from storage miner's earnings.
+//! * `set_heartbeat_key` - Register the key the miner's offchain worker signs heartbeats with.
+//! * `heartbeat` - Unsigned liveness heartbeat submitted by the miner's offchain worker.
+//! * `endorse_miner` - A gateway stakes on a miner it retrieved data from.
+//! * `withdraw_endorsement` - A gateway withdraws its endorsement and the stake.
+//! * `set_region` - Declare the region the miner node runs in.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -31,10 +37,11 @@ use frame_support::{
 		schedule::{Anon as ScheduleAnon, Named as ScheduleNamed},
 		Currency,
 		ExistenceRequirement::KeepAlive,
-		Get, Imbalance, OnUnbalanced, ReservableCurrency,
+		Contains, Get, Imbalance, OnUnbalanced, ReservableCurrency,
 	},
 };
 use cp_cess_common::*;
+use cp_cess_common::slash::{SlashPolicy, SlashSource};
 
 use sp_runtime::traits::Zero;
 
@@ -58,11 +65,16 @@ use frame_system::{self as system};
 pub use pallet::*;
 use scale_info::TypeInfo;
 use sp_runtime::{
-	traits::{AccountIdConversion, CheckedAdd, CheckedSub, SaturatedConversion},
+	traits::{AccountIdConversion, CheckedAdd, CheckedSub, SaturatedConversion, Saturating},
 	RuntimeDebug, Perbill,
 };
-use sp_std::{convert::TryInto, prelude::*};
-use sp_core::ConstU32;
+use sp_std::{collections::btree_map::BTreeMap, convert::TryInto, prelude::*};
+use sp_core::{crypto::KeyTypeId, ConstU32};
+use sp_runtime::{
+	app_crypto::RuntimeAppPublic,
+	offchain::storage::{StorageRetrievalError, StorageValueRef},
+};
+use frame_system::offchain::{SendTransactionTypes, SubmitTransaction};
 
 pub mod weights;
 pub use weights::WeightInfo;
@@ -75,6 +87,42 @@ type NegativeImbalanceOf<T> = <<T as pallet::Config>::Currency as Currency<
 >>::NegativeImbalance;
 type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
 
+pub const MINER_HEARTBEAT: KeyTypeId = KeyTypeId(*b"smhb");
+
+pub mod sr25519 {
+	mod app_sr25519 {
+		use crate::*;
+		use sp_runtime::app_crypto::{app_crypto, sr25519};
+		app_crypto!(sr25519, MINER_HEARTBEAT);
+	}
+
+	sp_runtime::app_crypto::with_pair! {
+		pub type AuthorityPair = app_sr25519::Pair;
+	}
+
+	pub type AuthoritySignature = app_sr25519::Signature;
+
+	pub type AuthorityId = app_sr25519::Public;
+}
+
+enum OffchainErr {
+	Ineligible,
+	Working,
+	FailedSigning,
+	SubmitTransactionFailed,
+}
+
+impl sp_std::fmt::Debug for OffchainErr {
+	fn fmt(&self, fmt: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		match *self {
+			OffchainErr::Ineligible => write!(fmt, "No local heartbeat key belongs to a registered miner"),
+			OffchainErr::Working => write!(fmt, "A heartbeat of this key is still waiting for inclusion"),
+			OffchainErr::FailedSigning => write!(fmt, "Signing heartbeat failed"),
+			OffchainErr::SubmitTransactionFailed => write!(fmt, "Failed to submit transaction."),
+		}
+	}
+}
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
@@ -85,11 +133,15 @@ pub mod pallet {
 	use frame_system::{ensure_signed, pallet_prelude::*};
 
 	#[pallet::config]
-	pub trait Config: pallet_timestamp::Config + frame_system::Config {
+	pub trait Config:
+		pallet_timestamp::Config + frame_system::Config + SendTransactionTypes<Call<Self>>
+	{
 		/// The overarching event type.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 		/// The currency trait.
 		type Currency: ReservableCurrency<Self::AccountId>;
+		/// Where the collateral slashed from punished miners goes.
+		type SlashPolicy: SlashPolicy<Self::AccountId, Self::Currency>;
 		/// The treasury's pallet id, used for deriving its sovereign account ID.
 		#[pallet::constant]
 		type PalletId: Get<PalletId>;
@@ -118,6 +170,30 @@ pub mod pallet {
 		type SProposal: Parameter + Dispatchable<RuntimeOrigin = Self::RuntimeOrigin> + From<Call<Self>>;
 		/// The WeightInfo.
 		type WeightInfo: WeightInfo;
+		/// The key a miner's offchain worker signs its heartbeats with.
+		type AuthorityId: Member
+			+ Parameter
+			+ RuntimeAppPublic
+			+ Ord
+			+ MaybeSerializeDeserialize
+			+ MaxEncodedLen;
+		/// A configuration for base priority of unsigned transactions.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+		/// Minimum number of blocks between two heartbeats of the same miner.
+		#[pallet::constant]
+		type HeartbeatInterval: Get<BlockNumberOf<Self>>;
+		/// The gateways allowed to endorse miners.
+		type Gateways: Contains<Self::AccountId>;
+		/// Minimum number of blocks between two endorsements of the same gateway.
+		#[pallet::constant]
+		type EndorsementInterval: Get<BlockNumberOf<Self>>;
+		/// Maximum number of gateways endorsing a miner.
+		#[pallet::constant]
+		type MaxEndorsements: Get<u32>;
+		/// Endorsed stake at which the endorsement reputation of a miner reaches 50%.
+		#[pallet::constant]
+		type EndorsementHalfStake: Get<BalanceOf<Self>>;
 	}
 
 	#[pallet::event]
@@ -164,7 +240,39 @@ pub mod pallet {
 		Receive {
 			acc: AccountOf<T>,
 			reward: BalanceOf<T>,
-		}
+		},
+		//A miner registered the key of its heartbeats
+		HeartbeatKeySet {
+			acc: AccountOf<T>,
+			key: T::AuthorityId,
+		},
+		//The offchain worker of a miner reported it alive
+		Heartbeat {
+			acc: AccountOf<T>,
+			block: BlockNumberOf<T>,
+		},
+		//A gateway staked on a miner it retrieved data from
+		Endorsed {
+			gateway: AccountOf<T>,
+			miner: AccountOf<T>,
+			stake: BalanceOf<T>,
+		},
+		//A gateway withdrew its endorsement of a miner
+		EndorsementWithdrawn {
+			gateway: AccountOf<T>,
+			miner: AccountOf<T>,
+			stake: BalanceOf<T>,
+		},
+		//A miner changed the lowest price it accepts deals at, None accepts any price
+		PriceFloorSet {
+			acc: AccountOf<T>,
+			floor: Option<BalanceOf<T>>,
+		},
+		//A miner declared the region it runs in, None clears it
+		MinerRegionSet {
+			acc: AccountOf<T>,
+			region: Option<RegionCode>,
+		},
 	}
 
 	/// Error for the sminer pallet.
@@ -199,6 +307,24 @@ pub mod pallet {
 		Unexpected,
 
 		NoReward,
+		//The heartbeat key is already registered by another miner
+		HeartbeatKeyInUse,
+		//The last heartbeat of the miner is too recent
+		HeartbeatTooEarly,
+		//The peer id does not inline an ed25519 node key
+		InvalidPeerId,
+		//The account is not a gateway
+		NotGateway,
+		//The last endorsement of the gateway is too recent
+		EndorsementTooEarly,
+		//The gateway does not endorse the miner
+		NotEndorsed,
+		//The region is not two upper case letters
+		InvalidRegion,
+		//The idle space of the miner is smaller than the space to reserve
+		InsufficientIdleSpace,
+		//The miner reserved less space than the space to confirm or cancel
+		InsufficientReservedSpace,
 	}
 
 	#[pallet::storage]
@@ -241,10 +367,101 @@ pub mod pallet {
 	#[pallet::getter(fn currency_reward)]
 	pub(super) type CurrencyReward<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
 
+	/// The key each miner signs its heartbeats with.
+	#[pallet::storage]
+	#[pallet::getter(fn heartbeat_key)]
+	pub(super) type HeartbeatKey<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, T::AuthorityId>;
+
+	/// The miner a heartbeat key belongs to.
+	#[pallet::storage]
+	#[pallet::getter(fn heartbeat_key_owner)]
+	pub(super) type HeartbeatKeyOwner<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AuthorityId, AccountOf<T>>;
+
+	/// The block of the last heartbeat of each miner.
+	#[pallet::storage]
+	#[pallet::getter(fn last_heartbeat)]
+	pub(super) type LastHeartbeat<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberOf<T>>;
+
+	/// The challenge outcomes of each miner.
+	#[pallet::storage]
+	#[pallet::getter(fn miner_reputation)]
+	pub(super) type MinerReputation<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, ReputationInfo, ValueQuery>;
+
+	/// The stake each gateway endorses a miner with.
+	#[pallet::storage]
+	#[pallet::getter(fn endorsement)]
+	pub(super) type Endorsements<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AccountOf<T>,
+		EndorsementInfo<BalanceOf<T>, BlockNumberOf<T>>,
+	>;
+
+	/// The number of gateways endorsing each miner and their total stake.
+	#[pallet::storage]
+	#[pallet::getter(fn endorsement_summary)]
+	pub(super) type EndorsementSummary<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, EndorsementTotal<BalanceOf<T>>, ValueQuery>;
+
+	/// The block of the last endorsement of each gateway.
+	#[pallet::storage]
+	#[pallet::getter(fn last_endorsement)]
+	pub(super) type LastEndorsement<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberOf<T>>;
+
+	/// The lowest price per gib and 30 days each miner accepts deals at.
+	#[pallet::storage]
+	#[pallet::getter(fn price_floor)]
+	pub(super) type PriceFloor<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BalanceOf<T>>;
+
+	/// The region each miner declared it runs in.
+	#[pallet::storage]
+	#[pallet::getter(fn miner_region)]
+	pub(super) type MinerRegion<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, RegionCode>;
+
+	/// The replicas and the miner health of every region, recomputed every day.
+	#[pallet::storage]
+	#[pallet::getter(fn region_heatmap)]
+	pub(super) type RegionHeatmap<T: Config> =
+		StorageValue<_, BoundedVec<RegionAvailability, ConstU32<REGION_LIMIT>>, ValueQuery>;
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
 	pub struct Pallet<T>(_);
 
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: BlockNumberOf<T>) -> Weight {
+			if now % T::OneDayBlock::get() != 0u32.saturated_into() {
+				return Weight::from_ref_time(0);
+			}
+
+			Self::update_region_heatmap()
+		}
+
+		// Miner nodes are not validators, so this runs on every node whose
+		// keystore holds a heartbeat key of a registered miner.
+		fn offchain_worker(now: T::BlockNumber) {
+			if let Err(e) = Self::offchain_heartbeat(now) {
+				match e {
+					OffchainErr::Ineligible => {},
+					_ => log::info!("offchain worker heartbeat failed: {:?}", e),
+				};
+			}
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
+	}
+
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// Staking and register for storage miner.
@@ -266,6 +483,7 @@ pub mod pallet {
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			ensure!(!(<MinerItems<T>>::contains_key(&sender)), Error::<T>::AlreadyRegistered);
+			ensure!(peer::is_valid_peer_id(&peer_id), Error::<T>::InvalidPeerId);
 			T::Currency::reserve(&sender, staking_val)?;
 
 			<MinerItems<T>>::insert(
@@ -391,6 +609,7 @@ pub mod pallet {
 		pub fn update_peer_id(origin: OriginFor<T>, peer_id: PeerId) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			ensure!(MinerItems::<T>::contains_key(&sender), Error::<T>::NotMiner);
+			ensure!(peer::is_valid_peer_id(&peer_id), Error::<T>::InvalidPeerId);
 
 			let old = <MinerItems<T>>::try_mutate(&sender, |miner_info_opt| -> Result<PeerId, DispatchError> {
 				let miner_info = miner_info_opt.as_mut().ok_or(Error::<T>::ConversionError)?;
@@ -548,10 +767,326 @@ pub mod pallet {
 			Self::deposit_event(Event::<T>::DrawFaucetMoney());
 			Ok(())
 		}
+
+		/// Register the key the miner's offchain worker signs heartbeats with.
+		///
+		/// The dispatch origin of this call must be _Signed_.
+		///
+		/// Parameters:
+		/// - `key`: The public key of the heartbeat key in the miner node's keystore.
+		#[pallet::call_index(15)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_heartbeat_key())]
+		pub fn set_heartbeat_key(origin: OriginFor<T>, key: T::AuthorityId) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(<MinerItems<T>>::contains_key(&sender), Error::<T>::NotMiner);
+
+			if let Some(owner) = <HeartbeatKeyOwner<T>>::get(&key) {
+				ensure!(owner == sender, Error::<T>::HeartbeatKeyInUse);
+			}
+
+			if let Some(old) = <HeartbeatKey<T>>::get(&sender) {
+				<HeartbeatKeyOwner<T>>::remove(&old);
+			}
+			<HeartbeatKey<T>>::insert(&sender, key.clone());
+			<HeartbeatKeyOwner<T>>::insert(&key, sender.clone());
+
+			Self::deposit_event(Event::<T>::HeartbeatKeySet { acc: sender, key });
+			Ok(())
+		}
+
+		/// Report a miner alive, submitted by the offchain worker of the miner node.
+		///
+		/// The dispatch origin of this call must be _None_,
+		/// the heartbeat is checked against the registered key of the miner in `validate_unsigned`.
+		///
+		/// Parameters:
+		/// - `heartbeat`: The miner and the block the heartbeat was generated at.
+		/// - `signature`: Signature of the heartbeat by the heartbeat key of the miner.
+		#[pallet::call_index(16)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::heartbeat())]
+		pub fn heartbeat(
+			origin: OriginFor<T>,
+			heartbeat: Heartbeat<AccountOf<T>, BlockNumberOf<T>>,
+			_signature: <T::AuthorityId as RuntimeAppPublic>::Signature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			ensure!(<MinerItems<T>>::contains_key(&heartbeat.miner), Error::<T>::NotMiner);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(!Self::heartbeat_throttled(&heartbeat.miner, now), Error::<T>::HeartbeatTooEarly);
+			<LastHeartbeat<T>>::insert(&heartbeat.miner, now);
+
+			Self::deposit_event(Event::<T>::Heartbeat { acc: heartbeat.miner, block: now });
+			Ok(())
+		}
+
+		/// Endorse a miner the gateway retrieved data from.
+		///
+		/// The dispatch origin of this call must be _Signed_ by a gateway.
+		/// The stake is reserved until the endorsement is withdrawn or the miner exits,
+		/// and raises the endorsement reputation of the miner, which carries new miners
+		/// in the placement of files. A gateway endorses at most once per `EndorsementInterval`,
+		/// endorsing a miner again adds to the stake.
+		///
+		/// Parameters:
+		/// - `miner`: The endorsed miner.
+		/// - `stake`: The amount staked on the miner.
+		#[pallet::call_index(17)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::endorse_miner())]
+		pub fn endorse_miner(origin: OriginFor<T>, miner: AccountOf<T>, stake: BalanceOf<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(T::Gateways::contains(&sender), Error::<T>::NotGateway);
+			ensure!(<MinerItems<T>>::contains_key(&miner), Error::<T>::NotMiner);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			if let Some(last) = <LastEndorsement<T>>::get(&sender) {
+				let next = last.checked_add(&T::EndorsementInterval::get()).ok_or(Error::<T>::Overflow)?;
+				ensure!(now >= next, Error::<T>::EndorsementTooEarly);
+			}
+
+			T::Currency::reserve(&sender, stake)?;
+			let is_new = !<Endorsements<T>>::contains_key(&miner, &sender);
+			<EndorsementSummary<T>>::try_mutate(&miner, |total| -> DispatchResult {
+				if is_new {
+					ensure!(total.endorsers < T::MaxEndorsements::get(), Error::<T>::StorageLimitReached);
+					total.endorsers += 1;
+				}
+				total.stake = total.stake.checked_add(&stake).ok_or(Error::<T>::Overflow)?;
+				Ok(())
+			})?;
+			<Endorsements<T>>::try_mutate(&miner, &sender, |info_opt| -> DispatchResult {
+				let info = info_opt.get_or_insert(EndorsementInfo { stake: Zero::zero(), block: now });
+				info.stake = info.stake.checked_add(&stake).ok_or(Error::<T>::Overflow)?;
+				info.block = now;
+				Ok(())
+			})?;
+			<LastEndorsement<T>>::insert(&sender, now);
+
+			Self::deposit_event(Event::<T>::Endorsed { gateway: sender, miner, stake });
+			Ok(())
+		}
+
+		/// Withdraw the endorsement of a miner and unreserve its stake.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the endorsing account,
+		/// which does not need to still be a gateway.
+		///
+		/// Parameters:
+		/// - `miner`: The endorsed miner.
+		#[pallet::call_index(18)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::withdraw_endorsement())]
+		pub fn withdraw_endorsement(origin: OriginFor<T>, miner: AccountOf<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let info = <Endorsements<T>>::take(&miner, &sender).ok_or(Error::<T>::NotEndorsed)?;
+
+			Self::release_endorsement(&miner, &sender, info.stake);
+
+			Self::deposit_event(Event::<T>::EndorsementWithdrawn { gateway: sender, miner, stake: info.stake });
+			Ok(())
+		}
+
+		/// Set the lowest price the miner accepts deals at.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the miner.
+		/// New deals are not placed on the miner while the unit price a user pays,
+		/// region multiplier included, is below the floor. Users keep buying at the posted price.
+		///
+		/// Parameters:
+		/// - `floor`: Price per gib and 30 days, None accepts deals at any price.
+		#[pallet::call_index(19)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_price_floor())]
+		pub fn set_price_floor(origin: OriginFor<T>, floor: Option<BalanceOf<T>>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(MinerItems::<T>::contains_key(&sender), Error::<T>::NotMiner);
+
+			match floor {
+				Some(value) => <PriceFloor<T>>::insert(&sender, value),
+				None => <PriceFloor<T>>::remove(&sender),
+			}
+
+			Self::deposit_event(Event::<T>::PriceFloorSet { acc: sender, floor });
+			Ok(())
+		}
+
+		/// Register a storage miner and set up everything it needs to be placed files on.
+		///
+		/// The dispatch origin of this call must be _Signed_.
+		/// Does `regnstk`, `set_heartbeat_key` and `set_price_floor` in one transaction,
+		/// either the miner is registered with all of them or with none.
+		///
+		/// Parameters:
+		/// - `beneficiary`: The beneficiary related to signer account.
+		/// - `peer_id`: The peer id of the miner node.
+		/// - `staking_val`: The number of staking.
+		/// - `key`: The public key of the heartbeat key in the miner node's keystore.
+		/// - `floor`: Price per gib and 30 days, None accepts deals at any price.
+		#[pallet::call_index(20)]
+		#[transactional]
+		#[pallet::weight(
+			<T as pallet::Config>::WeightInfo::regnstk()
+				.saturating_add(<T as pallet::Config>::WeightInfo::set_heartbeat_key())
+				.saturating_add(<T as pallet::Config>::WeightInfo::set_price_floor())
+		)]
+		pub fn onboard_miner(
+			origin: OriginFor<T>,
+			beneficiary: AccountOf<T>,
+			peer_id: PeerId,
+			staking_val: BalanceOf<T>,
+			key: T::AuthorityId,
+			floor: Option<BalanceOf<T>>,
+		) -> DispatchResult {
+			Self::regnstk(origin.clone(), beneficiary, peer_id, staking_val)?;
+			Self::set_heartbeat_key(origin.clone(), key)?;
+			if floor.is_some() {
+				Self::set_price_floor(origin, floor)?;
+			}
+
+			Ok(())
+		}
+
+		/// Declare the region the miner node runs in.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the miner.
+		/// The region is not verified, it groups the miner in the daily region heatmap.
+		///
+		/// Parameters:
+		/// - `region`: ISO 3166-1 alpha-2 country code, None clears the region.
+		#[pallet::call_index(21)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_region())]
+		pub fn set_region(origin: OriginFor<T>, region: Option<RegionCode>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(MinerItems::<T>::contains_key(&sender), Error::<T>::NotMiner);
+
+			match region {
+				Some(code) => {
+					ensure!(code.iter().all(|c| c.is_ascii_uppercase()), Error::<T>::InvalidRegion);
+					<MinerRegion<T>>::insert(&sender, code);
+				},
+				None => <MinerRegion<T>>::remove(&sender),
+			}
+
+			Self::deposit_event(Event::<T>::MinerRegionSet { acc: sender, region });
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			if let Call::heartbeat { heartbeat, signature } = call {
+				Self::check_heartbeat(heartbeat, signature)
+			} else {
+				InvalidTransaction::Call.into()
+			}
+		}
 	}
 }
 
 impl<T: Config> Pallet<T> {
+	fn check_heartbeat(
+		heartbeat: &Heartbeat<AccountOf<T>, BlockNumberOf<T>>,
+		signature: &<T::AuthorityId as RuntimeAppPublic>::Signature,
+	) -> TransactionValidity {
+		let now = <frame_system::Pallet<T>>::block_number();
+		let interval = T::HeartbeatInterval::get();
+
+		if heartbeat.block_number > now {
+			return InvalidTransaction::Future.into();
+		}
+		// A heartbeat generated a whole interval ago says nothing about the miner now.
+		if heartbeat.block_number.saturating_add(interval) <= now {
+			return InvalidTransaction::Stale.into();
+		}
+		if Self::heartbeat_throttled(&heartbeat.miner, now) {
+			return InvalidTransaction::Stale.into();
+		}
+
+		let key = match <HeartbeatKey<T>>::get(&heartbeat.miner) {
+			Some(key) => key,
+			None => return InvalidTransaction::BadSigner.into(),
+		};
+
+		let signature_valid = heartbeat.using_encoded(|encoded_heartbeat| {
+			key.verify(&encoded_heartbeat, signature)
+		});
+
+		if !signature_valid {
+			log::error!("bad heartbeat signature.");
+			return InvalidTransaction::BadProof.into()
+		}
+
+		ValidTransaction::with_tag_prefix("MinerHeartbeat")
+			.priority(T::UnsignedPriority::get())
+			.and_provides((heartbeat.miner.clone(), <LastHeartbeat<T>>::get(&heartbeat.miner)))
+			.longevity(TryInto::<u64>::try_into(interval).unwrap_or(64_u64))
+			.propagate(true)
+			.build()
+	}
+
+	/// Whether the last heartbeat of the miner is less than `HeartbeatInterval` blocks old.
+	fn heartbeat_throttled(miner: &AccountOf<T>, now: BlockNumberOf<T>) -> bool {
+		match <LastHeartbeat<T>>::get(miner) {
+			Some(last) => last.saturating_add(T::HeartbeatInterval::get()) > now,
+			None => false,
+		}
+	}
+
+	fn offchain_heartbeat(now: BlockNumberOf<T>) -> Result<(), OffchainErr> {
+		let mut eligible = false;
+		for key in T::AuthorityId::all() {
+			let miner = match <HeartbeatKeyOwner<T>>::get(&key) {
+				Some(miner) => miner,
+				None => continue,
+			};
+			eligible = true;
+
+			if Self::heartbeat_throttled(&miner, now) || !Self::check_heartbeat_lock(now, &key) {
+				continue;
+			}
+
+			let heartbeat = Heartbeat { miner, block_number: now };
+			let signature = key.sign(&heartbeat.encode()).ok_or(OffchainErr::FailedSigning)?;
+			let call = Call::heartbeat { heartbeat, signature };
+
+			if let Err(e) = SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()) {
+				log::error!("{:?}", e);
+				return Err(OffchainErr::SubmitTransactionFailed);
+			}
+		}
+
+		if !eligible {
+			return Err(OffchainErr::Ineligible);
+		}
+
+		Ok(())
+	}
+
+	// Keeps the offchain worker from submitting the same heartbeat on every block
+	// while the previous one is still waiting for inclusion.
+	fn check_heartbeat_lock(now: BlockNumberOf<T>, key: &T::AuthorityId) -> bool {
+		let lock_key = (b"sminer::heartbeat", key).encode();
+		let storage = StorageValueRef::persistent(&lock_key);
+
+		let res = storage.mutate(|status: Result<Option<BlockNumberOf<T>>, StorageRetrievalError>| {
+			match status {
+				Ok(Some(last_block)) if last_block.saturating_add(T::HeartbeatInterval::get()) > now => {
+					Err(OffchainErr::Working)
+				},
+				_ => Ok(now),
+			}
+		});
+
+		res.is_ok()
+	}
+
 	/// Add computing power to corresponding miners.
 	///
 	/// Parameters:
@@ -721,25 +1256,17 @@ impl<T: Config> Pallet<T> {
 		Ok(())
 	}
 
-	pub fn deposit_punish(miner: &AccountOf<T>, punish_amount: BalanceOf<T>) -> DispatchResult {
+	pub fn deposit_punish(miner: &AccountOf<T>, punish_amount: BalanceOf<T>, source: SlashSource) -> DispatchResult {
 		<MinerItems<T>>::try_mutate(miner, |miner_info_opt| -> DispatchResult {
 			let miner_info = miner_info_opt.as_mut().ok_or(Error::<T>::NotMiner)?;
-			
-			let reward_pot = T::PalletId::get().into_account_truncating();
 
 			if miner_info.collaterals > punish_amount {
-				T::Currency::unreserve(miner, punish_amount);
-				T::Currency::transfer(miner, &reward_pot, punish_amount, KeepAlive)?;
-				<CurrencyReward<T>>::mutate(|reward| {
-					*reward = *reward + punish_amount;
-				});
+				let (slashed, _) = T::Currency::slash_reserved(miner, punish_amount);
+				T::SlashPolicy::distribute(source, slashed, &[], None);
 				miner_info.collaterals = miner_info.collaterals.checked_sub(&punish_amount).ok_or(Error::<T>::Overflow)?;
 			} else {
-				T::Currency::unreserve(miner, miner_info.collaterals);
-				T::Currency::transfer(miner, &reward_pot, miner_info.collaterals, KeepAlive)?;
-				<CurrencyReward<T>>::mutate(|reward| {
-					*reward = *reward + miner_info.collaterals;
-				});
+				let (slashed, _) = T::Currency::slash_reserved(miner, miner_info.collaterals);
+				T::SlashPolicy::distribute(source, slashed, &[], None);
 				miner_info.collaterals = BalanceOf::<T>::zero();
 				miner_info.debt = punish_amount.checked_sub(&miner_info.collaterals).ok_or(Error::<T>::Overflow)?;
 			}
@@ -763,7 +1290,7 @@ impl<T: Config> Pallet<T> {
 
 		let punish_amount = IDLE_PUNI_MUTI.mul_floor(limit);
 
-		Self::deposit_punish(miner, punish_amount)?;
+		Self::deposit_punish(miner, punish_amount, SlashSource::MinerIdle)?;
 
 		Ok(())
 	}
@@ -774,7 +1301,7 @@ impl<T: Config> Pallet<T> {
 
 		let punish_amount = SERVICE_PUNI_MUTI.mul_floor(limit);
 
-		Self::deposit_punish(miner, punish_amount)?;
+		Self::deposit_punish(miner, punish_amount, SlashSource::MinerService)?;
 
 		Ok(())
 	}
@@ -790,7 +1317,32 @@ impl<T: Config> Pallet<T> {
 			_ => return Err(Error::<T>::Unexpected)?,
 		};
 
-		Self::deposit_punish(miner, punish_amount)?;
+		Self::deposit_punish(miner, punish_amount, SlashSource::MinerClear)?;
+
+		Ok(())
+	}
+
+	/// Punish a miner that did not clear an invalid file before its deadline.
+	pub fn invalid_file_punish(miner: &AccountOf<T>) -> DispatchResult {
+		let miner_info = <MinerItems<T>>::try_get(miner).map_err(|_| Error::<T>::NotMiner)?;
+		let power = Self::calculate_power(miner_info.idle_space, miner_info.service_space);
+		let limit = Self::check_collateral_limit(power)?;
+
+		let punish_amount = INVALID_FILE_PUNI_MUTI.mul_floor(limit);
+
+		Self::deposit_punish(miner, punish_amount, SlashSource::MinerInvalidFile)?;
+
+		Ok(())
+	}
+
+	pub fn unreachable_punish(miner: &AccountOf<T>) -> DispatchResult {
+		let miner_info = <MinerItems<T>>::try_get(miner).map_err(|_| Error::<T>::NotMiner)?;
+		let power = Self::calculate_power(miner_info.idle_space, miner_info.service_space);
+		let limit = Self::check_collateral_limit(power)?;
+
+		let punish_amount = UNREACHABLE_PUNI_MUTI.mul_floor(limit);
+
+		Self::deposit_punish(miner, punish_amount, SlashSource::MinerUnreachable)?;
 
 		Ok(())
 	}
@@ -862,14 +1414,188 @@ impl<T: Config> Pallet<T> {
 			Ok(())
 		})
 	}
+
+	// Pay for the replicas of an exited miner that were never restored by other miners.
+	// Unlike deposit_punish, the miner state is left untouched.
+	fn compensate_lost_replica(acc: &AccountOf<T>, lost_space: u128) -> DispatchResult {
+		<MinerItems<T>>::try_mutate(acc, |miner_info_opt| -> DispatchResult {
+			let miner_info = miner_info_opt.as_mut().ok_or(Error::<T>::NotMiner)?;
+
+			let limit = Self::check_collateral_limit(lost_space)?;
+			let amount = if miner_info.collaterals > limit { limit } else { miner_info.collaterals };
+
+			let reward_pot = T::PalletId::get().into_account_truncating();
+			T::Currency::unreserve(acc, amount);
+			<T as pallet::Config>::Currency::transfer(acc, &reward_pot, amount, KeepAlive)?;
+			<CurrencyReward<T>>::mutate(|reward| {
+				*reward = *reward + amount;
+			});
+			miner_info.collaterals = miner_info.collaterals.checked_sub(&amount).ok_or(Error::<T>::Overflow)?;
+
+			Ok(())
+		})
+	}
 	// Note: that it is necessary to determine whether the state meets the exit conditions before use.
 	fn withdraw(acc: &AccountOf<T>) -> DispatchResult {
 		let miner_info = <MinerItems<T>>::try_get(acc).map_err(|_| Error::<T>::NotMiner)?;
 		T::Currency::unreserve(acc, miner_info.collaterals);
 		<MinerItems<T>>::remove(acc);
 
+		if let Some(key) = <HeartbeatKey<T>>::take(acc) {
+			<HeartbeatKeyOwner<T>>::remove(&key);
+		}
+		<LastHeartbeat<T>>::remove(acc);
+		<MinerReputation<T>>::remove(acc);
+		<PriceFloor<T>>::remove(acc);
+		<MinerRegion<T>>::remove(acc);
+
+		for (gateway, info) in <Endorsements<T>>::drain_prefix(acc) {
+			T::Currency::unreserve(&gateway, info.stake);
+		}
+		<EndorsementSummary<T>>::remove(acc);
+
 		Ok(())
 	}
+
+	/// Record whether the miner passed a challenge.
+	pub fn record_challenge_result(acc: &AccountOf<T>, passed: bool) {
+		<MinerReputation<T>>::mutate(acc, |info| {
+			if passed {
+				info.successes = info.successes.saturating_add(1);
+			} else {
+				info.failures = info.failures.saturating_add(1);
+			}
+
+			if info.successes.saturating_add(info.failures) > REPUTATION_WINDOW {
+				info.successes /= 2;
+				info.failures /= 2;
+			}
+		});
+	}
+
+	/// The share of challenges the miner passed.
+	///
+	/// Every miner starts with one passed and one failed challenge,
+	/// so a new miner has a reputation of 50%.
+	pub fn get_reputation(acc: &AccountOf<T>) -> Perbill {
+		let info = <MinerReputation<T>>::get(acc);
+		Perbill::from_rational(
+			info.successes.saturating_add(1),
+			info.successes.saturating_add(info.failures).saturating_add(2),
+		)
+	}
+
+	/// The reputation of the miner among gateways.
+	///
+	/// It grows with the stake endorsing the miner and reaches 50%
+	/// when the stake equals `EndorsementHalfStake`.
+	pub fn get_endorsement(acc: &AccountOf<T>) -> Perbill {
+		let stake: u128 = <EndorsementSummary<T>>::get(acc).stake.saturated_into();
+		let half: u128 = T::EndorsementHalfStake::get().saturated_into();
+		Perbill::from_rational(stake, stake.saturating_add(half).max(1))
+	}
+
+	/// The reputation the files are placed by.
+	///
+	/// The endorsement reputation fills part of the gap left by the challenge reputation.
+	/// Its part shrinks as the miner records challenges, so it carries new miners
+	/// and hardly moves established ones.
+	pub fn get_placement_reputation(acc: &AccountOf<T>) -> Perbill {
+		let info = <MinerReputation<T>>::get(acc);
+		let challenges = info.successes.saturating_add(info.failures) as u128;
+		let reputation = Self::get_reputation(acc).deconstruct() as u128;
+		let endorsement = Self::get_endorsement(acc).deconstruct() as u128;
+
+		let gap = 1_000_000_000u128.saturating_sub(reputation);
+		let bonus = gap * endorsement / 1_000_000_000 * ENDORSEMENT_CHALLENGES / (challenges + ENDORSEMENT_CHALLENGES);
+
+		Perbill::from_parts(reputation.saturating_add(bonus) as u32)
+	}
+
+	/// The challenge, endorsement and placement reputation of the miner for the runtime api.
+	pub fn get_reputation_brief(acc: &AccountOf<T>) -> ReputationBrief {
+		let total = <EndorsementSummary<T>>::get(acc);
+		ReputationBrief {
+			challenge: Self::get_reputation(acc).deconstruct(),
+			endorsement: Self::get_endorsement(acc).deconstruct(),
+			placement: Self::get_placement_reputation(acc).deconstruct(),
+			endorsers: total.endorsers,
+			endorsed_stake: total.stake.saturated_into(),
+		}
+	}
+
+	fn release_endorsement(miner: &AccountOf<T>, gateway: &AccountOf<T>, stake: BalanceOf<T>) {
+		T::Currency::unreserve(gateway, stake);
+		<EndorsementSummary<T>>::mutate_exists(miner, |total_opt| {
+			if let Some(total) = total_opt {
+				total.endorsers = total.endorsers.saturating_sub(1);
+				total.stake = total.stake.saturating_sub(stake);
+				if total.endorsers == 0 {
+					*total_opt = None;
+				}
+			}
+		});
+	}
+
+	// Counts the miners, their space and fragments per region, exited miners are left out.
+	fn update_region_heatmap() -> Weight {
+		let mut regions: BTreeMap<RegionCode, RegionAvailability> = BTreeMap::new();
+		let mut count: u64 = 0;
+
+		for (acc, info) in <MinerItems<T>>::iter() {
+			count = count.saturating_add(1);
+			if info.state.to_vec() == STATE_EXIT.as_bytes().to_vec() {
+				continue;
+			}
+			let region = <MinerRegion<T>>::get(&acc).unwrap_or(UNKNOWN_REGION);
+			let entry = regions
+				.entry(region)
+				.or_insert_with(|| RegionAvailability { region, ..Default::default() });
+			entry.miners = entry.miners.saturating_add(1);
+			if info.state.to_vec() == STATE_POSITIVE.as_bytes().to_vec() && Self::is_alive(&acc) {
+				entry.healthy_miners = entry.healthy_miners.saturating_add(1);
+			}
+			entry.fragments = entry.fragments.saturating_add((info.service_space / FRAGMENT_SIZE) as u64);
+			entry.idle_space = entry.idle_space.saturating_add(info.idle_space);
+			entry.service_space = entry.service_space.saturating_add(info.service_space);
+		}
+
+		let heatmap: Vec<RegionAvailability> = regions.into_values().collect();
+		<RegionHeatmap<T>>::put(BoundedVec::truncate_from(heatmap));
+
+		T::DbWeight::get().reads_writes(count.saturating_mul(3), 1)
+	}
+
+	/// Whether the miner sent a heartbeat within the last `HEARTBEAT_TOLERANCE` intervals.
+	pub fn is_alive(acc: &AccountOf<T>) -> bool {
+		let now = <frame_system::Pallet<T>>::block_number();
+		let tolerance = T::HeartbeatInterval::get().saturating_mul(HEARTBEAT_TOLERANCE.into());
+		match <LastHeartbeat<T>>::get(acc) {
+			Some(last) => last.saturating_add(tolerance) >= now,
+			None => false,
+		}
+	}
+}
+
+#[cfg(any(test, feature = "try-runtime", feature = "consistency-checks"))]
+impl<T: Config> Pallet<T> {
+	/// Checks that every listed miner is registered and listed once.
+	pub fn do_try_state() -> Result<(), &'static str> {
+		let all_miner = AllMiner::<T>::get();
+		for (i, acc) in all_miner.iter().enumerate() {
+			ensure!(MinerItems::<T>::contains_key(acc), "AllMiner lists an unregistered miner");
+			ensure!(!all_miner[..i].contains(acc), "AllMiner lists a miner twice");
+		}
+
+		Ok(())
+	}
+
+	/// The idle and service space summed over all registered miners.
+	pub fn miner_space_totals() -> (u128, u128) {
+		MinerItems::<T>::iter_values().fold((0, 0), |(idle, service), miner| {
+			(idle.saturating_add(miner.idle_space), service.saturating_add(miner.service_space))
+		})
+	}
 }
 
 impl<T: Config> OnUnbalanced<NegativeImbalanceOf<T>> for Pallet<T> {
@@ -895,9 +1621,13 @@ pub trait MinerControl<AccountId> {
 	fn miner_is_exist(acc: AccountId) -> bool;
 	fn get_miner_state(acc: &AccountId) -> Result<Vec<u8>, DispatchError>;
 	fn get_all_miner() -> Result<Vec<AccountId>, DispatchError>;
-	fn lock_space(acc: &AccountId, space: u128) -> DispatchResult;
-	fn unlock_space(acc: &AccountId, space: u128) -> DispatchResult;
-	fn unlock_space_to_service(acc: &AccountId, space: u128) -> DispatchResult;
+	// Set aside idle space for the fragments of a deal, so that no other deal is placed in it.
+	fn reserve_space(acc: &AccountId, space: u128) -> DispatchResult;
+	// The fragments were stored, the reserved space becomes service space.
+	fn confirm_space(acc: &AccountId, space: u128) -> DispatchResult;
+	// The deal moved on without the miner, the reserved space becomes idle again.
+	// Miners that already left are skipped.
+	fn cancel_space(acc: &AccountId, space: u128) -> DispatchResult;
 	fn get_miner_idle_space(acc: &AccountId) -> Result<u128, DispatchError>;
 	fn get_miner_count() -> u32;
 	fn get_reward() -> u128; 
@@ -912,14 +1642,24 @@ pub trait MinerControl<AccountId> {
 	fn clear_punish(miner: &AccountId, level: u8, idle_space: u128, service_space: u128) -> DispatchResult;
 	fn idle_punish(miner: &AccountId, idle_space: u128, service_space: u128) -> DispatchResult;
 	fn service_punish(miner: &AccountId, idle_space: u128, service_space: u128) -> DispatchResult;
+	fn invalid_file_punish(miner: &AccountId) -> DispatchResult;
+	fn unreachable_punish(miner: &AccountId) -> DispatchResult;
 
 	fn execute_exit(acc: &AccountId) -> DispatchResult;
 	fn withdraw(acc: &AccountId) -> DispatchResult;
+	fn compensate_lost_replica(acc: &AccountId, lost_space: u128) -> DispatchResult;
 	fn force_miner_exit(acc: &AccountId) -> DispatchResult; 
 
 	fn is_positive(miner: &AccountId) -> Result<bool, DispatchError>;
 	fn is_lock(miner: &AccountId) -> Result<bool, DispatchError>;
 	fn update_miner_state(miner: &AccountId, state: &str) -> DispatchResult;
+	fn is_alive(miner: &AccountId) -> bool;
+	fn record_challenge_result(miner: &AccountId, passed: bool);
+	fn get_reputation(miner: &AccountId) -> Perbill;
+	fn get_placement_reputation(miner: &AccountId) -> Perbill;
+	fn get_peer_id(miner: &AccountId) -> Result<PeerId, DispatchError>;
+	fn get_price_floor(miner: &AccountId) -> Option<u128>;
+	fn get_miner_region(miner: &AccountId) -> Option<RegionCode>;
 	fn test_update_miner_idle_space(acc: &AccountId, space: u128) -> DispatchResult;
 }
 
@@ -976,30 +1716,31 @@ impl<T: Config> MinerControl<<T as frame_system::Config>::AccountId> for Pallet<
 		Ok(AllMiner::<T>::get().to_vec())
 	}
 
-	fn lock_space(acc: &AccountOf<T>, space: u128) -> DispatchResult {
+	fn reserve_space(acc: &AccountOf<T>, space: u128) -> DispatchResult {
 		<MinerItems<T>>::try_mutate(acc, |miner_opt| -> DispatchResult {
 			let miner = miner_opt.as_mut().ok_or(Error::<T>::NotExisted)?;
+			// Checked and taken in one step, a second deal sees the idle space left.
+			miner.idle_space = miner.idle_space.checked_sub(space).ok_or(Error::<T>::InsufficientIdleSpace)?;
 			miner.lock_space = miner.lock_space.checked_add(space).ok_or(Error::<T>::Overflow)?;
-			miner.idle_space = miner.idle_space.checked_sub(space).ok_or(Error::<T>::Overflow)?;
 			Ok(())
 		})
 	}
 
-	fn unlock_space(acc: &AccountOf<T>, space: u128) -> DispatchResult {
+	fn confirm_space(acc: &AccountOf<T>, space: u128) -> DispatchResult {
 		<MinerItems<T>>::try_mutate(acc, |miner_opt| -> DispatchResult {
-			if let Ok(miner) = miner_opt.as_mut().ok_or(Error::<T>::NotExisted) {
-				miner.lock_space = miner.lock_space.checked_sub(space).ok_or(Error::<T>::Overflow)?;
-				miner.idle_space = miner.idle_space.checked_add(space).ok_or(Error::<T>::Overflow)?;
-			}
+			let miner = miner_opt.as_mut().ok_or(Error::<T>::NotExisted)?;
+			miner.lock_space = miner.lock_space.checked_sub(space).ok_or(Error::<T>::InsufficientReservedSpace)?;
+			miner.service_space = miner.service_space.checked_add(space).ok_or(Error::<T>::Overflow)?;
 			Ok(())
 		})
 	}
 
-	fn unlock_space_to_service(acc: &AccountOf<T>, space: u128) -> DispatchResult {
+	fn cancel_space(acc: &AccountOf<T>, space: u128) -> DispatchResult {
 		<MinerItems<T>>::try_mutate(acc, |miner_opt| -> DispatchResult {
-			let miner = miner_opt.as_mut().ok_or(Error::<T>::NotExisted)?;
-			miner.lock_space = miner.lock_space.checked_sub(space).ok_or(Error::<T>::Overflow)?;
-			miner.service_space = miner.service_space.checked_add(space).ok_or(Error::<T>::Overflow)?;
+			if let Some(miner) = miner_opt.as_mut() {
+				miner.lock_space = miner.lock_space.checked_sub(space).ok_or(Error::<T>::InsufficientReservedSpace)?;
+				miner.idle_space = miner.idle_space.checked_add(space).ok_or(Error::<T>::Overflow)?;
+			}
 			Ok(())
 		})
 	}
@@ -1055,6 +1796,14 @@ impl<T: Config> MinerControl<<T as frame_system::Config>::AccountId> for Pallet<
 		Self::service_punish(miner, idle_space, service_space)
 	}
 
+	fn invalid_file_punish(miner: &AccountOf<T>) -> DispatchResult {
+		Self::invalid_file_punish(miner)
+	}
+
+	fn unreachable_punish(miner: &AccountOf<T>) -> DispatchResult {
+		Self::unreachable_punish(miner)
+	}
+
 	fn is_positive(miner: &AccountOf<T>) -> Result<bool, DispatchError> {
 		let state = Self::get_miner_state(miner)?;
 		Ok(state == STATE_POSITIVE.as_bytes().to_vec())
@@ -1092,6 +1841,39 @@ impl<T: Config> MinerControl<<T as frame_system::Config>::AccountId> for Pallet<
 		Self::withdraw(acc)
 	}
 
+	fn compensate_lost_replica(acc: &AccountOf<T>, lost_space: u128) -> DispatchResult {
+		Self::compensate_lost_replica(acc, lost_space)
+	}
+
+	fn is_alive(miner: &AccountOf<T>) -> bool {
+		Self::is_alive(miner)
+	}
+
+	fn record_challenge_result(miner: &AccountOf<T>, passed: bool) {
+		Self::record_challenge_result(miner, passed)
+	}
+
+	fn get_reputation(miner: &AccountOf<T>) -> Perbill {
+		Self::get_reputation(miner)
+	}
+
+	fn get_placement_reputation(miner: &AccountOf<T>) -> Perbill {
+		Self::get_placement_reputation(miner)
+	}
+
+	fn get_peer_id(miner: &AccountOf<T>) -> Result<PeerId, DispatchError> {
+		let miner_info = <MinerItems<T>>::try_get(miner).map_err(|_| Error::<T>::NotMiner)?;
+		Ok(miner_info.peer_id)
+	}
+
+	fn get_price_floor(miner: &AccountOf<T>) -> Option<u128> {
+		<PriceFloor<T>>::get(miner).map(|floor| floor.saturated_into())
+	}
+
+	fn get_miner_region(miner: &AccountOf<T>) -> Option<RegionCode> {
+		<MinerRegion<T>>::get(miner)
+	}
+
 	fn test_update_miner_idle_space(acc: &AccountOf<T>, space: u128) -> DispatchResult {
 		MinerItems::<T>::try_mutate(&acc, |miner_opt| -> DispatchResult {
 			let miner = miner_opt.as_mut().ok_or(Error::<T>::Overflow)?;
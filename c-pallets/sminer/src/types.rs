@@ -43,3 +43,33 @@ pub struct RewardOrder<Balance> {
 pub struct FaucetRecord<BlockNumber> {
 	pub(super) last_claim_time: BlockNumber,
 }
+
+/// Challenge outcomes of a miner, the base of its reputation.
+#[derive(PartialEq, Eq, Encode, Default, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ReputationInfo {
+	pub(super) successes: u32,
+	pub(super) failures: u32,
+}
+
+/// Stake of a gateway endorsing a miner.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct EndorsementInfo<Balance, BlockNumber> {
+	pub(super) stake: Balance,
+	// The block the gateway last endorsed the miner at.
+	pub(super) block: BlockNumber,
+}
+
+/// The gateways endorsing a miner and their total stake.
+#[derive(PartialEq, Eq, Encode, Default, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct EndorsementTotal<Balance> {
+	pub(super) endorsers: u32,
+	pub(super) stake: Balance,
+}
+
+/// The payload a miner's offchain worker signs with its heartbeat key.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct Heartbeat<AccountId, BlockNumber> {
+	pub miner: AccountId,
+	// The block the heartbeat was generated at.
+	pub block_number: BlockNumber,
+}
@@ -60,6 +60,12 @@ pub trait WeightInfo {
 	fn timing_task_increase_power_rewards() -> Weight;
 	fn timing_task_award_table() -> Weight;
 	fn timing_user_receive_award() -> Weight;
+	fn set_heartbeat_key() -> Weight;
+	fn heartbeat() -> Weight;
+	fn endorse_miner() -> Weight;
+	fn withdraw_endorsement() -> Weight;
+	fn set_price_floor() -> Weight;
+	fn set_region() -> Weight;
 }
 
 /// Weights for pallet_sminer using the Substrate node and recommended hardware.
@@ -169,6 +175,53 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(2 as u64))
 			.saturating_add(T::DbWeight::get().writes(2 as u64))
 	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer HeartbeatKeyOwner (r:1 w:1)
+	// Storage: Sminer HeartbeatKey (r:1 w:1)
+	fn set_heartbeat_key() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer LastHeartbeat (r:1 w:1)
+	fn heartbeat() -> Weight {
+		Weight::from_ref_time(25_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer LastEndorsement (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Sminer Endorsements (r:1 w:1)
+	// Storage: Sminer EndorsementSummary (r:1 w:1)
+	fn endorse_miner() -> Weight {
+		Weight::from_ref_time(40_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(5 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	// Storage: Sminer Endorsements (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Sminer EndorsementSummary (r:1 w:1)
+	fn withdraw_endorsement() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer PriceFloor (r:0 w:1)
+	fn set_price_floor() -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer MinerRegion (r:0 w:1)
+	fn set_region() -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
 }
 
 // For backwards compatibility and tests
@@ -277,4 +330,51 @@ impl WeightInfo for () {
 			.saturating_add(RocksDbWeight::get().reads(2 as u64))
 			.saturating_add(RocksDbWeight::get().writes(2 as u64))
 	}
-}
\ No newline at end of file
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer HeartbeatKeyOwner (r:1 w:1)
+	// Storage: Sminer HeartbeatKey (r:1 w:1)
+	fn set_heartbeat_key() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer LastHeartbeat (r:1 w:1)
+	fn heartbeat() -> Weight {
+		Weight::from_ref_time(25_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer LastEndorsement (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Sminer Endorsements (r:1 w:1)
+	// Storage: Sminer EndorsementSummary (r:1 w:1)
+	fn endorse_miner() -> Weight {
+		Weight::from_ref_time(40_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	// Storage: Sminer Endorsements (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Sminer EndorsementSummary (r:1 w:1)
+	fn withdraw_endorsement() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer PriceFloor (r:0 w:1)
+	fn set_price_floor() -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: Sminer MinerItems (r:1 w:0)
+	// Storage: Sminer MinerRegion (r:0 w:1)
+	fn set_region() -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+}
@@ -26,7 +26,7 @@ use frame_support::{
 	pallet_prelude::*,
 	traits::{
 		Currency, CurrencyToVote, Defensive, DefensiveResult, EstimateNextNewSession, Get,
-		Imbalance, LockableCurrency, OnUnbalanced, TryCollect, WithdrawReasons,
+		Imbalance, LockableCurrency, OnUnbalanced, TryCollect, ValidatorCredits, WithdrawReasons,
 	},
 	weights::Weight,
 };
@@ -442,6 +442,7 @@ impl<T: Config> Pallet<T> {
 
 			// Set ending era reward.
 			<ErasValidatorReward<T>>::insert(&active_era.index, validator_payout);
+			Self::apply_validator_credits(active_era.index);
 			T::SminerRewardPool::on_unbalanced(T::Currency::issue(sminer_payout));
 
 			// Clear offending validators.
@@ -449,6 +450,26 @@ impl<T: Config> Pallet<T> {
 		}
 	}
 
+	/// Raise the reward points of the validators of the era by their scheduler credit,
+	/// so that the credit scales their share of the era payout.
+	fn apply_validator_credits(era: EraIndex) {
+		let full_credit = T::ValidatorCredits::full_credit();
+		if full_credit == 0 {
+			return
+		}
+
+		let credits = T::ValidatorCredits::credits(era as u64);
+		<ErasRewardPoints<T>>::mutate(era, |era_rewards| {
+			let mut total = 0u32;
+			for (validator, points) in era_rewards.individual.iter_mut() {
+				let credit = credits.get(validator).copied().unwrap_or(0).min(full_credit);
+				*points = points.saturating_add(Perbill::from_rational(credit, full_credit) * *points);
+				total = total.saturating_add(*points);
+			}
+			era_rewards.total = total;
+		});
+	}
+
 	/// Compute rewards for validator and sminer for era.
 	pub(crate) fn rewards_in_era(active_era_index: EraIndex) -> (BalanceOf<T>, BalanceOf<T>) {
 		let mut year_num = active_era_index as u64 / T::ERAS_PER_YEAR;
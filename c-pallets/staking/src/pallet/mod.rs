@@ -26,7 +26,7 @@ use frame_support::{
 	traits::{
 		Currency, CurrencyToVote, Defensive, DefensiveResult, DefensiveSaturating, EnsureOrigin,
 		EstimateNextNewSession, Get, LockIdentifier, LockableCurrency, OnUnbalanced, TryCollect,
-		UnixTime,
+		UnixTime, ValidatorCredits,
 	},
 	weights::Weight,
 	BoundedVec,
@@ -37,6 +37,7 @@ use sp_runtime::{
 	ArithmeticError, Perbill, Percent,
 };
 use sp_staking::{EraIndex, SessionIndex};
+use cp_cess_common::slash::SlashPolicy;
 use sp_std::prelude::*;
 
 mod impls;
@@ -99,6 +100,10 @@ pub mod pallet {
 		/// Tokens have been minted for sminers.
 		type SminerRewardPool: OnUnbalanced<NegativeImbalanceOf<Self>>;
 
+		/// Credits of the validators that also run a scheduler. At the end of an era the
+		/// reward points of a validator are raised by its credit, up to doubled at full credit.
+		type ValidatorCredits: ValidatorCredits<Self::AccountId>;
+
 		/// The staking balance.
 		type Currency: LockableCurrency<
 			Self::AccountId,
@@ -181,6 +186,9 @@ pub mod pallet {
 		/// Handler for the unbalanced reduction when slashing a staker.
 		type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
 
+		/// Where the funds slashed from a scheduler go.
+		type SlashPolicy: SlashPolicy<Self::AccountId, Self::Currency>;
+
 		/// Handler for the unbalanced increment when rewarding a staker.
 		/// NOTE: in most cases, the implementation of `OnUnbalanced` should modify the total
 		/// issuance.
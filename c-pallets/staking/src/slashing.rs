@@ -64,6 +64,7 @@ use sp_runtime::{
 	DispatchResult, RuntimeDebug,
 };
 use sp_staking::{offence::DisableStrategy, EraIndex};
+use cp_cess_common::slash::{SlashPolicy, SlashSource};
 use sp_std::vec::Vec;
 
 /// The proportion of the slashing reward to be paid out on the first slashing detection.
@@ -690,18 +691,22 @@ fn pay_reporters<T: Config>(
 	T::Slash::on_unbalanced(value_slashed);
 }
 
-/// Apply a slash to a scheduler
+/// Apply a slash to a scheduler, the slashed funds go where `T::SlashPolicy` decides.
 pub fn slash_scheduler<T: Config>(stash: &T::AccountId) {
 	let min_bond = <Pallet<T> as Store>::MinValidatorBond::get();
 	let slash_era = CurrentEra::<T>::get().unwrap();
-	let unapplied_slash = UnappliedSlash::<T::AccountId, BalanceOf<T>> {
-		validator: stash.clone(),
-		own: Perbill::from_percent(5) * min_bond,
-		others: Vec::new(),
-		reporters: Vec::new(),
-		payout: Zero::zero(),
-	};
-	apply_slash::<T>(unapplied_slash, slash_era);
+	let mut slashed_imbalance = NegativeImbalanceOf::<T>::zero();
+	let mut reward_payout = Zero::zero();
+
+	do_slash::<T>(
+		stash,
+		Perbill::from_percent(5) * min_bond,
+		&mut reward_payout,
+		&mut slashed_imbalance,
+		slash_era,
+	);
+
+	T::SlashPolicy::distribute(SlashSource::Scheduler, slashed_imbalance, &[], None);
 }
 
 #[cfg(test)]
@@ -9,21 +9,29 @@ use frame_support::{
     storage::bounded_vec::BoundedVec,
     traits::{
         StorageVersion, Currency, ReservableCurrency, ExistenceRequirement::KeepAlive,
+        BalanceStatus, Contains,
     },
     pallet_prelude::*,
 };
 use sp_runtime::{
 	traits::{
         AccountIdConversion, CheckedAdd, CheckedMul, CheckedDiv, CheckedSub,
-		SaturatedConversion,
+		SaturatedConversion, Saturating, Zero,
 	},
-	RuntimeDebug,
+	app_crypto::RuntimeAppPublic,
+	offchain::{
+		http, Duration,
+		storage::{StorageRetrievalError, StorageValueRef},
+	},
+	RuntimeDebug, Perbill, FixedPointNumber, FixedU128,
 };
+use frame_system::offchain::{SendTransactionTypes, SubmitTransaction};
 use sp_std::{convert::TryInto, prelude::*, str};
 /// for types 
 use codec::{Decode, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
 use cp_cess_common::*;
+use sp_runtime::KeyTypeId;
 
 pub mod weights;
 use weights::WeightInfo;
@@ -31,26 +39,87 @@ use weights::WeightInfo;
 mod types;
 use types::*;
 
+pub mod migrations;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
 pub use pallet::*;
 
 pub const SPACE_NORMAL: &str = "normal";
 pub const SPACE_FROZEN: &str = "frozen";
 pub const SPACE_DEAD: &str = "dead";
+// Size and length of the free trial package.
+pub const TRIAL_GIB: u32 = 10;
+pub const TRIAL_DAYS: u32 = 30;
+// Offchain local storage key of the price source urls, a SCALE encoded `Vec<Vec<u8>>`
+// set by the node operator. Every source answers with the unit price as a plain integer.
+pub const PRICE_SOURCES_KEY: &[u8] = b"storage-handler::price-sources";
+// Milliseconds a price source is given to answer.
+pub const PRICE_FETCH_TIMEOUT: u64 = 3_000;
+// Pricing changes that can activate in the same block.
+pub const PRICING_QUEUE_LIMIT: u32 = 50;
+// Keys allowed to report the unit price at the same time.
+pub const PRICE_FEEDER_LIMIT: u32 = 32;
+
+pub const PRICE_FEED: KeyTypeId = KeyTypeId(*b"cspf");
+
+pub mod sr25519 {
+	mod app_sr25519 {
+		use crate::*;
+		use sp_runtime::app_crypto::{app_crypto, sr25519};
+		app_crypto!(sr25519, PRICE_FEED);
+	}
+
+	sp_runtime::app_crypto::with_pair! {
+		pub type AuthorityPair = app_sr25519::Pair;
+	}
+
+	pub type AuthoritySignature = app_sr25519::Signature;
+
+	pub type AuthorityId = app_sr25519::Public;
+}
 
 type AccountOf<T> = <T as frame_system::Config>::AccountId;
 type BalanceOf<T> =
 	<<T as pallet::Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
+pub type ReferralCodeOf = BoundedVec<u8, ConstU32<32>>;
+type AssetIdOf<T> = <<T as pallet::Config>::PackagePayment as PackagePayment<<T as frame_system::Config>::AccountId>>::AssetId;
+
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
+enum OffchainErr {
+	Ineligible,
+	Working,
+	NotEnoughSources,
+	DeviationExceeded,
+	FailedSigning,
+	SubmitTransactionFailed,
+}
 
-const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
-
+impl sp_std::fmt::Debug for OffchainErr {
+	fn fmt(&self, fmt: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		match *self {
+			OffchainErr::Ineligible => write!(fmt, "No local key belongs to a price authority"),
+			OffchainErr::Working => write!(fmt, "A price report is still waiting for inclusion"),
+			OffchainErr::NotEnoughSources => write!(fmt, "Too few price sources answered"),
+			OffchainErr::DeviationExceeded => write!(fmt, "The median price deviates too far from the unit price"),
+			OffchainErr::FailedSigning => write!(fmt, "Signing price report failed"),
+			OffchainErr::SubmitTransactionFailed => write!(fmt, "Failed to submit transaction."),
+		}
+	}
+}
 
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
 
     #[pallet::config]
-	pub trait Config: frame_system::Config + sp_std::fmt::Debug {
+	pub trait Config: frame_system::Config + sp_std::fmt::Debug + SendTransactionTypes<Call<Self>> {
 		/// The overarching event type.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
         /// The currency trait.
@@ -72,21 +141,107 @@ pub mod pallet {
         
 		#[pallet::constant]
 		type FrozenDays: Get<BlockNumberOf<Self>> + Clone + Eq + PartialEq;
+		/// Package operations kept in the ledger of each account, the oldest are dropped first.
+		#[pallet::constant]
+		type MaxLedgerEntries: Get<u32>;
+		/// Deposit reserved when claiming the free trial package.
+		#[pallet::constant]
+		type TrialDeposit: Get<BalanceOf<Self>>;
+		/// Blocks after the claim at which the trial deposit can be released.
+		#[pallet::constant]
+		type TrialDepositPeriod: Get<BlockNumberOf<Self>>;
+		/// The key the offchain worker of a price authority signs its price reports with.
+		type AuthorityId: Member
+			+ Parameter
+			+ RuntimeAppPublic
+			+ Ord
+			+ MaybeSerializeDeserialize
+			+ MaxEncodedLen;
+		/// The keys allowed to report the unit price.
+		type PriceAuthorities: Contains<Self::AuthorityId>;
+		/// The origin allowed to change the registered price feeder keys.
+		type PriceFeederOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		/// A configuration for base priority of unsigned transactions.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+		/// Minimum number of blocks between two unit price updates.
+		#[pallet::constant]
+		type PriceInterval: Get<BlockNumberOf<Self>>;
+		/// Price sources that must answer before the offchain worker reports their median.
+		#[pallet::constant]
+		type MinPriceSources: Get<u32>;
+		/// Largest change of the unit price a single report may make.
+		#[pallet::constant]
+		type MaxPriceDeviation: Get<Perbill>;
+		/// Price authorities that must report in the same interval before their median becomes the unit price.
+		#[pallet::constant]
+		type MinPriceReports: Get<u32>;
+		/// Blocks between the announcement of a pricing change and its activation.
+		#[pallet::constant]
+		type PricingAnnouncePeriod: Get<BlockNumberOf<Self>>;
+		/// The origin allowed to cancel an announced pricing change before it activates.
+		type PricingCancelOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		/// Moves the registered assets packages can be paid in.
+		type PackagePayment: PackagePayment<Self::AccountId>;
     }
 
     #[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
 		//User buy package event
-		BuySpace { acc: AccountOf<T>, storage_capacity: u128, spend: BalanceOf<T> },
+		BuySpace { acc: AccountOf<T>, storage_capacity: u128, spend: BalanceOf<T>, deadline: BlockNumberOf<T> },
 		//Expansion Space
 		ExpansionSpace { acc: AccountOf<T>, expansion_space: u128, fee: BalanceOf<T> },
-		//Package upgrade
+		//Unit price updated to the median of the reports of the price authorities
+		UnitPriceUpdated { old: BalanceOf<T>, new: BalanceOf<T> },
+		//A price authority reported a price, the unit price changes once enough authorities reported
+		PriceReported { authority: T::AuthorityId, price: BalanceOf<T> },
+		//Package renewal by day
 		RenewalSpace { acc: AccountOf<T>, renewal_days: u32, fee: BalanceOf<T> },
+		//A deprecated call was dispatched, `replacement` is the call to use instead
+		CallDeprecated { acc: AccountOf<T>, call: Vec<u8>, replacement: Vec<u8> },
+		//Package renewed by month
+		PackageRenewed { acc: AccountOf<T>, months: u32, deadline: BlockNumberOf<T>, fee: BalanceOf<T> },
+		//Package moved to a larger tier of the catalogue
+		PackageUpgraded { acc: AccountOf<T>, tier: PackageTierId, old_space: u128, new_space: u128, fee: BalanceOf<T> },
+		//Package moved to a smaller tier of the catalogue, the remaining credit extends the lease
+		PackageDowngraded { acc: AccountOf<T>, tier: PackageTierId, old_space: u128, new_space: u128, deadline: BlockNumberOf<T> },
+		//Referral code registered
+		ReferralCodeRegistered { acc: AccountOf<T>, code: ReferralCodeOf },
+		//Package bought with a referral code
+		ReferralReward { referrer: AccountOf<T>, buyer: AccountOf<T>, reward: BalanceOf<T>, discount: BalanceOf<T> },
+		//Referral schedule changed
+		ReferralScheduleSet { reward_rate: Perbill, discount_rate: Perbill },
+		//Price multiplier of a region changed, None means the region is no longer offered
+		RegionMultiplierSet { region: RegionCode, multiplier: Option<u32> },
+		//Free trial package claimed against a deposit
+		TrialClaimed { acc: AccountOf<T>, space: u128, deposit: BalanceOf<T> },
+		//Trial deposit returned to the account
+		TrialDepositReleased { acc: AccountOf<T>, deposit: BalanceOf<T> },
+		//Trial deposit of a sybil account moved to the treasury
+		TrialDepositSlashed { acc: AccountOf<T>, deposit: BalanceOf<T> },
+		//Pricing change announced, applied at the `activation` block unless cancelled
+		PricingChangeAnnounced { id: u32, change: PricingChange<BalanceOf<T>>, activation: BlockNumberOf<T> },
+		//Announced pricing change cancelled before its activation
+		PricingChangeCancelled { id: u32 },
+		//Package paid in an asset, `amount` went to the treasury
+		PaidWithAsset { acc: AccountOf<T>, asset: AssetIdOf<T>, amount: u128 },
+		//Price of an asset changed, None means packages can no longer be paid in it
+		AssetPriceSet { asset: AssetIdOf<T>, price: Option<FixedU128> },
+		//Keys allowed to report the unit price replaced
+		PriceFeedersSet { feeders: Vec<T::AuthorityId> },
+		//Tier of the package catalogue changed, None means the tier is no longer offered
+		PackageSet { tier: PackageTierId, package: Option<PackageTier<BalanceOf<T>>> },
+		//Package of the catalogue bought
+		PackageBought { acc: AccountOf<T>, tier: PackageTierId },
         //Expired storage space
 		LeaseExpired { acc: AccountOf<T>, size: u128 },
 		//Storage space expiring within 24 hours
 		LeaseExpireIn24Hours { acc: AccountOf<T>, size: u128 },
+		//Expired storage space not reactivated within the frozen days, its files are deleted
+		LeasePurged { acc: AccountOf<T>, size: u128 },
+		//Frozen package reactivated, `fee` includes the frozen days
+		PackageReactivated { acc: AccountOf<T>, months: u32, deadline: BlockNumberOf<T>, fee: BalanceOf<T> },
     }
 
     #[pallet::error]
@@ -112,6 +267,44 @@ pub mod pallet {
         LeaseFreeze,
 
         LeaseExpired,
+        // The space in use exceeds the capacity of the target package
+        ExceedCapacity,
+        // The referral code is already taken
+        ReferralCodeExisted,
+        // The referral code does not exist or belongs to the buyer
+        InvalidReferralCode,
+        // The reward and discount together exceed the price
+        InvalidReferralSchedule,
+        // No price multiplier is set for the region
+        RegionNotSupported,
+        // A region multiplier must be above zero
+        InvalidRegionMultiplier,
+        // The account already claimed the trial package
+        TrialClaimed,
+        // The account holds no trial deposit
+        NoTrialDeposit,
+        // The trial deposit is still locked
+        TrialDepositLocked,
+        // The unit price was updated less than `PriceInterval` blocks ago
+        PriceReportTooEarly,
+        // The reported price deviates more than `MaxPriceDeviation` from the unit price
+        PriceDeviationExceeded,
+        // The price authority already reported in this interval
+        DuplicatePriceReport,
+        // No pricing change with this id is waiting for activation
+        PricingChangeNotFound,
+        // Too many pricing changes activate in the same block
+        PricingQueueFull,
+        // Packages cannot be paid in the asset
+        AssetNotAccepted,
+        // The package costs more of the asset than the buyer allowed
+        AssetAmountExceeded,
+        // Only a frozen package can be reactivated
+        PackageNotFrozen,
+        // The tier is not in the package catalogue
+        PackageNotFound,
+        // A package tier needs space and days
+        InvalidPackage,
     }
 
 	#[pallet::storage]
@@ -123,6 +316,20 @@ pub mod pallet {
 	#[pallet::getter(fn unit_price)]
     pub(super) type UnitPrice<T: Config> = StorageValue<_, BalanceOf<T>>;
 
+	/// The block the unit price was last updated from a price report.
+	#[pallet::storage]
+	#[pallet::getter(fn last_price_update)]
+	pub(super) type LastPriceUpdate<T: Config> = StorageValue<_, BlockNumberOf<T>>;
+
+	/// The price reports of the current interval, one per price authority, cleared once their median is applied.
+	#[pallet::storage]
+	#[pallet::getter(fn price_reports)]
+	pub(super) type PriceReports<T: Config> = StorageValue<
+		_,
+		BoundedVec<PriceReport<BalanceOf<T>, BlockNumberOf<T>, T::AuthorityId>, ConstU32<PRICE_FEEDER_LIMIT>>,
+		ValueQuery,
+	>;
+
     /// The total power of all storage miners.
 	#[pallet::storage]
 	#[pallet::getter(fn total_power)]
@@ -137,6 +344,84 @@ pub mod pallet {
 	#[pallet::getter(fn purchased_space)]
 	pub(super) type PurchasedSpace<T: Config> = StorageValue<_, u128, ValueQuery>;
 
+	/// Accounts whose package has to be checked by the lease sweep at the given block.
+	/// Only blocks that are multiples of `OneDay` are used as keys.
+	#[pallet::storage]
+	#[pallet::getter(fn lease_expire_queue)]
+	pub(super) type LeaseExpireQueue<T: Config> =
+		StorageMap<_, Blake2_128Concat, BlockNumberOf<T>, BoundedVec<AccountOf<T>, ConstU32<5000>>, ValueQuery>;
+
+	/// The first slot of `LeaseExpireQueue` not checked by the lease sweep yet.
+	#[pallet::storage]
+	#[pallet::getter(fn next_lease_slot)]
+	pub(super) type NextLeaseSlot<T: Config> = StorageValue<_, BlockNumberOf<T>, ValueQuery>;
+
+	/// The referrer owning each referral code.
+	#[pallet::storage]
+	#[pallet::getter(fn referral_code)]
+	pub(super) type ReferralCode<T: Config> = StorageMap<_, Blake2_128Concat, ReferralCodeOf, AccountOf<T>>;
+
+	/// Reward and discount applied to purchases with a referral code, both zero until set by root.
+	#[pallet::storage]
+	#[pallet::getter(fn referral_schedule)]
+	pub(super) type ReferralSchedules<T: Config> = StorageValue<_, ReferralSchedule, ValueQuery>;
+
+	/// Price multiplier of each region in percent, 100 keeps the unit price.
+	#[pallet::storage]
+	#[pallet::getter(fn region_multiplier)]
+	pub(super) type RegionMultiplier<T: Config> = StorageMap<_, Blake2_128Concat, RegionCode, u32>;
+
+	/// Announced pricing changes waiting for their activation block.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_pricing_change)]
+	pub(super) type PendingPricingChanges<T: Config> =
+		StorageMap<_, Blake2_128Concat, u32, PendingPricingChange<BalanceOf<T>, BlockNumberOf<T>>>;
+
+	/// The id given to the next announced pricing change.
+	#[pallet::storage]
+	#[pallet::getter(fn next_pricing_change_id)]
+	pub(super) type NextPricingChangeId<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The ids of the pricing changes activating at each block.
+	#[pallet::storage]
+	#[pallet::getter(fn pricing_change_queue)]
+	pub(super) type PricingChangeQueue<T: Config> =
+		StorageMap<_, Blake2_128Concat, BlockNumberOf<T>, BoundedVec<u32, ConstU32<PRICING_QUEUE_LIMIT>>, ValueQuery>;
+
+	/// Units of each accepted asset paid per unit of the native token.
+	#[pallet::storage]
+	#[pallet::getter(fn asset_price)]
+	pub(super) type AssetPrice<T: Config> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, FixedU128>;
+
+	/// The package tiers offered by `buy_package`.
+	#[pallet::storage]
+	#[pallet::getter(fn package_catalogue)]
+	pub(super) type PackageCatalogue<T: Config> =
+		StorageMap<_, Blake2_128Concat, PackageTierId, PackageTier<BalanceOf<T>>>;
+
+	/// The region a user's package is placed in, set when the package is bought.
+	#[pallet::storage]
+	#[pallet::getter(fn user_region)]
+	pub(super) type UserRegion<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, RegionCode>;
+
+	/// The latest package operations of each account, used for account statements.
+	#[pallet::storage]
+	#[pallet::getter(fn account_ledger)]
+	pub(super) type AccountLedger<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, BoundedVec<LedgerEntry<BlockNumberOf<T>>, T::MaxLedgerEntries>, ValueQuery>;
+
+	/// Accounts that claimed the free trial package, kept after the deposit is released
+	/// so that every account claims it only once.
+	#[pallet::storage]
+	#[pallet::getter(fn user_free_record)]
+	pub(super) type UserFreeRecord<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, TrialRecord<T>>;
+
+	/// Keys registered as price feeders, the offchain workers holding them report the unit price.
+	#[pallet::storage]
+	#[pallet::getter(fn price_feeders)]
+	pub(super) type PriceFeeders<T: Config> =
+		StorageValue<_, BoundedVec<T::AuthorityId, ConstU32<PRICE_FEEDER_LIMIT>>, ValueQuery>;
+
     #[pallet::pallet]
 	#[pallet::storage_version(STORAGE_VERSION)]
 	#[pallet::generate_store(pub(super) trait Store)]
@@ -164,6 +449,28 @@ pub mod pallet {
 		}
 	}
 
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: BlockNumberOf<T>) -> Weight {
+			Self::apply_pricing_changes(now)
+		}
+
+		// Runs on every node whose keystore holds a key of a price authority.
+		fn offchain_worker(now: T::BlockNumber) {
+			if let Err(e) = Self::offchain_price_report(now) {
+				match e {
+					OffchainErr::Ineligible | OffchainErr::Working => {},
+					_ => log::info!("offchain worker price report failed: {:?}", e),
+				};
+			}
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
+	}
+
     #[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// Transaction of user purchasing space.
@@ -172,94 +479,46 @@ pub mod pallet {
 		///
 		/// Parameters:
 		/// - `gib_count`: Quantity of several gibs purchased.
+		/// - `referral_code`: Optional code of a referrer, gives the buyer a discount.
+		/// - `region`: Optional region the package is placed in, priced with the multiplier of the region.
 		#[pallet::call_index(0)]
 		#[transactional]
 		#[pallet::weight(<T as pallet::Config>::WeightInfo::buy_space())]
-		pub fn buy_space(origin: OriginFor<T>, gib_count: u32) -> DispatchResult {
+		pub fn buy_space(
+			origin: OriginFor<T>,
+			gib_count: u32,
+			referral_code: Option<ReferralCodeOf>,
+			region: Option<RegionCode>,
+		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			ensure!(!<UserOwnedSpace<T>>::contains_key(&sender), Error::<T>::PurchasedSpace);
-
-			let space = G_BYTE.checked_mul(gib_count as u128).ok_or(Error::<T>::Overflow)?;
-			let unit_price = <UnitPrice<T>>::try_get()
-				.map_err(|_e| Error::<T>::BugInvalid)?;
-
-			Self::add_user_purchased_space(sender.clone(), space, 30)?;
-			Self::add_purchased_space(space)?;
-			let price: BalanceOf<T> = unit_price
-				.checked_mul(&gib_count.saturated_into())
-				.ok_or(Error::<T>::Overflow)?;
-			ensure!(
-				<T as pallet::Config>::Currency::can_slash(&sender, price.clone()),
-				Error::<T>::InsufficientBalance
-			);
-			let acc = T::FilbakPalletId::get().into_account_truncating();
-			<T as pallet::Config>::Currency::transfer(&sender, &acc, price.clone(), KeepAlive)?;
 
-			Self::deposit_event(Event::<T>::BuySpace { acc: sender, storage_capacity: space, spend: price });
-			Ok(())
+			Self::do_buy_space(sender, Self::monthly_package(gib_count), referral_code, region, None)
 		}
-		/// Upgrade package (expansion of storage space)
+		/// Deprecated, use `upgrade_package`.
 		///
-		/// It can only be called when the package has been purchased,
-		/// And the upgrade target needs to be higher than the current package.
+		/// Adds `gib_count` gibs to the package through the logic of `upgrade_package`,
+		/// and emits `CallDeprecated` besides the `ExpansionSpace` event integrations expect.
+		/// The call keeps its index for at least two runtime upgrades after the deprecation.
 		///
 		/// Parameters:
 		/// - `gib_count`: Additional purchase quantity of several gibs.
 		#[pallet::call_index(1)]
 		#[transactional]
-		#[pallet::weight(<T as pallet::Config>::WeightInfo::expansion_space())]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::upgrade_package())]
 		pub fn expansion_space(origin: OriginFor<T>, gib_count: u32) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			let cur_owned_space = <UserOwnedSpace<T>>::try_get(&sender)
 				.map_err(|_e| Error::<T>::NotPurchasedSpace)?;
-			let now = <frame_system::Pallet<T>>::block_number();
-			ensure!(now < cur_owned_space.deadline, Error::<T>::LeaseExpired);
-			ensure!(
-				cur_owned_space.state.to_vec() != SPACE_FROZEN.as_bytes().to_vec(),
-				Error::<T>::LeaseFreeze
-			);
-			// The unit price recorded in UnitPrice is the unit price of one month.
-			// Here, the daily unit price is calculated.
-			let day_unit_price = <UnitPrice<T>>::try_get()
-				.map_err(|_e| Error::<T>::BugInvalid)?
-				.checked_div(&30u32.saturated_into()).ok_or(Error::<T>::Overflow)?;
 			let space = G_BYTE.checked_mul(gib_count as u128).ok_or(Error::<T>::Overflow)?;
-			//Calculate remaining days.
-			let block_oneday: BlockNumberOf<T> = <T as pallet::Config>::OneDay::get();
-			let diff_block = cur_owned_space.deadline.checked_sub(&now).ok_or(Error::<T>::Overflow)?;
-			let mut remain_day: u32 = diff_block
-				.checked_div(&block_oneday)
-				.ok_or(Error::<T>::Overflow)?
-				.saturated_into();
-			if diff_block % block_oneday != 0u32.saturated_into() {
-				remain_day = remain_day
-					.checked_add(1)
-					.ok_or(Error::<T>::Overflow)?
-					.saturated_into();
-			}
-			//Calculate the final price difference to be made up.
-			let price: BalanceOf<T> = day_unit_price
-				.checked_mul(&gib_count.saturated_into())
-				.ok_or(Error::<T>::Overflow)?
-				.checked_mul(&remain_day.saturated_into())
-				.ok_or(Error::<T>::Overflow)?
-				.try_into()
-				.map_err(|_e| Error::<T>::Overflow)?;
-			//Judge whether the balance is sufficient
-			ensure!(
-				<T as pallet::Config>::Currency::can_slash(&sender, price.clone()),
-				Error::<T>::InsufficientBalance
-			);
-
-			let acc: AccountOf<T> = T::FilbakPalletId::get().into_account_truncating();
-			Self::add_purchased_space(
-				space,
-			)?;
+			let new_space = cur_owned_space.total_space.checked_add(space).ok_or(Error::<T>::Overflow)?;
 
-			Self::expension_puchased_package(sender.clone(), space)?;
-
-			<T as pallet::Config>::Currency::transfer(&sender, &acc, price.clone(), KeepAlive)?;
+			let (_, price) = Self::do_upgrade_package(&sender, new_space)?;
 
+			Self::deposit_event(Event::<T>::CallDeprecated {
+				acc: sender.clone(),
+				call: b"expansion_space".to_vec(),
+				replacement: b"upgrade_package".to_vec(),
+			});
 			Self::deposit_event(Event::<T>::ExpansionSpace {
 				acc: sender,
 				expansion_space: space,
@@ -267,41 +526,30 @@ pub mod pallet {
 			});
 			Ok(())
 		}
-		/// Package renewal
+		/// Deprecated, use `renew_package`.
 		///
-		/// Currently, lease renewal only supports single month renewal
+		/// Renews the package by `days` through the logic of `renew_package`, priced by the day,
+		/// and emits `CallDeprecated` besides the `RenewalSpace` event integrations expect.
+		/// The call keeps its index for at least two runtime upgrades after the deprecation.
+		///
+		/// Parameters:
+		/// - `days`: Number of days to renew.
 		#[pallet::call_index(2)]
 		#[transactional]
-		#[pallet::weight(<T as pallet::Config>::WeightInfo::renewal_space())]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::renew_package())]
 		pub fn renewal_space(origin: OriginFor<T>, days: u32) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
-			let cur_owned_space = <UserOwnedSpace<T>>::try_get(&sender)
-				.map_err(|_e| Error::<T>::NotPurchasedSpace)?;
+			ensure!(!Self::is_frozen(&sender), Error::<T>::LeaseFreeze);
+			let gib_count = Self::renewable_gib_count(&sender)?;
 
-            ensure!(
-                cur_owned_space.state.to_vec() != SPACE_DEAD.as_bytes().to_vec(), 
-                Error::<T>::LeaseExpired,
-            );
+			let price = Self::space_price(gib_count, days)?;
+			let (price, _) = Self::do_renew_package(&sender, days, price)?;
 
-			let days_unit_price = <UnitPrice<T>>::try_get()
-				.map_err(|_e| Error::<T>::BugInvalid)?
-				.checked_div(&30u32.saturated_into())
-				.ok_or(Error::<T>::Overflow)?;
-			let gib_count = cur_owned_space.total_space.checked_div(G_BYTE).ok_or(Error::<T>::Overflow)?;
-			let price: BalanceOf<T> = days_unit_price
-				.checked_mul(&gib_count.saturated_into())
-				.ok_or(Error::<T>::Overflow)?
-				.checked_mul(&days.saturated_into())
-				.ok_or(Error::<T>::Overflow)?
-				.try_into()
-				.map_err(|_e| Error::<T>::Overflow)?;
-			ensure!(
-				<T as pallet::Config>::Currency::can_slash(&sender, price.clone()),
-				Error::<T>::InsufficientBalance
-			);
-			let acc = T::FilbakPalletId::get().into_account_truncating();
-			<T as pallet::Config>::Currency::transfer(&sender, &acc, price.clone(), KeepAlive)?;
-			Self::update_puchased_package(sender.clone(), days)?;
+			Self::deposit_event(Event::<T>::CallDeprecated {
+				acc: sender.clone(),
+				call: b"renewal_space".to_vec(),
+				replacement: b"renew_package".to_vec(),
+			});
 			Self::deposit_event(Event::<T>::RenewalSpace {
 				acc: sender,
 				renewal_days: days,
@@ -310,20 +558,772 @@ pub mod pallet {
 			Ok(())
 		}
 
+		/// Renew the purchased package by whole months.
+		///
+		/// The price is recomputed against the current total space of the package.
+		/// A frozen package is reactivated with `reactivate_package`,
+		/// a package that has already been cleared cannot be renewed.
+		///
+		/// Parameters:
+		/// - `months`: Number of months to renew.
+		#[pallet::call_index(3)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::renew_package())]
+		pub fn renew_package(origin: OriginFor<T>, months: u32) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(months > 0, Error::<T>::WrongOperation);
+			ensure!(!Self::is_frozen(&sender), Error::<T>::LeaseFreeze);
+			let price = Self::renewal_base_price(&sender, months)?;
+			let days = months.checked_mul(30).ok_or(Error::<T>::Overflow)?;
+			let (price, deadline) = Self::do_renew_package(&sender, days, price)?;
+
+			Self::deposit_event(Event::<T>::PackageRenewed {
+				acc: sender,
+				months,
+				deadline,
+				fee: price,
+			});
+			Ok(())
+		}
+
+		/// Register a referral code for the caller.
+		///
+		/// Parameters:
+		/// - `code`: The code given to buyers, must not be taken.
+		#[pallet::call_index(7)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::register_referral_code())]
+		pub fn register_referral_code(origin: OriginFor<T>, code: ReferralCodeOf) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(!<ReferralCode<T>>::contains_key(&code), Error::<T>::ReferralCodeExisted);
+
+			<ReferralCode<T>>::insert(&code, &sender);
+
+			Self::deposit_event(Event::<T>::ReferralCodeRegistered { acc: sender, code });
+			Ok(())
+		}
+		/// Set the reward and discount of purchases with a referral code.
+		///
+		/// The dispatch origin of this call must be Root.
+		/// The schedule is announced and takes effect `PricingAnnouncePeriod` blocks later.
+		///
+		/// Parameters:
+		/// - `reward_rate`: Share of the price paid to the referrer.
+		/// - `discount_rate`: Share of the price taken off for the buyer.
+		#[pallet::call_index(8)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_referral_schedule())]
+		pub fn set_referral_schedule(origin: OriginFor<T>, reward_rate: Perbill, discount_rate: Perbill) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+			ensure!(
+				reward_rate.deconstruct().saturating_add(discount_rate.deconstruct()) <= Perbill::one().deconstruct(),
+				Error::<T>::InvalidReferralSchedule
+			);
+
+			Self::announce_pricing_change(PricingChange::ReferralSchedule(ReferralSchedule { reward_rate, discount_rate }))
+		}
+
+		/// Set the price multiplier of a region.
+		///
+		/// The dispatch origin of this call must be Root.
+		/// The multiplier is announced and takes effect `PricingAnnouncePeriod` blocks later.
+		///
+		/// Parameters:
+		/// - `region`: The region code.
+		/// - `multiplier`: Multiplier in percent, None stops offering the region to new packages.
+		#[pallet::call_index(9)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_region_multiplier())]
+		pub fn set_region_multiplier(origin: OriginFor<T>, region: RegionCode, multiplier: Option<u32>) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+
+			if let Some(value) = multiplier {
+				ensure!(value > 0, Error::<T>::InvalidRegionMultiplier);
+			}
+
+			Self::announce_pricing_change(PricingChange::RegionMultiplier { region, multiplier })
+		}
+
+		/// Reset the unit price to the default price.
+		///
+		/// The dispatch origin of this call must be Root.
+		/// The price is announced and takes effect `PricingAnnouncePeriod` blocks later.
 		#[pallet::call_index(4)]
 		#[transactional]
 		#[pallet::weight(100_000_000)]
 		pub fn update_price(origin: OriginFor<T>) -> DispatchResult {
 			let _ = ensure_root(origin)?;
 			let default_price: BalanceOf<T> = 30u32.saturated_into();
-			UnitPrice::<T>::put(default_price);
 
+			Self::announce_pricing_change(PricingChange::UnitPrice(default_price))
+		}
+		/// Upgrade the purchased package to a larger tier of the catalogue.
+		///
+		/// The remaining tenancy of the current package is credited against the new one,
+		/// so only the difference for the remaining days is charged, the deadline is unchanged.
+		///
+		/// Parameters:
+		/// - `new_package_type`: The tier of the catalogue to move to.
+		#[pallet::call_index(5)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::upgrade_package())]
+		pub fn upgrade_package(origin: OriginFor<T>, new_package_type: PackageTierId) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let package = <PackageCatalogue<T>>::get(new_package_type).ok_or(Error::<T>::PackageNotFound)?;
+			let new_space = G_BYTE.checked_mul(package.gib_count as u128).ok_or(Error::<T>::Overflow)?;
+
+			let (old_space, price) = Self::do_upgrade_package(&sender, new_space)?;
+
+			Self::deposit_event(Event::<T>::PackageUpgraded {
+				acc: sender,
+				tier: new_package_type,
+				old_space,
+				new_space,
+				fee: price,
+			});
+			Ok(())
+		}
+		/// Downgrade the purchased package to a smaller tier of the catalogue.
+		///
+		/// The stored data is kept, so the space in use must fit into the new package.
+		/// The credit left by the released space is converted into additional days of lease.
+		///
+		/// Parameters:
+		/// - `new_package_type`: The tier of the catalogue to move to.
+		#[pallet::call_index(6)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::downgrade_package())]
+		pub fn downgrade_package(origin: OriginFor<T>, new_package_type: PackageTierId) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let gib_count = <PackageCatalogue<T>>::get(new_package_type)
+				.ok_or(Error::<T>::PackageNotFound)?
+				.gib_count;
+			let cur_owned_space = <UserOwnedSpace<T>>::try_get(&sender)
+				.map_err(|_e| Error::<T>::NotPurchasedSpace)?;
+			Self::check_package_active(&cur_owned_space)?;
+
+			let old_space = cur_owned_space.total_space;
+			let new_space = G_BYTE.checked_mul(gib_count as u128).ok_or(Error::<T>::Overflow)?;
+			ensure!(gib_count > 0 && new_space < old_space, Error::<T>::WrongOperation);
+			let occupied_space = cur_owned_space.used_space
+				.checked_add(cur_owned_space.locked_space).ok_or(Error::<T>::Overflow)?;
+			ensure!(occupied_space <= new_space, Error::<T>::ExceedCapacity);
+
+			// The released gibs for the remaining days, spread over the gibs of the new package.
+			let remain_day = Self::calculate_remain_day(cur_owned_space.deadline)?;
+			let released_space = old_space.checked_sub(new_space).ok_or(Error::<T>::Overflow)?;
+			let extra_days: u32 = released_space
+				.checked_div(G_BYTE).ok_or(Error::<T>::Overflow)?
+				.checked_mul(remain_day as u128).ok_or(Error::<T>::Overflow)?
+				.checked_div(gib_count as u128).ok_or(Error::<T>::Overflow)?
+				.try_into()
+				.map_err(|_e| Error::<T>::Overflow)?;
+
+			Self::sub_purchased_space(released_space)?;
+			let deadline = <UserOwnedSpace<T>>::try_mutate(&sender, |s_opt| -> Result<BlockNumberOf<T>, DispatchError> {
+				let s = s_opt.as_mut().ok_or(Error::<T>::NotPurchasedSpace)?;
+				let sur_block: BlockNumberOf<T> = <T as pallet::Config>::OneDay::get()
+					.checked_mul(&extra_days.saturated_into()).ok_or(Error::<T>::Overflow)?;
+				s.total_space = new_space;
+				s.remaining_space = new_space.checked_sub(occupied_space).ok_or(Error::<T>::Overflow)?;
+				s.deadline = s.deadline.checked_add(&sur_block).ok_or(Error::<T>::Overflow)?;
+				Ok(s.deadline)
+			})?;
+			Self::enqueue_lease(&sender, deadline)?;
+			Self::record_ledger(&sender, LedgerKind::Downgrade, Zero::zero(), 0, released_space);
+
+			Self::deposit_event(Event::<T>::PackageDowngraded {
+				acc: sender,
+				tier: new_package_type,
+				old_space,
+				new_space,
+				deadline,
+			});
+			Ok(())
+		}
+		/// Claim the free trial package.
+		///
+		/// Every account can claim one package of `TRIAL_GIB` gibs for `TRIAL_DAYS` days
+		/// while it holds no package. `TrialDeposit` is reserved against sybil accounts
+		/// and can be released `TrialDepositPeriod` blocks after the claim.
+		///
+		/// The dispatch origin of this call must be Signed.
+		#[pallet::call_index(10)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::claim_trial_space())]
+		pub fn claim_trial_space(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(!<UserFreeRecord<T>>::contains_key(&sender), Error::<T>::TrialClaimed);
+			ensure!(!<UserOwnedSpace<T>>::contains_key(&sender), Error::<T>::PurchasedSpace);
+
+			let deposit = T::TrialDeposit::get();
+			<T as pallet::Config>::Currency::reserve(&sender, deposit)
+				.map_err(|_| Error::<T>::InsufficientBalance)?;
+
+			let space = G_BYTE.checked_mul(TRIAL_GIB as u128).ok_or(Error::<T>::Overflow)?;
+			Self::add_user_purchased_space(sender.clone(), space, TRIAL_DAYS)?;
+			Self::add_purchased_space(space)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let release_at = now.checked_add(&T::TrialDepositPeriod::get()).ok_or(Error::<T>::Overflow)?;
+			<UserFreeRecord<T>>::insert(&sender, TrialRecord::<T> { deposit, release_at });
+
+			Self::record_ledger(&sender, LedgerKind::Purchase, Zero::zero(), space, 0);
+			Self::deposit_event(Event::<T>::TrialClaimed { acc: sender, space, deposit });
+			Ok(())
+		}
+		/// Release the deposit of the trial package.
+		///
+		/// The dispatch origin of this call must be Signed.
+		#[pallet::call_index(11)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::release_trial_deposit())]
+		pub fn release_trial_deposit(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let deposit = <UserFreeRecord<T>>::try_mutate(&sender, |record_opt| -> Result<BalanceOf<T>, DispatchError> {
+				let record = record_opt.as_mut().ok_or(Error::<T>::NoTrialDeposit)?;
+				ensure!(!record.deposit.is_zero(), Error::<T>::NoTrialDeposit);
+				let now = <frame_system::Pallet<T>>::block_number();
+				ensure!(now >= record.release_at, Error::<T>::TrialDepositLocked);
+
+				Ok(sp_std::mem::replace(&mut record.deposit, Zero::zero()))
+			})?;
+			<T as pallet::Config>::Currency::unreserve(&sender, deposit);
+
+			Self::deposit_event(Event::<T>::TrialDepositReleased { acc: sender, deposit });
+			Ok(())
+		}
+		/// Move the trial deposit of a sybil account to the treasury.
+		///
+		/// The dispatch origin of this call must be _Root_.
+		///
+		/// Parameters:
+		/// - `acc`: The account that claimed the trial package.
+		#[pallet::call_index(12)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::slash_trial_deposit())]
+		pub fn slash_trial_deposit(origin: OriginFor<T>, acc: AccountOf<T>) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+
+			let deposit = <UserFreeRecord<T>>::try_mutate(&acc, |record_opt| -> Result<BalanceOf<T>, DispatchError> {
+				let record = record_opt.as_mut().ok_or(Error::<T>::NoTrialDeposit)?;
+				ensure!(!record.deposit.is_zero(), Error::<T>::NoTrialDeposit);
+
+				Ok(sp_std::mem::replace(&mut record.deposit, Zero::zero()))
+			})?;
+			let treasury: AccountOf<T> = T::TreasuryPalletId::get().into_account_truncating();
+			<T as pallet::Config>::Currency::repatriate_reserved(&acc, &treasury, deposit, BalanceStatus::Free)?;
+
+			Self::deposit_event(Event::<T>::TrialDepositSlashed { acc, deposit });
+			Ok(())
+		}
+
+		/// Record the price reported by a price authority.
+		///
+		/// Submitted unsigned by the offchain worker, the report is checked against
+		/// the price authorities and its signature in `validate_unsigned`.
+		/// Every authority reports once per interval, once `MinPriceReports` authorities
+		/// reported the unit price is set to the median of their reports.
+		/// Reports are checked against the unit price of the interval, so the price moves
+		/// at most `MaxPriceDeviation` per interval.
+		///
+		/// Parameters:
+		/// - `report`: The median price, the block it was fetched at and the reporting key.
+		/// - `signature`: Signature of the report by the reporting key.
+		#[pallet::call_index(13)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::submit_price())]
+		pub fn submit_price(
+			origin: OriginFor<T>,
+			report: PriceReport<BalanceOf<T>, BlockNumberOf<T>, T::AuthorityId>,
+			_signature: <T::AuthorityId as RuntimeAppPublic>::Signature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(!Self::price_throttled(now), Error::<T>::PriceReportTooEarly);
+			ensure!(Self::price_within_deviation(report.price), Error::<T>::PriceDeviationExceeded);
+
+			let mut reports = Self::current_price_reports(now);
+			ensure!(
+				!reports.iter().any(|reported| reported.authority == report.authority),
+				Error::<T>::DuplicatePriceReport
+			);
+			reports.try_push(report.clone()).map_err(|_| Error::<T>::BoundedVecError)?;
+
+			Self::deposit_event(Event::<T>::PriceReported { authority: report.authority, price: report.price });
+
+			if (reports.len() as u32) < T::MinPriceReports::get() {
+				<PriceReports<T>>::put(reports);
+				return Ok(());
+			}
+
+			let prices: Vec<u128> = reports.iter().map(|reported| reported.price.saturated_into()).collect();
+			let new: BalanceOf<T> = Self::median(prices).saturated_into();
+			let old = <UnitPrice<T>>::get().unwrap_or_default();
+			<UnitPrice<T>>::put(new);
+			<LastPriceUpdate<T>>::put(now);
+			<PriceReports<T>>::kill();
+
+			Self::deposit_event(Event::<T>::UnitPriceUpdated { old, new });
+			Ok(())
+		}
+
+		/// Cancel an announced pricing change before it activates.
+		///
+		/// The dispatch origin of this call must be `PricingCancelOrigin`.
+		///
+		/// Parameters:
+		/// - `id`: The id of the announced change.
+		#[pallet::call_index(14)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::cancel_pricing_change())]
+		pub fn cancel_pricing_change(origin: OriginFor<T>, id: u32) -> DispatchResult {
+			T::PricingCancelOrigin::ensure_origin(origin)?;
+
+			let pending = <PendingPricingChanges<T>>::take(id).ok_or(Error::<T>::PricingChangeNotFound)?;
+			<PricingChangeQueue<T>>::mutate(&pending.activation, |queue| queue.retain(|queued| *queued != id));
+
+			Self::deposit_event(Event::<T>::PricingChangeCancelled { id });
+			Ok(())
+		}
+
+		/// Purchase a package paid in a registered asset.
+		///
+		/// The price is converted at the price of the asset, the asset goes to the treasury
+		/// and the treasury pays the package in the native token.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// Parameters:
+		/// - `gib_count`: Quantity of several gibs purchased.
+		/// - `referral_code`: Optional code of a referrer, gives the buyer a discount.
+		/// - `region`: Optional region the package is placed in, priced with the multiplier of the region.
+		/// - `asset`: The asset paid in.
+		/// - `max_amount`: Most of the asset the buyer pays.
+		#[pallet::call_index(15)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::buy_space_with_asset())]
+		pub fn buy_space_with_asset(
+			origin: OriginFor<T>,
+			gib_count: u32,
+			referral_code: Option<ReferralCodeOf>,
+			region: Option<RegionCode>,
+			asset: AssetIdOf<T>,
+			max_amount: u128,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_buy_space(sender, Self::monthly_package(gib_count), referral_code, region, Some((asset, max_amount)))
+		}
+
+		/// Set the price of an asset packages can be paid in.
+		///
+		/// The dispatch origin of this call must be Root.
+		///
+		/// Parameters:
+		/// - `asset`: The asset.
+		/// - `price`: Units of the asset per unit of the native token, None stops accepting the asset.
+		#[pallet::call_index(16)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_asset_price())]
+		pub fn set_asset_price(origin: OriginFor<T>, asset: AssetIdOf<T>, price: Option<FixedU128>) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+
+			match price {
+				Some(value) => {
+					ensure!(!value.is_zero(), Error::<T>::WrongOperation);
+					<AssetPrice<T>>::insert(&asset, value);
+				},
+				None => <AssetPrice<T>>::remove(&asset),
+			}
+
+			Self::deposit_event(Event::<T>::AssetPriceSet { asset, price });
+			Ok(())
+		}
+
+		/// Replace the keys registered as price feeders.
+		///
+		/// The dispatch origin of this call must be `PriceFeederOrigin`.
+		/// The keys are of the `cspf` key type, separate from the keys of the other offchain workers.
+		///
+		/// Parameters:
+		/// - `feeders`: The new keys, an empty list stops the price reports.
+		#[pallet::call_index(17)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_price_feeders())]
+		pub fn set_price_feeders(
+			origin: OriginFor<T>,
+			feeders: BoundedVec<T::AuthorityId, ConstU32<PRICE_FEEDER_LIMIT>>,
+		) -> DispatchResult {
+			T::PriceFeederOrigin::ensure_origin(origin)?;
+
+			<PriceFeeders<T>>::put(&feeders);
+
+			Self::deposit_event(Event::<T>::PriceFeedersSet { feeders: feeders.into_inner() });
+			Ok(())
+		}
+
+		/// Add, replace or withdraw a tier of the package catalogue.
+		///
+		/// The dispatch origin of this call must be Root.
+		/// The change is announced and takes effect `PricingAnnouncePeriod` blocks later.
+		/// Packages bought before keep their space and deadline.
+		///
+		/// Parameters:
+		/// - `tier`: The tier id.
+		/// - `package`: Space, days and price of the tier, None withdraws the tier.
+		#[pallet::call_index(19)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_package())]
+		pub fn set_package(
+			origin: OriginFor<T>,
+			tier: PackageTierId,
+			package: Option<PackageTier<BalanceOf<T>>>,
+		) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+
+			if let Some(value) = &package {
+				ensure!(value.gib_count > 0 && value.days > 0, Error::<T>::InvalidPackage);
+			}
+
+			Self::announce_pricing_change(PricingChange::Package { tier, package })
+		}
+
+		/// Buy a package of the catalogue.
+		///
+		/// The dispatch origin of this call must be Signed.
+		/// Priced like `buy_space` by the formula of the tier instead of a month at the unit price.
+		///
+		/// Parameters:
+		/// - `tier`: The tier of the catalogue.
+		/// - `referral_code`: Optional code of a referrer, gives the buyer a discount.
+		/// - `region`: Optional region the package is placed in, priced with the multiplier of the region.
+		#[pallet::call_index(20)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::buy_package())]
+		pub fn buy_package(
+			origin: OriginFor<T>,
+			tier: PackageTierId,
+			referral_code: Option<ReferralCodeOf>,
+			region: Option<RegionCode>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let package = <PackageCatalogue<T>>::get(tier).ok_or(Error::<T>::PackageNotFound)?;
+
+			Self::do_buy_space(sender.clone(), package, referral_code, region, None)?;
+
+			Self::deposit_event(Event::<T>::PackageBought { acc: sender, tier });
+			Ok(())
+		}
+
+		/// Reactivate a frozen package by whole months.
+		///
+		/// The files of a frozen package stay stored until the frozen days are over,
+		/// so the days since the deadline are charged on top of the renewal.
+		/// The lease restarts from the current block and uploads are accepted again.
+		///
+		/// Parameters:
+		/// - `months`: Number of months to renew from the current block.
+		#[pallet::call_index(18)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::reactivate_package())]
+		pub fn reactivate_package(origin: OriginFor<T>, months: u32) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(months > 0, Error::<T>::WrongOperation);
+			ensure!(Self::is_frozen(&sender), Error::<T>::PackageNotFrozen);
+
+			let price = Self::renewal_base_price(&sender, months)?
+				.checked_add(&Self::frozen_days_price(&sender)?)
+				.ok_or(Error::<T>::Overflow)?;
+			let days = months.checked_mul(30).ok_or(Error::<T>::Overflow)?;
+			let (price, deadline) = Self::do_renew_package(&sender, days, price)?;
+
+			Self::deposit_event(Event::<T>::PackageReactivated {
+				acc: sender,
+				months,
+				deadline,
+				fee: price,
+			});
 			Ok(())
 		}
     }
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			if let Call::submit_price { report, signature } = call {
+				Self::check_price_report(report, signature)
+			} else {
+				InvalidTransaction::Call.into()
+			}
+		}
+	}
 }
 
 impl<T: Config> Pallet<T> {
+    fn check_price_report(
+        report: &PriceReport<BalanceOf<T>, BlockNumberOf<T>, T::AuthorityId>,
+        signature: &<T::AuthorityId as RuntimeAppPublic>::Signature,
+    ) -> TransactionValidity {
+        let now = <frame_system::Pallet<T>>::block_number();
+        let interval = T::PriceInterval::get();
+
+        if !T::PriceAuthorities::contains(&report.authority) {
+            return InvalidTransaction::BadSigner.into();
+        }
+        if report.block_number > now {
+            return InvalidTransaction::Future.into();
+        }
+        // Prices fetched a whole interval ago are replaced by the next report.
+        if report.block_number.saturating_add(interval) <= now || Self::price_throttled(now) {
+            return InvalidTransaction::Stale.into();
+        }
+        if !Self::price_within_deviation(report.price) {
+            return InvalidTransaction::Call.into();
+        }
+        if Self::current_price_reports(now).iter().any(|reported| reported.authority == report.authority) {
+            return InvalidTransaction::Stale.into();
+        }
+
+        let signature_valid = report.using_encoded(|encoded_report| {
+            report.authority.verify(&encoded_report, signature)
+        });
+
+        if !signature_valid {
+            log::error!("bad price report signature.");
+            return InvalidTransaction::BadProof.into()
+        }
+
+        // One report per authority and interval is included.
+        ValidTransaction::with_tag_prefix("StorageUnitPrice")
+            .priority(T::UnsignedPriority::get())
+            .and_provides((<LastPriceUpdate<T>>::get(), &report.authority))
+            .longevity(TryInto::<u64>::try_into(interval).unwrap_or(64_u64))
+            .propagate(true)
+            .build()
+    }
+
+    /// The price reports of the current interval, reports fetched a whole interval ago are dropped.
+    fn current_price_reports(
+        now: BlockNumberOf<T>,
+    ) -> BoundedVec<PriceReport<BalanceOf<T>, BlockNumberOf<T>, T::AuthorityId>, ConstU32<PRICE_FEEDER_LIMIT>> {
+        let mut reports = <PriceReports<T>>::get();
+        reports.retain(|reported| reported.block_number.saturating_add(T::PriceInterval::get()) > now);
+        reports
+    }
+
+    /// Whether the unit price was updated less than `PriceInterval` blocks ago.
+    fn price_throttled(now: BlockNumberOf<T>) -> bool {
+        match <LastPriceUpdate<T>>::get() {
+            Some(last) => last.saturating_add(T::PriceInterval::get()) > now,
+            None => false,
+        }
+    }
+
+    /// Whether the price is within `MaxPriceDeviation` of the current unit price.
+    /// Any price is accepted while no unit price is set.
+    fn price_within_deviation(price: BalanceOf<T>) -> bool {
+        let unit_price = match <UnitPrice<T>>::get() {
+            Some(unit_price) if !unit_price.is_zero() => unit_price,
+            _ => return true,
+        };
+        let diff = if price > unit_price { price - unit_price } else { unit_price - price };
+
+        diff <= T::MaxPriceDeviation::get() * unit_price
+    }
+
+    /// Store the change and queue it for activation `PricingAnnouncePeriod` blocks from now.
+    fn announce_pricing_change(change: PricingChange<BalanceOf<T>>) -> DispatchResult {
+        let now = <frame_system::Pallet<T>>::block_number();
+        let activation = now.checked_add(&T::PricingAnnouncePeriod::get()).ok_or(Error::<T>::Overflow)?;
+        let id = <NextPricingChangeId<T>>::get();
+
+        <PricingChangeQueue<T>>::try_mutate(&activation, |queue| {
+            queue.try_push(id).map_err(|_| Error::<T>::PricingQueueFull)
+        })?;
+        <PendingPricingChanges<T>>::insert(id, PendingPricingChange { change: change.clone(), activation });
+        <NextPricingChangeId<T>>::put(id.checked_add(1).ok_or(Error::<T>::Overflow)?);
+
+        Self::deposit_event(Event::<T>::PricingChangeAnnounced { id, change, activation });
+        Ok(())
+    }
+
+    /// Apply the pricing changes whose activation block is `now`.
+    fn apply_pricing_changes(now: BlockNumberOf<T>) -> Weight {
+        let queue = <PricingChangeQueue<T>>::take(&now);
+        let mut weight = T::DbWeight::get().reads_writes(1, 1);
+
+        for id in queue {
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 2));
+            let pending = match <PendingPricingChanges<T>>::take(id) {
+                Some(pending) => pending,
+                None => continue,
+            };
+
+            match pending.change {
+                PricingChange::UnitPrice(new) => {
+                    let old = <UnitPrice<T>>::get().unwrap_or_default();
+                    <UnitPrice<T>>::put(new);
+                    Self::deposit_event(Event::<T>::UnitPriceUpdated { old, new });
+                },
+                PricingChange::ReferralSchedule(schedule) => {
+                    <ReferralSchedules<T>>::put(schedule.clone());
+                    Self::deposit_event(Event::<T>::ReferralScheduleSet {
+                        reward_rate: schedule.reward_rate,
+                        discount_rate: schedule.discount_rate,
+                    });
+                },
+                PricingChange::RegionMultiplier { region, multiplier } => {
+                    match multiplier {
+                        Some(value) => <RegionMultiplier<T>>::insert(&region, value),
+                        None => <RegionMultiplier<T>>::remove(&region),
+                    }
+                    Self::deposit_event(Event::<T>::RegionMultiplierSet { region, multiplier });
+                },
+                PricingChange::Package { tier, package } => {
+                    match &package {
+                        Some(value) => <PackageCatalogue<T>>::insert(tier, value),
+                        None => <PackageCatalogue<T>>::remove(tier),
+                    }
+                    Self::deposit_event(Event::<T>::PackageSet { tier, package });
+                },
+            }
+        }
+
+        weight
+    }
+
+    fn offchain_price_report(now: BlockNumberOf<T>) -> Result<(), OffchainErr> {
+        let key = T::AuthorityId::all()
+            .into_iter()
+            .find(|key| T::PriceAuthorities::contains(key))
+            .ok_or(OffchainErr::Ineligible)?;
+
+        let reported = Self::current_price_reports(now).iter().any(|reported| reported.authority == key);
+        if Self::price_throttled(now) || reported || !Self::check_price_lock(now, &key) {
+            return Err(OffchainErr::Working);
+        }
+
+        let price: BalanceOf<T> = Self::fetch_price_median()?.saturated_into();
+        if !Self::price_within_deviation(price) {
+            return Err(OffchainErr::DeviationExceeded);
+        }
+
+        let report = PriceReport { price, block_number: now, authority: key.clone() };
+        let signature = key.sign(&report.encode()).ok_or(OffchainErr::FailedSigning)?;
+        let call = Call::submit_price { report, signature };
+
+        if let Err(e) = SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()) {
+            log::error!("{:?}", e);
+            return Err(OffchainErr::SubmitTransactionFailed);
+        }
+
+        Ok(())
+    }
+
+    // Keeps the offchain worker from fetching and submitting on every block
+    // while the previous report is still waiting for inclusion.
+    fn check_price_lock(now: BlockNumberOf<T>, key: &T::AuthorityId) -> bool {
+        let lock_key = (b"storage-handler::price", key).encode();
+        let storage = StorageValueRef::persistent(&lock_key);
+
+        let res = storage.mutate(|status: Result<Option<BlockNumberOf<T>>, StorageRetrievalError>| {
+            match status {
+                Ok(Some(last_block)) if last_block.saturating_add(T::PriceInterval::get()) > now => {
+                    Err(OffchainErr::Working)
+                },
+                _ => Ok(now),
+            }
+        });
+
+        res.is_ok()
+    }
+
+    /// Median of the prices answered by the configured price sources.
+    ///
+    /// Sources that fail or answer garbage are skipped, at least `MinPriceSources`
+    /// have to answer so that a single bad source cannot move the price.
+    fn fetch_price_median() -> Result<u128, OffchainErr> {
+        let sources = StorageValueRef::persistent(PRICE_SOURCES_KEY)
+            .get::<Vec<Vec<u8>>>()
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+
+        let prices: Vec<u128> = sources
+            .iter()
+            .filter_map(|url| {
+                Self::fetch_price(url)
+                    .map_err(|e| log::warn!("price source {:?} failed: {:?}", str::from_utf8(url), e))
+                    .ok()
+            })
+            .collect();
+
+        if prices.is_empty() || (prices.len() as u32) < T::MinPriceSources::get() {
+            return Err(OffchainErr::NotEnoughSources);
+        }
+
+        Ok(Self::median(prices))
+    }
+
+    fn median(mut prices: Vec<u128>) -> u128 {
+        prices.sort_unstable();
+        let mid = prices.len() / 2;
+        if prices.len() % 2 == 0 {
+            prices[mid - 1] / 2 + prices[mid] / 2 + (prices[mid - 1] % 2 + prices[mid] % 2) / 2
+        } else {
+            prices[mid]
+        }
+    }
+
+    fn fetch_price(url: &[u8]) -> Result<u128, http::Error> {
+        let url = str::from_utf8(url).map_err(|_| http::Error::Unknown)?;
+        let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(PRICE_FETCH_TIMEOUT));
+
+        let pending = http::Request::get(url)
+            .deadline(deadline)
+            .send()
+            .map_err(|_| http::Error::IoError)?;
+        let response = pending
+            .try_wait(deadline)
+            .map_err(|_| http::Error::DeadlineReached)??;
+        if response.code != 200 {
+            return Err(http::Error::Unknown);
+        }
+
+        let body = response.body().collect::<Vec<u8>>();
+        let body = str::from_utf8(&body).map_err(|_| http::Error::Unknown)?;
+
+        body.trim().parse::<u128>().map_err(|_| http::Error::Unknown)
+    }
+
+    /// helper: apply region multiplier.
+    ///
+    /// Scale a package price by the multiplier of the region the user's package is placed in.
+    /// Packages without a region, or in a region no longer offered, keep the price.
+    ///
+    /// Parameters:
+    /// - `acc`: Account
+    /// - `price`: Price at the unit price
+    fn apply_region_multiplier(acc: &AccountOf<T>, price: BalanceOf<T>) -> Result<BalanceOf<T>, DispatchError> {
+        let multiplier = match <UserRegion<T>>::get(acc).and_then(|region| <RegionMultiplier<T>>::get(&region)) {
+            Some(multiplier) => multiplier,
+            None => return Ok(price),
+        };
+
+        let price = price
+            .checked_mul(&multiplier.saturated_into())
+            .ok_or(Error::<T>::Overflow)?
+            .checked_div(&100u32.saturated_into())
+            .ok_or(Error::<T>::Overflow)?;
+
+        Ok(price)
+    }
+
     /// helper: update_puchased_package.
     ///
     /// How to update the corresponding data after renewing the package.
@@ -354,10 +1354,320 @@ impl<T: Config> Pallet<T> {
                 .map_err(|_e| Error::<T>::BoundedVecError)?;
             }
 
+            Self::enqueue_lease(&acc, s.deadline)?;
+
             Ok(())
         })?;
         Ok(())
     }
+    /// helper: check package active.
+    ///
+    /// The package can only be changed while its lease is running.
+    ///
+    /// Parameters:
+    /// - `space_info`: The package of the user.
+    fn check_package_active(space_info: &OwnedSpaceDetails<T>) -> DispatchResult {
+        let now = <frame_system::Pallet<T>>::block_number();
+        ensure!(now < space_info.deadline, Error::<T>::LeaseExpired);
+        ensure!(
+            space_info.state.to_vec() != SPACE_FROZEN.as_bytes().to_vec(),
+            Error::<T>::LeaseFreeze
+        );
+
+        Ok(())
+    }
+    /// helper: upgrade package.
+    ///
+    /// Grow the package of `acc` to `new_space`, charging the new space for the remaining days
+    /// minus the credit of the current package. Shared by `upgrade_package` and `expansion_space`.
+    ///
+    /// Result:
+    /// - The previous space of the package and the fee charged.
+    fn do_upgrade_package(acc: &AccountOf<T>, new_space: u128) -> Result<(u128, BalanceOf<T>), DispatchError> {
+        let (old_space, price) = Self::upgrade_price(acc, new_space)?;
+        ensure!(
+            <T as pallet::Config>::Currency::can_slash(acc, price.clone()),
+            Error::<T>::InsufficientBalance
+        );
+
+        let increment = new_space.checked_sub(old_space).ok_or(Error::<T>::Overflow)?;
+        Self::add_purchased_space(increment)?;
+        Self::expension_puchased_package(acc.clone(), increment)?;
+
+        let pot: AccountOf<T> = T::FilbakPalletId::get().into_account_truncating();
+        <T as pallet::Config>::Currency::transfer(acc, &pot, price.clone(), KeepAlive)?;
+        Self::record_ledger(acc, LedgerKind::Upgrade, price, increment, 0);
+
+        Ok((old_space, price))
+    }
+    /// helper: buy space.
+    ///
+    /// Purchase a package for `sender`, paid in the native token or, with `asset`,
+    /// in a registered asset of at most the given amount. Shared by `buy_space`
+    /// and `buy_space_with_asset`.
+    fn do_buy_space(
+        sender: AccountOf<T>,
+        package: PackageTier<BalanceOf<T>>,
+        referral_code: Option<ReferralCodeOf>,
+        region: Option<RegionCode>,
+        asset: Option<(AssetIdOf<T>, u128)>,
+    ) -> DispatchResult {
+        ensure!(!<UserOwnedSpace<T>>::contains_key(&sender), Error::<T>::PurchasedSpace);
+        if let Some(region) = region {
+            ensure!(<RegionMultiplier<T>>::contains_key(&region), Error::<T>::RegionNotSupported);
+            <UserRegion<T>>::insert(&sender, region);
+        }
+
+        let space = G_BYTE.checked_mul(package.gib_count as u128).ok_or(Error::<T>::Overflow)?;
+
+        Self::add_user_purchased_space(sender.clone(), space, package.days)?;
+        Self::add_purchased_space(space)?;
+        let mut price = Self::package_price(&sender, &package)?;
+
+        let mut referral: Option<(AccountOf<T>, BalanceOf<T>, BalanceOf<T>)> = None;
+        if let Some(code) = referral_code {
+            let referrer = <ReferralCode<T>>::try_get(&code).map_err(|_| Error::<T>::InvalidReferralCode)?;
+            ensure!(referrer != sender, Error::<T>::InvalidReferralCode);
+            let schedule = <ReferralSchedules<T>>::get();
+            let discount = schedule.discount_rate.mul_floor(price);
+            let reward = schedule.reward_rate.mul_floor(price);
+            price = price.checked_sub(&discount).ok_or(Error::<T>::Overflow)?;
+            referral = Some((referrer, reward, discount));
+        }
+
+        let acc = T::FilbakPalletId::get().into_account_truncating();
+        match asset {
+            Some((asset, max_amount)) => {
+                let amount = <AssetPrice<T>>::get(&asset)
+                    .ok_or(Error::<T>::AssetNotAccepted)?
+                    .checked_mul_int(price.saturated_into::<u128>())
+                    .ok_or(Error::<T>::Overflow)?;
+                ensure!(amount <= max_amount, Error::<T>::AssetAmountExceeded);
+                // The treasury takes the asset and pays the pallet account in the native token.
+                let treasury: AccountOf<T> = T::TreasuryPalletId::get().into_account_truncating();
+                T::PackagePayment::transfer_asset(asset, &sender, &treasury, amount)?;
+                <T as pallet::Config>::Currency::transfer(&treasury, &acc, price.clone(), KeepAlive)?;
+                Self::deposit_event(Event::<T>::PaidWithAsset { acc: sender.clone(), asset, amount });
+            },
+            None => {
+                ensure!(
+                    <T as pallet::Config>::Currency::can_slash(&sender, price.clone()),
+                    Error::<T>::InsufficientBalance
+                );
+                <T as pallet::Config>::Currency::transfer(&sender, &acc, price.clone(), KeepAlive)?;
+            },
+        }
+        // The referrer is paid from the pallet account out of the purchase.
+        if let Some((referrer, reward, discount)) = referral {
+            <T as pallet::Config>::Currency::transfer(&acc, &referrer, reward, KeepAlive)?;
+            Self::record_ledger(&referrer, LedgerKind::ReferralReward, reward, 0, 0);
+            Self::deposit_event(Event::<T>::ReferralReward { referrer, buyer: sender.clone(), reward, discount });
+        }
+
+        Self::record_ledger(&sender, LedgerKind::Purchase, price, space, 0);
+        let deadline = <UserOwnedSpace<T>>::get(&sender).ok_or(Error::<T>::NotPurchasedSpace)?.deadline;
+        Self::deposit_event(Event::<T>::BuySpace { acc: sender, storage_capacity: space, spend: price, deadline });
+        Ok(())
+    }
+    /// helper: upgrade price.
+    ///
+    /// The fee of upgrading the package of `acc` to `new_space` for its remaining days,
+    /// the credit of the current package taken off.
+    ///
+    /// Result:
+    /// - The current space of the package and the fee.
+    fn upgrade_price(acc: &AccountOf<T>, new_space: u128) -> Result<(u128, BalanceOf<T>), DispatchError> {
+        let cur_owned_space = <UserOwnedSpace<T>>::try_get(acc)
+            .map_err(|_e| Error::<T>::NotPurchasedSpace)?;
+        Self::check_package_active(&cur_owned_space)?;
+
+        let old_space = cur_owned_space.total_space;
+        ensure!(new_space > old_space, Error::<T>::WrongOperation);
+
+        let remain_day = Self::calculate_remain_day(cur_owned_space.deadline)?;
+        // The credit of the current package covers old_space for the remaining days,
+        // the user pays the added gibs for the same period.
+        let added_gib = (new_space / G_BYTE).checked_sub(old_space / G_BYTE).ok_or(Error::<T>::Overflow)?;
+        let price = Self::space_price(added_gib, remain_day)?;
+        let price = Self::apply_region_multiplier(acc, price)?;
+
+        Ok((old_space, price))
+    }
+    /// helper: purchase price.
+    ///
+    /// The fee of a new package of `gib_count` gibs for 30 days, before the referral discount.
+    fn purchase_price(acc: &AccountOf<T>, gib_count: u32) -> Result<BalanceOf<T>, DispatchError> {
+        let unit_price = <UnitPrice<T>>::try_get()
+            .map_err(|_e| Error::<T>::BugInvalid)?;
+        let price: BalanceOf<T> = unit_price
+            .checked_mul(&gib_count.saturated_into())
+            .ok_or(Error::<T>::Overflow)?;
+
+        Self::apply_region_multiplier(acc, price)
+    }
+    /// helper: monthly package.
+    ///
+    /// The package `buy_space` sells, `gib_count` gibs for 30 days at the unit price.
+    fn monthly_package(gib_count: u32) -> PackageTier<BalanceOf<T>> {
+        PackageTier { gib_count, days: 30, base_fee: Zero::zero(), discount: Perbill::from_percent(0) }
+    }
+    /// helper: package price.
+    ///
+    /// The fee of buying `package` for `acc`, region multiplier included.
+    fn package_price(acc: &AccountOf<T>, package: &PackageTier<BalanceOf<T>>) -> Result<BalanceOf<T>, DispatchError> {
+        let space_price = Self::space_price(package.gib_count as u128, package.days)?;
+        let space_price = space_price.saturating_sub(package.discount.mul_floor(space_price));
+        let price = space_price.checked_add(&package.base_fee).ok_or(Error::<T>::Overflow)?;
+
+        Self::apply_region_multiplier(acc, price)
+    }
+    /// helper: space price.
+    ///
+    /// The fee of `gib_count` gibs for `days` days at the unit price of 30 days,
+    /// the division comes last so that no precision is lost.
+    fn space_price(gib_count: u128, days: u32) -> Result<BalanceOf<T>, DispatchError> {
+        let unit_price = <UnitPrice<T>>::try_get()
+            .map_err(|_e| Error::<T>::BugInvalid)?;
+        let price: BalanceOf<T> = unit_price
+            .checked_mul(&gib_count.saturated_into())
+            .ok_or(Error::<T>::Overflow)?
+            .checked_mul(&days.saturated_into())
+            .ok_or(Error::<T>::Overflow)?
+            .checked_div(&30u32.saturated_into())
+            .ok_or(Error::<T>::Overflow)?;
+
+        Ok(price)
+    }
+    /// helper: renewal base price.
+    ///
+    /// The fee of renewing the package of `acc` by `months`, before the region multiplier.
+    fn renewal_base_price(acc: &AccountOf<T>, months: u32) -> Result<BalanceOf<T>, DispatchError> {
+        let gib_count = Self::renewable_gib_count(acc)?;
+        let unit_price = <UnitPrice<T>>::try_get()
+            .map_err(|_e| Error::<T>::BugInvalid)?;
+        let price: BalanceOf<T> = unit_price
+            .checked_mul(&gib_count.saturated_into())
+            .ok_or(Error::<T>::Overflow)?
+            .checked_mul(&months.saturated_into())
+            .ok_or(Error::<T>::Overflow)?;
+
+        Ok(price)
+    }
+    /// Quote the fee of a package operation with the formula of its call, at the current block.
+    ///
+    /// Parameters:
+    /// - `acc`: The account the operation is quoted for.
+    /// - `kind`: `Purchase`, `Renewal` or `Upgrade`.
+    /// - `count`: Gibs of a purchase, months of a renewal, or total gibs after an upgrade.
+    ///
+    /// Result:
+    /// - The fee, referral discounts are not taken off.
+    pub fn quote_package_price(acc: &AccountOf<T>, kind: LedgerKind, count: u32) -> Result<BalanceOf<T>, DispatchError> {
+        match kind {
+            LedgerKind::Purchase => {
+                ensure!(!<UserOwnedSpace<T>>::contains_key(acc), Error::<T>::PurchasedSpace);
+                Self::purchase_price(acc, count)
+            },
+            LedgerKind::Renewal => {
+                ensure!(count > 0, Error::<T>::WrongOperation);
+                let price = Self::renewal_base_price(acc, count)?;
+                Self::apply_region_multiplier(acc, price)
+            },
+            LedgerKind::Upgrade => {
+                let new_space = G_BYTE.checked_mul(count as u128).ok_or(Error::<T>::Overflow)?;
+                Self::upgrade_price(acc, new_space).map(|(_, price)| price)
+            },
+            _ => Err(Error::<T>::WrongOperation)?,
+        }
+    }
+    /// helper: frozen days price.
+    ///
+    /// The fee of the days since the deadline of the frozen package of `acc`,
+    /// a partial day counts as a whole day, before the region multiplier.
+    fn frozen_days_price(acc: &AccountOf<T>) -> Result<BalanceOf<T>, DispatchError> {
+        let gib_count = Self::renewable_gib_count(acc)?;
+        let deadline = <UserOwnedSpace<T>>::try_get(acc)
+            .map_err(|_e| Error::<T>::NotPurchasedSpace)?
+            .deadline;
+        let now = <frame_system::Pallet<T>>::block_number();
+        let one_day = <T as pallet::Config>::OneDay::get();
+        let frozen_block = now.saturating_sub(deadline);
+        let mut days: u32 = (frozen_block / one_day).saturated_into();
+        if frozen_block % one_day != 0u32.saturated_into() {
+            days = days.saturating_add(1);
+        }
+
+        Self::space_price(gib_count, days)
+    }
+    /// helper: is frozen.
+    ///
+    /// Whether the lease sweep froze the package of `acc`.
+    fn is_frozen(acc: &AccountOf<T>) -> bool {
+        <UserOwnedSpace<T>>::get(acc)
+            .map_or(false, |info| info.state.to_vec() == SPACE_FROZEN.as_bytes().to_vec())
+    }
+    /// helper: renewable gib count.
+    ///
+    /// The gibs of the package of `acc`, the package must not have been cleared.
+    fn renewable_gib_count(acc: &AccountOf<T>) -> Result<u128, DispatchError> {
+        let cur_owned_space = <UserOwnedSpace<T>>::try_get(acc)
+            .map_err(|_e| Error::<T>::NotPurchasedSpace)?;
+        ensure!(
+            cur_owned_space.state.to_vec() != SPACE_DEAD.as_bytes().to_vec(),
+            Error::<T>::LeaseExpired,
+        );
+
+        Ok(cur_owned_space.total_space.checked_div(G_BYTE).ok_or(Error::<T>::Overflow)?)
+    }
+    /// helper: renew package.
+    ///
+    /// Extend the package of `acc` by `days` for `price` before the region multiplier.
+    /// Shared by `renew_package` and `renewal_space`.
+    ///
+    /// Result:
+    /// - The fee charged and the new deadline of the package.
+    fn do_renew_package(
+        acc: &AccountOf<T>,
+        days: u32,
+        price: BalanceOf<T>,
+    ) -> Result<(BalanceOf<T>, BlockNumberOf<T>), DispatchError> {
+        let price = Self::apply_region_multiplier(acc, price)?;
+        ensure!(
+            <T as pallet::Config>::Currency::can_slash(acc, price.clone()),
+            Error::<T>::InsufficientBalance
+        );
+
+        let pot: AccountOf<T> = T::FilbakPalletId::get().into_account_truncating();
+        <T as pallet::Config>::Currency::transfer(acc, &pot, price.clone(), KeepAlive)?;
+        Self::update_puchased_package(acc.clone(), days)?;
+
+        let deadline = <UserOwnedSpace<T>>::try_get(acc)
+            .map_err(|_e| Error::<T>::NotPurchasedSpace)?
+            .deadline;
+        Self::record_ledger(acc, LedgerKind::Renewal, price, 0, 0);
+
+        Ok((price, deadline))
+    }
+    /// helper: calculate remaining days.
+    ///
+    /// Number of days left before the deadline, a partial day counts as a whole day.
+    ///
+    /// Parameters:
+    /// - `deadline`: Deadline of the package.
+    fn calculate_remain_day(deadline: BlockNumberOf<T>) -> Result<u32, DispatchError> {
+        let now = <frame_system::Pallet<T>>::block_number();
+        let block_oneday: BlockNumberOf<T> = <T as pallet::Config>::OneDay::get();
+        let diff_block = deadline.checked_sub(&now).ok_or(Error::<T>::Overflow)?;
+        let mut remain_day: u32 = diff_block
+            .checked_div(&block_oneday)
+            .ok_or(Error::<T>::Overflow)?
+            .saturated_into();
+        if diff_block % block_oneday != 0u32.saturated_into() {
+            remain_day = remain_day.checked_add(1).ok_or(Error::<T>::Overflow)?;
+        }
+
+        Ok(remain_day)
+    }
     /// helper: Expand storage space.
     ///
     /// Relevant data of users after updating the expansion package.
@@ -412,8 +1722,47 @@ impl<T: Config> Pallet<T> {
                 .map_err(|_e| Error::<T>::BoundedVecError)?,
         };
         <UserOwnedSpace<T>>::insert(&acc, info);
+        Self::enqueue_lease(&acc, deadline)?;
         Ok(())
     }
+    /// helper: enqueue lease.
+    ///
+    /// Put the account into the lease sweep of the day before `deadline`, for the expiry
+    /// warning, and of the day after it, for freezing the package.
+    ///
+    /// Parameters:
+    /// - `acc`: The owner of the package.
+    /// - `deadline`: The deadline of the package.
+    pub(super) fn enqueue_lease(acc: &AccountOf<T>, deadline: BlockNumberOf<T>) -> DispatchResult {
+        let one_day = <T as pallet::Config>::OneDay::get();
+        Self::enqueue_lease_expiry(acc, deadline.saturating_sub(one_day))?;
+        Self::enqueue_lease_expiry(acc, deadline)
+    }
+    /// helper: enqueue lease expiry.
+    ///
+    /// Put the account into the lease sweep of the first day boundary after `block`.
+    /// If that day is full, the next day is used.
+    ///
+    /// Parameters:
+    /// - `acc`: The owner of the package.
+    /// - `block`: The block after which the package state changes.
+    pub(super) fn enqueue_lease_expiry(acc: &AccountOf<T>, block: BlockNumberOf<T>) -> DispatchResult {
+        let one_day = <T as pallet::Config>::OneDay::get();
+        let mut slot = block
+            .checked_div(&one_day).ok_or(Error::<T>::Overflow)?
+            .checked_add(&1u32.saturated_into()).ok_or(Error::<T>::Overflow)?
+            .checked_mul(&one_day).ok_or(Error::<T>::Overflow)?;
+
+        for _ in 0..30 {
+            let pushed = <LeaseExpireQueue<T>>::mutate(&slot, |queue| queue.try_push(acc.clone()).is_ok());
+            if pushed {
+                return Ok(());
+            }
+            slot = slot.checked_add(&one_day).ok_or(Error::<T>::Overflow)?;
+        }
+
+        Err(Error::<T>::BoundedVecError)?
+    }
 
     /// helper: update user storage space.
     ///
@@ -446,8 +1795,9 @@ impl<T: Config> Pallet<T> {
             2 => <UserOwnedSpace<T>>::try_mutate(acc, |s_opt| -> DispatchResult {
                 let s = s_opt.as_mut().unwrap();
                 s.used_space = s.used_space.checked_sub(size).ok_or(Error::<T>::Overflow)?;
-                s.remaining_space =
-                    s.total_space.checked_sub(s.used_space).ok_or(Error::<T>::Overflow)?;
+                s.remaining_space = s.total_space
+                    .checked_sub(s.used_space).ok_or(Error::<T>::Overflow)?
+                    .checked_sub(s.locked_space).ok_or(Error::<T>::Overflow)?;
                 Ok(())
             })?,
             _ => Err(Error::<T>::WrongOperation)?,
@@ -458,16 +1808,40 @@ impl<T: Config> Pallet<T> {
     fn frozen_task() -> (Weight, Vec<AccountOf<T>>) {
         let now: BlockNumberOf<T> = <frame_system::Pallet<T>>::block_number();
         let number: u128 = now.saturated_into();
-    
+
         let mut weight: Weight = Weight::from_ref_time(0);
         let mut clear_acc_list: Vec<AccountOf<T>> = Default::default();
 
         log::info!("Start lease expiration check");
-        for (acc, info) in <UserOwnedSpace<T>>::iter() {
+        // Only the packages queued for the days since the last sweep are checked,
+        // renewed packages are skipped as they have been queued again at their new deadline.
+        // A package goes from normal to frozen the day after its deadline, with a warning
+        // the day before, and from frozen to dead once the frozen days are over.
+        let one_day = <T as pallet::Config>::OneDay::get();
+        let mut slot = <NextLeaseSlot<T>>::get();
+        if slot == 0u32.saturated_into() {
+            slot = now / one_day * one_day;
+        }
+        let mut queue: Vec<AccountOf<T>> = Default::default();
+        while slot <= now {
+            queue.extend(<LeaseExpireQueue<T>>::take(&slot).into_iter());
+            weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+            slot = slot + one_day;
+        }
+        <NextLeaseSlot<T>>::put(slot);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+        for acc in queue.into_iter() {
+            let info = match <UserOwnedSpace<T>>::get(&acc) {
+                Some(info) => info,
+                None => continue,
+            };
             weight = weight.saturating_add(T::DbWeight::get().reads(1 as u64));
             if now > info.deadline {
                 let frozen_day: BlockNumberOf<T> = <T as pallet::Config>::FrozenDays::get();
                 if now > info.deadline + frozen_day {
+                    if info.state.to_vec() == SPACE_DEAD.as_bytes().to_vec() {
+                        continue;
+                    }
                     log::info!("clear user:#{}'s files", number);
                     let result = <UserOwnedSpace<T>>::try_mutate(
                         &acc,
@@ -484,7 +1858,10 @@ impl<T: Config> Pallet<T> {
                         },
                     );
                     match result {
-                        Ok(()) => log::info!("user space dead: #{}", number),
+                        Ok(()) => {
+                            log::info!("user space dead: #{}", number);
+                            Self::deposit_event(Event::<T>::LeasePurged { acc: acc.clone(), size: info.total_space });
+                        },
                         Err(e) => log::error!("space mark dead failed: {:?}", e),
                     }
                     weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
@@ -506,18 +1883,106 @@ impl<T: Config> Pallet<T> {
                             },
                         );
                         match result {
-                            Ok(()) => log::info!("user space frozen: #{}", number),
+                            Ok(()) => {
+                                log::info!("user space frozen: #{}", number);
+                                Self::deposit_event(Event::<T>::LeaseExpired { acc: acc.clone(), size: info.total_space });
+                            },
                             Err(e) => log::error!("frozen failed: {:?}", e),
                         }
                         weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
                     }
+                    // Check again once the frozen period is over.
+                    if let Err(e) = Self::enqueue_lease_expiry(&acc, info.deadline + frozen_day) {
+                        log::error!("enqueue lease expiry failed: {:?}", e);
+                    }
+                    weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
                 }
+            } else if info.deadline - now <= one_day {
+                Self::deposit_event(Event::<T>::LeaseExpireIn24Hours { acc, size: info.total_space });
             }
         }
         log::info!("End lease expiration check");
         (weight, clear_acc_list)
     }
 
+    /// helper: record ledger.
+    ///
+    /// Append a package operation to the ledger of an account, dropping the oldest entry when full.
+    ///
+    /// Parameters:
+    /// - `acc`: Account
+    /// - `kind`: The package operation
+    /// - `amount`: Paid by the account, or received for a referral reward
+    /// - `space_added`: Space added to the package
+    /// - `space_removed`: Space released from the package
+    fn record_ledger(acc: &AccountOf<T>, kind: LedgerKind, amount: BalanceOf<T>, space_added: u128, space_removed: u128) {
+        let entry = LedgerEntry {
+            block: <frame_system::Pallet<T>>::block_number(),
+            kind,
+            amount: amount.saturated_into(),
+            space_added,
+            space_removed,
+        };
+
+        <AccountLedger<T>>::mutate(acc, |ledger| {
+            if !ledger.is_empty() && ledger.len() as u32 >= T::MaxLedgerEntries::get() {
+                ledger.remove(0);
+            }
+            // Cannot fail, there is room for the entry.
+            let _ = ledger.try_push(entry);
+        });
+    }
+
+    // Package operations of an account between two blocks, both inclusive, for the runtime api.
+    pub fn account_statement(
+        acc: &T::AccountId,
+        from_block: BlockNumberOf<T>,
+        to_block: BlockNumberOf<T>,
+    ) -> AccountStatement<BlockNumberOf<T>> {
+        let mut statement = AccountStatement {
+            purchases: 0,
+            renewals: 0,
+            refunds: 0,
+            space_added: 0,
+            space_removed: 0,
+            entries: Default::default(),
+        };
+
+        for entry in <AccountLedger<T>>::get(acc) {
+            if entry.block < from_block || entry.block > to_block {
+                continue;
+            }
+            match entry.kind {
+                LedgerKind::Purchase | LedgerKind::Expansion | LedgerKind::Upgrade =>
+                    statement.purchases = statement.purchases.saturating_add(entry.amount),
+                LedgerKind::Renewal =>
+                    statement.renewals = statement.renewals.saturating_add(entry.amount),
+                LedgerKind::ReferralReward =>
+                    statement.refunds = statement.refunds.saturating_add(entry.amount),
+                LedgerKind::Downgrade | LedgerKind::Expiry => (),
+            }
+            statement.space_added = statement.space_added.saturating_add(entry.space_added);
+            statement.space_removed = statement.space_removed.saturating_add(entry.space_removed);
+            statement.entries.push(entry);
+        }
+
+        statement
+    }
+
+    // Package usage of the user for the runtime api.
+    pub fn get_user_space_info(acc: &T::AccountId) -> Option<SpaceInfo<BlockNumberOf<T>>> {
+        let info = <UserOwnedSpace<T>>::get(acc)?;
+        Some(SpaceInfo {
+            total_space: info.total_space,
+            used_space: info.used_space,
+            locked_space: info.locked_space,
+            remaining_space: info.remaining_space,
+            start: info.start,
+            deadline: info.deadline,
+            state: info.state.to_vec(),
+        })
+    }
+
     pub fn lock_user_space(acc: &T::AccountId, needed_space: u128) -> DispatchResult {
         <UserOwnedSpace<T>>::try_mutate(acc, |storage_space_opt| -> DispatchResult {
             let storage_space = storage_space_opt.as_mut().ok_or(Error::<T>::NotPurchasedSpace)?;
@@ -542,6 +2007,18 @@ impl<T: Config> Pallet<T> {
         })
     }
 
+    // Overwrite the used space of the user with the value recomputed from the file list.
+    pub fn correct_user_used_space(acc: &T::AccountId, used_space: u128) -> DispatchResult {
+        <UserOwnedSpace<T>>::try_mutate(acc, |storage_space_opt| -> DispatchResult {
+            let storage_space = storage_space_opt.as_mut().ok_or(Error::<T>::NotPurchasedSpace)?;
+            storage_space.used_space = used_space;
+            storage_space.remaining_space = storage_space.total_space
+                .saturating_sub(storage_space.used_space)
+                .saturating_sub(storage_space.locked_space);
+            Ok(())
+        })
+    }
+
     pub fn unlock_and_used_user_space(acc: &T::AccountId, needed_space: u128) -> DispatchResult {
         <UserOwnedSpace<T>>::try_mutate(acc, |storage_space_opt| -> DispatchResult {
             let storage_space = storage_space_opt.as_mut().ok_or(Error::<T>::NotPurchasedSpace)?;
@@ -619,6 +2096,44 @@ impl<T: Config> Pallet<T> {
     }
 }
 
+#[cfg(any(test, feature = "try-runtime", feature = "consistency-checks"))]
+impl<T: Config> Pallet<T> {
+    /// Checks that the purchased space is the sum of the packages and that each package adds up.
+    pub fn do_try_state() -> Result<(), &'static str> {
+        let mut purchased: u128 = 0;
+        for info in <UserOwnedSpace<T>>::iter_values() {
+            let accounted = info.used_space
+                .checked_add(info.locked_space)
+                .and_then(|space| space.checked_add(info.remaining_space));
+            ensure!(accounted == Some(info.total_space), "package space does not add up");
+            purchased = purchased.checked_add(info.total_space).ok_or("purchased space overflow")?;
+        }
+        ensure!(purchased == <PurchasedSpace<T>>::get(), "PurchasedSpace differs from the packages");
+
+        Ok(())
+    }
+
+    /// The total idle and service space, as recorded for the whole network.
+    pub fn total_space_records() -> (u128, u128) {
+        (<TotalIdleSpace<T>>::get(), <TotalServiceSpace<T>>::get())
+    }
+}
+
+/// Moves the assets packages are paid in, implemented by the runtime over its asset pallet.
+pub trait PackagePayment<AccountId> {
+    type AssetId: Parameter + MaxEncodedLen + Copy;
+
+    fn transfer_asset(asset: Self::AssetId, from: &AccountId, to: &AccountId, amount: u128) -> DispatchResult;
+}
+
+impl<AccountId> PackagePayment<AccountId> for () {
+    type AssetId = u32;
+
+    fn transfer_asset(_asset: u32, _from: &AccountId, _to: &AccountId, _amount: u128) -> DispatchResult {
+        Err(DispatchError::Other("no assets are accepted"))
+    }
+}
+
 pub trait StorageHandle<AccountId> {
     fn update_user_space(acc: &AccountId, opeartion: u8, size: u128) -> DispatchResult;
     fn add_total_idle_space(increment: u128) -> DispatchResult;
@@ -628,10 +2143,14 @@ pub trait StorageHandle<AccountId> {
     fn add_purchased_space(size: u128) -> DispatchResult;
 	fn sub_purchased_space(size: u128) -> DispatchResult;
     fn get_total_space() -> Result<u128, DispatchError>;
+    fn get_unit_price() -> Result<u128, DispatchError>;
+    fn get_user_unit_price(acc: &AccountId) -> Result<u128, DispatchError>;
     fn lock_user_space(acc: &AccountId, needed_space: u128) -> DispatchResult;
     fn unlock_user_space(acc: &AccountId, needed_space: u128) -> DispatchResult;
     fn unlock_and_used_user_space(acc: &AccountId, needed_space: u128) -> DispatchResult;
     fn get_user_avail_space(acc: &AccountId) -> Result<u128, DispatchError>;
+    fn get_user_used_space(acc: &AccountId) -> Result<u128, DispatchError>;
+    fn correct_user_used_space(acc: &AccountId, used_space: u128) -> DispatchResult;
     fn frozen_task() -> (Weight, Vec<AccountId>);
     fn delete_user_space_storage(acc: &AccountId) -> Result<Weight, DispatchError>;
 }
@@ -669,6 +2188,17 @@ impl<T: Config> StorageHandle<T::AccountId> for Pallet<T> {
 		Pallet::<T>::get_total_space()
 	}
 
+    fn get_unit_price() -> Result<u128, DispatchError> {
+        let unit_price = <UnitPrice<T>>::try_get().map_err(|_e| Error::<T>::BugInvalid)?;
+        Ok(unit_price.saturated_into())
+    }
+
+    fn get_user_unit_price(acc: &T::AccountId) -> Result<u128, DispatchError> {
+        let unit_price = <UnitPrice<T>>::try_get().map_err(|_e| Error::<T>::BugInvalid)?;
+        let unit_price = Self::apply_region_multiplier(acc, unit_price)?;
+        Ok(unit_price.saturated_into())
+    }
+
     fn lock_user_space(acc: &T::AccountId, needed_space: u128) -> DispatchResult {
         Pallet::<T>::lock_user_space(acc, needed_space)
     }
@@ -686,6 +2216,15 @@ impl<T: Config> StorageHandle<T::AccountId> for Pallet<T> {
         Ok(info.remaining_space)
     }
 
+    fn get_user_used_space(acc: &T::AccountId) -> Result<u128, DispatchError> {
+        let info = <UserOwnedSpace<T>>::try_get(acc).map_err(|_e| Error::<T>::NotPurchasedSpace)?;
+        Ok(info.used_space)
+    }
+
+    fn correct_user_used_space(acc: &T::AccountId, used_space: u128) -> DispatchResult {
+        Pallet::<T>::correct_user_used_space(acc, used_space)
+    }
+
     fn frozen_task() -> (Weight, Vec<AccountOf<T>>) {
         Self::frozen_task()
     }
@@ -700,7 +2239,9 @@ impl<T: Config> StorageHandle<T::AccountId> for Pallet<T> {
         weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
 
         <UserOwnedSpace<T>>::remove(acc);
-        weight = weight.saturating_add(T::DbWeight::get().writes(1 as u64));
+        <UserRegion<T>>::remove(acc);
+        Self::record_ledger(acc, LedgerKind::Expiry, Zero::zero(), 0, space_info.total_space);
+        weight = weight.saturating_add(T::DbWeight::get().writes(3 as u64));
 
         Ok(weight)
     }
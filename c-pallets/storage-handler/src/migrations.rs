@@ -0,0 +1,58 @@
+use crate::*;
+use frame_support::traits::OnRuntimeUpgrade;
+
+/// Fill the lease expiration queue with the packages purchased before it existed.
+pub struct MigrationLeaseExpireQueue<T: crate::Config>(sp_std::marker::PhantomData<T>);
+impl<T: crate::Config> OnRuntimeUpgrade for MigrationLeaseExpireQueue<T> {
+	fn on_runtime_upgrade() -> Weight {
+		log::info!("StorageHandler migrate start!");
+		migrate::<T>()
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+		log::info!("storage-handler check access");
+		return Ok(Default::default())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+		ensure!(StorageVersion::get::<Pallet<T>>() == 2, "storage-handler not migrated");
+		return Ok(())
+	}
+}
+
+pub fn migrate<T: Config>() -> Weight {
+	let version = StorageVersion::get::<Pallet<T>>();
+	let mut weight: Weight = Weight::from_ref_time(0);
+
+	if version < 2 {
+		log::info!("StorageHandler version 1 -> 2 migrations start!");
+		weight = weight.saturating_add(v2::migrate::<T>());
+		StorageVersion::new(2).put::<Pallet<T>>();
+	}
+
+	weight
+}
+
+mod v2 {
+	use super::*;
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		for (acc, info) in <UserOwnedSpace<T>>::iter() {
+			weight = weight.saturating_add(T::DbWeight::get().reads(1 as u64));
+			// Packages past their deadline are checked at the next sweep,
+			// the others at the first sweep after their deadline.
+			let now = <frame_system::Pallet<T>>::block_number();
+			let block = if info.deadline < now { now } else { info.deadline };
+			if let Err(e) = Pallet::<T>::enqueue_lease_expiry(&acc, block) {
+				log::error!("enqueue lease expiry failed: {:?}, acc: {:?}", e, acc);
+			}
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+		}
+
+		weight
+	}
+}
@@ -0,0 +1,150 @@
+//! Test utilities
+
+use super::*;
+use crate as pallet_storage_handler;
+use frame_support::{
+    parameter_types,
+    traits::{ConstU32, ConstU64, Everything},
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+    testing::{Header, TestXt},
+    traits::{BlakeTwo256, IdentityLookup},
+    Perbill,
+};
+
+pub(crate) type AccountId = u64;
+type BlockNumber = u64;
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+type Balance = u64;
+
+pub(crate) const ALICE: AccountId = 1;
+pub(crate) const ONE_DAY: BlockNumber = 10;
+pub(crate) const FROZEN_DAYS: BlockNumber = 3 * ONE_DAY;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		StorageHandler: pallet_storage_handler,
+	}
+);
+
+parameter_types! {
+	pub const OneDay: BlockNumber = ONE_DAY;
+	pub const FilbakPalletId: PalletId = PalletId(*b"rewardpt");
+	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
+	#[derive(Clone, PartialEq, Eq)]
+	pub const StateStringMax: u32 = 20;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const FrozenDays: BlockNumber = FROZEN_DAYS;
+	pub const MaxLedgerEntries: u32 = 10;
+	pub const TrialDeposit: Balance = 10;
+	pub const TrialDepositPeriod: BlockNumber = 100;
+	pub const UnsignedPriority: TransactionPriority = TransactionPriority::max_value();
+	pub const PriceInterval: BlockNumber = 100;
+	pub const MinPriceSources: u32 = 1;
+	pub const MaxPriceDeviation: Perbill = Perbill::from_percent(20);
+	pub const MinPriceReports: u32 = 1;
+	pub const PricingAnnouncePeriod: BlockNumber = 100;
+}
+
+impl Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type WeightInfo = ();
+	type OneDay = OneDay;
+	type FilbakPalletId = FilbakPalletId;
+	type TreasuryPalletId = TreasuryPalletId;
+	type StateStringMax = StateStringMax;
+	type FrozenDays = FrozenDays;
+	type MaxLedgerEntries = MaxLedgerEntries;
+	type TrialDeposit = TrialDeposit;
+	type TrialDepositPeriod = TrialDepositPeriod;
+	type AuthorityId = crate::sr25519::AuthorityId;
+	type PriceAuthorities = Everything;
+	type PriceFeederOrigin = EnsureRoot<AccountId>;
+	type UnsignedPriority = UnsignedPriority;
+	type PriceInterval = PriceInterval;
+	type MinPriceSources = MinPriceSources;
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type MinPriceReports = MinPriceReports;
+	type PricingAnnouncePeriod = PricingAnnouncePeriod;
+	type PricingCancelOrigin = EnsureRoot<AccountId>;
+	type PackagePayment = ();
+}
+
+pub type Extrinsic = TestXt<RuntimeCall, ()>;
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
+    where
+        RuntimeCall: From<LocalCall>,
+{
+    type Extrinsic = Extrinsic;
+    type OverarchingCall = RuntimeCall;
+}
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type DbWeight = ();
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type DustRemoval = ();
+	type RuntimeEvent = RuntimeEvent;
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(ALICE, 10_000)],
+    }
+        .assimilate_storage(&mut t)
+        .unwrap();
+    pallet_storage_handler::GenesisConfig::<Test> {
+        price: 30,
+    }
+        .assimilate_storage(&mut t)
+        .unwrap();
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| {
+        System::set_block_number(1); //must set block_number, otherwise the deposit_event() don't work
+        TotalIdleSpace::<Test>::put(100 * G_BYTE);
+    });
+    ext
+}
@@ -0,0 +1,138 @@
+use super::*;
+use crate::{mock::*, Event as StorageHandlerEvent};
+use frame_support::{assert_noop, assert_ok};
+
+// Buys a package of one gib for 30 days at block 1, its deadline is block 301.
+fn buy_package() -> BlockNumberOf<Test> {
+    assert_ok!(StorageHandler::buy_space(RuntimeOrigin::signed(ALICE), 1, None, None));
+    <UserOwnedSpace<Test>>::get(&ALICE).unwrap().deadline
+}
+
+fn add_tier(tier: PackageTierId, gib_count: u32) {
+    <PackageCatalogue<Test>>::insert(
+        tier,
+        PackageTier { gib_count, days: 30, base_fee: 0, discount: Perbill::from_percent(0) },
+    );
+}
+
+fn space_state(acc: &AccountId) -> Vec<u8> {
+    <UserOwnedSpace<Test>>::get(acc).unwrap().state.to_vec()
+}
+
+fn sweep_at(now: BlockNumberOf<Test>) -> Vec<AccountId> {
+    System::set_block_number(now);
+    let (_, clear_list) = StorageHandler::frozen_task();
+    clear_list
+}
+
+#[test]
+fn lease_goes_from_normal_to_frozen_to_purged() {
+    new_test_ext().execute_with(|| {
+        let deadline = buy_package();
+        assert_eq!(deadline, 1 + 30 * ONE_DAY);
+        assert_eq!(space_state(&ALICE), SPACE_NORMAL.as_bytes().to_vec());
+
+        // The day before the deadline only warns the owner.
+        assert!(sweep_at(deadline - 1).is_empty());
+        assert_eq!(space_state(&ALICE), SPACE_NORMAL.as_bytes().to_vec());
+        System::assert_has_event(StorageHandlerEvent::LeaseExpireIn24Hours { acc: ALICE, size: G_BYTE }.into());
+
+        // The day after the deadline freezes the package.
+        assert!(sweep_at(deadline + ONE_DAY).is_empty());
+        assert_eq!(space_state(&ALICE), SPACE_FROZEN.as_bytes().to_vec());
+        System::assert_has_event(StorageHandlerEvent::LeaseExpired { acc: ALICE, size: G_BYTE }.into());
+
+        // Once the frozen days are over the files of the owner are cleared.
+        assert_eq!(sweep_at(deadline + FROZEN_DAYS + ONE_DAY), vec![ALICE]);
+        assert_eq!(space_state(&ALICE), SPACE_DEAD.as_bytes().to_vec());
+        System::assert_has_event(StorageHandlerEvent::LeasePurged { acc: ALICE, size: G_BYTE }.into());
+
+        // A purged package is not swept again.
+        assert!(sweep_at(deadline + FROZEN_DAYS + 2 * ONE_DAY).is_empty());
+    });
+}
+
+#[test]
+fn reactivated_package_is_not_purged() {
+    new_test_ext().execute_with(|| {
+        let deadline = buy_package();
+        sweep_at(deadline - 1);
+        sweep_at(deadline + ONE_DAY);
+        assert_eq!(space_state(&ALICE), SPACE_FROZEN.as_bytes().to_vec());
+
+        assert_ok!(StorageHandler::reactivate_package(RuntimeOrigin::signed(ALICE), 1));
+        let new_deadline = <UserOwnedSpace<Test>>::get(&ALICE).unwrap().deadline;
+        assert_eq!(new_deadline, deadline + ONE_DAY + 30 * ONE_DAY);
+        assert_eq!(space_state(&ALICE), SPACE_NORMAL.as_bytes().to_vec());
+
+        // The check queued when the package was frozen finds the lease running again.
+        assert!(sweep_at(deadline + FROZEN_DAYS + ONE_DAY).is_empty());
+        assert_eq!(space_state(&ALICE), SPACE_NORMAL.as_bytes().to_vec());
+
+        // The renewed lease expires on its own deadline.
+        sweep_at(new_deadline - 1);
+        assert!(sweep_at(new_deadline + ONE_DAY).is_empty());
+        assert_eq!(space_state(&ALICE), SPACE_FROZEN.as_bytes().to_vec());
+    });
+}
+
+#[test]
+fn upgrade_charges_the_added_gibs_for_the_remaining_days() {
+    new_test_ext().execute_with(|| {
+        buy_package();
+        // A unit price that does not divide into 30 daily prices.
+        <UnitPrice<Test>>::put(31);
+        add_tier(1, 3);
+        System::set_block_number(1 + 15 * ONE_DAY);
+        let before = Balances::free_balance(&ALICE);
+
+        assert_ok!(StorageHandler::upgrade_package(RuntimeOrigin::signed(ALICE), 1));
+        assert_eq!(<UserOwnedSpace<Test>>::get(&ALICE).unwrap().total_space, 3 * G_BYTE);
+        // 31 * 2 gibs * 15 days / 30
+        assert_eq!(before - Balances::free_balance(&ALICE), 31);
+    });
+}
+
+#[test]
+fn downgrade_moves_to_the_tier_and_extends_the_lease() {
+    new_test_ext().execute_with(|| {
+        let deadline = buy_package();
+        add_tier(1, 3);
+        add_tier(2, 1);
+        assert_noop!(
+            StorageHandler::downgrade_package(RuntimeOrigin::signed(ALICE), 3),
+            Error::<Test>::PackageNotFound
+        );
+        assert_ok!(StorageHandler::upgrade_package(RuntimeOrigin::signed(ALICE), 1));
+
+        assert_ok!(StorageHandler::downgrade_package(RuntimeOrigin::signed(ALICE), 2));
+        let space = <UserOwnedSpace<Test>>::get(&ALICE).unwrap();
+        assert_eq!(space.total_space, G_BYTE);
+        // 2 released gibs * 30 remaining days / 1 gib
+        assert_eq!(space.deadline, deadline + 60 * ONE_DAY);
+        System::assert_last_event(
+            StorageHandlerEvent::PackageDowngraded {
+                acc: ALICE,
+                tier: 2,
+                old_space: 3 * G_BYTE,
+                new_space: G_BYTE,
+                deadline: space.deadline,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn renewal_space_charges_the_days_at_the_space_price() {
+    new_test_ext().execute_with(|| {
+        buy_package();
+        // A unit price that does not divide into 30 daily prices.
+        <UnitPrice<Test>>::put(59);
+        let before = Balances::free_balance(&ALICE);
+
+        assert_ok!(StorageHandler::renewal_space(RuntimeOrigin::signed(ALICE), 10));
+        // 59 * 1 gib * 10 days / 30
+        assert_eq!(before - Balances::free_balance(&ALICE), 19);
+    });
+}
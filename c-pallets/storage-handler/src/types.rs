@@ -11,4 +11,61 @@ pub struct OwnedSpaceDetails<T: Config> {
 	pub(super) start: BlockNumberOf<T>,
 	pub(super) deadline: BlockNumberOf<T>,
 	pub(super) state: BoundedVec<u8, T::StateStringMax>,
-}
\ No newline at end of file
+}
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct TrialRecord<T: Config> {
+	// Reserved when the trial package is claimed, zero once released or slashed.
+	pub(super) deposit: BalanceOf<T>,
+	// The deposit can be released from this block on.
+	pub(super) release_at: BlockNumberOf<T>,
+}
+
+#[derive(PartialEq, Eq, Encode, Decode, Clone, Default, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ReferralSchedule {
+	// Share of the package price paid to the referrer.
+	pub(super) reward_rate: Perbill,
+	// Share of the package price taken off for the buyer.
+	pub(super) discount_rate: Perbill,
+}
+
+pub type PackageTierId = u8;
+
+// A package of the catalogue. Its price is `base_fee` plus the space
+// at the unit price for `days`, less `discount`, times the region multiplier.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct PackageTier<Balance> {
+	pub(super) gib_count: u32,
+	pub(super) days: u32,
+	pub(super) base_fee: Balance,
+	// Share of the space price taken off, the base fee is not discounted.
+	pub(super) discount: Perbill,
+}
+
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum PricingChange<Balance> {
+	UnitPrice(Balance),
+	ReferralSchedule(ReferralSchedule),
+	// None stops offering the region to new packages.
+	RegionMultiplier { region: RegionCode, multiplier: Option<u32> },
+	// None withdraws the tier from the catalogue.
+	Package { tier: PackageTierId, package: Option<PackageTier<Balance>> },
+}
+
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct PendingPricingChange<Balance, BlockNumber> {
+	pub(super) change: PricingChange<Balance>,
+	// The block the change is applied at.
+	pub(super) activation: BlockNumber,
+}
+
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct PriceReport<Balance, BlockNumber, AuthorityId> {
+	// Median of the prices fetched from the price sources.
+	pub(super) price: Balance,
+	// The block the prices were fetched at.
+	pub(super) block_number: BlockNumber,
+	// The key of the price authority that signed the report.
+	pub(super) authority: AuthorityId,
+}
@@ -9,6 +9,23 @@ pub trait WeightInfo {
     fn buy_space() -> Weight;
 	fn expansion_space() -> Weight;
 	fn renewal_space() -> Weight;
+	fn renew_package() -> Weight;
+	fn upgrade_package() -> Weight;
+	fn downgrade_package() -> Weight;
+	fn register_referral_code() -> Weight;
+	fn set_referral_schedule() -> Weight;
+	fn set_region_multiplier() -> Weight;
+	fn claim_trial_space() -> Weight;
+	fn release_trial_deposit() -> Weight;
+	fn slash_trial_deposit() -> Weight;
+	fn submit_price() -> Weight;
+	fn cancel_pricing_change() -> Weight;
+	fn buy_space_with_asset() -> Weight;
+	fn set_asset_price() -> Weight;
+	fn set_price_feeders() -> Weight;
+	fn reactivate_package() -> Weight;
+	fn set_package() -> Weight;
+	fn buy_package() -> Weight;
 }
 
 pub struct SubstrateWeight<T>(PhantomData<T>);
@@ -44,6 +61,137 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(3 as u64))
 			.saturating_add(T::DbWeight::get().writes(2 as u64))
 	}
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UnitPrice (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn renew_package() -> Weight {
+		Weight::from_ref_time(313_401_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: StorageHandler PackageCatalogue (r:1 w:0)
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UnitPrice (r:1 w:0)
+	// Storage: FileBank PurchasedSpace (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn upgrade_package() -> Weight {
+		Weight::from_ref_time(313_401_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(5 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: StorageHandler PackageCatalogue (r:1 w:0)
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank PurchasedSpace (r:1 w:1)
+	fn downgrade_package() -> Weight {
+		Weight::from_ref_time(120_301_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: StorageHandler ReferralCode (r:1 w:1)
+	fn register_referral_code() -> Weight {
+		Weight::from_ref_time(21_340_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler ReferralSchedules (r:0 w:1)
+	fn set_referral_schedule() -> Weight {
+		Weight::from_ref_time(15_120_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler RegionMultiplier (r:0 w:1)
+	fn set_region_multiplier() -> Weight {
+		Weight::from_ref_time(15_000_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler UserFreeRecord (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: StorageHandler PurchasedSpace (r:1 w:1)
+	// Storage: StorageHandler LeaseExpireQueue (r:1 w:1)
+	fn claim_trial_space() -> Weight {
+		Weight::from_ref_time(35_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(5 as u64))
+			.saturating_add(T::DbWeight::get().writes(5 as u64))
+	}
+	// Storage: StorageHandler UserFreeRecord (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn release_trial_deposit() -> Weight {
+		Weight::from_ref_time(22_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: StorageHandler UserFreeRecord (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn slash_trial_deposit() -> Weight {
+		Weight::from_ref_time(26_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: StorageHandler LastPriceUpdate (r:1 w:1)
+	// Storage: StorageHandler UnitPrice (r:1 w:1)
+	// Storage: StorageHandler PriceReports (r:1 w:1)
+	fn submit_price() -> Weight {
+		Weight::from_ref_time(28_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	// Storage: StorageHandler PendingPricingChanges (r:1 w:1)
+	// Storage: StorageHandler PricingChangeQueue (r:1 w:1)
+	fn cancel_pricing_change() -> Weight {
+		Weight::from_ref_time(19_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: StorageHandler UnitPrice (r:1 w:0)
+	// Storage: StorageHandler AssetPrice (r:1 w:0)
+	// Storage: StorageHandler PurchasedSpace (r:1 w:1)
+	// Storage: Sminer TotalIdleSpace (r:1 w:0)
+	// Storage: Sminer TotalServiceSpace (r:1 w:0)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:2 w:2)
+	fn buy_space_with_asset() -> Weight {
+		Weight::from_ref_time(352_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(11 as u64))
+			.saturating_add(T::DbWeight::get().writes(7 as u64))
+	}
+	// Storage: StorageHandler AssetPrice (r:0 w:1)
+	fn set_asset_price() -> Weight {
+		Weight::from_ref_time(15_000_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler PriceFeeders (r:0 w:1)
+	fn set_price_feeders() -> Weight {
+		Weight::from_ref_time(14_000_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UnitPrice (r:1 w:0)
+	// Storage: StorageHandler LeaseExpireQueue (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn reactivate_package() -> Weight {
+		Weight::from_ref_time(325_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(5 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	// Storage: StorageHandler PackageCatalogue (r:0 w:1)
+	fn set_package() -> Weight {
+		Weight::from_ref_time(15_000_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler PackageCatalogue (r:1 w:0)
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UnitPrice (r:1 w:0)
+	// Storage: Sminer PurchasedSpace (r:1 w:1)
+	// Storage: Sminer TotalIdleSpace (r:1 w:0)
+	// Storage: Sminer TotalServiceSpace (r:1 w:0)
+	// Storage: System Account (r:2 w:2)
+	fn buy_package() -> Weight {
+		Weight::from_ref_time(302_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(8 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
 }
 
 impl WeightInfo for () {
@@ -77,4 +225,135 @@ impl WeightInfo for () {
 			.saturating_add(RocksDbWeight::get().reads(3 as u64))
 			.saturating_add(RocksDbWeight::get().writes(2 as u64))
 	}
-}
\ No newline at end of file
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UnitPrice (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn renew_package() -> Weight {
+		Weight::from_ref_time(313_401_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: StorageHandler PackageCatalogue (r:1 w:0)
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UnitPrice (r:1 w:0)
+	// Storage: FileBank PurchasedSpace (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn upgrade_package() -> Weight {
+		Weight::from_ref_time(313_401_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: StorageHandler PackageCatalogue (r:1 w:0)
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank PurchasedSpace (r:1 w:1)
+	fn downgrade_package() -> Weight {
+		Weight::from_ref_time(120_301_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: StorageHandler ReferralCode (r:1 w:1)
+	fn register_referral_code() -> Weight {
+		Weight::from_ref_time(21_340_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler ReferralSchedules (r:0 w:1)
+	fn set_referral_schedule() -> Weight {
+		Weight::from_ref_time(15_120_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler RegionMultiplier (r:0 w:1)
+	fn set_region_multiplier() -> Weight {
+		Weight::from_ref_time(15_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler UserFreeRecord (r:1 w:1)
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: StorageHandler PurchasedSpace (r:1 w:1)
+	// Storage: StorageHandler LeaseExpireQueue (r:1 w:1)
+	fn claim_trial_space() -> Weight {
+		Weight::from_ref_time(35_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes(5 as u64))
+	}
+	// Storage: StorageHandler UserFreeRecord (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn release_trial_deposit() -> Weight {
+		Weight::from_ref_time(22_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: StorageHandler UserFreeRecord (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn slash_trial_deposit() -> Weight {
+		Weight::from_ref_time(26_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: StorageHandler LastPriceUpdate (r:1 w:1)
+	// Storage: StorageHandler UnitPrice (r:1 w:1)
+	// Storage: StorageHandler PriceReports (r:1 w:1)
+	fn submit_price() -> Weight {
+		Weight::from_ref_time(28_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	// Storage: StorageHandler PendingPricingChanges (r:1 w:1)
+	// Storage: StorageHandler PricingChangeQueue (r:1 w:1)
+	fn cancel_pricing_change() -> Weight {
+		Weight::from_ref_time(19_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: StorageHandler UserOwnedSpace (r:1 w:1)
+	// Storage: StorageHandler UnitPrice (r:1 w:0)
+	// Storage: StorageHandler AssetPrice (r:1 w:0)
+	// Storage: StorageHandler PurchasedSpace (r:1 w:1)
+	// Storage: Sminer TotalIdleSpace (r:1 w:0)
+	// Storage: Sminer TotalServiceSpace (r:1 w:0)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:2 w:2)
+	fn buy_space_with_asset() -> Weight {
+		Weight::from_ref_time(352_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(11 as u64))
+			.saturating_add(RocksDbWeight::get().writes(7 as u64))
+	}
+	// Storage: StorageHandler AssetPrice (r:0 w:1)
+	fn set_asset_price() -> Weight {
+		Weight::from_ref_time(15_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler PriceFeeders (r:0 w:1)
+	fn set_price_feeders() -> Weight {
+		Weight::from_ref_time(14_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UnitPrice (r:1 w:0)
+	// Storage: StorageHandler LeaseExpireQueue (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn reactivate_package() -> Weight {
+		Weight::from_ref_time(325_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	// Storage: StorageHandler PackageCatalogue (r:0 w:1)
+	fn set_package() -> Weight {
+		Weight::from_ref_time(15_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: StorageHandler PackageCatalogue (r:1 w:0)
+	// Storage: FileBank UserOwnedSpace (r:1 w:1)
+	// Storage: FileBank UnitPrice (r:1 w:0)
+	// Storage: Sminer PurchasedSpace (r:1 w:1)
+	// Storage: Sminer TotalIdleSpace (r:1 w:0)
+	// Storage: Sminer TotalServiceSpace (r:1 w:0)
+	// Storage: System Account (r:2 w:2)
+	fn buy_package() -> Weight {
+		Weight::from_ref_time(302_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(8 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+}
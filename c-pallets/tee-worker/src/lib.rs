@@ -5,25 +5,31 @@
 
 
 #[cfg(test)]
-mod tests;
-
 mod mock;
 
+#[cfg(test)]
+mod tests;
+
 mod types;
 pub use types::*;
 
+pub mod migrations;
+
 #[cfg(feature = "runtime-benchmarks")]
 pub mod benchmarking;
 
 use codec::{Decode, Encode};
 use frame_support::{
-	dispatch::DispatchResult, traits::ReservableCurrency, transactional, BoundedVec, PalletId,
+	dispatch::DispatchResult,
+	traits::{Currency, OnUnbalanced, ReservableCurrency, UnixTime},
+	transactional, BoundedVec, PalletId,
 	pallet_prelude::*,
 };
 pub use pallet::*;
 use scale_info::TypeInfo;
 use sp_runtime::{
-	DispatchError, RuntimeDebug,
+	traits::{Saturating, Zero},
+	DispatchError, Perbill, RuntimeDebug,
 };
 use sp_std::{ 
 	convert::TryInto,
@@ -38,6 +44,13 @@ use cp_enclave_verify::*;
 pub mod weights;
 
 type AccountOf<T> = <T as frame_system::Config>::AccountId;
+type BalanceOf<T> =
+	<<T as pallet::Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as pallet::Config>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::NegativeImbalance;
+
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(5);
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -72,6 +85,36 @@ pub mod pallet {
 
         #[pallet::constant]
         type MaxWhitelist: Get<u32> + Clone + Eq + PartialEq;
+		//Pallets holding work assigned to a worker, told when the worker exits
+		type OnExit: ScheduleExit<Self::AccountId>;
+		//Blocks between two heartbeats of a worker, the liveness sweep runs at the same interval
+		#[pallet::constant]
+		type HeartbeatInterval: Get<BlockNumberFor<Self>>;
+		//Missed intervals after which a worker is offline and receives no new work
+		#[pallet::constant]
+		type OfflineThreshold: Get<u32>;
+		//Missed intervals after which a worker is slashed and evicted
+		#[pallet::constant]
+		type EvictionThreshold: Get<u32>;
+		//Multiaddrs a worker can announce
+		#[pallet::constant]
+		type MaxEndpoints: Get<u32> + Clone + Eq + PartialEq;
+		//Workers in the committee of a session, whose majority signs verdicts
+		#[pallet::constant]
+		type CommitteeSize: Get<u32>;
+		//Reserved from the controller at registration, slashed when the worker is punished
+		#[pallet::constant]
+		type TeeWorkerBond: Get<BalanceOf<Self>>;
+		//Share of the bond slashed on each punishment
+		#[pallet::constant]
+		type BondSlashFraction: Get<Perbill>;
+		//Blocks after the exit of a worker before its bond can be withdrawn
+		#[pallet::constant]
+		type BondUnbondingPeriod: Get<BlockNumberFor<Self>>;
+		//Receives the slashed bonds
+		type BondSlash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+		//The time DCAP certificates, CRLs and collateral are checked at
+		type UnixTime: UnixTime;
 	}
 
 	#[pallet::event]
@@ -80,9 +123,41 @@ pub mod pallet {
 		//Scheduling registration method
 		RegistrationTeeWorker { acc: AccountOf<T>, peer_id: PeerId },
 
-		Exit { acc: AccountOf<T> },
+		TeeWorkerExit { acc: AccountOf<T> },
+		//The worker missed `OfflineThreshold` heartbeats
+		TeeWorkerOffline { acc: AccountOf<T> },
+		//An offline worker sent a heartbeat again
+		TeeWorkerOnline { acc: AccountOf<T> },
+		//The worker missed `EvictionThreshold` heartbeats and was removed
+		TeeWorkerEvicted { acc: AccountOf<T> },
+		//The attestation report of the worker is older than the validity window
+		TeeWorkerSuspended { acc: AccountOf<T> },
+		//The worker submitted a fresh attestation report
+		AttestationRefreshed { acc: AccountOf<T> },
+		//Root changed the validity window of attestation reports
+		AttestationValiditySet { validity: Option<BlockNumberFor<T>> },
+		//An mr_enclave was added to the whitelist
+		WhitelistAdded { mr_enclave: [u8; 64] },
+		//An mr_enclave was removed from the whitelist
+		WhitelistRemoved { mr_enclave: [u8; 64] },
+		//Root changed the root certificate of DCAP quotes
+		DcapRootCertSet { enabled: bool },
 
 		UpdatePeerId { acc: AccountOf<T> },
+		//A worker replaced the multiaddrs it is reachable at
+		UpdateScheduler { acc: AccountOf<T> },
+		//A worker replaced its podr2 public key
+		UpdatePodr2Key { acc: AccountOf<T> },
+		//A worker voted for the podr2 key of another worker to become the master key
+		MasterPodr2KeyVote { acc: AccountOf<T>, candidate: AccountOf<T> },
+		//The podr2 key of the worker became the master key
+		MasterPodr2KeyChanged { acc: AccountOf<T> },
+		//The committee of the new session was selected
+		CommitteeRotated { session: u32, members: Vec<AccountOf<T>> },
+		//A share of the bond of a punished worker was slashed
+		BondSlashed { acc: AccountOf<T>, amount: BalanceOf<T> },
+		//The bond of an exited worker was released after the unbonding period
+		BondWithdrawn { acc: AccountOf<T>, amount: BalanceOf<T> },
 	}
 
 	#[pallet::error]
@@ -105,28 +180,202 @@ pub mod pallet {
 		NonTeeWorker,
 
 		VerifyCertFailed,
+		//The worker already voted for this candidate
+		AlreadyVoted,
+		//The podr2 key of the worker is already the master key
+		AlreadyMaster,
+		//The mr_enclave is already in the whitelist
+		MrEnclaveExisted,
+		//The mr_enclave is not in the whitelist
+		NotInWhitelist,
+		//No root certificate is set for DCAP quotes
+		DcapDisabled,
+		//The peer id is not the one of the node key
+		PeerIdMismatch,
+		//An endpoint is not a valid multiaddr
+		InvalidMultiaddr,
+		//The nonce of the signed message is not above the last one consumed for the worker
+		StaleNonce,
+		//The declared roles are empty or contain unknown bits
+		InvalidRoles,
+		//The account has no bond in unbonding
+		NoUnbondingBond,
+		//The unbonding period of the bond has not passed yet
+		BondStillUnbonding,
 	}
 
 	#[pallet::storage]
 	#[pallet::getter(fn tee_worker_map)]
 	pub(super) type TeeWorkerMap<T: Config> = CountedStorageMap<_, Blake2_128Concat, AccountOf<T>, TeeWorkerInfo<T>>;
 
+	/// The controller account of the worker registered for each stash account.
+	#[pallet::storage]
+	#[pallet::getter(fn stash_to_controller)]
+	pub(super) type StashToController<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, AccountOf<T>>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn bond_acc)]
 	pub(super) type BondAcc<T: Config> =
 		StorageValue<_, BoundedVec<AccountOf<T>, T::SchedulerMaximum>, ValueQuery>;
 
+	/// The worker whose podr2 key is the master key.
 	#[pallet::storage]
-	#[pallet::getter(fn tee_podr2_pk)]
-	pub(super) type TeePodr2Pk<T: Config> = StorageValue<_, Podr2Key>;
+	#[pallet::getter(fn master_podr2_key)]
+	pub(super) type MasterPodr2Key<T: Config> = StorageValue<_, AccountOf<T>>;
+
+	/// Workers that voted for the podr2 key of a candidate worker to become the master key.
+	#[pallet::storage]
+	#[pallet::getter(fn master_key_votes)]
+	pub(super) type MasterKeyVotes<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, BoundedVec<AccountOf<T>, T::SchedulerMaximum>, ValueQuery>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn mr_enclave_whitelist)]
 	pub(super) type MrEnclaveWhitelist<T: Config> = StorageValue<_, BoundedVec<[u8; 64], T::MaxWhitelist>, ValueQuery>;
 
+	/// The Intel SGX root CA that the certificate chains of DCAP quotes lead to,
+	/// DCAP quotes are rejected while it is not set.
+	#[pallet::storage]
+	#[pallet::getter(fn dcap_root_ca)]
+	pub(super) type DcapRootCa<T: Config> = StorageValue<_, DcapRootCert>;
+
+	/// The block of the last heartbeat of each worker.
+	#[pallet::storage]
+	#[pallet::getter(fn last_seen)]
+	pub(super) type LastSeen<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberFor<T>>;
+
+	/// Workers that missed too many heartbeats, with the block they went offline at.
+	#[pallet::storage]
+	#[pallet::getter(fn offline_worker)]
+	pub(super) type OfflineWorker<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberFor<T>>;
+
+	/// Blocks an attestation report stays valid for, reports never expire when unset.
+	#[pallet::storage]
+	#[pallet::getter(fn attestation_validity)]
+	pub(super) type AttestationValidity<T: Config> = StorageValue<_, BlockNumberFor<T>>;
+
+	/// Workers whose attestation report expired, with the block they were suspended at.
+	#[pallet::storage]
+	#[pallet::getter(fn suspended_worker)]
+	pub(super) type SuspendedWorker<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BlockNumberFor<T>>;
+
+	/// Tasks assigned to each worker and not finished yet, reported through `ScheduleAssign`.
+	#[pallet::storage]
+	#[pallet::getter(fn worker_load)]
+	pub(super) type WorkerLoad<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, u32, ValueQuery>;
+
+	/// Position of the next worker returned by `get_next_controller`.
+	#[pallet::storage]
+	pub(super) type RoundRobinCursor<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The nonce of the last signed message of each worker consumed on chain.
+	/// Kept after the worker exits, so that its old messages stay stale if it registers again.
+	#[pallet::storage]
+	#[pallet::getter(fn worker_nonce)]
+	pub(super) type WorkerNonce<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, u64, ValueQuery>;
+
+	/// The workers whose majority signs challenge verdicts and restoral tags in the current session.
+	#[pallet::storage]
+	#[pallet::getter(fn committee)]
+	pub(super) type Committee<T: Config> = StorageValue<_, BoundedVec<AccountOf<T>, T::CommitteeSize>, ValueQuery>;
+
+	/// The bond reserved from the controller of each registered worker.
+	/// Workers registered before bonds existed hold none.
+	#[pallet::storage]
+	#[pallet::getter(fn worker_bond)]
+	pub(super) type WorkerBond<T: Config> = StorageMap<_, Blake2_128Concat, AccountOf<T>, BalanceOf<T>, ValueQuery>;
+
+	/// Bonds of exited workers still reserved, with the block they can be withdrawn from.
+	#[pallet::storage]
+	#[pallet::getter(fn unbonding_bond)]
+	pub(super) type UnbondingBond<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, (BalanceOf<T>, BlockNumberFor<T>)>;
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+			let interval = T::HeartbeatInterval::get();
+			if interval.is_zero() || !(now % interval).is_zero() {
+				return Weight::from_ref_time(0);
+			}
+
+			Self::liveness_sweep(now).saturating_add(Self::attestation_sweep(now))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
+	}
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		// mr_enclave values allowed to register at genesis, 64 bytes each
+		pub mr_enclave_whitelist: Vec<Vec<u8>>,
+		// (stash, controller, peer_id, node_key) of the bootstrap workers
+		pub workers: Vec<(AccountOf<T>, AccountOf<T>, Vec<u8>, NodePublicKey)>,
+		// podr2 public key of the bootstrap workers, the first worker holds the master key
+		pub podr2_pbk: Vec<u8>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self {
+				mr_enclave_whitelist: Default::default(),
+				workers: Default::default(),
+				podr2_pbk: Default::default(),
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			let whitelist: Vec<[u8; 64]> = self.mr_enclave_whitelist
+				.iter()
+				.map(|mr_enclave| mr_enclave.as_slice().try_into().expect("mr_enclave must be 64 bytes"))
+				.collect();
+			let whitelist: BoundedVec<[u8; 64], T::MaxWhitelist> = whitelist
+				.try_into()
+				.expect("genesis whitelist exceeds MaxWhitelist");
+			<MrEnclaveWhitelist<T>>::put(whitelist);
+
+			if self.workers.is_empty() {
+				return;
+			}
+
+			let podr2_pk: Podr2Key = self.podr2_pbk
+				.as_slice()
+				.try_into()
+				.expect("podr2 public key must be 270 bytes");
+
+			for (stash_account, controller_account, peer_id, node_key) in self.workers.iter() {
+				let peer_id: PeerId = peer_id.as_slice().try_into().expect("peer_id must be 38 bytes");
+				let tee_worker_info = TeeWorkerInfo::<T> {
+					controller_account: controller_account.clone(),
+					peer_id,
+					node_key: node_key.clone(),
+					stash_account: stash_account.clone(),
+					podr2_pk,
+					endpoints: Default::default(),
+					attested_at: Zero::zero(),
+					roles: ROLE_ALL,
+				};
+				TeeWorkerMap::<T>::insert(controller_account, tee_worker_info);
+				<StashToController<T>>::insert(stash_account, controller_account);
+			}
+
+			<MasterPodr2Key<T>>::put(self.workers[0].1.clone());
+			Pallet::<T>::rotate_committee(0);
+		}
+	}
+
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		//Scheduling registration method
@@ -140,8 +389,10 @@ pub mod pallet {
 			peer_id: PeerId,
 			podr2_pbk: Podr2Key,
 			sgx_attestation_report: SgxAttestationReport,
+			roles: WorkerRole,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			ensure!(roles != 0 && roles & !ROLE_ALL == 0, Error::<T>::InvalidRoles);
 			//Even if the primary key is not present here, panic will not be caused
 			let acc = <pallet_cess_staking::Pallet<T>>::bonded(&stash_account)
 				.ok_or(Error::<T>::NotBond)?;
@@ -149,25 +400,37 @@ pub mod pallet {
 				Err(Error::<T>::NotController)?;
 			}
 			ensure!(!TeeWorkerMap::<T>::contains_key(&sender), Error::<T>::AlreadyRegistration);
+			ensure!(peer::peer_id_from_node_key(&node_key) == peer_id, Error::<T>::PeerIdMismatch);
 
-			let _ = verify_miner_cert(
-				&sgx_attestation_report.sign, 
-				&sgx_attestation_report.cert_der, 
-				&sgx_attestation_report.report_json_raw,
-			).ok_or(Error::<T>::VerifyCertFailed)?;
+			Self::check_attestation(&sgx_attestation_report)?;
+
+			let bond = T::TeeWorkerBond::get();
+			<T as pallet::Config>::Currency::reserve(&sender, bond)?;
+			<WorkerBond<T>>::insert(&sender, bond);
 
 			let tee_worker_info = TeeWorkerInfo::<T> {
 				controller_account: sender.clone(),
 				peer_id: peer_id.clone(),
 				node_key,
-				stash_account: stash_account,
+				stash_account: stash_account.clone(),
+				podr2_pk: podr2_pbk,
+				endpoints: Default::default(),
+				attested_at: <frame_system::Pallet<T>>::block_number(),
+				roles,
 			};
 
-			if TeeWorkerMap::<T>::count() == 0 {
-				<TeePodr2Pk<T>>::put(podr2_pbk);
-			}
-
 			TeeWorkerMap::<T>::insert(&sender, tee_worker_info);
+			<StashToController<T>>::insert(&stash_account, &sender);
+			<LastSeen<T>>::insert(&sender, <frame_system::Pallet<T>>::block_number());
+
+			if !<MasterPodr2Key<T>>::exists() {
+				<MasterPodr2Key<T>>::put(&sender);
+				Self::deposit_event(Event::<T>::MasterPodr2KeyChanged { acc: sender.clone() });
+			}
+			// Until the next rotation, new workers fill the committee while it has room.
+			<Committee<T>>::mutate(|committee| {
+				let _ = committee.try_push(sender.clone());
+			});
 
 			Self::deposit_event(Event::<T>::RegistrationTeeWorker { acc: sender, peer_id: peer_id });
 
@@ -208,26 +471,298 @@ pub mod pallet {
         pub fn update_whitelist(origin: OriginFor<T>, mr_enclave: [u8; 64]) -> DispatchResult {
 			let _ = ensure_root(origin)?;
 			<MrEnclaveWhitelist<T>>::mutate(|list| -> DispatchResult {
-                list.try_push(mr_enclave).unwrap();
+				ensure!(!list.contains(&mr_enclave), Error::<T>::MrEnclaveExisted);
+                list.try_push(mr_enclave).map_err(|_| Error::<T>::StorageLimitReached)?;
                 Ok(())
             })?;
 
+			Self::deposit_event(Event::<T>::WhitelistAdded { mr_enclave });
+
 			Ok(())
 		}
 
+		/// Remove an mr_enclave from the whitelist.
+		///
+		/// Registered workers running the enclave stay registered,
+		/// their next attestation report is rejected.
+		///
+		/// The dispatch origin of this call must be _Root_.
+		///
+		/// Parameters:
+		/// - `mr_enclave`: The hex encoded mr_enclave to remove.
+		#[pallet::call_index(13)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn remove_whitelist(origin: OriginFor<T>, mr_enclave: [u8; 64]) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+			<MrEnclaveWhitelist<T>>::try_mutate(|list| -> DispatchResult {
+				let index = list.iter().position(|v| v == &mr_enclave).ok_or(Error::<T>::NotInWhitelist)?;
+				list.remove(index);
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::WhitelistRemoved { mr_enclave });
+
+			Ok(())
+		}
+
+		/// Set the root certificate of DCAP quotes.
+		///
+		/// The dispatch origin of this call must be _Root_.
+		///
+		/// Parameters:
+		/// - `root_cert`: The DER encoded Intel SGX root CA, `None` disables DCAP quotes.
+		#[pallet::call_index(14)]
+		#[transactional]
+		#[pallet::weight(100_000_000)]
+		pub fn set_dcap_root_cert(origin: OriginFor<T>, root_cert: Option<DcapRootCert>) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+
+			let enabled = root_cert.is_some();
+			match root_cert {
+				Some(root_cert) => <DcapRootCa<T>>::put(root_cert),
+				None => <DcapRootCa<T>>::kill(),
+			}
+
+			Self::deposit_event(Event::<T>::DcapRootCertSet { enabled });
+
+			Ok(())
+		}
+
+		/// Exit and deregister a tee worker.
+		///
+		/// The deals and verification missions assigned to the worker are handed
+		/// to the remaining workers by the pallets in `OnExit`.
+		/// The bond of the worker can be withdrawn after `BondUnbondingPeriod`.
+		///
+		/// This is the exit flow of a scheduler. The call keeps the name `exit` and index 4
+		/// it always had, so that workers and clients calling it keep working.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the controller of the worker.
 		#[pallet::call_index(4)]
         #[transactional]
-		#[pallet::weight(100_000_000)]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::exit())]
 		pub fn exit(origin: OriginFor<T>) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			let tee_worker = TeeWorkerMap::<T>::try_get(&sender).map_err(|_| Error::<T>::NonTeeWorker)?;
+
+			Self::remove_worker(&sender, &tee_worker.stash_account)?;
+
+			Self::deposit_event(Event::<T>::TeeWorkerExit { acc: sender });
+
+			Ok(())
+		}
+
+		/// Release the bond of an exited worker once its unbonding period passed.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the former controller of the worker.
+		#[pallet::call_index(16)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::withdraw_bond())]
+		pub fn withdraw_bond(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let (amount, release_at) = <UnbondingBond<T>>::get(&sender).ok_or(Error::<T>::NoUnbondingBond)?;
+			ensure!(
+				release_at <= <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::BondStillUnbonding
+			);
+
+			<UnbondingBond<T>>::remove(&sender);
+			<T as pallet::Config>::Currency::unreserve(&sender, amount);
+
+			Self::deposit_event(Event::<T>::BondWithdrawn { acc: sender, amount });
+
+			Ok(())
+		}
+
+		/// Replace the podr2 public key of a worker.
+		///
+		/// The new key must come with a fresh attestation report of the enclave that generated it.
+		/// If the worker holds the master key, the master key changes with it.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the controller of the worker.
+		///
+		/// Parameters:
+		/// - `podr2_pbk`: The new podr2 public key.
+		/// - `sgx_attestation_report`: The attestation report of the enclave.
+		#[pallet::call_index(7)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::update_podr2_pk())]
+		pub fn update_podr2_pk(
+			origin: OriginFor<T>,
+			podr2_pbk: Podr2Key,
+			sgx_attestation_report: SgxAttestationReport,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::check_attestation(&sgx_attestation_report)?;
+
+			TeeWorkerMap::<T>::try_mutate(&sender, |info_opt| -> DispatchResult {
+				let info = info_opt.as_mut().ok_or(Error::<T>::NonTeeWorker)?;
+				info.podr2_pk = podr2_pbk;
+				info.attested_at = <frame_system::Pallet<T>>::block_number();
+
+				Ok(())
+			})?;
+			<SuspendedWorker<T>>::remove(&sender);
+
+			Self::deposit_event(Event::<T>::UpdatePodr2Key { acc: sender });
+
+			Ok(())
+		}
+
+		/// Replace the multiaddrs a worker is reachable at.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the controller of the worker.
+		///
+		/// Parameters:
+		/// - `endpoints`: The binary multiaddrs of the worker, an empty list removes them.
+		#[pallet::call_index(15)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::update_scheduler())]
+		pub fn update_endpoint(
+			origin: OriginFor<T>,
+			endpoints: BoundedVec<Multiaddr, T::MaxEndpoints>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(
+				endpoints.iter().all(|endpoint| peer::is_valid_multiaddr(endpoint)),
+				Error::<T>::InvalidMultiaddr
+			);
+
+			TeeWorkerMap::<T>::try_mutate(&sender, |info_opt| -> DispatchResult {
+				let info = info_opt.as_mut().ok_or(Error::<T>::NonTeeWorker)?;
+				info.endpoints = endpoints;
+
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::<T>::UpdateScheduler { acc: sender });
+
+			Ok(())
+		}
+
+		/// Vote for the podr2 key of another worker to become the master key.
+		///
+		/// The candidate is elected once more than half of the registered workers voted for it.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the controller of a worker.
+		///
+		/// Parameters:
+		/// - `candidate`: The controller of the worker whose key is proposed.
+		#[pallet::call_index(8)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::vote_master_podr2_key())]
+		pub fn vote_master_podr2_key(origin: OriginFor<T>, candidate: AccountOf<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(TeeWorkerMap::<T>::contains_key(&sender), Error::<T>::NonTeeWorker);
+			ensure!(TeeWorkerMap::<T>::contains_key(&candidate), Error::<T>::NonTeeWorker);
+			ensure!(<MasterPodr2Key<T>>::get().as_ref() != Some(&candidate), Error::<T>::AlreadyMaster);
+
+			let votes = <MasterKeyVotes<T>>::try_mutate(&candidate, |votes| -> Result<u32, DispatchError> {
+				// Votes of workers that exited in the meantime no longer count.
+				votes.retain(|acc| TeeWorkerMap::<T>::contains_key(acc));
+				ensure!(!votes.contains(&sender), Error::<T>::AlreadyVoted);
+				votes.try_push(sender.clone()).map_err(|_| Error::<T>::BoundedVecError)?;
+
+				Ok(votes.len() as u32)
+			})?;
+
+			Self::deposit_event(Event::<T>::MasterPodr2KeyVote { acc: sender, candidate: candidate.clone() });
+
+			if votes.saturating_mul(2) > TeeWorkerMap::<T>::count() {
+				Self::set_master(candidate);
+			}
+
+			Ok(())
+		}
+
+		/// Make the podr2 key of a worker the master key.
+		///
+		/// The dispatch origin of this call must be _Root_.
+		///
+		/// Parameters:
+		/// - `worker`: The controller of the worker.
+		#[pallet::call_index(9)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_master_podr2_key())]
+		pub fn set_master_podr2_key(origin: OriginFor<T>, worker: AccountOf<T>) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+			ensure!(TeeWorkerMap::<T>::contains_key(&worker), Error::<T>::NonTeeWorker);
+
+			Self::set_master(worker);
+
+			Ok(())
+		}
+
+		/// Report a worker alive.
+		///
+		/// A worker missing `OfflineThreshold` heartbeats receives no new work,
+		/// one missing `EvictionThreshold` heartbeats is slashed and removed.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the controller of the worker.
+		#[pallet::call_index(10)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::report_heartbeat())]
+		pub fn report_heartbeat(origin: OriginFor<T>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(TeeWorkerMap::<T>::contains_key(&sender), Error::<T>::NonTeeWorker);
 
-			TeeWorkerMap::<T>::remove(&sender);
+			<LastSeen<T>>::insert(&sender, <frame_system::Pallet<T>>::block_number());
+			if <OfflineWorker<T>>::take(&sender).is_some() {
+				Self::deposit_event(Event::<T>::TeeWorkerOnline { acc: sender });
+			}
 
-			if TeeWorkerMap::<T>::count() == 0 {
-				<TeePodr2Pk<T>>::kill();
+			Ok(())
+		}
+
+		/// Submit a fresh attestation report of the enclave of a worker.
+		///
+		/// A worker suspended for an expired report is restored once the new report verifies.
+		///
+		/// The dispatch origin of this call must be _Signed_ by the controller of the worker.
+		///
+		/// Parameters:
+		/// - `sgx_attestation_report`: The attestation report of the enclave.
+		#[pallet::call_index(11)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::refresh_attestation())]
+		pub fn refresh_attestation(origin: OriginFor<T>, sgx_attestation_report: SgxAttestationReport) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::check_attestation(&sgx_attestation_report)?;
+
+			TeeWorkerMap::<T>::try_mutate(&sender, |info_opt| -> DispatchResult {
+				let info = info_opt.as_mut().ok_or(Error::<T>::NonTeeWorker)?;
+				info.attested_at = <frame_system::Pallet<T>>::block_number();
+
+				Ok(())
+			})?;
+			<SuspendedWorker<T>>::remove(&sender);
+
+			Self::deposit_event(Event::<T>::AttestationRefreshed { acc: sender });
+
+			Ok(())
+		}
+
+		/// Set the number of blocks an attestation report stays valid for.
+		///
+		/// The dispatch origin of this call must be _Root_.
+		///
+		/// Parameters:
+		/// - `validity`: The validity window, `None` lets reports never expire.
+		#[pallet::call_index(12)]
+		#[transactional]
+		#[pallet::weight(<T as pallet::Config>::WeightInfo::set_attestation_validity())]
+		pub fn set_attestation_validity(origin: OriginFor<T>, validity: Option<BlockNumberFor<T>>) -> DispatchResult {
+			let _ = ensure_root(origin)?;
+
+			match validity {
+				Some(blocks) => <AttestationValidity<T>>::put(blocks),
+				None => <AttestationValidity<T>>::kill(),
 			}
 
-			Self::deposit_event(Event::<T>::Exit { acc: sender });
+			Self::deposit_event(Event::<T>::AttestationValiditySet { validity });
 
 			Ok(())
 		}
@@ -270,11 +805,303 @@ pub mod pallet {
 	}
 }
 
+impl<T: Config> Pallet<T> {
+	/// The podr2 public key shared by the workers.
+	pub fn tee_podr2_pk() -> Option<Podr2Key> {
+		let master = <MasterPodr2Key<T>>::get()?;
+		TeeWorkerMap::<T>::get(&master).map(|info| info.podr2_pk)
+	}
+
+	/// Select the committee of a new session.
+	///
+	/// The available workers take turns, each session the committee moves on
+	/// by `CommitteeSize` workers in the order of their accounts.
+	pub fn rotate_committee(session: u32) -> Weight {
+		let mut workers = Self::get_controller_list();
+		let size = T::CommitteeSize::get() as usize;
+		let mut weight = T::DbWeight::get().reads_writes(workers.len() as u64 * 3, 1);
+		if workers.is_empty() || size == 0 {
+			<Committee<T>>::kill();
+			return weight;
+		}
+		workers.sort();
+
+		let offset = (session as usize).saturating_mul(size) % workers.len();
+		let members: Vec<AccountOf<T>> = workers
+			.iter()
+			.cycle()
+			.skip(offset)
+			.take(size.min(workers.len()))
+			.cloned()
+			.collect();
+		<Committee<T>>::put(BoundedVec::truncate_from(members.clone()));
+		weight = weight.saturating_add(T::DbWeight::get().writes(1));
+
+		Self::deposit_event(Event::<T>::CommitteeRotated { session, members });
+
+		weight
+	}
+
+	// Removes a worker and hands its work to the remaining workers.
+	fn remove_worker(acc: &AccountOf<T>, stash_account: &AccountOf<T>) -> DispatchResult {
+		TeeWorkerMap::<T>::remove(acc);
+		<StashToController<T>>::remove(stash_account);
+		<Committee<T>>::mutate(|committee| committee.retain(|member| member != acc));
+		<MasterKeyVotes<T>>::remove(acc);
+		<LastSeen<T>>::remove(acc);
+		<OfflineWorker<T>>::remove(acc);
+		<SuspendedWorker<T>>::remove(acc);
+		<WorkerLoad<T>>::remove(acc);
+		<BondAcc<T>>::mutate(|list| {
+			list.retain(|bond| bond != acc && bond != stash_account);
+		});
+		Self::start_unbonding(acc);
+
+		if <MasterPodr2Key<T>>::get().as_ref() == Some(acc) {
+			Self::rotate_master_podr2_key();
+		}
+
+		// The worker is already gone from the list, so its work goes to the others.
+		T::OnExit::on_scheduler_exit(acc)
+	}
+
+	// Moves what is left of the bond of a removed worker into unbonding.
+	// A worker registering again before withdrawing extends the unbonding of its old bond.
+	fn start_unbonding(acc: &AccountOf<T>) {
+		let bond = <WorkerBond<T>>::take(acc);
+		if bond.is_zero() {
+			return;
+		}
+		let release_at = <frame_system::Pallet<T>>::block_number().saturating_add(T::BondUnbondingPeriod::get());
+		<UnbondingBond<T>>::mutate(acc, |unbonding| {
+			let amount = unbonding.map(|(amount, _)| amount).unwrap_or_default();
+			*unbonding = Some((amount.saturating_add(bond), release_at));
+		});
+	}
+
+	// Slashes `BondSlashFraction` of the bond of a worker.
+	// A worker without a bond, such as one that exited, is slashed from its unbonding bond.
+	fn slash_bond(acc: &AccountOf<T>) {
+		let bond = <WorkerBond<T>>::get(acc);
+		let held = if !bond.is_zero() {
+			bond
+		} else {
+			match <UnbondingBond<T>>::get(acc) {
+				Some((amount, _)) => amount,
+				None => return,
+			}
+		};
+		let slash = T::BondSlashFraction::get() * held;
+		if slash.is_zero() {
+			return;
+		}
+		let (imbalance, missing) = <T as pallet::Config>::Currency::slash_reserved(acc, slash);
+		let amount = slash.saturating_sub(missing);
+		if !bond.is_zero() {
+			<WorkerBond<T>>::insert(acc, bond.saturating_sub(amount));
+		} else {
+			<UnbondingBond<T>>::mutate_exists(acc, |unbonding| {
+				if let Some((held, release_at)) = *unbonding {
+					let rest = held.saturating_sub(amount);
+					*unbonding = if rest.is_zero() { None } else { Some((rest, release_at)) };
+				}
+			});
+		}
+		T::BondSlash::on_unbalanced(imbalance);
+
+		Self::deposit_event(Event::<T>::BondSlashed { acc: acc.clone(), amount });
+	}
+
+	// Marks the workers that missed `OfflineThreshold` heartbeats offline,
+	// and slashes and evicts the ones that missed `EvictionThreshold`.
+	fn liveness_sweep(now: BlockNumberFor<T>) -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+		let interval = T::HeartbeatInterval::get();
+		let offline_after = interval.saturating_mul(T::OfflineThreshold::get().into());
+		let evict_after = interval.saturating_mul(T::EvictionThreshold::get().into());
+
+		let workers: Vec<(AccountOf<T>, AccountOf<T>)> = TeeWorkerMap::<T>::iter()
+			.map(|(acc, info)| (acc, info.stash_account))
+			.collect();
+		weight = weight.saturating_add(T::DbWeight::get().reads(workers.len() as u64));
+
+		for (acc, stash_account) in workers {
+			weight = weight.saturating_add(T::DbWeight::get().reads(2));
+			let last_seen = match <LastSeen<T>>::get(&acc) {
+				Some(block) => block,
+				// Workers registered before heartbeats existed start counting now.
+				None => {
+					<LastSeen<T>>::insert(&acc, now);
+					weight = weight.saturating_add(T::DbWeight::get().writes(1));
+					continue;
+				},
+			};
+			let silent = now.saturating_sub(last_seen);
+
+			if silent >= evict_after {
+				if let Err(e) = <Self as ScheduleFind<AccountOf<T>>>::punish_scheduler(acc.clone()) {
+					log::error!("punish evicted tee worker failed: {:?}", e);
+				}
+				if let Err(e) = Self::remove_worker(&acc, &stash_account) {
+					log::error!("evict tee worker failed: {:?}, acc: {:?}", e, acc);
+				}
+				weight = weight.saturating_add(T::DbWeight::get().reads_writes(6, 8));
+				Self::deposit_event(Event::<T>::TeeWorkerEvicted { acc });
+			} else if silent >= offline_after && !<OfflineWorker<T>>::contains_key(&acc) {
+				<OfflineWorker<T>>::insert(&acc, now);
+				weight = weight.saturating_add(T::DbWeight::get().writes(1));
+				Self::deposit_event(Event::<T>::TeeWorkerOffline { acc });
+			}
+		}
+
+		weight
+	}
+
+	// Verifies the signature of an attestation report and that its enclave is whitelisted.
+	fn check_attestation(sgx_attestation_report: &SgxAttestationReport) -> DispatchResult {
+		let mr_enclave = match sgx_attestation_report {
+			SgxAttestationReport::Epid { report_json_raw, sign, cert_der } => {
+				let _ = verify_miner_cert(sign, cert_der, report_json_raw).ok_or(Error::<T>::VerifyCertFailed)?;
+				report_mr_enclave(report_json_raw)
+			},
+			SgxAttestationReport::Dcap { quote, collateral } => {
+				let root_cert = <DcapRootCa<T>>::get().ok_or(Error::<T>::DcapDisabled)?;
+				let now = T::UnixTime::now().as_secs();
+				verify_dcap_quote(quote, collateral, &root_cert, now).ok_or(Error::<T>::VerifyCertFailed)?;
+				quote_mr_enclave(quote)
+			},
+		}.ok_or(Error::<T>::VerifyCertFailed)?;
+		ensure!(<MrEnclaveWhitelist<T>>::get().contains(&mr_enclave), Error::<T>::NotInWhitelist);
+
+		Ok(())
+	}
+
+	// Suspends the workers whose attestation report is older than `AttestationValidity`.
+	fn attestation_sweep(now: BlockNumberFor<T>) -> Weight {
+		let mut weight: Weight = T::DbWeight::get().reads(1);
+		let validity = match <AttestationValidity<T>>::get() {
+			Some(validity) => validity,
+			None => return weight,
+		};
+
+		for (acc, info) in TeeWorkerMap::<T>::iter() {
+			weight = weight.saturating_add(T::DbWeight::get().reads(2));
+			if info.attested_at.saturating_add(validity) <= now && !<SuspendedWorker<T>>::contains_key(&acc) {
+				<SuspendedWorker<T>>::insert(&acc, now);
+				weight = weight.saturating_add(T::DbWeight::get().writes(1));
+				Self::deposit_event(Event::<T>::TeeWorkerSuspended { acc });
+			}
+		}
+
+		weight
+	}
+
+	// Offline and suspended workers receive no new work.
+	fn is_available(acc: &AccountOf<T>) -> bool {
+		!<OfflineWorker<T>>::contains_key(acc) && !<SuspendedWorker<T>>::contains_key(acc)
+	}
+
+	fn has_role(info: &TeeWorkerInfo<T>, role: WorkerRole) -> bool {
+		info.roles & role == role
+	}
+
+	fn set_master(worker: AccountOf<T>) {
+		let _ = <MasterKeyVotes<T>>::clear(T::SchedulerMaximum::get(), None);
+		<MasterPodr2Key<T>>::put(&worker);
+		Self::deposit_event(Event::<T>::MasterPodr2KeyChanged { acc: worker });
+	}
+
+	// Hands the master key over to a remaining worker after the holder left.
+	fn rotate_master_podr2_key() {
+		match TeeWorkerMap::<T>::iter_keys().next() {
+			Some(worker) => Self::set_master(worker),
+			None => {
+				<MasterPodr2Key<T>>::kill();
+				let _ = <MasterKeyVotes<T>>::clear(T::SchedulerMaximum::get(), None);
+			},
+		}
+	}
+}
+
+#[cfg(any(test, feature = "try-runtime", feature = "consistency-checks"))]
+impl<T: Config> Pallet<T> {
+	/// Checks that only registered workers carry a load or a bond.
+	pub fn do_try_state() -> Result<(), &'static str> {
+		for acc in WorkerLoad::<T>::iter_keys() {
+			ensure!(TeeWorkerMap::<T>::contains_key(&acc), "WorkerLoad of an unregistered worker");
+		}
+		for acc in WorkerBond::<T>::iter_keys() {
+			ensure!(TeeWorkerMap::<T>::contains_key(&acc), "WorkerBond of an unregistered worker");
+		}
+
+		Ok(())
+	}
+}
+
+/// Hands the work of an exiting tee worker to the remaining workers.
+pub trait ScheduleExit<AccountId> {
+	fn on_scheduler_exit(acc: &AccountId) -> DispatchResult;
+}
+
+impl<AccountId> ScheduleExit<AccountId> for () {
+	fn on_scheduler_exit(_acc: &AccountId) -> DispatchResult {
+		Ok(())
+	}
+}
+
+impl<AccountId, A, B> ScheduleExit<AccountId> for (A, B)
+where
+	A: ScheduleExit<AccountId>,
+	B: ScheduleExit<AccountId>,
+{
+	fn on_scheduler_exit(acc: &AccountId) -> DispatchResult {
+		A::on_scheduler_exit(acc)?;
+		B::on_scheduler_exit(acc)
+	}
+}
+
 pub trait ScheduleFind<AccountId> {
 	fn contains_scheduler(acc: AccountId) -> bool;
 	fn punish_scheduler(acc: AccountId) -> DispatchResult;
 	fn get_first_controller() -> Result<AccountId, DispatchError>;
+	// The controller of the worker registered for the stash account.
+	fn get_controller_acc(stash: &AccountId) -> Option<AccountId>;
 	fn get_controller_list() -> Vec<AccountId>;
+	fn get_peer_id(acc: &AccountId) -> Result<PeerId, DispatchError>;
+	// The key the worker signs its reports with.
+	fn get_node_key(acc: &AccountId) -> Result<NodePublicKey, DispatchError>;
+	// Whether more than half of the current committee signed the message with their node keys.
+	fn is_committee_majority(message: &[u8], signatures: &CommitteeSignatures<AccountId>) -> bool;
+	// Consumes the nonce of a signed message of the worker, it must be above the last one consumed.
+	fn consume_nonce(acc: &AccountId, nonce: u64) -> DispatchResult;
+	// The available worker with the fewest unfinished tasks.
+	fn get_least_loaded_controller() -> Result<AccountId, DispatchError>;
+	// The available workers in turn.
+	fn get_next_controller() -> Result<AccountId, DispatchError>;
+	// The available workers that declared all the bits of `role`.
+	fn get_role_controller_list(role: WorkerRole) -> Vec<AccountId>;
+	// The available worker with the fewest unfinished tasks among those that declared `role`.
+	fn get_least_loaded_role_controller(role: WorkerRole) -> Result<AccountId, DispatchError>;
+}
+
+/// Tracks the unfinished tasks of each tee worker, fed by the pallets handing out work.
+pub trait ScheduleAssign<AccountId> {
+	fn task_assigned(acc: &AccountId, count: u32);
+	fn task_finished(acc: &AccountId, count: u32);
+}
+
+impl<T: Config> ScheduleAssign<<T as frame_system::Config>::AccountId> for Pallet<T> {
+	fn task_assigned(acc: &AccountOf<T>, count: u32) {
+		if TeeWorkerMap::<T>::contains_key(acc) {
+			<WorkerLoad<T>>::mutate(acc, |load| *load = load.saturating_add(count));
+		}
+	}
+
+	fn task_finished(acc: &AccountOf<T>, count: u32) {
+		<WorkerLoad<T>>::mutate_exists(acc, |load_opt| {
+			*load_opt = load_opt.map(|load| load.saturating_sub(count)).filter(|load| !load.is_zero());
+		});
+	}
 }
 
 impl<T: Config> ScheduleFind<<T as frame_system::Config>::AccountId> for Pallet<T> {
@@ -283,25 +1110,119 @@ impl<T: Config> ScheduleFind<<T as frame_system::Config>::AccountId> for Pallet<
 	}
 
 	fn punish_scheduler(acc: <T as frame_system::Config>::AccountId) -> DispatchResult {
-		let tee_worker = TeeWorkerMap::<T>::try_get(&acc).map_err(|_| Error::<T>::NonTeeWorker)?;
+		let tee_worker = match TeeWorkerMap::<T>::try_get(&acc) {
+			Ok(tee_worker) => tee_worker,
+			// An exited worker stays liable for its work with the bond it is unbonding.
+			Err(_) if <UnbondingBond<T>>::contains_key(&acc) => {
+				Self::slash_bond(&acc);
+				return Ok(());
+			},
+			Err(_) => Err(Error::<T>::NonTeeWorker)?,
+		};
 		pallet_cess_staking::slashing::slash_scheduler::<T>(&tee_worker.stash_account);
+		Self::slash_bond(&acc);
 		T::CreditCounter::record_punishment(&tee_worker.stash_account)?;
 
 		Ok(())
 	}
 
 	fn get_first_controller() -> Result<<T as frame_system::Config>::AccountId, DispatchError> {
-		let (controller_acc, _) = TeeWorkerMap::<T>::iter().next().ok_or(Error::<T>::NonTeeWorker)?;
+		let controller_acc = TeeWorkerMap::<T>::iter_keys()
+			.find(|acc| Self::is_available(acc))
+			.ok_or(Error::<T>::NonTeeWorker)?;
 		return Ok(controller_acc);
 	}
 
+	fn get_controller_acc(stash: &AccountOf<T>) -> Option<AccountOf<T>> {
+		<StashToController<T>>::get(stash)
+	}
+
 	fn get_controller_list() -> Vec<AccountOf<T>> {
 		let mut acc_list: Vec<AccountOf<T>> = Default::default();
 
 		for (acc, _) in <TeeWorkerMap<T>>::iter() {
+			if !Self::is_available(&acc) {
+				continue;
+			}
 			acc_list.push(acc);
 		}
 
 		acc_list
 	}
+
+	fn get_peer_id(acc: &AccountOf<T>) -> Result<PeerId, DispatchError> {
+		let tee_worker = TeeWorkerMap::<T>::try_get(acc).map_err(|_| Error::<T>::NonTeeWorker)?;
+
+		Ok(tee_worker.peer_id)
+	}
+
+	fn get_node_key(acc: &AccountOf<T>) -> Result<NodePublicKey, DispatchError> {
+		let tee_worker = TeeWorkerMap::<T>::try_get(acc).map_err(|_| Error::<T>::NonTeeWorker)?;
+
+		Ok(tee_worker.node_key)
+	}
+
+	fn is_committee_majority(message: &[u8], signatures: &CommitteeSignatures<AccountOf<T>>) -> bool {
+		let committee = <Committee<T>>::get();
+		let mut signers: Vec<&AccountOf<T>> = Vec::new();
+
+		for (signer, signature) in signatures.iter() {
+			if signers.contains(&signer) || !committee.contains(signer) {
+				continue;
+			}
+			let node_key = match TeeWorkerMap::<T>::get(signer) {
+				Some(info) => info.node_key,
+				None => continue,
+			};
+			let signature = sp_core::ed25519::Signature::from_raw(*signature);
+			if sp_io::crypto::ed25519_verify(&signature, message, &node_key) {
+				signers.push(signer);
+			}
+		}
+
+		!committee.is_empty() && signers.len() * 2 > committee.len()
+	}
+
+	fn consume_nonce(acc: &AccountOf<T>, nonce: u64) -> DispatchResult {
+		<WorkerNonce<T>>::try_mutate(acc, |last| -> DispatchResult {
+			ensure!(nonce > *last, Error::<T>::StaleNonce);
+			*last = nonce;
+			Ok(())
+		})
+	}
+
+	fn get_least_loaded_controller() -> Result<AccountOf<T>, DispatchError> {
+		let controller_acc = Self::get_controller_list()
+			.into_iter()
+			.min_by_key(|acc| <WorkerLoad<T>>::get(acc))
+			.ok_or(Error::<T>::NonTeeWorker)?;
+
+		Ok(controller_acc)
+	}
+
+	fn get_next_controller() -> Result<AccountOf<T>, DispatchError> {
+		let acc_list = Self::get_controller_list();
+		ensure!(!acc_list.is_empty(), Error::<T>::NonTeeWorker);
+
+		let cursor = <RoundRobinCursor<T>>::get();
+		<RoundRobinCursor<T>>::put(cursor.wrapping_add(1));
+
+		Ok(acc_list[cursor as usize % acc_list.len()].clone())
+	}
+
+	fn get_role_controller_list(role: WorkerRole) -> Vec<AccountOf<T>> {
+		<TeeWorkerMap<T>>::iter()
+			.filter(|(acc, info)| Self::has_role(info, role) && Self::is_available(acc))
+			.map(|(acc, _)| acc)
+			.collect()
+	}
+
+	fn get_least_loaded_role_controller(role: WorkerRole) -> Result<AccountOf<T>, DispatchError> {
+		let controller_acc = Self::get_role_controller_list(role)
+			.into_iter()
+			.min_by_key(|acc| <WorkerLoad<T>>::get(acc))
+			.ok_or(Error::<T>::NonTeeWorker)?;
+
+		Ok(controller_acc)
+	}
 }
@@ -0,0 +1,227 @@
+use crate::*;
+use frame_support::{storage_alias, traits::OnRuntimeUpgrade};
+
+/// Move the global podr2 public key into the info of every registered worker,
+/// record the attestation block of every registered worker, give every
+/// registered worker an empty endpoint list, index the workers by stash account
+/// and give every registered worker all the roles.
+pub struct MigrationPodr2Key<T: crate::Config>(sp_std::marker::PhantomData<T>);
+impl<T: crate::Config> OnRuntimeUpgrade for MigrationPodr2Key<T> {
+	fn on_runtime_upgrade() -> Weight {
+		log::info!("TeeWorker migrate start!");
+		migrate::<T>()
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+		log::info!("tee-worker check access");
+		return Ok(Default::default())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+		ensure!(StorageVersion::get::<Pallet<T>>() == 5, "tee-worker not migrated");
+		return Ok(())
+	}
+}
+
+pub fn migrate<T: Config>() -> Weight {
+	let version = StorageVersion::get::<Pallet<T>>();
+	let mut weight: Weight = Weight::from_ref_time(0);
+
+	if version < 1 {
+		log::info!("TeeWorker version 0 -> 1 migrations start!");
+		weight = weight.saturating_add(v1::migrate::<T>());
+		// v1 already writes the latest layout of `TeeWorkerInfo`.
+		StorageVersion::new(3).put::<Pallet<T>>();
+	} else if version < 2 {
+		log::info!("TeeWorker version 1 -> 2 migrations start!");
+		// v2 already writes the latest layout of `TeeWorkerInfo`.
+		weight = weight.saturating_add(v2::migrate::<T>());
+		StorageVersion::new(3).put::<Pallet<T>>();
+	} else if version < 3 {
+		log::info!("TeeWorker version 2 -> 3 migrations start!");
+		weight = weight.saturating_add(v3::migrate::<T>());
+		StorageVersion::new(3).put::<Pallet<T>>();
+	}
+
+	if version < 4 {
+		log::info!("TeeWorker version 3 -> 4 migrations start!");
+		weight = weight.saturating_add(v4::migrate::<T>());
+		StorageVersion::new(4).put::<Pallet<T>>();
+	}
+
+	if version < 5 {
+		log::info!("TeeWorker version 4 -> 5 migrations start!");
+		weight = weight.saturating_add(v5::migrate::<T>());
+		StorageVersion::new(5).put::<Pallet<T>>();
+	}
+
+	weight
+}
+
+mod v1 {
+	use super::*;
+
+	#[derive(Decode)]
+	struct OldTeeWorkerInfo<AccountId> {
+		controller_account: AccountId,
+		peer_id: PeerId,
+		node_key: NodePublicKey,
+		stash_account: AccountId,
+	}
+
+	#[storage_alias]
+	type TeePodr2Pk<T: Config> = StorageValue<Pallet<T>, Podr2Key>;
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		let podr2_pk = TeePodr2Pk::<T>::take().unwrap_or([0u8; 270]);
+		weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+
+		TeeWorkerMap::<T>::translate::<OldTeeWorkerInfo<AccountOf<T>>, _>(|_acc, old| {
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			Some(TeeWorkerInfo::<T> {
+				controller_account: old.controller_account,
+				peer_id: old.peer_id,
+				node_key: old.node_key,
+				stash_account: old.stash_account,
+				podr2_pk,
+				endpoints: Default::default(),
+				attested_at: <frame_system::Pallet<T>>::block_number(),
+				roles: ROLE_ALL,
+			})
+		});
+
+		// Every worker shared the first registrant's key so far, any of them can hold it.
+		if let Some(worker) = TeeWorkerMap::<T>::iter_keys().next() {
+			<MasterPodr2Key<T>>::put(worker);
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+		}
+
+		weight
+	}
+}
+
+mod v2 {
+	use super::*;
+
+	#[derive(Decode)]
+	struct OldTeeWorkerInfo<AccountId> {
+		controller_account: AccountId,
+		peer_id: PeerId,
+		node_key: NodePublicKey,
+		stash_account: AccountId,
+		podr2_pk: Podr2Key,
+	}
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		// The reports of the registered workers were verified at some earlier block,
+		// they get a full validity window from the upgrade on.
+		let now = <frame_system::Pallet<T>>::block_number();
+		TeeWorkerMap::<T>::translate::<OldTeeWorkerInfo<AccountOf<T>>, _>(|_acc, old| {
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			Some(TeeWorkerInfo::<T> {
+				controller_account: old.controller_account,
+				peer_id: old.peer_id,
+				node_key: old.node_key,
+				stash_account: old.stash_account,
+				podr2_pk: old.podr2_pk,
+				endpoints: Default::default(),
+				attested_at: now,
+				roles: ROLE_ALL,
+			})
+		});
+
+		weight
+	}
+}
+
+mod v3 {
+	use super::*;
+
+	#[derive(Decode)]
+	struct OldTeeWorkerInfo<AccountId, BlockNumber> {
+		controller_account: AccountId,
+		peer_id: PeerId,
+		node_key: NodePublicKey,
+		stash_account: AccountId,
+		podr2_pk: Podr2Key,
+		attested_at: BlockNumber,
+	}
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		// Workers announce their endpoints with `update_endpoint` after the upgrade.
+		TeeWorkerMap::<T>::translate::<OldTeeWorkerInfo<AccountOf<T>, BlockNumberFor<T>>, _>(|_acc, old| {
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			Some(TeeWorkerInfo::<T> {
+				controller_account: old.controller_account,
+				peer_id: old.peer_id,
+				node_key: old.node_key,
+				stash_account: old.stash_account,
+				podr2_pk: old.podr2_pk,
+				endpoints: Default::default(),
+				attested_at: old.attested_at,
+				roles: ROLE_ALL,
+			})
+		});
+
+		weight
+	}
+}
+
+mod v4 {
+	use super::*;
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		for (controller, info) in TeeWorkerMap::<T>::iter() {
+			<StashToController<T>>::insert(&info.stash_account, controller);
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+		}
+
+		weight
+	}
+}
+
+mod v5 {
+	use super::*;
+
+	#[derive(Decode)]
+	struct OldTeeWorkerInfo<T: Config> {
+		controller_account: AccountOf<T>,
+		peer_id: PeerId,
+		node_key: NodePublicKey,
+		stash_account: AccountOf<T>,
+		podr2_pk: Podr2Key,
+		endpoints: BoundedVec<Multiaddr, T::MaxEndpoints>,
+		attested_at: BlockNumberFor<T>,
+	}
+
+	pub fn migrate<T: Config>() -> Weight {
+		let mut weight: Weight = Weight::from_ref_time(0);
+
+		// The registered workers took every task so far.
+		TeeWorkerMap::<T>::translate::<OldTeeWorkerInfo<T>, _>(|_acc, old| {
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			Some(TeeWorkerInfo::<T> {
+				controller_account: old.controller_account,
+				peer_id: old.peer_id,
+				node_key: old.node_key,
+				stash_account: old.stash_account,
+				podr2_pk: old.podr2_pk,
+				endpoints: old.endpoints,
+				attested_at: old.attested_at,
+				roles: ROLE_ALL,
+			})
+		});
+
+		weight
+	}
+}
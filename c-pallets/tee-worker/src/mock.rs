@@ -0,0 +1,306 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test utilities
+
+use super::*;
+use crate as pallet_tee_worker;
+use frame_support::{
+    parameter_types,
+    traits::{ConstU32, ConstU64, OneSessionHandler},
+};
+use sp_core::H256;
+use sp_runtime::{
+    testing::{Header, UintAuthorityId},
+    traits::{BlakeTwo256, IdentityLookup},
+    Perbill,
+};
+use pallet_cess_staking::{StashOf, Exposure, ExposureOf};
+use frame_election_provider_support::{
+    onchain, SequentialPhragmen, VoteWeight,
+};
+use sp_staking::{
+    EraIndex, SessionIndex,
+};
+use cp_scheduler_credit::SchedulerStashAccountFinder;
+
+pub(crate) type AccountId = u64;
+type BlockNumber = u64;
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+type Balance = u64;
+
+pub(crate) const CONTROLLER: AccountId = 1;
+pub(crate) const STASH: AccountId = 2;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		Timestamp: pallet_timestamp,
+		Staking: pallet_cess_staking,
+		Session: pallet_session,
+		Historical: pallet_session::historical,
+		BagsList: pallet_bags_list,
+		SchedulerCredit: pallet_scheduler_credit,
+		TeeWorker: pallet_tee_worker,
+	}
+);
+
+parameter_types! {
+	pub const TeeWorkerPalletId: PalletId = PalletId(*b"filmpdpt");
+	#[derive(Clone, PartialEq, Eq)]
+	pub const StringLimit: u32 = 100;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const SchedulerMaximum: u32 = 10000;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const ParamsLimit: u32 = 359;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const MaxWhitelist: u32 = 200;
+	pub const HeartbeatInterval: BlockNumber = 10;
+	pub const OfflineThreshold: u32 = 3;
+	pub const EvictionThreshold: u32 = 24;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const MaxEndpoints: u32 = 8;
+	pub const CommitteeSize: u32 = 5;
+	pub const TeeWorkerBond: Balance = 1_000;
+	pub const BondSlashFraction: Perbill = Perbill::from_percent(10);
+	pub const BondUnbondingPeriod: BlockNumber = 100;
+}
+
+impl Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type TeeWorkerPalletId = TeeWorkerPalletId;
+	type StringLimit = StringLimit;
+	type SchedulerMaximum = SchedulerMaximum;
+	type WeightInfo = ();
+	type CreditCounter = SchedulerCredit;
+	type ParamsLimit = ParamsLimit;
+	type MaxWhitelist = MaxWhitelist;
+	type OnExit = ();
+	type HeartbeatInterval = HeartbeatInterval;
+	type OfflineThreshold = OfflineThreshold;
+	type EvictionThreshold = EvictionThreshold;
+	type MaxEndpoints = MaxEndpoints;
+	type CommitteeSize = CommitteeSize;
+	type TeeWorkerBond = TeeWorkerBond;
+	type BondSlashFraction = BondSlashFraction;
+	type BondUnbondingPeriod = BondUnbondingPeriod;
+	type BondSlash = ();
+	type UnixTime = Timestamp;
+}
+
+const THRESHOLDS: [sp_npos_elections::VoteWeight; 9] =
+    [10, 20, 30, 40, 50, 60, 1_000, 2_000, 10_000];
+
+parameter_types! {
+	pub static BagThresholds: &'static [sp_npos_elections::VoteWeight] = &THRESHOLDS;
+	pub static MaxNominations: u32 = 16;
+}
+
+impl pallet_bags_list::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type ScoreProvider = Staking;
+	type BagThresholds = BagThresholds;
+	type Score = VoteWeight;
+}
+
+parameter_types! {
+	pub static SessionsPerEra: SessionIndex = 3;
+	pub static SlashDeferDuration: EraIndex = 0;
+	pub static Period: BlockNumber = 5;
+	pub static Offset: BlockNumber = 0;
+}
+
+sp_runtime::impl_opaque_keys! {
+	pub struct SessionKeys {
+		pub other: OtherSessionHandler,
+	}
+}
+impl pallet_session::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type ValidatorId = AccountId;
+	type ValidatorIdOf = StashOf<Test>;
+	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+	type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
+	type SessionManager = pallet_session::historical::NoteHistoricalRoot<Test, Staking>;
+	type SessionHandler = (OtherSessionHandler, );
+	type Keys = SessionKeys;
+	type WeightInfo = ();
+}
+
+impl pallet_session::historical::Config for Test {
+	type FullIdentification = Exposure<AccountId, Balance>;
+	type FullIdentificationOf = ExposureOf<Test>;
+}
+
+pub struct OnChainSeqPhragmen;
+
+impl onchain::Config for OnChainSeqPhragmen {
+	type System = Test;
+	type Solver = SequentialPhragmen<AccountId, Perbill>;
+	type DataProvider = Staking;
+	type WeightInfo = ();
+	type MaxWinners = ConstU32<100>;
+	type VotersBound = ConstU32<{ u32::MAX }>;
+	type TargetsBound = ConstU32<{ u32::MAX }>;
+}
+
+impl pallet_cess_staking::Config for Test {
+    const ERAS_PER_YEAR: u64 = 8766;
+    const FIRST_YEAR_VALIDATOR_REWARDS: pallet_cess_staking::BalanceOf<Test> = 618_000_000;
+    const FIRST_YEAR_SMINER_REWARDS: pallet_cess_staking::BalanceOf<Test> = 309_000_000;
+    const REWARD_DECREASE_RATIO: Perbill = Perbill::from_perthousand(794);
+    type SminerRewardPool = ();
+    type Currency = Balances;
+	type CurrencyBalance = <Self as pallet_balances::Config>::Balance;
+    type UnixTime = Timestamp;
+    type CurrencyToVote = frame_support::traits::SaturatingCurrencyToVote;
+    type ElectionProvider = onchain::OnChainExecution<OnChainSeqPhragmen>;
+    type GenesisElectionProvider = Self::ElectionProvider;
+    type MaxNominations = MaxNominations;
+    type RewardRemainder = ();
+    type RuntimeEvent = RuntimeEvent;
+    type Slash = ();
+    type SlashPolicy = ();
+    type Reward = ();
+    type SessionsPerEra = ();
+    type BondingDuration = ();
+    type SlashDeferDuration = ();
+    type SlashCancelOrigin = frame_system::EnsureRoot<Self::AccountId>;
+    type SessionInterface = Self;
+    type EraPayout = ();
+    type NextNewSession = ();
+    type MaxNominatorRewardedPerValidator = ConstU32<64>;
+    type OffendingValidatorsThreshold = ();
+    type VoterList = BagsList;
+    type TargetList = pallet_cess_staking::UseValidatorsMap<Self>;
+	type MaxUnlockingChunks = ConstU32<32>;
+	type HistoryDepth = ConstU32<84>;
+    type OnStakerSlash = ();
+	type BenchmarkingConfig = pallet_cess_staking::TestBenchmarkingConfig;
+    type WeightInfo = ();
+}
+
+pub struct OtherSessionHandler;
+
+impl OneSessionHandler<AccountId> for OtherSessionHandler {
+    type Key = UintAuthorityId;
+
+    fn on_genesis_session<'a, I: 'a>(_: I)
+        where
+            I: Iterator<Item=(&'a AccountId, Self::Key)>,
+            AccountId: 'a,
+    {}
+
+    fn on_new_session<'a, I: 'a>(_: bool, _: I, _: I)
+        where
+            I: Iterator<Item=(&'a AccountId, Self::Key)>,
+            AccountId: 'a,
+    {}
+
+    fn on_disabled(_validator_index: u32) {}
+}
+
+impl sp_runtime::BoundToRuntimeAppPublic for OtherSessionHandler {
+    type Public = UintAuthorityId;
+}
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type DbWeight = ();
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+pub struct MockStashAccountFinder<AccountId>(PhantomData<AccountId>);
+
+impl<AccountId: Clone> SchedulerStashAccountFinder<AccountId>
+for MockStashAccountFinder<AccountId>
+{
+	fn find_stash_account_id(ctrl_account_id: &AccountId) -> Option<AccountId> {
+		Some(ctrl_account_id.clone())
+	}
+}
+
+parameter_types! {
+	pub const PeriodDuration: BlockNumber = 64_000;
+}
+
+impl pallet_scheduler_credit::Config for Test {
+	type StashAccountFinder = MockStashAccountFinder<Self::AccountId>;
+	type PeriodDuration = PeriodDuration;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type DustRemoval = ();
+	type RuntimeEvent = RuntimeEvent;
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<5>;
+    type WeightInfo = ();
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(CONTROLLER, 10_000), (STASH, 10_000)],
+    }
+        .assimilate_storage(&mut t)
+        .unwrap();
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| {
+        System::set_block_number(1); //must set block_number, otherwise the deposit_event() don't work
+    });
+    ext
+}
@@ -33,3 +33,25 @@ fn cryptos_are_compatible() {
 	));
 	assert!(libp2p_public.verify(message, sp_core_signature.as_ref()));
 }
+
+#[test]
+fn slash_bond_falls_back_to_unbonding_bond() {
+	use crate::mock::*;
+	use frame_support::{assert_noop, assert_ok};
+
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			<TeeWorker as ScheduleFind<AccountId>>::punish_scheduler(CONTROLLER),
+			Error::<Test>::NonTeeWorker
+		);
+
+		// The worker exited while bonded, its bond is still reserved until the end of unbonding.
+		assert_ok!(<Balances as ReservableCurrency<AccountId>>::reserve(&CONTROLLER, 1_000));
+		<UnbondingBond<Test>>::insert(CONTROLLER, (1_000, 101));
+
+		assert_ok!(<TeeWorker as ScheduleFind<AccountId>>::punish_scheduler(CONTROLLER));
+		assert_eq!(<UnbondingBond<Test>>::get(CONTROLLER), Some((900, 101)));
+		assert_eq!(Balances::reserved_balance(CONTROLLER), 900);
+		System::assert_last_event(Event::<Test>::BondSlashed { acc: CONTROLLER, amount: 100 }.into());
+	});
+}
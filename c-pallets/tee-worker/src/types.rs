@@ -8,11 +8,35 @@ pub struct TeeWorkerInfo<T: pallet::Config> {
     pub peer_id: PeerId,
     pub node_key: NodePublicKey,
     pub stash_account: AccountOf<T>,
+    pub podr2_pk: Podr2Key,
+    // The multiaddrs the worker is reachable at.
+    pub endpoints: BoundedVec<Multiaddr, T::MaxEndpoints>,
+    // The block the attestation report of the worker was last verified at.
+    pub attested_at: BlockNumberFor<T>,
+    // The tasks the worker takes, a combination of the `ROLE_*` bits.
+    pub roles: WorkerRole,
 }
 
-#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, Default, MaxEncodedLen, TypeInfo)]
-pub struct SgxAttestationReport {
-    pub report_json_raw: Report,
-    pub sign: ReportSign,
-    pub cert_der: Cert,
+/// The tasks a tee worker declares at registration, as a bitflag.
+pub type WorkerRole = u8;
+// Computes the PoDR2 tags of the fragments of new deals.
+pub const ROLE_MARKER: WorkerRole = 0b001;
+// Verifies the proofs miners submit for the challenges.
+pub const ROLE_VERIFIER: WorkerRole = 0b010;
+// Serves file data to gateways.
+pub const ROLE_GATEWAY: WorkerRole = 0b100;
+pub const ROLE_ALL: WorkerRole = ROLE_MARKER | ROLE_VERIFIER | ROLE_GATEWAY;
+
+// An IAS report of an EPID quote, or a DCAP quote verified on chain against its collateral.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum SgxAttestationReport {
+    Epid {
+        report_json_raw: Report,
+        sign: ReportSign,
+        cert_der: Cert,
+    },
+    Dcap {
+        quote: DcapQuote,
+        collateral: DcapCollateral,
+    },
 }
\ No newline at end of file
@@ -50,6 +50,14 @@ use sp_std::marker::PhantomData;
 pub trait WeightInfo {
 	fn registration_scheduler() -> Weight;
 	fn update_scheduler() -> Weight;
+	fn update_podr2_pk() -> Weight;
+	fn vote_master_podr2_key() -> Weight;
+	fn set_master_podr2_key() -> Weight;
+	fn exit() -> Weight;
+	fn report_heartbeat() -> Weight;
+	fn refresh_attestation() -> Weight;
+	fn set_attestation_validity() -> Weight;
+	fn withdraw_bond() -> Weight;
 }
 
 /// Weights for pallet_tee_worker using the Substrate node and recommended hardware.
@@ -68,6 +76,65 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(1 as u64))
 			.saturating_add(T::DbWeight::get().writes(1 as u64))
 	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:1)
+	fn update_podr2_pk() -> Weight {
+		Weight::from_ref_time(42_099_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:2 w:0)
+	// Storage: TeeWorker MasterPodr2Key (r:1 w:1)
+	// Storage: TeeWorker MasterKeyVotes (r:1 w:1)
+	fn vote_master_podr2_key() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:0)
+	// Storage: TeeWorker MasterPodr2Key (r:0 w:1)
+	// Storage: TeeWorker MasterKeyVotes (r:0 w:1)
+	fn set_master_podr2_key() -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:1)
+	// Storage: TeeWorker MasterKeyVotes (r:0 w:1)
+	// Storage: TeeWorker BondAcc (r:1 w:1)
+	// Storage: TeeWorker MasterPodr2Key (r:1 w:1)
+	// Storage: FileBank DealMap (r:1 w:0)
+	fn exit() -> Weight {
+		Weight::from_ref_time(60_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:0)
+	// Storage: TeeWorker LastSeen (r:0 w:1)
+	// Storage: TeeWorker OfflineWorker (r:1 w:1)
+	fn report_heartbeat() -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:1)
+	// Storage: TeeWorker SuspendedWorker (r:0 w:1)
+	fn refresh_attestation() -> Weight {
+		Weight::from_ref_time(40_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	// Storage: TeeWorker AttestationValidity (r:0 w:1)
+	fn set_attestation_validity() -> Weight {
+		Weight::from_ref_time(15_000_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	// Storage: TeeWorker UnbondingBond (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn withdraw_bond() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
 }
 
 // For backwards compatibility and tests
@@ -85,4 +152,63 @@ impl WeightInfo for () {
 			.saturating_add(RocksDbWeight::get().reads(1 as u64))
 			.saturating_add(RocksDbWeight::get().writes(1 as u64))
 	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:1)
+	fn update_podr2_pk() -> Weight {
+		Weight::from_ref_time(42_099_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:2 w:0)
+	// Storage: TeeWorker MasterPodr2Key (r:1 w:1)
+	// Storage: TeeWorker MasterKeyVotes (r:1 w:1)
+	fn vote_master_podr2_key() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:0)
+	// Storage: TeeWorker MasterPodr2Key (r:0 w:1)
+	// Storage: TeeWorker MasterKeyVotes (r:0 w:1)
+	fn set_master_podr2_key() -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:1)
+	// Storage: TeeWorker MasterKeyVotes (r:0 w:1)
+	// Storage: TeeWorker BondAcc (r:1 w:1)
+	// Storage: TeeWorker MasterPodr2Key (r:1 w:1)
+	// Storage: FileBank DealMap (r:1 w:0)
+	fn exit() -> Weight {
+		Weight::from_ref_time(60_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:0)
+	// Storage: TeeWorker LastSeen (r:0 w:1)
+	// Storage: TeeWorker OfflineWorker (r:1 w:1)
+	fn report_heartbeat() -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: TeeWorker TeeWorkerMap (r:1 w:1)
+	// Storage: TeeWorker SuspendedWorker (r:0 w:1)
+	fn refresh_attestation() -> Weight {
+		Weight::from_ref_time(40_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	// Storage: TeeWorker AttestationValidity (r:0 w:1)
+	fn set_attestation_validity() -> Weight {
+		Weight::from_ref_time(15_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	// Storage: TeeWorker UnbondingBond (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn withdraw_bond() -> Weight {
+		Weight::from_ref_time(30_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
 }
@@ -4,7 +4,7 @@ use cess_node_runtime::{
 	BalancesConfig, Block, CouncilConfig, GenesisConfig, GrandpaConfig, ImOnlineConfig,
 	IndicesConfig, MaxNominations, BabeConfig, SessionConfig, Signature, StakerStatus,
 	StakingConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig, DOLLARS,
-	StorageHandlerConfig,
+	StorageHandlerConfig, TeeWorkerConfig, FileBankConfig,
 };
 
 use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
@@ -506,6 +506,16 @@ fn testnet_genesis(
 			balances: endowed_accounts.iter().cloned().map(|k| (k, ENDOWMENT)).collect(),
 		},
 		storage_handler: StorageHandlerConfig { price: 30 * DOLLARS },
+		file_bank: FileBankConfig {
+			declaration_limit: None,
+			file_size_limit: None,
+		},
+		changelog: Default::default(),
+		tee_worker: TeeWorkerConfig {
+			mr_enclave_whitelist: vec![],
+			workers: vec![],
+			podr2_pbk: vec![],
+		},
 		indices: IndicesConfig { indices: vec![] },
 		session: SessionConfig {
 			keys: initial_authorities
@@ -15,6 +15,7 @@ use jsonrpsee::RpcModule;
 use sc_client_api::{
 	backend::{AuxStore, Backend, StateBackend, StorageProvider},
 	client::BlockchainEvents,
+	ProofProvider,
 };
 use grandpa::{
 	FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet, SharedVoterState,
@@ -154,6 +155,7 @@ where
 	BE::State: StateBackend<BlakeTwo256>,
 	C: ProvideRuntimeApi<Block>
 		+ StorageProvider<Block, BE>
+		+ ProofProvider<Block>
 		+ sc_client_api::BlockBackend<Block>
 		+ HeaderBackend<Block>
 		+ AuxStore
@@ -169,6 +171,7 @@ where
 	C::Api: BlockBuilder<Block>,
 	C::Api: fp_rpc::ConvertTransactionRuntimeApi<Block>,
 	C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
+	C::Api: pallet_file_bank_rpc::FileBankRuntimeApi<Block, AccountId, BlockNumber>,
 	P: TransactionPool<Block = Block> + 'static,
 	SC: SelectChain<Block> + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
@@ -181,6 +184,7 @@ where
 		EthPubSubApiServer, EthSigner, Net, NetApiServer, Web3,	Web3ApiServer,
 	};
 	use pallet_mmr_rpc::{Mmr, MmrApiServer};
+	use pallet_file_bank_rpc::{FileBank, FileBankApiServer};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use cessc_consensus_rrsc_rpc::{ RRSC, RRSCApiServer };
 	use sc_finality_grandpa_rpc::{Grandpa, GrandpaApiServer};
@@ -234,6 +238,7 @@ where
 	// io.merge(Contracts::new(client.clone()).into_rpc())?;
 	io.merge(Mmr::new(client.clone()).into_rpc())?;
 	io.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	io.merge(FileBank::new(client.clone(), subscription_executor.clone()).into_rpc())?;
 	io.merge(
 		RRSC::new(
 			client.clone(),
@@ -1,5 +1,9 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod name;
+pub mod peer;
+pub mod slash;
+
 /* use */
 use frame_support::{
 	RuntimeDebug,
@@ -11,6 +15,7 @@ use frame_support::{
 };
 use codec::{MaxEncodedLen};
 use scale_info::TypeInfo;
+use sp_std::vec::Vec;
 
 #[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo, PartialOrd, Ord)]
 pub struct Hash(pub [u8; 64]);
@@ -51,7 +56,12 @@ impl Hash {
 
 pub type Mrenclave = [u8; 32];
 pub type PeerId = [u8; 38];
+// A binary libp2p multiaddr, see `peer::is_valid_multiaddr`.
+pub type Multiaddr = BoundedVec<u8, ConstU32<128>>;
 pub type Podr2Key = [u8; 270];
+// ISO 3166-1 alpha-2 country code, for example *b"DE".
+pub type RegionCode = [u8; 2];
+pub const UNKNOWN_REGION: RegionCode = *b"ZZ";
 
 pub const M_BYTE: u128 = 1_048_576;
 pub const G_BYTE: u128 = 1_048_576 * 1024;
@@ -72,10 +82,31 @@ pub const NUMBER: [u8; 10] = [b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'
 
 pub type NodePublicKey = sp_core::ed25519::Public;
 pub type NodeSignature = [u8; 64];
+// Signatures of tee committee members, each with the controller account of the signer.
+pub type CommitteeSignatures<AccountId> = BoundedVec<(AccountId, NodeSignature), ConstU32<16>>;
 
 pub type ReportSign = BoundedVec<u8, ConstU32<344>>;
 pub type Report =  BoundedVec<u8, ConstU32<1354>>;
 pub type Cert = BoundedVec<u8, ConstU32<1588>> ;
+// A DCAP quote with its PCK certificate chain.
+pub type DcapQuote = BoundedVec<u8, ConstU32<8192>>;
+pub type DcapCollateralData = BoundedVec<u8, ConstU32<4096>>;
+// DER encoded root certificate of the DCAP certificate chains.
+pub type DcapRootCert = BoundedVec<u8, ConstU32<1024>>;
+
+// Signed collateral of a DCAP quote, as served by the Intel PCS.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct DcapCollateral {
+	// The raw `tcbInfo` and `enclaveIdentity` json objects, each signed with a raw r || s signature.
+	pub tcb_info: DcapCollateralData,
+	pub tcb_info_signature: DcapCollateralData,
+	pub qe_identity: DcapCollateralData,
+	pub qe_identity_signature: DcapCollateralData,
+	// PEM chain of the TCB signing certificate.
+	pub tcb_signing_chain: DcapCollateralData,
+	// DER encoded CRL of the PCK CA that issued the PCK certificate of the quote.
+	pub pck_crl: DcapCollateralData,
+}
 
 #[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 pub enum DataType {
@@ -83,6 +114,143 @@ pub enum DataType {
 	Filler,
 }
 
+// Package usage of a user, returned by the runtime api.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, TypeInfo)]
+pub struct SpaceInfo<BlockNumber> {
+	pub total_space: u128,
+	pub used_space: u128,
+	pub locked_space: u128,
+	pub remaining_space: u128,
+	pub start: BlockNumber,
+	pub deadline: BlockNumber,
+	// "normal", "frozen" or "dead".
+	pub state: Vec<u8>,
+}
+
+// Summary of a file, returned by the runtime api.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, TypeInfo)]
+pub struct FileBrief<AccountId, BlockNumber> {
+	pub owners: Vec<AccountId>,
+	pub file_size: u128,
+	pub segment_count: u32,
+	pub completion: BlockNumber,
+	// "active", "calculate", "missing" or "recovery".
+	pub state: Vec<u8>,
+}
+
+// Reputation of a miner, returned by the runtime api. Reputations are in parts per billion.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, TypeInfo)]
+pub struct ReputationBrief {
+	// Share of the recorded challenges the miner passed.
+	pub challenge: u32,
+	// Grows with the stake of the gateways endorsing the miner.
+	pub endorsement: u32,
+	// The reputation files are placed by.
+	pub placement: u32,
+	pub endorsers: u32,
+	pub endorsed_stake: u128,
+}
+
+// Replicas and miner health of a region, recomputed by sminer once a day.
+// Miners that declared no region are counted under `UNKNOWN_REGION`.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, Default, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct RegionAvailability {
+	pub region: RegionCode,
+	pub miners: u32,
+	// Positive miners whose last heartbeat is recent.
+	pub healthy_miners: u32,
+	// Fragments stored by the miners of the region.
+	pub fragments: u64,
+	pub idle_space: u128,
+	pub service_space: u128,
+}
+
+// Weight a call is charged, returned by the runtime api so that batches can be budgeted.
+// Calls taking a list are weighed for a single item.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, TypeInfo)]
+pub struct CallWeight {
+	pub call_index: u8,
+	pub name: Vec<u8>,
+	pub ref_time: u64,
+	pub proof_size: u64,
+	// Storage reads and writes counted by the benchmark, None if the call is not benchmarked.
+	pub db_access: Option<(u64, u64)>,
+}
+
+// Quote of storing a file, returned by the runtime api for clients mixing evm and native calls.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, TypeInfo)]
+pub struct StorageCostEstimate {
+	// Space taken from the package by the file stored `replication` times.
+	pub charged_space: u128,
+	// Smallest package in gibs the file can be declared in.
+	pub package_gib: u32,
+	// Fee of buying that package for a month, before the region multiplier.
+	pub package_fee: u128,
+	// Weight fee of buying the package and declaring the uploads, without length fee or tip.
+	pub transaction_fee: u128,
+	// Gas the storage precompile charges for the same calls.
+	pub evm_gas: u64,
+}
+
+// Kind of a package operation recorded in the ledger of an account.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, Copy, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum LedgerKind {
+	Purchase,
+	Expansion,
+	Renewal,
+	Upgrade,
+	Downgrade,
+	// Paid to the account as the referrer of a purchase.
+	ReferralReward,
+	// The package was cleared after the frozen period.
+	Expiry,
+}
+
+// One package operation of an account. `amount` is what the account paid,
+// or for a referral reward what it received.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct LedgerEntry<BlockNumber> {
+	pub block: BlockNumber,
+	pub kind: LedgerKind,
+	pub amount: u128,
+	pub space_added: u128,
+	pub space_removed: u128,
+}
+
+// Package operations of an account within a block range, returned by the runtime api.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, TypeInfo)]
+pub struct AccountStatement<BlockNumber> {
+	// Paid for purchases, expansions and upgrades.
+	pub purchases: u128,
+	pub renewals: u128,
+	// Paid back to the account.
+	pub refunds: u128,
+	pub space_added: u128,
+	pub space_removed: u128,
+	pub entries: Vec<LedgerEntry<BlockNumber>>,
+}
+
+// Storage events that more than one pallet acts on.
+#[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, TypeInfo)]
+pub enum StorageEvent<AccountId> {
+	// A miner left with the replicas of `service_space` bytes, they are restored by other miners.
+	ReplicaLost { miner: AccountId, service_space: u128 },
+	// The file of a deal became active, `scheduler` is the tee worker it was uploaded through.
+	DealCompleted { owner: AccountId, scheduler: AccountId, file_hash: Hash, file_size: u128 },
+	// A miner failed a challenge, or did not answer it.
+	ChallengeFailed { miner: AccountId },
+}
+
+// Implemented by the runtime, hands each storage event to the pallets listening for it.
+// The pallets raising the events do not need to know who listens.
+pub trait StorageEventRouter<AccountId> {
+	fn route(event: StorageEvent<AccountId>);
+}
+
+impl<AccountId> StorageEventRouter<AccountId> for () {
+	fn route(_event: StorageEvent<AccountId>) {}
+}
+
 #[derive(PartialEq, Eq, Encode, Decode, Clone, RuntimeDebug, MaxEncodedLen, TypeInfo)]
 pub enum IpAddress {
 	IPV4([u8; 4], u16),
@@ -0,0 +1,67 @@
+//! Validation of the names users give to files, buckets and other objects on chain.
+//!
+//! Names are stored in their NFC form, so that the same name typed on different systems
+//! is the same bytes, and indexers and wallets only ever read printable utf-8.
+use sp_std::vec::Vec;
+use unicode_normalization::UnicodeNormalization;
+
+/// Why a name was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameError {
+	TooShort,
+	TooLong,
+	NotUtf8,
+	ControlCharacter,
+}
+
+/// The NFC form of a name, which must be utf-8 without control or bidi override
+/// characters, and between `min` and `max` bytes long once normalized.
+pub fn normalize_name(raw: &[u8], min: usize, max: usize) -> Result<Vec<u8>, NameError> {
+	let name = sp_std::str::from_utf8(raw).map_err(|_| NameError::NotUtf8)?;
+	if name.chars().any(is_forbidden) {
+		return Err(NameError::ControlCharacter)
+	}
+
+	let mut normalized = Vec::with_capacity(raw.len());
+	let mut buf = [0u8; 4];
+	for c in name.nfc() {
+		normalized.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+	}
+
+	if normalized.len() < min {
+		return Err(NameError::TooShort)
+	}
+	if normalized.len() > max {
+		return Err(NameError::TooLong)
+	}
+
+	Ok(normalized)
+}
+
+// Control characters, and the bidi embeddings, overrides and isolates that make a name
+// display differently from its bytes.
+fn is_forbidden(c: char) -> bool {
+	c.is_control() || ('\u{202a}'..='\u{202e}').contains(&c) || ('\u{2066}'..='\u{2069}').contains(&c)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn names_are_normalized() {
+		assert_eq!(normalize_name(b"photos.tar", 3, 63), Ok(b"photos.tar".to_vec()));
+		// "e" followed by a combining acute accent is stored as the precomposed "é".
+		assert_eq!(normalize_name("cafe\u{301}".as_bytes(), 3, 63), Ok("caf\u{e9}".as_bytes().to_vec()));
+		assert_eq!(normalize_name("caf\u{e9}".as_bytes(), 3, 63), Ok("caf\u{e9}".as_bytes().to_vec()));
+	}
+
+	#[test]
+	fn malformed_names_are_rejected() {
+		assert_eq!(normalize_name(&[0x66, 0xff, 0x6f], 1, 63), Err(NameError::NotUtf8));
+		assert_eq!(normalize_name(b"new\nline", 1, 63), Err(NameError::ControlCharacter));
+		assert_eq!(normalize_name("txt.\u{202e}exe".as_bytes(), 1, 63), Err(NameError::ControlCharacter));
+		assert_eq!(normalize_name(b"ab", 3, 63), Err(NameError::TooShort));
+		assert_eq!(normalize_name(b"abcd", 1, 3), Err(NameError::TooLong));
+	}
+}
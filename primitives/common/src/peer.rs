@@ -0,0 +1,132 @@
+//! Node keys, peer ids and multiaddrs of the libp2p nodes run by tee workers and miners.
+use crate::{NodePublicKey, PeerId};
+
+// Identity multihash of a protobuf encoded ed25519 public key:
+// identity code, digest length, then the `KeyType` and `Data` fields of the key.
+const ED25519_PEER_ID_PREFIX: [u8; 6] = [0x00, 0x24, 0x08, 0x01, 0x12, 0x20];
+
+/// The peer id a libp2p node announces for its ed25519 node key.
+pub fn peer_id_from_node_key(node_key: &NodePublicKey) -> PeerId {
+	let mut peer_id = [0u8; 38];
+	peer_id[..6].copy_from_slice(&ED25519_PEER_ID_PREFIX);
+	peer_id[6..].copy_from_slice(node_key.as_ref());
+
+	peer_id
+}
+
+/// The ed25519 node key inlined in a peer id, `None` if it is not an ed25519 peer id.
+pub fn node_key_from_peer_id(peer_id: &PeerId) -> Option<NodePublicKey> {
+	if peer_id[..6] != ED25519_PEER_ID_PREFIX {
+		return None;
+	}
+
+	let mut raw = [0u8; 32];
+	raw.copy_from_slice(&peer_id[6..]);
+
+	Some(NodePublicKey::from_raw(raw))
+}
+
+/// Whether the peer id inlines an ed25519 node key, the only kind of 38 byte peer id.
+pub fn is_valid_peer_id(peer_id: &PeerId) -> bool {
+	node_key_from_peer_id(peer_id).is_some()
+}
+
+/// Whether the bytes are a binary multiaddr made of the protocols nodes are reachable over:
+/// ip4, ip6, dns, dns4, dns6, dnsaddr, tcp, udp, quic, quic-v1, ws, wss and p2p.
+pub fn is_valid_multiaddr(addr: &[u8]) -> bool {
+	let mut rest = addr;
+	if rest.is_empty() {
+		return false;
+	}
+
+	while !rest.is_empty() {
+		let code = match read_varint(&mut rest) {
+			Some(code) => code,
+			None => return false,
+		};
+		let len = match code {
+			// ip4
+			4 => 4,
+			// ip6
+			41 => 16,
+			// tcp, udp
+			6 | 273 => 2,
+			// quic, quic-v1, ws, wss
+			460 | 461 | 477 | 478 => 0,
+			// dns, dns4, dns6, dnsaddr, p2p
+			53 | 54 | 55 | 56 | 421 => match read_varint(&mut rest) {
+				Some(len) if len > 0 => len as usize,
+				_ => return false,
+			},
+			_ => return false,
+		};
+		if rest.len() < len {
+			return false;
+		}
+		// The p2p component must hold an ed25519 peer id.
+		if code == 421 {
+			let peer_id: Option<&PeerId> = rest[..len].try_into().ok();
+			if !peer_id.map_or(false, is_valid_peer_id) {
+				return false;
+			}
+		}
+		rest = &rest[len..];
+	}
+
+	true
+}
+
+// Reads an unsigned varint of at most 9 bytes, as used by multiaddr.
+fn read_varint(input: &mut &[u8]) -> Option<u64> {
+	let mut value: u64 = 0;
+	for (i, byte) in input.iter().enumerate().take(9) {
+		value |= ((byte & 0x7f) as u64) << (7 * i);
+		if byte & 0x80 == 0 {
+			*input = &input[i + 1..];
+			return Some(value);
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn peer_id_round_trip() {
+		let node_key = NodePublicKey::from_raw([7u8; 32]);
+		let peer_id = peer_id_from_node_key(&node_key);
+
+		assert_eq!(peer_id[..6], ED25519_PEER_ID_PREFIX);
+		assert_eq!(node_key_from_peer_id(&peer_id), Some(node_key));
+
+		let mut other = peer_id;
+		other[3] = 0x02;
+		assert!(!is_valid_peer_id(&other));
+	}
+
+	#[test]
+	fn multiaddr_validation() {
+		// /ip4/127.0.0.1/tcp/4001
+		assert!(is_valid_multiaddr(&[0x04, 127, 0, 0, 1, 0x06, 0x0f, 0xa1]));
+		// /dns4/cess.network/tcp/443/wss
+		let mut dns = vec![0x36, 12];
+		dns.extend_from_slice(b"cess.network");
+		dns.extend_from_slice(&[0x06, 0x01, 0xbb, 0xde, 0x03]);
+		assert!(is_valid_multiaddr(&dns));
+		// /ip4/127.0.0.1/udp/4001/quic-v1/p2p/<peer id>
+		let mut p2p = vec![0x04, 127, 0, 0, 1, 0x91, 0x02, 0x0f, 0xa1, 0xcd, 0x03, 0xa5, 0x03, 38];
+		p2p.extend_from_slice(&peer_id_from_node_key(&NodePublicKey::from_raw([1u8; 32])));
+		assert!(is_valid_multiaddr(&p2p));
+
+		assert!(!is_valid_multiaddr(&[]));
+		// truncated ip4
+		assert!(!is_valid_multiaddr(&[0x04, 127, 0]));
+		// unknown protocol
+		assert!(!is_valid_multiaddr(&[0x7f]));
+		// p2p with a value that is not a peer id
+		assert!(!is_valid_multiaddr(&[0xa5, 0x03, 2, 0, 0]));
+	}
+}
@@ -0,0 +1,94 @@
+//! Destination policy of slashed funds, shared by the scheduler slashes
+//! of the staking pallet and the punishments of storage miners.
+use frame_support::{
+	RuntimeDebug,
+	dispatch::{Decode, Encode},
+	sp_runtime::Perbill,
+	traits::{Currency, Imbalance, OnUnbalanced},
+};
+use codec::MaxEncodedLen;
+use scale_info::TypeInfo;
+
+/// What a slash is applied for.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum SlashSource {
+	/// A tee worker was punished through `punish_scheduler`.
+	Scheduler,
+	/// A miner failed the idle space challenge.
+	MinerIdle,
+	/// A miner failed the service space challenge.
+	MinerService,
+	/// A miner did not submit its challenge proofs at all.
+	MinerClear,
+	/// A miner did not clear an invalid file before its deadline.
+	MinerInvalidFile,
+	/// Tee workers attested that they repeatedly could not connect to a miner.
+	MinerUnreachable,
+}
+
+/// Shares of a slash, whatever the shares leave over is burned.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct SlashSplit {
+	pub treasury: Perbill,
+	/// Split evenly among the users affected by the slash.
+	pub users: Perbill,
+	/// Bounty of the account that reported the fault.
+	pub reporter: Perbill,
+}
+
+/// Decides where the funds of each kind of slash go.
+///
+/// The share of the affected users or of the reporter goes to the treasury
+/// when the slash has no affected user or no reporter.
+pub trait SlashPolicy<AccountId, C: Currency<AccountId>> {
+	/// Receives the treasury share of a slash.
+	type Treasury: OnUnbalanced<C::NegativeImbalance>;
+
+	fn split(source: SlashSource) -> SlashSplit;
+
+	/// Distribute the funds slashed for `source`.
+	fn distribute(
+		source: SlashSource,
+		slashed: C::NegativeImbalance,
+		affected: &[AccountId],
+		reporter: Option<&AccountId>,
+	) {
+		let split = Self::split(source);
+		let total = slashed.peek();
+
+		let (mut to_treasury, rest) = slashed.split(split.treasury.mul_floor(total));
+		let (mut to_users, rest) = rest.split(split.users.mul_floor(total));
+		let (to_reporter, burned) = rest.split(split.reporter.mul_floor(total));
+
+		if affected.is_empty() {
+			to_treasury.subsume(to_users);
+		} else {
+			let per_user = to_users.peek() / (affected.len() as u32).into();
+			for user in affected {
+				let (user_share, rest) = to_users.split(per_user);
+				to_users = rest;
+				C::resolve_creating(user, user_share);
+			}
+			// remainder of the division.
+			to_treasury.subsume(to_users);
+		}
+
+		match reporter {
+			Some(reporter) => C::resolve_creating(reporter, to_reporter),
+			None => to_treasury.subsume(to_reporter),
+		}
+
+		Self::Treasury::on_unbalanced(to_treasury);
+		// dropping the rest of the imbalance reduces the total issuance.
+		drop(burned);
+	}
+}
+
+/// Burns every slash.
+impl<AccountId, C: Currency<AccountId>> SlashPolicy<AccountId, C> for () {
+	type Treasury = ();
+
+	fn split(_source: SlashSource) -> SlashSplit {
+		SlashSplit::default()
+	}
+}
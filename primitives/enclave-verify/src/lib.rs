@@ -23,6 +23,9 @@ use ic_verify_bls_signature::{
     PublicKey as BLSPubilc,
 };
 
+#[cfg(target_arch = "wasm32")]
+extern crate ring_wasmable as ring;
+
 #[test]
 use rand::RngCore;
 
@@ -91,6 +94,45 @@ static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[
     &webpki::RSA_PKCS1_2048_8192_SHA512,
     &webpki::RSA_PKCS1_3072_8192_SHA384,
 ];
+// The PCK and TCB signing chains of DCAP are signed with P-256 keys.
+static DCAP_SIG_ALGS: SignatureAlgorithms = &[
+    &webpki::ECDSA_P256_SHA256,
+];
+
+// IAS reports are checked at a fixed time, 2022-12-09 00:00:00.
+// DCAP quotes are checked at the time of the block.
+const CERT_VERIFY_TIME: u64 = 1670515200;
+
+// Layout of a version 3 ECDSA quote.
+const QUOTE_VERSION: u16 = 3;
+const ATT_KEY_TYPE_ECDSA_P256: u16 = 2;
+const QUOTE_SIGNED_LEN: usize = 48 + 384;
+const QUOTE_SIG_DATA_START: usize = QUOTE_SIGNED_LEN + 4;
+const QE_REPORT_LEN: usize = 384;
+// PEM chain of the PCK leaf, intermediate and root certificates.
+const CERT_DATA_PCK_CHAIN: u16 = 5;
+
+// TCB levels a platform may be at, software hardening is up to the enclave.
+const ACCEPTED_TCB_STATUS: [&str; 2] = ["UpToDate", "SWHardeningNeeded"];
+
+// DER tags read from certificates and CRLs.
+const DER_BOOLEAN: u8 = 0x01;
+const DER_INTEGER: u8 = 0x02;
+const DER_BIT_STRING: u8 = 0x03;
+const DER_OCTET_STRING: u8 = 0x04;
+const DER_OID: u8 = 0x06;
+const DER_SEQUENCE: u8 = 0x30;
+const DER_UTC_TIME: u8 = 0x17;
+const DER_GENERALIZED_TIME: u8 = 0x18;
+const DER_VERSION: u8 = 0xa0;
+const DER_EXTENSIONS: u8 = 0xa3;
+// OID 1.2.840.113741.1.13.1 of the SGX extension of PCK certificates.
+const SGX_EXTENSION_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x01, 0x0d, 0x01];
+// Arcs below the SGX extension.
+const SGX_TCB_ARC: u8 = 2;
+const SGX_FMSPC_ARC: u8 = 4;
+// Arcs below the TCB arc, the 16 components come first.
+const SGX_PCESVN_ARC: u8 = 17;
 
 // pub fn u8v_to_hexstr(x: &[u8]) -> String {
 //     // produce a hexnum string from a byte vector
@@ -147,8 +189,7 @@ pub fn verify_miner_cert(
     };
 
     let intermediate_report: Vec<&[u8]> = Vec::new();
-    //2022-12-09 00:00:00
-    let now_func = webpki::Time::from_seconds_since_unix_epoch(1670515200); 
+    let now_func = webpki::Time::from_seconds_since_unix_epoch(CERT_VERIFY_TIME);
 
     if let Err(_e) = sig_cert.verify_is_valid_tls_server_cert(
         SUPPORTED_SIG_ALGS,
@@ -218,6 +259,533 @@ pub fn verify_miner_cert(
     Option::Some(1)
 }
 
+/// The mr_enclave of the quote in an IAS report, hex encoded the way the whitelist stores it.
+pub fn report_mr_enclave(report_json_raw: &Report) -> Option<[u8; 64]> {
+    let report: serde_json::Value = match serde_json::from_slice(report_json_raw) {
+        Ok(report) => report,
+        Err(_) => return Option::None,
+    };
+
+    let quote_body = match &report["isvEnclaveQuoteBody"] {
+        serde_json::Value::String(quote_body) => quote_body,
+        _ => return Option::None,
+    };
+
+    let decoded_quote_body = match base64::decode(quote_body) {
+        Ok(decoded_qb) => decoded_qb,
+        Err(_) => return Option::None,
+    };
+
+    quote_mr_enclave(&decoded_quote_body)
+}
+
+/// The mr_enclave of an EPID quote body or a DCAP quote, hex encoded the way the whitelist stores it.
+///
+/// Both quote formats start with the same header and report body.
+pub fn quote_mr_enclave(quote: &[u8]) -> Option<[u8; 64]> {
+    let mr_enclave = quote.get(112..144)?;
+
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut encoded = [0u8; 64];
+    for (i, byte) in mr_enclave.iter().enumerate() {
+        encoded[2 * i] = HEX[(byte >> 4) as usize];
+        encoded[2 * i + 1] = HEX[(byte & 0x0f) as usize];
+    }
+
+    Option::Some(encoded)
+}
+
+/// Verifies a DCAP quote against its collateral at `now`, in seconds since the unix epoch.
+///
+/// The PCK chain embedded in the quote and the TCB signing chain of the collateral
+/// must both lead to `root_cert`, the DER encoded Intel SGX root CA.
+/// The PCK certificate must not be revoked by the PCK CRL of the collateral,
+/// and the platform and its quoting enclave must be at an accepted TCB level.
+pub fn verify_dcap_quote(
+    quote: &DcapQuote,
+    collateral: &DcapCollateral,
+    root_cert: &[u8],
+    now: u64,
+) -> Option<()> {
+    let root = webpki::trust_anchor_util::cert_der_as_trust_anchor(root_cert).ok()?;
+    let anchors = [root];
+    let trust_anchors = webpki::TLSServerTrustAnchors(&anchors);
+
+    let version = u16::from_le_bytes(quote.get(0..2)?.try_into().ok()?);
+    let att_key_type = u16::from_le_bytes(quote.get(2..4)?.try_into().ok()?);
+    if version != QUOTE_VERSION || att_key_type != ATT_KEY_TYPE_ECDSA_P256 {
+        return Option::None;
+    }
+
+    let mut offset = QUOTE_SIG_DATA_START;
+    let isv_signature = read_bytes(quote, &mut offset, 64)?;
+    let attestation_key = read_bytes(quote, &mut offset, 64)?;
+    let qe_report = read_bytes(quote, &mut offset, QE_REPORT_LEN)?;
+    let qe_report_signature = read_bytes(quote, &mut offset, 64)?;
+    let qe_auth_len = u16::from_le_bytes(read_bytes(quote, &mut offset, 2)?.try_into().ok()?);
+    let qe_auth_data = read_bytes(quote, &mut offset, qe_auth_len as usize)?;
+    let cert_type = u16::from_le_bytes(read_bytes(quote, &mut offset, 2)?.try_into().ok()?);
+    let cert_len = u32::from_le_bytes(read_bytes(quote, &mut offset, 4)?.try_into().ok()?);
+    let cert_data = read_bytes(quote, &mut offset, cert_len as usize)?;
+    if cert_type != CERT_DATA_PCK_CHAIN {
+        return Option::None;
+    }
+
+    // The quoting enclave is certified by the PCK certificate of the platform.
+    let pck_chain = decode_pem_chain(cert_data)?;
+    let pck_cert = verify_cert_chain(&pck_chain, &trust_anchors, now)?;
+    check_pck_crl(&collateral.pck_crl, pck_chain.get(1)?, &pck_chain[0], now)?;
+    pck_cert.verify_signature(
+        &webpki::ECDSA_P256_SHA256,
+        qe_report,
+        &ecdsa_raw_to_der(qe_report_signature)?,
+    ).ok()?;
+
+    // The quoting enclave binds the attestation key to its report.
+    let key_hash = ring::digest::digest(
+        &ring::digest::SHA256,
+        &[attestation_key, qe_auth_data].concat(),
+    );
+    if qe_report.get(320..352)? != key_hash.as_ref() {
+        return Option::None;
+    }
+
+    // The attestation key signs the header and the report body of the enclave.
+    let mut public_key = Vec::with_capacity(65);
+    public_key.push(0x04);
+    public_key.extend_from_slice(attestation_key);
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ECDSA_P256_SHA256_FIXED, &public_key)
+        .verify(quote.get(..QUOTE_SIGNED_LEN)?, isv_signature)
+        .ok()?;
+
+    // The collateral is signed by the TCB signing certificate.
+    let tcb_chain = decode_pem_chain(&collateral.tcb_signing_chain)?;
+    let tcb_cert = verify_cert_chain(&tcb_chain, &trust_anchors, now)?;
+    for (data, signature) in [
+        (&collateral.tcb_info, &collateral.tcb_info_signature),
+        (&collateral.qe_identity, &collateral.qe_identity_signature),
+    ] {
+        tcb_cert.verify_signature(&webpki::ECDSA_P256_SHA256, data, &ecdsa_raw_to_der(signature)?).ok()?;
+    }
+
+    // The quoting enclave must be the one described by the QE identity.
+    let qe_identity: serde_json::Value = serde_json::from_slice(&collateral.qe_identity).ok()?;
+    let mr_signer = match &qe_identity["mrsigner"] {
+        serde_json::Value::String(mr_signer) => decode_hex(mr_signer)?,
+        _ => return Option::None,
+    };
+    if qe_report.get(128..160)? != mr_signer.as_slice() {
+        return Option::None;
+    }
+    let isv_prod_id = qe_identity["isvprodid"].as_u64()?;
+    if u16::from_le_bytes(qe_report.get(256..258)?.try_into().ok()?) as u64 != isv_prod_id {
+        return Option::None;
+    }
+    check_next_update(&qe_identity, now)?;
+    let qe_isv_svn = u16::from_le_bytes(qe_report.get(258..260)?.try_into().ok()?) as u64;
+    let qe_status = qe_identity["tcbLevels"]
+        .as_array()?
+        .iter()
+        .find(|level| level["tcb"]["isvsvn"].as_u64().map_or(false, |svn| qe_isv_svn >= svn))?["tcbStatus"]
+        .as_str()?;
+    if qe_status != "UpToDate" {
+        return Option::None;
+    }
+
+    // The platform must be at an accepted level of the TCB info of its FMSPC.
+    let tcb_info: serde_json::Value = serde_json::from_slice(&collateral.tcb_info).ok()?;
+    check_next_update(&tcb_info, now)?;
+    let platform = platform_tcb(&pck_chain[0])?;
+    let fmspc = match &tcb_info["fmspc"] {
+        serde_json::Value::String(fmspc) => decode_hex(fmspc)?,
+        _ => return Option::None,
+    };
+    if fmspc != platform.fmspc {
+        return Option::None;
+    }
+    let status = tcb_info["tcbLevels"]
+        .as_array()?
+        .iter()
+        .find(|level| platform.meets(&level["tcb"]))?["tcbStatus"]
+        .as_str()?;
+    if !ACCEPTED_TCB_STATUS.contains(&status) {
+        return Option::None;
+    }
+
+    Option::Some(())
+}
+
+// The TCB of a platform, as certified by the SGX extension of its PCK certificate.
+struct PlatformTcb {
+    components: [u64; 16],
+    pce_svn: u64,
+    fmspc: Vec<u8>,
+}
+
+impl PlatformTcb {
+    // Whether every svn of the platform is at least the one of a `tcbLevels` entry,
+    // in the `sgxtcbcomponents` layout of version 3 or the `sgxtcbcompNNsvn` one of version 2.
+    fn meets(&self, tcb: &serde_json::Value) -> bool {
+        let required = |index: usize| -> Option<u64> {
+            match tcb["sgxtcbcomponents"].as_array() {
+                Some(components) => components.get(index)?["svn"].as_u64(),
+                None => {
+                    let number = index as u8 + 1;
+                    let key = [b"sgxtcbcomp", &[b'0' + number / 10, b'0' + number % 10][..], b"svn"].concat();
+                    tcb[core::str::from_utf8(&key).ok()?].as_u64()
+                },
+            }
+        };
+
+        (0..16).all(|index| required(index).map_or(false, |svn| self.components[index] >= svn))
+            && tcb["pcesvn"].as_u64().map_or(false, |svn| self.pce_svn >= svn)
+    }
+}
+
+// Reads the TCB and the FMSPC from the SGX extension of a PCK certificate.
+fn platform_tcb(pck_cert: &[u8]) -> Option<PlatformTcb> {
+    let sgx_extension = cert_extensions(pck_cert)?
+        .into_iter()
+        .find(|(oid, _)| *oid == SGX_EXTENSION_OID)?
+        .1;
+    let (_, mut entries, _) = der_read(sgx_extension, DER_SEQUENCE)?;
+
+    let mut tcb = None;
+    let mut fmspc = None;
+    while !entries.is_empty() {
+        let (_, entry, rest) = der_read(entries, DER_SEQUENCE)?;
+        entries = rest;
+        let (_, oid, value) = der_read(entry, DER_OID)?;
+        match sgx_arc(oid, SGX_EXTENSION_OID) {
+            Some(SGX_TCB_ARC) => tcb = Some(der_read(value, DER_SEQUENCE)?.1),
+            Some(SGX_FMSPC_ARC) => fmspc = Some(der_read(value, DER_OCTET_STRING)?.1.to_vec()),
+            _ => {},
+        }
+    }
+
+    let mut tcb_entries = tcb?;
+    let tcb_oid = [SGX_EXTENSION_OID, &[SGX_TCB_ARC]].concat();
+    let mut components = [None; 16];
+    let mut pce_svn = None;
+    while !tcb_entries.is_empty() {
+        let (_, entry, rest) = der_read(tcb_entries, DER_SEQUENCE)?;
+        tcb_entries = rest;
+        let (_, oid, value) = der_read(entry, DER_OID)?;
+        match sgx_arc(oid, &tcb_oid) {
+            Some(arc @ 1..=16) => components[arc as usize - 1] = Some(der_uint(der_read(value, DER_INTEGER)?.1)?),
+            Some(SGX_PCESVN_ARC) => pce_svn = Some(der_uint(der_read(value, DER_INTEGER)?.1)?),
+            _ => {},
+        }
+    }
+
+    let mut svns = [0u64; 16];
+    for (svn, component) in svns.iter_mut().zip(components) {
+        *svn = component?;
+    }
+
+    Option::Some(PlatformTcb { components: svns, pce_svn: pce_svn?, fmspc: fmspc? })
+}
+
+// The last arc of `oid` when it is directly below `parent`.
+fn sgx_arc(oid: &[u8], parent: &[u8]) -> Option<u8> {
+    match oid.strip_prefix(parent)? {
+        [arc] if *arc < 0x80 => Option::Some(*arc),
+        _ => Option::None,
+    }
+}
+
+// The (OID, value) pairs of the extensions of a DER certificate.
+fn cert_extensions(cert: &[u8]) -> Option<Vec<(&[u8], &[u8])>> {
+    let (_, cert, _) = der_read(cert, DER_SEQUENCE)?;
+    let (_, mut tbs, _) = der_read(cert, DER_SEQUENCE)?;
+    // Skip the version, serial, signature algorithm, issuer, validity, subject and key.
+    if tbs.first() == Some(&DER_VERSION) {
+        tbs = der_read(tbs, DER_VERSION)?.2;
+    }
+    tbs = der_read(tbs, DER_INTEGER)?.2;
+    for _ in 0..5 {
+        tbs = der_read(tbs, DER_SEQUENCE)?.2;
+    }
+    while tbs.first()? != &DER_EXTENSIONS {
+        tbs = der_any(tbs)?.2;
+    }
+    let (_, extensions, _) = der_read(tbs, DER_EXTENSIONS)?;
+    let (_, mut extensions, _) = der_read(extensions, DER_SEQUENCE)?;
+
+    let mut found = Vec::new();
+    while !extensions.is_empty() {
+        let (_, extension, rest) = der_read(extensions, DER_SEQUENCE)?;
+        extensions = rest;
+        let (_, oid, mut value) = der_read(extension, DER_OID)?;
+        if value.first() == Some(&DER_BOOLEAN) {
+            value = der_read(value, DER_BOOLEAN)?.2;
+        }
+        found.push((oid, der_read(value, DER_OCTET_STRING)?.1));
+    }
+
+    Option::Some(found)
+}
+
+// The serial number of a DER certificate.
+fn cert_serial(cert: &[u8]) -> Option<&[u8]> {
+    let (_, cert, _) = der_read(cert, DER_SEQUENCE)?;
+    let (_, mut tbs, _) = der_read(cert, DER_SEQUENCE)?;
+    if tbs.first() == Some(&DER_VERSION) {
+        tbs = der_read(tbs, DER_VERSION)?.2;
+    }
+
+    Option::Some(der_read(tbs, DER_INTEGER)?.1)
+}
+
+// Checks that `crl` is signed by `issuer`, is still current at `now`
+// and does not revoke the certificate `cert`.
+fn check_pck_crl(crl: &[u8], issuer: &[u8], cert: &[u8], now: u64) -> Option<()> {
+    let (_, crl, _) = der_read(crl, DER_SEQUENCE)?;
+    let (tbs_raw, mut tbs, rest) = der_read(crl, DER_SEQUENCE)?;
+    let rest = der_read(rest, DER_SEQUENCE)?.2;
+    let (_, signature, _) = der_read(rest, DER_BIT_STRING)?;
+    // The first byte of the bit string counts the unused bits.
+    webpki::EndEntityCert::from(issuer)
+        .ok()?
+        .verify_signature(&webpki::ECDSA_P256_SHA256, tbs_raw, signature.get(1..)?)
+        .ok()?;
+
+    if tbs.first() == Some(&DER_INTEGER) {
+        tbs = der_read(tbs, DER_INTEGER)?.2;
+    }
+    tbs = der_read(tbs, DER_SEQUENCE)?.2;
+    tbs = der_read(tbs, DER_SEQUENCE)?.2;
+    let (this_update, rest) = der_time(tbs)?;
+    tbs = rest;
+    if this_update > now {
+        return Option::None;
+    }
+    // A CRL without a next update is never current.
+    let (next_update, rest) = der_time(tbs)?;
+    tbs = rest;
+    if next_update < now {
+        return Option::None;
+    }
+
+    let serial = cert_serial(cert)?;
+    if tbs.first() == Some(&DER_SEQUENCE) {
+        let (_, mut revoked, _) = der_read(tbs, DER_SEQUENCE)?;
+        while !revoked.is_empty() {
+            let (_, entry, rest) = der_read(revoked, DER_SEQUENCE)?;
+            revoked = rest;
+            if der_read(entry, DER_INTEGER)?.1 == serial {
+                return Option::None;
+            }
+        }
+    }
+
+    Option::Some(())
+}
+
+// Checks that the `nextUpdate` of a TCB info or QE identity is not before `now`.
+fn check_next_update(collateral: &serde_json::Value, now: u64) -> Option<()> {
+    // Formatted as 2023-01-08T12:00:00Z.
+    let next_update = collateral["nextUpdate"].as_str()?.as_bytes();
+    if next_update.len() < 19 {
+        return Option::None;
+    }
+    let field = |range: core::ops::Range<usize>| decimal(next_update.get(range)?);
+    let next_update = unix_time(
+        field(0..4)?,
+        field(5..7)?,
+        field(8..10)?,
+        field(11..13)?,
+        field(14..16)?,
+        field(17..19)?,
+    )?;
+    if next_update < now {
+        return Option::None;
+    }
+
+    Option::Some(())
+}
+
+// Reads a DER element with tag `tag`, returning its encoding, its contents and the rest of the input.
+fn der_read(data: &[u8], tag: u8) -> Option<(&[u8], &[u8], &[u8])> {
+    let (found, element, contents, rest) = der_element(data)?;
+    if found != tag {
+        return Option::None;
+    }
+
+    Option::Some((element, contents, rest))
+}
+
+// Reads a DER element of any tag.
+fn der_any(data: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let (_, element, contents, rest) = der_element(data)?;
+    Option::Some((element, contents, rest))
+}
+
+fn der_element(data: &[u8]) -> Option<(u8, &[u8], &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first = *data.get(1)?;
+    let (len, header) = if first & 0x80 == 0 {
+        (first as usize, 2)
+    } else {
+        let octets = (first & 0x7f) as usize;
+        if octets == 0 || octets > 4 {
+            return Option::None;
+        }
+        let len = data.get(2..2 + octets)?.iter().fold(0usize, |len, byte| (len << 8) | *byte as usize);
+        (len, 2 + octets)
+    };
+    let end = header.checked_add(len)?;
+
+    Option::Some((tag, data.get(..end)?, data.get(header..end)?, data.get(end..)?))
+}
+
+// Decodes a non-negative DER integer of at most 64 bits.
+fn der_uint(int: &[u8]) -> Option<u64> {
+    let start = int.iter().position(|byte| *byte != 0).unwrap_or(int.len());
+    if int.len() - start > 8 {
+        return Option::None;
+    }
+
+    Option::Some(int[start..].iter().fold(0u64, |value, byte| (value << 8) | *byte as u64))
+}
+
+// Reads a UTCTime or GeneralizedTime, returning seconds since the unix epoch and the rest of the input.
+fn der_time(data: &[u8]) -> Option<(u64, &[u8])> {
+    let (tag, _, time, rest) = der_element(data)?;
+    let (year, time) = match tag {
+        // Two digit years below 50 are in the 21st century.
+        DER_UTC_TIME => {
+            let year = decimal(time.get(0..2)?)?;
+            (if year < 50 { 2000 + year } else { 1900 + year }, time.get(2..)?)
+        },
+        DER_GENERALIZED_TIME => (decimal(time.get(0..4)?)?, time.get(4..)?),
+        _ => return Option::None,
+    };
+    if time.len() != 11 || time[10] != b'Z' {
+        return Option::None;
+    }
+    let field = |start: usize| decimal(&time[start..start + 2]);
+    let seconds = unix_time(year, field(0)?, field(2)?, field(4)?, field(6)?, field(8)?)?;
+
+    Option::Some((seconds, rest))
+}
+
+fn decimal(digits: &[u8]) -> Option<u64> {
+    digits.iter().try_fold(0u64, |value, digit| {
+        if !digit.is_ascii_digit() {
+            return Option::None;
+        }
+        Option::Some(value * 10 + (digit - b'0') as u64)
+    })
+}
+
+// Seconds since the unix epoch of a UTC date, using the days from civil algorithm.
+fn unix_time(year: u64, month: u64, day: u64, hours: u64, minutes: u64, seconds: u64) -> Option<u64> {
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) || hours > 23 || minutes > 59 || seconds > 60 {
+        return Option::None;
+    }
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    // Days from 0000-03-01 to 1970-01-01.
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+
+    Option::Some(days * 86_400 + hours * 3_600 + minutes * 60 + seconds)
+}
+
+fn read_bytes<'a>(data: &'a [u8], offset: &mut usize, len: usize) -> Option<&'a [u8]> {
+    let bytes = data.get(*offset..offset.checked_add(len)?)?;
+    *offset += len;
+    Option::Some(bytes)
+}
+
+// Verifies that the first certificate of `chain` leads to one of `trust_anchors`
+// through the rest of the chain.
+fn verify_cert_chain<'a>(
+    chain: &'a [Vec<u8>],
+    trust_anchors: &webpki::TLSServerTrustAnchors,
+    now: u64,
+) -> Option<webpki::EndEntityCert<'a>> {
+    let (leaf, intermediates) = chain.split_first()?;
+    let leaf = webpki::EndEntityCert::from(leaf.as_slice()).ok()?;
+    let intermediates: Vec<&[u8]> = intermediates.iter().map(|cert| cert.as_slice()).collect();
+
+    leaf.verify_is_valid_tls_server_cert(
+        DCAP_SIG_ALGS,
+        trust_anchors,
+        &intermediates,
+        webpki::Time::from_seconds_since_unix_epoch(now),
+    ).ok()?;
+
+    Option::Some(leaf)
+}
+
+// Decodes the DER certificates of a PEM chain.
+fn decode_pem_chain(pem: &[u8]) -> Option<Vec<Vec<u8>>> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let mut pem = core::str::from_utf8(pem).ok()?.trim_end_matches('\0');
+    let mut chain = Vec::new();
+    while let Some(start) = pem.find(BEGIN) {
+        let rest = &pem[start + BEGIN.len()..];
+        let end = rest.find(END)?;
+        let body: Vec<u8> = rest[..end].bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+        chain.push(base64::decode(&body).ok()?);
+        pem = &rest[end + END.len()..];
+    }
+
+    if chain.is_empty() {
+        return Option::None;
+    }
+
+    Option::Some(chain)
+}
+
+// Encodes a raw r || s P-256 signature as the DER `ECDSA-Sig-Value` webpki expects.
+fn ecdsa_raw_to_der(signature: &[u8]) -> Option<Vec<u8>> {
+    if signature.len() != 64 {
+        return Option::None;
+    }
+
+    let mut body = Vec::with_capacity(70);
+    for int in [&signature[..32], &signature[32..]] {
+        let start = int.iter().position(|byte| *byte != 0).unwrap_or(int.len() - 1);
+        let int = &int[start..];
+        let pad = int[0] & 0x80 != 0;
+        body.push(0x02);
+        body.push((int.len() + pad as usize) as u8);
+        if pad {
+            body.push(0x00);
+        }
+        body.extend_from_slice(int);
+    }
+
+    let mut der = Vec::with_capacity(body.len() + 2);
+    der.push(0x30);
+    der.push(body.len() as u8);
+    der.extend_from_slice(&body);
+
+    Option::Some(der)
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return Option::None;
+    }
+
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let high = (pair[0] as char).to_digit(16)?;
+            let low = (pair[1] as char).to_digit(16)?;
+            Option::Some((high * 16 + low) as u8)
+        })
+        .collect()
+}
+
 pub fn verify_rsa(key: &[u8], msg: &[u8], sig: &[u8]) -> bool {
     let pk = rsa::RsaPublicKey::from_public_key_der(key).unwrap();
 
@@ -2,6 +2,7 @@
 # Some scheduler credit primitives
 */
 #![cfg_attr(not(feature = "std"), no_std)]
+use codec::Codec;
 use frame_support::dispatch::DispatchResult;
 /// API necessary for Scheduler record ops about credit.
 pub trait SchedulerCreditCounter<SchedulerCtrlAccountId> {
@@ -12,6 +13,9 @@ pub trait SchedulerCreditCounter<SchedulerCtrlAccountId> {
   /// Record the number of times the scheduler has been punished
 	fn record_punishment(scheduler_id: &SchedulerCtrlAccountId) -> DispatchResult;
 
+  /// The credit score of the scheduler over the last periods, out of the full credit score
+	fn current_credit(scheduler_id: &SchedulerCtrlAccountId) -> u32;
+
 }
 
 /// Stash account finder, used to find the corresponding Stash account according to the Controller account
@@ -19,3 +23,12 @@ pub trait SchedulerStashAccountFinder<AccountId> {
 	/// find the corresponding Stash account according to the Controller account
 	fn find_stash_account_id(ctrl_account_id: &AccountId) -> Option<AccountId>;
 }
+
+sp_api::decl_runtime_apis! {
+	pub trait SchedulerCreditApi<AccountId> where
+		AccountId: Codec,
+	{
+		/// Returns the credit score of a scheduler controller account, out of the full credit score.
+		fn current_credit(acc: AccountId) -> u32;
+	}
+}
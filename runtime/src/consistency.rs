@@ -0,0 +1,92 @@
+//! Invariant checks over the storage pallets, run after every signed extrinsic
+//! when the runtime is built with the `consistency-checks` feature.
+//!
+//! An upload touches file-bank, sminer and storage-handler in one dispatch, a bug
+//! that updates one of them and not the others only shows up much later as a
+//! wrong reward or a failed challenge. With the checks enabled such a dispatch is
+//! rejected along with its storage changes and the broken invariant is logged.
+//! Inherents and unsigned extrinsics are not checked.
+use crate::{AccountId, Runtime, RuntimeCall};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, PostDispatchInfoOf, SignedExtension},
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	DispatchResult,
+};
+
+/// Custom `InvalidTransaction` code of a dispatch that broke an invariant.
+pub const CONSISTENCY_VIOLATION: u8 = 200;
+
+/// Checks the invariants of the storage pallets after the dispatch.
+///
+/// Encodes to nothing, extrinsics are the same with or without the checks.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Default, TypeInfo)]
+pub struct CheckConsistency;
+
+impl CheckConsistency {
+	pub fn new() -> Self {
+		Self
+	}
+
+	/// Every invariant of the storage pallets, including those spanning several of them.
+	pub fn check() -> Result<(), &'static str> {
+		pallet_sminer::Pallet::<Runtime>::do_try_state()?;
+		pallet_storage_handler::Pallet::<Runtime>::do_try_state()?;
+		pallet_file_bank::Pallet::<Runtime>::do_try_state()?;
+		pallet_tee_worker::Pallet::<Runtime>::do_try_state()?;
+
+		let (idle_space, service_space) = pallet_sminer::Pallet::<Runtime>::miner_space_totals();
+		let (total_idle, total_service) =
+			pallet_storage_handler::Pallet::<Runtime>::total_space_records();
+		if idle_space != total_idle {
+			return Err("TotalIdleSpace differs from the idle space of the miners")
+		}
+		if service_space != total_service {
+			return Err("TotalServiceSpace differs from the service space of the miners")
+		}
+
+		Ok(())
+	}
+}
+
+impl sp_std::fmt::Debug for CheckConsistency {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckConsistency")
+	}
+}
+
+impl SignedExtension for CheckConsistency {
+	const IDENTIFIER: &'static str = "CheckConsistency";
+	type AccountId = AccountId;
+	type Call = RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn pre_dispatch(
+		self,
+		_who: &Self::AccountId,
+		_call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		Ok(())
+	}
+
+	fn post_dispatch(
+		_pre: Option<Self::Pre>,
+		_info: &DispatchInfoOf<Self::Call>,
+		_post_info: &PostDispatchInfoOf<Self::Call>,
+		_len: usize,
+		_result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		Self::check().map_err(|e| {
+			log::error!(target: "runtime::consistency", "invariant broken by a dispatch: {}", e);
+			InvalidTransaction::Custom(CONSISTENCY_VIOLATION).into()
+		})
+	}
+}
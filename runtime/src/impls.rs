@@ -1,10 +1,23 @@
-use crate::{AccountId, Assets, Authorship, Balances, NegativeImbalance, Runtime};
-use frame_support::traits::{
-	fungibles::{Balanced, CreditOf},
-	Currency, OnUnbalanced,
+use crate::{
+	AccountId, Assets, Authorship, Balances, FileBank, NegativeImbalance, Runtime, SchedulerCredit,
+	Sminer, StorageHandler, TeeWorker, TransactionPayment,
+};
+use cp_cess_common::{StorageCostEstimate, StorageEvent, StorageEventRouter, G_BYTE, SEGMENT_SIZE};
+use cp_scheduler_credit::SchedulerCreditCounter;
+use frame_support::{
+	dispatch::DispatchResult,
+	traits::{
+		fungibles::{Balanced, CreditOf, Transfer},
+		Contains, Currency, OnUnbalanced,
+	},
 };
 use pallet_asset_tx_payment::HandleCredit;
+use pallet_evm::GasWeightMapping;
+use pallet_file_bank::WeightInfo as _;
+use pallet_storage_handler::weights::WeightInfo as _;
 use pallet_cess_staking::Pallet as StakingPallet;
+use sp_staking::SessionIndex;
+use sp_std::{marker::PhantomData, prelude::*};
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -38,3 +51,118 @@ impl cp_scheduler_credit::SchedulerStashAccountFinder<AccountId> for SchedulerSt
 		}
 	}
 }
+
+/// Hands the storage events of file-bank and audit to the pallets acting on them.
+pub struct StorageEvents;
+
+impl StorageEventRouter<AccountId> for StorageEvents {
+	fn route(event: StorageEvent<AccountId>) {
+		match event {
+			// The tee worker is credited with the bytes of the files it uploaded.
+			StorageEvent::DealCompleted { scheduler, file_size, .. } => {
+				let size = u64::try_from(file_size).unwrap_or(u64::MAX);
+				if let Err(e) = <SchedulerCredit as SchedulerCreditCounter<AccountId>>::record_proceed_block_size(&scheduler, size) {
+					log::error!("record block size of scheduler {:?} failed: {:?}", scheduler, e);
+				}
+			},
+			StorageEvent::ChallengeFailed { miner } => {
+				Sminer::record_challenge_result(&miner, false);
+				// The miner is not paid by storage orders for the day.
+				FileBank::record_challenge_failure(&miner);
+			},
+			// File-bank restores the replicas itself, no other pallet acts on the loss yet.
+			StorageEvent::ReplicaLost { .. } => {},
+		}
+	}
+}
+
+/// The storage unit price is reported with the keys registered as price feeders,
+/// which are separate from the audit keys of the validators.
+pub struct PriceFeeders;
+
+impl Contains<pallet_storage_handler::sr25519::AuthorityId> for PriceFeeders {
+	fn contains(key: &pallet_storage_handler::sr25519::AuthorityId) -> bool {
+		pallet_storage_handler::Pallet::<Runtime>::price_feeders().contains(key)
+	}
+}
+
+/// Invalid file reminders are signed with the audit keys of the current validators.
+pub struct AuditAuthorities;
+
+impl Contains<pallet_audit::sr25519::AuthorityId> for AuditAuthorities {
+	fn contains(key: &pallet_audit::sr25519::AuthorityId) -> bool {
+		pallet_audit::Pallet::<Runtime>::keys().contains(key)
+	}
+}
+
+/// The gateways registered in the oss pallet endorse storage miners.
+pub struct OssGateways;
+
+impl Contains<AccountId> for OssGateways {
+	fn contains(acc: &AccountId) -> bool {
+		pallet_oss::Pallet::<Runtime>::oss(acc).is_some()
+	}
+}
+
+/// Packages are paid in the assets of the assets pallet.
+pub struct AssetPackagePayment;
+
+impl pallet_storage_handler::PackagePayment<AccountId> for AssetPackagePayment {
+	type AssetId = u32;
+
+	fn transfer_asset(asset: u32, from: &AccountId, to: &AccountId, amount: u128) -> DispatchResult {
+		<Assets as Transfer<AccountId>>::transfer(asset, from, to, amount, true).map(|_| ())
+	}
+}
+
+/// Selects the tee worker committee of every new session, then hands the session to `I`.
+pub struct RotateTeeCommittee<I>(PhantomData<I>);
+
+impl<I: pallet_session::SessionManager<AccountId>> pallet_session::SessionManager<AccountId>
+	for RotateTeeCommittee<I>
+{
+	fn new_session(new_index: SessionIndex) -> Option<Vec<AccountId>> {
+		TeeWorker::rotate_committee(new_index);
+		I::new_session(new_index)
+	}
+
+	fn new_session_genesis(new_index: SessionIndex) -> Option<Vec<AccountId>> {
+		I::new_session_genesis(new_index)
+	}
+
+	fn end_session(end_index: SessionIndex) {
+		I::end_session(end_index)
+	}
+
+	fn start_session(start_index: SessionIndex) {
+		I::start_session(start_index)
+	}
+}
+
+/// Quotes storing `replication` copies of a file of `file_size` bytes in a new package.
+///
+/// The space is charged like `upload_declaration` charges it, every segment taking one and a half
+/// segments of fragments. The fees are of `buy_space` and one `upload_declaration` per copy,
+/// and the gas is what the storage precompile charges for the same calls.
+pub fn estimate_storage_cost(file_size: u128, replication: u32) -> Option<StorageCostEstimate> {
+	let segment_count = file_size.checked_add(SEGMENT_SIZE - 1)? / SEGMENT_SIZE;
+	let charged_space = segment_count
+		.checked_mul(SEGMENT_SIZE * 15 / 10)?
+		.checked_mul(replication.max(1) as u128)?;
+	// The available space must be larger than the charged space.
+	let package_gib: u32 = (charged_space / G_BYTE + 1).try_into().ok()?;
+	let package_fee = StorageHandler::unit_price()?.checked_mul(package_gib as u128)?;
+
+	let weight = <Runtime as pallet_storage_handler::Config>::WeightInfo::buy_space().saturating_add(
+		<Runtime as pallet_file_bank::Config>::WeightInfo::upload_declaration()
+			.saturating_mul(replication.max(1) as u64),
+	);
+
+	Some(StorageCostEstimate {
+		charged_space,
+		package_gib,
+		package_fee,
+		transaction_fee: TransactionPayment::weight_to_fee(weight),
+		evm_gas: <Runtime as pallet_evm::Config>::GasWeightMapping::weight_to_gas(weight),
+	})
+}
@@ -30,6 +30,7 @@ use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use pallet_session::historical as pallet_session_historical;
 pub use pallet_transaction_payment::{CurrencyAdapter, Multiplier, TargetedFeeAdjustment};
 use pallet_transaction_payment::{FeeDetails, RuntimeDispatchInfo};
+use cp_cess_common::slash::{SlashPolicy, SlashSource, SlashSplit};
 use sp_api::impl_runtime_apis;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H160, H256, U256};
@@ -78,9 +79,11 @@ use frame_system::{
 };
 
 pub mod impls;
-use impls::{Author, CreditToBlockAuthor, SchedulerStashAccountFinder};
+use impls::{
+	AssetPackagePayment, AuditAuthorities, Author, CreditToBlockAuthor, OssGateways, PriceFeeders,
+	RotateTeeCommittee, SchedulerStashAccountFinder, StorageEvents,
+};
 // use frame_support::traits::OnRuntimeUpgrade;
-// pub use pallet_file_bank::migrations::TestMigrationFileBank;
 // pub use pallet_audit::migrations::MigrationSegmentBook;
 
 pub mod constants;
@@ -97,6 +100,9 @@ use pallet_election_provider_multi_phase::SolutionAccuracyOf;
 mod precompiles;
 use precompiles::FrontierPrecompiles;
 
+#[cfg(feature = "consistency-checks")]
+pub mod consistency;
+
 #[cfg(any(feature = "std", test))]
 pub use pallet_cess_staking::StakerStatus;
 #[cfg(any(feature = "std", test))]
@@ -170,10 +176,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 107,
+	spec_version: 108,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -203,6 +209,40 @@ impl OnUnbalanced<NegativeImbalance> for DealWithFees {
 	}
 }
 
+// Whatever a split leaves over is burned.
+parameter_types! {
+	pub SchedulerSlashSplit: SlashSplit = SlashSplit {
+		treasury: Perbill::from_percent(100),
+		users: Perbill::from_percent(0),
+		reporter: Perbill::from_percent(0),
+	};
+	pub MinerChallengeSlashSplit: SlashSplit = SlashSplit {
+		treasury: Perbill::from_percent(80),
+		users: Perbill::from_percent(0),
+		reporter: Perbill::from_percent(0),
+	};
+	pub MinerClearSlashSplit: SlashSplit = SlashSplit {
+		treasury: Perbill::from_percent(50),
+		users: Perbill::from_percent(0),
+		reporter: Perbill::from_percent(0),
+	};
+}
+
+/// Destination of the funds slashed from schedulers and storage miners.
+pub struct CessSlashPolicy;
+impl SlashPolicy<AccountId, Balances> for CessSlashPolicy {
+	type Treasury = Treasury;
+
+	fn split(source: SlashSource) -> SlashSplit {
+		match source {
+			SlashSource::Scheduler => SchedulerSlashSplit::get(),
+			SlashSource::MinerIdle | SlashSource::MinerService => MinerChallengeSlashSplit::get(),
+			SlashSource::MinerClear | SlashSource::MinerInvalidFile | SlashSource::MinerUnreachable =>
+				MinerClearSlashSplit::get(),
+		}
+	}
+}
+
 pub const MILLICENTS: Balance = 10_000_000;
 pub const CENTS: Balance = 1_000 * MILLICENTS; // assume this is worth about a cent.
 pub const DOLLARS: Balance = 100 * CENTS;
@@ -546,7 +586,7 @@ impl pallet_session::Config for Runtime {
 	type ValidatorIdOf = pallet_cess_staking::StashOf<Self>;
 	type ShouldEndSession = Babe;
 	type NextSessionRotation = Babe;
-	type SessionManager = pallet_session::historical::NoteHistoricalRoot<Self, Staking>;
+	type SessionManager = RotateTeeCommittee<pallet_session::historical::NoteHistoricalRoot<Self, Staking>>;
 	type SessionHandler = <opaque::SessionKeys as OpaqueKeys>::KeyTypeIdProviders;
 	type Keys = opaque::SessionKeys;
 	type WeightInfo = pallet_session::weights::SubstrateWeight<Runtime>;
@@ -588,6 +628,7 @@ impl pallet_cess_staking::Config for Runtime {
 	const REWARD_DECREASE_RATIO: Perbill = Perbill::from_perthousand(841);
 	const REWARD_DECREASE_YEARS: u64 = 30;
 	type SminerRewardPool = Sminer;
+	type ValidatorCredits = SchedulerCredit;
 	type MaxNominations = MaxNominations;
 	type Currency = Balances;
 	type CurrencyBalance = Balance;
@@ -596,6 +637,7 @@ impl pallet_cess_staking::Config for Runtime {
 	type RewardRemainder = Treasury;
 	type RuntimeEvent = RuntimeEvent;
 	type Slash = Treasury; // send the slashed funds to the treasury.
+	type SlashPolicy = CessSlashPolicy;
 	type Reward = (); // rewards are minted from the void
 	type SessionsPerEra = SessionsPerEra;
 	type BondingDuration = BondingDuration;
@@ -939,10 +981,16 @@ parameter_types! {
 	pub const DepositBufferPeriod: u32 = 3;
 	pub const MaxAward: u128 = 1_306_849_000_000_000_000;
 	pub const LockInPeriod: u8 = 2;
+	pub const MinerHeartbeatUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 4;
+	pub const MinerHeartbeatInterval: BlockNumber = HOURS;
+	pub const EndorsementInterval: BlockNumber = HOURS;
+	pub const MaxEndorsements: u32 = 100;
+	pub const EndorsementHalfStake: Balance = 10_000 * DOLLARS;
 }
 
 impl pallet_sminer::Config for Runtime {
 	type Currency = Balances;
+	type SlashPolicy = CessSlashPolicy;
 	// The ubiquitous event type.
 	type RuntimeEvent = RuntimeEvent;
 	type PalletId = RewardPalletId;
@@ -958,6 +1006,13 @@ impl pallet_sminer::Config for Runtime {
 	type MaxAward = MaxAward;
 	type LockInPeriod = LockInPeriod;
 	type ChallengeMinerMax = ChallengeMinerMax;
+	type AuthorityId = pallet_sminer::sr25519::AuthorityId;
+	type UnsignedPriority = MinerHeartbeatUnsignedPriority;
+	type HeartbeatInterval = MinerHeartbeatInterval;
+	type Gateways = OssGateways;
+	type EndorsementInterval = EndorsementInterval;
+	type MaxEndorsements = MaxEndorsements;
+	type EndorsementHalfStake = EndorsementHalfStake;
 }
 
 parameter_types! {
@@ -965,6 +1020,15 @@ parameter_types! {
 	pub const FrozenDays: BlockNumber = 7 * DAYS;
 	#[derive(Clone, Eq, PartialEq)]
 	pub const StateStringMax: u32 = 20;
+	pub const MaxLedgerEntries: u32 = 200;
+	pub const TrialDeposit: Balance = 10 * DOLLARS;
+	pub const TrialDepositPeriod: BlockNumber = 30 * DAYS;
+	pub const PriceUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 4;
+	pub const PriceInterval: BlockNumber = DAYS;
+	pub const MinPriceSources: u32 = 3;
+	pub const MaxPriceDeviation: Perbill = Perbill::from_percent(20);
+	pub const MinPriceReports: u32 = 3;
+	pub const PricingAnnouncePeriod: BlockNumber = 7 * DAYS;
 }
 
 impl pallet_storage_handler::Config for Runtime {
@@ -976,6 +1040,26 @@ impl pallet_storage_handler::Config for Runtime {
 	type TreasuryPalletId = TreasuryPalletId;
 	type StateStringMax = StateStringMax;
 	type FrozenDays = FrozenDays;
+	type MaxLedgerEntries = MaxLedgerEntries;
+	type TrialDeposit = TrialDeposit;
+	type TrialDepositPeriod = TrialDepositPeriod;
+	type AuthorityId = pallet_storage_handler::sr25519::AuthorityId;
+	type PriceAuthorities = PriceFeeders;
+	type PriceFeederOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionAtLeast<AccountId, TechnicalCollective, 2, 3>,
+	>;
+	type UnsignedPriority = PriceUnsignedPriority;
+	type PriceInterval = PriceInterval;
+	type MinPriceSources = MinPriceSources;
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type MinPriceReports = MinPriceReports;
+	type PricingAnnouncePeriod = PricingAnnouncePeriod;
+	type PricingCancelOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionAtLeast<AccountId, TechnicalCollective, 2, 3>,
+	>;
+	type PackagePayment = AssetPackagePayment;
 }
 
 parameter_types! {
@@ -993,6 +1077,22 @@ parameter_types! {
 	pub const OneHours: BlockNumber = HOURS;
 	pub const SegUnsignedPriority: TransactionPriority = TransactionPriority::max_value();
 	pub const LockTime: BlockNumber = HOURS / 60;
+	pub const SamplingInterval: BlockNumber = HOURS;
+	pub const SamplingReward: Balance = 10 * CENTS;
+	pub const SamplerBond: Balance = 100 * DOLLARS;
+	pub const SamplerSlash: Perbill = Perbill::from_percent(10);
+	// One era, see `SessionsPerEra`.
+	pub const ExtensionPeriod: BlockNumber = EPOCH_DURATION_IN_BLOCKS * 6;
+	pub const ExtensionLife: BlockNumber = HOURS / 6;
+	pub const ExtensionFee: Balance = 1 * DOLLARS;
+	pub const SpotAuditFee: Balance = 1 * DOLLARS;
+	pub const SpotAuditInterval: BlockNumber = HOURS;
+	pub const MaxSpotAudits: u32 = 50;
+	pub const SeedEpoch: BlockNumber = 6 * HOURS;
+	pub const MinAbsentAttempts: u32 = 3;
+	#[derive(Clone, PartialEq, Eq)]
+	pub const MaxConnectionAttempts: u32 = 10;
+	pub const AbsentAttemptInterval: BlockNumber = 10 * MINUTES;
 }
 
 impl pallet_audit::Config for Runtime {
@@ -1011,6 +1111,7 @@ impl pallet_audit::Config for Runtime {
 	type Scheduler = TeeWorker;
 	type MinerControl = Sminer;
 	type StorageHandle = StorageHandler;
+	type EventRouter = StorageEvents;
 	type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Babe>;
 	type ValidatorSet = Historical;
 	type NextSessionRotation = Babe;
@@ -1019,6 +1120,20 @@ impl pallet_audit::Config for Runtime {
 	type SubmitValidationLimit = SubmitValidationLimit;
 	type ChallengeMinerMax = ChallengeMinerMax;
 	type SigmaMax = SigmaMax;
+	type SamplingInterval = SamplingInterval;
+	type SamplingReward = SamplingReward;
+	type SamplerBond = SamplerBond;
+	type SamplerSlash = SamplerSlash;
+	type ExtensionPeriod = ExtensionPeriod;
+	type ExtensionLife = ExtensionLife;
+	type ExtensionFee = ExtensionFee;
+	type SpotAuditFee = SpotAuditFee;
+	type SpotAuditInterval = SpotAuditInterval;
+	type MaxSpotAudits = MaxSpotAudits;
+	type SeedEpoch = SeedEpoch;
+	type MinAbsentAttempts = MinAbsentAttempts;
+	type MaxConnectionAttempts = MaxConnectionAttempts;
+	type AbsentAttemptInterval = AbsentAttemptInterval;
 }
 
 pub const SEGMENT_COUNT: u32 = 1000;
@@ -1030,7 +1145,10 @@ parameter_types! {
 	#[derive(Clone, Eq, PartialEq)]
 	pub const UploadFillerLimit: u8 = 10;
 	#[derive(Clone, Eq, PartialEq)]
+	pub const BatchDeleteLimit: u32 = 100;
+	#[derive(Clone, Eq, PartialEq)]
 	pub const InvalidLimit: u32 = 100000;
+	pub const InvalidFileGracePeriod: BlockNumber = 3 * DAYS;
 	#[derive(Clone, Eq, PartialEq)]
 	pub const RecoverLimit: u32 = 8000;
 	#[derive(Clone, Eq, PartialEq)]
@@ -1046,6 +1164,29 @@ parameter_types! {
 	#[derive(Clone, Eq, PartialEq)]
 	pub const OwnerLimit: u32 = 50000;
 	#[derive(Clone, Eq, PartialEq)]
+	pub const VersionLimit: u32 = 20;
+	// One era, see `SessionsPerEra`.
+	pub const DeclarationEra: BlockNumber = EPOCH_DURATION_IN_BLOCKS * 6;
+	pub const DeleteGracePeriod: BlockNumber = DAYS * 7;
+	pub const DownloadOwnerShare: Perbill = Perbill::from_percent(70);
+	pub const DownloadMinerShare: Perbill = Perbill::from_percent(20);
+	pub const PopularityEra: BlockNumber = DAYS;
+	pub const PopularityDecay: Perbill = Perbill::from_percent(50);
+	pub const MaxReceiptsPerEra: u32 = 1000;
+	pub const MaxPopularityGain: u32 = 10_000;
+	pub const AuctionThreshold: u128 = 100 * cp_cess_common::G_BYTE;
+	pub const AuctionPeriod: BlockNumber = 10 * MINUTES;
+	pub const MinAuctionBidders: u32 = 3;
+	pub const DedupDiscount: Perbill = Perbill::from_percent(50);
+	pub const MaxPendingDeals: u32 = 50;
+	// Reminders are sent daily within the grace period, a file without a deadline is given one.
+	pub const InvalidFileReminderPeriod: BlockNumber = DAYS;
+	pub const InvalidFileReminderPriority: TransactionPriority = TransactionPriority::max_value() / 4;
+	pub const DeclarationDeposit: Balance = 1 * DOLLARS;
+	pub const MaxDeclarationsPerBlock: u32 = 10;
+	// Files can be paid by storage orders as well as by packages.
+	pub const StorageOrdersEnabled: bool = true;
+	#[derive(Clone, Eq, PartialEq)]
 	pub const NameMinLength: u32 = 3;
 	#[derive(Clone, Eq, PartialEq)]
 	pub const RestoralOrderLife: u32 = 250;
@@ -1070,9 +1211,11 @@ impl pallet_file_bank::Config for Runtime {
 	type Scheduler = TeeWorker;
 	type StringLimit = StringLimit;
 	type OneDay = OneDay;
-	type CreditCounter = SchedulerCredit;
+	type EventRouter = StorageEvents;
 	type UploadFillerLimit = UploadFillerLimit;
+	type BatchDeleteLimit = BatchDeleteLimit;
 	type InvalidLimit = InvalidLimit;
+	type InvalidFileGracePeriod = InvalidFileGracePeriod;
 	type RecoverLimit = RecoverLimit;
 	type OssFindAuthor = Oss;
 	type BucketLimit = BucketLimit;
@@ -1084,6 +1227,31 @@ impl pallet_file_bank::Config for Runtime {
 	type NameMinLength = NameMinLength;
 	type RestoralOrderLife = RestoralOrderLife;
 	type MissionCount = MissionCount;
+	type ForceOrigin = EnsureRootOrHalfCouncil;
+	type VersionLimit = VersionLimit;
+	type DeclarationEra = DeclarationEra;
+	type DeleteGracePeriod = DeleteGracePeriod;
+	type Currency = Balances;
+	type TreasuryPalletId = TreasuryPalletId;
+	type OwnerShare = DownloadOwnerShare;
+	type MinerShare = DownloadMinerShare;
+	type PopularityEra = PopularityEra;
+	type PopularityDecay = PopularityDecay;
+	type MaxReceiptsPerEra = MaxReceiptsPerEra;
+	type MaxPopularityGain = MaxPopularityGain;
+	type AuctionThreshold = AuctionThreshold;
+	type AuctionPeriod = AuctionPeriod;
+	type MinAuctionBidders = MinAuctionBidders;
+	type DedupDiscount = DedupDiscount;
+	type Gateways = OssGateways;
+	type MaxPendingDeals = MaxPendingDeals;
+	type AuthorityId = pallet_audit::sr25519::AuthorityId;
+	type Authorities = AuditAuthorities;
+	type InvalidFileReminderPeriod = InvalidFileReminderPeriod;
+	type UnsignedPriority = InvalidFileReminderPriority;
+	type DeclarationDeposit = DeclarationDeposit;
+	type MaxDeclarationsPerBlock = MaxDeclarationsPerBlock;
+	type StorageOrdersEnabled = StorageOrdersEnabled;
 }
 
 parameter_types! {
@@ -1094,6 +1262,15 @@ parameter_types! {
 	pub const ParamsLimit: u32 = 359;
 	#[derive(Clone, Eq, PartialEq)]
 	pub const MaxWhitelist: u32 = 200;
+	pub const TeeHeartbeatInterval: BlockNumber = HOURS;
+	pub const TeeOfflineThreshold: u32 = 3;
+	pub const TeeEvictionThreshold: u32 = 24;
+	#[derive(Clone, Eq, PartialEq)]
+	pub const TeeMaxEndpoints: u32 = 8;
+	pub const TeeCommitteeSize: u32 = 5;
+	pub const TeeWorkerBond: Balance = 1_000 * DOLLARS;
+	pub const TeeBondSlashFraction: Perbill = Perbill::from_percent(10);
+	pub const TeeBondUnbondingPeriod: BlockNumber = 28 * DAYS;
 	// #[derive(Clone, Eq, PartialEq)]
 	// pub const ReportLength: u32 = 1354;
 	// #[derive(Clone, Eq, PartialEq)]
@@ -1111,6 +1288,17 @@ impl pallet_tee_worker::Config for Runtime {
 	type CreditCounter = SchedulerCredit;
 	type ParamsLimit = ParamsLimit;
 	type MaxWhitelist = MaxWhitelist;
+	type OnExit = (FileBank, Audit);
+	type HeartbeatInterval = TeeHeartbeatInterval;
+	type OfflineThreshold = TeeOfflineThreshold;
+	type EvictionThreshold = TeeEvictionThreshold;
+	type MaxEndpoints = TeeMaxEndpoints;
+	type CommitteeSize = TeeCommitteeSize;
+	type TeeWorkerBond = TeeWorkerBond;
+	type BondSlashFraction = TeeBondSlashFraction;
+	type BondUnbondingPeriod = TeeBondUnbondingPeriod;
+	type BondSlash = Treasury;
+	type UnixTime = Timestamp;
 	// type AuthorityId = pallet_tee_worker::ed25519::AuthorityId;
 }
 
@@ -1125,6 +1313,105 @@ impl pallet_oss::Config for Runtime {
 	type P2PLength = P2PLength;
 }
 
+parameter_types! {
+	#[derive(Clone, Eq, PartialEq)]
+	pub const ChangelogFeatureLimit: u32 = 128;
+	// Keep in sync with the features shipped by the CESS pallets.
+	pub ChangelogFeatures: Vec<&'static [u8]> = vec![
+		b"file-bank/deal-scheduler",
+		b"file-bank/force-reassign-deal",
+		b"file-bank/file-acl",
+		b"file-bank/public-dataset",
+		b"file-bank/segment-commitment",
+		b"file-bank/segment-report-timeout",
+		b"file-bank/file-versioning",
+		b"file-bank/lease-sweep-task",
+		b"file-bank/declaration-limit",
+		b"file-bank/mirror-declaration",
+		b"file-bank/scheduled-deletion",
+		b"file-bank/download-market",
+		b"file-bank/file-popularity",
+		b"file-bank/deal-auction",
+		b"file-bank/batch-delete",
+		b"file-bank/file-ref-count",
+		b"file-bank/file-summary",
+		b"file-bank/invalid-file-queue",
+		b"file-bank/invalid-file-reminder",
+		b"file-bank/restoral-tag",
+		b"file-bank/recovery-queue",
+		b"file-bank/buckets",
+		b"file-bank/bucket-policy",
+		b"file-bank/ownership-transfer",
+		b"file-bank/share-links",
+		b"file-bank/file-size-limit",
+		b"file-bank/pending-deal-limit",
+		b"file-bank/declaration-rate-limit",
+		b"file-bank/user-file-trie",
+		b"file-bank/filler-index",
+		b"file-bank/storage-orders",
+		b"file-bank/evm-precompile",
+		b"storage-handler/package-renewal",
+		b"storage-handler/package-upgrade",
+		b"storage-handler/lease-expire-queue",
+		b"storage-handler/lease-grace-state",
+		b"storage-handler/referral-code",
+		b"storage-handler/region-pricing",
+		b"storage-handler/trial-deposit",
+		b"storage-handler/price-oracle-quorum",
+		b"storage-handler/timelocked-pricing",
+		b"storage-handler/asset-payment",
+		b"storage-handler/package-catalogue",
+		b"storage-handler/account-statement",
+		b"audit/sampling",
+		b"audit/bonded-sampler",
+		b"audit/challenge-extension",
+		b"audit/spot-audit",
+		b"audit/spot-audit-rotation",
+		b"audit/commit-reveal-seed",
+		b"audit/proof-format",
+		b"audit/liveness-evidence",
+		b"audit/distinct-challenge-votes",
+		b"sminer/reputation",
+		b"sminer/endorsements",
+		b"sminer/pricing-floor",
+		b"sminer/onboarding",
+		b"tee-worker/multi-podr2-key",
+		b"tee-worker/attestation-expiry",
+		b"tee-worker/dcap-attestation",
+		b"tee-worker/endpoint-registry",
+		b"tee-worker/worker-exit",
+		b"tee-worker/heartbeat-eviction",
+		b"tee-worker/whitelist-governance",
+		b"tee-worker/load-balancing",
+		b"tee-worker/stash-index",
+		b"tee-worker/worker-roles",
+		b"tee-worker/verdict-nonce",
+		b"tee-worker/bond-slashing",
+	];
+	// Replace with the migrations of each runtime upgrade.
+	pub ChangelogMigrations: Vec<&'static [u8]> = vec![
+		b"storage-handler/lease-expire-queue-v2",
+		b"tee-worker/podr2-key-v1",
+		b"tee-worker/attestation-expiry-v2",
+		b"tee-worker/endpoints-v3",
+		b"tee-worker/stash-index-v4",
+		b"tee-worker/worker-roles-v5",
+		b"file-bank/file-ref-count-v3",
+		b"file-bank/user-file-trie-v4",
+		b"file-bank/filler-index-v5",
+		b"file-bank/deal-scheduler-v6",
+		b"audit/proof-format-v2",
+		b"audit/challenge-extension-v3",
+	];
+}
+
+impl pallet_changelog::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Features = ChangelogFeatures;
+	type Migrations = ChangelogMigrations;
+	type FeatureLimit = ChangelogFeatureLimit;
+}
+
 impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime
 where
 	RuntimeCall: From<LocalCall>,
@@ -1154,6 +1441,8 @@ where
 			frame_system::CheckNonce::<Runtime>::from(nonce),
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+			#[cfg(feature = "consistency-checks")]
+			consistency::CheckConsistency::new(),
 		);
 		let raw_payload = SignedPayload::new(call, extra)
 			.map_err(|e| {
@@ -1459,10 +1748,12 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
  */
 parameter_types! {
 	pub const PeriodDuration: BlockNumber = EPOCH_DURATION_IN_BLOCKS * SessionsPerEra::get();
+	pub const SchedulerCreditDecay: Percent = Percent::from_percent(20);
 }
 
 impl pallet_scheduler_credit::Config for Runtime {
 	type PeriodDuration = PeriodDuration;
+	type CreditDecay = SchedulerCreditDecay;
 	type StashAccountFinder = SchedulerStashAccountFinder;
 }
 
@@ -1535,6 +1826,7 @@ construct_runtime!(
 		SchedulerCredit: pallet_scheduler_credit = 65,
 		Oss: pallet_oss = 66,
 		Cacher: pallet_cacher = 67,
+		Changelog: pallet_changelog = 68,
 	}
 );
 
@@ -1560,6 +1852,19 @@ pub type SignedBlock = generic::SignedBlock<Block>;
 /// BlockId type as expected by this runtime.
 pub type BlockId = generic::BlockId<Block>;
 /// The SignedExtension to the basic transaction logic.
+#[cfg(not(feature = "consistency-checks"))]
+pub type SignedExtra = (
+	frame_system::CheckNonZeroSender<Runtime>,
+	frame_system::CheckSpecVersion<Runtime>,
+	frame_system::CheckTxVersion<Runtime>,
+	frame_system::CheckGenesis<Runtime>,
+	frame_system::CheckEra<Runtime>,
+	frame_system::CheckNonce<Runtime>,
+	frame_system::CheckWeight<Runtime>,
+	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+);
+/// The SignedExtension to the basic transaction logic, checking the storage invariants last.
+#[cfg(feature = "consistency-checks")]
 pub type SignedExtra = (
 	frame_system::CheckNonZeroSender<Runtime>,
 	frame_system::CheckSpecVersion<Runtime>,
@@ -1569,6 +1874,7 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	consistency::CheckConsistency,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic =
@@ -1584,7 +1890,12 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
-	// TestMigrationFileBank<Runtime>,
+	(
+		pallet_storage_handler::migrations::MigrationLeaseExpireQueue<Runtime>,
+		pallet_tee_worker::migrations::MigrationPodr2Key<Runtime>,
+		pallet_file_bank::migrations::MigrationFileBank<Runtime>,
+		pallet_audit::migrations::MigrationProofFormat<Runtime>,
+	),
 	// MigrationSegmentBook<Runtime>,
 >;
 
@@ -1848,6 +2159,92 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_file_bank_runtime_api::FileBankApi<Block, AccountId, BlockNumber> for Runtime {
+		fn deal_scheduler(deal_hash: cp_cess_common::Hash) -> Option<(AccountId, cp_cess_common::PeerId, BlockNumber)> {
+			FileBank::get_deal_scheduler(&deal_hash)
+		}
+
+		fn query_user_space(acc: AccountId) -> Option<cp_cess_common::SpaceInfo<BlockNumber>> {
+			StorageHandler::get_user_space_info(&acc)
+		}
+
+		fn query_file(file_hash: cp_cess_common::Hash) -> Option<cp_cess_common::FileBrief<AccountId, BlockNumber>> {
+			FileBank::get_file_brief(&file_hash)
+		}
+
+		fn miner_list() -> Vec<AccountId> {
+			<Sminer as pallet_sminer::MinerControl<AccountId>>::get_all_miner().unwrap_or_default()
+		}
+
+		fn file_popularity(file_hash: cp_cess_common::Hash) -> u32 {
+			FileBank::get_file_popularity(&file_hash)
+		}
+
+		fn hot_files(limit: u32) -> Vec<(cp_cess_common::Hash, u32)> {
+			FileBank::get_hot_files(limit)
+		}
+
+		fn account_statement(
+			acc: AccountId,
+			from_block: BlockNumber,
+			to_block: BlockNumber,
+		) -> cp_cess_common::AccountStatement<BlockNumber> {
+			StorageHandler::account_statement(&acc, from_block, to_block)
+		}
+
+		fn user_buckets(acc: AccountId) -> Vec<Vec<u8>> {
+			FileBank::get_user_buckets(&acc)
+		}
+
+		fn bucket_files(acc: AccountId, bucket_name: Vec<u8>) -> Option<Vec<cp_cess_common::Hash>> {
+			FileBank::get_bucket_files(&acc, bucket_name)
+		}
+
+		fn miner_reputation(acc: AccountId) -> cp_cess_common::ReputationBrief {
+			Sminer::get_reputation_brief(&acc)
+		}
+
+		fn file_storage(file_hash: cp_cess_common::Hash) -> (Vec<u8>, Option<Vec<u8>>) {
+			FileBank::get_file_storage(&file_hash)
+		}
+
+		fn user_file_storage(acc: AccountId, file_hash: cp_cess_common::Hash) -> (Vec<u8>, Vec<u8>, Option<u128>) {
+			FileBank::get_user_file_storage(&acc, &file_hash)
+		}
+
+		fn query_package_price(acc: AccountId, package_type: cp_cess_common::LedgerKind, count: u32) -> Option<Balance> {
+			StorageHandler::quote_package_price(&acc, package_type, count).ok()
+		}
+
+		fn call_weights() -> Vec<cp_cess_common::CallWeight> {
+			FileBank::get_call_weights()
+		}
+
+		fn estimate_storage_cost(file_size: u128, replication: u32) -> Option<cp_cess_common::StorageCostEstimate> {
+			impls::estimate_storage_cost(file_size, replication)
+		}
+
+		fn region_heatmap() -> Vec<cp_cess_common::RegionAvailability> {
+			Sminer::region_heatmap().into_inner()
+		}
+
+		fn file_state_changes(file_hash: cp_cess_common::Hash) -> Vec<Vec<u8>> {
+			System::read_events_no_consensus()
+				.filter_map(|record| match &record.event {
+					RuntimeEvent::FileBank(event) => FileBank::file_state_transition(event, &file_hash),
+					_ => None,
+				})
+				.map(|state| state.to_vec())
+				.collect()
+		}
+	}
+
+	impl cp_scheduler_credit::SchedulerCreditApi<Block, AccountId> for Runtime {
+		fn current_credit(acc: AccountId) -> u32 {
+			SchedulerCredit::current_credit(&acc)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentCallApi<Block, Balance, RuntimeCall>
 		for Runtime
 	{
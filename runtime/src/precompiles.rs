@@ -6,22 +6,28 @@ use pallet_evm_precompile_modexp::Modexp;
 use pallet_evm_precompile_sha3fips::Sha3FIPS256;
 use pallet_evm_precompile_simple::{ECRecover, ECRecoverPublicKey, Identity, Ripemd160, Sha256};
 
+mod storage;
+pub use storage::StoragePrecompile;
+
+// Address of the storage precompile, the first address after the Frontier specific ones.
+pub const STORAGE_PRECOMPILE: u64 = 2048;
+
 pub struct FrontierPrecompiles<R>(PhantomData<R>);
 
 impl<R> FrontierPrecompiles<R>
 where
-	R: pallet_evm::Config,
+	R: pallet_evm::Config + pallet_file_bank::Config + pallet_storage_handler::Config,
 {
 	pub fn new() -> Self {
 		Self(Default::default())
 	}
 	pub fn used_addresses() -> sp_std::vec::Vec<H160> {
-		sp_std::vec![1, 2, 3, 4, 5, 1024, 1025].into_iter().map(|x| hash(x)).collect()
+		sp_std::vec![1, 2, 3, 4, 5, 1024, 1025, STORAGE_PRECOMPILE].into_iter().map(|x| hash(x)).collect()
 	}
 }
 impl<R> PrecompileSet for FrontierPrecompiles<R>
 where
-	R: pallet_evm::Config,
+	R: pallet_evm::Config + pallet_file_bank::Config + pallet_storage_handler::Config,
 {
 	fn execute(
 		&self, handle: &mut impl PrecompileHandle
@@ -38,6 +44,9 @@ where
 				Some(Sha3FIPS256::execute(handle)),
 			a if a == hash(1025) =>
 				Some(ECRecoverPublicKey::execute(handle)),
+			// CESS precompiles :
+			a if a == hash(STORAGE_PRECOMPILE) =>
+				Some(StoragePrecompile::<R>::execute(handle)),
 			_ => None,
 		}
 	}
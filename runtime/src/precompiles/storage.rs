@@ -0,0 +1,346 @@
+//! Storage precompile, lets contracts buy packages, declare files and read the space of an account.
+//!
+//! The calls are ABI encoded, the caller of the precompile is mapped to its substrate account
+//! with the `AddressMapping` of the evm pallet and acts as a signed origin:
+//!
+//! - `buyPackage(uint32 gibCount)`
+//! - `uploadDeclaration(bytes fileHash, bytes dealInfo, bytes fileName, bytes bucketName, uint256 fileSize)`,
+//!   `dealInfo` is the SCALE encoded segment list of the deal.
+//! - `queryUserSpace(address user) returns (uint256 totalSpace, uint256 usedSpace,
+//!   uint256 lockedSpace, uint256 remainingSpace, uint256 deadline)`
+//!
+//! The gas of a call is its pallet weight, successful calls emit a log of the precompile address.
+//! Calls changing state must be made to the precompile directly, a contract reaching it through
+//! DELEGATECALL or CALLCODE would otherwise spend the balance of whoever called that contract.
+use codec::Decode;
+use cp_cess_common::Hash;
+use fp_evm::{
+	ExitError, ExitRevert, ExitSucceed, PrecompileFailure, PrecompileHandle, PrecompileOutput,
+	PrecompileResult,
+};
+use frame_support::{dispatch::DispatchResult, traits::Get, weights::Weight, BoundedVec};
+use pallet_evm::{AddressMapping, GasWeightMapping};
+use pallet_file_bank::{FileHash, SegmentList, UserBrief, WeightInfo as FileBankWeightInfo};
+use pallet_storage_handler::weights::WeightInfo as StorageHandlerWeightInfo;
+use sp_core::{H160, H256};
+use sp_runtime::traits::SaturatedConversion;
+use sp_std::{marker::PhantomData, vec::Vec};
+
+// Gas of a log, its topics and its data bytes, as charged by the evm for LOG opcodes.
+const LOG_GAS: u64 = 375;
+const LOG_TOPIC_GAS: u64 = 375;
+const LOG_DATA_GAS: u64 = 8;
+
+enum Action {
+	BuyPackage,
+	UploadDeclaration,
+	QueryUserSpace,
+}
+
+impl Action {
+	fn from_selector(selector: &[u8]) -> Option<Self> {
+		[Action::BuyPackage, Action::UploadDeclaration, Action::QueryUserSpace]
+			.into_iter()
+			.find(|action| selector == &sp_io::hashing::keccak_256(action.signature())[..4])
+	}
+
+	fn signature(&self) -> &'static [u8] {
+		match self {
+			Action::BuyPackage => b"buyPackage(uint32)",
+			Action::UploadDeclaration => b"uploadDeclaration(bytes,bytes,bytes,bytes,uint256)",
+			Action::QueryUserSpace => b"queryUserSpace(address)",
+		}
+	}
+}
+
+pub struct StoragePrecompile<R>(PhantomData<R>);
+
+impl<R> StoragePrecompile<R>
+where
+	R: pallet_evm::Config + pallet_file_bank::Config + pallet_storage_handler::Config,
+{
+	pub fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		if input.len() < 4 {
+			return Err(revert(b"missing selector"))
+		}
+		let action = Action::from_selector(&input[..4]).ok_or_else(|| revert(b"unknown selector"))?;
+		let args = &input[4..];
+
+		match action {
+			Action::BuyPackage => Self::buy_package(handle, args),
+			Action::UploadDeclaration => Self::upload_declaration(handle, args),
+			Action::QueryUserSpace => Self::query_user_space(handle, args),
+		}
+	}
+
+	fn buy_package(handle: &mut impl PrecompileHandle, args: &[u8]) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		record_weight::<R>(handle, <R as pallet_storage_handler::Config>::WeightInfo::buy_space())?;
+		let gib_count: u32 = read_uint(args, 0, 4)?.saturated_into();
+
+		let caller = handle.context().caller;
+		let origin = signed_origin::<R>(caller);
+		dispatch(pallet_storage_handler::Pallet::<R>::buy_space(origin, gib_count, None, None))?;
+
+		emit_log(handle, b"PackageBought(address,uint32)", caller, encode_words(&[gib_count as u128]))?;
+		Ok(succeed(Vec::new()))
+	}
+
+	fn upload_declaration(handle: &mut impl PrecompileHandle, args: &[u8]) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		record_weight::<R>(
+			handle,
+			<R as pallet_file_bank::Config>::WeightInfo::upload_declaration()
+				.saturating_add(<R as pallet_file_bank::Config>::WeightInfo::user_file_root()),
+		)?;
+
+		let raw_hash = read_bytes(args, 0)?;
+		let file_hash = FileHash(Hash(raw_hash.as_slice().try_into().map_err(|_| revert(b"file hash is not 64 bytes"))?));
+		let deal_info = BoundedVec::<SegmentList<R>, <R as pallet_file_bank::Config>::SegmentCount>::decode(
+			&mut &read_bytes(args, 1)?[..],
+		)
+		.map_err(|_| revert(b"invalid deal info"))?;
+		let file_name = read_bytes(args, 2)?.try_into().map_err(|_| revert(b"file name too long"))?;
+		let bucket_name = read_bytes(args, 3)?.try_into().map_err(|_| revert(b"bucket name too long"))?;
+		let file_size = read_uint(args, 4, 16)?;
+
+		let caller = handle.context().caller;
+		let user_brief = UserBrief::<R> { user: R::AddressMapping::into_account_id(caller), file_name, bucket_name };
+		let origin = signed_origin::<R>(caller);
+		dispatch(pallet_file_bank::Pallet::<R>::upload_declaration(
+			origin,
+			file_hash,
+			deal_info,
+			user_brief,
+			file_size,
+			None,
+		))?;
+
+		emit_log(handle, b"FileDeclared(address,bytes)", caller, encode_bytes(&file_hash.0 .0))?;
+		Ok(succeed(Vec::new()))
+	}
+
+	fn query_user_space(handle: &mut impl PrecompileHandle, args: &[u8]) -> PrecompileResult {
+		record_weight::<R>(handle, <R as frame_system::Config>::DbWeight::get().reads(1))?;
+		let user = read_address(args, 0)?;
+
+		let acc = R::AddressMapping::into_account_id(user);
+		let words = match pallet_storage_handler::Pallet::<R>::get_user_space_info(&acc) {
+			Some(info) => [
+				info.total_space,
+				info.used_space,
+				info.locked_space,
+				info.remaining_space,
+				info.deadline.saturated_into(),
+			],
+			None => [0; 5],
+		};
+
+		Ok(succeed(encode_words(&words)))
+	}
+}
+
+fn signed_origin<R: pallet_evm::Config>(caller: H160) -> R::RuntimeOrigin {
+	frame_system::RawOrigin::Signed(R::AddressMapping::into_account_id(caller)).into()
+}
+
+fn ensure_mutable(handle: &impl PrecompileHandle) -> Result<(), PrecompileFailure> {
+	if handle.is_static() {
+		return Err(revert(b"cannot modify state in static call"))
+	}
+	// The caller is only the signer when the code run is the precompile at its own address.
+	if handle.code_address() != handle.context().address {
+		return Err(revert(b"cannot be called through delegatecall or callcode"))
+	}
+
+	Ok(())
+}
+
+fn record_weight<R: pallet_evm::Config>(
+	handle: &mut impl PrecompileHandle,
+	weight: Weight,
+) -> Result<(), PrecompileFailure> {
+	handle
+		.record_cost(R::GasWeightMapping::weight_to_gas(weight))
+		.map_err(|exit_status| PrecompileFailure::Error { exit_status })
+}
+
+fn dispatch(result: DispatchResult) -> Result<(), PrecompileFailure> {
+	result.map_err(|e| {
+		let reason: &'static str = e.into();
+		revert(reason.as_bytes())
+	})
+}
+
+fn emit_log(
+	handle: &mut impl PrecompileHandle,
+	signature: &[u8],
+	caller: H160,
+	data: Vec<u8>,
+) -> Result<(), PrecompileFailure> {
+	let topics = sp_std::vec![H256(sp_io::hashing::keccak_256(signature)), H256::from(caller)];
+	let cost = LOG_GAS + LOG_TOPIC_GAS * topics.len() as u64 + LOG_DATA_GAS * data.len() as u64;
+	handle.record_cost(cost).map_err(|exit_status| PrecompileFailure::Error { exit_status })?;
+
+	let address = handle.code_address();
+	handle.log(address, topics, data).map_err(|exit_status| PrecompileFailure::Error { exit_status })
+}
+
+fn succeed(output: Vec<u8>) -> PrecompileOutput {
+	PrecompileOutput { exit_status: ExitSucceed::Returned, output }
+}
+
+fn revert(reason: &[u8]) -> PrecompileFailure {
+	PrecompileFailure::Revert { exit_status: ExitRevert::Reverted, output: reason.to_vec() }
+}
+
+// The `index`th head word of the arguments.
+fn read_word(args: &[u8], index: usize) -> Result<&[u8], PrecompileFailure> {
+	let start = index.checked_mul(32).ok_or_else(|| revert(b"argument out of range"))?;
+	args.get(start..start.saturating_add(32)).ok_or_else(|| revert(b"argument out of range"))
+}
+
+// An unsigned integer argument that must fit in `size` bytes.
+fn read_uint(args: &[u8], index: usize, size: usize) -> Result<u128, PrecompileFailure> {
+	let word = read_word(args, index)?;
+	if word[..32 - size].iter().any(|byte| *byte != 0) {
+		return Err(revert(b"integer argument out of range"))
+	}
+
+	let mut raw = [0u8; 16];
+	raw[16 - size..].copy_from_slice(&word[32 - size..]);
+	Ok(u128::from_be_bytes(raw))
+}
+
+fn read_address(args: &[u8], index: usize) -> Result<H160, PrecompileFailure> {
+	let word = read_word(args, index)?;
+	if word[..12].iter().any(|byte| *byte != 0) {
+		return Err(revert(b"invalid address"))
+	}
+
+	Ok(H160::from_slice(&word[12..]))
+}
+
+// A dynamic `bytes` argument, the head word holds the offset of its length and data.
+fn read_bytes(args: &[u8], index: usize) -> Result<Vec<u8>, PrecompileFailure> {
+	let offset = read_uint(args, index, 4)? as usize;
+	let start = offset.saturating_add(32);
+	let len_word = args.get(offset..start).ok_or_else(|| revert(b"bytes out of range"))?;
+	let len = read_uint(len_word, 0, 4)? as usize;
+	let data = args.get(start..start.saturating_add(len)).ok_or_else(|| revert(b"bytes out of range"))?;
+
+	Ok(data.to_vec())
+}
+
+fn encode_words(values: &[u128]) -> Vec<u8> {
+	let mut output = Vec::with_capacity(values.len() * 32);
+	for value in values {
+		output.extend_from_slice(&[0u8; 16]);
+		output.extend_from_slice(&value.to_be_bytes());
+	}
+
+	output
+}
+
+// The ABI encoding of a single `bytes` value: its offset, its length, then the data padded to words.
+fn encode_bytes(data: &[u8]) -> Vec<u8> {
+	let mut output = encode_words(&[32, data.len() as u128]);
+	output.extend_from_slice(data);
+	output.resize(output.len() + (32 - data.len() % 32) % 32, 0);
+
+	output
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{precompiles::STORAGE_PRECOMPILE, Runtime};
+	use fp_evm::{Context, ExitReason, Transfer};
+
+	struct MockHandle {
+		input: Vec<u8>,
+		context: Context,
+		code_address: H160,
+	}
+
+	impl PrecompileHandle for MockHandle {
+		fn call(
+			&mut self,
+			_to: H160,
+			_transfer: Option<Transfer>,
+			_input: Vec<u8>,
+			_gas_limit: Option<u64>,
+			_is_static: bool,
+			_context: &Context,
+		) -> (ExitReason, Vec<u8>) {
+			unimplemented!()
+		}
+
+		fn record_cost(&mut self, _cost: u64) -> Result<(), ExitError> {
+			Ok(())
+		}
+
+		fn remaining_gas(&self) -> u64 {
+			u64::MAX
+		}
+
+		fn log(&mut self, _address: H160, _topics: Vec<H256>, _data: Vec<u8>) -> Result<(), ExitError> {
+			Ok(())
+		}
+
+		fn code_address(&self) -> H160 {
+			self.code_address
+		}
+
+		fn input(&self) -> &[u8] {
+			&self.input
+		}
+
+		fn context(&self) -> &Context {
+			&self.context
+		}
+
+		fn is_static(&self) -> bool {
+			false
+		}
+
+		fn gas_limit(&self) -> Option<u64> {
+			None
+		}
+	}
+
+	// The precompile code run in the storage of a contract, as DELEGATECALL and CALLCODE do.
+	fn delegated_handle(signature: &[u8], args: Vec<u8>) -> MockHandle {
+		let mut input = sp_io::hashing::keccak_256(signature)[..4].to_vec();
+		input.extend(args);
+		MockHandle {
+			input,
+			context: Context {
+				address: H160::repeat_byte(0x11),
+				caller: H160::repeat_byte(0x22),
+				apparent_value: Default::default(),
+			},
+			code_address: H160::from_low_u64_be(STORAGE_PRECOMPILE),
+		}
+	}
+
+	#[test]
+	fn delegatecall_reverts() {
+		let mut handle = delegated_handle(b"buyPackage(uint32)", encode_words(&[1]));
+		assert!(matches!(
+			StoragePrecompile::<Runtime>::execute(&mut handle),
+			Err(PrecompileFailure::Revert { .. })
+		));
+
+		let mut handle = delegated_handle(
+			b"uploadDeclaration(bytes,bytes,bytes,bytes,uint256)",
+			encode_words(&[0, 0, 0, 0, 1]),
+		);
+		assert!(matches!(
+			StoragePrecompile::<Runtime>::execute(&mut handle),
+			Err(PrecompileFailure::Revert { .. })
+		));
+	}
+}
@@ -0,0 +1,97 @@
+//! Storage economics simulator.
+//!
+//! Drives the runtime through months of miners joining, users buying and renewing
+//! packages, uploads, failed challenges and reward halvings, inside `TestExternalities`,
+//! and writes one csv row of price, capacity and reward metrics per simulated day.
+//! Meant for trying out parameters before proposing them to governance, e.g.
+//!
+//! `cess-sim --days 365 --halving-days 180 --failure-rate 0.05 --output year.csv`
+mod metrics;
+mod scenario;
+
+use clap::Parser;
+use std::{
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::PathBuf,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[command(about = "Simulate the storage market on the runtime and write the metrics as csv")]
+pub struct Cli {
+	/// Number of days to simulate.
+	#[arg(long, default_value_t = 180)]
+	pub days: u32,
+	/// Seed of the random choices, the same seed gives the same run.
+	#[arg(long, default_value_t = 0)]
+	pub seed: u64,
+	/// Unit price at genesis, in dollars per gib and month.
+	#[arg(long, default_value_t = 30)]
+	pub unit_price: u128,
+	/// Miners joining in total.
+	#[arg(long, default_value_t = 100)]
+	pub miners: u32,
+	/// Miners joining per day until all have joined.
+	#[arg(long, default_value_t = 5)]
+	pub miners_per_day: u32,
+	/// Idle space every miner brings, in gib.
+	#[arg(long, default_value_t = 4096)]
+	pub miner_gib: u32,
+	/// Collateral every miner stakes, in dollars.
+	#[arg(long, default_value_t = 4000)]
+	pub miner_collateral: u128,
+	/// Users buying a package in total.
+	#[arg(long, default_value_t = 1000)]
+	pub users: u32,
+	/// Users buying a package per day until all have bought one.
+	#[arg(long, default_value_t = 20)]
+	pub users_per_day: u32,
+	/// Size of the package every user buys, in gib.
+	#[arg(long, default_value_t = 100)]
+	pub package_gib: u32,
+	/// Most gib a user uploads per day.
+	#[arg(long, default_value_t = 5)]
+	pub upload_gib: u32,
+	/// Days before the deadline at which users consider renewing.
+	#[arg(long, default_value_t = 3)]
+	pub renew_before: u32,
+	/// Chance that a user renews for a month once the deadline is near.
+	#[arg(long, default_value_t = 0.5)]
+	pub renew_rate: f64,
+	/// Chance that a miner fails the challenge of a day.
+	#[arg(long, default_value_t = 0.02)]
+	pub failure_rate: f64,
+	/// Consecutive failed challenges after which a miner is punished.
+	#[arg(long, default_value_t = 2)]
+	pub fault_tolerance: u32,
+	/// Reward issued into the miner reward pool per day, in dollars.
+	#[arg(long, default_value_t = 100_000)]
+	pub daily_reward: u128,
+	/// Days after which the daily reward halves, 0 never halves it.
+	#[arg(long, default_value_t = 0)]
+	pub halving_days: u32,
+	/// File the csv is written to, stdout if not set.
+	#[arg(long)]
+	pub output: Option<PathBuf>,
+}
+
+fn main() -> Result<(), String> {
+	let cli = Cli::parse();
+	if !(0.0..=1.0).contains(&cli.renew_rate) || !(0.0..=1.0).contains(&cli.failure_rate) {
+		return Err("rates must be between 0 and 1".into())
+	}
+
+	let mut out: Box<dyn Write> = match &cli.output {
+		Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|e| e.to_string())?)),
+		None => Box::new(BufWriter::new(io::stdout())),
+	};
+
+	let days = cli.days;
+	let mut scenario = scenario::Scenario::new(cli)?;
+	writeln!(out, "{}", metrics::CSV_HEADER).map_err(|e| e.to_string())?;
+	for day in 0..days {
+		scenario.step(day).write_csv(&mut out).map_err(|e| e.to_string())?;
+	}
+
+	out.flush().map_err(|e| e.to_string())
+}
@@ -0,0 +1,83 @@
+//! The metrics recorded at the end of every simulated day.
+use cess_node_runtime::{AccountId, Balance, Balances, BlockNumber, Sminer, StorageHandler};
+use cp_cess_common::G_BYTE;
+use frame_support::traits::Currency;
+use pallet_sminer::MinerControl;
+use pallet_storage_handler::StorageHandle;
+use std::io::{self, Write};
+
+pub const CSV_HEADER: &str = "day,block,unit_price,purchased_gib,idle_gib,service_gib,miners,\
+	users,failed_purchases,renewals,uploaded_gib,reward_issued,reward_distributed,reward_pool,\
+	punishments,total_issuance";
+
+/// Counters of the actions taken during a day, filled in by the scenario.
+#[derive(Default, Clone, Debug)]
+pub struct DayActivity {
+	pub failed_purchases: u32,
+	pub renewals: u32,
+	pub uploaded: u128,
+	pub reward_issued: Balance,
+	pub reward_distributed: Balance,
+	pub punishments: u32,
+}
+
+/// One csv row, balances in the smallest unit and space in gib.
+#[derive(Clone, Debug)]
+pub struct DayMetrics {
+	pub day: u32,
+	pub block: BlockNumber,
+	pub unit_price: Balance,
+	pub purchased_space: u128,
+	pub idle_space: u128,
+	pub service_space: u128,
+	pub miners: u32,
+	pub users: u32,
+	pub activity: DayActivity,
+	pub reward_pool: Balance,
+	pub total_issuance: Balance,
+}
+
+impl DayMetrics {
+	/// Read the metrics from the state, must be called inside the externalities.
+	pub fn collect(day: u32, block: BlockNumber, users: u32, activity: DayActivity) -> Self {
+		let (idle_space, service_space) = StorageHandler::total_space_records();
+
+		DayMetrics {
+			day,
+			block,
+			unit_price: <StorageHandler as StorageHandle<AccountId>>::get_unit_price()
+				.unwrap_or_default(),
+			purchased_space: StorageHandler::purchased_space(),
+			idle_space,
+			service_space,
+			miners: <Sminer as MinerControl<AccountId>>::get_miner_count(),
+			users,
+			activity,
+			reward_pool: Sminer::currency_reward(),
+			total_issuance: Balances::total_issuance(),
+		}
+	}
+
+	pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+		writeln!(
+			out,
+			"{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+			self.day,
+			self.block,
+			self.unit_price,
+			self.purchased_space / G_BYTE,
+			self.idle_space / G_BYTE,
+			self.service_space / G_BYTE,
+			self.miners,
+			self.users,
+			self.activity.failed_purchases,
+			self.activity.renewals,
+			self.activity.uploaded / G_BYTE,
+			self.activity.reward_issued,
+			self.activity.reward_distributed,
+			self.reward_pool,
+			self.activity.punishments,
+			self.total_issuance,
+		)
+	}
+}
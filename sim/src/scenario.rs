@@ -0,0 +1,241 @@
+//! The simulated participants and what they do every day.
+//!
+//! Miners and users act through the pallet calls where a call exists. Uploads and
+//! challenges need tee workers and proofs, so their effect on the space and reward
+//! storage is applied through the same pallet functions file-bank and audit use.
+use crate::{
+	metrics::{DayActivity, DayMetrics},
+	Cli,
+};
+use cess_node_runtime::{
+	AccountId, Balance, Balances, BlockNumber, Runtime, RuntimeOrigin, Sminer, StorageHandler,
+	System, DAYS, DOLLARS,
+};
+use cp_cess_common::{peer, NodePublicKey, G_BYTE};
+use frame_support::{
+	storage::with_storage_layer,
+	traits::{Currency, GenesisBuild, OnUnbalanced},
+};
+use pallet_sminer::MinerControl;
+use pallet_storage_handler::StorageHandle;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sp_io::TestExternalities;
+use sp_runtime::DispatchResult;
+use std::collections::BTreeMap;
+
+// Balance every simulated account starts with.
+const ENDOWMENT: Balance = 1_000_000 * DOLLARS;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+	Miner = 1,
+	User = 2,
+}
+
+fn account(role: Role, index: u32) -> AccountId {
+	let mut raw = [0u8; 32];
+	raw[0] = role as u8;
+	raw[1..5].copy_from_slice(&index.to_le_bytes());
+
+	AccountId::new(raw)
+}
+
+fn node_key(index: u32) -> NodePublicKey {
+	let mut raw = [0u8; 32];
+	raw[..4].copy_from_slice(&index.to_le_bytes());
+
+	NodePublicKey::from_raw(raw)
+}
+
+/// The state of one simulation run.
+pub struct Scenario {
+	cli: Cli,
+	rng: StdRng,
+	ext: TestExternalities,
+	miners: Vec<AccountId>,
+	users: Vec<AccountId>,
+	// Consecutive failed challenges of each miner.
+	failures: BTreeMap<AccountId, u32>,
+}
+
+impl Scenario {
+	pub fn new(cli: Cli) -> Result<Self, String> {
+		let mut storage = frame_system::GenesisConfig::default().build_storage::<Runtime>()?;
+
+		let balances = (0..cli.miners)
+			.map(|i| account(Role::Miner, i))
+			.chain((0..cli.users).map(|i| account(Role::User, i)))
+			.map(|acc| (acc, ENDOWMENT))
+			.collect();
+		pallet_balances::GenesisConfig::<Runtime> { balances }.assimilate_storage(&mut storage)?;
+		pallet_storage_handler::GenesisConfig::<Runtime> { price: cli.unit_price * DOLLARS }
+			.assimilate_storage(&mut storage)?;
+
+		Ok(Scenario {
+			rng: StdRng::seed_from_u64(cli.seed),
+			ext: TestExternalities::new(storage),
+			miners: Vec::new(),
+			users: Vec::new(),
+			failures: BTreeMap::new(),
+			cli,
+		})
+	}
+
+	/// Run one day and return its metrics.
+	pub fn step(&mut self, day: u32) -> DayMetrics {
+		let block: BlockNumber = day * DAYS + 1;
+		let mut ext = std::mem::replace(&mut self.ext, TestExternalities::new(Default::default()));
+
+		let metrics = ext.execute_with(|| {
+			System::set_block_number(block);
+			let mut activity = DayActivity::default();
+
+			self.onboard_miners();
+			self.onboard_users(&mut activity);
+			self.renew_packages(block, &mut activity);
+			self.upload(&mut activity);
+			let _ = <StorageHandler as StorageHandle<AccountId>>::frozen_task();
+			self.reward(day, &mut activity);
+
+			DayMetrics::collect(day, block, self.users.len() as u32, activity)
+		});
+
+		self.ext = ext;
+		metrics
+	}
+
+	fn onboard_miners(&mut self) {
+		let target = (self.miners.len() as u32 + self.cli.miners_per_day).min(self.cli.miners);
+		for index in self.miners.len() as u32..target {
+			let miner = account(Role::Miner, index);
+			let peer_id = peer::peer_id_from_node_key(&node_key(index));
+			let collateral = self.cli.miner_collateral * DOLLARS;
+
+			let origin = RuntimeOrigin::signed(miner.clone());
+			if Sminer::regnstk(origin, miner.clone(), peer_id, collateral).is_err() {
+				continue;
+			}
+
+			let idle_space = self.cli.miner_gib as u128 * G_BYTE;
+			// Idle space is certified by the tee workers before it is added.
+			let _ = Sminer::add_miner_idle_space(&miner, idle_space).and_then(|_| {
+				<StorageHandler as StorageHandle<AccountId>>::add_total_idle_space(idle_space)
+			});
+			self.miners.push(miner);
+		}
+	}
+
+	fn onboard_users(&mut self, activity: &mut DayActivity) {
+		let target = (self.users.len() as u32 + self.cli.users_per_day).min(self.cli.users);
+		for index in self.users.len() as u32..target {
+			let user = account(Role::User, index);
+			let origin = RuntimeOrigin::signed(user.clone());
+
+			match StorageHandler::buy_space(origin, self.cli.package_gib, None, None) {
+				Ok(_) => self.users.push(user),
+				// Tried again the next day, once more miners have joined.
+				Err(_) => {
+					activity.failed_purchases += 1;
+					break
+				},
+			}
+		}
+	}
+
+	fn renew_packages(&mut self, now: BlockNumber, activity: &mut DayActivity) {
+		for user in self.users.iter() {
+			let info = match StorageHandler::get_user_space_info(user) {
+				Some(info) => info,
+				None => continue,
+			};
+			if info.deadline > now + self.cli.renew_before * DAYS ||
+				!self.rng.gen_bool(self.cli.renew_rate)
+			{
+				continue;
+			}
+			if StorageHandler::renew_package(RuntimeOrigin::signed(user.clone()), 1).is_ok() {
+				activity.renewals += 1;
+			}
+		}
+	}
+
+	fn upload(&mut self, activity: &mut DayActivity) {
+		for user in self.users.iter() {
+			let gib = self.rng.gen_range(0..=self.cli.upload_gib);
+			if gib == 0 {
+				continue;
+			}
+			let size = gib as u128 * G_BYTE;
+			let miner = match self.miners.iter().find(|miner| {
+				<Sminer as MinerControl<AccountId>>::get_power(miner)
+					.map_or(false, |(idle_space, _)| idle_space >= size)
+			}) {
+				Some(miner) => miner,
+				None => break,
+			};
+
+			let uploaded = with_storage_layer(|| Self::store(user, miner, size));
+			if uploaded.is_ok() {
+				activity.uploaded += size;
+			}
+		}
+	}
+
+	// Moves the space the same way as when file-bank completes a deal.
+	fn store(user: &AccountId, miner: &AccountId, size: u128) -> DispatchResult {
+		<StorageHandler as StorageHandle<AccountId>>::update_user_space(user, 1, size)?;
+		Sminer::sub_miner_idle_space(miner, size)?;
+		Sminer::add_miner_service_space(miner, size)?;
+		<StorageHandler as StorageHandle<AccountId>>::sub_total_idle_space(size)?;
+		<StorageHandler as StorageHandle<AccountId>>::add_total_service_space(size)
+	}
+
+	// Issues the reward of the day into the reward pool, then runs a challenge round
+	// in which the miners share the pool the way audit pays verified miners.
+	fn reward(&mut self, day: u32, activity: &mut DayActivity) {
+		let halvings = if self.cli.halving_days == 0 { 0 } else { day / self.cli.halving_days };
+		let issued = (self.cli.daily_reward * DOLLARS).checked_shr(halvings).unwrap_or(0);
+		if issued > 0 {
+			<Sminer as OnUnbalanced<_>>::on_unbalanced(Balances::issue(issued));
+		}
+		activity.reward_issued = issued;
+
+		let pool_before = Sminer::currency_reward();
+		let (total_idle, total_service) = StorageHandler::total_space_records();
+		if total_idle + total_service == 0 {
+			return
+		}
+
+		for miner in self.miners.iter() {
+			let (idle_space, service_space) =
+				match <Sminer as MinerControl<AccountId>>::get_power(miner) {
+					Ok(power) => power,
+					Err(_) => continue,
+				};
+			let passed = !self.rng.gen_bool(self.cli.failure_rate);
+			Sminer::record_challenge_result(miner, passed);
+
+			let failures = self.failures.entry(miner.clone()).or_default();
+			if passed {
+				*failures = 0;
+				let _ = Sminer::calculate_miner_reward(
+					miner,
+					pool_before,
+					total_idle,
+					total_service,
+					idle_space,
+					service_space,
+				);
+			} else {
+				*failures += 1;
+				if *failures >= self.cli.fault_tolerance &&
+					Sminer::idle_punish(miner, idle_space, service_space).is_ok()
+				{
+					activity.punishments += 1;
+				}
+			}
+		}
+
+		activity.reward_distributed = pool_before.saturating_sub(Sminer::currency_reward());
+	}
+}